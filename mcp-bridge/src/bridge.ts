@@ -1,15 +1,26 @@
-import { listDir, readFile, searchFiles } from './tools.js'
+import { applyPatch, listDir, pathsInPatch, readFile, runCommand, searchFiles, writeFile } from './tools.js'
 import type {
-  BridgeAction,
   BridgeDirEntry,
+  BridgeMcpConfig,
   BridgeRequest,
+  CommandResult,
   DirectoryListing,
+  McpNotification,
+  McpToolList,
+  PingResult,
+  ResourceSubscriptionResult,
   SearchMatch,
   SearchResult,
   TargetFileContent,
+  WriteResult,
 } from './types.js'
 
+/** Upper bound when subscribing to everything a server lists. */
+const MAX_SUBSCRIBED_RESOURCES = 100
+
 type BridgeResult = DirectoryListing | TargetFileContent | SearchResult
+type FileAction = 'list_dir' | 'read_file' | 'search_files'
+type WriteAction = 'write_file' | 'apply_patch'
 
 interface BridgeExecutionContext {
   source: 'local' | 'mcp' | 'mcp_fallback_local'
@@ -18,6 +29,8 @@ interface BridgeExecutionContext {
 
 interface McpToolDescriptor {
   name: string
+  description?: string
+  inputSchema?: Record<string, unknown>
 }
 
 interface McpToolResponse {
@@ -27,8 +40,14 @@ interface McpToolResponse {
 interface McpClientLike {
   connect(transport: unknown): Promise<void>
   close(): Promise<void>
+  ping(): Promise<unknown>
+  getServerCapabilities(): { resources?: { subscribe?: boolean } } | undefined
   listTools(): Promise<{ tools?: McpToolDescriptor[] }>
+  listResources(): Promise<{ resources?: Array<{ uri: string }> }>
+  subscribeResource(params: { uri: string }): Promise<unknown>
+  unsubscribeResource(params: { uri: string }): Promise<unknown>
   callTool(args: { name: string; arguments: Record<string, unknown> }): Promise<McpToolResponse | unknown>
+  fallbackNotificationHandler?: (notification: McpNotification) => Promise<void>
 }
 
 interface McpClientCtor {
@@ -42,6 +61,10 @@ interface StdioClientTransportCtor {
   new (config: { command: string; args?: string[]; env?: NodeJS.ProcessEnv }): unknown
 }
 
+interface HttpClientTransportCtor {
+  new (url: URL, options?: { requestInit?: RequestInit }): unknown
+}
+
 function withMetadata<T extends { source: 'local' | 'mcp' | 'mcp_fallback_local'; warnings: string[] }>(
   result: T,
   context: BridgeExecutionContext,
@@ -125,23 +148,62 @@ function parseToolPayload(payload: unknown): unknown {
   }
 }
 
-async function callMcpTool(request: BridgeRequest): Promise<BridgeResult> {
-  if (!request.mcp?.command) {
-    throw new Error('Missing MCP command configuration')
+function hasMcpServer(request: BridgeRequest): boolean {
+  return Boolean(request.mcp?.command || request.mcp?.url)
+}
+
+/** Headers for hosted servers; the client passes the auth token through the environment. */
+function mcpRequestHeaders(mcp: BridgeMcpConfig): Record<string, string> {
+  const headers: Record<string, string> = { ...mcp.headers }
+  const authorization = process.env.AOP_MCP_AUTHORIZATION
+  if (authorization) {
+    headers.Authorization = authorization
   }
+  return headers
+}
 
-  const { Client } = (await import('@modelcontextprotocol/sdk/client/index.js')) as {
-    Client: McpClientCtor
+async function createTransport(mcp: BridgeMcpConfig): Promise<unknown> {
+  const transport = mcp.transport ?? 'stdio'
+  if (transport === 'stdio') {
+    if (!mcp.command) {
+      throw new Error('Missing MCP command configuration')
+    }
+    const { StdioClientTransport } = (await import('@modelcontextprotocol/sdk/client/stdio.js')) as {
+      StdioClientTransport: StdioClientTransportCtor
+    }
+    return new StdioClientTransport({
+      command: mcp.command,
+      args: mcp.args ?? [],
+      env: { ...process.env, ...mcp.env },
+    })
+  }
+
+  if (!mcp.url) {
+    throw new Error(`Missing MCP url for ${transport} transport`)
+  }
+  const url = new URL(mcp.url)
+  const requestInit: RequestInit = { headers: mcpRequestHeaders(mcp) }
+  if (transport === 'http') {
+    const { StreamableHTTPClientTransport } = (await import('@modelcontextprotocol/sdk/client/streamableHttp.js')) as {
+      StreamableHTTPClientTransport: HttpClientTransportCtor
+    }
+    return new StreamableHTTPClientTransport(url, { requestInit })
   }
-  const { StdioClientTransport } = (await import('@modelcontextprotocol/sdk/client/stdio.js')) as {
-    StdioClientTransport: StdioClientTransportCtor
+  const { SSEClientTransport } = (await import('@modelcontextprotocol/sdk/client/sse.js')) as {
+    SSEClientTransport: HttpClientTransportCtor
   }
+  return new SSEClientTransport(url, { requestInit })
+}
 
-  const transport = new StdioClientTransport({
-    command: request.mcp.command,
-    args: request.mcp.args ?? [],
-    env: process.env,
-  })
+async function connectMcpClient(request: BridgeRequest): Promise<McpClientLike> {
+  if (!request.mcp) {
+    throw new Error('Missing MCP server configuration')
+  }
+
+  const { Client } = (await import('@modelcontextprotocol/sdk/client/index.js')) as {
+    Client: McpClientCtor
+  }
+  const transport = await createTransport(request.mcp)
 
   const client = new Client(
     {
@@ -152,76 +214,306 @@ async function callMcpTool(request: BridgeRequest): Promise<BridgeResult> {
   )
 
   await client.connect(transport)
+  return client
+}
 
+/** Set in serve mode, where one process talks to one server for its whole life. */
+let keepClientOpen = false
+let keptClient: Promise<McpClientLike> | null = null
+let notificationListener: ((notification: McpNotification) => void) | null = null
+
+/** Keeps the MCP client open across requests and passes resource notifications to `onNotification`. */
+export function keepMcpClientOpen(onNotification?: (notification: McpNotification) => void): void {
+  keepClientOpen = true
+  notificationListener = onNotification ?? null
+}
+
+export async function closeKeptMcpClient(): Promise<void> {
+  const pending = keptClient
+  keptClient = null
+  if (pending) {
+    await pending.then((client) => client.close()).catch(() => undefined)
+  }
+}
+
+async function acquireMcpClient(request: BridgeRequest): Promise<McpClientLike> {
+  if (!keepClientOpen) {
+    return connectMcpClient(request)
+  }
+  keptClient ??= connectMcpClient(request).then(
+    (client) => {
+      client.fallbackNotificationHandler = async (notification) => {
+        if (notification.method.startsWith('notifications/resources/')) {
+          notificationListener?.(notification)
+        }
+      }
+      return client
+    },
+    (error: unknown) => {
+      keptClient = null
+      throw error
+    },
+  )
+  return keptClient
+}
+
+async function releaseMcpClient(client: McpClientLike): Promise<void> {
+  if (!keepClientOpen) {
+    await client.close()
+  }
+}
+
+async function pingMcpServer(request: BridgeRequest): Promise<PingResult> {
+  const client = await acquireMcpClient(request)
   try {
-    const tools = await client.listTools()
-    const availableToolNames = new Set<string>((tools.tools ?? []).map((tool) => tool.name))
+    await client.ping()
+    return { ok: true }
+  } finally {
+    await releaseMcpClient(client)
+  }
+}
+
+async function updateResourceSubscriptions(
+  request: BridgeRequest,
+  subscribe: boolean,
+): Promise<ResourceSubscriptionResult> {
+  if (subscribe && !keepClientOpen) {
+    throw new Error('Resource subscriptions need a session bridge (--serve)')
+  }
 
-    const candidates: Record<BridgeAction, string[]> = {
-      read_file: ['read_file', 'readFile'],
-      list_dir: ['list_dir', 'list_directory', 'listDir'],
-      search_files: ['search_files', 'searchFiles'],
+  const client = await acquireMcpClient(request)
+  try {
+    if (!client.getServerCapabilities()?.resources?.subscribe) {
+      throw new Error('Target MCP server does not support resource subscriptions')
+    }
+    let uris = request.uris ?? []
+    if (uris.length === 0 && subscribe) {
+      const listed = await client.listResources()
+      uris = (listed.resources ?? []).map((resource) => resource.uri).slice(0, MAX_SUBSCRIBED_RESOURCES)
+    }
+    for (const uri of uris) {
+      if (subscribe) {
+        await client.subscribeResource({ uri })
+      } else {
+        await client.unsubscribeResource({ uri })
+      }
     }
+    return { uris }
+  } finally {
+    await releaseMcpClient(client)
+  }
+}
 
-    const selectedTool = candidates[request.action].find((candidate) => availableToolNames.has(candidate))
+async function listMcpTools(request: BridgeRequest): Promise<McpToolList> {
+  const client = await acquireMcpClient(request)
+  try {
+    const tools = await client.listTools()
+    return {
+      tools: (tools.tools ?? []).map((tool) => ({
+        name: tool.name,
+        description: tool.description ?? null,
+        inputSchema: tool.inputSchema ?? {},
+      })),
+    }
+  } finally {
+    await releaseMcpClient(client)
+  }
+}
+
+interface McpToolCall {
+  tool: string
+  text: string
+  isError: boolean
+  raw: McpToolResponse
+}
+
+/**
+ * Calls the first of `candidates` the server exposes, provided the server's allowed tools
+ * permit it, and joins the text items of the reply.
+ */
+async function callFirstMcpTool(
+  request: BridgeRequest,
+  capability: string,
+  candidates: string[],
+  toolArgs: Record<string, unknown>,
+): Promise<McpToolCall> {
+  const client = await acquireMcpClient(request)
+  try {
+    const tools = await client.listTools()
+    const availableToolNames = new Set<string>((tools.tools ?? []).map((tool) => tool.name))
+    const selectedTool = candidates.find((candidate) => availableToolNames.has(candidate))
     if (!selectedTool) {
-      throw new Error(`Target MCP server does not expose ${request.action}`)
+      throw new Error(`Target MCP server does not expose ${capability}`)
     }
 
-    const toolArgs =
-      request.action === 'search_files'
-        ? { path: request.path, pattern: request.pattern, limit: request.limit }
-        : { path: request.path }
+    const allowedTools = request.mcp?.allowedTools ?? []
+    if (allowedTools.length > 0 && !allowedTools.includes(selectedTool)) {
+      throw new Error(`MCP tool ${selectedTool} is not in the server's allowed tools`)
+    }
 
     const rawToolResult = (await client.callTool({
       name: selectedTool,
       arguments: toolArgs,
-    })) as McpToolResponse
+    })) as McpToolResponse & { isError?: boolean }
 
-    const textPayload =
+    const text =
       rawToolResult?.content
         ?.filter((item) => item.type === 'text')
         ?.map((item) => item.text ?? '')
         ?.join('\n') ?? ''
+    return { tool: selectedTool, text, isError: Boolean(rawToolResult?.isError), raw: rawToolResult }
+  } finally {
+    await releaseMcpClient(client)
+  }
+}
 
-    const payload = parseToolPayload(textPayload || rawToolResult)
+async function callMcpTool(request: BridgeRequest, action: FileAction): Promise<BridgeResult> {
+  const candidates: Record<FileAction, string[]> = {
+    read_file: ['read_file', 'readFile'],
+    list_dir: ['list_dir', 'list_directory', 'listDir'],
+    search_files: ['search_files', 'searchFiles'],
+  }
+  const toolArgs =
+    action === 'search_files'
+      ? { path: request.path, pattern: request.pattern, limit: request.limit }
+      : { path: request.path }
 
-    if (request.action === 'list_dir') {
-      return normalizeListDirPayload(payload)
-    }
+  const result = await callFirstMcpTool(request, action, candidates[action], toolArgs)
+  const payload = parseToolPayload(result.text || result.raw)
+
+  if (action === 'list_dir') {
+    return normalizeListDirPayload(payload)
+  }
+
+  if (action === 'read_file') {
+    return normalizeReadFilePayload(payload)
+  }
+
+  return normalizeSearchPayload(payload, request.pattern ?? '')
+}
+
+/**
+ * Writes go only where they were sent: a failing MCP server is reported rather than
+ * falling back to the local checkout, which may not be the target at all.
+ */
+async function callMcpWriteTool(request: BridgeRequest, action: WriteAction): Promise<WriteResult> {
+  const candidates: Record<WriteAction, string[]> = {
+    write_file: ['write_file', 'writeFile'],
+    apply_patch: ['apply_patch', 'applyPatch'],
+  }
+  const dryRun = request.dryRun ?? false
+  const toolArgs =
+    action === 'write_file'
+      ? { path: request.path, content: request.content ?? '', dryRun }
+      : { patch: request.patch ?? '', dryRun }
+
+  const result = await callFirstMcpTool(request, action, candidates[action], toolArgs)
+  if (result.isError) {
+    throw new Error(`MCP ${result.tool} failed: ${result.text || 'no details'}`)
+  }
+
+  return {
+    root: request.targetProject,
+    paths: action === 'write_file' ? [request.path ?? ''] : pathsInPatch(request.patch ?? ''),
+    dryRun,
+    applied: !dryRun,
+    bytesWritten: action === 'write_file' && !dryRun ? Buffer.byteLength(request.content ?? '', 'utf8') : null,
+    source: 'mcp',
+    warnings: [],
+  }
+}
+
+async function executeWrite(request: BridgeRequest, action: WriteAction): Promise<WriteResult> {
+  if (hasMcpServer(request)) {
+    return callMcpWriteTool(request, action)
+  }
+
+  if (action === 'write_file') {
+    return writeFile(request.targetProject, request.path, request.content ?? '', request.dryRun ?? false)
+  }
 
-    if (request.action === 'read_file') {
-      return normalizeReadFilePayload(payload)
+  return applyPatch(request.targetProject, request.patch ?? '', request.dryRun ?? false)
+}
+
+/** Like writes, commands sent to a server never fall back to the local checkout. */
+async function callMcpRunCommand(request: BridgeRequest): Promise<CommandResult> {
+  const startedAt = Date.now()
+  const result = await callFirstMcpTool(request, 'run_command', ['run_command', 'runCommand', 'execute_command'], {
+    command: request.command,
+    args: request.args ?? [],
+    timeoutMs: request.timeoutMs,
+  })
+
+  const payload = parseToolPayload(result.text) as Partial<CommandResult> | string
+  if (payload && typeof payload === 'object' && 'stdout' in payload) {
+    return {
+      exitCode: payload.exitCode == null ? null : Number(payload.exitCode),
+      stdout: String(payload.stdout ?? ''),
+      stderr: String(payload.stderr ?? ''),
+      timedOut: Boolean(payload.timedOut),
+      durationMs: Number(payload.durationMs ?? Date.now() - startedAt),
+      source: 'mcp',
     }
+  }
 
-    return normalizeSearchPayload(payload, request.pattern ?? '')
-  } finally {
-    await client.close()
+  return {
+    exitCode: result.isError ? 1 : 0,
+    stdout: result.isError ? '' : result.text,
+    stderr: result.isError ? result.text : '',
+    timedOut: false,
+    durationMs: Date.now() - startedAt,
+    source: 'mcp',
   }
 }
 
-async function executeLocal(request: BridgeRequest): Promise<BridgeResult> {
-  if (request.action === 'list_dir') {
+async function executeLocal(request: BridgeRequest, action: FileAction): Promise<BridgeResult> {
+  if (action === 'list_dir') {
     return listDir(request.targetProject, request.path)
   }
 
-  if (request.action === 'read_file') {
+  if (action === 'read_file') {
     return readFile(request.targetProject, request.path)
   }
 
   return searchFiles(request.targetProject, request.pattern ?? '', request.limit ?? 40)
 }
 
-export async function executeBridgeRequest(request: BridgeRequest): Promise<BridgeResult> {
-  if (!request.mcp?.command) {
-    return executeLocal(request)
+export async function executeBridgeRequest(
+  request: BridgeRequest,
+): Promise<BridgeResult | McpToolList | WriteResult | CommandResult | PingResult | ResourceSubscriptionResult> {
+  const { action } = request
+  if (action === 'list_tools') {
+    return listMcpTools(request)
+  }
+
+  if (action === 'ping') {
+    return pingMcpServer(request)
+  }
+
+  if (action === 'subscribe_resources' || action === 'unsubscribe_resources') {
+    return updateResourceSubscriptions(request, action === 'subscribe_resources')
+  }
+
+  if (action === 'write_file' || action === 'apply_patch') {
+    return executeWrite(request, action)
+  }
+
+  if (action === 'run_command') {
+    if (hasMcpServer(request)) {
+      return callMcpRunCommand(request)
+    }
+    return runCommand(request.targetProject, request.command ?? '', request.args ?? [], request.timeoutMs ?? 120_000)
+  }
+
+  if (!hasMcpServer(request)) {
+    return executeLocal(request, action)
   }
 
   try {
-    const mcpResult = await callMcpTool(request)
+    const mcpResult = await callMcpTool(request, action)
     return withMetadata(mcpResult, { source: 'mcp', warnings: [] })
   } catch (error) {
-    const fallback = await executeLocal(request)
+    const fallback = await executeLocal(request, action)
     return withMetadata(fallback, {
       source: 'mcp_fallback_local',
       warnings: [error instanceof Error ? error.message : String(error)],
@@ -1,5 +1,7 @@
-import { executeBridgeRequest } from './bridge.js'
-import type { BridgeEnvelope, BridgeRequest } from './types.js'
+import { createInterface } from 'node:readline'
+
+import { closeKeptMcpClient, executeBridgeRequest, keepMcpClientOpen } from './bridge.js'
+import type { BridgeEnvelope, BridgeRequest, SessionEnvelope, SessionMessage, SessionNotification } from './types.js'
 
 function getRequestPayload(argv: string[]): string {
   const requestBase64FlagIndex = argv.findIndex((arg) => arg === '--request-base64')
@@ -26,10 +28,43 @@ function parseArgs(argv: string[]): BridgeRequest {
   }
 }
 
-function writeResponse(payload: BridgeEnvelope): void {
+function writeResponse(payload: BridgeEnvelope | SessionEnvelope | SessionNotification): void {
   process.stdout.write(`${JSON.stringify(payload)}\n`)
 }
 
+async function handleSessionLine(line: string): Promise<void> {
+  let id: number | null = null
+  try {
+    const message = JSON.parse(line) as SessionMessage
+    id = message.id
+    const data = await executeBridgeRequest(message.request)
+    writeResponse({ id, ok: true, data })
+  } catch (error) {
+    writeResponse({
+      id,
+      ok: false,
+      error: error instanceof Error ? error.message : String(error),
+    })
+  }
+}
+
+/**
+ * Session mode: answers requests read line by line from stdin, concurrently and tagged
+ * with their id, over one MCP connection kept open until stdin closes. Resource
+ * notifications from the server are written as untagged lines in between.
+ */
+async function serve(): Promise<void> {
+  keepMcpClientOpen((notification) => writeResponse({ notification }))
+  const lines = createInterface({ input: process.stdin, crlfDelay: Infinity })
+  for await (const line of lines) {
+    if (line.trim()) {
+      void handleSessionLine(line)
+    }
+  }
+  await closeKeptMcpClient()
+  process.exit(0)
+}
+
 async function main(): Promise<void> {
   try {
     const request = parseArgs(process.argv.slice(2))
@@ -44,4 +79,8 @@ async function main(): Promise<void> {
   }
 }
 
-void main()
+if (process.argv.includes('--serve')) {
+  void serve()
+} else {
+  void main()
+}
@@ -1,7 +1,16 @@
+import { spawn } from 'node:child_process'
 import { promises as fs } from 'node:fs'
 import path from 'node:path'
 
-import type { BridgeDirEntry, DirectoryListing, SearchMatch, SearchResult, TargetFileContent } from './types.js'
+import type {
+  BridgeDirEntry,
+  CommandResult,
+  DirectoryListing,
+  SearchMatch,
+  SearchResult,
+  TargetFileContent,
+  WriteResult,
+} from './types.js'
 
 function securityViolation(message: string): Error {
   return new Error(`SECURITY_VIOLATION: ${message}`)
@@ -309,3 +318,185 @@ export async function searchFiles(
     warnings: [],
   }
 }
+
+export async function writeFile(
+  targetProject: string,
+  requestedPath: string | undefined,
+  content: string,
+  dryRun = false,
+): Promise<WriteResult> {
+  if (!requestedPath || !requestedPath.trim()) {
+    throw new Error('path is required for write_file')
+  }
+
+  const absoluteRoot = normalizeRoot(targetProject)
+  const absoluteFile = await resolveWithinRoot(absoluteRoot, requestedPath)
+  if (absoluteFile === absoluteRoot) {
+    throw new Error('write_file needs a file path, not the project root')
+  }
+
+  if (!dryRun) {
+    await fs.mkdir(path.dirname(absoluteFile), { recursive: true })
+    await fs.writeFile(absoluteFile, content, 'utf8')
+  }
+
+  return {
+    root: absoluteRoot,
+    paths: [toPosixRelative(absoluteRoot, absoluteFile)],
+    dryRun,
+    applied: !dryRun,
+    bytesWritten: dryRun ? null : Buffer.byteLength(content, 'utf8'),
+    source: 'local',
+    warnings: [],
+  }
+}
+
+const HUNK_HEADER = /^@@ -\d+(?:,(\d+))? \+\d+(?:,(\d+))? @@/
+
+/**
+ * The files a unified diff touches, read from its `---`/`+++` headers. Hunk bodies are
+ * skipped by their line counts, so a removed `-- x` or added `++ x` line is not taken for
+ * a header.
+ */
+export function pathsInPatch(patch: string): string[] {
+  const paths = new Set<string>()
+  let oldLinesLeft = 0
+  let newLinesLeft = 0
+  for (const line of patch.split(/\r?\n/)) {
+    if (oldLinesLeft > 0 || newLinesLeft > 0) {
+      if (line.startsWith('-')) {
+        oldLinesLeft -= 1
+      } else if (line.startsWith('+')) {
+        newLinesLeft -= 1
+      } else if (!line.startsWith('\\')) {
+        oldLinesLeft -= 1
+        newLinesLeft -= 1
+      }
+      continue
+    }
+
+    const hunk = HUNK_HEADER.exec(line)
+    if (hunk) {
+      oldLinesLeft = hunk[1] === undefined ? 1 : Number(hunk[1])
+      newLinesLeft = hunk[2] === undefined ? 1 : Number(hunk[2])
+      continue
+    }
+
+    const header = line.startsWith('+++ ') || line.startsWith('--- ') ? line.slice(4) : null
+    if (header === null) {
+      continue
+    }
+
+    const rawPath = header.split('\t')[0].trim()
+    if (!rawPath || rawPath === '/dev/null') {
+      continue
+    }
+
+    paths.add(rawPath.replace(/^[ab]\//, ''))
+  }
+
+  return [...paths]
+}
+
+function runGitApply(root: string, args: string[], patch: string): Promise<void> {
+  return new Promise((resolve, reject) => {
+    const child = spawn('git', ['apply', ...args, '-'], { cwd: root })
+    let stderr = ''
+    child.stderr.on('data', (chunk: Buffer) => {
+      stderr += chunk.toString('utf8')
+    })
+    child.on('error', reject)
+    child.on('close', (code) => {
+      if (code === 0) {
+        resolve()
+      } else {
+        reject(new Error(`git apply failed: ${stderr.trim() || `exit code ${code}`}`))
+      }
+    })
+    child.stdin.end(patch)
+  })
+}
+
+export async function applyPatch(targetProject: string, patch: string, dryRun = false): Promise<WriteResult> {
+  if (!patch.trim()) {
+    throw new Error('patch is required for apply_patch')
+  }
+
+  const absoluteRoot = normalizeRoot(targetProject)
+  const paths = pathsInPatch(patch)
+  if (paths.length === 0) {
+    throw new Error('patch does not name any file')
+  }
+
+  for (const requestedPath of paths) {
+    await resolveWithinRoot(absoluteRoot, requestedPath)
+  }
+
+  await runGitApply(absoluteRoot, dryRun ? ['--check'] : [], patch)
+
+  return {
+    root: absoluteRoot,
+    paths,
+    dryRun,
+    applied: !dryRun,
+    bytesWritten: null,
+    source: 'local',
+    warnings: [],
+  }
+}
+
+const MAX_COMMAND_OUTPUT_BYTES = 256 * 1024
+
+/**
+ * Runs `command` with `args` in the project root without a shell, so arguments are never
+ * interpreted. Output beyond 256 KB per stream is dropped and the process is killed at
+ * `timeoutMs`.
+ */
+export async function runCommand(
+  targetProject: string,
+  command: string,
+  args: string[],
+  timeoutMs: number,
+): Promise<CommandResult> {
+  if (!command.trim()) {
+    throw new Error('command is required for run_command')
+  }
+
+  const absoluteRoot = normalizeRoot(targetProject)
+  await ensureDirectory(absoluteRoot)
+  const startedAt = Date.now()
+
+  return new Promise((resolve, reject) => {
+    const child = spawn(command, args, { cwd: absoluteRoot, shell: false })
+    const output = { stdout: '', stderr: '' }
+    let timedOut = false
+    const capture = (stream: 'stdout' | 'stderr') => (chunk: Buffer) => {
+      if (Buffer.byteLength(output[stream], 'utf8') < MAX_COMMAND_OUTPUT_BYTES) {
+        output[stream] += chunk.toString('utf8')
+      }
+    }
+    child.stdout.on('data', capture('stdout'))
+    child.stderr.on('data', capture('stderr'))
+
+    const timer = setTimeout(() => {
+      timedOut = true
+      child.kill('SIGKILL')
+    }, Math.max(1, timeoutMs))
+
+    child.on('error', (error) => {
+      clearTimeout(timer)
+      reject(error)
+    })
+    child.on('close', (code) => {
+      clearTimeout(timer)
+      resolve({
+        exitCode: code,
+        stdout: output.stdout,
+        stderr: output.stderr,
+        timedOut,
+        durationMs: Date.now() - startedAt,
+        source: 'local',
+      })
+    })
+  })
+}
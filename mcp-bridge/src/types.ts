@@ -1,8 +1,25 @@
-export type BridgeAction = 'list_dir' | 'read_file' | 'search_files'
+export type BridgeAction =
+  | 'list_dir'
+  | 'read_file'
+  | 'search_files'
+  | 'list_tools'
+  | 'write_file'
+  | 'apply_patch'
+  | 'run_command'
+  | 'ping'
+  | 'subscribe_resources'
+  | 'unsubscribe_resources'
+
+export type McpTransport = 'stdio' | 'http' | 'sse'
 
 export interface BridgeMcpConfig {
+  transport?: McpTransport
   command: string
   args?: string[]
+  env?: Record<string, string>
+  url?: string
+  headers?: Record<string, string>
+  allowedTools?: string[]
 }
 
 export interface BridgeRequest {
@@ -11,6 +28,13 @@ export interface BridgeRequest {
   path?: string
   pattern?: string
   limit?: number
+  content?: string
+  patch?: string
+  dryRun?: boolean
+  command?: string
+  args?: string[]
+  timeoutMs?: number
+  uris?: string[]
   mcp?: BridgeMcpConfig
 }
 
@@ -53,8 +77,65 @@ export interface SearchResult {
   warnings: string[]
 }
 
+export interface WriteResult {
+  root: string
+  paths: string[]
+  dryRun: boolean
+  applied: boolean
+  bytesWritten: number | null
+  source: 'local' | 'mcp'
+  warnings: string[]
+}
+
+export interface CommandResult {
+  exitCode: number | null
+  stdout: string
+  stderr: string
+  timedOut: boolean
+  durationMs: number
+  source: 'local' | 'mcp'
+}
+
+export interface McpToolSchema {
+  name: string
+  description: string | null
+  inputSchema: Record<string, unknown>
+}
+
+export interface McpToolList {
+  tools: McpToolSchema[]
+}
+
 export interface BridgeEnvelope<T = unknown> {
   ok: boolean
   data?: T
   error?: string
 }
+
+/** A request on a session bridge's stdin; the reply is a `SessionEnvelope` with its id. */
+export interface SessionMessage {
+  id: number
+  request: BridgeRequest
+}
+
+export interface SessionEnvelope extends BridgeEnvelope {
+  id: number | null
+}
+
+export interface PingResult {
+  ok: boolean
+}
+
+export interface ResourceSubscriptionResult {
+  uris: string[]
+}
+
+export interface McpNotification {
+  method: string
+  params?: Record<string, unknown>
+}
+
+/** Written by a session bridge whenever the server sends a notification it forwards. */
+export interface SessionNotification {
+  notification: McpNotification
+}
@@ -1,10 +1,18 @@
-import { mkdtemp, mkdir, rm, symlink, writeFile } from 'node:fs/promises'
+import { mkdtemp, mkdir, readFile as readFileFromDisk, realpath, rm, symlink, writeFile } from 'node:fs/promises'
 import os from 'node:os'
 import path from 'node:path'
 
 import { afterEach, describe, expect, it } from 'vitest'
 
-import { listDir, readFile, searchFiles } from '../src/tools.js'
+import {
+  applyPatch,
+  listDir,
+  pathsInPatch,
+  readFile,
+  runCommand,
+  searchFiles,
+  writeFile as writeTargetFile,
+} from '../src/tools.js'
 
 const tempRoots: string[] = []
 
@@ -82,4 +90,55 @@ describe('bridge local tools', () => {
 
     await expect(listDir(root, 'linked-outside')).rejects.toThrow('SECURITY_VIOLATION')
   })
+
+  it('writes files inside the project and leaves them alone on dry runs', async () => {
+    const { root } = await createFixture()
+    const dryRun = await writeTargetFile(root, 'src/main.ts', 'export const hello = "dry"\n', true)
+    expect(dryRun.applied).toBe(false)
+    expect(await readFileFromDisk(path.join(root, 'src', 'main.ts'), 'utf8')).toContain('world')
+
+    const written = await writeTargetFile(root, 'src/new/file.ts', 'export {}\n')
+    expect(written.paths).toEqual(['src/new/file.ts'])
+    expect(await readFileFromDisk(path.join(root, 'src', 'new', 'file.ts'), 'utf8')).toBe('export {}\n')
+
+    await expect(writeTargetFile(root, '../outside.ts', 'x')).rejects.toThrow('SECURITY_VIOLATION')
+  })
+
+  it('rejects patches that touch paths outside the project', async () => {
+    const { root } = await createFixture()
+    const patch = ['--- a/../outside.ts', '+++ b/../outside.ts', '@@ -0,0 +1 @@', '+x', ''].join('\n')
+    await expect(applyPatch(root, patch, true)).rejects.toThrow('SECURITY_VIOLATION')
+  })
+
+  it('reads patch headers outside hunk bodies only', () => {
+    const patch = [
+      'diff --git a/docs/README.md b/docs/README.md',
+      '--- a/docs/README.md',
+      '+++ b/docs/README.md',
+      '@@ -1,2 +1,2 @@',
+      '--- a/../outside.md',
+      '+++ b/../outside.md',
+      ' Bridge test content',
+      'diff --git a/src/main.ts b/src/main.ts',
+      '--- a/src/main.ts',
+      '+++ b/src/main.ts',
+      '@@ -1 +1 @@',
+      '-export const hello = "world"',
+      '+export const hello = "there"',
+      '',
+    ].join('\n')
+
+    expect(pathsInPatch(patch)).toEqual(['docs/README.md', 'src/main.ts'])
+  })
+
+  it('runs commands in the project root and stops them at the timeout', async () => {
+    const { root } = await createFixture()
+    const listed = await runCommand(root, process.execPath, ['-e', 'console.log(process.cwd())'], 10_000)
+    expect(listed.exitCode).toBe(0)
+    expect(listed.timedOut).toBe(false)
+    expect(listed.stdout.trim()).toBe(await realpath(root))
+
+    const slow = await runCommand(root, process.execPath, ['-e', 'setTimeout(() => {}, 10000)'], 100)
+    expect(slow.timedOut).toBe(true)
+  })
 })
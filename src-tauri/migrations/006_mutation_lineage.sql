@@ -0,0 +1,3 @@
+ALTER TABLE aop_mutations ADD COLUMN parent_mutation_id TEXT REFERENCES aop_mutations(id);
+
+CREATE INDEX IF NOT EXISTS idx_mutations_parent ON aop_mutations(parent_mutation_id);
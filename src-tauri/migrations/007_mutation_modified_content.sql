@@ -0,0 +1 @@
+ALTER TABLE aop_mutations ADD COLUMN modified_content TEXT;
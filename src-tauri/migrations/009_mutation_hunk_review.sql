@@ -0,0 +1,2 @@
+ALTER TABLE aop_mutations ADD COLUMN approved_hunks TEXT;
+ALTER TABLE aop_mutations ADD COLUMN rejected_hunks_diff TEXT;
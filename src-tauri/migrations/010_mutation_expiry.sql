@@ -0,0 +1,47 @@
+-- Allows the 'expired' status for proposals that went unreviewed past their TTL. SQLite
+-- cannot alter a CHECK constraint in place, so the table is rebuilt.
+CREATE TABLE aop_mutations_new (
+    id TEXT PRIMARY KEY,
+    task_id TEXT NOT NULL REFERENCES aop_tasks(id),
+    agent_uid TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    diff_content TEXT NOT NULL,
+    intent_description TEXT,
+    intent_hash TEXT,
+    confidence REAL DEFAULT 0.0,
+    test_result TEXT,
+    test_exit_code INTEGER,
+    rejection_reason TEXT,
+    rejected_at_step TEXT,
+    status TEXT DEFAULT 'proposed'
+        CHECK (status IN ('proposed', 'validated', 'validated_no_tests', 'applied', 'rejected', 'stale_base', 'expired')),
+    proposed_at INTEGER NOT NULL,
+    applied_at INTEGER,
+    parent_mutation_id TEXT REFERENCES aop_mutations_new(id),
+    modified_content TEXT,
+    base_checksum TEXT,
+    approved_hunks TEXT,
+    rejected_hunks_diff TEXT
+);
+
+INSERT INTO aop_mutations_new (
+    id, task_id, agent_uid, file_path, diff_content, intent_description, intent_hash,
+    confidence, test_result, test_exit_code, rejection_reason, rejected_at_step,
+    status, proposed_at, applied_at, parent_mutation_id, modified_content, base_checksum,
+    approved_hunks, rejected_hunks_diff
+)
+SELECT
+    id, task_id, agent_uid, file_path, diff_content, intent_description, intent_hash,
+    confidence, test_result, test_exit_code, rejection_reason, rejected_at_step,
+    status, proposed_at, applied_at, parent_mutation_id, modified_content, base_checksum,
+    approved_hunks, rejected_hunks_diff
+FROM aop_mutations;
+
+DROP TABLE aop_mutations;
+ALTER TABLE aop_mutations_new RENAME TO aop_mutations;
+
+CREATE INDEX idx_mutations_task ON aop_mutations(task_id);
+CREATE INDEX idx_mutations_status ON aop_mutations(status);
+CREATE INDEX idx_mutations_agent ON aop_mutations(agent_uid);
+CREATE INDEX idx_mutations_parent ON aop_mutations(parent_mutation_id);
+CREATE INDEX idx_mutations_proposed_at ON aop_mutations(proposed_at);
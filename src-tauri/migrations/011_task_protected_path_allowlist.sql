@@ -0,0 +1,2 @@
+-- JSON array of globs a plan may change despite the built-in protected-path deny-list.
+ALTER TABLE aop_tasks ADD COLUMN allowed_protected_paths TEXT;
@@ -0,0 +1,9 @@
+ALTER TABLE aop_vector_chunks ADD COLUMN dimension INTEGER NOT NULL DEFAULT 256;
+
+CREATE TABLE IF NOT EXISTS aop_vector_indexes (
+    project_root TEXT PRIMARY KEY,
+    embedding_provider TEXT NOT NULL,
+    dimension INTEGER NOT NULL,
+    chunk_count INTEGER NOT NULL,
+    indexed_at INTEGER NOT NULL
+);
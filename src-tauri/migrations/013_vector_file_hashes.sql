@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS aop_vector_files (
+    project_root TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    modified_at INTEGER,
+    indexed_at INTEGER NOT NULL,
+    PRIMARY KEY (project_root, file_path)
+);
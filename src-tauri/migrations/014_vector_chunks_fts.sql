@@ -0,0 +1,34 @@
+-- Keyword index over chunk names, paths and content for hybrid retrieval. `_` is kept
+-- inside tokens so snake_case identifiers match as a whole.
+CREATE VIRTUAL TABLE IF NOT EXISTS aop_vector_chunks_fts USING fts5(
+    name,
+    file_path,
+    content,
+    content = 'aop_vector_chunks',
+    tokenize = "unicode61 tokenchars '_'"
+);
+
+CREATE TRIGGER IF NOT EXISTS aop_vector_chunks_fts_insert
+AFTER INSERT ON aop_vector_chunks
+BEGIN
+    INSERT INTO aop_vector_chunks_fts(rowid, name, file_path, content)
+    VALUES (new.rowid, new.name, new.file_path, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS aop_vector_chunks_fts_delete
+AFTER DELETE ON aop_vector_chunks
+BEGIN
+    INSERT INTO aop_vector_chunks_fts(aop_vector_chunks_fts, rowid, name, file_path, content)
+    VALUES ('delete', old.rowid, old.name, old.file_path, old.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS aop_vector_chunks_fts_update
+AFTER UPDATE ON aop_vector_chunks
+BEGIN
+    INSERT INTO aop_vector_chunks_fts(aop_vector_chunks_fts, rowid, name, file_path, content)
+    VALUES ('delete', old.rowid, old.name, old.file_path, old.content);
+    INSERT INTO aop_vector_chunks_fts(rowid, name, file_path, content)
+    VALUES (new.rowid, new.name, new.file_path, new.content);
+END;
+
+INSERT INTO aop_vector_chunks_fts(aop_vector_chunks_fts) VALUES ('rebuild');
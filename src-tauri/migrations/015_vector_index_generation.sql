@@ -0,0 +1,3 @@
+-- Bumped whenever an indexing run adds or removes chunks, so derived structures such as
+-- the ANN graph know to catch up.
+ALTER TABLE aop_vector_indexes ADD COLUMN generation INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,6 @@
+-- Table name assigned to each indexed project. Unique, so two roots whose hashes collide
+-- get distinct names instead of sharing one.
+ALTER TABLE aop_vector_indexes ADD COLUMN table_name TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_vector_indexes_table_name
+    ON aop_vector_indexes(table_name);
@@ -0,0 +1,18 @@
+-- Recent commits of indexed projects, embedded for "what changed recently and why"
+-- lookups. One row per commit with an empty file_path, plus one per file it changed.
+CREATE TABLE IF NOT EXISTS aop_vector_git_history (
+    project_root TEXT NOT NULL,
+    commit_hash TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    author TEXT NOT NULL,
+    committed_at INTEGER NOT NULL,
+    subject TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    embedding_provider TEXT NOT NULL,
+    vector_json TEXT NOT NULL,
+    indexed_at INTEGER NOT NULL,
+    PRIMARY KEY (project_root, commit_hash, file_path)
+);
+
+CREATE INDEX IF NOT EXISTS idx_vector_git_history_file
+    ON aop_vector_git_history(project_root, file_path);
@@ -0,0 +1,15 @@
+-- Summaries of finished root tasks, embedded so new objectives can recall similar runs.
+CREATE TABLE IF NOT EXISTS aop_run_memories (
+    root_task_id TEXT PRIMARY KEY,
+    target_project TEXT NOT NULL,
+    objective TEXT NOT NULL,
+    plan_summary TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    status TEXT NOT NULL,
+    key_diffs_json TEXT NOT NULL,
+    embedding_provider TEXT NOT NULL,
+    vector_json TEXT NOT NULL,
+    recorded_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_run_memories_project ON aop_run_memories(target_project);
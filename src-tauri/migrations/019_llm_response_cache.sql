@@ -0,0 +1,15 @@
+-- Adapter replies keyed by a hash of provider, model and prompts, reused until they expire.
+CREATE TABLE IF NOT EXISTS aop_llm_cache (
+    cache_key TEXT PRIMARY KEY,
+    provider TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    response_text TEXT NOT NULL,
+    input_tokens INTEGER,
+    output_tokens INTEGER,
+    resolved_model TEXT,
+    hit_count INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_llm_cache_expires ON aop_llm_cache(expires_at);
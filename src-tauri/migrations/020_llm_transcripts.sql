@@ -0,0 +1,22 @@
+-- Complete adapter requests and replies, recorded only when transcript logging is enabled.
+CREATE TABLE IF NOT EXISTS aop_llm_transcripts (
+    id TEXT PRIMARY KEY,
+    root_task_id TEXT,
+    task_id TEXT,
+    provider TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    resolved_model TEXT,
+    system_prompt TEXT NOT NULL,
+    user_prompt TEXT NOT NULL,
+    response_schema_json TEXT,
+    response_text TEXT,
+    error TEXT,
+    input_tokens INTEGER,
+    output_tokens INTEGER,
+    latency_ms INTEGER NOT NULL,
+    truncated INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_llm_transcripts_root_task ON aop_llm_transcripts(root_task_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_llm_transcripts_task ON aop_llm_transcripts(task_id, created_at);
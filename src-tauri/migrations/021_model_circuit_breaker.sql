@@ -0,0 +1,4 @@
+-- Consecutive failures per model; once the streak reaches the threshold the circuit stays
+-- open until circuit_open_until and routing skips the model.
+ALTER TABLE aop_model_health ADD COLUMN consecutive_failures INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE aop_model_health ADD COLUMN circuit_open_until INTEGER;
@@ -0,0 +1,13 @@
+-- Images attached to an objective (screenshots, design exports). Specialists under the
+-- task inherit them from their ancestors.
+CREATE TABLE IF NOT EXISTS aop_task_attachments (
+    id TEXT PRIMARY KEY,
+    task_id TEXT NOT NULL REFERENCES aop_tasks(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    media_type TEXT NOT NULL,
+    data_base64 TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_attachments_task ON aop_task_attachments(task_id);
@@ -0,0 +1,3 @@
+-- Calls abandoned at their deadline, counted apart from other failures so routing can
+-- deprioritize models that hang.
+ALTER TABLE aop_model_health ADD COLUMN timeout_calls INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,37 @@
+-- Observed mutation outcomes per model within a routing segment (tier, persona, skill).
+-- Empty persona/skill stand for "none" so they can be part of the primary key.
+CREATE TABLE IF NOT EXISTS aop_model_arms (
+    tier INTEGER NOT NULL,
+    persona TEXT NOT NULL DEFAULT '',
+    skill TEXT NOT NULL DEFAULT '',
+    provider TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    pulls INTEGER NOT NULL DEFAULT 0,
+    reward_sum REAL NOT NULL DEFAULT 0,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (tier, persona, skill, provider, model_id)
+);
+
+-- Which model produced a mutation, settled once the mutation is applied or rejected.
+CREATE TABLE IF NOT EXISTS aop_mutation_arms (
+    mutation_id TEXT PRIMARY KEY REFERENCES aop_mutations(id) ON DELETE CASCADE,
+    tier INTEGER NOT NULL,
+    persona TEXT NOT NULL DEFAULT '',
+    skill TEXT NOT NULL DEFAULT '',
+    provider TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    reward REAL,
+    created_at INTEGER NOT NULL,
+    settled_at INTEGER
+);
+
+-- Pinned models bypass adaptive selection for their segment; empty persona/skill match any.
+CREATE TABLE IF NOT EXISTS aop_model_pins (
+    tier INTEGER NOT NULL,
+    persona TEXT NOT NULL DEFAULT '',
+    skill TEXT NOT NULL DEFAULT '',
+    provider TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (tier, persona, skill)
+);
@@ -0,0 +1,34 @@
+-- A/B routing trials: a share of a segment's selections go to the variant model.
+-- Empty persona/skill match any, as for pins.
+CREATE TABLE IF NOT EXISTS aop_model_experiments (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    tier INTEGER NOT NULL,
+    persona TEXT NOT NULL DEFAULT '',
+    skill TEXT NOT NULL DEFAULT '',
+    control_provider TEXT NOT NULL,
+    control_model_id TEXT NOT NULL,
+    variant_provider TEXT NOT NULL,
+    variant_model_id TEXT NOT NULL,
+    variant_percent INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'running',
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_aop_model_experiments_segment
+    ON aop_model_experiments(tier, status);
+
+-- Every selection an experiment decided, tagging the task with the arm it ran on.
+CREATE TABLE IF NOT EXISTS aop_experiment_assignments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    experiment_id TEXT NOT NULL REFERENCES aop_model_experiments(id) ON DELETE CASCADE,
+    arm TEXT NOT NULL,
+    task_id TEXT,
+    provider TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_aop_experiment_assignments_experiment
+    ON aop_experiment_assignments(experiment_id, arm);
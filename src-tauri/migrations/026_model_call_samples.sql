@@ -0,0 +1,15 @@
+-- One row per model call outcome. aop_model_health only keeps running averages, so trends
+-- and failure streaks over a window are computed from these.
+CREATE TABLE IF NOT EXISTS aop_model_call_samples (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    provider TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    success INTEGER NOT NULL,
+    timed_out INTEGER NOT NULL DEFAULT 0,
+    latency_ms INTEGER,
+    cost_usd REAL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_aop_model_call_samples_created
+    ON aop_model_call_samples(created_at);
@@ -0,0 +1,67 @@
+-- Keyword indexes over telemetry messages and audit details, kept in sync by triggers
+-- so archiving events also drops them from the index.
+CREATE VIRTUAL TABLE IF NOT EXISTS aop_agent_events_fts USING fts5(
+    message,
+    payload_json,
+    content = 'aop_agent_events',
+    content_rowid = 'id',
+    tokenize = "unicode61 tokenchars '_'"
+);
+
+CREATE TRIGGER IF NOT EXISTS aop_agent_events_fts_insert
+AFTER INSERT ON aop_agent_events
+BEGIN
+    INSERT INTO aop_agent_events_fts(rowid, message, payload_json)
+    VALUES (new.id, new.message, new.payload_json);
+END;
+
+CREATE TRIGGER IF NOT EXISTS aop_agent_events_fts_delete
+AFTER DELETE ON aop_agent_events
+BEGIN
+    INSERT INTO aop_agent_events_fts(aop_agent_events_fts, rowid, message, payload_json)
+    VALUES ('delete', old.id, old.message, old.payload_json);
+END;
+
+CREATE TRIGGER IF NOT EXISTS aop_agent_events_fts_update
+AFTER UPDATE ON aop_agent_events
+BEGIN
+    INSERT INTO aop_agent_events_fts(aop_agent_events_fts, rowid, message, payload_json)
+    VALUES ('delete', old.id, old.message, old.payload_json);
+    INSERT INTO aop_agent_events_fts(rowid, message, payload_json)
+    VALUES (new.id, new.message, new.payload_json);
+END;
+
+INSERT INTO aop_agent_events_fts(aop_agent_events_fts) VALUES ('rebuild');
+
+CREATE VIRTUAL TABLE IF NOT EXISTS aop_audit_log_fts USING fts5(
+    action,
+    details,
+    content = 'aop_audit_log',
+    content_rowid = 'id',
+    tokenize = "unicode61 tokenchars '_'"
+);
+
+CREATE TRIGGER IF NOT EXISTS aop_audit_log_fts_insert
+AFTER INSERT ON aop_audit_log
+BEGIN
+    INSERT INTO aop_audit_log_fts(rowid, action, details)
+    VALUES (new.id, new.action, new.details);
+END;
+
+CREATE TRIGGER IF NOT EXISTS aop_audit_log_fts_delete
+AFTER DELETE ON aop_audit_log
+BEGIN
+    INSERT INTO aop_audit_log_fts(aop_audit_log_fts, rowid, action, details)
+    VALUES ('delete', old.id, old.action, old.details);
+END;
+
+CREATE TRIGGER IF NOT EXISTS aop_audit_log_fts_update
+AFTER UPDATE ON aop_audit_log
+BEGIN
+    INSERT INTO aop_audit_log_fts(aop_audit_log_fts, rowid, action, details)
+    VALUES ('delete', old.id, old.action, old.details);
+    INSERT INTO aop_audit_log_fts(rowid, action, details)
+    VALUES (new.id, new.action, new.details);
+END;
+
+INSERT INTO aop_audit_log_fts(aop_audit_log_fts) VALUES ('rebuild');
@@ -0,0 +1,4 @@
+-- Rows restored from a telemetry archive get a fresh retention window, counted from
+-- the import instead of when they were first recorded.
+ALTER TABLE aop_agent_events ADD COLUMN imported_at INTEGER;
+ALTER TABLE aop_agent_runs ADD COLUMN imported_at INTEGER;
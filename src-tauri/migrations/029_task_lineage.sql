@@ -0,0 +1,38 @@
+-- Materialized lineage per task: the tree root and a `/root/.../self/` path, so subtree,
+-- tier and root lookups are single indexed queries instead of parent-pointer walks.
+-- Parent links never change after insert, so the trigger below keeps both columns current.
+ALTER TABLE aop_tasks ADD COLUMN root_task_id TEXT;
+ALTER TABLE aop_tasks ADD COLUMN lineage_path TEXT;
+
+WITH RECURSIVE lineage(id, root_task_id, lineage_path) AS (
+    SELECT t.id, t.id, '/' || t.id || '/'
+    FROM aop_tasks t
+    WHERE t.parent_id IS NULL
+       OR TRIM(t.parent_id) = ''
+       OR NOT EXISTS (SELECT 1 FROM aop_tasks p WHERE p.id = t.parent_id)
+    UNION ALL
+    SELECT child.id, lineage.root_task_id, lineage.lineage_path || child.id || '/'
+    FROM aop_tasks child
+    JOIN lineage ON child.parent_id = lineage.id
+)
+UPDATE aop_tasks
+SET root_task_id = (SELECT l.root_task_id FROM lineage l WHERE l.id = aop_tasks.id),
+    lineage_path = (SELECT l.lineage_path FROM lineage l WHERE l.id = aop_tasks.id);
+
+CREATE INDEX IF NOT EXISTS idx_aop_tasks_root_task_id ON aop_tasks(root_task_id, tier);
+CREATE INDEX IF NOT EXISTS idx_aop_tasks_lineage_path ON aop_tasks(lineage_path);
+
+CREATE TRIGGER IF NOT EXISTS aop_tasks_lineage_insert
+AFTER INSERT ON aop_tasks
+BEGIN
+    UPDATE aop_tasks
+    SET root_task_id = COALESCE(
+            (SELECT p.root_task_id FROM aop_tasks p WHERE p.id = new.parent_id),
+            new.id
+        ),
+        lineage_path = COALESCE(
+            (SELECT p.lineage_path FROM aop_tasks p WHERE p.id = new.parent_id),
+            '/'
+        ) || new.id || '/'
+    WHERE id = new.id;
+END;
@@ -0,0 +1,2 @@
+-- Dispatch order among sibling assignments: higher runs first, ties keep plan order.
+ALTER TABLE aop_tasks ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,9 @@
+-- Wall-clock budget per task, counted from when it first starts executing.
+ALTER TABLE aop_tasks ADD COLUMN deadline_seconds INTEGER;
+ALTER TABLE aop_tasks ADD COLUMN started_at INTEGER;
+
+UPDATE aop_tasks SET started_at = updated_at WHERE status = 'executing';
+
+CREATE INDEX IF NOT EXISTS idx_aop_tasks_deadline
+    ON aop_tasks(status, deadline_seconds)
+    WHERE deadline_seconds IS NOT NULL;
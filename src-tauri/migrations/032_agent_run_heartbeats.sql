@@ -0,0 +1,10 @@
+-- Liveness of open runs: checkpoints and new events refresh `heartbeat_at`, and the stall
+-- watchdog sets `stalled_at` on executing runs that stay silent too long.
+ALTER TABLE aop_agent_runs ADD COLUMN heartbeat_at INTEGER;
+ALTER TABLE aop_agent_runs ADD COLUMN stalled_at INTEGER;
+
+UPDATE aop_agent_runs SET heartbeat_at = started_at WHERE ended_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_agent_runs_open_heartbeat
+    ON aop_agent_runs(heartbeat_at)
+    WHERE ended_at IS NULL;
@@ -0,0 +1,12 @@
+-- Auto-approval policies for budget requests. A request is approved without a human when
+-- any enabled policy admits it; NULL limits and an empty tier list do not restrict.
+CREATE TABLE IF NOT EXISTS aop_budget_policies (
+    name TEXT PRIMARY KEY,
+    max_increment INTEGER,
+    max_total_per_root INTEGER,
+    allowed_tiers TEXT NOT NULL DEFAULT '',
+    max_risk REAL,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
@@ -0,0 +1,8 @@
+-- Model spend per UTC day (`YYYY-MM-DD`), summed from each call's cost, so the global
+-- daily and monthly spend caps survive restarts.
+CREATE TABLE IF NOT EXISTS aop_spend_ledger (
+    day TEXT PRIMARY KEY,
+    cost_usd REAL NOT NULL DEFAULT 0,
+    calls INTEGER NOT NULL DEFAULT 0,
+    updated_at INTEGER NOT NULL
+);
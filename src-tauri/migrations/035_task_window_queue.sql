@@ -0,0 +1,3 @@
+-- Set while an approved root task waits for its execution window. The task stays
+-- `pending` meanwhile and the marker is cleared once the plan starts.
+ALTER TABLE aop_tasks ADD COLUMN queued_at INTEGER;
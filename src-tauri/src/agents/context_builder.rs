@@ -0,0 +1,369 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use sqlx::SqlitePool;
+
+use crate::agents::CodeBlock;
+use crate::vector::symbols;
+use crate::vector::ContextChunk;
+
+const MAX_OBJECTIVE_SYMBOLS: usize = 3;
+const MAX_DEFINITIONS_PER_SYMBOL: usize = 2;
+const MAX_REFERENCES_PER_SYMBOL: u32 = 8;
+const MAX_FILE_TREE_ENTRIES: usize = 40;
+/// Share of an assignment's token budget spent on context around the target file.
+const CONTEXT_BUDGET_SHARE: f32 = 0.4;
+const MIN_CONTEXT_TOKENS: u32 = 200;
+const MAX_CONTEXT_TOKENS: u32 = 6_000;
+
+/// What an agent assignment should see besides the target files themselves.
+pub struct ContextRequest<'a> {
+    pub target_project: &'a str,
+    pub objective: &'a str,
+    /// Files the assignment edits; their full content is sent separately, so their
+    /// chunks are left out of the pack.
+    pub target_files: &'a [String],
+    /// Chunks the caller already retrieved for the objective, best first.
+    pub retrieved: &'a [ContextChunk],
+    pub token_budget: u32,
+}
+
+/// Deduplicated, token-budgeted context in priority order: definitions of the symbols
+/// the objective names, then related code and usages, then nearby files.
+#[derive(Debug, Clone, Default)]
+pub struct ContextPack {
+    pub definitions: Vec<CodeBlock>,
+    pub usages: Vec<CodeBlock>,
+    pub file_tree: Vec<String>,
+    pub estimated_tokens: u32,
+    /// Candidates left out as duplicates or for exceeding the budget.
+    pub dropped: u32,
+}
+
+impl ContextPack {
+    /// Definitions followed by usages, the order prompts present them in.
+    pub fn code_blocks(&self) -> Vec<CodeBlock> {
+        self.definitions
+            .iter()
+            .chain(self.usages.iter())
+            .cloned()
+            .collect()
+    }
+}
+
+/// Context budget for an assignment with `token_budget` tokens in total.
+pub fn context_budget(token_budget: u32) -> u32 {
+    ((token_budget as f32 * CONTEXT_BUDGET_SHARE) as u32)
+        .clamp(MIN_CONTEXT_TOKENS, MAX_CONTEXT_TOKENS)
+}
+
+pub async fn build_context_pack(pool: &SqlitePool, request: ContextRequest<'_>) -> ContextPack {
+    let mut definitions = Vec::new();
+    let mut references = Vec::new();
+    for symbol in objective_symbols(request.objective)
+        .into_iter()
+        .take(MAX_OBJECTIVE_SYMBOLS)
+    {
+        // A project without an index simply contributes no symbol context.
+        let Ok(result) = symbols::search_symbols(
+            pool,
+            request.target_project,
+            &symbol,
+            Some(MAX_REFERENCES_PER_SYMBOL),
+        )
+        .await
+        else {
+            continue;
+        };
+        definitions.extend(
+            result
+                .definitions
+                .into_iter()
+                .take(MAX_DEFINITIONS_PER_SYMBOL)
+                .map(|location| CodeBlock {
+                    file_path: location.file_path,
+                    start_line: location.start_line,
+                    end_line: location.end_line,
+                    content: location.snippet,
+                    embedding: None,
+                }),
+        );
+        references.extend(result.references.into_iter().map(|location| CodeBlock {
+            file_path: location.file_path,
+            start_line: location.start_line,
+            end_line: location.end_line,
+            content: location.snippet,
+            embedding: None,
+        }));
+    }
+    let related = request.retrieved.iter().map(|chunk| CodeBlock {
+        file_path: chunk.file_path.clone(),
+        start_line: chunk.start_line,
+        end_line: chunk.end_line,
+        content: chunk.content.clone(),
+        embedding: None,
+    });
+
+    let mut packer = Packer::new(request.target_files, request.token_budget);
+    let mut pack = ContextPack {
+        definitions: packer.take(definitions),
+        usages: packer.take(related.chain(references)),
+        ..ContextPack::default()
+    };
+
+    let mut nearby = BTreeSet::new();
+    for file in request
+        .target_files
+        .iter()
+        .chain(pack.definitions.iter().map(|block| &block.file_path))
+        .chain(pack.usages.iter().map(|block| &block.file_path))
+    {
+        nearby.insert(file.clone());
+    }
+    for file in request.retrieved.iter().map(|chunk| &chunk.file_path) {
+        let sibling_of_target = request
+            .target_files
+            .iter()
+            .any(|target| parent_dir(target) == parent_dir(file));
+        if sibling_of_target {
+            nearby.insert(file.clone());
+        }
+    }
+    for file in nearby.into_iter().take(MAX_FILE_TREE_ENTRIES) {
+        if packer.fits(estimate_tokens(&file) + 1) {
+            pack.file_tree.push(file);
+        } else {
+            packer.dropped += 1;
+        }
+    }
+
+    pack.estimated_tokens = packer.used;
+    pack.dropped = packer.dropped;
+    pack
+}
+
+/// Renders code blocks and a file list as prompt context, again dropping overlapping
+/// blocks and stopping at `token_budget`, so callers that received blocks from
+/// elsewhere get the same shape.
+pub fn render_context(blocks: &[CodeBlock], file_tree: &[String], token_budget: u32) -> String {
+    let mut packer = Packer::new(&[], token_budget);
+    let blocks = packer.take(blocks.iter().cloned());
+    let mut sections = blocks
+        .iter()
+        .map(|block| {
+            format!(
+                "[{}:{}-{}]\n{}",
+                block.file_path, block.start_line, block.end_line, block.content
+            )
+        })
+        .collect::<Vec<_>>();
+    if !file_tree.is_empty() {
+        sections.push(format!("nearbyFiles:\n{}", file_tree.join("\n")));
+    }
+    sections.join("\n\n")
+}
+
+/// Chars / 4, the estimate used for prompt budgeting across the agents.
+pub fn estimate_tokens(text: &str) -> u32 {
+    (text.len() / 4).max(1) as u32
+}
+
+struct Packer<'a> {
+    excluded_files: &'a [String],
+    budget: u32,
+    used: u32,
+    dropped: u32,
+    kept: Vec<(String, u32, u32)>,
+}
+
+impl<'a> Packer<'a> {
+    fn new(excluded_files: &'a [String], budget: u32) -> Self {
+        Self {
+            excluded_files,
+            budget,
+            used: 0,
+            dropped: 0,
+            kept: Vec::new(),
+        }
+    }
+
+    fn fits(&mut self, tokens: u32) -> bool {
+        if self.used + tokens > self.budget {
+            return false;
+        }
+        self.used += tokens;
+        true
+    }
+
+    /// Keeps blocks in order that neither overlap a kept block nor sit in an excluded
+    /// file, while they fit the budget. Smaller blocks further down may still fit after a
+    /// large one is skipped.
+    fn take(&mut self, blocks: impl IntoIterator<Item = CodeBlock>) -> Vec<CodeBlock> {
+        let mut taken = Vec::new();
+        for block in blocks {
+            let excluded = self.excluded_files.contains(&block.file_path);
+            let overlaps = self.kept.iter().any(|(file, start, end)| {
+                *file == block.file_path && block.start_line <= *end && *start <= block.end_line
+            });
+            if excluded || overlaps || !self.fits(estimate_tokens(&block.content)) {
+                self.dropped += 1;
+                continue;
+            }
+            self.kept
+                .push((block.file_path.clone(), block.start_line, block.end_line));
+            taken.push(block);
+        }
+        taken
+    }
+}
+
+fn parent_dir(path: &str) -> &str {
+    Path::new(path)
+        .parent()
+        .and_then(|parent| parent.to_str())
+        .unwrap_or_default()
+}
+
+/// Identifiers in backticks plus words shaped like code (`snake_case`, `camelCase`,
+/// `PascalCase`, `Type::member`), in order of appearance.
+fn objective_symbols(objective: &str) -> Vec<String> {
+    let mut symbols: Vec<String> = Vec::new();
+    for (index, segment) in objective.split('`').enumerate() {
+        let quoted = index % 2 == 1;
+        if quoted && segment.contains(['/', '\\']) {
+            // Quoted paths name files, not symbols.
+            continue;
+        }
+        for word in segment.split(|ch: char| !ch.is_alphanumeric() && ch != '_' && ch != ':') {
+            let word = word.trim_matches(':');
+            let code_shaped = word.contains('_')
+                || word.contains("::")
+                || (word.chars().skip(1).any(|ch| ch.is_ascii_uppercase())
+                    && word.chars().any(|ch| ch.is_ascii_lowercase()));
+            if word.len() >= 3
+                && (quoted || code_shaped)
+                && word
+                    .chars()
+                    .next()
+                    .is_some_and(|ch| ch.is_alphabetic() || ch == '_')
+                && !symbols.iter().any(|existing| existing == word)
+            {
+                symbols.push(word.to_string());
+            }
+        }
+    }
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::db;
+    use crate::vector::indexer;
+
+    use super::*;
+
+    #[test]
+    fn objective_symbols_picks_code_shaped_words() {
+        assert_eq!(
+            objective_symbols(
+                "Make `evict` honor maxEntries in LruCache::insert and read_config, see `src/cache.rs`"
+            ),
+            vec!["evict", "maxEntries", "LruCache::insert", "read_config"]
+        );
+    }
+
+    #[tokio::test]
+    async fn packs_definitions_before_usages_without_duplicates() {
+        let project = tempdir().expect("project temp dir should exist");
+        std::fs::create_dir_all(project.path().join("src")).expect("src dir");
+        std::fs::write(
+            project.path().join("src/cache.rs"),
+            "pub fn evict_stale(entries: &mut Vec<u32>) {\n    entries.clear();\n}\n",
+        )
+        .expect("fixture should be written");
+        std::fs::write(
+            project.path().join("src/worker.rs"),
+            "pub fn tick(entries: &mut Vec<u32>) {\n    evict_stale(entries);\n}\n",
+        )
+        .expect("fixture should be written");
+        std::fs::write(project.path().join("src/main.rs"), "fn main() {}\n")
+            .expect("fixture should be written");
+
+        let db_dir = tempdir().expect("db temp dir should exist");
+        let pool = db::connect_pool(&db_dir.path().join("context.db"))
+            .await
+            .expect("sqlite pool should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should initialize");
+        let root = project.path().to_string_lossy().to_string();
+        indexer::index_project(&pool, &root)
+            .await
+            .expect("indexing should succeed");
+
+        let chunk = |file_path: &str, content: &str| ContextChunk {
+            id: file_path.to_string(),
+            file_path: file_path.to_string(),
+            start_line: 1,
+            end_line: 3,
+            chunk_type: "function".to_string(),
+            name: String::new(),
+            content: content.to_string(),
+            score: 1.0,
+        };
+        // The definition is also a retrieved chunk, and the target file is retrieved too.
+        let retrieved = vec![
+            chunk(
+                "src/cache.rs",
+                "pub fn evict_stale(entries: &mut Vec<u32>) {",
+            ),
+            chunk("src/main.rs", "fn main() {}"),
+        ];
+        let target_files = vec!["src/main.rs".to_string()];
+        let pack = build_context_pack(
+            &pool,
+            ContextRequest {
+                target_project: &root,
+                objective: "Call evict_stale from main",
+                target_files: &target_files,
+                retrieved: &retrieved,
+                token_budget: 500,
+            },
+        )
+        .await;
+
+        let files = |blocks: &[CodeBlock]| {
+            blocks
+                .iter()
+                .map(|block| (block.file_path.clone(), block.start_line))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            files(&pack.definitions),
+            vec![("src/cache.rs".to_string(), 1)]
+        );
+        assert_eq!(files(&pack.usages), vec![("src/worker.rs".to_string(), 2)]);
+        assert_eq!(pack.dropped, 2);
+        assert_eq!(
+            pack.file_tree,
+            vec!["src/cache.rs", "src/main.rs", "src/worker.rs"]
+        );
+
+        let tight = build_context_pack(
+            &pool,
+            ContextRequest {
+                target_project: &root,
+                objective: "Call evict_stale from main",
+                target_files: &target_files,
+                retrieved: &retrieved,
+                token_budget: 5,
+            },
+        )
+        .await;
+        assert!(tight.estimated_tokens <= 5);
+        assert!(tight.definitions.is_empty());
+        assert!(!tight.usages.is_empty());
+    }
+}
@@ -1,22 +1,35 @@
 use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 
-use crate::agents::specialist::{self, DiffProposal, SpecialistTask};
-use crate::agents::CodeBlock;
+use crate::agents;
+use crate::agents::context_builder::{self, ContextRequest};
+use crate::agents::specialist::{self, DiffProposal, SpecialistJob, SpecialistTask};
+use crate::compliance;
+use crate::db::attachments;
+use crate::db::model_arms::{self, ArmSegment};
 use crate::db::mutations::{self, CreateMutationInput};
 use crate::db::tasks::{
     self, CreateTaskRecordInput, TaskStatus, UpdateTaskOutcomeInput, UpdateTaskStatusInput,
 };
+use crate::db::telemetry;
+use crate::llm_adapter;
 use crate::mcp_bridge::client::BridgeClient;
+use crate::mcp_bridge::command_runner::{self, RunTargetCommandInput};
+use crate::mcp_bridge::tool_cache;
 use crate::mcp_bridge::tool_caller::{self, ReadTargetFileInput, SearchTargetFilesInput};
-use crate::model_intelligence::{self, ModelSelectionRequest};
-use crate::model_registry::ModelRegistry;
-use crate::task_runtime;
+use crate::mcp_servers;
+use crate::model_intelligence::{
+    self, CostPreference, ModelRequirements, ModelSelectionRequest, ModelSelectionResult,
+};
+use crate::model_registry::{ModelRegistry, ModelSelection};
+use crate::notifications::{self, NotificationKind};
+use crate::protected_paths;
+use crate::task_runtime::{self, ExecutionLane};
 use crate::vector::search;
 use crate::vector::ContextChunk;
 
@@ -26,6 +39,8 @@ pub struct ExecuteDomainTaskInput {
     pub task_id: String,
     pub target_project: String,
     pub top_k: Option<u32>,
+    /// Registered MCP server for file tools; the task's domain must be allowed to use it.
+    pub mcp_server: Option<String>,
     pub mcp_command: Option<String>,
     pub mcp_args: Option<Vec<String>>,
 }
@@ -53,6 +68,17 @@ pub struct IntentSummary {
     pub conflicts: Option<ConflictReport>,
 }
 
+/// Routing skill for specialist model selection; applied mutations are credited to it.
+const SPECIALIST_SKILL: &str = "specialist_assignment";
+
+/// A specialist task record waiting on its batched model call.
+struct PreparedSpecialist {
+    record_id: String,
+    persona: String,
+    target_file: String,
+    model: ModelSelection,
+}
+
 pub async fn execute_domain_task(
     pool: &SqlitePool,
     bridge_client: &BridgeClient,
@@ -60,6 +86,7 @@ pub async fn execute_domain_task(
     input: ExecuteDomainTaskInput,
 ) -> Result<IntentSummary, String> {
     validate_input(&input)?;
+    let task = tasks::get_task_by_id(pool, input.task_id.trim()).await?;
     let tier2_model = model_intelligence::select_model(
         pool,
         model_registry,
@@ -69,11 +96,13 @@ pub async fn execute_domain_task(
             tier: 2,
             persona: None,
             skill: Some("domain_coordination"),
+            remaining_budget: Some(task.token_budget - task.token_usage),
+            cost_preference: CostPreference::for_task(2, &task.domain, task.risk_factor),
+            requirements: ModelRequirements::default(),
         },
     )
     .await?
     .selection;
-    let task = tasks::get_task_by_id(pool, input.task_id.trim()).await?;
 
     if task.tier != 2 {
         return Err(format!(
@@ -81,11 +110,17 @@ pub async fn execute_domain_task(
             task.id, task.tier
         ));
     }
+    if let Some(server) = input.mcp_server.as_deref() {
+        mcp_servers::resolve_for_domain(server, &task.domain)?;
+    }
+    let mcp_tools = tool_cache::tools_for_prompt(bridge_client, input.mcp_server.as_deref()).await;
 
+    let allowed_protected = protected_paths::plan_allowlist(pool, &task.id).await?;
     let stored_target_files: Vec<String> = task
         .target_files
         .as_deref()
-        .and_then(|json| serde_json::from_str(json).ok())
+        .and_then(|json| serde_json::from_str::<Vec<String>>(json).ok())
+        .map(|files| protected_paths::partition_allowed(&files, &allowed_protected).0)
         .unwrap_or_default();
 
     tasks::update_task_status(
@@ -109,6 +144,7 @@ pub async fn execute_domain_task(
         ),
     )
     .await?;
+    run_coordination_commands(pool, bridge_client, &task.id, &input).await;
 
     // Skip expensive vector search when we already have target files from the plan
     let (chunks, candidate_files) = if !stored_target_files.is_empty() {
@@ -119,11 +155,13 @@ pub async fn execute_domain_task(
             &input.target_project,
             &task.objective,
             input.top_k.unwrap_or(8).max(3),
+            &agents::retrieval_filters(&task.objective),
         )
         .await
         .unwrap_or_default();
 
         let candidate_files = collect_candidate_files(
+            pool,
             bridge_client,
             &input.target_project,
             &task.domain,
@@ -132,6 +170,8 @@ pub async fn execute_domain_task(
             &input,
         )
         .await;
+        let candidate_files =
+            protected_paths::partition_allowed(&candidate_files, &allowed_protected).0;
         (chunks, candidate_files)
     };
     task_runtime::record_task_activity(
@@ -153,6 +193,9 @@ pub async fn execute_domain_task(
     let specialist_budgets = distribute_budget_for_specialists(task.token_budget, personas.len());
     let mut proposals: Vec<DiffProposal> = Vec::with_capacity(personas.len());
     let mut tokens_spent = 0_u32;
+    let mut jobs: Vec<SpecialistJob> = Vec::with_capacity(personas.len());
+    let mut prepared: Vec<PreparedSpecialist> = Vec::with_capacity(personas.len());
+    let design_images = attachments::design_images_for(pool, &task).await?;
 
     for (idx, persona) in personas.iter().enumerate() {
         task_runtime::cooperative_checkpoint(
@@ -192,7 +235,29 @@ pub async fn execute_domain_task(
                 })
                 .unwrap_or_else(|| infer_target_path_from_objective(&specialist_objective))
         };
-        let code_context = hydrate_code_context(&chunks, &target_file, 2);
+        if let Err(reason) = protected_paths::check_path(&target_file, &allowed_protected) {
+            task_runtime::record_task_activity(
+                pool,
+                "tier2_domain_leader",
+                "tier3_target_protected",
+                &task.id,
+                &format!("persona={persona} {reason}"),
+            )
+            .await?;
+            continue;
+        }
+        let target_files = vec![target_file.clone()];
+        let context_pack = context_builder::build_context_pack(
+            pool,
+            ContextRequest {
+                target_project: &input.target_project,
+                objective: &task.objective,
+                target_files: &target_files,
+                retrieved: &chunks,
+                token_budget: context_builder::context_budget(specialist_budgets[idx]),
+            },
+        )
+        .await;
         let specialist_task_record = tasks::create_task_record(
             pool,
             CreateTaskRecordInput {
@@ -206,6 +271,7 @@ pub async fn execute_domain_task(
                 target_files: Some(
                     serde_json::to_string(&vec![target_file.clone()]).unwrap_or_default(),
                 ),
+                priority: task.priority,
             },
         )
         .await?;
@@ -215,12 +281,22 @@ pub async fn execute_domain_task(
             "tier3_task_created",
             &specialist_task_record.id,
             &format!(
-                "parent={} persona={} targetFile={} budget={}",
-                task.id, persona, target_file, specialist_budgets[idx]
+                "parent={} persona={} targetFile={} budget={} definitions={} usages={} contextTokens={}",
+                task.id,
+                persona,
+                target_file,
+                specialist_budgets[idx],
+                context_pack.definitions.len(),
+                context_pack.usages.len(),
+                context_pack.estimated_tokens
             ),
         )
         .await?;
-        let specialist_model = model_intelligence::select_model(
+        let ModelSelectionResult {
+            selection: specialist_model,
+            fallbacks: specialist_fallbacks,
+            ..
+        } = model_intelligence::select_model(
             pool,
             model_registry,
             ModelSelectionRequest {
@@ -228,13 +304,15 @@ pub async fn execute_domain_task(
                 actor: "tier2_domain_leader",
                 tier: 3,
                 persona: Some(persona.as_str()),
-                skill: Some("specialist_assignment"),
+                skill: Some(SPECIALIST_SKILL),
+                remaining_budget: Some(i64::from(specialist_budgets[idx])),
+                cost_preference: CostPreference::for_task(3, &task.domain, task.risk_factor),
+                requirements: ModelRequirements::for_specialist(!design_images.is_empty()),
             },
         )
-        .await?
-        .selection;
+        .await?;
 
-        let file_content = read_file_with_fallback(bridge_client, &input, &target_file).await;
+        let file_content = read_file_with_fallback(pool, bridge_client, &input, &target_file).await;
         task_runtime::cooperative_checkpoint(
             pool,
             &specialist_task_record.id,
@@ -270,8 +348,9 @@ pub async fn execute_domain_task(
             persona: persona.clone(),
             objective: specialist_objective,
             token_budget: specialist_budgets[idx],
-            target_files: vec![target_file.clone()],
-            code_context,
+            target_files,
+            code_context: context_pack.code_blocks(),
+            file_tree: context_pack.file_tree,
             constraints: {
                 let mut constraints =
                     build_constraints_for_specialist(&task.domain, task.risk_factor as f32);
@@ -289,18 +368,60 @@ pub async fn execute_domain_task(
             },
             model_provider: Some(specialist_model.provider.clone()),
             model_id: Some(specialist_model.model_id.clone()),
+            model_endpoint: specialist_model.endpoint.clone(),
+            model_generation: specialist_model.generation.clone(),
+            model_fallbacks: specialist_fallbacks,
+            target_project: Some(input.target_project.trim().to_string()),
+            attachments: design_images.clone(),
+            mcp_tools: mcp_tools.clone(),
         };
+        jobs.push((specialist_task, file_content));
+        prepared.push(PreparedSpecialist {
+            record_id: specialist_task_record.id,
+            persona: persona.clone(),
+            target_file,
+            model: specialist_model,
+        });
+    }
 
-        let model_started_at = Instant::now();
-        match specialist::run_specialist_task(&specialist_task, file_content.as_deref()) {
+    // Specialists are independent, so their model calls run concurrently, up to the slots
+    // the execution governor grants this batch.
+    let requested_slots = jobs.len().min(llm_adapter::batch_parallelism()) as u32;
+    let specialist_permit = task_runtime::acquire_execution_slots(
+        pool,
+        ExecutionLane::Specialist,
+        &task.id,
+        None,
+        requested_slots,
+    )
+    .await?;
+    let max_parallel = specialist_permit.slots() as usize;
+    let batch_pool = pool.clone();
+    let (jobs, outcomes) = tokio::task::spawn_blocking(move || {
+        let outcomes = specialist::run_specialist_tasks(&jobs, max_parallel, Some(&batch_pool));
+        (jobs, outcomes)
+    })
+    .await
+    .map_err(|error| format!("Specialist batch panicked: {error}"))?;
+    drop(specialist_permit);
+    for ((prepared, job), attempt) in prepared.into_iter().zip(jobs).zip(outcomes) {
+        let (outcome, latency) =
+            retry_failed_specialist(pool, &task.id, &prepared, job, attempt).await?;
+        let PreparedSpecialist {
+            record_id,
+            persona,
+            target_file,
+            model: specialist_model,
+        } = prepared;
+        match outcome {
             Ok(proposal) => {
                 model_intelligence::record_model_call_outcome(
                     pool,
                     specialist_model.provider.as_str(),
                     specialist_model.model_id.as_str(),
                     true,
-                    Some(model_started_at.elapsed().as_millis() as i64),
-                    None,
+                    Some(latency.as_millis() as i64),
+                    proposal.cost_usd,
                     None,
                 )
                 .await;
@@ -315,7 +436,7 @@ pub async fn execute_domain_task(
                     tasks::update_task_status(
                         pool,
                         UpdateTaskStatusInput {
-                            task_id: specialist_task_record.id.clone(),
+                            task_id: record_id.clone(),
                             status: TaskStatus::Failed,
                             error_message: Some(error),
                         },
@@ -325,22 +446,8 @@ pub async fn execute_domain_task(
                 }
 
                 tokens_spent = tokens_spent.saturating_add(proposal.tokens_used);
-                tasks::update_task_outcome(
-                    pool,
-                    UpdateTaskOutcomeInput {
-                        task_id: task.id.clone(),
-                        status: TaskStatus::Executing,
-                        token_usage: Some(i64::from(tokens_spent)),
-                        context_efficiency_ratio: None,
-                        compliance_score: None,
-                        checksum_before: None,
-                        checksum_after: None,
-                        error_message: None,
-                    },
-                )
-                .await?;
 
-                mutations::create_mutation(
+                let mutation = mutations::create_mutation(
                     pool,
                     CreateMutationInput {
                         task_id: task.id.clone(),
@@ -350,16 +457,27 @@ pub async fn execute_domain_task(
                         intent_description: Some(proposal.intent_description.clone()),
                         intent_hash: Some(proposal.intent_hash.clone()),
                         confidence: proposal.confidence as f64,
+                        parent_mutation_id: None,
+                        modified_content: proposal.modified_content.clone(),
+                        base_checksum: proposal.base_checksum.clone(),
                     },
                 )
                 .await?;
+                model_arms::record_mutation_arm(
+                    pool,
+                    &mutation.id,
+                    &ArmSegment::new(3, Some(&persona), Some(SPECIALIST_SKILL)),
+                    &specialist_model.provider,
+                    &specialist_model.model_id,
+                )
+                .await?;
 
                 tasks::update_task_outcome(
                     pool,
                     UpdateTaskOutcomeInput {
-                        task_id: specialist_task_record.id,
+                        task_id: record_id,
                         status: TaskStatus::Completed,
-                        token_usage: Some(i64::from(proposal.tokens_used)),
+                        token_usage: None,
                         context_efficiency_ratio: None,
                         compliance_score: None,
                         checksum_before: None,
@@ -374,11 +492,14 @@ pub async fn execute_domain_task(
                     "specialist_proposal_persisted",
                     &task.id,
                     &format!(
-                        "agentUid={} file={} confidence={:.2} tokensUsed={}",
+                        "agentUid={} file={} confidence={:.2} tokensUsed={} tokensIn={} tokensOut={}{}",
                         proposal.agent_uid,
                         proposal.file_path,
                         proposal.confidence,
-                        proposal.tokens_used
+                        proposal.tokens_used,
+                        proposal.tokens_in,
+                        proposal.tokens_out,
+                        telemetry::cost_detail(proposal.cost_usd)
                     ),
                 )
                 .await?;
@@ -391,18 +512,18 @@ pub async fn execute_domain_task(
                     specialist_model.provider.as_str(),
                     specialist_model.model_id.as_str(),
                     false,
-                    Some(model_started_at.elapsed().as_millis() as i64),
+                    Some(latency.as_millis() as i64),
                     None,
                     Some(error.clone()),
                 )
                 .await;
-                let specialist_task_id = specialist_task_record.id.clone();
+                let specialist_task_id = record_id.clone();
                 tasks::update_task_outcome(
                     pool,
                     UpdateTaskOutcomeInput {
                         task_id: specialist_task_id.clone(),
                         status: TaskStatus::Failed,
-                        token_usage: Some(0),
+                        token_usage: None,
                         context_efficiency_ratio: None,
                         compliance_score: None,
                         checksum_before: None,
@@ -466,7 +587,7 @@ pub async fn execute_domain_task(
         UpdateTaskOutcomeInput {
             task_id: task.id.clone(),
             status: final_task_status,
-            token_usage: Some(i64::from(tokens_spent)),
+            token_usage: None,
             context_efficiency_ratio: None,
             compliance_score: Some(compliance_score),
             checksum_before: None,
@@ -489,6 +610,18 @@ pub async fn execute_domain_task(
         ),
     )
     .await?;
+    if status == "consensus_failed" {
+        notifications::notify(
+            NotificationKind::ConsensusFailed,
+            "Consensus failed: review needed",
+            &format!(
+                "{} proposals for {}: {}",
+                proposals.len(),
+                task.domain,
+                task.objective
+            ),
+        );
+    }
 
     Ok(IntentSummary {
         task_id: task.id,
@@ -502,6 +635,70 @@ pub async fn execute_domain_task(
     })
 }
 
+/// Reruns a failed specialist with backoff while the tier-3 retry policy allows it.
+async fn retry_failed_specialist(
+    pool: &SqlitePool,
+    parent_task_id: &str,
+    prepared: &PreparedSpecialist,
+    job: SpecialistJob,
+    mut attempt: (Result<DiffProposal, String>, Duration),
+) -> Result<(Result<DiffProposal, String>, Duration), String> {
+    let actor = format!("tier3_{}", prepared.persona);
+    while let Err(error) = &attempt.0 {
+        let Some(backoff) =
+            task_runtime::schedule_retry(pool, &prepared.record_id, &actor, error).await?
+        else {
+            break;
+        };
+        model_intelligence::record_model_call_outcome(
+            pool,
+            prepared.model.provider.as_str(),
+            prepared.model.model_id.as_str(),
+            false,
+            Some(attempt.1.as_millis() as i64),
+            None,
+            Some(error.clone()),
+        )
+        .await;
+        tokio::time::sleep(backoff).await;
+        if let Err(stop) =
+            task_runtime::cooperative_checkpoint(pool, &prepared.record_id, &actor, "retry").await
+        {
+            return Ok((Err(stop), Duration::ZERO));
+        }
+        tasks::update_task_status(
+            pool,
+            UpdateTaskStatusInput {
+                task_id: prepared.record_id.clone(),
+                status: TaskStatus::Executing,
+                error_message: None,
+            },
+        )
+        .await?;
+
+        let permit = task_runtime::acquire_execution_slots(
+            pool,
+            ExecutionLane::Specialist,
+            parent_task_id,
+            None,
+            1,
+        )
+        .await?;
+        let job = job.clone();
+        let retry_pool = pool.clone();
+        attempt = tokio::task::spawn_blocking(move || {
+            let started_at = Instant::now();
+            let outcome =
+                specialist::run_specialist_task(&job.0, job.1.as_deref(), Some(&retry_pool));
+            (outcome, started_at.elapsed())
+        })
+        .await
+        .map_err(|error| format!("Specialist retry panicked: {error}"))?;
+        drop(permit);
+    }
+    Ok(attempt)
+}
+
 fn validate_input(input: &ExecuteDomainTaskInput) -> Result<(), String> {
     if input.task_id.trim().is_empty() {
         return Err("taskId is required".to_string());
@@ -513,7 +710,40 @@ fn validate_input(input: &ExecuteDomainTaskInput) -> Result<(), String> {
     Ok(())
 }
 
+/// Runs the project's `coordination_commands` (codegen, lint, ...) before specialists
+/// start. Their transcripts land in the task's terminal; a failing command does not
+/// stop the task.
+async fn run_coordination_commands(
+    pool: &SqlitePool,
+    bridge_client: &BridgeClient,
+    task_id: &str,
+    input: &ExecuteDomainTaskInput,
+) {
+    let Ok(policy) = compliance::load_project_policy(Path::new(input.target_project.trim())) else {
+        return;
+    };
+    for command in policy.coordination_commands {
+        let _ = command_runner::run_command(
+            pool,
+            bridge_client,
+            RunTargetCommandInput {
+                target_project: input.target_project.clone(),
+                command,
+                args: None,
+                timeout_secs: None,
+                task_id: Some(task_id.to_string()),
+                actor: Some("tier2_domain_leader".to_string()),
+                mcp_server: input.mcp_server.clone(),
+                mcp_command: input.mcp_command.clone(),
+                mcp_args: input.mcp_args.clone(),
+            },
+        )
+        .await;
+    }
+}
+
 async fn collect_candidate_files(
+    pool: &SqlitePool,
     bridge_client: &BridgeClient,
     target_project: &str,
     domain: &str,
@@ -543,11 +773,13 @@ async fn collect_candidate_files(
         .unwrap_or("src");
 
     if let Ok(search_result) = tool_caller::search_files(
+        pool,
         bridge_client,
         SearchTargetFilesInput {
             target_project: target_project.to_string(),
             pattern: fallback_pattern.to_string(),
             limit: Some(8),
+            mcp_server: input.mcp_server.clone(),
             mcp_command: input.mcp_command.clone(),
             mcp_args: input.mcp_args.clone(),
         },
@@ -828,58 +1060,30 @@ fn infer_target_path_from_objective(objective: &str) -> String {
     format!("{dir}/{filename}.{ext}")
 }
 
-fn hydrate_code_context(
-    chunks: &[ContextChunk],
-    target_file: &str,
-    limit: usize,
-) -> Vec<CodeBlock> {
-    let mut selected = chunks
-        .iter()
-        .filter(|chunk| chunk.file_path == target_file)
-        .take(limit)
-        .map(|chunk| CodeBlock {
-            file_path: chunk.file_path.clone(),
-            start_line: chunk.start_line,
-            end_line: chunk.end_line,
-            content: chunk.content.clone(),
-            embedding: None,
-        })
-        .collect::<Vec<_>>();
-
-    if selected.is_empty() {
-        selected = chunks
-            .iter()
-            .take(limit)
-            .map(|chunk| CodeBlock {
-                file_path: chunk.file_path.clone(),
-                start_line: chunk.start_line,
-                end_line: chunk.end_line,
-                content: chunk.content.clone(),
-                embedding: None,
-            })
-            .collect();
-    }
-
-    selected
-}
-
+/// Reads over the bridge, else from the local checkout unless the read path policy
+/// denied the file.
 async fn read_file_with_fallback(
+    pool: &SqlitePool,
     bridge_client: &BridgeClient,
     input: &ExecuteDomainTaskInput,
     target_file: &str,
 ) -> Option<String> {
-    if let Ok(result) = tool_caller::read_file(
+    match tool_caller::read_file(
+        pool,
         bridge_client,
         ReadTargetFileInput {
             target_project: input.target_project.clone(),
             file_path: target_file.to_string(),
+            mcp_server: input.mcp_server.clone(),
             mcp_command: input.mcp_command.clone(),
             mcp_args: input.mcp_args.clone(),
         },
     )
     .await
     {
-        return Some(result.content);
+        Ok(result) => return Some(result.content),
+        Err(error) if error.starts_with("SECURITY_VIOLATION") => return None,
+        Err(_) => {}
     }
 
     let root = PathBuf::from(input.target_project.trim());
@@ -1006,14 +1210,19 @@ fn build_summary(
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use sqlx::sqlite::SqlitePoolOptions;
     use tempfile::tempdir;
+    use tokio::sync::Mutex;
 
     use crate::db;
     use crate::db::mutations::{list_mutations_for_task, ListTaskMutationsInput};
     use crate::db::tasks::{self, CreateTaskInput};
     use crate::mcp_bridge::client::BridgeClient;
+    use crate::mcp_bridge::session::SessionContext;
     use crate::model_registry::ModelRegistry;
+    use crate::secret_vault::SecretVault;
     use crate::vector::indexer;
 
     use super::*;
@@ -1080,7 +1289,14 @@ mod tests {
 
         let runtime = tempdir().expect("runtime should initialize");
         fs::create_dir_all(runtime.path().join("mcp-bridge")).expect("bridge dir should exist");
-        let bridge_client = BridgeClient::new(runtime.path());
+        let bridge_client = BridgeClient::new(
+            runtime.path(),
+            SessionContext {
+                secret_vault: Arc::new(Mutex::new(SecretVault::new(runtime.path().to_path_buf()))),
+                db_pool: pool.clone(),
+                resources: Arc::default(),
+            },
+        );
 
         let model_registry = ModelRegistry::default();
         let result = execute_domain_task(
@@ -1091,6 +1307,7 @@ mod tests {
                 task_id: tier2.id.clone(),
                 target_project: project.path().to_string_lossy().to_string(),
                 top_k: Some(5),
+                mcp_server: None,
                 mcp_command: None,
                 mcp_args: None,
             },
@@ -1,9 +1,12 @@
+pub mod context_builder;
 pub mod domain_leader;
 pub mod orchestrator;
 pub mod specialist;
 
 use serde::{Deserialize, Serialize};
 
+use crate::vector::QueryFilters;
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -37,3 +40,16 @@ pub struct CodeBlock {
     pub content: String,
     pub embedding: Option<Vec<f32>>,
 }
+
+/// Retrieval scope for an assignment: test code stays out unless the objective is about
+/// tests, so specialists are not pointed at fixtures when changing product code.
+pub fn retrieval_filters(objective: &str) -> QueryFilters {
+    let about_tests = objective
+        .split(|ch: char| !ch.is_alphanumeric())
+        .map(str::to_ascii_lowercase)
+        .any(|word| word.starts_with("test") || word == "spec" || word == "specs");
+    QueryFilters {
+        exclude_tests: !about_tests,
+        ..QueryFilters::default()
+    }
+}
@@ -1,30 +1,64 @@
-use std::collections::VecDeque;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Instant;
 
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use sqlx::SqlitePool;
+use tokio::sync::Mutex;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use crate::agents;
+use crate::agents::context_builder::{self, ContextRequest};
 use crate::agents::domain_leader::{self, ExecuteDomainTaskInput};
 use crate::agents::specialist::{self, SpecialistTask};
-use crate::agents::CodeBlock;
+use crate::db::attachments;
+use crate::db::model_arms::{self, ArmSegment};
 use crate::db::mutations::{self, CreateMutationInput, ListTaskMutationsInput, MutationStatus};
 use crate::db::tasks::{
     self, CreateTaskRecordInput, TaskRecord, TaskStatus, UpdateTaskOutcomeInput,
     UpdateTaskStatusInput,
 };
-use crate::llm_adapter::{self, AdapterRequest};
+use crate::db::telemetry;
+use crate::llm_adapter::{self, AdapterRequest, ResponseSchema};
+use crate::llm_stream;
 use crate::mcp_bridge::client::BridgeClient;
+use crate::mcp_bridge::tool_cache;
 use crate::mcp_bridge::tool_caller::{self, ReadTargetFileInput};
-use crate::model_intelligence::{self, ModelSelectionRequest};
-use crate::model_registry::ModelRegistry;
+use crate::mcp_servers;
+use crate::model_intelligence::{
+    self, CostPreference, ModelRequirements, ModelSelectionRequest, ModelSelectionResult,
+};
+use crate::model_registry::{ModelRegistry, ModelSelection, CAPABILITY_JSON_MODE};
 use crate::mutation_pipeline::{self, RunMutationPipelineInput};
-use crate::task_runtime;
+use crate::notifications::{self, NotificationKind};
+use crate::project_ignore;
+use crate::prompt_templates;
+use crate::protected_paths;
+use crate::run_memory::{self, RunMemory};
+use crate::secret_vault::SecretVault;
+use crate::task_runtime::{self, ExecutionLane};
+use crate::vector::git_history;
 use crate::vector::search;
-use crate::vector::ContextChunk;
+use crate::vector::{ContextChunk, GitHistoryEntry};
+use crate::webhooks::{self, WebhookEvent};
+
+/// History entries shown to the planner for the objective as a whole.
+const PLAN_HISTORY_ENTRIES: u32 = 12;
+/// Changes per assignment consulted for risk and constraints.
+const ASSIGNMENT_HISTORY_ENTRIES: usize = 10;
+/// Window in which changes to an assignment's files count as recent churn.
+const RECENT_CHURN_WINDOW_SECS: i64 = 30 * 24 * 60 * 60;
+/// Past runs recalled for a new objective.
+const SIMILAR_RUNS: usize = 3;
+/// Routing skill for planned tier-3 work; applied mutations are credited to it.
+const TIER3_SPECIALIST_SKILL: &str = "tier3_specialist_spawn";
+/// File trees at least this large need a long-context planner.
+const LARGE_FILE_TREE_FILES: usize = 400;
+const LARGE_CONTEXT_TOKENS: u32 = 100_000;
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -47,6 +81,7 @@ pub struct TaskAssignment {
     pub risk_factor: f32,
     pub constraints: Vec<String>,
     pub relevant_files: Vec<String>,
+    pub priority: i64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -65,6 +100,7 @@ struct AssignmentDraft {
     domain: String,
     objective: String,
     target_files: Vec<String>,
+    priority: i64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -73,8 +109,25 @@ pub struct ApproveOrchestrationPlanInput {
     pub root_task_id: String,
     pub target_project: String,
     pub top_k: Option<u32>,
+    /// Registered MCP server for assignments without a `domain_mcp_servers` entry.
+    pub mcp_server: Option<String>,
+    /// Registered MCP server per assignment domain.
+    pub domain_mcp_servers: Option<HashMap<String, String>>,
     pub mcp_command: Option<String>,
     pub mcp_args: Option<Vec<String>>,
+    /// Replaces the plan's protected-path allow-list when set.
+    pub allowed_protected_paths: Option<Vec<String>>,
+}
+
+impl ApproveOrchestrationPlanInput {
+    fn mcp_server_for(&self, domain: &str) -> Option<String> {
+        self.domain_mcp_servers
+            .as_ref()
+            .and_then(|servers| servers.get(domain))
+            .or(self.mcp_server.as_ref())
+            .map(|server| server.trim().to_string())
+            .filter(|server| !server.is_empty())
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -110,6 +163,9 @@ pub struct AnalyzeObjectiveInput {
     pub objective: String,
     pub target_project: String,
     pub global_token_budget: u32,
+    /// Ask the model again even if an identical analysis is cached.
+    #[serde(default)]
+    pub bypass_cache: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -120,6 +176,8 @@ pub struct ObjectiveAnalysis {
     pub initial_analysis: String,
     pub suggested_approach: String,
     pub file_tree_summary: String,
+    /// Earlier runs with a similar objective, most similar first.
+    pub similar_runs: Vec<RunMemory>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -131,6 +189,12 @@ pub struct GeneratePlanInput {
     pub target_project: String,
     pub global_token_budget: u32,
     pub max_risk_tolerance: f32,
+    /// Globs of protected paths (lockfiles, `.env`, CI workflows, migrations) this plan may
+    /// change.
+    pub allowed_protected_paths: Option<Vec<String>>,
+    /// Ask the model again even if an identical plan is cached.
+    #[serde(default)]
+    pub bypass_cache: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -177,22 +241,46 @@ struct LlmPlannedTask {
     target_files: Vec<String>,
     #[serde(default)]
     rationale: Option<String>,
+    /// Planner-assigned dispatch priority; clamped when the task is stored.
+    #[serde(default)]
+    priority: i64,
 }
 
 fn default_tier() -> u8 {
     3
 }
 
+/// Planner output is parsed as JSON; large projects also need room for the file tree.
+fn planner_requirements(file_count: usize) -> ModelRequirements {
+    let requirements = ModelRequirements::default().with(CAPABILITY_JSON_MODE);
+    if file_count >= LARGE_FILE_TREE_FILES {
+        requirements.min_context(LARGE_CONTEXT_TOKENS)
+    } else {
+        requirements
+    }
+}
+
 // --- Orchestration functions ---
 
 pub async fn orchestrate_and_persist(
     pool: &SqlitePool,
+    secret_vault: &Arc<Mutex<SecretVault>>,
     model_registry: &ModelRegistry,
     input: UserObjectiveInput,
 ) -> Result<OrchestrationResult, String> {
     validate_objective_input(&input)?;
 
-    let tier1_model = model_intelligence::select_model(
+    let objective = input.objective.trim().to_string();
+    let domain = infer_primary_domain(&objective);
+    let target_root = normalize_project_root(&input.target_project)?;
+    let all_candidate_files = collect_source_files(&target_root, 600)?;
+    let file_tree_summary = build_file_tree_summary(&all_candidate_files, 120);
+
+    let ModelSelectionResult {
+        selection: tier1_model,
+        fallbacks: tier1_fallbacks,
+        ..
+    } = model_intelligence::select_model(
         pool,
         model_registry,
         ModelSelectionRequest {
@@ -201,24 +289,38 @@ pub async fn orchestrate_and_persist(
             tier: 1,
             persona: None,
             skill: Some("orchestration_planning"),
+            remaining_budget: None,
+            cost_preference: CostPreference::Best,
+            requirements: planner_requirements(all_candidate_files.len()),
         },
     )
-    .await?
-    .selection;
-    let objective = input.objective.trim().to_string();
-    let domain = infer_primary_domain(&objective);
-    let target_root = normalize_project_root(&input.target_project)?;
-    let all_candidate_files = collect_source_files(&target_root, 600)?;
-    let file_tree_summary = build_file_tree_summary(&all_candidate_files, 120);
-    let drafts = generate_drafts_with_llm(
-        &tier1_model.provider,
-        &tier1_model.model_id,
-        &objective,
-        &domain,
-        &file_tree_summary,
-        input.global_token_budget,
-        input.max_risk_tolerance,
-    );
+    .await?;
+    // The adapter waits on its database queries, so keep it off the runtime workers.
+    let drafts = {
+        let (pool, model, objective, domain) = (
+            pool.clone(),
+            tier1_model.clone(),
+            objective.clone(),
+            domain.clone(),
+        );
+        let target_project = input.target_project.trim().to_string();
+        let (token_budget, risk_tolerance) = (input.global_token_budget, input.max_risk_tolerance);
+        tokio::task::spawn_blocking(move || {
+            generate_drafts_with_llm(
+                &pool,
+                &model,
+                tier1_fallbacks,
+                &objective,
+                &domain,
+                &file_tree_summary,
+                token_budget,
+                risk_tolerance,
+                &target_project,
+            )
+        })
+        .await
+        .map_err(|error| format!("LLM task panicked: {error}"))?
+    };
 
     let overhead_budget = ((input.global_token_budget as f32) * 0.10).round() as u32;
     let reserve_budget = ((input.global_token_budget as f32) * 0.10).round() as u32;
@@ -240,6 +342,7 @@ pub async fn orchestrate_and_persist(
             risk_factor: 0.0,
             status: TaskStatus::Pending,
             target_files: None,
+            priority: 0,
         },
     )
     .await?;
@@ -347,6 +450,7 @@ pub async fn orchestrate_and_persist(
                 risk_factor: f64::from(*risk_factor),
                 status: TaskStatus::Paused,
                 target_files: target_files_json,
+                priority: draft.priority,
             },
         )
         .await?;
@@ -379,6 +483,7 @@ pub async fn orchestrate_and_persist(
             risk_factor: *risk_factor,
             constraints: constraints.clone(),
             relevant_files: relevant_files.clone(),
+            priority: draft.priority,
         });
     }
 
@@ -405,6 +510,19 @@ pub async fn orchestrate_and_persist(
         ),
     )
     .await?;
+    notifications::notify(
+        NotificationKind::PlanReady,
+        "Plan ready for review",
+        &format!("{} assignments: {}", assignments.len(), root_task.objective),
+    );
+    webhooks::emit(
+        pool,
+        secret_vault,
+        WebhookEvent::PlanReady,
+        &root_task.id,
+        &root_task.objective,
+        json!({ "assignments": assignments.len() }),
+    );
 
     Ok(OrchestrationResult {
         root_task,
@@ -417,6 +535,7 @@ pub async fn orchestrate_and_persist(
 
 pub async fn approve_plan_and_spawn(
     pool: &SqlitePool,
+    secret_vault: &Arc<Mutex<SecretVault>>,
     bridge_client: &BridgeClient,
     model_registry: &ModelRegistry,
     input: ApproveOrchestrationPlanInput,
@@ -431,6 +550,10 @@ pub async fn approve_plan_and_spawn(
             root_task.id, root_task.tier
         ));
     }
+    if let Some(allowed) = input.allowed_protected_paths.as_deref() {
+        let allowed = protected_paths::validate_allowlist(allowed)?;
+        tasks::set_allowed_protected_paths(pool, &root_task.id, &allowed).await?;
+    }
 
     let task_tree_ids = tasks::collect_task_tree_ids(pool, root_task_id).await?;
     let mut planned_tasks = Vec::new();
@@ -452,6 +575,15 @@ pub async fn approve_plan_and_spawn(
         ));
     }
 
+    task_runtime::wait_for_execution_window(pool, &root_task.id, planned_tasks.len()).await?;
+    let _execution_permit = task_runtime::acquire_execution_slots(
+        pool,
+        ExecutionLane::RootOrchestration,
+        &root_task.id,
+        Some(input.target_project.trim()),
+        1,
+    )
+    .await?;
     tasks::update_task_status(
         pool,
         UpdateTaskStatusInput {
@@ -484,6 +616,8 @@ pub async fn approve_plan_and_spawn(
             .then_with(|| right.tier.cmp(&left.tier))
             .then_with(|| left.created_at.cmp(&right.created_at))
     });
+    // Stable, so the risk order above only breaks ties within a priority.
+    tasks::sort_by_priority(&mut planned_tasks);
 
     let mut executed_task_ids = Vec::new();
     let mut tier2_executions = 0_u32;
@@ -493,6 +627,22 @@ pub async fn approve_plan_and_spawn(
     let mut notes: Vec<String> = Vec::new();
 
     for planned_task in planned_tasks {
+        if let Err(error) = task_runtime::cooperative_checkpoint(
+            pool,
+            &root_task.id,
+            "tier1_orchestrator",
+            &format!("dispatch_{}", planned_task.id),
+        )
+        .await
+        {
+            failed_executions = failed_executions.saturating_add(1);
+            notes.push(format!(
+                "dispatch stopped before task {}: {error}",
+                planned_task.id
+            ));
+            break;
+        }
+
         let execution = if planned_task.tier == 2 {
             tier2_executions = tier2_executions.saturating_add(1);
             domain_leader::execute_domain_task(
@@ -503,6 +653,7 @@ pub async fn approve_plan_and_spawn(
                     task_id: planned_task.id.clone(),
                     target_project: input.target_project.trim().to_string(),
                     top_k: input.top_k,
+                    mcp_server: input.mcp_server_for(&planned_task.domain),
                     mcp_command: input.mcp_command.clone(),
                     mcp_args: input.mcp_args.clone(),
                 },
@@ -547,8 +698,14 @@ pub async fn approve_plan_and_spawn(
         let mut task_first_error: Option<String> = None;
 
         for apply_id in &apply_task_ids {
-            let apply_summary =
-                apply_mutations_for_task(pool, apply_id, input.target_project.trim()).await?;
+            let apply_summary = apply_mutations_for_task(
+                pool,
+                secret_vault,
+                model_registry,
+                apply_id,
+                input.target_project.trim(),
+            )
+            .await?;
             task_applied = task_applied.saturating_add(apply_summary.applied_mutations);
             task_failed_runs = task_failed_runs.saturating_add(apply_summary.failed_runs);
             if task_first_error.is_none() {
@@ -612,6 +769,26 @@ pub async fn approve_plan_and_spawn(
     )
     .await?;
 
+    // Paused runs may still be resumed; remember only runs that reached an end.
+    if matches!(updated_root.status.as_str(), "completed" | "failed") {
+        if let Ok(memory) =
+            run_memory::record_run(pool, &root_task.id, input.target_project.trim(), &message).await
+        {
+            let _ = task_runtime::record_task_activity(
+                pool,
+                "tier1_orchestrator",
+                "run_memory_recorded",
+                &root_task.id,
+                &format!(
+                    "status={} keyDiffs={}",
+                    memory.status,
+                    memory.key_diffs.len()
+                ),
+            )
+            .await;
+        }
+    }
+
     task_runtime::record_task_activity(
         pool,
         "tier1_orchestrator",
@@ -627,6 +804,29 @@ pub async fn approve_plan_and_spawn(
         ),
     )
     .await?;
+    notifications::notify(
+        NotificationKind::RunFinished,
+        &format!("Run {}", updated_root.status),
+        &format!("{}: {}", updated_root.objective, message),
+    );
+    webhooks::emit(
+        pool,
+        secret_vault,
+        if updated_root.status == TaskStatus::Completed.as_str() {
+            WebhookEvent::RunCompleted
+        } else {
+            WebhookEvent::RunFailed
+        },
+        &updated_root.id,
+        &updated_root.objective,
+        json!({
+            "status": updated_root.status,
+            "message": message,
+            "executedTasks": executed_task_ids.len(),
+            "appliedMutations": applied_mutations,
+            "failedExecutions": failed_executions,
+        }),
+    );
 
     // Collect mutation summaries for all executed tasks + their descendants
     let mut all_mutation_task_ids = Vec::new();
@@ -684,6 +884,8 @@ struct MutationApplySummary {
 
 async fn apply_mutations_for_task(
     pool: &SqlitePool,
+    secret_vault: &Arc<Mutex<SecretVault>>,
+    model_registry: &ModelRegistry,
     task_id: &str,
     target_project: &str,
 ) -> Result<MutationApplySummary, String> {
@@ -709,12 +911,20 @@ async fn apply_mutations_for_task(
 
         match mutation_pipeline::run_mutation_pipeline(
             pool,
+            secret_vault,
+            model_registry,
             RunMutationPipelineInput {
                 mutation_id: mutation.id.clone(),
                 target_project: target_project.to_string(),
                 tier1_approved: true,
                 ci_command: None,
                 ci_args: None,
+                max_repair_attempts: None,
+                use_task_branch: None,
+                approved_by: None,
+                limits: None,
+                accept_partial_alignment: None,
+                mcp_server: None,
             },
         )
         .await
@@ -764,6 +974,10 @@ async fn execute_planned_tier3_task(
     task: &TaskRecord,
     input: &ApproveOrchestrationPlanInput,
 ) -> Result<(), String> {
+    let mcp_server = input.mcp_server_for(&task.domain);
+    if let Some(server) = mcp_server.as_deref() {
+        mcp_servers::resolve_for_domain(server, &task.domain)?;
+    }
     tasks::update_task_status(
         pool,
         UpdateTaskStatusInput {
@@ -775,7 +989,12 @@ async fn execute_planned_tier3_task(
     .await?;
 
     let persona = infer_tier3_persona(&task.domain, &task.objective);
-    let tier3_model = model_intelligence::select_model(
+    let design_images = attachments::design_images_for(pool, task).await?;
+    let ModelSelectionResult {
+        selection: tier3_model,
+        fallbacks: tier3_fallbacks,
+        ..
+    } = model_intelligence::select_model(
         pool,
         model_registry,
         ModelSelectionRequest {
@@ -783,16 +1002,20 @@ async fn execute_planned_tier3_task(
             actor: "tier1_orchestrator",
             tier: 3,
             persona: Some(persona.as_str()),
-            skill: Some("tier3_specialist_spawn"),
+            skill: Some(TIER3_SPECIALIST_SKILL),
+            remaining_budget: Some(task.token_budget - task.token_usage),
+            cost_preference: CostPreference::for_task(3, &task.domain, task.risk_factor),
+            requirements: ModelRequirements::for_specialist(!design_images.is_empty()),
         },
     )
-    .await?
-    .selection;
+    .await?;
 
+    let allowed_protected = protected_paths::plan_allowlist(pool, &task.id).await?;
     let stored_target_files: Vec<String> = task
         .target_files
         .as_deref()
-        .and_then(|json| serde_json::from_str(json).ok())
+        .and_then(|json| serde_json::from_str::<Vec<String>>(json).ok())
+        .map(|files| protected_paths::partition_allowed(&files, &allowed_protected).0)
         .unwrap_or_default();
 
     let (target_file, chunks) = if !stored_target_files.is_empty() {
@@ -803,6 +1026,7 @@ async fn execute_planned_tier3_task(
             input.target_project.trim(),
             task.objective.trim(),
             input.top_k.unwrap_or(8).max(3),
+            &agents::retrieval_filters(&task.objective),
         )
         .await
         .unwrap_or_default();
@@ -810,8 +1034,22 @@ async fn execute_planned_tier3_task(
             .unwrap_or_else(|| "src/App.tsx".to_string());
         (file, chunks)
     };
-    let code_context = hydrate_tier3_code_context(&chunks, &target_file, 2);
-    let file_content = read_tier3_file_with_fallback(bridge_client, input, &target_file).await;
+    protected_paths::check_path(&target_file, &allowed_protected)?;
+    let target_files = vec![target_file.clone()];
+    let context_pack = context_builder::build_context_pack(
+        pool,
+        ContextRequest {
+            target_project: input.target_project.trim(),
+            objective: task.objective.trim(),
+            target_files: &target_files,
+            retrieved: &chunks,
+            token_budget: context_builder::context_budget(task.token_budget.max(1) as u32),
+        },
+    )
+    .await;
+    let file_content =
+        read_tier3_file_with_fallback(pool, bridge_client, input, mcp_server.clone(), &target_file)
+            .await;
 
     task_runtime::record_task_activity(
         pool,
@@ -819,8 +1057,12 @@ async fn execute_planned_tier3_task(
         "tier3_planned_execution_started",
         &task.id,
         &format!(
-            "persona={} model={}/{} targetFile={}",
-            persona, tier3_model.provider, tier3_model.model_id, target_file
+            "persona={} model={}/{} targetFile={} contextTokens={}",
+            persona,
+            tier3_model.provider,
+            tier3_model.model_id,
+            target_file,
+            context_pack.estimated_tokens
         ),
     )
     .await?;
@@ -832,8 +1074,9 @@ async fn execute_planned_tier3_task(
         persona: persona.clone(),
         objective: task.objective.clone(),
         token_budget: task.token_budget.max(1) as u32,
-        target_files: vec![target_file.clone()],
-        code_context,
+        target_files,
+        code_context: context_pack.code_blocks(),
+        file_tree: context_pack.file_tree,
         constraints: vec![
             "plan approved by tier1 orchestrator".to_string(),
             "keep diff focused to task objective".to_string(),
@@ -841,40 +1084,80 @@ async fn execute_planned_tier3_task(
         ],
         model_provider: Some(tier3_model.provider.clone()),
         model_id: Some(tier3_model.model_id.clone()),
+        model_endpoint: tier3_model.endpoint.clone(),
+        model_generation: tier3_model.generation.clone(),
+        model_fallbacks: tier3_fallbacks,
+        target_project: Some(input.target_project.trim().to_string()),
+        attachments: design_images,
+        mcp_tools: tool_cache::tools_for_prompt(bridge_client, mcp_server.as_deref()).await,
     };
 
-    let model_started_at = Instant::now();
-    let proposal = specialist::run_specialist_task(&specialist_task, file_content.as_deref());
-    let model_elapsed = model_started_at.elapsed().as_millis() as i64;
-    let proposal = match proposal {
-        Ok(value) => {
-            model_intelligence::record_model_call_outcome(
-                pool,
-                tier3_model.provider.as_str(),
-                tier3_model.model_id.as_str(),
-                true,
-                Some(model_elapsed),
-                None,
-                None,
-            )
-            .await;
-            value
-        }
-        Err(error) => {
-            model_intelligence::record_model_call_outcome(
-                pool,
-                tier3_model.provider.as_str(),
-                tier3_model.model_id.as_str(),
-                false,
-                Some(model_elapsed),
-                None,
-                Some(error.clone()),
-            )
-            .await;
-            return Err(error);
+    let proposal = loop {
+        let specialist_permit = task_runtime::acquire_execution_slots(
+            pool,
+            ExecutionLane::Specialist,
+            &task.id,
+            None,
+            1,
+        )
+        .await?;
+        let model_started_at = Instant::now();
+        let proposal =
+            specialist::run_specialist_task(&specialist_task, file_content.as_deref(), Some(pool));
+        drop(specialist_permit);
+        let model_elapsed = model_started_at.elapsed().as_millis() as i64;
+        match proposal {
+            Ok(value) => {
+                model_intelligence::record_model_call_outcome(
+                    pool,
+                    tier3_model.provider.as_str(),
+                    tier3_model.model_id.as_str(),
+                    true,
+                    Some(model_elapsed),
+                    value.cost_usd,
+                    None,
+                )
+                .await;
+                break value;
+            }
+            Err(error) => {
+                model_intelligence::record_model_call_outcome(
+                    pool,
+                    tier3_model.provider.as_str(),
+                    tier3_model.model_id.as_str(),
+                    false,
+                    Some(model_elapsed),
+                    None,
+                    Some(error.clone()),
+                )
+                .await;
+                let Some(backoff) =
+                    task_runtime::schedule_retry(pool, &task.id, "tier1_orchestrator", &error)
+                        .await?
+                else {
+                    return Err(error);
+                };
+                tokio::time::sleep(backoff).await;
+                task_runtime::cooperative_checkpoint(
+                    pool,
+                    &task.id,
+                    "tier1_orchestrator",
+                    "tier3_retry",
+                )
+                .await?;
+                tasks::update_task_status(
+                    pool,
+                    UpdateTaskStatusInput {
+                        task_id: task.id.clone(),
+                        status: TaskStatus::Executing,
+                        error_message: None,
+                    },
+                )
+                .await?;
+            }
         }
     };
-    let _mutation = mutations::create_mutation(
+    let mutation = mutations::create_mutation(
         pool,
         CreateMutationInput {
             task_id: task.id.clone(),
@@ -884,16 +1167,27 @@ async fn execute_planned_tier3_task(
             intent_description: Some(proposal.intent_description.clone()),
             intent_hash: Some(proposal.intent_hash.clone()),
             confidence: proposal.confidence as f64,
+            parent_mutation_id: None,
+            modified_content: proposal.modified_content.clone(),
+            base_checksum: proposal.base_checksum.clone(),
         },
     )
     .await?;
+    model_arms::record_mutation_arm(
+        pool,
+        &mutation.id,
+        &ArmSegment::new(3, Some(&persona), Some(TIER3_SPECIALIST_SKILL)),
+        &tier3_model.provider,
+        &tier3_model.model_id,
+    )
+    .await?;
 
     tasks::update_task_outcome(
         pool,
         UpdateTaskOutcomeInput {
             task_id: task.id.clone(),
             status: TaskStatus::Paused,
-            token_usage: Some(i64::from(proposal.tokens_used)),
+            token_usage: None,
             context_efficiency_ratio: None,
             compliance_score: Some(70),
             checksum_before: None,
@@ -912,8 +1206,13 @@ async fn execute_planned_tier3_task(
         "tier3_planned_execution_completed",
         &task.id,
         &format!(
-            "targetFile={} confidence={:.2} tokensUsed={}",
-            proposal.file_path, proposal.confidence, proposal.tokens_used
+            "targetFile={} confidence={:.2} tokensUsed={} tokensIn={} tokensOut={}{}",
+            proposal.file_path,
+            proposal.confidence,
+            proposal.tokens_used,
+            proposal.tokens_in,
+            proposal.tokens_out,
+            telemetry::cost_detail(proposal.cost_usd)
         ),
     )
     .await?;
@@ -1005,58 +1304,31 @@ fn frontend_path_score(path: &str) -> i64 {
     score
 }
 
-fn hydrate_tier3_code_context(
-    chunks: &[ContextChunk],
-    target_file: &str,
-    limit: usize,
-) -> Vec<CodeBlock> {
-    let mut selected = chunks
-        .iter()
-        .filter(|chunk| chunk.file_path == target_file)
-        .take(limit)
-        .map(|chunk| CodeBlock {
-            file_path: chunk.file_path.clone(),
-            start_line: chunk.start_line,
-            end_line: chunk.end_line,
-            content: chunk.content.clone(),
-            embedding: None,
-        })
-        .collect::<Vec<_>>();
-
-    if selected.is_empty() {
-        selected = chunks
-            .iter()
-            .take(limit)
-            .map(|chunk| CodeBlock {
-                file_path: chunk.file_path.clone(),
-                start_line: chunk.start_line,
-                end_line: chunk.end_line,
-                content: chunk.content.clone(),
-                embedding: None,
-            })
-            .collect::<Vec<_>>();
-    }
-
-    selected
-}
-
+/// Reads over the bridge, else from the local checkout unless the read path policy
+/// denied the file.
 async fn read_tier3_file_with_fallback(
+    pool: &SqlitePool,
     bridge_client: &BridgeClient,
     input: &ApproveOrchestrationPlanInput,
+    mcp_server: Option<String>,
     target_file: &str,
 ) -> Option<String> {
-    if let Ok(result) = tool_caller::read_file(
+    match tool_caller::read_file(
+        pool,
         bridge_client,
         ReadTargetFileInput {
             target_project: input.target_project.clone(),
             file_path: target_file.to_string(),
+            mcp_server,
             mcp_command: input.mcp_command.clone(),
             mcp_args: input.mcp_args.clone(),
         },
     )
     .await
     {
-        return Some(result.content);
+        Ok(result) => return Some(result.content),
+        Err(error) if error.starts_with("SECURITY_VIOLATION") => return None,
+        Err(_) => {}
     }
 
     let root = PathBuf::from(input.target_project.trim());
@@ -1117,8 +1389,20 @@ pub async fn analyze_objective(
     let target_root = normalize_project_root(&input.target_project)?;
     let source_files = collect_source_files(&target_root, 600)?;
     let file_tree_summary = build_file_tree_summary(&source_files, 120);
+    let similar_runs = run_memory::similar_runs(
+        pool,
+        &target_root.to_string_lossy(),
+        &objective,
+        SIMILAR_RUNS,
+    )
+    .await
+    .unwrap_or_default();
 
-    let tier1_model = model_intelligence::select_model(
+    let ModelSelectionResult {
+        selection: tier1_model,
+        fallbacks: tier1_fallbacks,
+        ..
+    } = model_intelligence::select_model(
         pool,
         model_registry,
         ModelSelectionRequest {
@@ -1127,10 +1411,12 @@ pub async fn analyze_objective(
             tier: 1,
             persona: None,
             skill: Some("objective_analysis"),
+            remaining_budget: None,
+            cost_preference: CostPreference::Best,
+            requirements: planner_requirements(source_files.len()),
         },
     )
-    .await?
-    .selection;
+    .await?;
 
     let root_task = tasks::create_task_record(
         pool,
@@ -1143,6 +1429,7 @@ pub async fn analyze_objective(
             risk_factor: 0.0,
             status: TaskStatus::Executing,
             target_files: None,
+            priority: 0,
         },
     )
     .await?;
@@ -1153,37 +1440,28 @@ pub async fn analyze_objective(
         "objective_analysis_started",
         &root_task.id,
         &format!(
-            "objective={} model={}/{} files={}",
-            objective, tier1_model.provider, tier1_model.model_id, source_files.len()
+            "objective={} model={}/{} files={} similarRuns={}",
+            objective,
+            tier1_model.provider,
+            tier1_model.model_id,
+            source_files.len(),
+            similar_runs.len()
         ),
     )
     .await?;
 
-    let system_prompt = r#"You are a Tier-1 orchestrator for the Autonomous Orchestration Platform (AOP).
-Your job is to analyze a user's objective and generate clarifying questions that will help create a precise implementation plan.
-
-Respond with JSON only:
-{
-  "questions": ["question 1", "question 2"],
-  "initialAnalysis": "Your understanding of what needs to be done",
-  "suggestedApproach": "High-level approach you would recommend"
-}
-
-CRITICAL Rules:
-- For TRIVIAL objectives (edit one file, add a line, fix a typo, update README, simple config change): return 0 questions. Do NOT ask clarifying questions for obvious single-step tasks.
-- For SIMPLE objectives (1-2 file changes with clear intent): return 0-1 questions max.
-- For MODERATE to COMPLEX objectives: generate 2-5 focused questions about ambiguous requirements, constraints, or preferences.
-- Questions should be answerable in 1-2 sentences.
-- Focus on: scope boundaries, technology preferences, testing expectations, risk tolerance.
-- Your initialAnalysis should be concise and demonstrate understanding of what needs to be done.
-- Your suggestedApproach should match the complexity: trivial tasks need a one-sentence approach."#
-        .to_string();
+    let system_prompt = prompt_templates::render(
+        prompt_templates::ANALYSIS,
+        Some(input.target_project.trim()),
+        json!({ "objective": objective }),
+    );
 
     let user_prompt = format!(
-        "OBJECTIVE:\n{}\n\nPROJECT FILE TREE ({} files):\n{}\n\nGenerate clarifying questions and initial analysis.",
+        "OBJECTIVE:\n{}\n\nPROJECT FILE TREE ({} files):\n{}\n\nSIMILAR PAST RUNS:\n{}\n\nGenerate clarifying questions and initial analysis.",
         objective,
         source_files.len(),
-        file_tree_summary
+        file_tree_summary,
+        run_memory::render_similar_runs(&similar_runs).unwrap_or_else(|| "none".to_string())
     );
 
     let request = AdapterRequest {
@@ -1191,9 +1469,20 @@ CRITICAL Rules:
         model_id: tier1_model.model_id.clone(),
         system_prompt,
         user_prompt,
+        endpoint: tier1_model.endpoint.clone(),
+        generation: tier1_model.generation.clone(),
+        task_id: Some(root_task.id.clone()),
+        fallbacks: tier1_fallbacks,
+        response_schema: Some(analysis_output_schema()),
+        bypass_cache: input.bypass_cache,
+        attachments: Vec::new(),
+        pool: Some(pool.clone()),
     };
 
-    let llm_result = tokio::task::spawn_blocking(move || llm_adapter::generate(&request))
+    let stream_task_id = root_task.id.clone();
+    let llm_result = tokio::task::spawn_blocking(move || {
+        llm_stream::generate(&request, Some(&stream_task_id), "analysis")
+    })
         .await
         .map_err(|error| format!("LLM task panicked: {error}"))?;
 
@@ -1231,10 +1520,13 @@ CRITICAL Rules:
         "objective_analysis_completed",
         &root_task.id,
         &format!(
-            "questions={} model={}/{}",
+            "questions={} model={}/{} tokensIn={} tokensOut={}{}",
             analysis.questions.len(),
             tier1_model.provider,
-            tier1_model.model_id
+            tier1_model.model_id,
+            response.input_tokens.unwrap_or_default(),
+            response.output_tokens.unwrap_or_default(),
+            telemetry::cost_detail(response.total_cost_usd)
         ),
     )
     .await?;
@@ -1245,11 +1537,13 @@ CRITICAL Rules:
         initial_analysis: analysis.initial_analysis.unwrap_or_default(),
         suggested_approach: analysis.suggested_approach.unwrap_or_default(),
         file_tree_summary,
+        similar_runs,
     })
 }
 
 pub async fn generate_plan(
     pool: &SqlitePool,
+    secret_vault: &Arc<Mutex<SecretVault>>,
     model_registry: &ModelRegistry,
     input: GeneratePlanInput,
 ) -> Result<GeneratedPlan, String> {
@@ -1266,12 +1560,31 @@ pub async fn generate_plan(
         return Err("globalTokenBudget must be at least 100".to_string());
     }
 
+    let allowed_protected = protected_paths::validate_allowlist(
+        input.allowed_protected_paths.as_deref().unwrap_or_default(),
+    )?;
+
     let objective = input.objective.trim().to_string();
     let target_root = normalize_project_root(&input.target_project)?;
     let source_files = collect_source_files(&target_root, 600)?;
     let file_tree_summary = build_file_tree_summary(&source_files, 120);
+    let target_root_str = target_root.to_string_lossy().to_string();
+    // Projects outside git are planned without history.
+    let recent_history = git_history::query_git_history(
+        pool,
+        &target_root_str,
+        &objective,
+        &[],
+        PLAN_HISTORY_ENTRIES,
+    )
+    .await
+    .unwrap_or_default();
 
-    let tier1_model = model_intelligence::select_model(
+    let ModelSelectionResult {
+        selection: tier1_model,
+        fallbacks: tier1_fallbacks,
+        ..
+    } = model_intelligence::select_model(
         pool,
         model_registry,
         ModelSelectionRequest {
@@ -1280,10 +1593,12 @@ pub async fn generate_plan(
             tier: 1,
             persona: None,
             skill: Some("plan_generation"),
+            remaining_budget: None,
+            cost_preference: CostPreference::Best,
+            requirements: planner_requirements(source_files.len()),
         },
     )
-    .await?
-    .selection;
+    .await?;
 
     tasks::update_task_status(
         pool,
@@ -1321,13 +1636,19 @@ pub async fn generate_plan(
             .join("\n\n")
     };
 
-    let system_prompt = build_plan_generation_prompt();
+    let system_prompt = build_plan_generation_prompt(
+        input.target_project.trim(),
+        &objective,
+        input.global_token_budget,
+        input.max_risk_tolerance,
+    );
     let user_prompt = format!(
-        "OBJECTIVE:\n{}\n\nUSER ANSWERS:\n{}\n\nPROJECT FILE TREE ({} files):\n{}\n\nTOKEN BUDGET: {}\nRISK TOLERANCE: {:.2}\n\nGenerate the implementation plan.",
+        "OBJECTIVE:\n{}\n\nUSER ANSWERS:\n{}\n\nPROJECT FILE TREE ({} files):\n{}\n\nRECENT CHANGES:\n{}\n\nTOKEN BUDGET: {}\nRISK TOLERANCE: {:.2}\n\nGenerate the implementation plan.",
         objective,
         answers_formatted,
         source_files.len(),
         file_tree_summary,
+        build_recent_changes_summary(&recent_history),
         input.global_token_budget,
         input.max_risk_tolerance
     );
@@ -1337,9 +1658,20 @@ pub async fn generate_plan(
         model_id: tier1_model.model_id.clone(),
         system_prompt,
         user_prompt,
+        endpoint: tier1_model.endpoint.clone(),
+        generation: tier1_model.generation.clone(),
+        task_id: Some(input.root_task_id.clone()),
+        fallbacks: tier1_fallbacks,
+        response_schema: Some(plan_output_schema()),
+        bypass_cache: input.bypass_cache,
+        attachments: Vec::new(),
+        pool: Some(pool.clone()),
     };
 
-    let llm_result = tokio::task::spawn_blocking(move || llm_adapter::generate(&request))
+    let stream_task_id = input.root_task_id.clone();
+    let llm_result = tokio::task::spawn_blocking(move || {
+        llm_stream::generate(&request, Some(&stream_task_id), "plan")
+    })
         .await
         .map_err(|error| format!("LLM task panicked: {error}"))?;
 
@@ -1377,24 +1709,37 @@ pub async fn generate_plan(
         .collect();
     let budgets = allocate_token_budgets(distributed_budget.max(1), &weights);
 
-    let root_task = tasks::get_task_by_id(pool, &input.root_task_id).await?;
+    let root_task =
+        tasks::set_allowed_protected_paths(pool, &input.root_task_id, &allowed_protected).await?;
     let mut assignments = Vec::with_capacity(plan.tasks.len());
 
     for (idx, llm_task) in plan.tasks.iter().enumerate() {
         let domain = normalize_domain(&llm_task.domain);
         let tier = llm_task.tier.clamp(2, 3);
-        let risk_factor = estimate_failure_probability(&objective, &llm_task.objective, &domain);
-        let constraints = build_constraints(
+        let (target_files, protected_files) =
+            protected_paths::partition_allowed(&llm_task.target_files, &allowed_protected);
+        let file_history = git_history::file_history(
+            pool,
+            &target_root_str,
+            &target_files,
+            ASSIGNMENT_HISTORY_ENTRIES,
+        )
+        .await
+        .unwrap_or_default();
+        let risk_factor = (estimate_failure_probability(&objective, &llm_task.objective, &domain)
+            + recent_churn_risk(&file_history, Utc::now().timestamp()))
+        .clamp(0.05, 0.95);
+        let mut constraints = build_constraints(
             &domain,
             risk_factor,
             input.max_risk_tolerance.clamp(0.0, 1.0),
             &objective,
         );
-
-        let target_files_json = if llm_task.target_files.is_empty() {
+        constraints.extend(recent_change_constraints(&file_history));
+        let target_files_json = if target_files.is_empty() {
             None
         } else {
-            Some(serde_json::to_string(&llm_task.target_files).unwrap_or_default())
+            Some(serde_json::to_string(&target_files).unwrap_or_default())
         };
         let created = tasks::create_task_record(
             pool,
@@ -1407,6 +1752,7 @@ pub async fn generate_plan(
                 risk_factor: f64::from(risk_factor),
                 status: TaskStatus::Paused,
                 target_files: target_files_json,
+                priority: llm_task.priority,
             },
         )
         .await?;
@@ -1417,13 +1763,15 @@ pub async fn generate_plan(
             "plan_assignment_created",
             &created.id,
             &format!(
-                "parent={} tier={} domain={} risk={:.3} budget={} files={} rationale={}",
+                "parent={} tier={} domain={} risk={:.3} budget={} files={} droppedProtected={} recentChanges={} rationale={}",
                 input.root_task_id,
                 tier,
                 domain,
                 risk_factor,
                 budgets[idx],
-                llm_task.target_files.join(","),
+                target_files.join(","),
+                protected_files.join(","),
+                file_history.len(),
                 llm_task.rationale.as_deref().unwrap_or("—")
             ),
         )
@@ -1438,7 +1786,8 @@ pub async fn generate_plan(
             token_budget: budgets[idx],
             risk_factor,
             constraints,
-            relevant_files: llm_task.target_files.clone(),
+            relevant_files: target_files,
+            priority: llm_task.priority,
         });
     }
 
@@ -1460,13 +1809,29 @@ pub async fn generate_plan(
         "plan_generation_completed",
         &input.root_task_id,
         &format!(
-            "assignments={} model={}/{}",
+            "assignments={} model={}/{} tokensIn={} tokensOut={}{}",
             assignments.len(),
             tier1_model.provider,
-            tier1_model.model_id
+            tier1_model.model_id,
+            response.input_tokens.unwrap_or_default(),
+            response.output_tokens.unwrap_or_default(),
+            telemetry::cost_detail(response.total_cost_usd)
         ),
     )
     .await?;
+    notifications::notify(
+        NotificationKind::PlanReady,
+        "Plan ready for review",
+        &format!("{} assignments: {}", assignments.len(), root_task.objective),
+    );
+    webhooks::emit(
+        pool,
+        secret_vault,
+        WebhookEvent::PlanReady,
+        &root_task.id,
+        &root_task.objective,
+        json!({ "assignments": assignments.len() }),
+    );
 
     Ok(GeneratedPlan {
         root_task,
@@ -1515,26 +1880,38 @@ fn infer_primary_domain(objective: &str) -> String {
 
 // --- LLM-driven draft generation with fallback ---
 
+#[allow(clippy::too_many_arguments)]
 fn generate_drafts_with_llm(
-    provider: &str,
-    model_id: &str,
+    pool: &SqlitePool,
+    model: &ModelSelection,
+    fallbacks: Vec<ModelSelection>,
     objective: &str,
     domain: &str,
     file_tree: &str,
     token_budget: u32,
     risk_tolerance: f32,
+    target_project: &str,
 ) -> Vec<AssignmentDraft> {
-    let system_prompt = build_plan_generation_prompt();
+    let system_prompt =
+        build_plan_generation_prompt(target_project, objective, token_budget, risk_tolerance);
     let user_prompt = format!(
         "OBJECTIVE:\n{}\n\nPROJECT FILE TREE:\n{}\n\nTOKEN BUDGET: {}\nRISK TOLERANCE: {:.2}\n\nGenerate the implementation plan.",
         objective, file_tree, token_budget, risk_tolerance
     );
 
     let request = AdapterRequest {
-        provider: provider.to_string(),
-        model_id: model_id.to_string(),
+        provider: model.provider.clone(),
+        model_id: model.model_id.clone(),
         system_prompt,
         user_prompt,
+        endpoint: model.endpoint.clone(),
+        generation: model.generation.clone(),
+        task_id: None,
+        fallbacks,
+        response_schema: Some(plan_output_schema()),
+        bypass_cache: false,
+        attachments: Vec::new(),
+        pool: Some(pool.clone()),
     };
 
     match llm_adapter::generate(&request) {
@@ -1550,6 +1927,7 @@ fn generate_drafts_with_llm(
                             domain: normalize_domain(&t.domain),
                             objective: t.objective.clone(),
                             target_files: t.target_files.clone(),
+                            priority: t.priority,
                         })
                         .collect();
                 }
@@ -1583,6 +1961,7 @@ fn build_simple_fallback_drafts(domain: &str, objective: &str) -> Vec<Assignment
             domain: domain.to_string(),
             objective: format!("Coordinate and plan: {objective}"),
             target_files: Vec::new(),
+            priority: 0,
         });
     }
 
@@ -1591,6 +1970,7 @@ fn build_simple_fallback_drafts(domain: &str, objective: &str) -> Vec<Assignment
         domain: domain.to_string(),
         objective: format!("Implement core changes: {objective}"),
         target_files: Vec::new(),
+        priority: 0,
     });
 
     // Only add testing task for non-trivial objectives
@@ -1600,6 +1980,7 @@ fn build_simple_fallback_drafts(domain: &str, objective: &str) -> Vec<Assignment
             domain: "testing".to_string(),
             objective: format!("Add tests for: {objective}"),
             target_files: Vec::new(),
+            priority: 0,
         });
     }
 
@@ -1609,44 +1990,84 @@ fn build_simple_fallback_drafts(domain: &str, objective: &str) -> Vec<Assignment
             domain: domain.to_string(),
             objective: format!("Apply follow-up fixes: {objective}"),
             target_files: Vec::new(),
+            priority: 0,
         });
     }
 
     drafts
 }
 
-fn build_plan_generation_prompt() -> String {
-    r#"You are a Tier-1 orchestrator for the Autonomous Orchestration Platform (AOP).
-Generate a concrete implementation plan broken into tasks that can be executed by Tier-2 domain leaders and Tier-3 specialists.
+fn build_plan_generation_prompt(
+    target_project: &str,
+    objective: &str,
+    token_budget: u32,
+    risk_tolerance: f32,
+) -> String {
+    prompt_templates::render(
+        prompt_templates::PLANNER,
+        Some(target_project),
+        json!({
+            "objective": objective,
+            "token_budget": token_budget,
+            "risk_tolerance": risk_tolerance,
+        }),
+    )
+}
 
-Respond with JSON only:
-{
-  "tasks": [
-    {
-      "objective": "what this task should accomplish",
-      "domain": "frontend|backend|auth|database|api|testing|docs|platform",
-      "tier": 2 or 3,
-      "targetFiles": ["file/path1.ts", "file/path2.tsx"],
-      "rationale": "why this task is needed"
-    }
-  ],
-  "riskAssessment": "overall risk analysis and mitigation notes"
+/// Schema of [`LlmAnalysisResponse`], enforced by providers with structured output.
+fn analysis_output_schema() -> ResponseSchema {
+    ResponseSchema {
+        name: "objective_analysis".to_string(),
+        schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "questions": { "type": "array", "items": { "type": "string" } },
+                "initialAnalysis": { "type": "string" },
+                "suggestedApproach": { "type": "string" }
+            },
+            "required": ["questions", "initialAnalysis", "suggestedApproach"],
+            "additionalProperties": false
+        }),
+    }
 }
 
-CRITICAL Rules:
-- Generate 1-6 tasks. Match task count to objective complexity:
-  * TRIVIAL objectives (edit one file, add one line, fix a typo, update a README): generate exactly 1 task. Do NOT create testing or validation tasks for trivial edits.
-  * SIMPLE objectives (change 1-2 files, small feature): 1-2 tasks.
-  * MODERATE objectives (3-5 files, new feature): 2-4 tasks.
-  * COMPLEX objectives (6+ files, refactor, migration): 3-6 tasks.
-- Do NOT create a separate "testing" task unless the objective explicitly mentions tests or the change is complex enough to warrant them.
-- Tier 2 tasks are for domain leaders who coordinate complex multi-file changes. Use tier 2 ONLY for tasks that genuinely need coordination across 3+ files.
-- Tier 3 tasks are for specialists who make focused single-file changes. Most tasks should be tier 3.
-- targetFiles MUST be real paths from the file tree provided. ONLY include files that are directly relevant to the objective. Never include infrastructure, pipeline, or framework files unless the objective explicitly targets them.
-- Order tasks by dependency (independent tasks first, dependent tasks last).
-- Each task objective must be specific and actionable, not vague.
-- For documentation objectives (README, docs, config files): use domain "docs" and generate exactly 1 tier 3 task."#
-        .to_string()
+/// Schema of [`LlmPlanResponse`], enforced by providers with structured output.
+fn plan_output_schema() -> ResponseSchema {
+    ResponseSchema {
+        name: "implementation_plan".to_string(),
+        schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "tasks": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "objective": { "type": "string" },
+                            "domain": {
+                                "type": "string",
+                                "enum": [
+                                    "frontend", "backend", "auth", "database", "api",
+                                    "testing", "docs", "platform"
+                                ]
+                            },
+                            "tier": { "type": "integer", "enum": [2, 3] },
+                            "targetFiles": { "type": "array", "items": { "type": "string" } },
+                            "rationale": { "type": ["string", "null"] },
+                            "priority": { "type": "integer" }
+                        },
+                        "required": [
+                            "objective", "domain", "tier", "targetFiles", "rationale", "priority"
+                        ],
+                        "additionalProperties": false
+                    }
+                },
+                "riskAssessment": { "type": ["string", "null"] }
+            },
+            "required": ["tasks", "riskAssessment"],
+            "additionalProperties": false
+        }),
+    }
 }
 
 fn build_file_tree_summary(files: &[String], max_entries: usize) -> String {
@@ -1665,6 +2086,56 @@ fn build_file_tree_summary(files: &[String], max_entries: usize) -> String {
     summary
 }
 
+fn build_recent_changes_summary(history: &[GitHistoryEntry]) -> String {
+    if history.is_empty() {
+        return "No git history available.".to_string();
+    }
+    history
+        .iter()
+        .map(|entry| {
+            let short_hash = entry.commit_hash.get(..8).unwrap_or(&entry.commit_hash);
+            match &entry.file_path {
+                Some(_) => format!("{short_hash} {} ({})", entry.subject, entry.summary),
+                None => format!(
+                    "{short_hash} {}\n  {}",
+                    entry.subject,
+                    entry.summary.replace('\n', "\n  ")
+                ),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Files that changed several times lately are mid-flight and more likely to conflict
+/// with, or be broken by, another change.
+fn recent_churn_risk(file_history: &[GitHistoryEntry], now: i64) -> f32 {
+    let recent_commits = file_history
+        .iter()
+        .filter(|entry| now - entry.committed_at <= RECENT_CHURN_WINDOW_SECS)
+        .map(|entry| entry.commit_hash.as_str())
+        .collect::<HashSet<_>>()
+        .len();
+    (recent_commits as f32 * 0.04).min(0.16)
+}
+
+fn recent_change_constraints(file_history: &[GitHistoryEntry]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    file_history
+        .iter()
+        .filter(|entry| seen.insert(entry.commit_hash.as_str()))
+        .take(2)
+        .map(|entry| {
+            format!(
+                "stay consistent with recent change {} \"{}\" to {}",
+                entry.commit_hash.get(..8).unwrap_or(&entry.commit_hash),
+                entry.subject,
+                entry.file_path.as_deref().unwrap_or("these files")
+            )
+        })
+        .collect()
+}
+
 fn normalize_domain(domain: &str) -> String {
     match domain.trim().to_ascii_lowercase().as_str() {
         "frontend" | "ui" | "react" | "vue" | "angular" => "frontend".to_string(),
@@ -1738,66 +2209,29 @@ fn strip_unc_prefix(path: PathBuf) -> PathBuf {
 }
 
 fn collect_source_files(root: &Path, limit: usize) -> Result<Vec<String>, String> {
-    let mut queue = VecDeque::from([root.to_path_buf()]);
     let mut files = Vec::new();
-
-    while let Some(current_dir) = queue.pop_front() {
+    for path in project_ignore::project_files(root)? {
         if files.len() >= limit {
             break;
         }
-
-        let entries = fs::read_dir(&current_dir).map_err(|error| {
-            format!(
-                "Failed to read directory '{}': {error}",
-                current_dir.display()
-            )
-        })?;
-
-        for entry in entries {
-            let entry =
-                entry.map_err(|error| format!("Failed to read directory entry: {error}"))?;
-            let path = entry.path();
-            let file_type = entry
-                .file_type()
-                .map_err(|error| format!("Failed to inspect '{}': {error}", path.display()))?;
-
-            if file_type.is_dir() {
-                let name = entry.file_name().to_string_lossy().to_string();
-                if should_skip_dir(&name) {
-                    continue;
-                }
-                queue.push_back(path);
-                continue;
-            }
-
-            if file_type.is_file() && is_supported_extension(&path) {
-                let relative = path
-                    .strip_prefix(root)
-                    .map_err(|error| format!("Failed to compute relative path: {error}"))?;
-                let relative = relative
-                    .components()
-                    .map(|component| component.as_os_str().to_string_lossy().to_string())
-                    .collect::<Vec<_>>()
-                    .join("/");
-                files.push(relative);
-            }
-
-            if files.len() >= limit {
-                break;
-            }
+        if !is_supported_extension(&path) {
+            continue;
         }
+        let relative = path
+            .strip_prefix(root)
+            .map_err(|error| format!("Failed to compute relative path: {error}"))?;
+        files.push(
+            relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join("/"),
+        );
     }
 
     Ok(files)
 }
 
-fn should_skip_dir(name: &str) -> bool {
-    matches!(
-        name,
-        ".git" | "node_modules" | "target" | "dist" | "build" | ".next" | ".turbo"
-    )
-}
-
 fn is_supported_extension(path: &Path) -> bool {
     matches!(
         path.extension().and_then(|value| value.to_str()),
@@ -2012,8 +2446,12 @@ mod tests {
         .expect("fixture should be written");
 
         let model_registry = ModelRegistry::default();
+        let secret_vault = Arc::new(Mutex::new(SecretVault::new(
+            project_dir.path().join("vault"),
+        )));
         let result = orchestrate_and_persist(
             &pool,
+            &secret_vault,
             &model_registry,
             UserObjectiveInput {
                 objective: "Refactor auth module".to_string(),
@@ -2040,4 +2478,28 @@ mod tests {
             result.distributed_budget.max(1)
         );
     }
+
+    #[test]
+    fn output_schemas_are_strict_and_match_the_parsers() {
+        fn assert_strict(schema: &serde_json::Value) {
+            if let Some(properties) = schema.get("properties").and_then(|value| value.as_object()) {
+                let required = schema["required"].as_array().expect("required list");
+                assert_eq!(required.len(), properties.len());
+                assert_eq!(schema["additionalProperties"], false);
+                properties.values().for_each(assert_strict);
+            }
+            if let Some(items) = schema.get("items") {
+                assert_strict(items);
+            }
+        }
+        assert_strict(&analysis_output_schema().schema);
+        assert_strict(&plan_output_schema().schema);
+
+        let plan = parse_plan_response(
+            r#"{"tasks":[{"objective":"Add logout","domain":"auth","tier":3,"targetFiles":["src/auth/session.ts"],"rationale":null}],"riskAssessment":null}"#,
+        )
+        .expect("schema-shaped plan should parse");
+        assert_eq!(plan.tasks.len(), 1);
+        assert_eq!(plan.tasks[0].tier, 3);
+    }
 }
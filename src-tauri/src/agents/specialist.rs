@@ -1,11 +1,19 @@
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
 use similar::{ChangeTag, TextDiff};
+use sqlx::SqlitePool;
 use uuid::Uuid;
 
+use crate::agents::context_builder;
 use crate::agents::CodeBlock;
-use crate::llm_adapter::{self, AdapterRequest};
+use crate::llm_adapter::{self, AdapterRequest, AdapterResponse, ImageAttachment, ResponseSchema};
+use crate::llm_stream;
+use crate::mcp_bridge::tool_cache::{self, McpToolSchema};
+use crate::model_registry::{GenerationParams, ModelEndpoint, ModelSelection};
+use crate::prompt_templates;
 use crate::vector::indexer::embed_text;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,11 +27,30 @@ pub struct SpecialistTask {
     pub token_budget: u32,
     pub target_files: Vec<String>,
     pub code_context: Vec<CodeBlock>,
+    /// Paths of files near the target, listed so the model knows what exists.
+    #[serde(default)]
+    pub file_tree: Vec<String>,
     pub constraints: Vec<String>,
     #[serde(default)]
     pub model_provider: Option<String>,
     #[serde(default)]
     pub model_id: Option<String>,
+    #[serde(default)]
+    pub model_endpoint: ModelEndpoint,
+    #[serde(default)]
+    pub model_generation: GenerationParams,
+    /// Models the adapter fails over to when the assigned one keeps erroring.
+    #[serde(default)]
+    pub model_fallbacks: Vec<ModelSelection>,
+    /// Project root used to pick up `.aop/prompts` overrides.
+    #[serde(default)]
+    pub target_project: Option<String>,
+    /// Screenshots or design exports attached to the objective, for vision models.
+    #[serde(default)]
+    pub attachments: Vec<ImageAttachment>,
+    /// Tools of the assignment's MCP server, listed so the model knows what exists.
+    #[serde(default)]
+    pub mcp_tools: Vec<McpToolSchema>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +65,18 @@ pub struct DiffProposal {
     pub intent_hash: String,
     pub confidence: f32,
     pub tokens_used: u32,
+    /// Prompt and completion tokens reported by the model; zero when no model ran.
+    #[serde(default)]
+    pub tokens_in: u32,
+    #[serde(default)]
+    pub tokens_out: u32,
+    /// USD the model call cost; `None` when no model ran or the model has no price.
+    #[serde(default)]
+    pub cost_usd: Option<f64>,
+    #[serde(default)]
+    pub modified_content: Option<String>,
+    #[serde(default)]
+    pub base_checksum: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -54,65 +93,145 @@ struct SpecialistModelOutput {
 struct RemoteGenerationResult {
     intent_description: String,
     diff_content: String,
+    modified_content: String,
     confidence: f32,
-    output_tokens: Option<u32>,
+    input_tokens: u32,
+    output_tokens: u32,
+    cost_usd: Option<f64>,
 }
 
+/// Generates the task's diff proposal. Model calls are cached in `pool` when one is given.
 pub fn run_specialist_task(
     task: &SpecialistTask,
     target_file_content: Option<&str>,
+    pool: Option<&SqlitePool>,
 ) -> Result<DiffProposal, String> {
     validate_specialist_task(task)?;
 
+    let file_path = resolve_target_file(task);
+    let remote_result = match remote_model_request(task, &file_path, target_file_content, pool) {
+        Some(request) => interpret_remote_response(
+            task,
+            &file_path,
+            target_file_content,
+            llm_stream::generate(&request, Some(&task.task_id), "specialist"),
+        )
+        .map(Some)?,
+        None => None,
+    };
+    Ok(build_proposal(
+        task,
+        file_path,
+        target_file_content,
+        remote_result,
+    ))
+}
+
+/// A specialist job for [`run_specialist_tasks`]: the task and its target file content.
+pub type SpecialistJob = (SpecialistTask, Option<String>);
+
+/// Runs independent specialist tasks with up to `max_parallel` model calls at once,
+/// streaming each reply. Results keep the job order; the duration is the model call's wall
+/// time.
+pub fn run_specialist_tasks(
+    jobs: &[SpecialistJob],
+    max_parallel: usize,
+    pool: Option<&SqlitePool>,
+) -> Vec<(Result<DiffProposal, String>, Duration)> {
+    let mut outcomes: Vec<Option<(Result<DiffProposal, String>, Duration)>> =
+        (0..jobs.len()).map(|_| None).collect();
+    let mut requests = Vec::new();
+    let mut request_jobs = Vec::new();
+    for (index, (task, content)) in jobs.iter().enumerate() {
+        if let Err(error) = validate_specialist_task(task) {
+            outcomes[index] = Some((Err(error), Duration::ZERO));
+            continue;
+        }
+        match remote_model_request(task, &resolve_target_file(task), content.as_deref(), pool) {
+            Some(request) => {
+                requests.push(request);
+                request_jobs.push(index);
+            }
+            None => {
+                let proposal =
+                    build_proposal(task, resolve_target_file(task), content.as_deref(), None);
+                outcomes[index] = Some((Ok(proposal), Duration::ZERO));
+            }
+        }
+    }
+
+    let batch = llm_adapter::generate_many(&requests, max_parallel.max(1), |request| {
+        llm_stream::generate(request, request.task_id.as_deref(), "specialist")
+    });
+    for ((index, response), latency) in request_jobs
+        .into_iter()
+        .zip(batch.results)
+        .zip(batch.latencies)
+    {
+        let (task, content) = &jobs[index];
+        let file_path = resolve_target_file(task);
+        let remote = interpret_remote_response(task, &file_path, content.as_deref(), response);
+        let proposal =
+            remote.map(|remote| build_proposal(task, file_path, content.as_deref(), Some(remote)));
+        outcomes[index] = Some((proposal, latency));
+    }
+
+    outcomes
+        .into_iter()
+        .map(|outcome| outcome.expect("every specialist job has an outcome"))
+        .collect()
+}
+
+fn build_proposal(
+    task: &SpecialistTask,
+    file_path: String,
+    target_file_content: Option<&str>,
+    remote_result: Option<RemoteGenerationResult>,
+) -> DiffProposal {
     let agent_uid = Uuid::new_v4().to_string();
     let proposal_id = Uuid::new_v4().to_string();
-    let file_path = resolve_target_file(task);
+    let model_ran = remote_result.is_some();
 
-    let remote_result = try_remote_model_generation(task, &file_path, target_file_content)?;
-
-    let (intent_description, diff_content, confidence, tokens_used) = match remote_result {
-        Some(result) => {
-            let baseline_tokens = estimate_tokens_used(task, target_file_content);
-            let tokens = result
-                .output_tokens
-                .map(|ot| {
-                    baseline_tokens
-                        .saturating_add(ot)
-                        .min(task.token_budget)
-                        .max(40)
-                })
-                .unwrap_or(baseline_tokens);
-            (
+    let cost_usd = remote_result.as_ref().and_then(|result| result.cost_usd);
+    let (intent_description, diff_content, modified_content, confidence, tokens_in, tokens_out) =
+        match remote_result {
+            Some(result) => (
                 result.intent_description,
                 result.diff_content,
+                Some(result.modified_content),
                 result.confidence,
-                tokens,
-            )
-        }
-        None => {
-            let model_tag = model_tag(task);
-            let intent = format!(
-                "{}{} proposal for {}: {}",
-                task.persona,
-                model_tag,
-                file_path,
-                task.objective.trim()
-            );
-            let diff = build_fallback_diff(
-                &file_path,
-                target_file_content,
-                &task.persona,
-                &task.objective,
-            );
-            let confidence = estimate_fallback_confidence(task, target_file_content);
-            let tokens = estimate_tokens_used(task, target_file_content);
-            (intent, diff, confidence, tokens)
-        }
+                result.input_tokens,
+                result.output_tokens,
+            ),
+            None => {
+                let model_tag = model_tag(task);
+                let intent = format!(
+                    "{}{} proposal for {}: {}",
+                    task.persona,
+                    model_tag,
+                    file_path,
+                    task.objective.trim()
+                );
+                let diff = build_fallback_diff(
+                    &file_path,
+                    target_file_content,
+                    &task.persona,
+                    &task.objective,
+                );
+                let confidence = estimate_fallback_confidence(task, target_file_content);
+                (intent, diff, None, confidence, 0, 0)
+            }
+        };
+    // Real usage when a model ran, so budgets see what was actually spent.
+    let tokens_used = if model_ran {
+        tokens_in.saturating_add(tokens_out)
+    } else {
+        estimate_tokens_used(task, target_file_content)
     };
 
     let intent_hash = hash_intent_embedding(&intent_description);
 
-    Ok(DiffProposal {
+    DiffProposal {
         proposal_id,
         task_id: task.task_id.clone(),
         agent_uid,
@@ -122,7 +241,12 @@ pub fn run_specialist_task(
         intent_hash,
         confidence,
         tokens_used,
-    })
+        tokens_in,
+        tokens_out,
+        cost_usd,
+        modified_content,
+        base_checksum: target_file_content.map(content_checksum),
+    }
 }
 
 pub fn semantic_distance(a: &DiffProposal, b: &DiffProposal) -> f32 {
@@ -188,7 +312,15 @@ fn resolve_target_file(task: &SpecialistTask) -> String {
     "unknown/file.ts".to_string()
 }
 
-fn hash_intent_embedding(intent_description: &str) -> String {
+/// SHA-256 of the content the proposal was generated against, in the same format the
+/// pipeline uses for target file checksums.
+fn content_checksum(content: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+pub fn hash_intent_embedding(intent_description: &str) -> String {
     let embedding = embed_text(intent_description);
     let serialized = embedding
         .iter()
@@ -263,29 +395,46 @@ fn estimate_tokens_used(task: &SpecialistTask, target_file_content: Option<&str>
         .max(40)
 }
 
-fn try_remote_model_generation(
+/// The model request for `task`, or `None` when no remote model should run.
+fn remote_model_request(
     task: &SpecialistTask,
     file_path: &str,
     target_file_content: Option<&str>,
-) -> Result<Option<RemoteGenerationResult>, String> {
+    pool: Option<&SqlitePool>,
+) -> Option<AdapterRequest> {
     if !remote_model_adapter_enabled() {
-        return Ok(None);
+        return None;
     }
 
     let (provider, model_id) = match (task.model_provider.as_deref(), task.model_id.as_deref()) {
         (Some(provider), Some(model_id)) => (provider.trim(), model_id.trim()),
-        _ => return Ok(None),
+        _ => return None,
     };
 
     let (system_prompt, user_prompt) = build_remote_prompts(task, file_path, target_file_content);
-    let request = AdapterRequest {
+    Some(AdapterRequest {
         provider: provider.to_string(),
         model_id: model_id.to_string(),
         system_prompt,
         user_prompt,
-    };
+        endpoint: task.model_endpoint.clone(),
+        generation: task.model_generation.clone(),
+        task_id: Some(task.task_id.clone()),
+        fallbacks: task.model_fallbacks.clone(),
+        response_schema: Some(specialist_output_schema()),
+        bypass_cache: false,
+        attachments: task.attachments.clone(),
+        pool: pool.cloned(),
+    })
+}
 
-    match llm_adapter::generate(&request) {
+fn interpret_remote_response(
+    task: &SpecialistTask,
+    file_path: &str,
+    target_file_content: Option<&str>,
+    response: Result<AdapterResponse, String>,
+) -> Result<RemoteGenerationResult, String> {
+    match response {
         Ok(response) => {
             let parsed = parse_specialist_model_output(&response.text);
 
@@ -309,7 +458,10 @@ fn try_remote_model_generation(
                 .map(|value| strip_code_fences(&value))
                 .filter(|value| !value.trim().is_empty());
 
-            let (diff_content, confidence) = match (target_file_content, modified_content) {
+            let (diff_content, modified_content, confidence) = match (
+                target_file_content,
+                modified_content,
+            ) {
                 (Some(original), Some(ref modified)) if original.trim() != modified.trim() => {
                     let original_normalized = original.replace("\r\n", "\n");
                     let modified_normalized = modified.replace("\r\n", "\n");
@@ -326,12 +478,12 @@ fn try_remote_model_generation(
                     }
                     let confidence =
                         estimate_llm_confidence(task, &original_normalized, &modified_normalized);
-                    (diff, confidence)
+                    (diff, modified_normalized, confidence)
                 }
                 (None, Some(ref modified)) => {
                     let modified_normalized = modified.replace("\r\n", "\n");
                     let diff = compute_unified_diff(file_path, "", &modified_normalized);
-                    (diff, 0.60)
+                    (diff, modified_normalized, 0.60)
                 }
                 (Some(_), Some(_)) => {
                     // LLM returned content identical to original — no-op change
@@ -362,18 +514,15 @@ fn try_remote_model_generation(
                 }
             };
 
-            let output_tokens = response.output_tokens.or_else(|| {
-                response
-                    .input_tokens
-                    .map(|input_tokens| (input_tokens / 6).max(1))
-            });
-
-            Ok(Some(RemoteGenerationResult {
+            Ok(RemoteGenerationResult {
                 intent_description,
                 diff_content,
+                modified_content,
                 confidence,
-                output_tokens,
-            }))
+                input_tokens: response.input_tokens.unwrap_or_default(),
+                output_tokens: response.output_tokens.unwrap_or_default(),
+                cost_usd: response.total_cost_usd,
+            })
         }
         Err(error) => {
             // Always propagate LLM errors. Silently falling back to a
@@ -384,7 +533,7 @@ fn try_remote_model_generation(
     }
 }
 
-fn remote_model_adapter_enabled() -> bool {
+pub(crate) fn remote_model_adapter_enabled() -> bool {
     let default_enabled = !cfg!(test);
     std::env::var("AOP_MODEL_ADAPTER_ENABLED")
         .ok()
@@ -402,38 +551,21 @@ fn build_remote_prompts(
     file_path: &str,
     target_file_content: Option<&str>,
 ) -> (String, String) {
-    let system_prompt =
-        r#"You are a Tier-3 software specialist for Autonomous Orchestration Platform (AOP).
-You will receive a file to modify and an objective.
-Apply the MINIMUM changes needed to accomplish the objective.
-
-Respond with JSON only:
-{
-  "intentDescription": "what this change accomplishes",
-  "modifiedContent": "the COMPLETE modified file content with your changes applied",
-  "changesSummary": ["change 1", "change 2"]
-}
-
-Rules:
-- Return the FULL file content with your modifications applied in modifiedContent.
-- Make minimal, focused changes — do not rewrite unrelated code.
-- Preserve existing formatting, style, and indentation.
-- If the objective cannot be safely accomplished, set modifiedContent to null and explain in intentDescription.
-- Do not wrap the JSON response in markdown fences."#
-            .to_string();
+    let system_prompt = prompt_templates::render(
+        prompt_templates::SPECIALIST,
+        task.target_project.as_deref(),
+        json!({
+            "persona": task.persona.trim(),
+            "objective": task.objective.trim(),
+            "file_path": file_path,
+        }),
+    );
 
-    let context_excerpt = task
-        .code_context
-        .iter()
-        .take(3)
-        .map(|block| {
-            format!(
-                "[{}:{}-{}]\n{}",
-                block.file_path, block.start_line, block.end_line, block.content
-            )
-        })
-        .collect::<Vec<_>>()
-        .join("\n\n");
+    let context_excerpt = context_builder::render_context(
+        &task.code_context,
+        &task.file_tree,
+        context_builder::context_budget(task.token_budget),
+    );
 
     let file_content = target_file_content
         .map(|content| {
@@ -452,7 +584,7 @@ Rules:
         task.constraints.join(" | ")
     };
 
-    let user_prompt = format!(
+    let mut user_prompt = format!(
         "persona: {}\nobjective: {}\nfilePath: {}\nconstraints: {}\n\n--- FILE CONTENT START ---\n{}\n--- FILE CONTENT END ---\n\ncodeContext:\n{}\n",
         task.persona.trim(),
         task.objective.trim(),
@@ -461,10 +593,33 @@ Rules:
         file_content,
         context_excerpt
     );
+    if !task.mcp_tools.is_empty() {
+        user_prompt.push_str(&format!(
+            "\nmcpTools:\n{}\n",
+            tool_cache::render_tools(&task.mcp_tools)
+        ));
+    }
 
     (system_prompt, user_prompt)
 }
 
+/// Schema of [`SpecialistModelOutput`], enforced by providers with structured output.
+fn specialist_output_schema() -> ResponseSchema {
+    ResponseSchema {
+        name: "specialist_proposal".to_string(),
+        schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "intentDescription": { "type": "string" },
+                "modifiedContent": { "type": ["string", "null"] },
+                "changesSummary": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["intentDescription", "modifiedContent", "changesSummary"],
+            "additionalProperties": false
+        }),
+    }
+}
+
 fn parse_specialist_model_output(raw: &str) -> Option<SpecialistModelOutput> {
     let trimmed = raw.trim();
     if trimmed.is_empty() {
@@ -671,9 +826,16 @@ mod tests {
                 content: "export function SessionProvider() { return null }".to_string(),
                 embedding: None,
             }],
+            file_tree: vec!["src/session.tsx".to_string()],
             constraints: vec!["avoid regressions in loading and error states".to_string()],
             model_provider: Some("openai".to_string()),
             model_id: Some("gpt-5-nano".to_string()),
+            model_endpoint: ModelEndpoint::default(),
+            model_generation: GenerationParams::default(),
+            model_fallbacks: Vec::new(),
+            target_project: None,
+            attachments: Vec::new(),
+            mcp_tools: Vec::new(),
         }
     }
 
@@ -682,6 +844,7 @@ mod tests {
         let proposal = run_specialist_task(
             &make_task(),
             Some("export function SessionProvider() { return null }"),
+            None,
         )
         .expect("proposal should be generated");
 
@@ -695,12 +858,13 @@ mod tests {
 
     #[test]
     fn semantic_distance_is_bounded() {
-        let proposal_a = run_specialist_task(&make_task(), None).expect("proposal should generate");
+        let proposal_a =
+            run_specialist_task(&make_task(), None, None).expect("proposal should generate");
         let mut alternate_task = make_task();
         alternate_task.objective =
             "Rewrite token refresh flow with stricter validation".to_string();
         let proposal_b =
-            run_specialist_task(&alternate_task, None).expect("proposal should generate");
+            run_specialist_task(&alternate_task, None, None).expect("proposal should generate");
 
         let distance = semantic_distance(&proposal_a, &proposal_b);
         assert!((0.0..=1.0).contains(&distance));
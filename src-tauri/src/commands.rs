@@ -1,46 +1,127 @@
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
 
 use crate::agents::domain_leader::{self, ExecuteDomainTaskInput, IntentSummary};
 use crate::agents::orchestrator::{
     self, AnalyzeObjectiveInput, ApproveOrchestrationPlanInput, GeneratePlanInput, GeneratedPlan,
     ObjectiveAnalysis, OrchestrationResult, PlanExecutionResult, UserObjectiveInput,
 };
+use crate::config_bundle::{
+    self, ConfigurationBundle, ImportConfigurationInput, ImportConfigurationResult,
+};
+use crate::db::analytics::{
+    self, CostBreakdown, GetCostBreakdownInput, GetTokenUsageTrendsInput, TokenUsageTrends,
+};
+use crate::db::attachments::{
+    self, AttachObjectiveImagesInput, ListTaskAttachmentsInput, TaskAttachmentRecord,
+};
+use crate::db::backup::{
+    self, DatabaseBackup, DatabaseIntegrityReport, RestoreDatabaseInput, RestoreDatabaseResult,
+};
 use crate::db::budget_requests::{
-    self, BudgetRequestRecord, CreateBudgetRequestInput, ListTaskBudgetRequestsInput,
-    ResolveBudgetRequestInput,
+    self, BudgetPolicyRecord, BudgetRequestRecord, CreateBudgetRequestInput,
+    ListTaskBudgetRequestsInput, ResolveBudgetRequestInput, SetBudgetPolicyInput,
 };
+use crate::db::event_search::{self, EventSearchHit, SearchEventsInput};
 use crate::db::metrics::{
     self, AgentTerminalSession, AuditLogEntry, ListAgentTerminalsInput, ListAuditLogInput,
-    ListTaskActivityInput, ListTerminalEventsInput, TerminalEventRecord,
+    ListSecretAccessLogInput, ListTaskActivityInput, ListTerminalEventsInput, TerminalEventRecord,
 };
 use crate::db::telemetry::{
-    self, AgentEventRecord, AgentRunRecord, ArchiveTelemetryInput, ArchiveTelemetryResult,
-    GetMissionControlSnapshotInput, ListAgentEventsInput, ListAgentRunsInput,
-    MissionControlSnapshot,
+    self, AgentEventPage, AgentEventRecord, AgentRunPage, AgentRunRecord, ArchiveTelemetryInput,
+    ArchiveTelemetryResult,
+    GetMissionControlSnapshotInput, GetModelHealthTrendsInput, GetTaskCostRollupInput,
+    ListAgentEventsInput, ListAgentRunsInput, MissionControlSnapshot, ModelHealthTrends,
+    TaskCostRollup,
+};
+use crate::db::telemetry_archive::{
+    self, ImportTelemetryArchiveInput, ImportTelemetryArchiveResult,
+};
+use crate::db::timeline::{self, GetTaskTimelineInput, TaskTimeline};
+use crate::db::model_arms::{
+    self, ListModelArmsInput, ModelArmRecord, ModelPinRecord, SetModelPinInput,
+};
+use crate::db::model_experiments::{
+    self, CreateModelExperimentInput, ModelExperimentComparison, ModelExperimentIdInput,
+    ModelExperimentRecord,
 };
 use crate::db::mutations::{
-    self, ListTaskMutationsInput, MutationRecord, UpdateMutationStatusInput,
+    self, ListTaskMutationsInput, MutationRecord, PurgeMutationsInput, PurgeMutationsResult,
+    UpdateMutationStatusInput,
 };
 use crate::db::tasks::{
-    self, ControlTaskInput, CreateTaskInput, TaskControlAction, TaskRecord, UpdateTaskStatusInput,
+    self, ControlTaskInput, CreateTaskInput, SetTaskDeadlineInput, SetTaskPriorityInput,
+    TaskControlAction, TaskRecord, UpdateTaskStatusInput,
+};
+use crate::llm_cache;
+use crate::llm_pricing;
+use crate::llm_rate_limit;
+use crate::llm_stream::{self, CancelLlmStreamInput};
+use crate::llm_transcript::{
+    self, ExportLlmTranscriptsInput, ExportLlmTranscriptsResult, ListLlmTranscriptsInput,
+    LlmTranscriptRecord,
 };
+use crate::mcp_bridge::command_runner::{self, CommandResult, RunTargetCommandInput};
+use crate::mcp_bridge::resources::{
+    self, McpResourceSubscription, SubscribeMcpResourcesInput, UnsubscribeMcpResourcesInput,
+};
+use crate::mcp_bridge::tool_cache::{self, ListMcpToolsInput, McpToolCatalog};
 use crate::mcp_bridge::tool_caller::{
-    self, DirectoryListing, ListTargetDirInput, ReadTargetFileInput, SearchResult,
-    SearchTargetFilesInput, TargetFileContent,
+    self, ApplyTargetPatchInput, DirectoryListing, ListTargetDirInput, ReadTargetFileInput,
+    SearchResult, SearchTargetFilesInput, TargetFileContent, WriteResult, WriteTargetFileInput,
+};
+use crate::mcp_servers::{self, McpServerConfig, RemoveMcpServerInput, SaveMcpServerInput};
+use crate::model_benchmark::{self, BenchmarkModelsInput, BenchmarkModelsResult};
+use crate::model_discovery::{self, DiscoverProviderModelsInput, DiscoverProviderModelsResult};
+use crate::model_registry::{
+    AddRegistryModelInput, ModelRegistry, ModelRegistrySnapshot, RemoveRegistryModelInput,
+    UpdateRegistryModelInput,
+};
+use crate::mutation_pipeline::{
+    self, MutationPipelineResult, RebaseMutationInput, RunMutationPipelineInput,
+};
+use crate::mutation_revision::{
+    self, EditMutationDiffInput, MutationRevisionResult, RequestMutationRevisionInput,
+};
+use crate::otlp_export::{self, ExportTaskTraceInput, ExportTaskTraceResult};
+use crate::pipeline_batch::{self, MutationPipelineBatchResult, RunMutationPipelineBatchInput};
+use crate::prompt_templates::{
+    self, GetPromptTemplatesInput, PromptTemplate, SetPromptTemplateInput,
+};
+use crate::provider_keys::{self, ProviderSecretValidation, ValidateProviderSecretInput};
+use crate::pull_request::{self, OpenPullRequestInput, OpenPullRequestResult};
+use crate::runtime_config::{
+    self, RuntimeFlags, RuntimeFlagsUpdateResult, RuntimeProfile, SetRuntimeFlagsInput,
+    SetRuntimeProfileInput,
 };
-use crate::model_registry::ModelRegistrySnapshot;
-use crate::mutation_pipeline::{self, MutationPipelineResult, RunMutationPipelineInput};
-use crate::mutation_revision::{self, MutationRevisionResult, RequestMutationRevisionInput};
-use crate::runtime_config::{RuntimeFlags, RuntimeFlagsUpdateResult, SetRuntimeFlagsInput};
 use crate::secret_vault::{
-    GetProviderSecretStatusInput, ProviderSecretStatus, RevealProviderSecretInput,
-    RevealProviderSecretResult, SecretOperationResult, SetProviderSecretInput,
+    self, GetProviderSecretStatusInput, ListProviderCredentialsInput, MigrateSecretBackendInput,
+    ProviderCredential, ProviderSecretStatus, RevealProviderSecretInput,
+    RevealProviderSecretResult, RotateProviderSecretInput, SecretBackend, SecretMigrationResult,
+    SecretOperationResult, SecretRotationResult, SetProviderSecretInput,
+};
+use crate::shadow_cache::{
+    self, CleanShadowCacheInput, PurgeShadowDirsInput, ShadowCleanupResult, ShadowDirEntry,
+};
+use crate::spend_cap::{self, SpendStatus};
+use crate::task_branch::{self, FinalizeBranchInput, FinalizeBranchResult};
+use crate::task_runtime::{self, RecoverStalledRunInput};
+use crate::telemetry_stream::{
+    self, MissionControlSubscription, MissionControlSubscriptionInput, MissionControlUpdate,
+    SubscribeMissionControlInput,
 };
-use crate::vector::indexer;
+use crate::vector::git_history;
+use crate::vector::jobs;
+use crate::vector::projects;
 use crate::vector::search;
-use crate::vector::{ContextChunk, IndexProjectInput, IndexProjectResult, QueryCodebaseInput};
+use crate::vector::symbols;
+use crate::vector::{
+    ContextChunk, DeleteProjectIndexResult, GitHistoryEntry, IndexGitHistoryResult, IndexJobInput,
+    IndexJobStatus, IndexProjectInput, IndexWatchInput, IndexWatchStatus, IndexedProjectStats,
+    QueryCodebaseInput, QueryGitHistoryInput, SearchSymbolsInput, SymbolSearchResult,
+};
 use crate::AppState;
 use serde::Deserialize;
+use std::sync::Arc;
 use std::time::Instant;
 
 #[derive(Debug, Clone, Deserialize)]
@@ -102,6 +183,42 @@ pub async fn control_task(
     Ok(updated)
 }
 
+#[tauri::command]
+pub async fn set_task_priority(
+    state: State<'_, AppState>,
+    input: SetTaskPriorityInput,
+) -> Result<TaskRecord, String> {
+    let task = tasks::set_task_priority(&state.db_pool, input).await?;
+    metrics::record_audit_event(
+        &state.db_pool,
+        "ui",
+        "task_priority_set",
+        Some(task.id.as_str()),
+        Some(&serde_json::json!({ "priority": task.priority }).to_string()),
+    )
+    .await?;
+
+    Ok(task)
+}
+
+#[tauri::command]
+pub async fn set_task_deadline(
+    state: State<'_, AppState>,
+    input: SetTaskDeadlineInput,
+) -> Result<TaskRecord, String> {
+    let task = tasks::set_task_deadline(&state.db_pool, input).await?;
+    metrics::record_audit_event(
+        &state.db_pool,
+        "ui",
+        "task_deadline_set",
+        Some(task.id.as_str()),
+        Some(&serde_json::json!({ "deadlineSeconds": task.deadline_seconds }).to_string()),
+    )
+    .await?;
+
+    Ok(task)
+}
+
 #[tauri::command]
 pub async fn request_task_budget_increase(
     state: State<'_, AppState>,
@@ -140,6 +257,22 @@ pub async fn list_task_budget_requests(
     budget_requests::list_task_budget_requests(&state.db_pool, input).await
 }
 
+#[tauri::command]
+pub async fn attach_objective_images(
+    state: State<'_, AppState>,
+    input: AttachObjectiveImagesInput,
+) -> Result<Vec<TaskAttachmentRecord>, String> {
+    attachments::attach_objective_images(&state.db_pool, input).await
+}
+
+#[tauri::command]
+pub async fn list_task_attachments(
+    state: State<'_, AppState>,
+    input: ListTaskAttachmentsInput,
+) -> Result<Vec<TaskAttachmentRecord>, String> {
+    attachments::list_task_attachments(&state.db_pool, input).await
+}
+
 #[tauri::command]
 pub async fn resolve_task_budget_request(
     state: State<'_, AppState>,
@@ -173,12 +306,56 @@ pub async fn resolve_task_budget_request(
     Ok(resolved)
 }
 
+#[tauri::command]
+pub async fn list_budget_policies(
+    state: State<'_, AppState>,
+) -> Result<Vec<BudgetPolicyRecord>, String> {
+    budget_requests::list_budget_policies(&state.db_pool).await
+}
+
+#[tauri::command]
+pub async fn set_budget_policy(
+    state: State<'_, AppState>,
+    input: SetBudgetPolicyInput,
+) -> Result<Option<BudgetPolicyRecord>, String> {
+    let name = input.name.trim().to_string();
+    let policy = budget_requests::set_budget_policy(&state.db_pool, input).await?;
+    let action = if policy.is_some() {
+        "budget_policy_saved"
+    } else {
+        "budget_policy_removed"
+    };
+
+    metrics::record_audit_event(
+        &state.db_pool,
+        "ui",
+        action,
+        None,
+        Some(&serde_json::json!({ "name": name }).to_string()),
+    )
+    .await?;
+
+    Ok(policy)
+}
+
+#[tauri::command]
+pub async fn get_spend_status(state: State<'_, AppState>) -> Result<SpendStatus, String> {
+    spend_cap::status(&state.db_pool).await
+}
+
 #[tauri::command]
 pub async fn orchestrate_objective(
     state: State<'_, AppState>,
     input: UserObjectiveInput,
 ) -> Result<OrchestrationResult, String> {
-    orchestrator::orchestrate_and_persist(&state.db_pool, &state.model_registry, input).await
+    let model_registry = current_model_registry(&state)?;
+    orchestrator::orchestrate_and_persist(
+        &state.db_pool,
+        &state.secret_vault,
+        &model_registry,
+        input,
+    )
+    .await
 }
 
 #[tauri::command]
@@ -186,7 +363,8 @@ pub async fn analyze_objective(
     state: State<'_, AppState>,
     input: AnalyzeObjectiveInput,
 ) -> Result<ObjectiveAnalysis, String> {
-    orchestrator::analyze_objective(&state.db_pool, &state.model_registry, input).await
+    let model_registry = current_model_registry(&state)?;
+    orchestrator::analyze_objective(&state.db_pool, &model_registry, input).await
 }
 
 #[tauri::command]
@@ -194,7 +372,8 @@ pub async fn submit_answers_and_plan(
     state: State<'_, AppState>,
     input: GeneratePlanInput,
 ) -> Result<GeneratedPlan, String> {
-    orchestrator::generate_plan(&state.db_pool, &state.model_registry, input).await
+    let model_registry = current_model_registry(&state)?;
+    orchestrator::generate_plan(&state.db_pool, &state.secret_vault, &model_registry, input).await
 }
 
 #[tauri::command]
@@ -202,27 +381,30 @@ pub async fn approve_orchestration_plan(
     state: State<'_, AppState>,
     input: ApproveOrchestrationPlanInput,
 ) -> Result<PlanExecutionResult, String> {
+    let model_registry = current_model_registry(&state)?;
     orchestrator::approve_plan_and_spawn(
         &state.db_pool,
+        &state.secret_vault,
         &state.bridge_client,
-        &state.model_registry,
+        &model_registry,
         input,
     )
     .await
 }
 
+#[tauri::command]
+pub fn cancel_llm_stream(input: CancelLlmStreamInput) -> Result<(), String> {
+    llm_stream::cancel(&input.stream_id)
+}
+
 #[tauri::command]
 pub async fn execute_domain_task(
     state: State<'_, AppState>,
     input: ExecuteDomainTaskInput,
 ) -> Result<IntentSummary, String> {
-    domain_leader::execute_domain_task(
-        &state.db_pool,
-        &state.bridge_client,
-        &state.model_registry,
-        input,
-    )
-    .await
+    let model_registry = current_model_registry(&state)?;
+    domain_leader::execute_domain_task(&state.db_pool, &state.bridge_client, &model_registry, input)
+        .await
 }
 
 #[tauri::command]
@@ -238,7 +420,72 @@ pub async fn run_mutation_pipeline(
     state: State<'_, AppState>,
     input: RunMutationPipelineInput,
 ) -> Result<MutationPipelineResult, String> {
-    mutation_pipeline::run_mutation_pipeline(&state.db_pool, input).await
+    let model_registry = current_model_registry(&state)?;
+    mutation_pipeline::run_mutation_pipeline(
+        &state.db_pool,
+        &state.secret_vault,
+        &model_registry,
+        input,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn run_mutation_pipeline_batch(
+    state: State<'_, AppState>,
+    input: RunMutationPipelineBatchInput,
+) -> Result<MutationPipelineBatchResult, String> {
+    let model_registry = current_model_registry(&state)?;
+    pipeline_batch::run_mutation_pipeline_batch(
+        &state.db_pool,
+        &state.secret_vault,
+        &model_registry,
+        input,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn rebase_mutation(
+    state: State<'_, AppState>,
+    input: RebaseMutationInput,
+) -> Result<MutationRecord, String> {
+    mutation_pipeline::rebase_mutation(&state.db_pool, input).await
+}
+
+#[tauri::command]
+pub async fn clean_shadow_cache(
+    state: State<'_, AppState>,
+    input: CleanShadowCacheInput,
+) -> Result<ShadowCleanupResult, String> {
+    shadow_cache::clean_shadow_cache(&state.db_pool, input).await
+}
+
+#[tauri::command]
+pub async fn list_shadow_dirs() -> Result<Vec<ShadowDirEntry>, String> {
+    shadow_cache::list_shadow_dirs()
+}
+
+#[tauri::command]
+pub async fn purge_shadow_dirs(
+    state: State<'_, AppState>,
+    input: PurgeShadowDirsInput,
+) -> Result<ShadowCleanupResult, String> {
+    shadow_cache::purge_shadow_dirs(&state.db_pool, input).await
+}
+
+/// Drops every cached model reply; returns how many were removed.
+#[tauri::command]
+pub async fn clear_llm_cache(state: State<'_, AppState>) -> Result<u64, String> {
+    llm_cache::clear(&state.db_pool).await
+}
+
+#[tauri::command]
+pub async fn purge_mutations(
+    state: State<'_, AppState>,
+    input: PurgeMutationsInput,
+) -> Result<PurgeMutationsResult, String> {
+    task_runtime::purge_mutations(&state.db_pool, input).await
 }
 
 #[tauri::command]
@@ -252,7 +499,13 @@ pub async fn set_mutation_status(
         "ui",
         "mutation_status_changed",
         Some(updated.id.as_str()),
-        Some(&format!("{{\"status\":\"{}\"}}", updated.status)),
+        Some(
+            &serde_json::json!({
+                "status": updated.status,
+                "approvedHunks": updated.approved_hunks,
+            })
+            .to_string(),
+        ),
     )
     .await?;
     Ok(updated)
@@ -263,7 +516,57 @@ pub async fn request_mutation_revision(
     state: State<'_, AppState>,
     input: RequestMutationRevisionInput,
 ) -> Result<MutationRevisionResult, String> {
-    mutation_revision::request_mutation_revision(&state.db_pool, &state.model_registry, input).await
+    let model_registry = current_model_registry(&state)?;
+    mutation_revision::request_mutation_revision(&state.db_pool, &model_registry, input).await
+}
+
+#[tauri::command]
+pub async fn edit_mutation_diff(
+    state: State<'_, AppState>,
+    input: EditMutationDiffInput,
+) -> Result<MutationRecord, String> {
+    mutation_revision::edit_mutation_diff(&state.db_pool, input).await
+}
+
+#[tauri::command]
+pub async fn finalize_branch(
+    state: State<'_, AppState>,
+    input: FinalizeBranchInput,
+) -> Result<FinalizeBranchResult, String> {
+    task_branch::finalize_branch(&state.db_pool, input).await
+}
+
+#[tauri::command]
+pub async fn open_pull_request(
+    state: State<'_, AppState>,
+    input: OpenPullRequestInput,
+) -> Result<OpenPullRequestResult, String> {
+    let provider = pull_request::resolve_provider(
+        &input.target_project,
+        input.remote.as_deref(),
+        input.provider,
+    )
+    .await?;
+    let token = {
+        let mut vault = state.secret_vault.lock().await;
+        vault.read_secret(provider.as_str(), "pull_request")?
+    }
+    .ok_or_else(|| {
+        format!(
+            "No '{}' token configured in the secret vault.",
+            provider.as_str()
+        )
+    })?;
+
+    pull_request::open_pull_request(
+        &state.db_pool,
+        OpenPullRequestInput {
+            provider: Some(provider),
+            ..input
+        },
+        &token,
+    )
+    .await
 }
 
 #[tauri::command]
@@ -311,12 +614,13 @@ pub async fn list_target_dir(
     input: ListTargetDirInput,
 ) -> Result<DirectoryListing, String> {
     let mcp_server = input
-        .mcp_command
+        .mcp_server
         .clone()
+        .or_else(|| input.mcp_command.clone())
         .unwrap_or_else(|| "local".to_string());
     let tool = "list_dir";
     let started_at = Instant::now();
-    let result = tool_caller::list_dir(&state.bridge_client, input.clone()).await;
+    let result = tool_caller::list_dir(&state.db_pool, &state.bridge_client, input.clone()).await;
     let elapsed = started_at.elapsed().as_millis() as i64;
 
     let (status, message, payload) = match &result {
@@ -365,12 +669,13 @@ pub async fn read_target_file(
     input: ReadTargetFileInput,
 ) -> Result<TargetFileContent, String> {
     let mcp_server = input
-        .mcp_command
+        .mcp_server
         .clone()
+        .or_else(|| input.mcp_command.clone())
         .unwrap_or_else(|| "local".to_string());
     let tool = "read_file";
     let started_at = Instant::now();
-    let result = tool_caller::read_file(&state.bridge_client, input.clone()).await;
+    let result = tool_caller::read_file(&state.db_pool, &state.bridge_client, input.clone()).await;
     let elapsed = started_at.elapsed().as_millis() as i64;
 
     let (status, message, payload) = match &result {
@@ -420,12 +725,14 @@ pub async fn search_target_files(
     input: SearchTargetFilesInput,
 ) -> Result<SearchResult, String> {
     let mcp_server = input
-        .mcp_command
+        .mcp_server
         .clone()
+        .or_else(|| input.mcp_command.clone())
         .unwrap_or_else(|| "local".to_string());
     let tool = "search_files";
     let started_at = Instant::now();
-    let result = tool_caller::search_files(&state.bridge_client, input.clone()).await;
+    let result =
+        tool_caller::search_files(&state.db_pool, &state.bridge_client, input.clone()).await;
     let elapsed = started_at.elapsed().as_millis() as i64;
 
     let (status, message, payload) = match &result {
@@ -472,12 +779,188 @@ pub async fn search_target_files(
     result
 }
 
+#[tauri::command]
+pub async fn write_target_file(
+    state: State<'_, AppState>,
+    input: WriteTargetFileInput,
+) -> Result<WriteResult, String> {
+    let mcp_server = input
+        .mcp_server
+        .clone()
+        .or_else(|| input.mcp_command.clone())
+        .unwrap_or_else(|| "local".to_string());
+    let started_at = Instant::now();
+    let result = tool_caller::write_file(&state.bridge_client, input).await;
+    record_target_write(&state, "write_file", &mcp_server, started_at, &result).await;
+    result
+}
+
+#[tauri::command]
+pub async fn apply_target_patch(
+    state: State<'_, AppState>,
+    input: ApplyTargetPatchInput,
+) -> Result<WriteResult, String> {
+    let mcp_server = input
+        .mcp_server
+        .clone()
+        .or_else(|| input.mcp_command.clone())
+        .unwrap_or_else(|| "local".to_string());
+    let started_at = Instant::now();
+    let result = tool_caller::apply_patch(&state.bridge_client, input).await;
+    record_target_write(&state, "apply_patch", &mcp_server, started_at, &result).await;
+    result
+}
+
+/// Traces a bridge write like the read tools and audits writes that changed files.
+async fn record_target_write(
+    state: &AppState,
+    tool: &str,
+    mcp_server: &str,
+    started_at: Instant,
+    result: &Result<WriteResult, String>,
+) {
+    let elapsed = started_at.elapsed().as_millis() as i64;
+    let (status, message, payload) = match result {
+        Ok(value) => (
+            "completed",
+            format!(
+                "source={} paths={} dryRun={}",
+                value.source,
+                value.paths.len(),
+                value.dry_run
+            ),
+            serde_json::json!({
+                "tool": tool,
+                "source": value.source,
+                "paths": value.paths,
+                "dryRun": value.dry_run,
+                "warnings": sanitize_mcp_warnings(&value.warnings),
+            }),
+        ),
+        Err(error) => (
+            "failed",
+            error.clone(),
+            serde_json::json!({
+                "tool": tool,
+                "error": sanitize_mcp_text(error),
+            }),
+        ),
+    };
+
+    let _ = telemetry::record_agent_event(
+        &state.db_pool,
+        telemetry::NewAgentEvent {
+            actor: "mcp_bridge".to_string(),
+            action: "mcp_call".to_string(),
+            status: Some(status.to_string()),
+            phase: Some("io".to_string()),
+            message: Some(message),
+            mcp_server: Some(sanitize_mcp_text(mcp_server)),
+            mcp_tool: Some(tool.to_string()),
+            latency_ms: Some(elapsed),
+            payload: Some(payload),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    if let Some(value) = result.as_ref().ok().filter(|value| value.applied) {
+        let _ = metrics::record_audit_event(
+            &state.db_pool,
+            "ui",
+            "target_files_written",
+            None,
+            Some(
+                &serde_json::json!({
+                    "tool": tool,
+                    "server": sanitize_mcp_text(mcp_server),
+                    "paths": value.paths,
+                })
+                .to_string(),
+            ),
+        )
+        .await;
+    }
+}
+
+#[tauri::command]
+pub async fn run_target_command(
+    state: State<'_, AppState>,
+    input: RunTargetCommandInput,
+) -> Result<CommandResult, String> {
+    command_runner::run_command(&state.db_pool, &state.bridge_client, input).await
+}
+
 #[tauri::command]
 pub async fn index_target_project(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    input: IndexProjectInput,
+) -> Result<IndexJobStatus, String> {
+    let mut jobs = state.index_jobs.lock().await;
+    jobs.start(
+        state.db_pool.clone(),
+        &input.target_project,
+        Arc::new(move |status: &IndexJobStatus| {
+            let _ = app.emit(jobs::INDEX_JOB_EVENT, status);
+        }),
+    )
+}
+
+#[tauri::command]
+pub async fn get_index_job_status(
+    state: State<'_, AppState>,
+    input: IndexJobInput,
+) -> Result<IndexJobStatus, String> {
+    state.index_jobs.lock().await.status(&input.job_id)
+}
+
+#[tauri::command]
+pub async fn cancel_index_job(
+    state: State<'_, AppState>,
+    input: IndexJobInput,
+) -> Result<IndexJobStatus, String> {
+    state.index_jobs.lock().await.cancel(&input.job_id)
+}
+
+#[tauri::command]
+pub async fn list_indexed_projects(
+    state: State<'_, AppState>,
+) -> Result<Vec<IndexedProjectStats>, String> {
+    let mut projects = projects::list_indexed_projects(&state.db_pool).await?;
+    let watchers = state.index_watchers.lock().await;
+    for project in &mut projects {
+        project.watching = watchers.is_watching(&project.target_project);
+    }
+    Ok(projects)
+}
+
+#[tauri::command]
+pub async fn get_project_index_stats(
+    state: State<'_, AppState>,
+    input: IndexProjectInput,
+) -> Result<IndexedProjectStats, String> {
+    let mut stats = projects::project_index_stats(&state.db_pool, &input.target_project).await?;
+    stats.watching = state
+        .index_watchers
+        .lock()
+        .await
+        .is_watching(&stats.target_project);
+    Ok(stats)
+}
+
+#[tauri::command]
+pub async fn delete_project_index(
     state: State<'_, AppState>,
     input: IndexProjectInput,
-) -> Result<IndexProjectResult, String> {
-    indexer::index_project(&state.db_pool, &input.target_project).await
+) -> Result<DeleteProjectIndexResult, String> {
+    // A running watcher would re-create the index on the next change.
+    let _ = state
+        .index_watchers
+        .lock()
+        .await
+        .stop(&input.target_project);
+    projects::delete_project_index(&state.db_pool, &input.target_project).await
 }
 
 #[tauri::command]
@@ -490,47 +973,527 @@ pub async fn query_codebase(
         &input.target_project,
         &input.query,
         input.top_k.unwrap_or(5),
+        &input.filters,
     )
     .await
 }
 
 #[tauri::command]
-pub async fn get_model_registry(
+pub async fn search_symbols(
     state: State<'_, AppState>,
-) -> Result<ModelRegistrySnapshot, String> {
-    Ok(state.model_registry.snapshot())
+    input: SearchSymbolsInput,
+) -> Result<SymbolSearchResult, String> {
+    symbols::search_symbols(
+        &state.db_pool,
+        &input.target_project,
+        &input.symbol,
+        input.limit,
+    )
+    .await
 }
 
 #[tauri::command]
-pub async fn list_agent_runs(
+pub async fn index_git_history(
     state: State<'_, AppState>,
-    input: ListAgentRunsInput,
-) -> Result<Vec<AgentRunRecord>, String> {
-    telemetry::list_agent_runs(&state.db_pool, input).await
+    input: IndexProjectInput,
+) -> Result<IndexGitHistoryResult, String> {
+    git_history::index_git_history(&state.db_pool, &input.target_project).await
 }
 
 #[tauri::command]
-pub async fn list_agent_events(
+pub async fn query_git_history(
     state: State<'_, AppState>,
-    input: ListAgentEventsInput,
-) -> Result<Vec<AgentEventRecord>, String> {
-    telemetry::list_agent_events(&state.db_pool, input).await
+    input: QueryGitHistoryInput,
+) -> Result<Vec<GitHistoryEntry>, String> {
+    git_history::query_git_history(
+        &state.db_pool,
+        &input.target_project,
+        &input.query,
+        &input.target_files,
+        input.limit.unwrap_or(10),
+    )
+    .await
 }
 
 #[tauri::command]
-pub async fn get_mission_control_snapshot(
+pub async fn start_index_watch(
     state: State<'_, AppState>,
-    input: GetMissionControlSnapshotInput,
-) -> Result<MissionControlSnapshot, String> {
-    telemetry::get_mission_control_snapshot(&state.db_pool, input).await
+    input: IndexWatchInput,
+) -> Result<IndexWatchStatus, String> {
+    let mut watchers = state.index_watchers.lock().await;
+    watchers.start(state.db_pool.clone(), &input.target_project)
 }
 
 #[tauri::command]
-pub async fn control_execution_scope(
+pub async fn stop_index_watch(
     state: State<'_, AppState>,
-    input: ControlExecutionScopeInput,
-) -> Result<Vec<TaskRecord>, String> {
-    let root_task_id = input.root_task_id.trim();
+    input: IndexWatchInput,
+) -> Result<IndexWatchStatus, String> {
+    let mut watchers = state.index_watchers.lock().await;
+    watchers.stop(&input.target_project)
+}
+
+#[tauri::command]
+pub async fn get_model_registry(
+    state: State<'_, AppState>,
+) -> Result<ModelRegistrySnapshot, String> {
+    Ok(current_model_registry(&state)?.snapshot())
+}
+
+#[tauri::command]
+pub async fn reload_model_registry(
+    state: State<'_, AppState>,
+) -> Result<ModelRegistrySnapshot, String> {
+    replace_model_registry(&state, |registry| {
+        let reloaded = registry.reload();
+        match reloaded.snapshot().load_error {
+            Some(error) => Err(error),
+            None => Ok(reloaded),
+        }
+    })
+}
+
+#[tauri::command]
+pub async fn add_registry_model(
+    state: State<'_, AppState>,
+    input: AddRegistryModelInput,
+) -> Result<ModelRegistrySnapshot, String> {
+    replace_model_registry(&state, |registry| registry.add_model(input))
+}
+
+#[tauri::command]
+pub async fn update_registry_model(
+    state: State<'_, AppState>,
+    input: UpdateRegistryModelInput,
+) -> Result<ModelRegistrySnapshot, String> {
+    replace_model_registry(&state, |registry| registry.update_model(input))
+}
+
+#[tauri::command]
+pub async fn remove_registry_model(
+    state: State<'_, AppState>,
+    input: RemoveRegistryModelInput,
+) -> Result<ModelRegistrySnapshot, String> {
+    replace_model_registry(&state, |registry| registry.remove_model(input))
+}
+
+#[tauri::command]
+pub async fn discover_provider_models(
+    state: State<'_, AppState>,
+    input: DiscoverProviderModelsInput,
+) -> Result<DiscoverProviderModelsResult, String> {
+    let registry = current_model_registry(&state)?;
+    let pool = state.db_pool.clone();
+    let mut result =
+        tokio::task::spawn_blocking(move || model_discovery::discover(&registry, &pool))
+            .await
+            .map_err(|error| format!("Model discovery task failed: {error}"))?;
+    if input.merge {
+        replace_model_registry(&state, |registry| {
+            let additions = model_discovery::catalog_additions(registry, &result);
+            result.merged = additions.len();
+            if additions.is_empty() {
+                return Ok(registry.clone());
+            }
+            registry.add_catalog_models(additions)
+        })?;
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn benchmark_models(
+    state: State<'_, AppState>,
+    input: BenchmarkModelsInput,
+) -> Result<BenchmarkModelsResult, String> {
+    let model_registry = current_model_registry(&state)?;
+    model_benchmark::benchmark_models(&state.db_pool, &state.secret_vault, &model_registry, input)
+        .await
+}
+
+#[tauri::command]
+pub async fn list_model_arms(
+    state: State<'_, AppState>,
+    input: ListModelArmsInput,
+) -> Result<Vec<ModelArmRecord>, String> {
+    model_arms::list_model_arms(&state.db_pool, input).await
+}
+
+#[tauri::command]
+pub async fn list_model_pins(state: State<'_, AppState>) -> Result<Vec<ModelPinRecord>, String> {
+    model_arms::list_model_pins(&state.db_pool).await
+}
+
+#[tauri::command]
+pub async fn set_model_pin(
+    state: State<'_, AppState>,
+    input: SetModelPinInput,
+) -> Result<Option<ModelPinRecord>, String> {
+    model_arms::set_model_pin(&state.db_pool, input).await
+}
+
+#[tauri::command]
+pub async fn create_model_experiment(
+    state: State<'_, AppState>,
+    input: CreateModelExperimentInput,
+) -> Result<ModelExperimentRecord, String> {
+    let model_registry = current_model_registry(&state)?;
+    for (provider, model_id) in [
+        (&input.control_provider, &input.control_model_id),
+        (&input.variant_provider, &input.variant_model_id),
+    ] {
+        if model_registry.known_profile(provider, model_id).is_none() {
+            return Err(format!(
+                "Model {}/{} is not in models.json",
+                provider.trim(),
+                model_id.trim()
+            ));
+        }
+    }
+    model_experiments::create_model_experiment(&state.db_pool, input).await
+}
+
+#[tauri::command]
+pub async fn list_model_experiments(
+    state: State<'_, AppState>,
+) -> Result<Vec<ModelExperimentRecord>, String> {
+    model_experiments::list_model_experiments(&state.db_pool).await
+}
+
+#[tauri::command]
+pub async fn stop_model_experiment(
+    state: State<'_, AppState>,
+    input: ModelExperimentIdInput,
+) -> Result<ModelExperimentRecord, String> {
+    model_experiments::stop_model_experiment(&state.db_pool, input).await
+}
+
+#[tauri::command]
+pub async fn compare_model_experiment(
+    state: State<'_, AppState>,
+    input: ModelExperimentIdInput,
+) -> Result<ModelExperimentComparison, String> {
+    model_experiments::compare_model_experiment(&state.db_pool, input).await
+}
+
+#[tauri::command]
+pub async fn get_prompt_templates(
+    input: GetPromptTemplatesInput,
+) -> Result<Vec<PromptTemplate>, String> {
+    prompt_templates::get_prompt_templates(input)
+}
+
+#[tauri::command]
+pub async fn set_prompt_template(input: SetPromptTemplateInput) -> Result<PromptTemplate, String> {
+    prompt_templates::set_prompt_template(input)
+}
+
+#[tauri::command]
+pub async fn list_mcp_servers() -> Result<Vec<McpServerConfig>, String> {
+    mcp_servers::list_servers()
+}
+
+#[tauri::command]
+pub async fn save_mcp_server(
+    state: State<'_, AppState>,
+    input: SaveMcpServerInput,
+) -> Result<McpServerConfig, String> {
+    let server = mcp_servers::save_server(input)?;
+    metrics::record_audit_event(
+        &state.db_pool,
+        "ui",
+        "mcp_server_saved",
+        Some(server.name.as_str()),
+        Some(
+            &serde_json::json!({
+                "transport": server.transport,
+                "command": server.command,
+                "url": server.url,
+                "allowedTools": server.allowed_tools,
+                "allowedDomains": server.allowed_domains,
+            })
+            .to_string(),
+        ),
+    )
+    .await?;
+
+    Ok(server)
+}
+
+#[tauri::command]
+pub async fn remove_mcp_server(
+    state: State<'_, AppState>,
+    input: RemoveMcpServerInput,
+) -> Result<bool, String> {
+    let name = input.name.trim().to_string();
+    let removed = mcp_servers::remove_server(input)?;
+    if removed {
+        metrics::record_audit_event(
+            &state.db_pool,
+            "ui",
+            "mcp_server_removed",
+            Some(name.as_str()),
+            None,
+        )
+        .await?;
+    }
+
+    Ok(removed)
+}
+
+#[tauri::command]
+pub async fn list_mcp_tools(
+    state: State<'_, AppState>,
+    input: ListMcpToolsInput,
+) -> Result<McpToolCatalog, String> {
+    tool_cache::list_tools(&state.bridge_client, input).await
+}
+
+#[tauri::command]
+pub async fn subscribe_mcp_resources(
+    state: State<'_, AppState>,
+    input: SubscribeMcpResourcesInput,
+) -> Result<McpResourceSubscription, String> {
+    let subscription =
+        resources::subscribe(&state.bridge_client, &state.mcp_resources, input).await?;
+    metrics::record_audit_event(
+        &state.db_pool,
+        "ui",
+        "mcp_resources_subscribed",
+        Some(subscription.server.as_str()),
+        Some(
+            &serde_json::json!({
+                "uris": subscription.uris.len(),
+                "targetProject": subscription.target_project,
+                "reindex": subscription.reindex,
+            })
+            .to_string(),
+        ),
+    )
+    .await?;
+
+    Ok(subscription)
+}
+
+#[tauri::command]
+pub async fn unsubscribe_mcp_resources(
+    state: State<'_, AppState>,
+    input: UnsubscribeMcpResourcesInput,
+) -> Result<bool, String> {
+    let server = input.mcp_server.trim().to_string();
+    let removed = resources::unsubscribe(&state.bridge_client, &state.mcp_resources, input).await?;
+    if removed {
+        metrics::record_audit_event(
+            &state.db_pool,
+            "ui",
+            "mcp_resources_unsubscribed",
+            Some(server.as_str()),
+            None,
+        )
+        .await?;
+    }
+
+    Ok(removed)
+}
+
+#[tauri::command]
+pub async fn list_mcp_resource_subscriptions(
+    state: State<'_, AppState>,
+) -> Result<Vec<McpResourceSubscription>, String> {
+    Ok(resources::list_subscriptions(&state.mcp_resources).await)
+}
+
+#[tauri::command]
+pub async fn list_agent_runs(
+    state: State<'_, AppState>,
+    input: ListAgentRunsInput,
+) -> Result<Vec<AgentRunRecord>, String> {
+    if input.include_archived.unwrap_or(false) {
+        let archive_root = state.app_data_dir.join("telemetry-archive");
+        return telemetry_archive::list_agent_runs_with_archive(
+            &state.db_reader,
+            archive_root.as_path(),
+            input,
+        )
+        .await;
+    }
+    telemetry::list_agent_runs(&state.db_reader, input).await
+}
+
+#[tauri::command]
+pub async fn list_agent_events(
+    state: State<'_, AppState>,
+    input: ListAgentEventsInput,
+) -> Result<Vec<AgentEventRecord>, String> {
+    if input.include_archived.unwrap_or(false) {
+        let archive_root = state.app_data_dir.join("telemetry-archive");
+        return telemetry_archive::list_agent_events_with_archive(
+            &state.db_reader,
+            archive_root.as_path(),
+            input,
+        )
+        .await;
+    }
+    telemetry::list_agent_events(&state.db_reader, input).await
+}
+
+#[tauri::command]
+pub async fn search_events(
+    state: State<'_, AppState>,
+    input: SearchEventsInput,
+) -> Result<Vec<EventSearchHit>, String> {
+    event_search::search_events(&state.db_reader, input).await
+}
+
+#[tauri::command]
+pub async fn export_task_trace(
+    state: State<'_, AppState>,
+    input: ExportTaskTraceInput,
+) -> Result<ExportTaskTraceResult, String> {
+    otlp_export::export_task_trace(&state.db_pool, input).await
+}
+
+#[tauri::command]
+pub async fn page_agent_runs(
+    state: State<'_, AppState>,
+    input: ListAgentRunsInput,
+) -> Result<AgentRunPage, String> {
+    telemetry::page_agent_runs(&state.db_reader, input).await
+}
+
+#[tauri::command]
+pub async fn page_agent_events(
+    state: State<'_, AppState>,
+    input: ListAgentEventsInput,
+) -> Result<AgentEventPage, String> {
+    telemetry::page_agent_events(&state.db_reader, input).await
+}
+
+#[tauri::command]
+pub async fn list_llm_transcripts(
+    state: State<'_, AppState>,
+    input: ListLlmTranscriptsInput,
+) -> Result<Vec<LlmTranscriptRecord>, String> {
+    llm_transcript::list_llm_transcripts(&state.db_pool, input).await
+}
+
+#[tauri::command]
+pub async fn export_llm_transcripts(
+    state: State<'_, AppState>,
+    input: ExportLlmTranscriptsInput,
+) -> Result<ExportLlmTranscriptsResult, String> {
+    llm_transcript::export_llm_transcripts(&state.db_pool, input).await
+}
+
+#[tauri::command]
+pub async fn get_task_cost_rollup(
+    state: State<'_, AppState>,
+    input: GetTaskCostRollupInput,
+) -> Result<TaskCostRollup, String> {
+    telemetry::get_task_cost_rollup(&state.db_reader, input).await
+}
+
+#[tauri::command]
+pub async fn get_task_timeline(
+    state: State<'_, AppState>,
+    input: GetTaskTimelineInput,
+) -> Result<TaskTimeline, String> {
+    timeline::get_task_timeline(&state.db_reader, input).await
+}
+
+#[tauri::command]
+pub async fn get_mission_control_snapshot(
+    state: State<'_, AppState>,
+    input: GetMissionControlSnapshotInput,
+) -> Result<MissionControlSnapshot, String> {
+    telemetry::get_mission_control_snapshot(&state.db_reader, input).await
+}
+
+#[tauri::command]
+pub async fn recover_stalled_run(
+    state: State<'_, AppState>,
+    input: RecoverStalledRunInput,
+) -> Result<Vec<TaskRecord>, String> {
+    let run_id = input.run_id.clone();
+    let action = input.action.as_str();
+    let updated = task_runtime::recover_stalled_run(&state.db_pool, input).await?;
+    metrics::record_audit_event(
+        &state.db_pool,
+        "ui",
+        "stalled_run_recovered",
+        updated.first().map(|task| task.id.as_str()),
+        Some(
+            &serde_json::json!({ "runId": run_id, "action": action, "updated": updated.len() })
+                .to_string(),
+        ),
+    )
+    .await?;
+
+    Ok(updated)
+}
+
+/// Pushes new telemetry as [`telemetry_stream::MISSION_CONTROL_EVENT`] events until
+/// unsubscribed.
+#[tauri::command]
+pub async fn subscribe_mission_control(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    input: SubscribeMissionControlInput,
+) -> Result<MissionControlSubscription, String> {
+    let mut subscriptions = state.telemetry_subscriptions.lock().await;
+    subscriptions
+        .subscribe(
+            state.db_reader.clone(),
+            input,
+            Arc::new(move |update: &MissionControlUpdate| {
+                let _ = app.emit(telemetry_stream::MISSION_CONTROL_EVENT, update);
+            }),
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn unsubscribe_mission_control(
+    state: State<'_, AppState>,
+    input: MissionControlSubscriptionInput,
+) -> Result<(), String> {
+    state
+        .telemetry_subscriptions
+        .lock()
+        .await
+        .unsubscribe(&input.subscription_id)
+}
+
+#[tauri::command]
+pub async fn get_model_health_trends(
+    state: State<'_, AppState>,
+    input: GetModelHealthTrendsInput,
+) -> Result<ModelHealthTrends, String> {
+    telemetry::get_model_health_trends(&state.db_reader, input).await
+}
+
+#[tauri::command]
+pub async fn get_cost_breakdown(
+    state: State<'_, AppState>,
+    input: GetCostBreakdownInput,
+) -> Result<CostBreakdown, String> {
+    analytics::get_cost_breakdown(&state.db_reader, input).await
+}
+
+#[tauri::command]
+pub async fn get_token_usage_trends(
+    state: State<'_, AppState>,
+    input: GetTokenUsageTrendsInput,
+) -> Result<TokenUsageTrends, String> {
+    analytics::get_token_usage_trends(&state.db_reader, input).await
+}
+
+#[tauri::command]
+pub async fn control_execution_scope(
+    state: State<'_, AppState>,
+    input: ControlExecutionScopeInput,
+) -> Result<Vec<TaskRecord>, String> {
+    let root_task_id = input.root_task_id.trim();
     if root_task_id.is_empty() {
         return Err("rootTaskId is required".to_string());
     }
@@ -548,14 +1511,7 @@ pub async fn control_execution_scope(
             let target_tier = input
                 .tier
                 .ok_or_else(|| "tier is required when scopeType='tier'".to_string())?;
-            let mut ids = Vec::new();
-            for task_id in tree_ids {
-                let task = tasks::get_task_by_id(&state.db_pool, task_id.as_str()).await?;
-                if task.tier == target_tier {
-                    ids.push(task.id);
-                }
-            }
-            ids
+            tasks::collect_task_subtree_ids(&state.db_pool, root_task_id, Some(target_tier)).await?
         }
         "agent" => {
             let task_id = input
@@ -653,14 +1609,126 @@ pub async fn set_runtime_flags(
         .runtime_flags
         .write()
         .map_err(|error| format!("Failed to update runtime flags: {error}"))?;
+    let restart_before = startup_flags(&guard);
     guard.apply_update(input);
     guard.sync_to_process_env();
+    runtime_config::save(&guard, None)?;
     Ok(RuntimeFlagsUpdateResult {
         flags: guard.clone(),
-        restart_required: false,
+        restart_required: restart_before != startup_flags(&guard),
     })
 }
 
+/// Switches to a named profile such as `cautious` or `fast-and-cheap`, or with
+/// `saveCurrent` saves the current flags as one.
+#[tauri::command]
+pub async fn set_runtime_profile(
+    state: State<'_, AppState>,
+    input: SetRuntimeProfileInput,
+) -> Result<RuntimeFlagsUpdateResult, String> {
+    let mut guard = state
+        .runtime_flags
+        .write()
+        .map_err(|error| format!("Failed to update runtime flags: {error}"))?;
+    if input.save_current.unwrap_or(false) {
+        runtime_config::save_profile(&guard, &input.name)?;
+        return Ok(RuntimeFlagsUpdateResult {
+            flags: guard.clone(),
+            restart_required: false,
+        });
+    }
+    let restart_before = startup_flags(&guard);
+    let mut flags = guard.clone();
+    runtime_config::apply_profile(&mut flags, &input.name)?;
+    *guard = flags;
+    guard.sync_to_process_env();
+    Ok(RuntimeFlagsUpdateResult {
+        flags: guard.clone(),
+        restart_required: restart_before != startup_flags(&guard),
+    })
+}
+
+#[tauri::command]
+pub async fn list_runtime_profiles() -> Result<Vec<RuntimeProfile>, String> {
+    runtime_config::list_profiles()
+}
+
+/// Bundles runtime flags, model routing, saved prompt templates and MCP servers for
+/// sharing. Secret values are never included.
+#[tauri::command]
+pub async fn export_configuration(
+    state: State<'_, AppState>,
+) -> Result<ConfigurationBundle, String> {
+    let flags = state
+        .runtime_flags
+        .read()
+        .map(|flags| flags.clone())
+        .map_err(|error| format!("Failed to read runtime flags: {error}"))?;
+    let routing = current_model_registry(&state)?.snapshot().config;
+    let bundle = config_bundle::export(&flags, routing)?;
+
+    metrics::record_audit_event(
+        &state.db_pool,
+        "ui",
+        "configuration_exported",
+        None,
+        Some(&serde_json::json!({ "omitted": bundle.omitted }).to_string()),
+    )
+    .await?;
+    Ok(bundle)
+}
+
+/// Applies a bundle from `export_configuration`. Flags tied to this machine keep their
+/// values, and settings the bundle omitted must be filled in here.
+#[tauri::command]
+pub async fn import_configuration(
+    state: State<'_, AppState>,
+    input: ImportConfigurationInput,
+) -> Result<ImportConfigurationResult, String> {
+    let bundle = input.bundle;
+    let (runtime_flags, restart_required) = {
+        let mut guard = state
+            .runtime_flags
+            .write()
+            .map_err(|error| format!("Failed to update runtime flags: {error}"))?;
+        let (flags, changed) = config_bundle::merge_runtime_flags(&guard, &bundle)?;
+        let restart_before = startup_flags(&guard);
+        *guard = flags;
+        guard.sync_to_process_env();
+        runtime_config::save(&guard, None)?;
+        (changed, restart_before != startup_flags(&guard))
+    };
+    let model_routing = match bundle.model_routing.clone() {
+        Some(routing) => {
+            replace_model_registry(&state, |registry| registry.replace_config(routing))?;
+            true
+        }
+        None => false,
+    };
+    let (prompt_templates, mcp_servers) = config_bundle::import_stores(&bundle)?;
+    let result = ImportConfigurationResult {
+        runtime_flags,
+        model_routing,
+        prompt_templates,
+        mcp_servers,
+        omitted: bundle.omitted,
+        restart_required,
+    };
+
+    metrics::record_audit_event(
+        &state.db_pool,
+        "ui",
+        "configuration_imported",
+        None,
+        Some(
+            &serde_json::to_string(&result)
+                .map_err(|error| format!("Failed to encode import result: {error}"))?,
+        ),
+    )
+    .await?;
+    Ok(result)
+}
+
 #[tauri::command]
 pub async fn get_provider_secret_status(
     state: State<'_, AppState>,
@@ -671,8 +1739,9 @@ pub async fn get_provider_secret_status(
         .read()
         .map(|flags| flags.dev_mode)
         .unwrap_or(false);
+    let reference = secret_vault::credential_reference(&input.provider, input.name.as_deref())?;
     let mut vault = state.secret_vault.lock().await;
-    vault.get_status(input.provider.as_str(), developer_mode)
+    vault.get_status(reference.as_str(), developer_mode)
 }
 
 #[tauri::command]
@@ -685,9 +1754,10 @@ pub async fn set_provider_secret(
         .read()
         .map(|flags| flags.dev_mode)
         .unwrap_or(false);
+    let reference = secret_vault::credential_reference(&input.provider, input.name.as_deref())?;
     let mut vault = state.secret_vault.lock().await;
     vault.set_secret(
-        input.provider.as_str(),
+        reference.as_str(),
         input.secret.as_str(),
         developer_mode,
         input.session_token.as_deref(),
@@ -704,12 +1774,154 @@ pub async fn reveal_provider_secret(
         .read()
         .map(|flags| flags.dev_mode)
         .unwrap_or(false);
+    let reference = secret_vault::credential_reference(&input.provider, input.name.as_deref())?;
     let mut vault = state.secret_vault.lock().await;
-    vault.reveal_secret(
-        input.provider.as_str(),
+    let result = vault.reveal_secret(
+        reference.as_str(),
         developer_mode,
         input.session_token.as_deref(),
+    );
+    let outcome = if result.is_ok() {
+        "revealed"
+    } else {
+        "refused"
+    };
+    secret_vault::record_access(&state.db_pool, "ui", &reference, "reveal", outcome);
+    result
+}
+
+#[tauri::command]
+pub async fn list_provider_credentials(
+    state: State<'_, AppState>,
+    input: ListProviderCredentialsInput,
+) -> Result<Vec<ProviderCredential>, String> {
+    let mut vault = state.secret_vault.lock().await;
+    vault.list_credentials(input.provider.as_deref())
+}
+
+/// Every read of a stored or environment key, by the component that made it.
+#[tauri::command]
+pub async fn list_secret_access_log(
+    state: State<'_, AppState>,
+    input: ListSecretAccessLogInput,
+) -> Result<Vec<AuditLogEntry>, String> {
+    metrics::list_secret_access_log(&state.db_pool, input).await
+}
+
+/// Replaces a stored key once the new one passes a test call against the provider, so a
+/// bad key never retires a working one.
+#[tauri::command]
+pub async fn rotate_provider_secret(
+    state: State<'_, AppState>,
+    input: RotateProviderSecretInput,
+) -> Result<SecretRotationResult, String> {
+    let reference = secret_vault::credential_reference(&input.provider, input.name.as_deref())?;
+    let validated = !input.skip_validation.unwrap_or(false);
+    if validated {
+        provider_keys::check_key(&reference, &input.secret)
+            .await
+            .map_err(|error| format!("New key for '{reference}' was not stored: {error}"))?;
+    }
+    state
+        .secret_vault
+        .lock()
+        .await
+        .replace_secret(&reference, &input.secret)?;
+
+    metrics::record_audit_event(
+        &state.db_pool,
+        "ui",
+        "provider_secret_rotated",
+        None,
+        Some(&serde_json::json!({ "provider": reference, "validated": validated }).to_string()),
     )
+    .await?;
+    Ok(SecretRotationResult {
+        provider: reference,
+        validated,
+    })
+}
+
+/// Tests a key with a minimal authenticated call, so a bad key shows up at setup rather
+/// than mid-run. Tests the stored key unless `secret` is given.
+#[tauri::command]
+pub async fn validate_provider_secret(
+    state: State<'_, AppState>,
+    input: ValidateProviderSecretInput,
+) -> Result<ProviderSecretValidation, String> {
+    let reference = secret_vault::credential_reference(&input.provider, input.name.as_deref())?;
+    let secret = match input.secret.filter(|secret| !secret.trim().is_empty()) {
+        Some(secret) => secret,
+        None => state
+            .secret_vault
+            .lock()
+            .await
+            .read_secret(&reference, "ui")?
+            .ok_or_else(|| format!("No secret configured for provider '{reference}'"))?,
+    };
+    let validation = provider_keys::probe_key(&reference, &secret).await?;
+
+    metrics::record_audit_event(
+        &state.db_pool,
+        "ui",
+        "provider_secret_validated",
+        None,
+        Some(
+            &serde_json::json!({
+                "provider": reference,
+                "valid": validation.valid,
+                "status": validation.status,
+            })
+            .to_string(),
+        ),
+    )
+    .await?;
+    Ok(validation)
+}
+
+/// Moves every stored secret to another backend and switches `secretBackend` to it.
+#[tauri::command]
+pub async fn migrate_secret_backend(
+    state: State<'_, AppState>,
+    input: MigrateSecretBackendInput,
+) -> Result<SecretMigrationResult, String> {
+    let target = SecretBackend::parse(&input.backend).ok_or_else(|| {
+        format!(
+            "Unknown secret backend '{}' (expected stronghold or keychain)",
+            input.backend
+        )
+    })?;
+    let mut vault = state.secret_vault.lock().await;
+    let result = vault.migrate_to(target)?;
+    {
+        // Switch while the vault is still locked so no read lands on the emptied backend.
+        let mut flags = state
+            .runtime_flags
+            .write()
+            .map_err(|error| format!("Failed to update runtime flags: {error}"))?;
+        flags.secret_backend = target.as_str().to_string();
+        flags.sync_to_process_env();
+        runtime_config::save(&flags, None)?;
+    }
+    drop(vault);
+
+    metrics::record_audit_event(
+        &state.db_pool,
+        "ui",
+        "secret_backend_migrated",
+        None,
+        Some(
+            &serde_json::json!({
+                "from": result.from,
+                "to": result.to,
+                "migrated": result.migrated.len(),
+                "leftInSource": result.left_in_source,
+            })
+            .to_string(),
+        ),
+    )
+    .await?;
+    Ok(result)
 }
 
 #[tauri::command]
@@ -730,6 +1942,90 @@ pub async fn archive_telemetry(
     .await
 }
 
+#[tauri::command]
+pub async fn import_telemetry_archive(
+    state: State<'_, AppState>,
+    input: ImportTelemetryArchiveInput,
+) -> Result<ImportTelemetryArchiveResult, String> {
+    telemetry_archive::import_telemetry_archive(
+        &state.db_pool,
+        state.app_data_dir.join("telemetry-archive").as_path(),
+        input,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn backup_database(state: State<'_, AppState>) -> Result<DatabaseBackup, String> {
+    backup::backup_database(&state.db_pool, state.app_data_dir.join("backups").as_path()).await
+}
+
+#[tauri::command]
+pub async fn list_database_backups(
+    state: State<'_, AppState>,
+) -> Result<Vec<DatabaseBackup>, String> {
+    backup::list_database_backups(state.app_data_dir.join("backups").as_path())
+}
+
+#[tauri::command]
+pub async fn restore_database(
+    state: State<'_, AppState>,
+    input: RestoreDatabaseInput,
+) -> Result<RestoreDatabaseResult, String> {
+    backup::restore_database(
+        &state.db_pool,
+        state.app_data_dir.join("backups").as_path(),
+        state.app_data_dir.join("aop_orchestrator.db").as_path(),
+        input,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn check_database_integrity(
+    state: State<'_, AppState>,
+) -> Result<DatabaseIntegrityReport, String> {
+    backup::check_database_integrity(&state.db_pool).await
+}
+
+pub(crate) fn current_model_registry(state: &AppState) -> Result<Arc<ModelRegistry>, String> {
+    state
+        .model_registry
+        .read()
+        .map(|registry| Arc::clone(&registry))
+        .map_err(|error| format!("Failed to read model registry: {error}"))
+}
+
+/// Flags only read at startup: the metrics endpoint binds its port, and the database is
+/// opened, once.
+fn startup_flags(flags: &RuntimeFlags) -> (bool, u32, bool, bool, u32) {
+    (
+        flags.metrics_enabled,
+        flags.metrics_port,
+        flags.db_encryption,
+        flags.http_api_enabled,
+        flags.http_api_port,
+    )
+}
+
+/// Builds the next registry under the write lock, so concurrent edits apply in turn, and
+/// swaps it in along with its provider limits and prices.
+fn replace_model_registry(
+    state: &AppState,
+    next: impl FnOnce(&ModelRegistry) -> Result<ModelRegistry, String>,
+) -> Result<ModelRegistrySnapshot, String> {
+    let mut guard = state
+        .model_registry
+        .write()
+        .map_err(|error| format!("Failed to update model registry: {error}"))?;
+    let registry = next(&guard)?;
+    llm_rate_limit::configure(registry.provider_limits());
+    llm_pricing::configure(registry.model_pricing());
+    let snapshot = registry.snapshot();
+    *guard = Arc::new(registry);
+    Ok(snapshot)
+}
+
 fn parse_task_control_action(action: &str) -> Result<TaskControlAction, String> {
     match action.trim().to_ascii_lowercase().as_str() {
         "pause" => Ok(TaskControlAction::Pause),
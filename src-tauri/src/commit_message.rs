@@ -0,0 +1,421 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::db::mutations::MutationRecord;
+use crate::db::tasks::{self, TaskRecord};
+use crate::llm_adapter::{self, AdapterRequest};
+use crate::model_intelligence::{
+    self, CostPreference, ModelRequirements, ModelSelectionRequest, ModelSelectionResult,
+};
+use crate::model_registry::ModelRegistry;
+
+/// Commit message settings, relative to the target project root.
+pub const COMMIT_CONFIG_PATH: &str = ".aop/commit.toml";
+const DEFAULT_TEMPLATE: &str = "{type}({scope}): {summary}\n\n{body}";
+const COMMIT_TYPES: &[&str] = &[
+    "feat", "fix", "refactor", "perf", "test", "docs", "style", "build", "ci", "chore",
+];
+const MAX_SUMMARY_CHARS: usize = 72;
+const MAX_DIFF_CHARS: usize = 6_000;
+
+/// Per-project commit message settings, e.g.
+///
+/// ```toml
+/// template = "{type}: {summary}\n\nRefs {task_id}\n\n{body}"
+/// use_model = false
+/// ```
+///
+/// Placeholders: `{type}`, `{scope}`, `{summary}`, `{body}`, `{file}`, `{mutation_id}` and
+/// `{task_id}`. `({scope})` is dropped when there is no scope.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommitConfig {
+    #[serde(default = "default_template")]
+    pub template: String,
+    #[serde(default = "default_use_model")]
+    pub use_model: bool,
+}
+
+impl Default for CommitConfig {
+    fn default() -> Self {
+        Self {
+            template: default_template(),
+            use_model: default_use_model(),
+        }
+    }
+}
+
+fn default_template() -> String {
+    DEFAULT_TEMPLATE.to_string()
+}
+
+fn default_use_model() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+struct CommitParts {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    scope: String,
+    summary: String,
+    #[serde(default)]
+    body: String,
+}
+
+pub fn load_commit_config(target_root: &Path) -> Result<CommitConfig, String> {
+    let path = target_root.join(COMMIT_CONFIG_PATH);
+    if !path.exists() {
+        return Ok(CommitConfig::default());
+    }
+    let raw = std::fs::read_to_string(&path)
+        .map_err(|error| format!("Failed to read '{}': {error}", path.display()))?;
+    let config: CommitConfig =
+        toml::from_str(&raw).map_err(|error| format!("Invalid '{COMMIT_CONFIG_PATH}': {error}"))?;
+    if !config.template.contains("{summary}") {
+        return Err(format!(
+            "Invalid '{COMMIT_CONFIG_PATH}': template must contain {{summary}}"
+        ));
+    }
+    Ok(config)
+}
+
+/// Builds a conventional-commit message for an applied mutation. The type, scope and
+/// summary come from a small model call when the adapter is available and fall back to
+/// the mutation's intent otherwise. The root task id is always added as a trailer.
+pub async fn compose_commit_message(
+    pool: &SqlitePool,
+    model_registry: &ModelRegistry,
+    config: &CommitConfig,
+    task: &TaskRecord,
+    mutation: &MutationRecord,
+) -> String {
+    let root_task_id = tasks::resolve_root_task_id(pool, &task.id)
+        .await
+        .unwrap_or_else(|_| task.id.clone());
+    let parts = if config.use_model && model_adapter_enabled() {
+        generate_parts(pool, model_registry, task, mutation)
+            .await
+            .ok()
+            .and_then(normalize_parts)
+    } else {
+        None
+    }
+    .unwrap_or_else(|| heuristic_parts(task, mutation));
+
+    let message = render_template(&config.template, &parts, task, mutation);
+    format!(
+        "{}\n\nAOP-Root-Task: {root_task_id}\nAOP-Mutation: {}",
+        message.trim_end(),
+        mutation.id
+    )
+}
+
+fn model_adapter_enabled() -> bool {
+    std::env::var("AOP_MODEL_ADAPTER_ENABLED")
+        .ok()
+        .map(|value| {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(!cfg!(test))
+}
+
+async fn generate_parts(
+    pool: &SqlitePool,
+    model_registry: &ModelRegistry,
+    task: &TaskRecord,
+    mutation: &MutationRecord,
+) -> Result<CommitParts, String> {
+    let ModelSelectionResult {
+        selection,
+        fallbacks,
+        ..
+    } = model_intelligence::select_model(
+        pool,
+        model_registry,
+        ModelSelectionRequest {
+            task_id: Some(&task.id),
+            actor: "mutation_pipeline",
+            tier: 3,
+            persona: Some("commit_writer"),
+            skill: Some("commit_message"),
+            remaining_budget: None,
+            cost_preference: CostPreference::Cheap,
+            requirements: ModelRequirements::default(),
+        },
+    )
+    .await?;
+
+    let diff = mutation
+        .diff_content
+        .chars()
+        .take(MAX_DIFF_CHARS)
+        .collect::<String>();
+    let request = AdapterRequest {
+        provider: selection.provider,
+        model_id: selection.model_id,
+        endpoint: selection.endpoint,
+        generation: selection.generation,
+        task_id: Some(task.id.clone()),
+        fallbacks,
+        response_schema: None,
+        bypass_cache: false,
+        attachments: Vec::new(),
+        pool: Some(pool.clone()),
+        system_prompt: SYSTEM_PROMPT.to_string(),
+        user_prompt: format!(
+            "OBJECTIVE:\n{}\n\nINTENT:\n{}\n\nCHANGED FILES:\n{}\n\nDIFF:\n{}",
+            task.objective.trim(),
+            mutation
+                .intent_description
+                .as_deref()
+                .unwrap_or("(none)")
+                .trim(),
+            mutation.file_path,
+            diff
+        ),
+    };
+    let response = tokio::task::spawn_blocking(move || llm_adapter::generate(&request))
+        .await
+        .map_err(|error| format!("Commit message task panicked: {error}"))??;
+
+    let text = response.text.trim();
+    let json = match (text.find('{'), text.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &text[start..=end],
+        _ => text,
+    };
+    serde_json::from_str::<CommitParts>(json)
+        .map_err(|error| format!("Failed to parse commit message response: {error}"))
+}
+
+const SYSTEM_PROMPT: &str = r#"You write git commit messages in the Conventional Commits format.
+You receive the task objective, the change intent, the changed files and the diff.
+
+Respond with ONLY a JSON object:
+{
+  "type": "feat" | "fix" | "refactor" | "perf" | "test" | "docs" | "style" | "build" | "ci" | "chore",
+  "scope": "short module or area name, or empty",
+  "summary": "imperative, lower-case, no trailing period, at most 60 characters",
+  "body": "One to three short lines explaining why, or empty"
+}"#;
+
+/// Drops model output that does not fit the format instead of committing it.
+fn normalize_parts(parts: CommitParts) -> Option<CommitParts> {
+    let kind = parts.kind.trim().to_ascii_lowercase();
+    if !COMMIT_TYPES.contains(&kind.as_str()) {
+        return None;
+    }
+    let summary = first_line(&parts.summary);
+    if summary.is_empty() {
+        return None;
+    }
+    Some(CommitParts {
+        kind,
+        scope: sanitize_scope(&parts.scope),
+        summary,
+        body: parts.body.trim().to_string(),
+    })
+}
+
+fn heuristic_parts(task: &TaskRecord, mutation: &MutationRecord) -> CommitParts {
+    let intent = mutation
+        .intent_description
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unwrap_or(task.objective.trim());
+    let lowered = intent.to_ascii_lowercase();
+    let kind = if mutation.file_path.contains("test") || lowered.starts_with("test") {
+        "test"
+    } else if mutation.file_path.ends_with(".md") || lowered.contains("document") {
+        "docs"
+    } else if ["fix", "bug", "repair", "correct", "resolve"]
+        .iter()
+        .any(|word| lowered.contains(word))
+    {
+        "fix"
+    } else if ["refactor", "rename", "extract", "clean"]
+        .iter()
+        .any(|word| lowered.contains(word))
+    {
+        "refactor"
+    } else if ["add", "implement", "support", "introduce", "create"]
+        .iter()
+        .any(|word| lowered.contains(word))
+    {
+        "feat"
+    } else {
+        "chore"
+    };
+    let mut summary = first_line(intent);
+    if let Some(first) = summary.chars().next() {
+        summary = first
+            .to_lowercase()
+            .chain(summary.chars().skip(1))
+            .collect();
+    }
+    CommitParts {
+        kind: kind.to_string(),
+        scope: sanitize_scope(&scope_for_path(&mutation.file_path)),
+        summary: if summary.is_empty() {
+            format!("update {}", mutation.file_path)
+        } else {
+            summary
+        },
+        body: String::new(),
+    }
+}
+
+/// The innermost directory, or the file stem for top-level files.
+fn scope_for_path(file_path: &str) -> String {
+    let path = Path::new(file_path);
+    path.parent()
+        .and_then(|parent| parent.file_name())
+        .or_else(|| path.file_stem())
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn sanitize_scope(scope: &str) -> String {
+    scope
+        .trim()
+        .chars()
+        .filter(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '/' | '.'))
+        .collect()
+}
+
+fn first_line(value: &str) -> String {
+    let line = value.lines().next().unwrap_or_default().trim();
+    let line = line.trim_end_matches('.');
+    if line.chars().count() <= MAX_SUMMARY_CHARS {
+        return line.to_string();
+    }
+    line.chars()
+        .take(MAX_SUMMARY_CHARS)
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+fn render_template(
+    template: &str,
+    parts: &CommitParts,
+    task: &TaskRecord,
+    mutation: &MutationRecord,
+) -> String {
+    let template = if parts.scope.is_empty() {
+        template.replace("({scope})", "")
+    } else {
+        template.to_string()
+    };
+    let rendered = template
+        .replace("{type}", &parts.kind)
+        .replace("{scope}", &parts.scope)
+        .replace("{summary}", &parts.summary)
+        .replace("{body}", &parts.body)
+        .replace("{file}", &mutation.file_path)
+        .replace("{mutation_id}", &mutation.id)
+        .replace("{task_id}", &task.id);
+    // An empty body leaves trailing blank lines behind.
+    let mut lines = rendered.lines().collect::<Vec<_>>();
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task() -> TaskRecord {
+        TaskRecord {
+            id: "task-1".to_string(),
+            parent_id: None,
+            tier: 3,
+            domain: "frontend".to_string(),
+            objective: "Handle empty session tokens".to_string(),
+            status: "executing".to_string(),
+            token_budget: 1_000,
+            token_usage: 0,
+            context_efficiency_ratio: 0.0,
+            risk_factor: 0.2,
+            compliance_score: 0,
+            checksum_before: None,
+            checksum_after: None,
+            error_message: None,
+            retry_count: 0,
+            created_at: 0,
+            updated_at: 0,
+            target_files: None,
+            allowed_protected_paths: None,
+            priority: 0,
+            deadline_seconds: None,
+            started_at: None,
+            queued_at: None,
+        }
+    }
+
+    fn mutation(intent: &str) -> MutationRecord {
+        MutationRecord {
+            id: "mut-1".to_string(),
+            task_id: "task-1".to_string(),
+            agent_uid: "agent".to_string(),
+            file_path: "src/auth/session.ts".to_string(),
+            diff_content: String::new(),
+            intent_description: Some(intent.to_string()),
+            intent_hash: None,
+            confidence: 0.9,
+            test_result: None,
+            test_exit_code: None,
+            rejection_reason: None,
+            rejected_at_step: None,
+            status: "validated".to_string(),
+            proposed_at: 0,
+            applied_at: None,
+            parent_mutation_id: None,
+            modified_content: None,
+            base_checksum: None,
+            approved_hunks: None,
+            rejected_hunks_diff: None,
+        }
+    }
+
+    #[test]
+    fn renders_heuristic_message_from_intent_and_template() {
+        let task = task();
+        let mutation = mutation("Fix crash when the session token is empty.");
+        let parts = heuristic_parts(&task, &mutation);
+        assert_eq!(
+            render_template(DEFAULT_TEMPLATE, &parts, &task, &mutation),
+            "fix(auth): fix crash when the session token is empty"
+        );
+
+        let custom = CommitParts {
+            scope: String::new(),
+            ..parts
+        };
+        assert_eq!(
+            render_template(
+                "{type}({scope}): {summary} [{file}]",
+                &custom,
+                &task,
+                &mutation
+            ),
+            "fix: fix crash when the session token is empty [src/auth/session.ts]"
+        );
+
+        assert!(normalize_parts(CommitParts {
+            kind: "wip".to_string(),
+            summary: "stuff".to_string(),
+            ..CommitParts::default()
+        })
+        .is_none());
+    }
+}
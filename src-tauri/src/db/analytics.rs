@@ -0,0 +1,384 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+
+const DEFAULT_BREAKDOWN_DAYS: u32 = 7;
+const DEFAULT_TREND_DAYS: u32 = 14;
+const MAX_DAYS: u32 = 366;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostGroupBy {
+    RootTask,
+    Provider,
+    Model,
+    Tier,
+    Day,
+}
+
+impl CostGroupBy {
+    /// Grouping expression over `aop_agent_events e`; days are UTC.
+    fn key_sql(self) -> &'static str {
+        match self {
+            CostGroupBy::RootTask => "COALESCE(e.root_task_id, 'unscoped')",
+            CostGroupBy::Provider => "COALESCE(e.provider, 'unknown')",
+            CostGroupBy::Model => {
+                "COALESCE(e.provider, 'unknown') || '/' || COALESCE(e.model_id, 'unknown')"
+            }
+            CostGroupBy::Tier => "COALESCE(CAST(e.tier AS TEXT), 'unknown')",
+            CostGroupBy::Day => "date(e.created_at, 'unixepoch')",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageBucket {
+    Hour,
+    #[default]
+    Day,
+}
+
+impl UsageBucket {
+    fn secs(self) -> i64 {
+        match self {
+            UsageBucket::Hour => 3_600,
+            UsageBucket::Day => 86_400,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetCostBreakdownInput {
+    pub group_by: CostGroupBy,
+    /// Unix seconds; defaults to `days` before `until`.
+    pub since: Option<i64>,
+    /// Unix seconds; defaults to now.
+    pub until: Option<i64>,
+    /// Window length when `since` is unset, 7 by default.
+    pub days: Option<u32>,
+    pub root_task_id: Option<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct CostBreakdownRow {
+    pub key: String,
+    /// The root task's objective when grouping by root task.
+    pub label: Option<String>,
+    pub calls: i64,
+    pub tokens_in: i64,
+    pub tokens_out: i64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostBreakdown {
+    pub group_by: CostGroupBy,
+    pub since: i64,
+    pub until: i64,
+    pub calls: i64,
+    pub tokens_in: i64,
+    pub tokens_out: i64,
+    pub total_cost_usd: f64,
+    /// Most expensive first, except day groups, which are in date order.
+    pub rows: Vec<CostBreakdownRow>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetTokenUsageTrendsInput {
+    /// Days back from now, 14 by default.
+    pub days: Option<u32>,
+    #[serde(default)]
+    pub bucket: UsageBucket,
+    pub provider: Option<String>,
+    pub model_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsagePoint {
+    pub bucket_start: i64,
+    pub calls: i64,
+    pub tokens_in: i64,
+    pub tokens_out: i64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsageTrends {
+    pub bucket: UsageBucket,
+    pub since: i64,
+    pub until: i64,
+    /// Oldest first, one per bucket in the window including empty ones.
+    pub points: Vec<TokenUsagePoint>,
+}
+
+/// Spend and tokens of model calls recorded in `aop_agent_events`, grouped by root task,
+/// provider, model, tier or day.
+pub async fn get_cost_breakdown(
+    pool: &SqlitePool,
+    input: GetCostBreakdownInput,
+) -> Result<CostBreakdown, String> {
+    let until = input.until.unwrap_or_else(|| Utc::now().timestamp());
+    let days = input
+        .days
+        .unwrap_or(DEFAULT_BREAKDOWN_DAYS)
+        .clamp(1, MAX_DAYS);
+    let since = input
+        .since
+        .unwrap_or_else(|| until - i64::from(days) * 86_400);
+    if since >= until {
+        return Err("since must be before until".to_string());
+    }
+    let limit = i64::from(input.limit.unwrap_or(200).clamp(1, 1_000));
+
+    let key = input.group_by.key_sql();
+    let mut query_builder = QueryBuilder::<Sqlite>::new("SELECT ");
+    query_builder.push(key).push(" AS key, ");
+    if input.group_by == CostGroupBy::RootTask {
+        query_builder.push("MAX(t.objective) AS label, ");
+    } else {
+        query_builder.push("NULL AS label, ");
+    }
+    query_builder.push(
+        r#"
+        COUNT(*) AS calls,
+        COALESCE(SUM(e.tokens_in), 0) AS tokens_in,
+        COALESCE(SUM(e.tokens_out), 0) AS tokens_out,
+        COALESCE(SUM(e.cost_usd), 0.0) AS cost_usd
+        FROM aop_agent_events e
+        LEFT JOIN aop_tasks t ON t.id = e.root_task_id
+        WHERE (e.cost_usd IS NOT NULL OR e.tokens_in IS NOT NULL OR e.tokens_out IS NOT NULL)
+          AND e.created_at >= "#,
+    );
+    query_builder.push_bind(since);
+    query_builder.push(" AND e.created_at < ").push_bind(until);
+    if let Some(root_task_id) = input
+        .root_task_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        query_builder
+            .push(" AND e.root_task_id = ")
+            .push_bind(root_task_id.to_string());
+    }
+    query_builder.push(" GROUP BY ").push(key);
+    if input.group_by == CostGroupBy::Day {
+        query_builder.push(" ORDER BY key ASC");
+    } else {
+        query_builder.push(" ORDER BY cost_usd DESC, calls DESC, key ASC");
+    }
+    query_builder.push(" LIMIT ").push_bind(limit);
+
+    let rows = query_builder
+        .build_query_as::<CostBreakdownRow>()
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to compute cost breakdown: {error}"))?;
+
+    Ok(CostBreakdown {
+        group_by: input.group_by,
+        since,
+        until,
+        calls: rows.iter().map(|row| row.calls).sum(),
+        tokens_in: rows.iter().map(|row| row.tokens_in).sum(),
+        tokens_out: rows.iter().map(|row| row.tokens_out).sum(),
+        total_cost_usd: rows.iter().map(|row| row.cost_usd).sum(),
+        rows,
+    })
+}
+
+/// Tokens and spend per hour or day, optionally for one provider or model.
+pub async fn get_token_usage_trends(
+    pool: &SqlitePool,
+    input: GetTokenUsageTrendsInput,
+) -> Result<TokenUsageTrends, String> {
+    let bucket_secs = input.bucket.secs();
+    let until = Utc::now().timestamp();
+    let days = input.days.unwrap_or(DEFAULT_TREND_DAYS).clamp(1, MAX_DAYS);
+    let since = (until - i64::from(days) * 86_400) / bucket_secs * bucket_secs;
+    let provider = input
+        .provider
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+    let model_id = input
+        .model_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+
+    let rows = sqlx::query_as::<_, TokenUsagePoint>(
+        r#"
+        SELECT (created_at / ?) * ? AS bucket_start,
+               COUNT(*) AS calls,
+               COALESCE(SUM(tokens_in), 0) AS tokens_in,
+               COALESCE(SUM(tokens_out), 0) AS tokens_out,
+               COALESCE(SUM(cost_usd), 0.0) AS cost_usd
+        FROM aop_agent_events
+        WHERE (cost_usd IS NOT NULL OR tokens_in IS NOT NULL OR tokens_out IS NOT NULL)
+          AND created_at >= ?
+          AND (? IS NULL OR provider = ?)
+          AND (? IS NULL OR model_id = ?)
+        GROUP BY bucket_start
+        ORDER BY bucket_start ASC
+        "#,
+    )
+    .bind(bucket_secs)
+    .bind(bucket_secs)
+    .bind(since)
+    .bind(provider)
+    .bind(provider)
+    .bind(model_id)
+    .bind(model_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to compute token usage trends: {error}"))?;
+
+    Ok(TokenUsageTrends {
+        bucket: input.bucket,
+        since,
+        until,
+        points: fill_buckets(rows, since, until, bucket_secs),
+    })
+}
+
+/// One point per bucket from `since` through `until`, zero where nothing was recorded.
+fn fill_buckets(
+    rows: Vec<TokenUsagePoint>,
+    since: i64,
+    until: i64,
+    bucket_secs: i64,
+) -> Vec<TokenUsagePoint> {
+    let mut rows = rows.into_iter().peekable();
+    let mut points = Vec::new();
+    let mut bucket_start = since;
+    while bucket_start <= until {
+        match rows.next_if(|row| row.bucket_start == bucket_start) {
+            Some(row) => points.push(row),
+            None => points.push(TokenUsagePoint {
+                bucket_start,
+                calls: 0,
+                tokens_in: 0,
+                tokens_out: 0,
+                cost_usd: 0.0,
+            }),
+        }
+        bucket_start += bucket_secs;
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db;
+    use crate::db::tasks::{self, CreateTaskInput};
+    use crate::db::telemetry;
+
+    async fn setup_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn breaks_down_cost_by_provider_and_root_task_and_fills_trend_buckets() {
+        let pool = setup_test_pool().await;
+        let root = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 1,
+                domain: "platform".to_string(),
+                objective: "weekly work".to_string(),
+                token_budget: 5000,
+            },
+        )
+        .await
+        .expect("task should be created");
+        for details in [
+            "model=openai/gpt-5 tokensIn=1000 tokensOut=200 costUsd=0.010000",
+            "model=openai/gpt-5-mini tokensIn=400 tokensOut=100 costUsd=0.000300",
+            "model=claude_code/haiku tokensIn=300 tokensOut=50 costUsd=0.002000",
+        ] {
+            telemetry::record_task_activity_event(
+                &pool,
+                "tier1_orchestrator",
+                "model_call",
+                Some(root.id.as_str()),
+                Some(details),
+            )
+            .await
+            .expect("telemetry should be recorded");
+        }
+
+        let by_provider = get_cost_breakdown(
+            &pool,
+            GetCostBreakdownInput {
+                group_by: CostGroupBy::Provider,
+                since: None,
+                until: Some(Utc::now().timestamp() + 1),
+                days: None,
+                root_task_id: None,
+                limit: None,
+            },
+        )
+        .await
+        .expect("breakdown should compute");
+        assert_eq!(by_provider.calls, 3);
+        assert_eq!(by_provider.rows[0].key, "openai");
+        assert_eq!(
+            (by_provider.rows[0].calls, by_provider.rows[0].tokens_in),
+            (2, 1400)
+        );
+
+        let by_root = get_cost_breakdown(
+            &pool,
+            GetCostBreakdownInput {
+                group_by: CostGroupBy::RootTask,
+                since: None,
+                until: Some(Utc::now().timestamp() + 1),
+                days: None,
+                root_task_id: Some(root.id.clone()),
+                limit: None,
+            },
+        )
+        .await
+        .expect("breakdown should compute");
+        assert_eq!(by_root.rows.len(), 1);
+        assert_eq!(by_root.rows[0].label.as_deref(), Some("weekly work"));
+        assert!((by_root.total_cost_usd - 0.0123).abs() < 1e-9);
+
+        let trends = get_token_usage_trends(
+            &pool,
+            GetTokenUsageTrendsInput {
+                days: Some(3),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("trends should compute");
+        assert_eq!(trends.points.len(), 4);
+        let latest = trends.points.last().expect("latest bucket");
+        assert_eq!((latest.calls, latest.tokens_out), (3, 350));
+        assert_eq!(trends.points[0].calls, 0);
+    }
+}
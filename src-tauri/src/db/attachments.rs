@@ -0,0 +1,282 @@
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine as _;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::tasks::{self, TaskRecord};
+use crate::llm_adapter::ImageAttachment;
+
+const SUPPORTED_MEDIA_TYPES: &[&str] = &["image/png", "image/jpeg", "image/webp", "image/gif"];
+/// Providers reject larger images; OpenAI's limit is 20 MB, most others are lower.
+const MAX_ATTACHMENT_BYTES: usize = 5 * 1024 * 1024;
+const MAX_ATTACHMENTS_PER_TASK: i64 = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskAttachmentRecord {
+    pub id: String,
+    pub task_id: String,
+    pub name: String,
+    pub media_type: String,
+    pub size_bytes: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachObjectiveImagesInput {
+    pub task_id: String,
+    pub images: Vec<ImageAttachment>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTaskAttachmentsInput {
+    pub task_id: String,
+}
+
+pub async fn attach_objective_images(
+    pool: &SqlitePool,
+    input: AttachObjectiveImagesInput,
+) -> Result<Vec<TaskAttachmentRecord>, String> {
+    let task = tasks::get_task_by_id(pool, input.task_id.trim()).await?;
+    if input.images.is_empty() {
+        return Err("At least one image is required".to_string());
+    }
+    let existing: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM aop_task_attachments WHERE task_id = ?")
+            .bind(&task.id)
+            .fetch_one(pool)
+            .await
+            .map_err(|error| format!("Failed to count task attachments: {error}"))?;
+    if existing + input.images.len() as i64 > MAX_ATTACHMENTS_PER_TASK {
+        return Err(format!(
+            "A task can have at most {MAX_ATTACHMENTS_PER_TASK} attachments"
+        ));
+    }
+
+    let now = Utc::now().timestamp();
+    let mut records = Vec::with_capacity(input.images.len());
+    for image in input.images {
+        let (image, size_bytes) = validate_image(image)?;
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO aop_task_attachments (
+                id, task_id, name, media_type, data_base64, size_bytes, created_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(&task.id)
+        .bind(&image.name)
+        .bind(&image.media_type)
+        .bind(&image.data_base64)
+        .bind(size_bytes)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to store task attachment: {error}"))?;
+        records.push(TaskAttachmentRecord {
+            id,
+            task_id: task.id.clone(),
+            name: image.name,
+            media_type: image.media_type,
+            size_bytes,
+            created_at: now,
+        });
+    }
+    Ok(records)
+}
+
+pub async fn list_task_attachments(
+    pool: &SqlitePool,
+    input: ListTaskAttachmentsInput,
+) -> Result<Vec<TaskAttachmentRecord>, String> {
+    sqlx::query_as::<_, TaskAttachmentRecord>(
+        r#"
+        SELECT id, task_id, name, media_type, size_bytes, created_at
+        FROM aop_task_attachments
+        WHERE task_id = ?
+        ORDER BY created_at ASC, name ASC
+        "#,
+    )
+    .bind(input.task_id.trim())
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to list task attachments: {error}"))
+}
+
+/// Images attached to `task_id` or any of its ancestors, root first.
+pub async fn images_for_task(
+    pool: &SqlitePool,
+    task_id: &str,
+) -> Result<Vec<ImageAttachment>, String> {
+    let rows: Vec<(String, String, String)> = sqlx::query_as(
+        r#"
+        WITH RECURSIVE chain(id, parent_id, depth) AS (
+            SELECT id, parent_id, 0 FROM aop_tasks WHERE id = ?
+            UNION ALL
+            SELECT t.id, t.parent_id, c.depth + 1 FROM aop_tasks t JOIN chain c ON t.id = c.parent_id
+        )
+        SELECT a.name, a.media_type, a.data_base64
+        FROM aop_task_attachments a
+        JOIN chain c ON a.task_id = c.id
+        ORDER BY c.depth DESC, a.created_at ASC, a.name ASC
+        "#,
+    )
+    .bind(task_id.trim())
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to load task attachments: {error}"))?;
+    Ok(rows
+        .into_iter()
+        .map(|(name, media_type, data_base64)| ImageAttachment {
+            name,
+            media_type,
+            data_base64,
+        })
+        .collect())
+}
+
+/// Design images for a specialist working under `task`. Only frontend work gets them;
+/// other domains would just pay for the extra input tokens.
+pub async fn design_images_for(
+    pool: &SqlitePool,
+    task: &TaskRecord,
+) -> Result<Vec<ImageAttachment>, String> {
+    if task.domain != "frontend" {
+        return Ok(Vec::new());
+    }
+    images_for_task(pool, &task.id).await
+}
+
+/// Normalizes the image and returns its decoded size. Data URLs are accepted and their
+/// media type is used when none is given.
+fn validate_image(image: ImageAttachment) -> Result<(ImageAttachment, i64), String> {
+    let name = image.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Attachment name is required".to_string());
+    }
+    let (url_media_type, data) = match image.data_base64.trim().strip_prefix("data:") {
+        Some(rest) => {
+            let (header, data) = rest
+                .split_once(',')
+                .ok_or_else(|| format!("Attachment '{name}' has a malformed data URL"))?;
+            (header.strip_suffix(";base64").map(str::to_string), data)
+        }
+        None => (None, image.data_base64.trim()),
+    };
+    let media_type = Some(image.media_type.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty())
+        .or(url_media_type)
+        .unwrap_or_default();
+    if !SUPPORTED_MEDIA_TYPES.contains(&media_type.as_str()) {
+        return Err(format!(
+            "Attachment '{name}' has unsupported type '{media_type}'. Use PNG, JPEG, WebP or GIF."
+        ));
+    }
+    let decoded = BASE64_STANDARD
+        .decode(data)
+        .map_err(|error| format!("Attachment '{name}' is not valid base64: {error}"))?;
+    if decoded.len() > MAX_ATTACHMENT_BYTES {
+        return Err(format!(
+            "Attachment '{name}' is {} bytes; the limit is {MAX_ATTACHMENT_BYTES}",
+            decoded.len()
+        ));
+    }
+    Ok((
+        ImageAttachment {
+            name,
+            media_type,
+            data_base64: data.to_string(),
+        },
+        decoded.len() as i64,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db;
+    use crate::db::tasks::CreateTaskInput;
+
+    async fn setup_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+
+        pool
+    }
+
+    async fn create_task(pool: &SqlitePool, parent_id: Option<String>, tier: i64) -> String {
+        tasks::create_task(
+            pool,
+            CreateTaskInput {
+                parent_id,
+                tier,
+                domain: "frontend".to_string(),
+                objective: "match the mockup".to_string(),
+                token_budget: 2000,
+            },
+        )
+        .await
+        .expect("task should be created")
+        .id
+    }
+
+    #[tokio::test]
+    async fn specialists_inherit_images_attached_to_the_objective() {
+        let pool = setup_test_pool().await;
+        let root_id = create_task(&pool, None, 1).await;
+        let child_id = create_task(&pool, Some(root_id.clone()), 2).await;
+
+        let records = attach_objective_images(
+            &pool,
+            AttachObjectiveImagesInput {
+                task_id: root_id.clone(),
+                images: vec![ImageAttachment {
+                    name: "mockup.png".to_string(),
+                    media_type: String::new(),
+                    data_base64: "data:image/png;base64,iVBORw0KGgo=".to_string(),
+                }],
+            },
+        )
+        .await
+        .expect("image should attach");
+        assert_eq!(records[0].media_type, "image/png");
+        assert_eq!(records[0].size_bytes, 8);
+
+        let images = images_for_task(&pool, &child_id)
+            .await
+            .expect("images should load");
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].data_base64, "iVBORw0KGgo=");
+
+        let error = attach_objective_images(
+            &pool,
+            AttachObjectiveImagesInput {
+                task_id: root_id,
+                images: vec![ImageAttachment {
+                    name: "notes.pdf".to_string(),
+                    media_type: "application/pdf".to_string(),
+                    data_base64: "JVBERi0=".to_string(),
+                }],
+            },
+        )
+        .await
+        .expect_err("non-image attachment should be rejected");
+        assert!(error.contains("unsupported type"));
+    }
+}
@@ -0,0 +1,386 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+
+use crate::db::{self, encryption, metrics};
+
+const BACKUP_PREFIX: &str = "aop_orchestrator_";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseBackup {
+    pub file_name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreDatabaseInput {
+    /// A file name from `list_database_backups`.
+    pub file_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreDatabaseResult {
+    pub file_name: String,
+    /// The open database is replaced on the next start; the current one is kept as a
+    /// `pre_restore` backup.
+    pub restart_required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseIntegrityReport {
+    pub ok: bool,
+    /// `PRAGMA integrity_check` output; just `ok` for a healthy database.
+    pub integrity_messages: Vec<String>,
+    pub foreign_key_violations: i64,
+    pub checked_at: i64,
+}
+
+/// Writes a compacted, consistent copy of the live database with `VACUUM INTO`.
+pub async fn backup_database(
+    pool: &SqlitePool,
+    backup_dir: &Path,
+) -> Result<DatabaseBackup, String> {
+    fs::create_dir_all(backup_dir)
+        .map_err(|error| format!("Failed to create backup dir: {error}"))?;
+    let created_at = Utc::now();
+    let file_name = format!("{BACKUP_PREFIX}{}.db", created_at.format("%Y%m%d_%H%M%S"));
+    let path = backup_dir.join(&file_name);
+    if path.exists() {
+        return Err(format!("Backup '{file_name}' already exists"));
+    }
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().to_string())
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to back up database: {error}"))?;
+
+    let size_bytes = fs::metadata(&path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    metrics::record_audit_event(
+        pool,
+        "ui",
+        "database_backup",
+        None,
+        Some(&json!({ "file": file_name, "sizeBytes": size_bytes }).to_string()),
+    )
+    .await?;
+
+    Ok(DatabaseBackup {
+        file_name,
+        path: path.to_string_lossy().to_string(),
+        size_bytes,
+        created_at: created_at.timestamp(),
+    })
+}
+
+/// Backups in `backup_dir`, newest first.
+pub fn list_database_backups(backup_dir: &Path) -> Result<Vec<DatabaseBackup>, String> {
+    if !backup_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut backups = fs::read_dir(backup_dir)
+        .map_err(|error| format!("Failed to read backup dir: {error}"))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if !is_backup_file_name(&file_name) {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            let created_at = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|elapsed| elapsed.as_secs() as i64)
+                .unwrap_or(0);
+            Some(DatabaseBackup {
+                path: entry.path().to_string_lossy().to_string(),
+                file_name,
+                size_bytes: metadata.len(),
+                created_at,
+            })
+        })
+        .collect::<Vec<_>>();
+    backups.sort_by(|left, right| {
+        right
+            .created_at
+            .cmp(&left.created_at)
+            .then_with(|| right.file_name.cmp(&left.file_name))
+    });
+    Ok(backups)
+}
+
+/// Checks a backup and stages it to replace `db_path` on the next start, since the open
+/// pool cannot be swapped while tasks are running against it.
+pub async fn restore_database(
+    pool: &SqlitePool,
+    backup_dir: &Path,
+    db_path: &Path,
+    input: RestoreDatabaseInput,
+) -> Result<RestoreDatabaseResult, String> {
+    let file_name = input.file_name.trim();
+    if !is_backup_file_name(file_name) {
+        return Err(format!("'{file_name}' is not a database backup"));
+    }
+    let backup_path = backup_dir.join(file_name);
+    if !backup_path.is_file() {
+        return Err(format!("Backup '{file_name}' does not exist"));
+    }
+    verify_backup(&backup_path).await?;
+
+    fs::copy(&backup_path, pending_restore_path(db_path))
+        .map_err(|error| format!("Failed to stage database restore: {error}"))?;
+    metrics::record_audit_event(
+        pool,
+        "ui",
+        "database_restore_staged",
+        None,
+        Some(&json!({ "file": file_name }).to_string()),
+    )
+    .await?;
+
+    Ok(RestoreDatabaseResult {
+        file_name: file_name.to_string(),
+        restart_required: true,
+    })
+}
+
+/// Runs SQLite's integrity and foreign key checks against the live database.
+pub async fn check_database_integrity(
+    pool: &SqlitePool,
+) -> Result<DatabaseIntegrityReport, String> {
+    let integrity_messages = integrity_check(pool).await?;
+    let foreign_key_violations = sqlx::query("PRAGMA foreign_key_check")
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to check foreign keys: {error}"))?
+        .len() as i64;
+    let ok = integrity_messages == ["ok"] && foreign_key_violations == 0;
+
+    metrics::record_audit_event(
+        pool,
+        "ui",
+        "database_integrity_check",
+        None,
+        Some(
+            &json!({
+                "ok": ok,
+                "issues": integrity_messages.len(),
+                "foreignKeyViolations": foreign_key_violations,
+            })
+            .to_string(),
+        ),
+    )
+    .await?;
+
+    Ok(DatabaseIntegrityReport {
+        ok,
+        integrity_messages,
+        foreign_key_violations,
+        checked_at: Utc::now().timestamp(),
+    })
+}
+
+/// Swaps a staged restore into place before the pool is opened. The replaced database is
+/// moved into `backup_dir`; returns its new path when a restore was applied.
+pub fn apply_pending_restore(db_path: &Path, backup_dir: &Path) -> Result<Option<PathBuf>, String> {
+    let pending = pending_restore_path(db_path);
+    if !pending.is_file() {
+        return Ok(None);
+    }
+    fs::create_dir_all(backup_dir)
+        .map_err(|error| format!("Failed to create backup dir: {error}"))?;
+    let replaced = backup_dir.join(format!(
+        "{BACKUP_PREFIX}pre_restore_{}.db",
+        Utc::now().format("%Y%m%d_%H%M%S")
+    ));
+    if db_path.exists() {
+        fs::rename(db_path, &replaced)
+            .map_err(|error| format!("Failed to move aside current database: {error}"))?;
+    }
+    // A journal left next to the old file would be replayed into the restored one.
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let sidecar = sidecar_path(db_path, suffix);
+        if sidecar.exists() {
+            fs::rename(&sidecar, sidecar_path(&replaced, suffix))
+                .map_err(|error| format!("Failed to move aside database {suffix} file: {error}"))?;
+        }
+    }
+    fs::rename(&pending, db_path)
+        .map_err(|error| format!("Failed to move restored database into place: {error}"))?;
+    Ok(Some(replaced))
+}
+
+async fn verify_backup(backup_path: &Path) -> Result<(), String> {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(if encryption::is_plaintext(backup_path)? {
+            // Taken before encryption was turned on; converted when the restore is applied.
+            SqliteConnectOptions::new()
+                .filename(backup_path)
+                .read_only(true)
+        } else {
+            db::connect_options(backup_path).read_only(true)
+        })
+        .await
+        .map_err(|error| format!("Failed to open backup: {error}"))?;
+
+    let messages = integrity_check(&pool).await?;
+    if messages != ["ok"] {
+        pool.close().await;
+        return Err(format!(
+            "Backup failed integrity check: {}",
+            messages.join("; ")
+        ));
+    }
+    let applied_version = sqlx::query_scalar::<_, Option<i64>>(
+        "SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1",
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(|_| "Backup is not an AOP database".to_string());
+    pool.close().await;
+
+    let known_version = sqlx::migrate!("./migrations")
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or(0);
+    match applied_version? {
+        Some(version) if version > known_version => Err(format!(
+            "Backup has schema version {version}, newer than this build ({known_version})"
+        )),
+        Some(_) => Ok(()),
+        None => Err("Backup is not an AOP database".to_string()),
+    }
+}
+
+async fn integrity_check(pool: &SqlitePool) -> Result<Vec<String>, String> {
+    sqlx::query_scalar::<_, String>("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to check database integrity: {error}"))
+}
+
+fn is_backup_file_name(file_name: &str) -> bool {
+    file_name.starts_with(BACKUP_PREFIX)
+        && file_name.ends_with(".db")
+        && !file_name.contains(['/', '\\'])
+        && !file_name.contains("..")
+}
+
+fn pending_restore_path(db_path: &Path) -> PathBuf {
+    sidecar_path(db_path, ".restore")
+}
+
+fn sidecar_path(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::db;
+    use crate::db::tasks::{self, CreateTaskInput};
+
+    async fn open_pool(db_path: &Path) -> SqlitePool {
+        let pool = db::connect_pool(db_path)
+            .await
+            .expect("sqlite file should open");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+        pool
+    }
+
+    #[tokio::test]
+    async fn backup_is_verified_staged_and_swapped_in_on_start() {
+        let dir = tempdir().expect("temp dir should be created");
+        let db_path = dir.path().join("aop_orchestrator.db");
+        let backup_dir = dir.path().join("backups");
+        let pool = open_pool(&db_path).await;
+        let task = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 1,
+                domain: "platform".to_string(),
+                objective: "keep me".to_string(),
+                token_budget: 5000,
+            },
+        )
+        .await
+        .expect("task should be created");
+
+        let report = check_database_integrity(&pool)
+            .await
+            .expect("integrity check should run");
+        assert!(report.ok);
+        let backup = backup_database(&pool, &backup_dir)
+            .await
+            .expect("backup should succeed");
+        assert!(backup.size_bytes > 0);
+        assert_eq!(
+            list_database_backups(&backup_dir).expect("backups should list")[0].file_name,
+            backup.file_name
+        );
+
+        sqlx::query("DELETE FROM aop_tasks")
+            .execute(&pool)
+            .await
+            .expect("tasks should be deleted");
+        let staged = restore_database(
+            &pool,
+            &backup_dir,
+            &db_path,
+            RestoreDatabaseInput {
+                file_name: backup.file_name.clone(),
+            },
+        )
+        .await
+        .expect("restore should be staged");
+        assert!(staged.restart_required);
+        assert!(restore_database(
+            &pool,
+            &backup_dir,
+            &db_path,
+            RestoreDatabaseInput {
+                file_name: "../aop_orchestrator.db".to_string(),
+            },
+        )
+        .await
+        .is_err());
+        pool.close().await;
+
+        let replaced = apply_pending_restore(&db_path, &backup_dir)
+            .expect("restore should apply")
+            .expect("a restore was pending");
+        assert!(replaced.is_file());
+        assert!(apply_pending_restore(&db_path, &backup_dir)
+            .expect("nothing should be pending")
+            .is_none());
+
+        let restored = open_pool(&db_path).await;
+        let restored_task = tasks::get_task_by_id(&restored, task.id.as_str())
+            .await
+            .expect("task should come back with the restore");
+        assert_eq!(restored_task.objective, "keep me");
+    }
+}
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::{QueryBuilder, Sqlite, SqlitePool};
 use uuid::Uuid;
 
-use crate::db::tasks::{self, ControlTaskInput, TaskControlAction};
+use crate::db::tasks::{self, ControlTaskInput, TaskControlAction, TaskRecord};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -87,6 +87,56 @@ pub struct ResolveBudgetRequestInput {
     pub resume_task: Option<bool>,
 }
 
+/// Admits a budget request for approval without a human; unset limits do not restrict.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetPolicyRecord {
+    pub name: String,
+    /// Largest single increment the policy approves.
+    pub max_increment: Option<i64>,
+    /// Cap on all approved increments within one root task's tree, this one included.
+    pub max_total_per_root: Option<i64>,
+    /// Comma-separated tiers whose tasks may be topped up; empty allows every tier.
+    pub allowed_tiers: String,
+    /// Highest task risk factor the policy approves for.
+    pub max_risk: Option<f64>,
+    pub enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl BudgetPolicyRecord {
+    fn admits(&self, task: &TaskRecord, increment: i64, root_approved_total: i64) -> bool {
+        let tier_allowed = self.allowed_tiers.trim().is_empty()
+            || self
+                .allowed_tiers
+                .split(',')
+                .filter_map(|tier| tier.trim().parse::<i64>().ok())
+                .any(|tier| tier == task.tier);
+
+        self.enabled
+            && tier_allowed
+            && self.max_increment.is_none_or(|max| increment <= max)
+            && self
+                .max_total_per_root
+                .is_none_or(|max| root_approved_total + increment <= max)
+            && self.max_risk.is_none_or(|max| task.risk_factor <= max)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetBudgetPolicyInput {
+    pub name: String,
+    pub max_increment: Option<i64>,
+    pub max_total_per_root: Option<i64>,
+    pub allowed_tiers: Option<Vec<u8>>,
+    pub max_risk: Option<f64>,
+    pub enabled: Option<bool>,
+    /// Deletes the policy instead of saving it.
+    pub remove: Option<bool>,
+}
+
 pub async fn create_budget_request(
     pool: &SqlitePool,
     input: CreateBudgetRequestInput,
@@ -120,15 +170,27 @@ pub async fn create_budget_request(
     .await
     .map_err(|error| format!("Failed to create budget request: {error}"))?;
 
-    if input.auto_approve.unwrap_or(false) {
+    let approval = if input.auto_approve.unwrap_or(false) {
+        Some(("auto-approved by runtime".to_string(), input.requested_by))
+    } else {
+        admitting_policy(pool, &task, input.requested_increment)
+            .await?
+            .map(|policy| {
+                (
+                    format!("auto-approved by budget policy '{}'", policy.name),
+                    format!("policy:{}", policy.name),
+                )
+            })
+    };
+    if let Some((reason, decided_by)) = approval {
         return resolve_budget_request(
             pool,
             ResolveBudgetRequestInput {
                 request_id,
                 decision: BudgetRequestDecision::Approve,
                 approved_increment: Some(input.requested_increment),
-                reason: Some("auto-approved by runtime".to_string()),
-                decided_by: Some(input.requested_by),
+                reason: Some(reason),
+                decided_by: Some(decided_by),
                 resume_task: Some(false),
             },
         )
@@ -324,6 +386,127 @@ pub async fn get_latest_pending_request_for_task(
     .map_err(|error| format!("Failed to fetch pending budget request: {error}"))
 }
 
+pub async fn list_budget_policies(pool: &SqlitePool) -> Result<Vec<BudgetPolicyRecord>, String> {
+    sqlx::query_as::<_, BudgetPolicyRecord>(
+        r#"
+        SELECT name, max_increment, max_total_per_root, allowed_tiers, max_risk, enabled,
+               created_at, updated_at
+        FROM aop_budget_policies
+        ORDER BY name ASC
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to list budget policies: {error}"))
+}
+
+/// Saves a budget policy by name, or deletes it when `remove` is set.
+pub async fn set_budget_policy(
+    pool: &SqlitePool,
+    input: SetBudgetPolicyInput,
+) -> Result<Option<BudgetPolicyRecord>, String> {
+    let name = input.name.trim();
+    if name.is_empty() {
+        return Err("name is required".to_string());
+    }
+
+    if input.remove.unwrap_or(false) {
+        sqlx::query("DELETE FROM aop_budget_policies WHERE name = ?")
+            .bind(name)
+            .execute(pool)
+            .await
+            .map_err(|error| format!("Failed to remove budget policy: {error}"))?;
+        return Ok(None);
+    }
+
+    if input.max_increment.is_some_and(|max| max <= 0) {
+        return Err("maxIncrement must be greater than 0".to_string());
+    }
+    if input.max_total_per_root.is_some_and(|max| max <= 0) {
+        return Err("maxTotalPerRoot must be greater than 0".to_string());
+    }
+    if input
+        .max_risk
+        .is_some_and(|max| !(0.0..=1.0).contains(&max))
+    {
+        return Err("maxRisk must be between 0 and 1".to_string());
+    }
+    let mut tiers = input.allowed_tiers.unwrap_or_default();
+    if tiers.iter().any(|tier| !(1..=3).contains(tier)) {
+        return Err("allowedTiers may only contain 1, 2 or 3".to_string());
+    }
+    tiers.sort_unstable();
+    tiers.dedup();
+    let allowed_tiers = tiers
+        .iter()
+        .map(u8::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let now = Utc::now().timestamp();
+    sqlx::query(
+        r#"
+        INSERT INTO aop_budget_policies (
+            name, max_increment, max_total_per_root, allowed_tiers, max_risk, enabled,
+            created_at, updated_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(name) DO UPDATE SET
+            max_increment = excluded.max_increment,
+            max_total_per_root = excluded.max_total_per_root,
+            allowed_tiers = excluded.allowed_tiers,
+            max_risk = excluded.max_risk,
+            enabled = excluded.enabled,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(name)
+    .bind(input.max_increment)
+    .bind(input.max_total_per_root)
+    .bind(&allowed_tiers)
+    .bind(input.max_risk)
+    .bind(input.enabled.unwrap_or(true))
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to save budget policy: {error}"))?;
+
+    list_budget_policies(pool)
+        .await
+        .map(|policies| policies.into_iter().find(|policy| policy.name == name))
+}
+
+/// The first enabled policy, by name, that admits topping `task` up by `increment`.
+async fn admitting_policy(
+    pool: &SqlitePool,
+    task: &TaskRecord,
+    increment: i64,
+) -> Result<Option<BudgetPolicyRecord>, String> {
+    let policies = list_budget_policies(pool).await?;
+    if !policies.iter().any(|policy| policy.enabled) {
+        return Ok(None);
+    }
+
+    let root_approved_total = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COALESCE(SUM(r.approved_increment), 0)
+        FROM aop_budget_requests r
+        JOIN aop_tasks t ON t.id = r.task_id
+        WHERE r.status = 'approved'
+          AND t.root_task_id = (SELECT root_task_id FROM aop_tasks WHERE id = ?)
+        "#,
+    )
+    .bind(&task.id)
+    .fetch_one(pool)
+    .await
+    .map_err(|error| format!("Failed to total approved budget increments: {error}"))?;
+
+    Ok(policies
+        .into_iter()
+        .find(|policy| policy.admits(task, increment, root_approved_total)))
+}
+
 fn build_resolution_note(decided_by: Option<&str>, reason: Option<&str>) -> Option<String> {
     let actor = decided_by
         .map(str::trim)
@@ -488,4 +671,81 @@ mod tests {
         assert_eq!(task_after.token_budget, 2250);
         assert_eq!(task_after.status, "executing");
     }
+
+    #[tokio::test]
+    async fn budget_policies_approve_small_top_ups_and_leave_large_ones_pending() {
+        let pool = setup_test_pool().await;
+        let create = |parent_id: Option<String>, tier: i64| CreateTaskInput {
+            parent_id,
+            tier,
+            domain: "platform".to_string(),
+            objective: "task".to_string(),
+            token_budget: 2000,
+        };
+        let root = tasks::create_task(&pool, create(None, 1))
+            .await
+            .expect("root should be created");
+        let leaf = tasks::create_task(&pool, create(Some(root.id.clone()), 3))
+            .await
+            .expect("leaf should be created");
+
+        set_budget_policy(
+            &pool,
+            SetBudgetPolicyInput {
+                name: "routine".to_string(),
+                max_increment: Some(500),
+                max_total_per_root: Some(800),
+                allowed_tiers: Some(vec![3]),
+                max_risk: Some(0.5),
+                enabled: None,
+                remove: None,
+            },
+        )
+        .await
+        .expect("policy should save");
+
+        let request = |task_id: &str, increment: i64| CreateBudgetRequestInput {
+            task_id: task_id.to_string(),
+            requested_by: "runtime".to_string(),
+            reason: "top-up".to_string(),
+            requested_increment: increment,
+            auto_approve: Some(false),
+        };
+        let routine = create_budget_request(&pool, request(&leaf.id, 400))
+            .await
+            .expect("routine request should be created");
+        assert_eq!(routine.status, "approved");
+        assert!(routine
+            .resolution_note
+            .as_deref()
+            .is_some_and(|note| note.contains("policy:routine")));
+
+        let too_large = create_budget_request(&pool, request(&leaf.id, 600))
+            .await
+            .expect("large request should be created");
+        assert_eq!(too_large.status, "pending");
+        resolve_budget_request(
+            &pool,
+            ResolveBudgetRequestInput {
+                request_id: too_large.id,
+                decision: BudgetRequestDecision::Reject,
+                approved_increment: None,
+                reason: None,
+                decided_by: None,
+                resume_task: None,
+            },
+        )
+        .await
+        .expect("request should reject");
+
+        let over_root_total = create_budget_request(&pool, request(&leaf.id, 450))
+            .await
+            .expect("request over the root total should be created");
+        assert_eq!(over_root_total.status, "pending");
+
+        let wrong_tier = create_budget_request(&pool, request(&root.id, 100))
+            .await
+            .expect("tier-1 request should be created");
+        assert_eq!(wrong_tier.status, "pending");
+    }
 }
@@ -0,0 +1,148 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+
+/// Every unencrypted SQLite file starts with this; SQLCipher files start with random salt.
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// `PRAGMA key` value for a raw 256-bit key, which skips SQLCipher's key derivation.
+pub fn key_literal(hex_key: &str) -> String {
+    format!("\"x'{hex_key}'\"")
+}
+
+/// Whether `db_path` is an unencrypted SQLite file. A missing or empty file counts as
+/// encrypted-compatible, since SQLCipher will initialize it with the key.
+pub fn is_plaintext(db_path: &Path) -> Result<bool, String> {
+    let mut file = match File::open(db_path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(error) => return Err(format!("Failed to open database file: {error}")),
+    };
+    let mut header = [0_u8; 16];
+    match file.read_exact(&mut header) {
+        Ok(()) => Ok(&header == SQLITE_HEADER),
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(error) => Err(format!("Failed to read database header: {error}")),
+    }
+}
+
+/// Fails unless SQLite was built with SQLCipher; a plain SQLite silently ignores
+/// `PRAGMA key` and would leave the data unencrypted.
+pub async fn ensure_cipher(pool: &SqlitePool) -> Result<(), String> {
+    let version = sqlx::query_scalar::<_, String>("PRAGMA cipher_version")
+        .fetch_optional(pool)
+        .await
+        .map_err(|error| format!("Failed to query SQLCipher version: {error}"))?;
+    match version.filter(|value| !value.trim().is_empty()) {
+        Some(_) => Ok(()),
+        None => Err(
+            "Database encryption needs a build with the `sqlcipher` feature (AOP_DB_ENCRYPTION)"
+                .to_string(),
+        ),
+    }
+}
+
+/// Rewrites a plaintext database encrypted under `hex_key` and swaps it into place.
+/// Returns false when there was nothing to convert.
+pub async fn encrypt_database(db_path: &Path, hex_key: &str) -> Result<bool, String> {
+    if !is_plaintext(db_path)? {
+        return Ok(false);
+    }
+    let encrypted_path = sidecar_path(db_path, ".encrypting");
+    if encrypted_path.exists() {
+        fs::remove_file(&encrypted_path)
+            .map_err(|error| format!("Failed to clear stale encryption output: {error}"))?;
+    }
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(SqliteConnectOptions::new().filename(db_path))
+        .await
+        .map_err(|error| format!("Failed to open plaintext database: {error}"))?;
+    let exported = export_encrypted(&pool, &encrypted_path, hex_key).await;
+    pool.close().await;
+    if let Err(error) = exported {
+        let _ = fs::remove_file(&encrypted_path);
+        return Err(error);
+    }
+
+    for suffix in ["-wal", "-shm"] {
+        let sidecar = sidecar_path(db_path, suffix);
+        if sidecar.exists() {
+            fs::remove_file(&sidecar)
+                .map_err(|error| format!("Failed to remove plaintext {suffix} file: {error}"))?;
+        }
+    }
+    fs::rename(&encrypted_path, db_path)
+        .map_err(|error| format!("Failed to replace plaintext database: {error}"))?;
+    Ok(true)
+}
+
+async fn export_encrypted(
+    pool: &SqlitePool,
+    encrypted_path: &Path,
+    hex_key: &str,
+) -> Result<(), String> {
+    ensure_cipher(pool).await?;
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to checkpoint plaintext database: {error}"))?;
+    sqlx::query("ATTACH DATABASE ? AS encrypted KEY ?")
+        .bind(encrypted_path.to_string_lossy().to_string())
+        .bind(format!("x'{hex_key}'"))
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to create encrypted database: {error}"))?;
+    sqlx::query("SELECT sqlcipher_export('encrypted')")
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to encrypt database: {error}"))?;
+    sqlx::query("DETACH DATABASE encrypted")
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to detach encrypted database: {error}"))?;
+    Ok(())
+}
+
+fn sidecar_path(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn recognizes_plaintext_databases_by_header() {
+        let dir = tempdir().expect("temp dir should be created");
+        let db_path = dir.path().join("plain.db");
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(
+                SqliteConnectOptions::new()
+                    .filename(&db_path)
+                    .create_if_missing(true),
+            )
+            .await
+            .expect("sqlite file should open");
+        sqlx::query("CREATE TABLE notes (body TEXT)")
+            .execute(&pool)
+            .await
+            .expect("table should be created");
+        pool.close().await;
+
+        assert!(is_plaintext(&db_path).expect("header should be readable"));
+        assert!(!is_plaintext(&dir.path().join("missing.db")).expect("missing is fine"));
+        let salted = dir.path().join("salted.db");
+        fs::write(&salted, [0x5a_u8; 64]).expect("file should be written");
+        assert!(!is_plaintext(&salted).expect("header should be readable"));
+    }
+}
@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchEventsInput {
+    pub query: String,
+    /// Limits hits to one task tree; audit entries match when they target a task in it.
+    pub root_task_id: Option<String>,
+    /// Also search audit log details, true by default.
+    pub include_audit: Option<bool>,
+    pub limit: Option<u32>,
+}
+
+/// A telemetry event or audit entry containing every query term, with the matching
+/// text excerpted around the hits.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct EventSearchHit {
+    /// `event` or `audit`.
+    pub source: String,
+    pub id: i64,
+    pub created_at: i64,
+    pub actor: String,
+    pub action: String,
+    pub run_id: Option<String>,
+    pub root_task_id: Option<String>,
+    /// The event's task, or the audit entry's target.
+    pub task_id: Option<String>,
+    pub snippet: String,
+}
+
+/// Newest events and audit entries matching `query`, found through the FTS5 indexes.
+pub async fn search_events(
+    pool: &SqlitePool,
+    input: SearchEventsInput,
+) -> Result<Vec<EventSearchHit>, String> {
+    let Some(match_expression) = match_expression(&input.query) else {
+        return Err("query needs at least one word".to_string());
+    };
+    let limit = i64::from(input.limit.unwrap_or(50).clamp(1, 500));
+    let root_task_id = input
+        .root_task_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+
+    let mut hits = sqlx::query_as::<_, EventSearchHit>(
+        r#"
+        SELECT 'event' AS source, e.id, e.created_at, e.actor, e.action, e.run_id,
+               e.root_task_id, e.task_id,
+               snippet(aop_agent_events_fts, -1, '[', ']', '…', 16) AS snippet
+        FROM aop_agent_events_fts
+        JOIN aop_agent_events AS e ON e.id = aop_agent_events_fts.rowid
+        WHERE aop_agent_events_fts MATCH ? AND (? IS NULL OR e.root_task_id = ?)
+        ORDER BY e.id DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(match_expression.as_str())
+    .bind(root_task_id)
+    .bind(root_task_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to search agent events: {error}"))?;
+
+    if input.include_audit.unwrap_or(true) {
+        let audit_hits = sqlx::query_as::<_, EventSearchHit>(
+            r#"
+            WITH RECURSIVE tree(id) AS (
+                SELECT id FROM aop_tasks WHERE id = ?
+                UNION ALL
+                SELECT t.id FROM aop_tasks t JOIN tree ON t.parent_id = tree.id
+            )
+            SELECT 'audit' AS source, a.id, a.timestamp AS created_at, a.actor, a.action,
+                   NULL AS run_id, NULL AS root_task_id, a.target_id AS task_id,
+                   snippet(aop_audit_log_fts, -1, '[', ']', '…', 16) AS snippet
+            FROM aop_audit_log_fts
+            JOIN aop_audit_log AS a ON a.id = aop_audit_log_fts.rowid
+            WHERE aop_audit_log_fts MATCH ?
+              AND (? IS NULL OR a.target_id IN (SELECT id FROM tree))
+            ORDER BY a.id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(root_task_id)
+        .bind(match_expression.as_str())
+        .bind(root_task_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to search audit log: {error}"))?;
+        hits.extend(audit_hits);
+    }
+
+    hits.sort_by(|left, right| {
+        right
+            .created_at
+            .cmp(&left.created_at)
+            .then_with(|| right.id.cmp(&left.id))
+    });
+    hits.truncate(limit as usize);
+    Ok(hits)
+}
+
+/// Every word of `query` as a quoted FTS5 term, so all must appear and operators in the
+/// query are taken literally.
+fn match_expression(query: &str) -> Option<String> {
+    let terms = query
+        .split(|ch: char| !ch.is_alphanumeric() && ch != '_')
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{term}\""))
+        .collect::<Vec<_>>();
+    (!terms.is_empty()).then(|| terms.join(" AND "))
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db;
+    use crate::db::metrics;
+    use crate::db::tasks::{self, CreateTaskInput};
+    use crate::db::telemetry;
+
+    async fn setup_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn finds_events_and_audit_entries_mentioning_every_term() {
+        let pool = setup_test_pool().await;
+        let task = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 1,
+                domain: "platform".to_string(),
+                objective: "flaky provider".to_string(),
+                token_budget: 5000,
+            },
+        )
+        .await
+        .expect("task should be created");
+        for details in [
+            "status=failed error=connect ECONNREFUSED 127.0.0.1:11434",
+            "status=executing",
+        ] {
+            telemetry::record_task_activity_event(
+                &pool,
+                "tier1_orchestrator",
+                "model_call",
+                Some(task.id.as_str()),
+                Some(details),
+            )
+            .await
+            .expect("telemetry should be recorded");
+        }
+        metrics::record_audit_event(
+            &pool,
+            "mcp_bridge",
+            "tool_failed",
+            Some(task.id.as_str()),
+            Some("read_file: ECONNREFUSED while contacting bridge"),
+        )
+        .await
+        .expect("audit should be recorded");
+
+        let hits = search_events(
+            &pool,
+            SearchEventsInput {
+                query: "econnrefused".to_string(),
+                root_task_id: Some(task.id.clone()),
+                include_audit: None,
+                limit: None,
+            },
+        )
+        .await
+        .expect("search should work");
+        let mut sources = hits
+            .iter()
+            .map(|hit| hit.source.as_str())
+            .collect::<Vec<_>>();
+        sources.sort_unstable();
+        assert_eq!(sources, ["audit", "event"]);
+        assert!(hits
+            .iter()
+            .all(|hit| hit.snippet.contains("[ECONNREFUSED]")));
+
+        let narrowed = search_events(
+            &pool,
+            SearchEventsInput {
+                query: "ECONNREFUSED bridge".to_string(),
+                root_task_id: None,
+                include_audit: Some(true),
+                limit: None,
+            },
+        )
+        .await
+        .expect("search should work");
+        assert_eq!(narrowed.len(), 1);
+        assert_eq!(narrowed[0].task_id.as_deref(), Some(task.id.as_str()));
+
+        assert!(search_events(
+            &pool,
+            SearchEventsInput {
+                query: " -- ".to_string(),
+                root_task_id: None,
+                include_audit: None,
+                limit: None,
+            },
+        )
+        .await
+        .is_err());
+    }
+}
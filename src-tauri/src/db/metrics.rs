@@ -5,6 +5,9 @@ use sqlx::{QueryBuilder, Sqlite};
 
 use crate::db::tasks;
 
+/// Audit action of every read of a stored key. The target is the key's name, never its value.
+pub const SECRET_ACCESS_ACTION: &str = "secret_access";
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct AuditLogEntry {
@@ -23,6 +26,17 @@ pub struct ListAuditLogInput {
     pub limit: Option<u32>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListSecretAccessLogInput {
+    /// A provider such as `openai`, which also matches its named keys (`openai/work`).
+    pub provider: Option<String>,
+    /// The component that read the key, such as `llm_adapter` or `ui`.
+    pub accessor: Option<String>,
+    pub since: Option<i64>,
+    pub limit: Option<u32>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ListTaskActivityInput {
@@ -144,6 +158,57 @@ pub async fn list_audit_log(
     }
 }
 
+/// Reads of stored keys, newest first.
+pub async fn list_secret_access_log(
+    pool: &SqlitePool,
+    input: ListSecretAccessLogInput,
+) -> Result<Vec<AuditLogEntry>, String> {
+    let limit = i64::from(input.limit.unwrap_or(100).clamp(1, 500));
+    let mut query_builder: QueryBuilder<'_, Sqlite> = QueryBuilder::new(
+        r#"
+        SELECT id, timestamp, actor, action, target_id, details
+        FROM aop_audit_log
+        WHERE action = "#,
+    );
+    query_builder.push_bind(SECRET_ACCESS_ACTION);
+
+    if let Some(provider) = input
+        .provider
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty())
+    {
+        let prefix = format!("{provider}/");
+        query_builder
+            .push(" AND (lower(target_id) = ")
+            .push_bind(provider)
+            .push(" OR lower(substr(target_id, 1, ")
+            .push_bind(prefix.len() as i64)
+            .push(")) = ")
+            .push_bind(prefix)
+            .push(")");
+    }
+    if let Some(accessor) = input
+        .accessor
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+    {
+        query_builder.push(" AND actor = ").push_bind(accessor);
+    }
+    if let Some(since) = input.since {
+        query_builder.push(" AND timestamp >= ").push_bind(since);
+    }
+
+    query_builder
+        .push(" ORDER BY id DESC LIMIT ")
+        .push_bind(limit);
+
+    query_builder
+        .build_query_as::<AuditLogEntry>()
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to list secret access log: {error}"))
+}
+
 pub async fn list_task_activity(
     pool: &SqlitePool,
     input: ListTaskActivityInput,
@@ -392,6 +457,44 @@ mod tests {
         assert_eq!(all.len(), 2);
     }
 
+    #[tokio::test]
+    async fn secret_access_log_filters_by_provider_and_accessor() {
+        let pool = setup_test_pool().await;
+
+        for (actor, target) in [
+            ("llm_adapter", "openai"),
+            ("llm_adapter", "openai/work"),
+            ("ui", "openai_compatible"),
+            ("webhooks", "webhook/signing"),
+        ] {
+            record_audit_event(&pool, actor, SECRET_ACCESS_ACTION, Some(target), None)
+                .await
+                .expect("access should be recorded");
+        }
+        record_audit_event(&pool, "ui", "provider_secret_set", Some("openai"), None)
+            .await
+            .expect("audit event should be recorded");
+
+        let list = |provider: Option<&str>, accessor: Option<&str>| {
+            list_secret_access_log(
+                &pool,
+                ListSecretAccessLogInput {
+                    provider: provider.map(str::to_string),
+                    accessor: accessor.map(str::to_string),
+                    since: None,
+                    limit: None,
+                },
+            )
+        };
+        assert_eq!(list(None, None).await.expect("log should list").len(), 4);
+        let openai = list(Some("OpenAI"), None).await.expect("log should filter");
+        assert_eq!(openai.len(), 2);
+        assert!(openai.iter().all(|entry| entry.actor == "llm_adapter"));
+        let ui = list(None, Some("ui")).await.expect("log should filter");
+        assert_eq!(ui.len(), 1);
+        assert_eq!(ui[0].target_id.as_deref(), Some("openai_compatible"));
+    }
+
     #[tokio::test]
     async fn lists_task_activity_with_descendants() {
         let pool = setup_test_pool().await;
@@ -1,27 +1,80 @@
+pub mod analytics;
+pub mod attachments;
+pub mod backup;
 pub mod budget_requests;
+pub mod encryption;
+pub mod event_search;
 pub mod metrics;
+pub mod model_arms;
+pub mod model_experiments;
 pub mod mutations;
+pub mod spend;
 pub mod tasks;
 pub mod telemetry;
+pub mod telemetry_archive;
+pub mod timeline;
 
 use std::path::Path;
+use std::sync::OnceLock;
 use std::time::Duration;
 
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
 use sqlx::SqlitePool;
 
+/// Waiting on another connection's write lock before giving up with `SQLITE_BUSY`.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+static ENCRYPTION_KEY: OnceLock<String> = OnceLock::new();
+
+/// Opens every later connection with SQLCipher key `hex_key` (64 hex characters).
+pub fn set_encryption_key(hex_key: String) {
+    let _ = ENCRYPTION_KEY.set(hex_key);
+}
+
+/// Options for opening `db_path`, keyed once the database is encrypted.
+pub fn connect_options(db_path: &Path) -> SqliteConnectOptions {
+    let options = SqliteConnectOptions::new().filename(db_path);
+    match ENCRYPTION_KEY.get() {
+        Some(hex_key) => options.pragma("key", encryption::key_literal(hex_key)),
+        None => options,
+    }
+}
+
+/// Read-write pool used for everything that records state. In WAL mode its writes no
+/// longer block readers, and readers never block it.
 pub async fn connect_pool(db_path: &Path) -> Result<SqlitePool, String> {
-    let connect_options = SqliteConnectOptions::new()
-        .filename(db_path)
+    let connect_options = connect_options(db_path)
         .create_if_missing(true)
-        .busy_timeout(Duration::from_secs(5))
+        .journal_mode(SqliteJournalMode::Wal)
+        // Durable at every checkpoint; a power loss can only drop the latest commits.
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(BUSY_TIMEOUT)
         .foreign_keys(true);
 
-    SqlitePoolOptions::new()
+    let pool = SqlitePoolOptions::new()
         .max_connections(5)
         .connect_with(connect_options)
         .await
-        .map_err(|error| format!("Failed to connect to SQLite: {error}"))
+        .map_err(|error| format!("Failed to connect to SQLite: {error}"))?;
+    if ENCRYPTION_KEY.get().is_some() {
+        encryption::ensure_cipher(&pool).await?;
+    }
+    Ok(pool)
+}
+
+/// Read-only pool for telemetry listings, search and analytics, so UI polling does not
+/// take connections from agents that are writing. Open it after `connect_pool`, which
+/// switches the database to WAL.
+pub async fn connect_read_pool(db_path: &Path) -> Result<SqlitePool, String> {
+    let connect_options = connect_options(db_path)
+        .read_only(true)
+        .busy_timeout(BUSY_TIMEOUT);
+
+    SqlitePoolOptions::new()
+        .max_connections(8)
+        .connect_with(connect_options)
+        .await
+        .map_err(|error| format!("Failed to open SQLite read pool: {error}"))
 }
 
 pub async fn run_migrations(pool: &SqlitePool) -> Result<(), String> {
@@ -0,0 +1,377 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+/// A routing segment: model choices are learned separately per tier, persona and skill.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArmSegment {
+    pub tier: i64,
+    pub persona: String,
+    pub skill: String,
+}
+
+impl ArmSegment {
+    pub fn new(tier: u8, persona: Option<&str>, skill: Option<&str>) -> Self {
+        let clean = |value: Option<&str>| value.unwrap_or_default().trim().to_ascii_lowercase();
+        Self {
+            tier: i64::from(tier),
+            persona: clean(persona),
+            skill: clean(skill),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelArmRecord {
+    pub tier: i64,
+    pub persona: String,
+    pub skill: String,
+    pub provider: String,
+    pub model_id: String,
+    /// Mutations with a settled outcome.
+    pub pulls: i64,
+    /// Applied mutations count 1, rejected ones 0.
+    pub reward_sum: f64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPinRecord {
+    pub tier: i64,
+    /// Empty matches any persona.
+    pub persona: String,
+    /// Empty matches any skill.
+    pub skill: String,
+    pub provider: String,
+    pub model_id: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetModelPinInput {
+    pub tier: u8,
+    pub persona: Option<String>,
+    pub skill: Option<String>,
+    /// Both unset removes the pin.
+    pub provider: Option<String>,
+    pub model_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListModelArmsInput {
+    pub tier: Option<u8>,
+    pub limit: Option<u32>,
+}
+
+/// Remembers which model produced `mutation_id` so its outcome can be credited later.
+pub async fn record_mutation_arm(
+    pool: &SqlitePool,
+    mutation_id: &str,
+    segment: &ArmSegment,
+    provider: &str,
+    model_id: &str,
+) -> Result<(), String> {
+    sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO aop_mutation_arms (
+            mutation_id, tier, persona, skill, provider, model_id, created_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(mutation_id)
+    .bind(segment.tier)
+    .bind(&segment.persona)
+    .bind(&segment.skill)
+    .bind(provider.trim())
+    .bind(model_id.trim())
+    .bind(Utc::now().timestamp())
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to record mutation model: {error}"))?;
+    Ok(())
+}
+
+/// Credits the model behind `mutation_id` with `reward` and returns its provider and model
+/// id. Only the first outcome counts, so a mutation rejected and later revised does not
+/// pay out twice.
+pub async fn settle_mutation_arm(
+    pool: &SqlitePool,
+    mutation_id: &str,
+    reward: f64,
+) -> Result<Option<(String, String)>, String> {
+    let now = Utc::now().timestamp();
+    let settled = sqlx::query_as::<_, (i64, String, String, String, String)>(
+        r#"
+        UPDATE aop_mutation_arms
+        SET reward = ?, settled_at = ?
+        WHERE mutation_id = ? AND reward IS NULL
+        RETURNING tier, persona, skill, provider, model_id
+        "#,
+    )
+    .bind(reward)
+    .bind(now)
+    .bind(mutation_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|error| format!("Failed to settle mutation model: {error}"))?;
+    let Some((tier, persona, skill, provider, model_id)) = settled else {
+        return Ok(None);
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO aop_model_arms (
+            tier, persona, skill, provider, model_id, pulls, reward_sum, updated_at
+        )
+        VALUES (?, ?, ?, ?, ?, 1, ?, ?)
+        ON CONFLICT(tier, persona, skill, provider, model_id) DO UPDATE SET
+            pulls = pulls + 1,
+            reward_sum = reward_sum + excluded.reward_sum,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(tier)
+    .bind(persona)
+    .bind(skill)
+    .bind(&provider)
+    .bind(&model_id)
+    .bind(reward)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to update model arm: {error}"))?;
+    Ok(Some((provider, model_id)))
+}
+
+pub async fn segment_arms(
+    pool: &SqlitePool,
+    segment: &ArmSegment,
+) -> Result<Vec<ModelArmRecord>, String> {
+    sqlx::query_as::<_, ModelArmRecord>(
+        r#"
+        SELECT tier, persona, skill, provider, model_id, pulls, reward_sum, updated_at
+        FROM aop_model_arms
+        WHERE tier = ? AND persona = ? AND skill = ?
+        "#,
+    )
+    .bind(segment.tier)
+    .bind(&segment.persona)
+    .bind(&segment.skill)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to load model arms: {error}"))
+}
+
+pub async fn list_model_arms(
+    pool: &SqlitePool,
+    input: ListModelArmsInput,
+) -> Result<Vec<ModelArmRecord>, String> {
+    let limit = i64::from(input.limit.unwrap_or(200).clamp(1, 1_000));
+    sqlx::query_as::<_, ModelArmRecord>(
+        r#"
+        SELECT tier, persona, skill, provider, model_id, pulls, reward_sum, updated_at
+        FROM aop_model_arms
+        WHERE ? IS NULL OR tier = ?
+        ORDER BY tier ASC, persona ASC, skill ASC, reward_sum / pulls DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(input.tier.map(i64::from))
+    .bind(input.tier.map(i64::from))
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to list model arms: {error}"))
+}
+
+/// The pin for `segment`, preferring the most specific match: exact persona and skill,
+/// then persona only, then skill only, then the whole tier.
+pub async fn pin_for_segment(
+    pool: &SqlitePool,
+    segment: &ArmSegment,
+) -> Result<Option<ModelPinRecord>, String> {
+    sqlx::query_as::<_, ModelPinRecord>(
+        r#"
+        SELECT tier, persona, skill, provider, model_id, created_at
+        FROM aop_model_pins
+        WHERE tier = ? AND persona IN (?, '') AND skill IN (?, '')
+        ORDER BY persona = '' ASC, skill = '' ASC
+        LIMIT 1
+        "#,
+    )
+    .bind(segment.tier)
+    .bind(&segment.persona)
+    .bind(&segment.skill)
+    .fetch_optional(pool)
+    .await
+    .map_err(|error| format!("Failed to load model pin: {error}"))
+}
+
+pub async fn list_model_pins(pool: &SqlitePool) -> Result<Vec<ModelPinRecord>, String> {
+    sqlx::query_as::<_, ModelPinRecord>(
+        r#"
+        SELECT tier, persona, skill, provider, model_id, created_at
+        FROM aop_model_pins
+        ORDER BY tier ASC, persona ASC, skill ASC
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to list model pins: {error}"))
+}
+
+/// Pins a model for a segment, or removes the pin when no model is given.
+pub async fn set_model_pin(
+    pool: &SqlitePool,
+    input: SetModelPinInput,
+) -> Result<Option<ModelPinRecord>, String> {
+    if !(1..=3).contains(&input.tier) {
+        return Err("tier must be 1, 2 or 3".to_string());
+    }
+    let segment = ArmSegment::new(input.tier, input.persona.as_deref(), input.skill.as_deref());
+    let provider = input.provider.as_deref().map(str::trim).unwrap_or_default();
+    let model_id = input.model_id.as_deref().map(str::trim).unwrap_or_default();
+
+    if provider.is_empty() && model_id.is_empty() {
+        sqlx::query("DELETE FROM aop_model_pins WHERE tier = ? AND persona = ? AND skill = ?")
+            .bind(segment.tier)
+            .bind(&segment.persona)
+            .bind(&segment.skill)
+            .execute(pool)
+            .await
+            .map_err(|error| format!("Failed to remove model pin: {error}"))?;
+        return Ok(None);
+    }
+    if provider.is_empty() || model_id.is_empty() {
+        return Err("provider and modelId are both required to pin a model".to_string());
+    }
+
+    let record = ModelPinRecord {
+        tier: segment.tier,
+        persona: segment.persona,
+        skill: segment.skill,
+        provider: provider.to_string(),
+        model_id: model_id.to_string(),
+        created_at: Utc::now().timestamp(),
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO aop_model_pins (tier, persona, skill, provider, model_id, created_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(tier, persona, skill) DO UPDATE SET
+            provider = excluded.provider,
+            model_id = excluded.model_id,
+            created_at = excluded.created_at
+        "#,
+    )
+    .bind(record.tier)
+    .bind(&record.persona)
+    .bind(&record.skill)
+    .bind(&record.provider)
+    .bind(&record.model_id)
+    .bind(record.created_at)
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to save model pin: {error}"))?;
+    Ok(Some(record))
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db;
+    use crate::db::mutations::{self, CreateMutationInput};
+    use crate::db::tasks::{self, CreateTaskInput};
+
+    async fn setup_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn settles_each_mutation_once_and_resolves_the_most_specific_pin() {
+        let pool = setup_test_pool().await;
+        let task = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 2,
+                domain: "frontend".to_string(),
+                objective: "tidy the header".to_string(),
+                token_budget: 2000,
+            },
+        )
+        .await
+        .expect("task should be created");
+        let mutation = mutations::create_mutation(
+            &pool,
+            CreateMutationInput {
+                task_id: task.id.clone(),
+                agent_uid: "agent".to_string(),
+                file_path: "src/header.tsx".to_string(),
+                diff_content: "--- a\n+++ b\n".to_string(),
+                intent_description: None,
+                intent_hash: None,
+                confidence: 0.8,
+                parent_mutation_id: None,
+                modified_content: None,
+                base_checksum: None,
+            },
+        )
+        .await
+        .expect("mutation should be created");
+
+        let segment = ArmSegment::new(3, Some("React_Specialist"), Some("specialist_assignment"));
+        record_mutation_arm(&pool, &mutation.id, &segment, "openai", "gpt-5")
+            .await
+            .expect("arm should be recorded");
+        settle_mutation_arm(&pool, &mutation.id, 1.0)
+            .await
+            .expect("arm should settle");
+        settle_mutation_arm(&pool, &mutation.id, 0.0)
+            .await
+            .expect("second settle is a no-op");
+
+        let arms = segment_arms(&pool, &segment)
+            .await
+            .expect("arms should load");
+        assert_eq!(arms.len(), 1);
+        assert_eq!((arms[0].pulls, arms[0].reward_sum), (1, 1.0));
+        assert_eq!(arms[0].persona, "react_specialist");
+
+        let pin = |persona: Option<&str>, model_id: &str| SetModelPinInput {
+            tier: 3,
+            persona: persona.map(ToOwned::to_owned),
+            skill: None,
+            provider: Some("claude_code".to_string()),
+            model_id: Some(model_id.to_string()),
+        };
+        set_model_pin(&pool, pin(None, "haiku"))
+            .await
+            .expect("tier pin should save");
+        set_model_pin(&pool, pin(Some("react_specialist"), "opus"))
+            .await
+            .expect("persona pin should save");
+        let resolved = pin_for_segment(&pool, &segment)
+            .await
+            .expect("pin should load")
+            .expect("a pin should match");
+        assert_eq!(resolved.model_id, "opus");
+    }
+}
@@ -0,0 +1,500 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::model_arms::ArmSegment;
+
+pub const ARM_CONTROL: &str = "control";
+pub const ARM_VARIANT: &str = "variant";
+const STATUS_RUNNING: &str = "running";
+const STATUS_STOPPED: &str = "stopped";
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelExperimentRecord {
+    pub id: String,
+    pub name: String,
+    pub tier: i64,
+    /// Empty matches any persona.
+    pub persona: String,
+    /// Empty matches any skill.
+    pub skill: String,
+    pub control_provider: String,
+    pub control_model_id: String,
+    pub variant_provider: String,
+    pub variant_model_id: String,
+    /// Share of selections, 1 to 99, routed to the variant.
+    pub variant_percent: i64,
+    pub status: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateModelExperimentInput {
+    pub name: String,
+    pub tier: u8,
+    pub persona: Option<String>,
+    pub skill: Option<String>,
+    pub control_provider: String,
+    pub control_model_id: String,
+    pub variant_provider: String,
+    pub variant_model_id: String,
+    pub variant_percent: u8,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelExperimentIdInput {
+    pub experiment_id: String,
+}
+
+/// The arm a selection was routed to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExperimentArm {
+    pub experiment_id: String,
+    pub arm: &'static str,
+    pub provider: String,
+    pub model_id: String,
+}
+
+impl ExperimentArm {
+    pub fn matches(&self, provider: &str, model_id: &str) -> bool {
+        self.provider.eq_ignore_ascii_case(provider) && self.model_id.eq_ignore_ascii_case(model_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentArmStats {
+    pub arm: String,
+    pub provider: String,
+    pub model_id: String,
+    pub selections: i64,
+    pub tasks: i64,
+    pub applied: i64,
+    pub rejected: i64,
+    /// Applied share of the arm's settled mutations; `None` before any settled.
+    pub apply_rate: Option<f64>,
+    pub total_cost_usd: f64,
+    pub avg_cost_usd_per_task: Option<f64>,
+    pub avg_latency_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelExperimentComparison {
+    pub experiment: ModelExperimentRecord,
+    pub arms: Vec<ExperimentArmStats>,
+}
+
+pub async fn create_model_experiment(
+    pool: &SqlitePool,
+    input: CreateModelExperimentInput,
+) -> Result<ModelExperimentRecord, String> {
+    if input.name.trim().is_empty() {
+        return Err("name is required".to_string());
+    }
+    if !(1..=3).contains(&input.tier) {
+        return Err("tier must be 1, 2 or 3".to_string());
+    }
+    if !(1..=99).contains(&input.variant_percent) {
+        return Err("variantPercent must be between 1 and 99".to_string());
+    }
+    let models = [
+        &input.control_provider,
+        &input.control_model_id,
+        &input.variant_provider,
+        &input.variant_model_id,
+    ];
+    if models.iter().any(|value| value.trim().is_empty()) {
+        return Err("control and variant provider and modelId are required".to_string());
+    }
+    if input
+        .control_provider
+        .trim()
+        .eq_ignore_ascii_case(input.variant_provider.trim())
+        && input
+            .control_model_id
+            .trim()
+            .eq_ignore_ascii_case(input.variant_model_id.trim())
+    {
+        return Err("control and variant must be different models".to_string());
+    }
+
+    let segment = ArmSegment::new(input.tier, input.persona.as_deref(), input.skill.as_deref());
+    let running: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT name FROM aop_model_experiments
+        WHERE tier = ? AND persona = ? AND skill = ? AND status = ?
+        "#,
+    )
+    .bind(segment.tier)
+    .bind(&segment.persona)
+    .bind(&segment.skill)
+    .bind(STATUS_RUNNING)
+    .fetch_optional(pool)
+    .await
+    .map_err(|error| format!("Failed to check running experiments: {error}"))?;
+    if let Some(name) = running {
+        return Err(format!(
+            "Experiment '{name}' is already running for this segment; stop it first."
+        ));
+    }
+
+    let now = Utc::now().timestamp();
+    let record = ModelExperimentRecord {
+        id: Uuid::new_v4().to_string(),
+        name: input.name.trim().to_string(),
+        tier: segment.tier,
+        persona: segment.persona,
+        skill: segment.skill,
+        control_provider: input.control_provider.trim().to_string(),
+        control_model_id: input.control_model_id.trim().to_string(),
+        variant_provider: input.variant_provider.trim().to_string(),
+        variant_model_id: input.variant_model_id.trim().to_string(),
+        variant_percent: i64::from(input.variant_percent),
+        status: STATUS_RUNNING.to_string(),
+        created_at: now,
+        updated_at: now,
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO aop_model_experiments (
+            id, name, tier, persona, skill, control_provider, control_model_id,
+            variant_provider, variant_model_id, variant_percent, status, created_at, updated_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&record.id)
+    .bind(&record.name)
+    .bind(record.tier)
+    .bind(&record.persona)
+    .bind(&record.skill)
+    .bind(&record.control_provider)
+    .bind(&record.control_model_id)
+    .bind(&record.variant_provider)
+    .bind(&record.variant_model_id)
+    .bind(record.variant_percent)
+    .bind(&record.status)
+    .bind(record.created_at)
+    .bind(record.updated_at)
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to create model experiment: {error}"))?;
+    Ok(record)
+}
+
+pub async fn list_model_experiments(
+    pool: &SqlitePool,
+) -> Result<Vec<ModelExperimentRecord>, String> {
+    sqlx::query_as::<_, ModelExperimentRecord>(
+        r#"
+        SELECT id, name, tier, persona, skill, control_provider, control_model_id,
+               variant_provider, variant_model_id, variant_percent, status, created_at,
+               updated_at
+        FROM aop_model_experiments
+        ORDER BY status = 'running' DESC, created_at DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to list model experiments: {error}"))
+}
+
+async fn get_model_experiment(
+    pool: &SqlitePool,
+    experiment_id: &str,
+) -> Result<ModelExperimentRecord, String> {
+    sqlx::query_as::<_, ModelExperimentRecord>(
+        r#"
+        SELECT id, name, tier, persona, skill, control_provider, control_model_id,
+               variant_provider, variant_model_id, variant_percent, status, created_at,
+               updated_at
+        FROM aop_model_experiments
+        WHERE id = ?
+        "#,
+    )
+    .bind(experiment_id.trim())
+    .fetch_optional(pool)
+    .await
+    .map_err(|error| format!("Failed to load model experiment: {error}"))?
+    .ok_or_else(|| format!("Model experiment '{}' not found", experiment_id.trim()))
+}
+
+/// Stops routing traffic through an experiment; its assignments are kept for comparison.
+pub async fn stop_model_experiment(
+    pool: &SqlitePool,
+    input: ModelExperimentIdInput,
+) -> Result<ModelExperimentRecord, String> {
+    sqlx::query("UPDATE aop_model_experiments SET status = ?, updated_at = ? WHERE id = ?")
+        .bind(STATUS_STOPPED)
+        .bind(Utc::now().timestamp())
+        .bind(input.experiment_id.trim())
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to stop model experiment: {error}"))?;
+    get_model_experiment(pool, &input.experiment_id).await
+}
+
+/// The running experiment for `segment`, preferring the most specific match as pins do.
+pub async fn experiment_for_segment(
+    pool: &SqlitePool,
+    segment: &ArmSegment,
+) -> Result<Option<ModelExperimentRecord>, String> {
+    sqlx::query_as::<_, ModelExperimentRecord>(
+        r#"
+        SELECT id, name, tier, persona, skill, control_provider, control_model_id,
+               variant_provider, variant_model_id, variant_percent, status, created_at,
+               updated_at
+        FROM aop_model_experiments
+        WHERE tier = ? AND persona IN (?, '') AND skill IN (?, '') AND status = ?
+        ORDER BY persona = '' ASC, skill = '' ASC, created_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(segment.tier)
+    .bind(&segment.persona)
+    .bind(&segment.skill)
+    .bind(STATUS_RUNNING)
+    .fetch_optional(pool)
+    .await
+    .map_err(|error| format!("Failed to load model experiment: {error}"))
+}
+
+/// Buckets `key` into the experiment's arms. The same task always gets the same arm, so
+/// its retries and revisions are attributed to one model.
+pub fn assign_arm(experiment: &ModelExperimentRecord, key: &str) -> ExperimentArm {
+    let digest = Sha256::digest(format!("{}:{}", experiment.id, key).as_bytes());
+    let mut bucket_bytes = [0_u8; 8];
+    bucket_bytes.copy_from_slice(&digest[..8]);
+    let bucket = u64::from_be_bytes(bucket_bytes) % 100;
+    let (arm, provider, model_id) = if (bucket as i64) < experiment.variant_percent {
+        (
+            ARM_VARIANT,
+            &experiment.variant_provider,
+            &experiment.variant_model_id,
+        )
+    } else {
+        (
+            ARM_CONTROL,
+            &experiment.control_provider,
+            &experiment.control_model_id,
+        )
+    };
+    ExperimentArm {
+        experiment_id: experiment.id.clone(),
+        arm,
+        provider: provider.clone(),
+        model_id: model_id.clone(),
+    }
+}
+
+pub async fn record_assignment(
+    pool: &SqlitePool,
+    arm: &ExperimentArm,
+    task_id: Option<&str>,
+) -> Result<(), String> {
+    sqlx::query(
+        r#"
+        INSERT INTO aop_experiment_assignments (
+            experiment_id, arm, task_id, provider, model_id, created_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&arm.experiment_id)
+    .bind(arm.arm)
+    .bind(task_id)
+    .bind(&arm.provider)
+    .bind(&arm.model_id)
+    .bind(Utc::now().timestamp())
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to record experiment assignment: {error}"))?;
+    Ok(())
+}
+
+/// Apply rate from the mutations of each arm's tasks, cost and latency from the arm
+/// model's events on those tasks.
+pub async fn compare_model_experiment(
+    pool: &SqlitePool,
+    input: ModelExperimentIdInput,
+) -> Result<ModelExperimentComparison, String> {
+    let experiment = get_model_experiment(pool, &input.experiment_id).await?;
+    let mut arms = Vec::new();
+    for (arm, provider, model_id) in [
+        (
+            ARM_CONTROL,
+            &experiment.control_provider,
+            &experiment.control_model_id,
+        ),
+        (
+            ARM_VARIANT,
+            &experiment.variant_provider,
+            &experiment.variant_model_id,
+        ),
+    ] {
+        let (selections, tasks): (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*), COUNT(DISTINCT task_id)
+            FROM aop_experiment_assignments
+            WHERE experiment_id = ? AND arm = ?
+            "#,
+        )
+        .bind(&experiment.id)
+        .bind(arm)
+        .fetch_one(pool)
+        .await
+        .map_err(|error| format!("Failed to count experiment assignments: {error}"))?;
+
+        let (applied, rejected): (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(SUM(status = 'applied'), 0), COALESCE(SUM(status = 'rejected'), 0)
+            FROM aop_mutations
+            WHERE task_id IN (
+                SELECT task_id FROM aop_experiment_assignments
+                WHERE experiment_id = ? AND arm = ? AND task_id IS NOT NULL
+            )
+            "#,
+        )
+        .bind(&experiment.id)
+        .bind(arm)
+        .fetch_one(pool)
+        .await
+        .map_err(|error| format!("Failed to count experiment mutations: {error}"))?;
+
+        let (total_cost_usd, avg_latency_ms): (f64, Option<f64>) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(SUM(cost_usd), 0.0), AVG(latency_ms)
+            FROM aop_agent_events
+            WHERE LOWER(provider) = LOWER(?) AND LOWER(model_id) = LOWER(?)
+              AND task_id IN (
+                SELECT task_id FROM aop_experiment_assignments
+                WHERE experiment_id = ? AND arm = ? AND task_id IS NOT NULL
+              )
+            "#,
+        )
+        .bind(provider)
+        .bind(model_id)
+        .bind(&experiment.id)
+        .bind(arm)
+        .fetch_one(pool)
+        .await
+        .map_err(|error| format!("Failed to sum experiment costs: {error}"))?;
+
+        let settled = applied + rejected;
+        arms.push(ExperimentArmStats {
+            arm: arm.to_string(),
+            provider: provider.clone(),
+            model_id: model_id.clone(),
+            selections,
+            tasks,
+            applied,
+            rejected,
+            apply_rate: (settled > 0).then(|| applied as f64 / settled as f64),
+            total_cost_usd,
+            avg_cost_usd_per_task: (tasks > 0).then(|| total_cost_usd / tasks as f64),
+            avg_latency_ms,
+        });
+    }
+    Ok(ModelExperimentComparison { experiment, arms })
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db;
+
+    async fn setup_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn splits_traffic_by_percent_and_compares_arms() {
+        let pool = setup_test_pool().await;
+        let input = CreateModelExperimentInput {
+            name: "gpt-5-mini trial".to_string(),
+            tier: 3,
+            persona: None,
+            skill: Some("tier3_specialist_spawn".to_string()),
+            control_provider: "claude_code".to_string(),
+            control_model_id: "haiku".to_string(),
+            variant_provider: "openai".to_string(),
+            variant_model_id: "gpt-5-mini".to_string(),
+            variant_percent: 20,
+        };
+        let experiment = create_model_experiment(&pool, input.clone())
+            .await
+            .expect("experiment should be created");
+        assert!(create_model_experiment(&pool, input).await.is_err());
+
+        let segment = ArmSegment::new(3, Some("react_specialist"), Some("tier3_specialist_spawn"));
+        let running = experiment_for_segment(&pool, &segment)
+            .await
+            .expect("experiment should load")
+            .expect("the experiment should match");
+        assert_eq!(running.id, experiment.id);
+
+        let variants = (0..1_000)
+            .map(|index| assign_arm(&experiment, &format!("task-{index}")))
+            .filter(|arm| arm.arm == ARM_VARIANT)
+            .count();
+        assert!((150..250).contains(&variants), "{variants} variant picks");
+        assert_eq!(
+            assign_arm(&experiment, "task-7"),
+            assign_arm(&experiment, "task-7")
+        );
+
+        let arm = assign_arm(&experiment, "task-7");
+        record_assignment(&pool, &arm, None)
+            .await
+            .expect("assignment should be recorded");
+        let comparison = compare_model_experiment(
+            &pool,
+            ModelExperimentIdInput {
+                experiment_id: experiment.id.clone(),
+            },
+        )
+        .await
+        .expect("comparison should load");
+        let stats = comparison
+            .arms
+            .iter()
+            .find(|stats| stats.arm == arm.arm)
+            .expect("arm stats");
+        assert_eq!(
+            (stats.selections, stats.tasks, stats.apply_rate),
+            (1, 0, None)
+        );
+
+        stop_model_experiment(
+            &pool,
+            ModelExperimentIdInput {
+                experiment_id: experiment.id,
+            },
+        )
+        .await
+        .expect("experiment should stop");
+        assert!(experiment_for_segment(&pool, &segment)
+            .await
+            .expect("lookup should succeed")
+            .is_none());
+    }
+}
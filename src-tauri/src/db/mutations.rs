@@ -3,6 +3,11 @@ use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
+use crate::db::model_arms;
+use crate::db::tasks;
+use crate::db::telemetry;
+use crate::patch_hunks;
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum MutationStatus {
@@ -11,6 +16,8 @@ pub enum MutationStatus {
     ValidatedNoTests,
     Applied,
     Rejected,
+    StaleBase,
+    Expired,
 }
 
 impl MutationStatus {
@@ -21,6 +28,8 @@ impl MutationStatus {
             MutationStatus::ValidatedNoTests => "validated_no_tests",
             MutationStatus::Applied => "applied",
             MutationStatus::Rejected => "rejected",
+            MutationStatus::StaleBase => "stale_base",
+            MutationStatus::Expired => "expired",
         }
     }
 }
@@ -43,6 +52,11 @@ pub struct MutationRecord {
     pub status: String,
     pub proposed_at: i64,
     pub applied_at: Option<i64>,
+    pub parent_mutation_id: Option<String>,
+    pub modified_content: Option<String>,
+    pub base_checksum: Option<String>,
+    pub approved_hunks: Option<String>,
+    pub rejected_hunks_diff: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -54,6 +68,19 @@ pub struct CreateMutationInput {
     pub intent_description: Option<String>,
     pub intent_hash: Option<String>,
     pub confidence: f64,
+    pub parent_mutation_id: Option<String>,
+    pub modified_content: Option<String>,
+    pub base_checksum: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplaceMutationContentInput {
+    pub mutation_id: String,
+    pub diff_content: String,
+    pub intent_description: Option<String>,
+    pub intent_hash: Option<String>,
+    pub modified_content: Option<String>,
+    pub base_checksum: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -62,6 +89,34 @@ pub struct ListTaskMutationsInput {
     pub task_id: String,
 }
 
+/// Statuses that still wait on a reviewer and therefore expire after the TTL.
+const UNREVIEWED_STATUSES: &[MutationStatus] = &[
+    MutationStatus::Proposed,
+    MutationStatus::Validated,
+    MutationStatus::ValidatedNoTests,
+    MutationStatus::StaleBase,
+];
+
+/// Filters for `purge_mutations`. Every filter that is set must match.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeMutationsInput {
+    /// Defaults to `expired` and `rejected`.
+    pub statuses: Option<Vec<MutationStatus>>,
+    pub older_than_days: Option<u32>,
+    /// Limits the purge to the mutations of this task and its descendants.
+    pub root_task_id: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeMutationsResult {
+    pub mutation_ids: Vec<String>,
+    pub dry_run: bool,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateMutationStatusInput {
@@ -71,6 +126,10 @@ pub struct UpdateMutationStatusInput {
     pub test_exit_code: Option<i64>,
     pub rejection_reason: Option<String>,
     pub rejected_at_step: Option<String>,
+    /// Zero-based hunk indices a reviewer approved; the remaining hunks are set aside for
+    /// the revision flow.
+    #[serde(default)]
+    pub approved_hunks: Option<Vec<usize>>,
 }
 
 pub async fn create_mutation(
@@ -87,9 +146,9 @@ pub async fn create_mutation(
         INSERT INTO aop_mutations (
             id, task_id, agent_uid, file_path, diff_content, intent_description, intent_hash,
             confidence, test_result, test_exit_code, rejection_reason, rejected_at_step,
-            status, proposed_at, applied_at
+            status, proposed_at, applied_at, parent_mutation_id, modified_content, base_checksum
         )
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, NULL, NULL, NULL, NULL, ?, ?, NULL)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, NULL, NULL, NULL, NULL, ?, ?, NULL, ?, ?, ?)
         "#,
     )
     .bind(&id)
@@ -106,6 +165,13 @@ pub async fn create_mutation(
     .bind(input.confidence)
     .bind(MutationStatus::Proposed.as_str())
     .bind(proposed_at)
+    .bind(
+        input
+            .parent_mutation_id
+            .map(|value| value.trim().to_string()),
+    )
+    .bind(input.modified_content)
+    .bind(input.base_checksum)
     .execute(pool)
     .await
     .map_err(|error| format!("Failed to create mutation: {error}"))?;
@@ -135,16 +201,38 @@ pub async fn update_mutation_status(
         current.rejected_at_step = Some(value);
     }
 
+    if let Some(approved) = input.approved_hunks.as_deref() {
+        let selection = patch_hunks::select_hunks(&current.diff_content, approved)?;
+        match selection.rejected_diff {
+            Some(rejected_diff) => {
+                let mut approved = approved.to_vec();
+                approved.sort_unstable();
+                approved.dedup();
+                current.approved_hunks = Some(
+                    serde_json::to_string(&approved)
+                        .map_err(|error| format!("Failed to encode approved hunks: {error}"))?,
+                );
+                current.rejected_hunks_diff = Some(rejected_diff);
+            }
+            None => {
+                current.approved_hunks = None;
+                current.rejected_hunks_diff = None;
+            }
+        }
+    }
+
     let applied_at = if input.status == MutationStatus::Applied {
         Some(Utc::now().timestamp())
     } else {
         current.applied_at
     };
+    let rejected_at_step = current.rejected_at_step.clone();
 
     sqlx::query(
         r#"
         UPDATE aop_mutations
-        SET status = ?, test_result = ?, test_exit_code = ?, rejection_reason = ?, rejected_at_step = ?, applied_at = ?
+        SET status = ?, test_result = ?, test_exit_code = ?, rejection_reason = ?, rejected_at_step = ?, applied_at = ?,
+            approved_hunks = ?, rejected_hunks_diff = ?
         WHERE id = ?
         "#,
     )
@@ -154,11 +242,167 @@ pub async fn update_mutation_status(
     .bind(current.rejection_reason)
     .bind(current.rejected_at_step)
     .bind(applied_at)
+    .bind(current.approved_hunks)
+    .bind(current.rejected_hunks_diff)
     .bind(input.mutation_id.trim())
     .execute(pool)
     .await
     .map_err(|error| format!("Failed to update mutation status: {error}"))?;
 
+    // Feeds adaptive model selection: the model behind an applied mutation earns a reward.
+    let reward = match input.status {
+        MutationStatus::Applied => Some(1.0),
+        MutationStatus::Rejected => Some(0.0),
+        _ => None,
+    };
+    if let Some(reward) = reward {
+        let settled =
+            model_arms::settle_mutation_arm(pool, input.mutation_id.trim(), reward).await?;
+        if let Some((provider, model_id)) = settled {
+            let revisions = revision_depth(pool, input.mutation_id.trim()).await?;
+            let delta = outcome_quality_delta(input.status, rejected_at_step.as_deref(), revisions);
+            telemetry::adjust_model_quality(pool, &provider, &model_id, delta).await?;
+        }
+    }
+
+    get_mutation_by_id(pool, input.mutation_id.trim()).await
+}
+
+/// How many revisions deep `mutation_id` sits in its `parent_mutation_id` chain.
+async fn revision_depth(pool: &SqlitePool, mutation_id: &str) -> Result<i64, String> {
+    sqlx::query_scalar::<_, i64>(
+        r#"
+        WITH RECURSIVE ancestors(id, parent_mutation_id, depth) AS (
+            SELECT id, parent_mutation_id, 0 FROM aop_mutations WHERE id = ?
+            UNION ALL
+            SELECT m.id, m.parent_mutation_id, a.depth + 1
+            FROM aop_mutations m
+            JOIN ancestors a ON m.id = a.parent_mutation_id
+            WHERE a.depth < 50
+        )
+        SELECT COALESCE(MAX(depth), 0) FROM ancestors
+        "#,
+    )
+    .bind(mutation_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|error| format!("Failed to count mutation revisions: {error}"))
+}
+
+/// Quality score change for the model that wrote a mutation. Code that breaks tests costs
+/// more than a reviewer's change request, steps that reject for reasons outside the
+/// model's control cost nothing, and an applied mutation earns less the more revisions it
+/// took.
+fn outcome_quality_delta(
+    status: MutationStatus,
+    rejected_at_step: Option<&str>,
+    revisions: i64,
+) -> f64 {
+    match status {
+        MutationStatus::Applied => 0.04 / (1.0 + revisions.max(0) as f64),
+        MutationStatus::Rejected => match rejected_at_step.unwrap_or_default() {
+            "shadow_test" => -0.10,
+            "semantic_regression" => -0.08,
+            "tier2_compliance" => -0.06,
+            "protected_paths" => -0.03,
+            "task_branch" | "base_check" | "apply" | "conflict_resolution" => 0.0,
+            _ => -0.05,
+        },
+        _ => 0.0,
+    }
+}
+
+/// Replaces the stored diff, e.g. after the pipeline regenerated it against newer content.
+pub async fn update_mutation_diff_content(
+    pool: &SqlitePool,
+    mutation_id: &str,
+    diff_content: &str,
+) -> Result<MutationRecord, String> {
+    if mutation_id.trim().is_empty() {
+        return Err("mutationId is required".to_string());
+    }
+    if diff_content.trim().is_empty() {
+        return Err("diffContent is required".to_string());
+    }
+
+    sqlx::query("UPDATE aop_mutations SET diff_content = ? WHERE id = ?")
+        .bind(diff_content)
+        .bind(mutation_id.trim())
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to update mutation diff: {error}"))?;
+
+    get_mutation_by_id(pool, mutation_id.trim()).await
+}
+
+/// Swaps in a diff rebuilt against newer file content and sends the mutation back through
+/// validation.
+pub async fn rebase_mutation_diff(
+    pool: &SqlitePool,
+    mutation_id: &str,
+    diff_content: &str,
+    base_checksum: &str,
+) -> Result<MutationRecord, String> {
+    if mutation_id.trim().is_empty() {
+        return Err("mutationId is required".to_string());
+    }
+    if diff_content.trim().is_empty() {
+        return Err("diffContent is required".to_string());
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE aop_mutations
+        SET diff_content = ?, base_checksum = ?, status = ?, test_result = NULL, test_exit_code = NULL,
+            approved_hunks = NULL, rejected_hunks_diff = NULL
+        WHERE id = ?
+        "#,
+    )
+    .bind(diff_content)
+    .bind(base_checksum)
+    .bind(MutationStatus::Proposed.as_str())
+    .bind(mutation_id.trim())
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to rebase mutation: {error}"))?;
+
+    get_mutation_by_id(pool, mutation_id.trim()).await
+}
+
+/// Replaces a mutation's proposal with edited content and clears every validation result,
+/// so the pipeline treats it as a fresh proposal.
+pub async fn replace_mutation_content(
+    pool: &SqlitePool,
+    input: ReplaceMutationContentInput,
+) -> Result<MutationRecord, String> {
+    if input.mutation_id.trim().is_empty() {
+        return Err("mutationId is required".to_string());
+    }
+    if input.diff_content.trim().is_empty() {
+        return Err("diffContent is required".to_string());
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE aop_mutations
+        SET diff_content = ?, intent_description = ?, intent_hash = ?, modified_content = ?,
+            base_checksum = ?, status = ?, test_result = NULL, test_exit_code = NULL,
+            rejection_reason = NULL, rejected_at_step = NULL, approved_hunks = NULL,
+            rejected_hunks_diff = NULL
+        WHERE id = ?
+        "#,
+    )
+    .bind(&input.diff_content)
+    .bind(input.intent_description)
+    .bind(input.intent_hash)
+    .bind(input.modified_content)
+    .bind(input.base_checksum)
+    .bind(MutationStatus::Proposed.as_str())
+    .bind(input.mutation_id.trim())
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to replace mutation content: {error}"))?;
+
     get_mutation_by_id(pool, input.mutation_id.trim()).await
 }
 
@@ -175,7 +419,8 @@ pub async fn list_mutations_for_task(
         SELECT
             id, task_id, agent_uid, file_path, diff_content, intent_description, intent_hash,
             confidence, test_result, test_exit_code, rejection_reason, rejected_at_step,
-            status, proposed_at, applied_at
+            status, proposed_at, applied_at, parent_mutation_id, modified_content,
+            base_checksum, approved_hunks, rejected_hunks_diff
         FROM aop_mutations
         WHERE task_id = ?
         ORDER BY proposed_at DESC
@@ -196,7 +441,8 @@ pub async fn get_mutation_by_id(
         SELECT
             id, task_id, agent_uid, file_path, diff_content, intent_description, intent_hash,
             confidence, test_result, test_exit_code, rejection_reason, rejected_at_step,
-            status, proposed_at, applied_at
+            status, proposed_at, applied_at, parent_mutation_id, modified_content,
+            base_checksum, approved_hunks, rejected_hunks_diff
         FROM aop_mutations
         WHERE id = ?
         "#,
@@ -228,6 +474,117 @@ fn validate_create_mutation_input(input: &CreateMutationInput) -> Result<(), Str
     Ok(())
 }
 
+/// Marks unreviewed mutations proposed before `cutoff` as `expired` and returns their ids.
+pub async fn expire_stale_mutations(
+    pool: &SqlitePool,
+    cutoff: i64,
+    reason: &str,
+) -> Result<Vec<String>, String> {
+    let placeholders = vec!["?"; UNREVIEWED_STATUSES.len()].join(", ");
+    let select_sql = format!(
+        "SELECT id FROM aop_mutations WHERE proposed_at < ? AND status IN ({placeholders})"
+    );
+    let mut select = sqlx::query_scalar::<_, String>(&select_sql).bind(cutoff);
+    for status in UNREVIEWED_STATUSES {
+        select = select.bind(status.as_str());
+    }
+    let ids = select
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to find stale mutations: {error}"))?;
+
+    for id in &ids {
+        sqlx::query(
+            "UPDATE aop_mutations SET status = ?, rejection_reason = ?, rejected_at_step = ? WHERE id = ?",
+        )
+        .bind(MutationStatus::Expired.as_str())
+        .bind(reason)
+        .bind("expiry")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to expire mutation: {error}"))?;
+    }
+
+    Ok(ids)
+}
+
+/// Deletes mutations matching `input`. Revisions of a purged mutation are kept and lose
+/// their parent link.
+pub async fn purge_mutations(
+    pool: &SqlitePool,
+    input: PurgeMutationsInput,
+) -> Result<PurgeMutationsResult, String> {
+    let task_ids = match input.root_task_id.as_deref().map(str::trim) {
+        Some(root) if !root.is_empty() => Some(tasks::collect_task_tree_ids(pool, root).await?),
+        _ => None,
+    };
+    let statuses = input
+        .statuses
+        .filter(|values| !values.is_empty())
+        .unwrap_or_else(|| vec![MutationStatus::Expired, MutationStatus::Rejected]);
+
+    let mut sql = format!(
+        "SELECT id FROM aop_mutations WHERE status IN ({})",
+        vec!["?"; statuses.len()].join(", ")
+    );
+    if input.older_than_days.is_some() {
+        sql.push_str(" AND proposed_at < ?");
+    }
+    if let Some(task_ids) = &task_ids {
+        sql.push_str(&format!(
+            " AND task_id IN ({})",
+            vec!["?"; task_ids.len()].join(", ")
+        ));
+    }
+    sql.push_str(" ORDER BY proposed_at ASC");
+
+    let mut select = sqlx::query_scalar::<_, String>(&sql);
+    for status in &statuses {
+        select = select.bind(status.as_str());
+    }
+    if let Some(days) = input.older_than_days {
+        select = select.bind(Utc::now().timestamp() - i64::from(days) * 86_400);
+    }
+    for task_id in task_ids.iter().flatten() {
+        select = select.bind(task_id);
+    }
+    let mutation_ids = select
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to select mutations to purge: {error}"))?;
+
+    if !input.dry_run && !mutation_ids.is_empty() {
+        let mut transaction = pool
+            .begin()
+            .await
+            .map_err(|error| format!("Failed to start purge transaction: {error}"))?;
+        for id in &mutation_ids {
+            sqlx::query(
+                "UPDATE aop_mutations SET parent_mutation_id = NULL WHERE parent_mutation_id = ?",
+            )
+            .bind(id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|error| format!("Failed to detach mutation revisions: {error}"))?;
+            sqlx::query("DELETE FROM aop_mutations WHERE id = ?")
+                .bind(id)
+                .execute(&mut *transaction)
+                .await
+                .map_err(|error| format!("Failed to delete mutation: {error}"))?;
+        }
+        transaction
+            .commit()
+            .await
+            .map_err(|error| format!("Failed to commit mutation purge: {error}"))?;
+    }
+
+    Ok(PurgeMutationsResult {
+        mutation_ids,
+        dry_run: input.dry_run,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use sqlx::sqlite::SqlitePoolOptions;
@@ -277,6 +634,9 @@ mod tests {
                 intent_description: Some("Improve loading state handling".to_string()),
                 intent_hash: Some("abc123".to_string()),
                 confidence: 0.78,
+                parent_mutation_id: None,
+                modified_content: None,
+                base_checksum: None,
             },
         )
         .await
@@ -324,6 +684,9 @@ mod tests {
                 intent_description: Some("Improve loading state handling".to_string()),
                 intent_hash: Some("abc123".to_string()),
                 confidence: 0.78,
+                parent_mutation_id: None,
+                modified_content: None,
+                base_checksum: None,
             },
         )
         .await
@@ -338,6 +701,7 @@ mod tests {
                 test_exit_code: Some(0),
                 rejection_reason: None,
                 rejected_at_step: None,
+                approved_hunks: None,
             },
         )
         .await
@@ -346,4 +710,224 @@ mod tests {
         assert_eq!(updated.status, "validated");
         assert_eq!(updated.test_exit_code, Some(0));
     }
+
+    #[tokio::test]
+    async fn partial_hunk_approval_keeps_rejected_remainder() {
+        let pool = setup_test_pool().await;
+        let task = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 2,
+                domain: "frontend".to_string(),
+                objective: "Split review".to_string(),
+                token_budget: 2200,
+            },
+        )
+        .await
+        .expect("task should be created");
+
+        let created = create_mutation(
+            &pool,
+            CreateMutationInput {
+                task_id: task.id,
+                agent_uid: Uuid::new_v4().to_string(),
+                file_path: "src/session.ts".to_string(),
+                diff_content: "--- a/src/session.ts\n+++ b/src/session.ts\n@@ -1,2 +1,2 @@\n-a\n+A\n b\n@@ -9,2 +9,2 @@\n i\n-j\n+J\n".to_string(),
+                intent_description: None,
+                intent_hash: None,
+                confidence: 0.7,
+                parent_mutation_id: None,
+                modified_content: None,
+                base_checksum: None,
+            },
+        )
+        .await
+        .expect("mutation should be created");
+
+        let updated = update_mutation_status(
+            &pool,
+            UpdateMutationStatusInput {
+                mutation_id: created.id,
+                status: MutationStatus::Validated,
+                test_result: None,
+                test_exit_code: None,
+                rejection_reason: None,
+                rejected_at_step: None,
+                approved_hunks: Some(vec![1]),
+            },
+        )
+        .await
+        .expect("partial approval should be stored");
+
+        assert_eq!(updated.approved_hunks.as_deref(), Some("[1]"));
+        let rejected = updated
+            .rejected_hunks_diff
+            .expect("rejected hunks should be stored");
+        assert!(rejected.contains("+A"));
+        assert!(!rejected.contains("+J"));
+    }
+
+    #[tokio::test]
+    async fn expires_unreviewed_mutations_and_purges_them() {
+        let pool = setup_test_pool().await;
+        let task = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 2,
+                domain: "frontend".to_string(),
+                objective: "Tidy review queue".to_string(),
+                token_budget: 2200,
+            },
+        )
+        .await
+        .expect("task should be created");
+
+        let mut created = Vec::new();
+        for _ in 0..2 {
+            let mutation = create_mutation(
+                &pool,
+                CreateMutationInput {
+                    task_id: task.id.clone(),
+                    agent_uid: Uuid::new_v4().to_string(),
+                    file_path: "src/session.ts".to_string(),
+                    diff_content: "--- a/src/session.ts\n+++ b/src/session.ts\n".to_string(),
+                    intent_description: None,
+                    intent_hash: None,
+                    confidence: 0.7,
+                    parent_mutation_id: created
+                        .first()
+                        .map(|value: &MutationRecord| value.id.clone()),
+                    modified_content: None,
+                    base_checksum: None,
+                },
+            )
+            .await
+            .expect("mutation should be created");
+            created.push(mutation);
+        }
+        update_mutation_status(
+            &pool,
+            UpdateMutationStatusInput {
+                mutation_id: created[1].id.clone(),
+                status: MutationStatus::Applied,
+                test_result: None,
+                test_exit_code: None,
+                rejection_reason: None,
+                rejected_at_step: None,
+                approved_hunks: None,
+            },
+        )
+        .await
+        .expect("mutation should update");
+
+        let expired = expire_stale_mutations(&pool, Utc::now().timestamp() + 1, "ttl")
+            .await
+            .expect("stale mutations should expire");
+        assert_eq!(expired, vec![created[0].id.clone()]);
+
+        let preview = purge_mutations(
+            &pool,
+            PurgeMutationsInput {
+                root_task_id: Some(task.id.clone()),
+                dry_run: true,
+                ..PurgeMutationsInput::default()
+            },
+        )
+        .await
+        .expect("dry run should succeed");
+        assert_eq!(preview.mutation_ids, expired);
+        assert!(get_mutation_by_id(&pool, &created[0].id).await.is_ok());
+
+        purge_mutations(&pool, PurgeMutationsInput::default())
+            .await
+            .expect("purge should succeed");
+        assert!(get_mutation_by_id(&pool, &created[0].id).await.is_err());
+        let revision = get_mutation_by_id(&pool, &created[1].id)
+            .await
+            .expect("applied revision should be kept");
+        assert_eq!(revision.parent_mutation_id, None);
+    }
+
+    #[tokio::test]
+    async fn mutation_outcomes_adjust_the_authoring_model_quality() {
+        let pool = setup_test_pool().await;
+        let task = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 2,
+                domain: "frontend".to_string(),
+                objective: "Fix the header".to_string(),
+                token_budget: 2200,
+            },
+        )
+        .await
+        .expect("task should be created");
+        telemetry::update_model_health(
+            &pool,
+            telemetry::ModelCallOutcomeInput {
+                provider: "openai".to_string(),
+                model_id: "gpt-5-mini".to_string(),
+                success: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("health should be seeded");
+
+        let segment = model_arms::ArmSegment::new(3, Some("react_specialist"), None);
+        let mut parent_mutation_id = None;
+        let mut quality = Vec::new();
+        for (status, step) in [
+            (MutationStatus::Rejected, Some("shadow_test")),
+            (MutationStatus::Applied, None),
+        ] {
+            let mutation = create_mutation(
+                &pool,
+                CreateMutationInput {
+                    task_id: task.id.clone(),
+                    agent_uid: Uuid::new_v4().to_string(),
+                    file_path: "src/header.tsx".to_string(),
+                    diff_content: "--- a/src/header.tsx\n+++ b/src/header.tsx\n".to_string(),
+                    intent_description: None,
+                    intent_hash: None,
+                    confidence: 0.7,
+                    parent_mutation_id: parent_mutation_id.clone(),
+                    modified_content: None,
+                    base_checksum: None,
+                },
+            )
+            .await
+            .expect("mutation should be created");
+            model_arms::record_mutation_arm(&pool, &mutation.id, &segment, "openai", "gpt-5-mini")
+                .await
+                .expect("arm should be recorded");
+            update_mutation_status(
+                &pool,
+                UpdateMutationStatusInput {
+                    mutation_id: mutation.id.clone(),
+                    status,
+                    test_result: None,
+                    test_exit_code: None,
+                    rejection_reason: None,
+                    rejected_at_step: step.map(ToOwned::to_owned),
+                    approved_hunks: None,
+                },
+            )
+            .await
+            .expect("status should update");
+            let health = telemetry::get_model_health(&pool, "openai", "gpt-5-mini")
+                .await
+                .expect("health should load")
+                .expect("health should exist");
+            quality.push(health.quality_score);
+            parent_mutation_id = Some(mutation.id);
+        }
+
+        // Failing tests costs 0.10; the applied revision earns half the usual 0.04.
+        assert!((quality[0] - 0.62).abs() < 1e-9);
+        assert!((quality[1] - 0.64).abs() < 1e-9);
+    }
 }
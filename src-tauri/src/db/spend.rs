@@ -0,0 +1,74 @@
+use chrono::{NaiveDate, Utc};
+use sqlx::SqlitePool;
+
+/// Adds one model call costing `cost_usd` to `day`'s spend.
+pub async fn add_spend(pool: &SqlitePool, day: NaiveDate, cost_usd: f64) -> Result<(), String> {
+    sqlx::query(
+        r#"
+        INSERT INTO aop_spend_ledger (day, cost_usd, calls, updated_at)
+        VALUES (?, ?, 1, ?)
+        ON CONFLICT(day) DO UPDATE SET
+            cost_usd = cost_usd + excluded.cost_usd,
+            calls = calls + 1,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(day.format("%Y-%m-%d").to_string())
+    .bind(cost_usd)
+    .bind(Utc::now().timestamp())
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to record model spend: {error}"))?;
+    Ok(())
+}
+
+/// Spend on `day` and in its calendar month up to and including it, in USD.
+pub async fn spend_totals(pool: &SqlitePool, day: NaiveDate) -> Result<(f64, f64), String> {
+    sqlx::query_as::<_, (f64, f64)>(
+        r#"
+        SELECT COALESCE(SUM(CASE WHEN day = ? THEN cost_usd END), 0.0),
+               COALESCE(SUM(cost_usd), 0.0)
+        FROM aop_spend_ledger
+        WHERE day >= ? AND day <= ?
+        "#,
+    )
+    .bind(day.format("%Y-%m-%d").to_string())
+    .bind(day.format("%Y-%m-01").to_string())
+    .bind(day.format("%Y-%m-%d").to_string())
+    .fetch_one(pool)
+    .await
+    .map_err(|error| format!("Failed to total model spend: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db;
+
+    #[tokio::test]
+    async fn totals_split_the_day_from_the_rest_of_its_month() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+
+        let date = |day: u32, month: u32| NaiveDate::from_ymd_opt(2026, month, day).expect("date");
+        for (day, month, cost) in [(30, 4, 9.0), (2, 5, 1.5), (17, 5, 0.25), (17, 5, 0.5)] {
+            add_spend(&pool, date(day, month), cost)
+                .await
+                .expect("spend should record");
+        }
+
+        let (daily, monthly) = spend_totals(&pool, date(17, 5))
+            .await
+            .expect("totals should load");
+        assert_eq!(daily, 0.75);
+        assert_eq!(monthly, 2.25);
+    }
+}
@@ -1,9 +1,14 @@
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use sqlx::SqlitePool;
-use std::collections::VecDeque;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
 use uuid::Uuid;
 
+pub const MIN_TASK_PRIORITY: i64 = -10;
+pub const MAX_TASK_PRIORITY: i64 = 10;
+pub const MAX_TASK_DEADLINE_SECONDS: i64 = 7 * 24 * 3_600;
+
+const PAUSE_MARKER: &str = "__aop_paused_prev_status:";
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum TaskStatus {
@@ -47,6 +52,17 @@ pub struct TaskRecord {
     pub created_at: i64,
     pub updated_at: i64,
     pub target_files: Option<String>,
+    /// JSON array of protected-path globs this plan may change; set on root tasks only.
+    pub allowed_protected_paths: Option<String>,
+    /// Dispatch order within a plan, from `MIN_TASK_PRIORITY` to `MAX_TASK_PRIORITY`;
+    /// higher runs first.
+    pub priority: i64,
+    /// Wall-clock budget counted from `started_at`; see `task_runtime::enforce_deadline`.
+    pub deadline_seconds: Option<i64>,
+    /// When the task first started executing since its last restart.
+    pub started_at: Option<i64>,
+    /// Set while an approved plan waits, `pending`, for its execution window.
+    pub queued_at: Option<i64>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -69,6 +85,22 @@ pub struct CreateTaskRecordInput {
     pub risk_factor: f64,
     pub status: TaskStatus,
     pub target_files: Option<String>,
+    pub priority: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetTaskPriorityInput {
+    pub task_id: String,
+    pub priority: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetTaskDeadlineInput {
+    pub task_id: String,
+    /// `None` removes the deadline.
+    pub deadline_seconds: Option<i64>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -132,6 +164,7 @@ pub async fn create_task(pool: &SqlitePool, input: CreateTaskInput) -> Result<Ta
             risk_factor: 0.0,
             status: TaskStatus::Pending,
             target_files: None,
+            priority: 0,
         },
     )
     .await
@@ -157,9 +190,9 @@ pub async fn create_task_record(
             id, parent_id, tier, domain, objective, status,
             token_budget, token_usage, context_efficiency_ratio, risk_factor,
             compliance_score, checksum_before, checksum_after, error_message,
-            retry_count, created_at, updated_at, target_files
+            retry_count, created_at, updated_at, target_files, priority, started_at
         )
-        VALUES (?, ?, ?, ?, ?, ?, ?, 0, 0.0, ?, 0, NULL, NULL, NULL, 0, ?, ?, ?)
+        VALUES (?, ?, ?, ?, ?, ?, ?, 0, 0.0, ?, 0, NULL, NULL, NULL, 0, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(&id)
@@ -173,6 +206,8 @@ pub async fn create_task_record(
     .bind(now)
     .bind(now)
     .bind(input.target_files.as_deref())
+    .bind(input.priority.clamp(MIN_TASK_PRIORITY, MAX_TASK_PRIORITY))
+    .bind((input.status == TaskStatus::Executing).then_some(now))
     .execute(pool)
     .await
     .map_err(|error| format!("Failed to create task: {error}"))?;
@@ -186,7 +221,8 @@ pub async fn get_tasks(pool: &SqlitePool) -> Result<Vec<TaskRecord>, String> {
         SELECT
             id, parent_id, tier, domain, objective, status, token_budget, token_usage,
             context_efficiency_ratio, risk_factor, compliance_score, checksum_before,
-            checksum_after, error_message, retry_count, created_at, updated_at, target_files
+            checksum_after, error_message, retry_count, created_at, updated_at, target_files,
+            allowed_protected_paths, priority, deadline_seconds, started_at, queued_at
         FROM aop_tasks
         ORDER BY created_at DESC
         "#,
@@ -199,38 +235,262 @@ pub async fn get_tasks(pool: &SqlitePool) -> Result<Vec<TaskRecord>, String> {
 pub async fn collect_task_tree_ids(
     pool: &SqlitePool,
     root_task_id: &str,
+) -> Result<Vec<String>, String> {
+    collect_task_subtree_ids(pool, root_task_id, None).await
+}
+
+/// Ids of `root_task_id` and its descendants, shallowest first, optionally limited to one
+/// tier. Served from the materialized `lineage_path` in a single query.
+pub async fn collect_task_subtree_ids(
+    pool: &SqlitePool,
+    root_task_id: &str,
+    tier: Option<i64>,
 ) -> Result<Vec<String>, String> {
     let root = root_task_id.trim();
     if root.is_empty() {
         return Err("taskId is required".to_string());
     }
 
-    get_task_by_id(pool, root).await?;
+    let lineage_path =
+        sqlx::query_scalar::<_, Option<String>>("SELECT lineage_path FROM aop_tasks WHERE id = ?")
+            .bind(root)
+            .fetch_optional(pool)
+            .await
+            .map_err(|error| format!("Failed to fetch task: {error}"))?
+            .ok_or_else(|| format!("Task '{root}' not found"))?
+            .ok_or_else(|| format!("Task '{root}' has no lineage"))?;
+
+    // Every descendant path sorts between `/.../root/` and `/.../root0`, since `0`
+    // follows `/`, which keeps the prefix match on the index.
+    let upper_bound = format!("{}0", lineage_path.trim_end_matches('/'));
+    sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT id
+        FROM aop_tasks
+        WHERE lineage_path >= ?
+          AND lineage_path < ?
+          AND (? IS NULL OR tier = ?)
+        ORDER BY LENGTH(lineage_path) - LENGTH(REPLACE(lineage_path, '/', '')) ASC,
+            created_at ASC
+        "#,
+    )
+    .bind(lineage_path.as_str())
+    .bind(upper_bound)
+    .bind(tier)
+    .bind(tier)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to resolve task descendants: {error}"))
+}
 
-    let mut ordered_ids = vec![root.to_string()];
-    let mut queue: VecDeque<String> = VecDeque::from([root.to_string()]);
+/// The root of the tree `task_id` belongs to, from its materialized lineage.
+pub async fn resolve_root_task_id(pool: &SqlitePool, task_id: &str) -> Result<String, String> {
+    let task_id = task_id.trim();
+    sqlx::query_scalar::<_, Option<String>>("SELECT root_task_id FROM aop_tasks WHERE id = ?")
+        .bind(task_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|error| format!("Failed to fetch task: {error}"))?
+        .ok_or_else(|| format!("Task '{task_id}' not found"))?
+        .ok_or_else(|| format!("Task '{task_id}' has no lineage"))
+}
 
-    while let Some(parent_id) = queue.pop_front() {
-        let children = sqlx::query_scalar::<_, String>(
-            r#"
-            SELECT id
-            FROM aop_tasks
-            WHERE parent_id = ?
-            ORDER BY created_at ASC
-            "#,
+/// Stores the protected-path allow-list of a plan on its root task.
+pub async fn set_allowed_protected_paths(
+    pool: &SqlitePool,
+    root_task_id: &str,
+    allowed_protected_paths: &[String],
+) -> Result<TaskRecord, String> {
+    let value = if allowed_protected_paths.is_empty() {
+        None
+    } else {
+        Some(
+            serde_json::to_string(allowed_protected_paths)
+                .map_err(|error| format!("Failed to encode allowed protected paths: {error}"))?,
         )
-        .bind(parent_id)
-        .fetch_all(pool)
+    };
+    sqlx::query("UPDATE aop_tasks SET allowed_protected_paths = ?, updated_at = ? WHERE id = ?")
+        .bind(value)
+        .bind(Utc::now().timestamp())
+        .bind(root_task_id.trim())
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to store allowed protected paths: {error}"))?;
+
+    get_task_by_id(pool, root_task_id.trim()).await
+}
+
+pub async fn set_task_priority(
+    pool: &SqlitePool,
+    input: SetTaskPriorityInput,
+) -> Result<TaskRecord, String> {
+    let task_id = input.task_id.trim();
+    if task_id.is_empty() {
+        return Err("taskId is required".to_string());
+    }
+    if !(MIN_TASK_PRIORITY..=MAX_TASK_PRIORITY).contains(&input.priority) {
+        return Err(format!(
+            "priority must be between {MIN_TASK_PRIORITY} and {MAX_TASK_PRIORITY}"
+        ));
+    }
+    let result = sqlx::query("UPDATE aop_tasks SET priority = ?, updated_at = ? WHERE id = ?")
+        .bind(input.priority)
+        .bind(Utc::now().timestamp())
+        .bind(task_id)
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to set task priority: {error}"))?;
+    if result.rows_affected() == 0 {
+        return Err(format!("Task '{task_id}' not found"));
+    }
+
+    get_task_by_id(pool, task_id).await
+}
+
+pub async fn set_task_deadline(
+    pool: &SqlitePool,
+    input: SetTaskDeadlineInput,
+) -> Result<TaskRecord, String> {
+    let task_id = input.task_id.trim();
+    if task_id.is_empty() {
+        return Err("taskId is required".to_string());
+    }
+    if let Some(seconds) = input.deadline_seconds {
+        if !(1..=MAX_TASK_DEADLINE_SECONDS).contains(&seconds) {
+            return Err(format!(
+                "deadlineSeconds must be between 1 and {MAX_TASK_DEADLINE_SECONDS}"
+            ));
+        }
+    }
+    let result =
+        sqlx::query("UPDATE aop_tasks SET deadline_seconds = ?, updated_at = ? WHERE id = ?")
+            .bind(input.deadline_seconds)
+            .bind(Utc::now().timestamp())
+            .bind(task_id)
+            .execute(pool)
+            .await
+            .map_err(|error| format!("Failed to set task deadline: {error}"))?;
+    if result.rows_affected() == 0 {
+        return Err(format!("Task '{task_id}' not found"));
+    }
+
+    get_task_by_id(pool, task_id).await
+}
+
+/// Puts a failed attempt back to pending for an automatic retry, counting it in `retry_count`.
+/// `started_at` is kept so a deadline still runs from the first attempt.
+pub async fn requeue_task(
+    pool: &SqlitePool,
+    task_id: &str,
+    error_message: &str,
+) -> Result<TaskRecord, String> {
+    let result = sqlx::query(
+        r#"
+        UPDATE aop_tasks
+        SET status = 'pending', error_message = ?, retry_count = retry_count + 1, updated_at = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(error_message)
+    .bind(Utc::now().timestamp())
+    .bind(task_id)
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to requeue task '{task_id}': {error}"))?;
+    if result.rows_affected() == 0 {
+        return Err(format!("Task '{task_id}' not found"));
+    }
+
+    get_task_by_id(pool, task_id).await
+}
+
+/// Ids from the root of `task_id`'s tree down to the task itself.
+pub async fn lineage_task_ids(pool: &SqlitePool, task_id: &str) -> Result<Vec<String>, String> {
+    let task_id = task_id.trim();
+    let lineage_path =
+        sqlx::query_scalar::<_, Option<String>>("SELECT lineage_path FROM aop_tasks WHERE id = ?")
+            .bind(task_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|error| format!("Failed to fetch task: {error}"))?
+            .ok_or_else(|| format!("Task '{task_id}' not found"))?;
+    Ok(match lineage_path {
+        Some(path) => path
+            .split('/')
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect(),
+        None => vec![task_id.to_string()],
+    })
+}
+
+/// Marks `task_id` as waiting for its execution window, or clears the mark.
+pub async fn set_task_queued(pool: &SqlitePool, task_id: &str, queued: bool) -> Result<(), String> {
+    let now = Utc::now().timestamp();
+    sqlx::query("UPDATE aop_tasks SET queued_at = ?, updated_at = ? WHERE id = ?")
+        .bind(queued.then_some(now))
+        .bind(now)
+        .bind(task_id)
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to update queued state of task '{task_id}': {error}"))?;
+    Ok(())
+}
+
+/// Clears window-queue marks left by a previous session, whose waiting plans died with it.
+pub async fn clear_stale_queued_tasks(pool: &SqlitePool) -> Result<u64, String> {
+    sqlx::query("UPDATE aop_tasks SET queued_at = NULL WHERE queued_at IS NOT NULL")
+        .execute(pool)
         .await
-        .map_err(|error| format!("Failed to resolve task descendants: {error}"))?;
+        .map(|result| result.rows_affected())
+        .map_err(|error| format!("Failed to clear queued tasks: {error}"))
+}
 
-        for child_id in children {
-            queue.push_back(child_id.clone());
-            ordered_ids.push(child_id);
+/// Ids of root tasks that are currently executing.
+pub async fn executing_root_task_ids(pool: &SqlitePool) -> Result<Vec<String>, String> {
+    sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT id
+        FROM aop_tasks
+        WHERE (parent_id IS NULL OR TRIM(parent_id) = '') AND status = 'executing'
+        ORDER BY created_at ASC
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to list executing root tasks: {error}"))
+}
+
+/// Pauses the pending and executing descendants of `task_id` so they can be resumed later.
+pub async fn pause_descendants(
+    pool: &SqlitePool,
+    task_id: &str,
+) -> Result<Vec<TaskRecord>, String> {
+    let mut paused = Vec::new();
+    let tree_ids = collect_task_tree_ids(pool, task_id).await?;
+    for descendant_id in tree_ids.into_iter().skip(1) {
+        let current = get_task_by_id(pool, &descendant_id).await?;
+        if !matches!(current.status.as_str(), "pending" | "executing") {
+            continue;
         }
+        paused.push(
+            update_task_status(
+                pool,
+                UpdateTaskStatusInput {
+                    task_id: descendant_id,
+                    status: TaskStatus::Paused,
+                    error_message: Some(format!("{PAUSE_MARKER}{}", current.status)),
+                },
+            )
+            .await?,
+        );
     }
+    Ok(paused)
+}
 
-    Ok(ordered_ids)
+/// Orders tasks for dispatch: higher priority first, keeping the given order among equals.
+pub fn sort_by_priority(tasks: &mut [TaskRecord]) {
+    tasks.sort_by_key(|task| std::cmp::Reverse(task.priority));
 }
 
 pub async fn control_task(
@@ -267,7 +527,7 @@ pub async fn control_task(
                     continue;
                 }
 
-                let pause_marker = format!("__aop_paused_prev_status:{}", current.status);
+                let pause_marker = format!("{PAUSE_MARKER}{}", current.status);
                 let record = update_task_status(
                     pool,
                     UpdateTaskStatusInput {
@@ -321,7 +581,8 @@ pub async fn control_task(
                 let rows_affected = sqlx::query(
                     r#"
                     UPDATE aop_tasks
-                    SET status = 'pending', error_message = NULL, retry_count = retry_count + 1, updated_at = ?
+                    SET status = 'pending', error_message = NULL, retry_count = retry_count + 1,
+                        started_at = NULL, updated_at = ?
                     WHERE id = ?
                     "#,
                 )
@@ -371,13 +632,16 @@ pub async fn update_task_status(
     let rows_affected = sqlx::query(
         r#"
         UPDATE aop_tasks
-        SET status = ?, error_message = ?, updated_at = ?
+        SET status = ?, error_message = ?, updated_at = ?,
+            started_at = CASE WHEN ? = 'executing' THEN COALESCE(started_at, ?) ELSE started_at END
         WHERE id = ?
         "#,
     )
     .bind(input.status.as_str())
     .bind(input.error_message)
     .bind(now)
+    .bind(input.status.as_str())
+    .bind(now)
     .bind(input.task_id.trim())
     .execute(pool)
     .await
@@ -391,6 +655,38 @@ pub async fn update_task_status(
     get_task_by_id(pool, input.task_id.trim()).await
 }
 
+/// Adds `tokens` to the usage of `task_id` and each of its ancestors, whose budgets cover
+/// their whole subtree.
+pub async fn debit_task_tokens(
+    pool: &SqlitePool,
+    task_id: &str,
+    tokens: i64,
+) -> Result<(), String> {
+    if tokens <= 0 {
+        return Ok(());
+    }
+    let lineage = lineage_task_ids(pool, task_id).await?;
+    let mut builder =
+        QueryBuilder::<Sqlite>::new("UPDATE aop_tasks SET token_usage = token_usage + ");
+    builder.push_bind(tokens);
+    builder.push(", updated_at = ");
+    builder.push_bind(Utc::now().timestamp());
+    builder.push(" WHERE id IN (");
+    {
+        let mut separated = builder.separated(", ");
+        for id in &lineage {
+            separated.push_bind(id);
+        }
+    }
+    builder.push(")");
+    builder
+        .build()
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to debit task tokens: {error}"))?;
+    Ok(())
+}
+
 pub async fn increase_task_budget(
     pool: &SqlitePool,
     task_id: &str,
@@ -436,7 +732,6 @@ pub async fn update_task_outcome(
     }
 
     let current = get_task_by_id(pool, input.task_id.trim()).await?;
-    let token_usage = input.token_usage.unwrap_or(current.token_usage);
     let context_efficiency_ratio = input
         .context_efficiency_ratio
         .unwrap_or(current.context_efficiency_ratio);
@@ -449,19 +744,23 @@ pub async fn update_task_outcome(
     sqlx::query(
         r#"
         UPDATE aop_tasks
-        SET status = ?, token_usage = ?, context_efficiency_ratio = ?, compliance_score = ?,
-            checksum_before = ?, checksum_after = ?, error_message = ?, updated_at = ?
+        SET status = ?, token_usage = COALESCE(?, token_usage), context_efficiency_ratio = ?,
+            compliance_score = ?,
+            checksum_before = ?, checksum_after = ?, error_message = ?, updated_at = ?,
+            started_at = CASE WHEN ? = 'executing' THEN COALESCE(started_at, ?) ELSE started_at END
         WHERE id = ?
         "#,
     )
     .bind(input.status.as_str())
-    .bind(token_usage)
+    .bind(input.token_usage)
     .bind(context_efficiency_ratio)
     .bind(compliance_score)
     .bind(checksum_before)
     .bind(checksum_after)
     .bind(error_message)
     .bind(now)
+    .bind(input.status.as_str())
+    .bind(now)
     .bind(input.task_id.trim())
     .execute(pool)
     .await
@@ -476,7 +775,8 @@ pub async fn get_task_by_id(pool: &SqlitePool, task_id: &str) -> Result<TaskReco
         SELECT
             id, parent_id, tier, domain, objective, status, token_budget, token_usage,
             context_efficiency_ratio, risk_factor, compliance_score, checksum_before,
-            checksum_after, error_message, retry_count, created_at, updated_at, target_files
+            checksum_after, error_message, retry_count, created_at, updated_at, target_files,
+            allowed_protected_paths, priority, deadline_seconds, started_at, queued_at
         FROM aop_tasks
         WHERE id = ?
         "#,
@@ -509,12 +809,11 @@ fn validate_create_record_input(input: &CreateTaskRecordInput) -> Result<(), Str
 }
 
 fn paused_previous_status(error_message: Option<&str>) -> TaskStatus {
-    const MARKER: &str = "__aop_paused_prev_status:";
     let Some(raw) = error_message else {
         return TaskStatus::Executing;
     };
 
-    let Some(value) = raw.trim().strip_prefix(MARKER) else {
+    let Some(value) = raw.trim().strip_prefix(PAUSE_MARKER) else {
         return TaskStatus::Executing;
     };
 
@@ -546,6 +845,26 @@ mod tests {
         pool
     }
 
+    async fn create_in_tree(
+        pool: &SqlitePool,
+        parent_id: Option<String>,
+        tier: i64,
+        objective: &str,
+    ) -> TaskRecord {
+        create_task(
+            pool,
+            CreateTaskInput {
+                parent_id,
+                tier,
+                domain: "platform".to_string(),
+                objective: objective.to_string(),
+                token_budget: 1000,
+            },
+        )
+        .await
+        .expect("task should be created")
+    }
+
     #[tokio::test]
     async fn create_list_and_update_task_flow_works() {
         let pool = setup_test_pool().await;
@@ -758,4 +1077,118 @@ mod tests {
             .expect("budget should increase");
         assert_eq!(updated.token_budget, 2500);
     }
+
+    #[tokio::test]
+    async fn resolve_root_task_id_walks_parent_chain() {
+        let pool = setup_test_pool().await;
+
+        let root = create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 1,
+                domain: "platform".to_string(),
+                objective: "Root".to_string(),
+                token_budget: 3000,
+            },
+        )
+        .await
+        .expect("root task should be created");
+        let leaf = create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: Some(root.id.clone()),
+                tier: 2,
+                domain: "platform".to_string(),
+                objective: "Leaf".to_string(),
+                token_budget: 1000,
+            },
+        )
+        .await
+        .expect("leaf task should be created");
+
+        let resolved = resolve_root_task_id(&pool, &leaf.id)
+            .await
+            .expect("root task should resolve");
+        assert_eq!(resolved, root.id);
+    }
+
+    #[tokio::test]
+    async fn task_tree_queries_use_materialized_lineage() {
+        let pool = setup_test_pool().await;
+        let root = create_in_tree(&pool, None, 1, "Root").await;
+        let domain = create_in_tree(&pool, Some(root.id.clone()), 2, "Domain").await;
+        let specialist = create_in_tree(&pool, Some(domain.id.clone()), 3, "Specialist").await;
+        let other_root = create_in_tree(&pool, None, 1, "Other").await;
+
+        let path =
+            sqlx::query_scalar::<_, String>("SELECT lineage_path FROM aop_tasks WHERE id = ?")
+                .bind(specialist.id.as_str())
+                .fetch_one(&pool)
+                .await
+                .expect("lineage path should be set on insert");
+        assert_eq!(
+            path,
+            format!("/{}/{}/{}/", root.id, domain.id, specialist.id)
+        );
+
+        let tree = collect_task_tree_ids(&pool, &root.id)
+            .await
+            .expect("tree should resolve");
+        assert_eq!(
+            tree,
+            vec![root.id.clone(), domain.id.clone(), specialist.id.clone()]
+        );
+        let subtree = collect_task_tree_ids(&pool, &domain.id)
+            .await
+            .expect("subtree should resolve");
+        assert_eq!(subtree, vec![domain.id.clone(), specialist.id.clone()]);
+        let tier_three = collect_task_subtree_ids(&pool, &root.id, Some(3))
+            .await
+            .expect("tier scope should resolve");
+        assert_eq!(tier_three, vec![specialist.id.clone()]);
+        assert!(!tree.contains(&other_root.id));
+        assert_eq!(
+            resolve_root_task_id(&pool, &specialist.id)
+                .await
+                .expect("root should resolve"),
+            root.id
+        );
+        assert!(collect_task_tree_ids(&pool, "missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn priority_orders_dispatch_and_rejects_out_of_range_values() {
+        let pool = setup_test_pool().await;
+        let root = create_in_tree(&pool, None, 1, "Root").await;
+        let first = create_in_tree(&pool, Some(root.id.clone()), 3, "First").await;
+        let second = create_in_tree(&pool, Some(root.id.clone()), 3, "Second").await;
+        let third = create_in_tree(&pool, Some(root.id.clone()), 3, "Third").await;
+        assert_eq!(first.priority, 0);
+
+        let bumped = set_task_priority(
+            &pool,
+            SetTaskPriorityInput {
+                task_id: third.id.clone(),
+                priority: 5,
+            },
+        )
+        .await
+        .expect("priority should update");
+        assert_eq!(bumped.priority, 5);
+        assert!(set_task_priority(
+            &pool,
+            SetTaskPriorityInput {
+                task_id: first.id.clone(),
+                priority: MAX_TASK_PRIORITY + 1,
+            },
+        )
+        .await
+        .is_err());
+
+        let mut planned = vec![first.clone(), second.clone(), bumped];
+        sort_by_priority(&mut planned);
+        let order = planned.into_iter().map(|task| task.id).collect::<Vec<_>>();
+        assert_eq!(order, vec![third.id, first.id, second.id]);
+    }
 }
@@ -9,6 +9,15 @@ use serde_json::{json, Value};
 use sqlx::{QueryBuilder, Sqlite, SqlitePool};
 use tokio::time::sleep;
 
+use crate::llm_rate_limit::{self, ProviderQueueMetrics};
+use crate::mcp_bridge::session::{self, McpSessionHealth};
+use crate::task_runtime::{self, ExecutionQueueSnapshot};
+
+/// Trend queries widen their buckets rather than return more than this many per model.
+const MAX_TREND_BUCKETS: i64 = 500;
+/// Rows deleted per statement when retention prunes telemetry.
+const RETENTION_DELETE_BATCH: usize = 500;
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct AgentRunRecord {
@@ -32,6 +41,19 @@ pub struct AgentRunRecord {
     pub metadata_json: Option<String>,
 }
 
+/// An executing run whose task has sent no heartbeat for longer than the stall threshold.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct StalledRunRecord {
+    pub run_id: String,
+    pub root_task_id: Option<String>,
+    pub task_id: String,
+    pub tier: Option<i64>,
+    pub actor: String,
+    pub heartbeat_at: i64,
+    pub stalled_at: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct AgentEventRecord {
@@ -75,9 +97,20 @@ pub struct ModelHealthRecord {
     pub last_error: Option<String>,
     pub last_used_at: Option<i64>,
     pub updated_at: i64,
+    pub consecutive_failures: i64,
+    /// While in the future, model selection skips this model.
+    pub circuit_open_until: Option<i64>,
+    /// Failed calls that hit the request timeout; also counted in `failed_calls`.
+    pub timeout_calls: i64,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl ModelHealthRecord {
+    pub fn circuit_open(&self, now: i64) -> bool {
+        self.circuit_open_until.is_some_and(|until| until > now)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ListAgentRunsInput {
     pub root_task_id: Option<String>,
@@ -86,9 +119,27 @@ pub struct ListAgentRunsInput {
     pub tier: Option<i64>,
     pub status: Option<String>,
     pub limit: Option<u32>,
+    /// Only runs started at or after this unix timestamp.
+    pub started_after: Option<i64>,
+    /// Only runs started before this unix timestamp.
+    pub started_before: Option<i64>,
+    /// Page cursor: the last run of the previous page, continuing with older runs.
+    pub before: Option<AgentRunCursor>,
+    /// Page cursor: the first run of the previous page, going back to newer runs.
+    pub after: Option<AgentRunCursor>,
+    /// Also read runs moved to archive files by retention.
+    pub include_archived: Option<bool>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// Position of a run in the listing order, newest `started_at` first with ties by id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentRunCursor {
+    pub started_at: i64,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ListAgentEventsInput {
     pub root_task_id: Option<String>,
@@ -97,6 +148,16 @@ pub struct ListAgentEventsInput {
     pub action: Option<String>,
     pub since_id: Option<i64>,
     pub limit: Option<u32>,
+    /// Only events created at or after this unix timestamp.
+    pub created_after: Option<i64>,
+    /// Only events created before this unix timestamp.
+    pub created_before: Option<i64>,
+    /// Page cursor: events older than this id.
+    pub before_id: Option<i64>,
+    /// Page cursor: the page of events directly newer than this id.
+    pub after_id: Option<i64>,
+    /// Also read events moved to archive files by retention.
+    pub include_archived: Option<bool>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -113,6 +174,127 @@ pub struct MissionControlSnapshot {
     pub active_runs: Vec<AgentRunRecord>,
     pub recent_events: Vec<AgentEventRecord>,
     pub model_health: Vec<ModelHealthRecord>,
+    #[serde(default)]
+    pub provider_queues: Vec<ProviderQueueMetrics>,
+    #[serde(default)]
+    pub execution_queue: ExecutionQueueSnapshot,
+    #[serde(default)]
+    pub stalled_runs: Vec<StalledRunRecord>,
+    #[serde(default)]
+    pub mcp_sessions: Vec<McpSessionHealth>,
+}
+
+/// One page of a listing, newest first. `total` counts every row matching the filters
+/// regardless of the page cursor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentEventPage {
+    pub items: Vec<AgentEventRecord>,
+    pub total: i64,
+    /// Whether rows remain beyond this page in the direction being paged.
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentRunPage {
+    pub items: Vec<AgentRunRecord>,
+    pub total: i64,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetTaskCostRollupInput {
+    pub root_task_id: String,
+}
+
+/// Spend of one model within a run.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelCostRollup {
+    pub provider: Option<String>,
+    pub model_id: Option<String>,
+    pub calls: i64,
+    pub tokens_in: i64,
+    pub tokens_out: i64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskCostRollup {
+    pub root_task_id: String,
+    pub calls: i64,
+    pub tokens_in: i64,
+    pub tokens_out: i64,
+    pub total_cost_usd: f64,
+    /// Most expensive model first.
+    pub by_model: Vec<ModelCostRollup>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetModelHealthTrendsInput {
+    pub provider: Option<String>,
+    pub model_id: Option<String>,
+    /// Hours back from now, 24 by default.
+    pub window_hours: Option<u32>,
+    /// Bucket width, 60 minutes by default; widened to keep at most 500 buckets.
+    pub bucket_minutes: Option<u32>,
+}
+
+/// Model call outcomes within one time bucket.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelHealthBucket {
+    pub bucket_start: i64,
+    pub calls: i64,
+    pub failures: i64,
+    pub timeouts: i64,
+    /// `None` for a bucket without calls.
+    pub success_rate: Option<f64>,
+    pub latency_p50_ms: Option<i64>,
+    pub latency_p95_ms: Option<i64>,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelHealthSeries {
+    pub provider: String,
+    pub model_id: String,
+    pub calls: i64,
+    pub success_rate: f64,
+    pub latency_p50_ms: Option<i64>,
+    pub latency_p95_ms: Option<i64>,
+    pub total_cost_usd: f64,
+    /// Longest run of consecutive failed calls within the window.
+    pub longest_failure_streak: i64,
+    /// Failed calls since the model's last success in the window.
+    pub current_failure_streak: i64,
+    /// Oldest first, covering the whole window.
+    pub buckets: Vec<ModelHealthBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelHealthTrends {
+    pub window_start: i64,
+    pub window_end: i64,
+    pub bucket_secs: i64,
+    pub series: Vec<ModelHealthSeries>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct ModelCallSample {
+    provider: String,
+    model_id: String,
+    success: bool,
+    timed_out: bool,
+    latency_ms: Option<i64>,
+    cost_usd: Option<f64>,
+    created_at: i64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -165,6 +347,16 @@ pub struct ModelCallOutcomeInput {
     pub latency_ms: Option<i64>,
     pub cost_usd: Option<f64>,
     pub error: Option<String>,
+    /// The call was abandoned at its timeout rather than failing outright.
+    pub timed_out: bool,
+    /// Opens the circuit once the failure streak reaches the threshold.
+    pub circuit_breaker: Option<CircuitBreakerPolicy>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreakerPolicy {
+    pub failure_threshold: u32,
+    pub open_secs: i64,
 }
 
 #[derive(Debug, Default)]
@@ -187,6 +379,13 @@ struct ParsedTelemetryFields {
     payload: Option<Value>,
 }
 
+/// ` costUsd=<value>` for activity details, or nothing when the call was not priced.
+pub fn cost_detail(cost_usd: Option<f64>) -> String {
+    cost_usd
+        .map(|cost| format!(" costUsd={cost:.6}"))
+        .unwrap_or_default()
+}
+
 pub async fn record_task_activity_event(
     pool: &SqlitePool,
     actor: &str,
@@ -352,16 +551,111 @@ pub async fn list_agent_runs(
     input: ListAgentRunsInput,
 ) -> Result<Vec<AgentRunRecord>, String> {
     let limit = i64::from(input.limit.unwrap_or(80).clamp(1, 500));
+    fetch_agent_runs(pool, &input, limit).await
+}
+
+/// Lists a page of runs with keyset cursors, so old history is reachable without
+/// loading everything in between.
+pub async fn page_agent_runs(
+    pool: &SqlitePool,
+    input: ListAgentRunsInput,
+) -> Result<AgentRunPage, String> {
+    let limit = i64::from(input.limit.unwrap_or(80).clamp(1, 500));
+    let mut items = fetch_agent_runs(pool, &input, limit + 1).await?;
+    let has_more = items.len() as i64 > limit;
+    if has_more {
+        // The extra row sits on the far side of the page from the cursor.
+        if input.after.is_some() && input.before.is_none() {
+            items.remove(0);
+        } else {
+            items.truncate(limit as usize);
+        }
+    }
+    let total = count_agent_runs(pool, &input).await?;
+    Ok(AgentRunPage {
+        items,
+        total,
+        has_more,
+    })
+}
+
+pub async fn count_agent_runs(
+    pool: &SqlitePool,
+    input: &ListAgentRunsInput,
+) -> Result<i64, String> {
+    let mut query_builder: QueryBuilder<'_, Sqlite> =
+        QueryBuilder::new("SELECT COUNT(*) FROM aop_agent_runs WHERE 1=1");
+    push_run_filters(&mut query_builder, input);
+    query_builder
+        .build_query_scalar::<i64>()
+        .fetch_one(pool)
+        .await
+        .map_err(|error| format!("Failed to count agent runs: {error}"))
+}
+
+async fn fetch_agent_runs(
+    pool: &SqlitePool,
+    input: &ListAgentRunsInput,
+    limit: i64,
+) -> Result<Vec<AgentRunRecord>, String> {
     let mut query_builder: QueryBuilder<'_, Sqlite> =
         QueryBuilder::new("SELECT id, root_task_id, task_id, tier, actor, persona, skill, provider, model_id, adapter_kind, status, started_at, ended_at, tokens_in, tokens_out, token_delta, cost_usd, metadata_json FROM aop_agent_runs WHERE 1=1");
+    push_run_filters(&mut query_builder, input);
+    if let Some(before) = &input.before {
+        query_builder
+            .push(" AND (started_at < ")
+            .push_bind(before.started_at)
+            .push(" OR (started_at = ")
+            .push_bind(before.started_at)
+            .push(" AND id < ")
+            .push_bind(before.id.as_str())
+            .push("))");
+    }
+    if let Some(after) = &input.after {
+        query_builder
+            .push(" AND (started_at > ")
+            .push_bind(after.started_at)
+            .push(" OR (started_at = ")
+            .push_bind(after.started_at)
+            .push(" AND id > ")
+            .push_bind(after.id.as_str())
+            .push("))");
+    }
 
+    // Paging towards newer runs takes the ones closest to the cursor, then flips them back.
+    let ascending = input.after.is_some() && input.before.is_none();
+    query_builder
+        .push(if ascending {
+            " ORDER BY started_at ASC, id ASC LIMIT "
+        } else {
+            " ORDER BY started_at DESC, id DESC LIMIT "
+        })
+        .push_bind(limit);
+
+    let mut runs = query_builder
+        .build_query_as::<AgentRunRecord>()
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to list agent runs: {error}"))?;
+    if ascending {
+        runs.reverse();
+    }
+    Ok(runs)
+}
+
+fn push_run_filters<'a>(
+    query_builder: &mut QueryBuilder<'a, Sqlite>,
+    input: &'a ListAgentRunsInput,
+) {
     if let Some(root_task_id) = input
         .root_task_id
         .as_deref()
         .map(str::trim)
         .filter(|value| !value.is_empty())
     {
-        query_builder.push(" AND root_task_id = ").push_bind(root_task_id);
+        query_builder
+            .push(" AND root_task_id = ")
+            .push_bind(root_task_id);
     }
     if let Some(task_id) = input
         .task_id
@@ -390,34 +684,112 @@ pub async fn list_agent_runs(
     {
         query_builder.push(" AND status = ").push_bind(status);
     }
+    if let Some(started_after) = input.started_after {
+        query_builder
+            .push(" AND started_at >= ")
+            .push_bind(started_after);
+    }
+    if let Some(started_before) = input.started_before {
+        query_builder
+            .push(" AND started_at < ")
+            .push_bind(started_before);
+    }
+}
 
-    query_builder
-        .push(" ORDER BY started_at DESC LIMIT ")
-        .push_bind(limit);
+pub async fn list_agent_events(
+    pool: &SqlitePool,
+    input: ListAgentEventsInput,
+) -> Result<Vec<AgentEventRecord>, String> {
+    let limit = i64::from(input.limit.unwrap_or(250).clamp(1, 2000));
+    fetch_agent_events(pool, &input, limit).await
+}
 
+/// Lists a page of events with `beforeId`/`afterId` keyset cursors.
+pub async fn page_agent_events(
+    pool: &SqlitePool,
+    input: ListAgentEventsInput,
+) -> Result<AgentEventPage, String> {
+    let limit = i64::from(input.limit.unwrap_or(250).clamp(1, 2000));
+    let mut items = fetch_agent_events(pool, &input, limit + 1).await?;
+    let has_more = items.len() as i64 > limit;
+    if has_more {
+        if input.after_id.is_some() && input.before_id.is_none() {
+            items.remove(0);
+        } else {
+            items.truncate(limit as usize);
+        }
+    }
+    let total = count_agent_events(pool, &input).await?;
+    Ok(AgentEventPage {
+        items,
+        total,
+        has_more,
+    })
+}
+
+pub async fn count_agent_events(
+    pool: &SqlitePool,
+    input: &ListAgentEventsInput,
+) -> Result<i64, String> {
+    let mut query_builder: QueryBuilder<'_, Sqlite> =
+        QueryBuilder::new("SELECT COUNT(*) FROM aop_agent_events WHERE 1=1");
+    push_event_filters(&mut query_builder, input);
     query_builder
-        .build_query_as::<AgentRunRecord>()
-        .fetch_all(pool)
+        .build_query_scalar::<i64>()
+        .fetch_one(pool)
         .await
-        .map_err(|error| format!("Failed to list agent runs: {error}"))
+        .map_err(|error| format!("Failed to count agent events: {error}"))
 }
 
-pub async fn list_agent_events(
+async fn fetch_agent_events(
     pool: &SqlitePool,
-    input: ListAgentEventsInput,
+    input: &ListAgentEventsInput,
+    limit: i64,
 ) -> Result<Vec<AgentEventRecord>, String> {
-    let limit = i64::from(input.limit.unwrap_or(250).clamp(1, 2000));
     let mut query_builder: QueryBuilder<'_, Sqlite> = QueryBuilder::new(
         "SELECT id, run_id, root_task_id, task_id, tier, actor, action, status, phase, message, provider, model_id, persona, skill, mcp_server, mcp_tool, latency_ms, retry_count, tokens_in, tokens_out, token_delta, cost_usd, payload_json, created_at FROM aop_agent_events WHERE 1=1",
     );
+    push_event_filters(&mut query_builder, input);
+    if let Some(before_id) = input.before_id {
+        query_builder.push(" AND id < ").push_bind(before_id);
+    }
+    if let Some(after_id) = input.after_id {
+        query_builder.push(" AND id > ").push_bind(after_id);
+    }
+
+    let ascending = input.after_id.is_some() && input.before_id.is_none();
+    query_builder
+        .push(if ascending {
+            " ORDER BY id ASC LIMIT "
+        } else {
+            " ORDER BY id DESC LIMIT "
+        })
+        .push_bind(limit);
+
+    let mut events = query_builder
+        .build_query_as::<AgentEventRecord>()
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to list agent events: {error}"))?;
+    if ascending {
+        events.reverse();
+    }
+    Ok(events)
+}
 
+fn push_event_filters<'a>(
+    query_builder: &mut QueryBuilder<'a, Sqlite>,
+    input: &'a ListAgentEventsInput,
+) {
     if let Some(root_task_id) = input
         .root_task_id
         .as_deref()
         .map(str::trim)
         .filter(|value| !value.is_empty())
     {
-        query_builder.push(" AND root_task_id = ").push_bind(root_task_id);
+        query_builder
+            .push(" AND root_task_id = ")
+            .push_bind(root_task_id);
     }
     if let Some(task_id) = input
         .task_id
@@ -446,16 +818,55 @@ pub async fn list_agent_events(
     if let Some(since_id) = input.since_id {
         query_builder.push(" AND id > ").push_bind(since_id);
     }
+    if let Some(created_after) = input.created_after {
+        query_builder
+            .push(" AND created_at >= ")
+            .push_bind(created_after);
+    }
+    if let Some(created_before) = input.created_before {
+        query_builder
+            .push(" AND created_at < ")
+            .push_bind(created_before);
+    }
+}
 
-    query_builder
-        .push(" ORDER BY id DESC LIMIT ")
-        .push_bind(limit);
+/// Sums the model calls recorded under `root_task_id` (events carrying token usage or a
+/// cost), per model and in total.
+pub async fn get_task_cost_rollup(
+    pool: &SqlitePool,
+    input: GetTaskCostRollupInput,
+) -> Result<TaskCostRollup, String> {
+    let root_task_id = input.root_task_id.trim();
+    if root_task_id.is_empty() {
+        return Err("rootTaskId is required".to_string());
+    }
 
-    query_builder
-        .build_query_as::<AgentEventRecord>()
-        .fetch_all(pool)
-        .await
-        .map_err(|error| format!("Failed to list agent events: {error}"))
+    let by_model = sqlx::query_as::<_, ModelCostRollup>(
+        r#"
+        SELECT provider, model_id, COUNT(*) AS calls,
+               COALESCE(SUM(tokens_in), 0) AS tokens_in,
+               COALESCE(SUM(tokens_out), 0) AS tokens_out,
+               COALESCE(SUM(cost_usd), 0.0) AS cost_usd
+        FROM aop_agent_events
+        WHERE root_task_id = ?
+          AND (cost_usd IS NOT NULL OR tokens_in IS NOT NULL OR tokens_out IS NOT NULL)
+        GROUP BY provider, model_id
+        ORDER BY cost_usd DESC, calls DESC
+        "#,
+    )
+    .bind(root_task_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to roll up task cost: {error}"))?;
+
+    Ok(TaskCostRollup {
+        root_task_id: root_task_id.to_string(),
+        calls: by_model.iter().map(|model| model.calls).sum(),
+        tokens_in: by_model.iter().map(|model| model.tokens_in).sum(),
+        tokens_out: by_model.iter().map(|model| model.tokens_out).sum(),
+        total_cost_usd: by_model.iter().map(|model| model.cost_usd).sum(),
+        by_model,
+    })
 }
 
 pub async fn list_model_health(
@@ -467,7 +878,8 @@ pub async fn list_model_health(
         r#"
         SELECT
             provider, model_id, total_calls, success_calls, failed_calls,
-            avg_latency_ms, avg_cost_usd, quality_score, last_error, last_used_at, updated_at
+            avg_latency_ms, avg_cost_usd, quality_score, last_error, last_used_at, updated_at,
+            consecutive_failures, circuit_open_until, timeout_calls
         FROM aop_model_health
         ORDER BY updated_at DESC
         LIMIT ?
@@ -488,7 +900,8 @@ pub async fn get_model_health(
         r#"
         SELECT
             provider, model_id, total_calls, success_calls, failed_calls,
-            avg_latency_ms, avg_cost_usd, quality_score, last_error, last_used_at, updated_at
+            avg_latency_ms, avg_cost_usd, quality_score, last_error, last_used_at, updated_at,
+            consecutive_failures, circuit_open_until, timeout_calls
         FROM aop_model_health
         WHERE provider = ? AND model_id = ?
         "#,
@@ -511,11 +924,32 @@ pub async fn update_model_health(
     }
 
     let now = Utc::now().timestamp();
+    let timed_out = !input.success && input.timed_out;
+    sqlx::query(
+        r#"
+        INSERT INTO aop_model_call_samples (
+            provider, model_id, success, timed_out, latency_ms, cost_usd, created_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(provider)
+    .bind(model_id)
+    .bind(input.success)
+    .bind(timed_out)
+    .bind(input.latency_ms)
+    .bind(input.cost_usd)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to record model call sample: {error}"))?;
+
     let current = get_model_health(pool, provider, model_id).await?;
     let next = if let Some(current) = current {
         let total_calls = current.total_calls.saturating_add(1);
         let success_calls = current.success_calls + i64::from(input.success);
         let failed_calls = current.failed_calls + i64::from(!input.success);
+        let timeout_calls = current.timeout_calls + i64::from(timed_out);
         let avg_latency_ms = input
             .latency_ms
             .map(|value| ewma(current.avg_latency_ms, value as f64, 0.20))
@@ -534,13 +968,25 @@ pub async fn update_model_health(
         } else {
             input.error.clone().filter(|value| !value.trim().is_empty())
         };
+        let consecutive_failures = if input.success {
+            0
+        } else {
+            current.consecutive_failures.saturating_add(1)
+        };
+        let circuit_open_until = if input.success {
+            None
+        } else {
+            tripped_until(input.circuit_breaker, consecutive_failures, now)
+                .or(current.circuit_open_until)
+        };
 
         sqlx::query(
             r#"
             UPDATE aop_model_health
             SET total_calls = ?, success_calls = ?, failed_calls = ?,
                 avg_latency_ms = ?, avg_cost_usd = ?, quality_score = ?,
-                last_error = ?, last_used_at = ?, updated_at = ?
+                last_error = ?, last_used_at = ?, updated_at = ?,
+                consecutive_failures = ?, circuit_open_until = ?, timeout_calls = ?
             WHERE provider = ? AND model_id = ?
             "#,
         )
@@ -553,6 +999,9 @@ pub async fn update_model_health(
         .bind(last_error.clone())
         .bind(now)
         .bind(now)
+        .bind(consecutive_failures)
+        .bind(circuit_open_until)
+        .bind(timeout_calls)
         .bind(provider)
         .bind(model_id)
         .execute(pool)
@@ -571,11 +1020,15 @@ pub async fn update_model_health(
             last_error,
             last_used_at: Some(now),
             updated_at: now,
+            consecutive_failures,
+            circuit_open_until,
+            timeout_calls,
         }
     } else {
         let total_calls = 1_i64;
         let success_calls = i64::from(input.success);
         let failed_calls = i64::from(!input.success);
+        let timeout_calls = i64::from(timed_out);
         let avg_latency_ms = input.latency_ms.unwrap_or(0) as f64;
         let avg_cost_usd = input.cost_usd.unwrap_or(0.0);
         let quality_score = if input.success { 0.72 } else { 0.62 };
@@ -584,14 +1037,17 @@ pub async fn update_model_health(
         } else {
             input.error.clone().filter(|value| !value.trim().is_empty())
         };
+        let consecutive_failures = failed_calls;
+        let circuit_open_until = tripped_until(input.circuit_breaker, consecutive_failures, now);
 
         sqlx::query(
             r#"
             INSERT INTO aop_model_health (
                 provider, model_id, total_calls, success_calls, failed_calls,
-                avg_latency_ms, avg_cost_usd, quality_score, last_error, last_used_at, updated_at
+                avg_latency_ms, avg_cost_usd, quality_score, last_error, last_used_at, updated_at,
+                consecutive_failures, circuit_open_until, timeout_calls
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(provider)
@@ -605,6 +1061,9 @@ pub async fn update_model_health(
         .bind(last_error.clone())
         .bind(now)
         .bind(now)
+        .bind(consecutive_failures)
+        .bind(circuit_open_until)
+        .bind(timeout_calls)
         .execute(pool)
         .await
         .map_err(|error| format!("Failed to insert model health: {error}"))?;
@@ -621,12 +1080,182 @@ pub async fn update_model_health(
             last_error,
             last_used_at: Some(now),
             updated_at: now,
+            consecutive_failures,
+            circuit_open_until,
+            timeout_calls,
         }
     };
 
     Ok(next)
 }
 
+/// When the streak reaches the threshold, the circuit opens (or reopens after a failed
+/// probe) for the policy's window.
+fn tripped_until(
+    policy: Option<CircuitBreakerPolicy>,
+    consecutive_failures: i64,
+    now: i64,
+) -> Option<i64> {
+    let policy = policy.filter(|policy| policy.failure_threshold > 0)?;
+    (consecutive_failures >= i64::from(policy.failure_threshold)).then(|| now + policy.open_secs)
+}
+
+/// Shifts a model's quality score by `delta` for a downstream outcome, such as one of its
+/// mutations failing tests. Models without health history yet are left alone.
+pub async fn adjust_model_quality(
+    pool: &SqlitePool,
+    provider: &str,
+    model_id: &str,
+    delta: f64,
+) -> Result<Option<ModelHealthRecord>, String> {
+    sqlx::query(
+        r#"
+        UPDATE aop_model_health
+        SET quality_score = MIN(MAX(quality_score + ?, 0.05), 0.99), updated_at = ?
+        WHERE provider = ? AND model_id = ?
+        "#,
+    )
+    .bind(delta)
+    .bind(Utc::now().timestamp())
+    .bind(provider.trim())
+    .bind(model_id.trim())
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to adjust model quality: {error}"))?;
+
+    get_model_health(pool, provider, model_id).await
+}
+
+/// Success rate, latency percentiles and cost per provider/model in time buckets over a
+/// window, so degradation shows up as a trend rather than only in the running averages.
+pub async fn get_model_health_trends(
+    pool: &SqlitePool,
+    input: GetModelHealthTrendsInput,
+) -> Result<ModelHealthTrends, String> {
+    let window_secs = i64::from(input.window_hours.unwrap_or(24).clamp(1, 24 * 90)) * 3_600;
+    let bucket_secs = (i64::from(input.bucket_minutes.unwrap_or(60).max(1)) * 60)
+        .max(window_secs / MAX_TREND_BUCKETS);
+    let window_end = Utc::now().timestamp();
+    let window_start = window_end - window_secs;
+    let provider = input
+        .provider
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+    let model_id = input
+        .model_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+
+    let samples = sqlx::query_as::<_, ModelCallSample>(
+        r#"
+        SELECT provider, model_id, success, timed_out, latency_ms, cost_usd, created_at
+        FROM aop_model_call_samples
+        WHERE created_at >= ?
+          AND (? IS NULL OR provider = ?)
+          AND (? IS NULL OR model_id = ?)
+        ORDER BY provider ASC, model_id ASC, created_at ASC, id ASC
+        "#,
+    )
+    .bind(window_start)
+    .bind(provider)
+    .bind(provider)
+    .bind(model_id)
+    .bind(model_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to load model call samples: {error}"))?;
+
+    Ok(ModelHealthTrends {
+        window_start,
+        window_end,
+        bucket_secs,
+        series: build_health_series(&samples, window_start, window_end, bucket_secs),
+    })
+}
+
+/// Groups samples sorted by provider, model and time into one series per model.
+fn build_health_series(
+    samples: &[ModelCallSample],
+    window_start: i64,
+    window_end: i64,
+    bucket_secs: i64,
+) -> Vec<ModelHealthSeries> {
+    let bucket_count =
+        ((window_end - window_start + bucket_secs - 1) / bucket_secs).max(1) as usize;
+    let mut series = Vec::new();
+    for model_samples in samples
+        .chunk_by(|left, right| left.provider == right.provider && left.model_id == right.model_id)
+    {
+        let mut buckets: Vec<Vec<&ModelCallSample>> = vec![Vec::new(); bucket_count];
+        let mut longest_failure_streak = 0;
+        let mut current_failure_streak = 0;
+        for sample in model_samples {
+            let index = ((sample.created_at - window_start).max(0) / bucket_secs) as usize;
+            buckets[index.min(bucket_count - 1)].push(sample);
+            current_failure_streak = if sample.success {
+                0
+            } else {
+                current_failure_streak + 1
+            };
+            longest_failure_streak = longest_failure_streak.max(current_failure_streak);
+        }
+
+        let all = model_samples.iter().collect::<Vec<_>>();
+        let (latency_p50_ms, latency_p95_ms) = latency_percentiles(&all);
+        let successes = all.iter().filter(|sample| sample.success).count();
+        series.push(ModelHealthSeries {
+            provider: model_samples[0].provider.clone(),
+            model_id: model_samples[0].model_id.clone(),
+            calls: all.len() as i64,
+            success_rate: successes as f64 / all.len() as f64,
+            latency_p50_ms,
+            latency_p95_ms,
+            total_cost_usd: all.iter().filter_map(|sample| sample.cost_usd).sum(),
+            longest_failure_streak,
+            current_failure_streak,
+            buckets: buckets
+                .iter()
+                .enumerate()
+                .map(|(index, bucket)| {
+                    let (latency_p50_ms, latency_p95_ms) = latency_percentiles(bucket);
+                    let failures = bucket.iter().filter(|sample| !sample.success).count();
+                    ModelHealthBucket {
+                        bucket_start: window_start + index as i64 * bucket_secs,
+                        calls: bucket.len() as i64,
+                        failures: failures as i64,
+                        timeouts: bucket.iter().filter(|sample| sample.timed_out).count() as i64,
+                        success_rate: (!bucket.is_empty())
+                            .then(|| 1.0 - failures as f64 / bucket.len() as f64),
+                        latency_p50_ms,
+                        latency_p95_ms,
+                        cost_usd: bucket.iter().filter_map(|sample| sample.cost_usd).sum(),
+                    }
+                })
+                .collect(),
+        });
+    }
+    series
+}
+
+/// Nearest-rank p50 and p95 of the samples that reported a latency.
+fn latency_percentiles(samples: &[&ModelCallSample]) -> (Option<i64>, Option<i64>) {
+    let mut latencies = samples
+        .iter()
+        .filter_map(|sample| sample.latency_ms)
+        .collect::<Vec<_>>();
+    if latencies.is_empty() {
+        return (None, None);
+    }
+    latencies.sort_unstable();
+    let rank = |percentile: f64| {
+        let index = (percentile * latencies.len() as f64).ceil() as usize;
+        latencies[index.clamp(1, latencies.len()) - 1]
+    };
+    (Some(rank(0.50)), Some(rank(0.95)))
+}
+
 pub async fn get_mission_control_snapshot(
     pool: &SqlitePool,
     input: GetMissionControlSnapshotInput,
@@ -648,6 +1277,7 @@ pub async fn get_mission_control_snapshot(
             tier: None,
             status: None,
             limit: Some(limit),
+            ..Default::default()
         },
     )
     .await?
@@ -658,26 +1288,146 @@ pub async fn get_mission_control_snapshot(
     let recent_events = list_agent_events(
         pool,
         ListAgentEventsInput {
-            root_task_id: root_filter,
+            root_task_id: root_filter.clone(),
             task_id: None,
             actor: None,
             action: None,
             since_id: None,
             limit: Some(limit.saturating_mul(3)),
+            ..Default::default()
         },
     )
     .await?;
 
     let model_health = list_model_health(pool, Some(100)).await?;
+    let stalled_runs = list_stalled_runs(pool, root_filter.as_deref()).await?;
 
     Ok(MissionControlSnapshot {
         generated_at: Utc::now().timestamp(),
         active_runs,
         recent_events,
         model_health,
+        provider_queues: llm_rate_limit::metrics(),
+        execution_queue: task_runtime::execution_queue(),
+        stalled_runs,
+        mcp_sessions: session::health(),
     })
 }
 
+/// Marks the open runs of `task_id` as alive, clearing any stall flag.
+pub async fn record_run_heartbeat(pool: &SqlitePool, task_id: &str) -> Result<(), String> {
+    sqlx::query(
+        r#"
+        UPDATE aop_agent_runs
+        SET heartbeat_at = ?, stalled_at = NULL
+        WHERE task_id = ? AND ended_at IS NULL
+        "#,
+    )
+    .bind(Utc::now().timestamp())
+    .bind(task_id.trim())
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to record run heartbeat: {error}"))?;
+    Ok(())
+}
+
+/// Flags executing runs of executing tasks silent for `stale_after_seconds`, returning only
+/// the runs newly flagged by this call.
+pub async fn flag_stalled_runs(
+    pool: &SqlitePool,
+    stale_after_seconds: i64,
+) -> Result<Vec<StalledRunRecord>, String> {
+    let now = Utc::now().timestamp();
+    let run_ids = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT r.id
+        FROM aop_agent_runs r
+        JOIN aop_tasks t ON t.id = r.task_id
+        WHERE r.ended_at IS NULL
+          AND r.stalled_at IS NULL
+          AND r.status = 'executing'
+          AND t.status = 'executing'
+          AND COALESCE(r.heartbeat_at, r.started_at) <= ?
+        "#,
+    )
+    .bind(now - stale_after_seconds.max(1))
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to find stalled runs: {error}"))?;
+    if run_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut builder = QueryBuilder::<Sqlite>::new("UPDATE aop_agent_runs SET stalled_at = ");
+    builder.push_bind(now);
+    builder.push(" WHERE id IN (");
+    {
+        let mut separated = builder.separated(", ");
+        for run_id in &run_ids {
+            separated.push_bind(run_id);
+        }
+    }
+    builder.push(")");
+    builder
+        .build()
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to flag stalled runs: {error}"))?;
+
+    let flagged = list_stalled_runs(pool, None).await?;
+    Ok(flagged
+        .into_iter()
+        .filter(|run| run_ids.contains(&run.run_id))
+        .collect())
+}
+
+pub async fn list_stalled_runs(
+    pool: &SqlitePool,
+    root_task_id: Option<&str>,
+) -> Result<Vec<StalledRunRecord>, String> {
+    sqlx::query_as::<_, StalledRunRecord>(
+        r#"
+        SELECT r.id AS run_id, r.root_task_id, r.task_id, r.tier, r.actor,
+               COALESCE(r.heartbeat_at, r.started_at) AS heartbeat_at, r.stalled_at
+        FROM aop_agent_runs r
+        JOIN aop_tasks t ON t.id = r.task_id
+        WHERE r.stalled_at IS NOT NULL
+          AND r.ended_at IS NULL
+          AND t.status = 'executing'
+          AND (? IS NULL OR r.root_task_id = ?)
+        ORDER BY heartbeat_at ASC
+        "#,
+    )
+    .bind(root_task_id)
+    .bind(root_task_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to list stalled runs: {error}"))
+}
+
+/// Ends a run without a closing event, e.g. after its task was stopped from outside.
+pub async fn close_run(pool: &SqlitePool, run_id: &str, status: &str) -> Result<(), String> {
+    sqlx::query(
+        "UPDATE aop_agent_runs SET status = ?, ended_at = ?, stalled_at = NULL WHERE id = ?",
+    )
+    .bind(status)
+    .bind(Utc::now().timestamp())
+    .bind(run_id)
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to close agent run: {error}"))?;
+    Ok(())
+}
+
+/// Looks up the task behind a stalled run so it can be stopped or restarted.
+pub async fn get_stalled_run(pool: &SqlitePool, run_id: &str) -> Result<StalledRunRecord, String> {
+    list_stalled_runs(pool, None)
+        .await?
+        .into_iter()
+        .find(|run| run.run_id == run_id.trim())
+        .ok_or_else(|| format!("Run '{}' is not stalled", run_id.trim()))
+}
+
 pub async fn archive_telemetry(
     pool: &SqlitePool,
     archive_root: &Path,
@@ -686,13 +1436,35 @@ pub async fn archive_telemetry(
     let effective_days = retention_days.clamp(1, 365);
     let cutoff = Utc::now().timestamp() - i64::from(effective_days) * 86_400;
 
+    // Samples only feed health trends, so they are dropped rather than archived.
+    loop {
+        let deleted = sqlx::query(
+            r#"
+            DELETE FROM aop_model_call_samples
+            WHERE id IN (
+                SELECT id FROM aop_model_call_samples WHERE created_at < ? LIMIT ?
+            )
+            "#,
+        )
+        .bind(cutoff)
+        .bind(RETENTION_DELETE_BATCH as i64)
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to delete old model call samples: {error}"))?
+        .rows_affected();
+        if deleted < RETENTION_DELETE_BATCH as u64 {
+            break;
+        }
+        tokio::task::yield_now().await;
+    }
+
     let events = sqlx::query_as::<_, AgentEventRecord>(
         r#"
         SELECT id, run_id, root_task_id, task_id, tier, actor, action, status, phase, message,
                provider, model_id, persona, skill, mcp_server, mcp_tool, latency_ms, retry_count,
                tokens_in, tokens_out, token_delta, cost_usd, payload_json, created_at
         FROM aop_agent_events
-        WHERE created_at < ?
+        WHERE COALESCE(imported_at, created_at) < ?
         ORDER BY id ASC
         "#,
     )
@@ -707,7 +1479,7 @@ pub async fn archive_telemetry(
                adapter_kind, status, started_at, ended_at, tokens_in, tokens_out, token_delta,
                cost_usd, metadata_json
         FROM aop_agent_runs
-        WHERE ended_at IS NOT NULL AND ended_at < ?
+        WHERE ended_at IS NOT NULL AND COALESCE(imported_at, ended_at) < ?
         ORDER BY ended_at ASC
         "#,
     )
@@ -767,16 +1539,42 @@ pub async fn archive_telemetry(
         .flush()
         .map_err(|error| format!("Failed to flush telemetry archive: {error}"))?;
 
-    sqlx::query("DELETE FROM aop_agent_events WHERE created_at < ?")
-        .bind(cutoff)
-        .execute(pool)
-        .await
-        .map_err(|error| format!("Failed to delete old agent events: {error}"))?;
-    sqlx::query("DELETE FROM aop_agent_runs WHERE ended_at IS NOT NULL AND ended_at < ?")
-        .bind(cutoff)
-        .execute(pool)
-        .await
-        .map_err(|error| format!("Failed to delete old agent runs: {error}"))?;
+    // Only the archived rows are deleted, a batch per transaction, so agents writing
+    // telemetry meanwhile wait for one short batch at most.
+    for batch in events.chunks(RETENTION_DELETE_BATCH) {
+        let mut query_builder: QueryBuilder<'_, Sqlite> =
+            QueryBuilder::new("DELETE FROM aop_agent_events WHERE id IN (");
+        {
+            let mut separated = query_builder.separated(", ");
+            for event in batch {
+                separated.push_bind(event.id);
+            }
+        }
+        query_builder
+            .push(")")
+            .build()
+            .execute(pool)
+            .await
+            .map_err(|error| format!("Failed to delete old agent events: {error}"))?;
+        tokio::task::yield_now().await;
+    }
+    for batch in runs.chunks(RETENTION_DELETE_BATCH) {
+        let mut query_builder: QueryBuilder<'_, Sqlite> =
+            QueryBuilder::new("DELETE FROM aop_agent_runs WHERE id IN (");
+        {
+            let mut separated = query_builder.separated(", ");
+            for run in batch {
+                separated.push_bind(run.id.as_str());
+            }
+        }
+        query_builder
+            .push(")")
+            .build()
+            .execute(pool)
+            .await
+            .map_err(|error| format!("Failed to delete old agent runs: {error}"))?;
+        tokio::task::yield_now().await;
+    }
 
     Ok(ArchiveTelemetryResult {
         retention_days: effective_days,
@@ -850,7 +1648,8 @@ async fn upsert_run(pool: &SqlitePool, next: &AgentRunRecord) -> Result<(), Stri
             UPDATE aop_agent_runs
             SET root_task_id = ?, task_id = ?, tier = ?, actor = ?, persona = ?, skill = ?,
                 provider = ?, model_id = ?, adapter_kind = ?, status = ?, ended_at = ?,
-                tokens_in = ?, tokens_out = ?, token_delta = ?, cost_usd = ?, metadata_json = ?
+                tokens_in = ?, tokens_out = ?, token_delta = ?, cost_usd = ?, metadata_json = ?,
+                heartbeat_at = ?, stalled_at = NULL
             WHERE id = ?
             "#,
         )
@@ -870,6 +1669,7 @@ async fn upsert_run(pool: &SqlitePool, next: &AgentRunRecord) -> Result<(), Stri
         .bind(merged.token_delta)
         .bind(merged.cost_usd)
         .bind(merged.metadata_json)
+        .bind(Utc::now().timestamp())
         .bind(next.id.as_str())
         .execute(pool)
         .await
@@ -880,9 +1680,9 @@ async fn upsert_run(pool: &SqlitePool, next: &AgentRunRecord) -> Result<(), Stri
             INSERT INTO aop_agent_runs (
                 id, root_task_id, task_id, tier, actor, persona, skill, provider, model_id,
                 adapter_kind, status, started_at, ended_at, tokens_in, tokens_out, token_delta,
-                cost_usd, metadata_json
+                cost_usd, metadata_json, heartbeat_at
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(next.id.as_str())
@@ -903,6 +1703,7 @@ async fn upsert_run(pool: &SqlitePool, next: &AgentRunRecord) -> Result<(), Stri
         .bind(next.token_delta)
         .bind(next.cost_usd)
         .bind(next.metadata_json.clone())
+        .bind(next.started_at)
         .execute(pool)
         .await
         .map_err(|error| format!("Failed to insert agent run: {error}"))?;
@@ -915,40 +1716,26 @@ async fn infer_task_scope(
     pool: &SqlitePool,
     task_id: &str,
 ) -> Result<(Option<String>, Option<i64>), String> {
-    let mut current_id = task_id.trim().to_string();
-    if current_id.is_empty() {
+    let task_id = task_id.trim();
+    if task_id.is_empty() {
         return Ok((None, None));
     }
 
-    let mut root_id = Some(current_id.clone());
-    let mut tier = None;
-    let mut safety = 0_u8;
-
-    while safety < 48 {
-        let row = sqlx::query_as::<_, (Option<String>, i64)>(
-            "SELECT parent_id, tier FROM aop_tasks WHERE id = ?",
-        )
-        .bind(current_id.as_str())
-        .fetch_optional(pool)
-        .await
-        .map_err(|error| format!("Failed to infer task scope: {error}"))?;
-
-        let Some((parent_id, task_tier)) = row else {
-            break;
-        };
-        if tier.is_none() {
-            tier = Some(task_tier);
-        }
-        root_id = Some(current_id.clone());
-        if let Some(parent) = parent_id.filter(|value| !value.trim().is_empty()) {
-            current_id = parent;
-            safety = safety.saturating_add(1);
-            continue;
-        }
-        break;
-    }
-
-    Ok((root_id, tier))
+    let row = sqlx::query_as::<_, (Option<String>, i64)>(
+        "SELECT root_task_id, tier FROM aop_tasks WHERE id = ?",
+    )
+    .bind(task_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|error| format!("Failed to infer task scope: {error}"))?;
+
+    Ok(match row {
+        Some((root_task_id, tier)) => (
+            Some(root_task_id.unwrap_or_else(|| task_id.to_string())),
+            Some(tier),
+        ),
+        None => (Some(task_id.to_string()), None),
+    })
 }
 
 fn parse_task_id_from_message(message: Option<&str>) -> Option<String> {
@@ -1159,6 +1946,7 @@ mod tests {
                 tier: None,
                 status: None,
                 limit: Some(20),
+                ..Default::default()
             },
         )
         .await
@@ -1175,6 +1963,7 @@ mod tests {
                 action: None,
                 since_id: None,
                 limit: Some(20),
+                ..Default::default()
             },
         )
         .await
@@ -1183,6 +1972,205 @@ mod tests {
         assert_eq!(events[0].model_id.as_deref(), Some("sonnet"));
     }
 
+    #[tokio::test]
+    async fn flags_executing_runs_without_heartbeats() {
+        let pool = setup_test_pool().await;
+        let task = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 3,
+                domain: "platform".to_string(),
+                objective: "hang".to_string(),
+                token_budget: 1000,
+            },
+        )
+        .await
+        .expect("task should be created");
+        tasks::update_task_status(
+            &pool,
+            tasks::UpdateTaskStatusInput {
+                task_id: task.id.clone(),
+                status: tasks::TaskStatus::Executing,
+                error_message: None,
+            },
+        )
+        .await
+        .expect("task should start");
+        record_task_activity_event(
+            &pool,
+            "tier3_frontend",
+            "specialist_execution_started",
+            Some(task.id.as_str()),
+            None,
+        )
+        .await
+        .expect("telemetry should be recorded");
+
+        assert!(flag_stalled_runs(&pool, 600).await.unwrap().is_empty());
+        sqlx::query("UPDATE aop_agent_runs SET heartbeat_at = heartbeat_at - 900")
+            .execute(&pool)
+            .await
+            .expect("heartbeat should be rewound");
+
+        let flagged = flag_stalled_runs(&pool, 600).await.unwrap();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].task_id, task.id);
+        assert!(flag_stalled_runs(&pool, 600).await.unwrap().is_empty());
+        assert_eq!(list_stalled_runs(&pool, None).await.unwrap().len(), 1);
+
+        record_run_heartbeat(&pool, &task.id).await.unwrap();
+        assert!(list_stalled_runs(&pool, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn pages_events_and_runs_with_keyset_cursors_and_totals() {
+        let pool = setup_test_pool().await;
+        let task = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 1,
+                domain: "platform".to_string(),
+                objective: "history".to_string(),
+                token_budget: 5000,
+            },
+        )
+        .await
+        .expect("task should be created");
+        for index in 0..5 {
+            record_task_activity_event(
+                &pool,
+                "tier1_orchestrator",
+                "model_call",
+                Some(task.id.as_str()),
+                Some(format!("tokensIn={index}").as_str()),
+            )
+            .await
+            .expect("telemetry should be recorded");
+        }
+
+        let filter = ListAgentEventsInput {
+            task_id: Some(task.id.clone()),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let first = page_agent_events(&pool, filter.clone())
+            .await
+            .expect("first page should list");
+        assert_eq!((first.items.len(), first.total, first.has_more), (2, 5, true));
+        let oldest_on_first = first.items[1].id;
+
+        let second = page_agent_events(
+            &pool,
+            ListAgentEventsInput {
+                before_id: Some(oldest_on_first),
+                ..filter.clone()
+            },
+        )
+        .await
+        .expect("second page should list");
+        assert_eq!(second.items[0].id, oldest_on_first - 1);
+        assert_eq!(second.total, 5);
+
+        let back = page_agent_events(
+            &pool,
+            ListAgentEventsInput {
+                after_id: Some(second.items[0].id),
+                ..filter.clone()
+            },
+        )
+        .await
+        .expect("newer page should list");
+        assert_eq!(
+            back.items.iter().map(|event| event.id).collect::<Vec<_>>(),
+            first.items.iter().map(|event| event.id).collect::<Vec<_>>()
+        );
+        assert!(!back.has_more);
+
+        let future = page_agent_events(
+            &pool,
+            ListAgentEventsInput {
+                created_after: Some(Utc::now().timestamp() + 60),
+                ..filter
+            },
+        )
+        .await
+        .expect("time-filtered page should list");
+        assert_eq!((future.items.len(), future.total), (0, 0));
+
+        let runs = page_agent_runs(
+            &pool,
+            ListAgentRunsInput {
+                task_id: Some(task.id.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("runs should page");
+        assert_eq!((runs.items.len(), runs.total, runs.has_more), (1, 1, false));
+    }
+
+    #[tokio::test]
+    async fn rolls_up_cost_per_model_under_the_root_task() {
+        let pool = setup_test_pool().await;
+        let root = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 1,
+                domain: "platform".to_string(),
+                objective: "cost rollup".to_string(),
+                token_budget: 5000,
+            },
+        )
+        .await
+        .expect("root task should be created");
+        let child = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: Some(root.id.clone()),
+                tier: 3,
+                domain: "frontend".to_string(),
+                objective: "specialist".to_string(),
+                token_budget: 1000,
+            },
+        )
+        .await
+        .expect("child task should be created");
+
+        for (task_id, details) in [
+            (&root.id, "model=openai/gpt-5 tokensIn=1000 tokensOut=200 costUsd=0.010000"),
+            (&child.id, "model=openai/gpt-5-mini tokensIn=400 tokensOut=100 costUsd=0.000300"),
+            (&child.id, "model=openai/gpt-5-mini tokensIn=600 tokensOut=50 costUsd=0.000250"),
+            (&child.id, "status=executing"),
+        ] {
+            record_task_activity_event(
+                &pool,
+                "tier1_orchestrator",
+                "model_call",
+                Some(task_id.as_str()),
+                Some(details),
+            )
+            .await
+            .expect("telemetry should be recorded");
+        }
+
+        let rollup = get_task_cost_rollup(
+            &pool,
+            GetTaskCostRollupInput {
+                root_task_id: root.id.clone(),
+            },
+        )
+        .await
+        .expect("rollup should compute");
+        assert_eq!(rollup.calls, 3);
+        assert_eq!((rollup.tokens_in, rollup.tokens_out), (2000, 350));
+        assert!((rollup.total_cost_usd - 0.01055).abs() < 1e-9);
+        assert_eq!(rollup.by_model[0].model_id.as_deref(), Some("gpt-5"));
+        assert_eq!(rollup.by_model[1].calls, 2);
+    }
+
     #[tokio::test]
     async fn updates_model_health_scores() {
         let pool = setup_test_pool().await;
@@ -1196,6 +2184,8 @@ mod tests {
                 latency_ms: Some(420),
                 cost_usd: Some(0.02),
                 error: None,
+                timed_out: false,
+                circuit_breaker: None,
             },
         )
         .await
@@ -1212,11 +2202,99 @@ mod tests {
                 latency_ms: Some(900),
                 cost_usd: Some(0.03),
                 error: Some("timeout".to_string()),
+                timed_out: true,
+                circuit_breaker: None,
             },
         )
         .await
         .expect("model health should update");
         assert_eq!(failure.total_calls, 2);
         assert_eq!(failure.failed_calls, 1);
+        assert_eq!(failure.timeout_calls, 1);
+    }
+
+    #[tokio::test]
+    async fn failure_streak_opens_and_success_closes_circuit() {
+        let pool = setup_test_pool().await;
+        let outcome = |success: bool| ModelCallOutcomeInput {
+            provider: "openai".to_string(),
+            model_id: "gpt-5".to_string(),
+            success,
+            error: (!success).then(|| "503".to_string()),
+            circuit_breaker: Some(CircuitBreakerPolicy {
+                failure_threshold: 2,
+                open_secs: 300,
+            }),
+            ..Default::default()
+        };
+
+        let first = update_model_health(&pool, outcome(false))
+            .await
+            .expect("model health should update");
+        assert_eq!(first.consecutive_failures, 1);
+        assert!(!first.circuit_open(Utc::now().timestamp()));
+
+        let second = update_model_health(&pool, outcome(false))
+            .await
+            .expect("model health should update");
+        assert_eq!(second.consecutive_failures, 2);
+        assert!(second.circuit_open(Utc::now().timestamp()));
+        let stored = get_model_health(&pool, "openai", "gpt-5")
+            .await
+            .expect("model health should load")
+            .expect("model health should exist");
+        assert_eq!(stored.circuit_open_until, second.circuit_open_until);
+
+        let recovered = update_model_health(&pool, outcome(true))
+            .await
+            .expect("model health should update");
+        assert_eq!(recovered.consecutive_failures, 0);
+        assert_eq!(recovered.circuit_open_until, None);
+    }
+
+    #[tokio::test]
+    async fn buckets_call_samples_into_trends_with_failure_streaks() {
+        let pool = setup_test_pool().await;
+        for (success, latency_ms) in [(true, 100), (false, 900), (false, 1_200), (true, 300)] {
+            update_model_health(
+                &pool,
+                ModelCallOutcomeInput {
+                    provider: "openai".to_string(),
+                    model_id: "gpt-5".to_string(),
+                    success,
+                    latency_ms: Some(latency_ms),
+                    cost_usd: Some(0.01),
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("outcome should be recorded");
+        }
+
+        let trends = get_model_health_trends(
+            &pool,
+            GetModelHealthTrendsInput {
+                window_hours: Some(2),
+                bucket_minutes: Some(30),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("trends should load");
+        assert_eq!(trends.series.len(), 1);
+        let series = &trends.series[0];
+        assert_eq!(series.buckets.len(), 4);
+        assert_eq!((series.calls, series.success_rate), (4, 0.5));
+        assert_eq!(
+            (series.latency_p50_ms, series.latency_p95_ms),
+            (Some(300), Some(1_200))
+        );
+        assert_eq!(
+            (series.longest_failure_streak, series.current_failure_streak),
+            (2, 0)
+        );
+        let latest = series.buckets.last().expect("latest bucket");
+        assert_eq!((latest.calls, latest.failures), (4, 2));
+        assert!(series.buckets[0].success_rate.is_none());
     }
 }
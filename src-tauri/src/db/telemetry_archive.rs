@@ -0,0 +1,471 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+use crate::db::telemetry::{
+    self, AgentEventRecord, AgentRunRecord, ListAgentEventsInput, ListAgentRunsInput,
+};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportTelemetryArchiveInput {
+    /// Archive file name, e.g. `telemetry_20250101_120000.jsonl`; every archive when empty.
+    pub file_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportTelemetryArchiveResult {
+    pub files_imported: Vec<String>,
+    pub events_imported: usize,
+    pub runs_imported: usize,
+    /// Rows already present in the database, and lines that could not be parsed.
+    pub rows_skipped: usize,
+}
+
+/// One line of an archive file as written by `telemetry::archive_telemetry`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+enum ArchiveEntry {
+    AgentEvent(AgentEventRecord),
+    AgentRun(AgentRunRecord),
+}
+
+/// Moves archived telemetry back into the database, deleting each archive file once its
+/// rows are restored. Restored rows are archived again after a full retention window.
+pub async fn import_telemetry_archive(
+    pool: &SqlitePool,
+    archive_root: &Path,
+    input: ImportTelemetryArchiveInput,
+) -> Result<ImportTelemetryArchiveResult, String> {
+    let files = match input
+        .file_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        Some(file_name) => {
+            if archive_written_at(file_name).is_none() {
+                return Err(format!("'{file_name}' is not a telemetry archive file"));
+            }
+            let path = archive_root.join(file_name);
+            if !path.is_file() {
+                return Err(format!("Telemetry archive '{file_name}' does not exist"));
+            }
+            vec![path]
+        }
+        None => archive_files(archive_root, None)?,
+    };
+
+    let now = Utc::now().timestamp();
+    let mut result = ImportTelemetryArchiveResult {
+        files_imported: Vec::new(),
+        events_imported: 0,
+        runs_imported: 0,
+        rows_skipped: 0,
+    };
+    for path in files {
+        let (entries, unparsed) = read_archive(&path)?;
+        result.rows_skipped += unparsed;
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|error| format!("Failed to begin archive import: {error}"))?;
+        for entry in entries {
+            let inserted = match &entry {
+                ArchiveEntry::AgentEvent(event) => sqlx::query(
+                    r#"
+                    INSERT OR IGNORE INTO aop_agent_events (
+                        id, run_id, root_task_id, task_id, tier, actor, action, status, phase,
+                        message, provider, model_id, persona, skill, mcp_server, mcp_tool,
+                        latency_ms, retry_count, tokens_in, tokens_out, token_delta, cost_usd,
+                        payload_json, created_at, imported_at
+                    )
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+                            ?, ?)
+                    "#,
+                )
+                .bind(event.id)
+                .bind(event.run_id.as_deref())
+                .bind(event.root_task_id.as_deref())
+                .bind(event.task_id.as_deref())
+                .bind(event.tier)
+                .bind(event.actor.as_str())
+                .bind(event.action.as_str())
+                .bind(event.status.as_deref())
+                .bind(event.phase.as_deref())
+                .bind(event.message.as_deref())
+                .bind(event.provider.as_deref())
+                .bind(event.model_id.as_deref())
+                .bind(event.persona.as_deref())
+                .bind(event.skill.as_deref())
+                .bind(event.mcp_server.as_deref())
+                .bind(event.mcp_tool.as_deref())
+                .bind(event.latency_ms)
+                .bind(event.retry_count)
+                .bind(event.tokens_in)
+                .bind(event.tokens_out)
+                .bind(event.token_delta)
+                .bind(event.cost_usd)
+                .bind(event.payload_json.as_deref())
+                .bind(event.created_at)
+                .bind(now)
+                .execute(&mut *tx)
+                .await
+                .map_err(|error| format!("Failed to import archived event: {error}"))?,
+                ArchiveEntry::AgentRun(run) => sqlx::query(
+                    r#"
+                    INSERT OR IGNORE INTO aop_agent_runs (
+                        id, root_task_id, task_id, tier, actor, persona, skill, provider,
+                        model_id, adapter_kind, status, started_at, ended_at, tokens_in,
+                        tokens_out, token_delta, cost_usd, metadata_json, imported_at
+                    )
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                    "#,
+                )
+                .bind(run.id.as_str())
+                .bind(run.root_task_id.as_deref())
+                .bind(run.task_id.as_deref())
+                .bind(run.tier)
+                .bind(run.actor.as_str())
+                .bind(run.persona.as_deref())
+                .bind(run.skill.as_deref())
+                .bind(run.provider.as_deref())
+                .bind(run.model_id.as_deref())
+                .bind(run.adapter_kind.as_deref())
+                .bind(run.status.as_str())
+                .bind(run.started_at)
+                .bind(run.ended_at)
+                .bind(run.tokens_in)
+                .bind(run.tokens_out)
+                .bind(run.token_delta)
+                .bind(run.cost_usd)
+                .bind(run.metadata_json.as_deref())
+                .bind(now)
+                .execute(&mut *tx)
+                .await
+                .map_err(|error| format!("Failed to import archived run: {error}"))?,
+            };
+            match (inserted.rows_affected() > 0, &entry) {
+                (true, ArchiveEntry::AgentEvent(_)) => result.events_imported += 1,
+                (true, ArchiveEntry::AgentRun(_)) => result.runs_imported += 1,
+                (false, _) => result.rows_skipped += 1,
+            }
+        }
+        tx.commit()
+            .await
+            .map_err(|error| format!("Failed to commit archive import: {error}"))?;
+
+        fs::remove_file(&path)
+            .map_err(|error| format!("Failed to remove imported archive: {error}"))?;
+        result.files_imported.push(
+            path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+        );
+    }
+    Ok(result)
+}
+
+/// `telemetry::list_agent_runs` over the database and the archive files together.
+pub async fn list_agent_runs_with_archive(
+    pool: &SqlitePool,
+    archive_root: &Path,
+    input: ListAgentRunsInput,
+) -> Result<Vec<AgentRunRecord>, String> {
+    let limit = input.limit.unwrap_or(80).clamp(1, 500) as usize;
+    let mut runs = telemetry::list_agent_runs(pool, input.clone()).await?;
+    let mut seen = runs
+        .iter()
+        .map(|run| run.id.clone())
+        .collect::<HashSet<_>>();
+    for path in archive_files(archive_root, input.started_after)? {
+        let (entries, _) = read_archive(&path)?;
+        for entry in entries {
+            if let ArchiveEntry::AgentRun(run) = entry {
+                if run_matches(&run, &input) && seen.insert(run.id.clone()) {
+                    runs.push(run);
+                }
+            }
+        }
+    }
+
+    runs.sort_by(|left, right| {
+        right
+            .started_at
+            .cmp(&left.started_at)
+            .then_with(|| right.id.cmp(&left.id))
+    });
+    // Paging towards newer runs keeps the ones closest to the cursor.
+    if input.after.is_some() && input.before.is_none() && runs.len() > limit {
+        runs.drain(..runs.len() - limit);
+    }
+    runs.truncate(limit);
+    Ok(runs)
+}
+
+/// `telemetry::list_agent_events` over the database and the archive files together.
+pub async fn list_agent_events_with_archive(
+    pool: &SqlitePool,
+    archive_root: &Path,
+    input: ListAgentEventsInput,
+) -> Result<Vec<AgentEventRecord>, String> {
+    let limit = input.limit.unwrap_or(250).clamp(1, 2000) as usize;
+    let mut events = telemetry::list_agent_events(pool, input.clone()).await?;
+    let mut seen = events.iter().map(|event| event.id).collect::<HashSet<_>>();
+    for path in archive_files(archive_root, input.created_after)? {
+        let (entries, _) = read_archive(&path)?;
+        for entry in entries {
+            if let ArchiveEntry::AgentEvent(event) = entry {
+                if event_matches(&event, &input) && seen.insert(event.id) {
+                    events.push(event);
+                }
+            }
+        }
+    }
+
+    events.sort_by(|left, right| right.id.cmp(&left.id));
+    if input.after_id.is_some() && input.before_id.is_none() && events.len() > limit {
+        events.drain(..events.len() - limit);
+    }
+    events.truncate(limit);
+    Ok(events)
+}
+
+/// Archive files oldest first. Everything in a file was recorded before the file was
+/// written, so files written before `newer_than` are skipped.
+fn archive_files(archive_root: &Path, newer_than: Option<i64>) -> Result<Vec<PathBuf>, String> {
+    if !archive_root.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = fs::read_dir(archive_root)
+        .map_err(|error| format!("Failed to read telemetry archive dir: {error}"))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter_map(|path| {
+            let written_at = archive_written_at(path.file_name()?.to_str()?)?;
+            Some((written_at, path))
+        })
+        .filter(|(written_at, _)| newer_than.is_none_or(|since| *written_at >= since))
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+/// When an archive was written, from its `telemetry_%Y%m%d_%H%M%S.jsonl` name.
+fn archive_written_at(file_name: &str) -> Option<i64> {
+    let stamp = file_name
+        .strip_prefix("telemetry_")?
+        .strip_suffix(".jsonl")?;
+    NaiveDateTime::parse_from_str(stamp, "%Y%m%d_%H%M%S")
+        .ok()
+        .map(|written_at| written_at.and_utc().timestamp())
+}
+
+/// Parsed entries of an archive file, and how many lines could not be parsed.
+fn read_archive(path: &Path) -> Result<(Vec<ArchiveEntry>, usize), String> {
+    let file =
+        File::open(path).map_err(|error| format!("Failed to open telemetry archive: {error}"))?;
+    let mut entries = Vec::new();
+    let mut unparsed = 0;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|error| format!("Failed to read telemetry archive: {error}"))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<ArchiveEntry>(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(_) => unparsed += 1,
+        }
+    }
+    Ok((entries, unparsed))
+}
+
+fn field_matches(filter: Option<&str>, value: Option<&str>) -> bool {
+    match filter.map(str::trim).filter(|filter| !filter.is_empty()) {
+        Some(filter) => value == Some(filter),
+        None => true,
+    }
+}
+
+fn run_matches(run: &AgentRunRecord, input: &ListAgentRunsInput) -> bool {
+    field_matches(input.root_task_id.as_deref(), run.root_task_id.as_deref())
+        && field_matches(input.task_id.as_deref(), run.task_id.as_deref())
+        && field_matches(input.actor.as_deref(), Some(run.actor.as_str()))
+        && field_matches(input.status.as_deref(), Some(run.status.as_str()))
+        && input.tier.is_none_or(|tier| run.tier == Some(tier))
+        && input
+            .started_after
+            .is_none_or(|started_after| run.started_at >= started_after)
+        && input
+            .started_before
+            .is_none_or(|started_before| run.started_at < started_before)
+        && input.before.as_ref().is_none_or(|before| {
+            (run.started_at, run.id.as_str()) < (before.started_at, before.id.as_str())
+        })
+        && input.after.as_ref().is_none_or(|after| {
+            (run.started_at, run.id.as_str()) > (after.started_at, after.id.as_str())
+        })
+}
+
+fn event_matches(event: &AgentEventRecord, input: &ListAgentEventsInput) -> bool {
+    field_matches(input.root_task_id.as_deref(), event.root_task_id.as_deref())
+        && field_matches(input.task_id.as_deref(), event.task_id.as_deref())
+        && field_matches(input.actor.as_deref(), Some(event.actor.as_str()))
+        && field_matches(input.action.as_deref(), Some(event.action.as_str()))
+        && input.since_id.is_none_or(|since_id| event.id > since_id)
+        && input.before_id.is_none_or(|before_id| event.id < before_id)
+        && input.after_id.is_none_or(|after_id| event.id > after_id)
+        && input
+            .created_after
+            .is_none_or(|created_after| event.created_at >= created_after)
+        && input
+            .created_before
+            .is_none_or(|created_before| event.created_at < created_before)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::db;
+    use crate::db::tasks::{self, CreateTaskInput};
+
+    async fn setup_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn archived_telemetry_stays_queryable_and_can_be_imported() {
+        let pool = setup_test_pool().await;
+        let archive_root = tempdir().expect("temp dir should be created");
+        let task = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 1,
+                domain: "platform".to_string(),
+                objective: "old work".to_string(),
+                token_budget: 5000,
+            },
+        )
+        .await
+        .expect("task should be created");
+        for details in [
+            "status=executing",
+            "status=completed tokensIn=10 tokensOut=5",
+        ] {
+            telemetry::record_task_activity_event(
+                &pool,
+                "tier1_orchestrator",
+                "task_status_changed",
+                Some(task.id.as_str()),
+                Some(details),
+            )
+            .await
+            .expect("telemetry should be recorded");
+        }
+        let long_ago = Utc::now().timestamp() - 30 * 86_400;
+        sqlx::query("UPDATE aop_agent_events SET created_at = ?")
+            .bind(long_ago)
+            .execute(&pool)
+            .await
+            .expect("events should be backdated");
+        sqlx::query("UPDATE aop_agent_runs SET started_at = ?, ended_at = ?")
+            .bind(long_ago)
+            .bind(long_ago)
+            .execute(&pool)
+            .await
+            .expect("runs should be backdated");
+
+        let archived = telemetry::archive_telemetry(&pool, archive_root.path(), 7)
+            .await
+            .expect("archive should succeed");
+        assert_eq!(archived.events_archived, 2);
+        assert_eq!(archived.runs_archived, 1);
+
+        let events_input = ListAgentEventsInput {
+            root_task_id: Some(task.id.clone()),
+            include_archived: Some(true),
+            ..Default::default()
+        };
+        assert!(telemetry::list_agent_events(&pool, events_input.clone())
+            .await
+            .expect("events should list")
+            .is_empty());
+        let events =
+            list_agent_events_with_archive(&pool, archive_root.path(), events_input.clone())
+                .await
+                .expect("archived events should list");
+        assert_eq!(events.len(), 2);
+        assert!(events[0].id > events[1].id);
+        let runs = list_agent_runs_with_archive(
+            &pool,
+            archive_root.path(),
+            ListAgentRunsInput {
+                task_id: Some(task.id.clone()),
+                status: Some("completed".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("archived runs should list");
+        assert_eq!(runs.len(), 1);
+
+        let imported = import_telemetry_archive(
+            &pool,
+            archive_root.path(),
+            ImportTelemetryArchiveInput::default(),
+        )
+        .await
+        .expect("import should succeed");
+        assert_eq!(imported.files_imported.len(), 1);
+        assert_eq!(imported.events_imported, 2);
+        assert_eq!(imported.runs_imported, 1);
+        assert!(archive_files(archive_root.path(), None)
+            .expect("archive dir should be readable")
+            .is_empty());
+        assert_eq!(
+            telemetry::list_agent_events(&pool, events_input)
+                .await
+                .expect("events should list")
+                .len(),
+            2
+        );
+
+        // Restored rows get a new retention window instead of being archived straight away.
+        let rearchived = telemetry::archive_telemetry(&pool, archive_root.path(), 7)
+            .await
+            .expect("archive should succeed");
+        assert_eq!(rearchived.events_archived, 0);
+        assert_eq!(rearchived.runs_archived, 0);
+
+        assert!(import_telemetry_archive(
+            &pool,
+            archive_root.path(),
+            ImportTelemetryArchiveInput {
+                file_name: Some("../aop.db".to_string()),
+            },
+        )
+        .await
+        .is_err());
+    }
+}
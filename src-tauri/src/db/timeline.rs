@@ -0,0 +1,490 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+/// `tree`: the root task, bound as the first parameter, and all of its descendants.
+const TASK_TREE_CTE: &str = r#"
+    WITH RECURSIVE tree(id) AS (
+        SELECT id FROM aop_tasks WHERE id = ?
+        UNION ALL
+        SELECT t.id FROM aop_tasks t JOIN tree ON t.parent_id = tree.id
+    )
+"#;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetTaskTimelineInput {
+    pub root_task_id: String,
+    /// Include agent events, true by default. Runs, mutations and budget requests are
+    /// always included.
+    pub include_events: Option<bool>,
+    /// Most recent agent events kept, 500 by default.
+    pub event_limit: Option<u32>,
+}
+
+/// One bar or marker of a task timeline. Entries with `ended_at` span an interval;
+/// the rest are instants, or intervals still open when `kind` is `task` or `run`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEntry {
+    /// `task`, `run`, `event`, `mutation`, `pipeline_step` or `budget_request`.
+    pub kind: String,
+    pub id: String,
+    pub task_id: Option<String>,
+    /// The enclosing entry: parent task for tasks, task for runs, run (or task) for
+    /// events, mutation for pipeline steps.
+    pub parent_id: Option<String>,
+    pub label: String,
+    pub status: Option<String>,
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskTimeline {
+    pub root_task_id: String,
+    pub started_at: i64,
+    /// Latest point on the timeline, open entries aside.
+    pub ended_at: i64,
+    /// Ordered by `started_at`, then by kind from tasks down to pipeline steps.
+    pub entries: Vec<TimelineEntry>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct TaskRow {
+    id: String,
+    parent_id: Option<String>,
+    tier: i64,
+    domain: String,
+    objective: String,
+    status: String,
+    error_message: Option<String>,
+    created_at: i64,
+    updated_at: i64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct RunRow {
+    id: String,
+    task_id: Option<String>,
+    actor: String,
+    model_id: Option<String>,
+    status: String,
+    started_at: i64,
+    ended_at: Option<i64>,
+    tokens_in: i64,
+    tokens_out: i64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct EventRow {
+    id: i64,
+    run_id: Option<String>,
+    task_id: Option<String>,
+    action: String,
+    status: Option<String>,
+    message: Option<String>,
+    latency_ms: Option<i64>,
+    created_at: i64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct MutationRow {
+    id: String,
+    task_id: String,
+    file_path: String,
+    status: String,
+    proposed_at: i64,
+    applied_at: Option<i64>,
+    rejection_reason: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PipelineStepRow {
+    id: i64,
+    mutation_id: String,
+    task_id: String,
+    action: String,
+    timestamp: i64,
+    details: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct BudgetRequestRow {
+    id: String,
+    task_id: String,
+    requested_increment: i64,
+    status: String,
+    reason: String,
+    created_at: i64,
+    resolved_at: Option<i64>,
+}
+
+/// The whole history of a root task in one chronological list: its task tree, agent
+/// runs and events, mutations with their pipeline steps, and budget requests.
+pub async fn get_task_timeline(
+    pool: &SqlitePool,
+    input: GetTaskTimelineInput,
+) -> Result<TaskTimeline, String> {
+    let root_task_id = input.root_task_id.trim();
+    if root_task_id.is_empty() {
+        return Err("rootTaskId is required".to_string());
+    }
+
+    let tasks = sqlx::query_as::<_, TaskRow>(&format!(
+        "{TASK_TREE_CTE}
+        SELECT t.id, t.parent_id, t.tier, t.domain, t.objective, t.status, t.error_message,
+               t.created_at, t.updated_at
+        FROM aop_tasks t JOIN tree ON tree.id = t.id"
+    ))
+    .bind(root_task_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to load timeline tasks: {error}"))?;
+    if tasks.is_empty() {
+        return Err(format!("Task '{root_task_id}' not found"));
+    }
+
+    let runs = sqlx::query_as::<_, RunRow>(
+        r#"
+        SELECT id, task_id, actor, model_id, status, started_at, ended_at, tokens_in, tokens_out
+        FROM aop_agent_runs
+        WHERE root_task_id = ?
+        "#,
+    )
+    .bind(root_task_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to load timeline runs: {error}"))?;
+
+    let events = if input.include_events.unwrap_or(true) {
+        sqlx::query_as::<_, EventRow>(
+            r#"
+            SELECT id, run_id, task_id, action, status, message, latency_ms, created_at
+            FROM aop_agent_events
+            WHERE root_task_id = ?
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(root_task_id)
+        .bind(i64::from(input.event_limit.unwrap_or(500).clamp(1, 5000)))
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to load timeline events: {error}"))?
+    } else {
+        Vec::new()
+    };
+
+    let mutations = sqlx::query_as::<_, MutationRow>(&format!(
+        "{TASK_TREE_CTE}
+        SELECT m.id, m.task_id, m.file_path, m.status, m.proposed_at, m.applied_at,
+               m.rejection_reason
+        FROM aop_mutations m JOIN tree ON tree.id = m.task_id"
+    ))
+    .bind(root_task_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to load timeline mutations: {error}"))?;
+
+    let pipeline_steps = sqlx::query_as::<_, PipelineStepRow>(&format!(
+        "{TASK_TREE_CTE}
+        SELECT a.id, m.id AS mutation_id, m.task_id, a.action, a.timestamp, a.details
+        FROM aop_audit_log a
+        JOIN aop_mutations m ON m.id = a.target_id
+        JOIN tree ON tree.id = m.task_id
+        WHERE a.actor = 'mutation_pipeline'"
+    ))
+    .bind(root_task_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to load timeline pipeline steps: {error}"))?;
+
+    let budget_requests = sqlx::query_as::<_, BudgetRequestRow>(&format!(
+        "{TASK_TREE_CTE}
+        SELECT b.id, b.task_id, b.requested_increment, b.status, b.reason, b.created_at,
+               b.resolved_at
+        FROM aop_budget_requests b JOIN tree ON tree.id = b.task_id"
+    ))
+    .bind(root_task_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to load timeline budget requests: {error}"))?;
+
+    let mut entries = Vec::new();
+    entries.extend(tasks.into_iter().map(|task| {
+        let finished = matches!(task.status.as_str(), "completed" | "failed");
+        TimelineEntry {
+            kind: "task".to_string(),
+            task_id: Some(task.id.clone()),
+            id: task.id,
+            parent_id: task.parent_id,
+            label: format!("tier{} {}: {}", task.tier, task.domain, task.objective),
+            status: Some(task.status),
+            started_at: task.created_at,
+            ended_at: finished.then_some(task.updated_at),
+            detail: task.error_message,
+        }
+    }));
+    entries.extend(runs.into_iter().map(|run| TimelineEntry {
+        kind: "run".to_string(),
+        id: run.id,
+        parent_id: run.task_id.clone(),
+        task_id: run.task_id,
+        label: match run.model_id {
+            Some(model_id) => format!("{} ({model_id})", run.actor),
+            None => run.actor,
+        },
+        status: Some(run.status),
+        started_at: run.started_at,
+        ended_at: run.ended_at,
+        detail: Some(format!(
+            "tokensIn={} tokensOut={}",
+            run.tokens_in, run.tokens_out
+        )),
+    }));
+    entries.extend(events.into_iter().map(|event| {
+        // Timed calls are recorded when they finish, so they cover the preceding latency.
+        let latency_secs = event
+            .latency_ms
+            .filter(|latency| *latency > 0)
+            .map(|ms| ms / 1000);
+        TimelineEntry {
+            kind: "event".to_string(),
+            id: event.id.to_string(),
+            parent_id: event.run_id.or_else(|| event.task_id.clone()),
+            task_id: event.task_id,
+            label: event.action,
+            status: event.status,
+            started_at: event.created_at - latency_secs.unwrap_or(0),
+            ended_at: latency_secs.map(|_| event.created_at),
+            detail: event.message,
+        }
+    }));
+    entries.extend(mutations.into_iter().map(|mutation| TimelineEntry {
+        kind: "mutation".to_string(),
+        id: mutation.id,
+        parent_id: Some(mutation.task_id.clone()),
+        task_id: Some(mutation.task_id),
+        label: mutation.file_path,
+        status: Some(mutation.status),
+        started_at: mutation.proposed_at,
+        ended_at: mutation.applied_at,
+        detail: mutation.rejection_reason,
+    }));
+    entries.extend(pipeline_steps.into_iter().map(|step| TimelineEntry {
+        kind: "pipeline_step".to_string(),
+        id: step.id.to_string(),
+        task_id: Some(step.task_id),
+        parent_id: Some(step.mutation_id),
+        label: step.action,
+        status: None,
+        started_at: step.timestamp,
+        ended_at: None,
+        detail: step.details,
+    }));
+    entries.extend(budget_requests.into_iter().map(|request| TimelineEntry {
+        kind: "budget_request".to_string(),
+        id: request.id,
+        parent_id: Some(request.task_id.clone()),
+        task_id: Some(request.task_id),
+        label: format!("+{} tokens", request.requested_increment),
+        status: Some(request.status),
+        started_at: request.created_at,
+        ended_at: request.resolved_at,
+        detail: Some(request.reason),
+    }));
+
+    entries.sort_by(|left, right| {
+        left.started_at
+            .cmp(&right.started_at)
+            .then_with(|| kind_rank(&left.kind).cmp(&kind_rank(&right.kind)))
+            .then_with(|| left.id.cmp(&right.id))
+    });
+    let started_at = entries.first().map_or(0, |entry| entry.started_at);
+    let ended_at = entries
+        .iter()
+        .map(|entry| entry.ended_at.unwrap_or(entry.started_at))
+        .max()
+        .unwrap_or(started_at);
+
+    Ok(TaskTimeline {
+        root_task_id: root_task_id.to_string(),
+        started_at,
+        ended_at,
+        entries,
+    })
+}
+
+fn kind_rank(kind: &str) -> u8 {
+    match kind {
+        "task" => 0,
+        "run" => 1,
+        "budget_request" => 2,
+        "mutation" => 3,
+        "pipeline_step" => 4,
+        _ => 5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::db;
+    use crate::db::budget_requests::{self, CreateBudgetRequestInput};
+    use crate::db::metrics;
+    use crate::db::mutations::{self, CreateMutationInput};
+    use crate::db::tasks::{self, CreateTaskInput};
+    use crate::db::telemetry;
+
+    async fn setup_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn timeline_stitches_the_task_tree_history_in_order() {
+        let pool = setup_test_pool().await;
+        let root = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 1,
+                domain: "platform".to_string(),
+                objective: "ship login".to_string(),
+                token_budget: 5000,
+            },
+        )
+        .await
+        .expect("root task should be created");
+        let child = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: Some(root.id.clone()),
+                tier: 2,
+                domain: "frontend".to_string(),
+                objective: "login form".to_string(),
+                token_budget: 2000,
+            },
+        )
+        .await
+        .expect("child task should be created");
+        telemetry::record_task_activity_event(
+            &pool,
+            "tier2_domain_leader",
+            "model_call",
+            Some(child.id.as_str()),
+            Some("model=openai/gpt-5 latencyMs=4000 tokensIn=100 tokensOut=20"),
+        )
+        .await
+        .expect("telemetry should be recorded");
+        let mutation = mutations::create_mutation(
+            &pool,
+            CreateMutationInput {
+                task_id: child.id.clone(),
+                agent_uid: Uuid::new_v4().to_string(),
+                file_path: "src/login.tsx".to_string(),
+                diff_content: "--- a/src/login.tsx\n+++ b/src/login.tsx\n".to_string(),
+                intent_description: None,
+                intent_hash: None,
+                confidence: 0.8,
+                parent_mutation_id: None,
+                modified_content: None,
+                base_checksum: None,
+            },
+        )
+        .await
+        .expect("mutation should be created");
+        metrics::record_audit_event(
+            &pool,
+            "mutation_pipeline",
+            "pipeline_started",
+            Some(mutation.id.as_str()),
+            None,
+        )
+        .await
+        .expect("audit should be recorded");
+        budget_requests::create_budget_request(
+            &pool,
+            CreateBudgetRequestInput {
+                task_id: child.id.clone(),
+                requested_by: "tier2_domain_leader".to_string(),
+                reason: "bigger form".to_string(),
+                requested_increment: 500,
+                auto_approve: Some(false),
+            },
+        )
+        .await
+        .expect("budget request should be created");
+
+        let timeline = get_task_timeline(
+            &pool,
+            GetTaskTimelineInput {
+                root_task_id: root.id.clone(),
+                include_events: None,
+                event_limit: None,
+            },
+        )
+        .await
+        .expect("timeline should load");
+
+        let mut kinds = timeline
+            .entries
+            .iter()
+            .map(|entry| entry.kind.as_str())
+            .collect::<Vec<_>>();
+        kinds.sort_unstable();
+        assert_eq!(
+            kinds,
+            [
+                "budget_request",
+                "event",
+                "mutation",
+                "pipeline_step",
+                "run",
+                "task",
+                "task"
+            ]
+        );
+        assert!(timeline
+            .entries
+            .windows(2)
+            .all(|pair| pair[0].started_at <= pair[1].started_at));
+        let event = timeline
+            .entries
+            .iter()
+            .find(|entry| entry.kind == "event")
+            .expect("event should be on the timeline");
+        assert_eq!(event.ended_at.map(|end| end - event.started_at), Some(4));
+        let step = timeline
+            .entries
+            .iter()
+            .find(|entry| entry.kind == "pipeline_step")
+            .expect("pipeline step should be on the timeline");
+        assert_eq!(step.parent_id.as_deref(), Some(mutation.id.as_str()));
+
+        assert!(get_task_timeline(
+            &pool,
+            GetTaskTimelineInput {
+                root_task_id: "missing".to_string(),
+                include_events: None,
+                event_limit: None,
+            },
+        )
+        .await
+        .is_err());
+    }
+}
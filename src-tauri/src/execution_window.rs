@@ -0,0 +1,157 @@
+use std::path::Path;
+use std::process::Command;
+
+use chrono::{Local, NaiveTime};
+
+/// When approved plans may start, from `AOP_EXECUTION_WINDOW`,
+/// `AOP_EXECUTION_WINDOW_AC_POWER` and `AOP_EXECUTION_WINDOW_MIN_TASKS`. Both conditions
+/// must hold when both are set; with neither set every plan starts immediately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionWindow {
+    /// Local start and end time; the window wraps past midnight when start is after end.
+    hours: Option<(NaiveTime, NaiveTime)>,
+    ac_power_only: bool,
+    /// Plans with fewer assignments are not heavy enough to wait; 0 holds every plan.
+    min_tasks: usize,
+}
+
+impl ExecutionWindow {
+    pub fn from_env() -> Self {
+        Self {
+            hours: std::env::var("AOP_EXECUTION_WINDOW")
+                .ok()
+                .and_then(|value| parse_hours(&value)),
+            ac_power_only: std::env::var("AOP_EXECUTION_WINDOW_AC_POWER")
+                .map(|value| {
+                    matches!(
+                        value.trim().to_ascii_lowercase().as_str(),
+                        "1" | "true" | "yes" | "on"
+                    )
+                })
+                .unwrap_or(false),
+            min_tasks: std::env::var("AOP_EXECUTION_WINDOW_MIN_TASKS")
+                .ok()
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .unwrap_or(0),
+        }
+    }
+
+    /// Whether a plan with `planned_tasks` assignments must wait for the window at all.
+    pub fn applies_to(&self, planned_tasks: usize) -> bool {
+        (self.hours.is_some() || self.ac_power_only) && planned_tasks >= self.min_tasks
+    }
+
+    /// Whether the window is open now.
+    pub fn is_open(&self) -> bool {
+        let time = Local::now().time();
+        let within_hours = self
+            .hours
+            .is_none_or(|(start, end)| hours_contain(start, end, time));
+        within_hours && (!self.ac_power_only || on_ac_power())
+    }
+
+    /// Human-readable form for activity logs, e.g. `22:00-06:00 on AC power`.
+    pub fn describe(&self) -> String {
+        let hours = self
+            .hours
+            .map(|(start, end)| format!("{}-{}", start.format("%H:%M"), end.format("%H:%M")));
+        match (hours, self.ac_power_only) {
+            (Some(hours), true) => format!("{hours} on AC power"),
+            (Some(hours), false) => hours,
+            (None, true) => "on AC power".to_string(),
+            (None, false) => "always".to_string(),
+        }
+    }
+}
+
+/// Whether `value` is a `HH:MM-HH:MM` window.
+pub fn is_valid_hours(value: &str) -> bool {
+    parse_hours(value).is_some()
+}
+
+/// Parses `HH:MM-HH:MM`; anything else leaves the hours unrestricted.
+fn parse_hours(value: &str) -> Option<(NaiveTime, NaiveTime)> {
+    let (start, end) = value.trim().split_once('-')?;
+    let parse = |part: &str| NaiveTime::parse_from_str(part.trim(), "%H:%M").ok();
+    Some((parse(start)?, parse(end)?))
+}
+
+fn hours_contain(start: NaiveTime, end: NaiveTime, time: NaiveTime) -> bool {
+    if start == end {
+        true
+    } else if start < end {
+        start <= time && time < end
+    } else {
+        time >= start || time < end
+    }
+}
+
+/// Whether the machine runs on mains power. Machines without a battery, and platforms
+/// where the power source cannot be read, count as plugged in.
+pub fn on_ac_power() -> bool {
+    if cfg!(target_os = "linux") {
+        linux_on_ac_power(Path::new("/sys/class/power_supply")).unwrap_or(true)
+    } else if cfg!(target_os = "macos") {
+        Command::new("pmset")
+            .args(["-g", "batt"])
+            .output()
+            .ok()
+            .map(|output| !String::from_utf8_lossy(&output.stdout).contains("Battery Power"))
+            .unwrap_or(true)
+    } else if cfg!(target_os = "windows") {
+        // BatteryStatus 1 means discharging; no battery prints nothing.
+        Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                "(Get-CimInstance Win32_Battery).BatteryStatus",
+            ])
+            .output()
+            .ok()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim() != "1")
+            .unwrap_or(true)
+    } else {
+        true
+    }
+}
+
+/// Reads the `online` flag of the first mains supply under `root`; `None` when there is none.
+fn linux_on_ac_power(root: &Path) -> Option<bool> {
+    let entries = std::fs::read_dir(root).ok()?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let kind = std::fs::read_to_string(path.join("type")).unwrap_or_default();
+        if kind.trim() != "Mains" {
+            continue;
+        }
+        if let Ok(online) = std::fs::read_to_string(path.join("online")) {
+            return Some(online.trim() == "1");
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hours_wrap_past_midnight_and_small_plans_skip_the_window() {
+        let at = |hour: u32, minute: u32| NaiveTime::from_hms_opt(hour, minute, 0).expect("time");
+        let (start, end) = parse_hours(" 22:00 - 06:00 ").expect("window should parse");
+        assert!(hours_contain(start, end, at(23, 30)));
+        assert!(hours_contain(start, end, at(5, 59)));
+        assert!(!hours_contain(start, end, at(6, 0)));
+        assert!(!hours_contain(start, end, at(12, 0)));
+        assert!(parse_hours("tonight").is_none());
+
+        let window = ExecutionWindow {
+            hours: Some((start, end)),
+            ac_power_only: false,
+            min_tasks: 4,
+        };
+        assert!(!window.applies_to(3));
+        assert!(window.applies_to(4));
+        assert_eq!(window.describe(), "22:00-06:00");
+    }
+}
@@ -0,0 +1,275 @@
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::agents::orchestrator;
+use crate::commands;
+use crate::db::metrics;
+use crate::mcp_server;
+use crate::mutation_pipeline;
+use crate::vector::indexer;
+use crate::vector::IndexProjectInput;
+use crate::AppState;
+
+/// Matches `identifier` in `tauri.conf.json`, so the CLI shares the desktop app's data.
+const APP_IDENTIFIER: &str = "com.derianandre.aop";
+const COMMANDS: [&str; 6] = ["index", "analyze", "plan", "approve", "pipeline", "mcp"];
+const USAGE: &str = "\
+Usage: aop-cli <command> [--input <file|->] [--<field> <value>]...
+
+Commands:
+  index     Index a project for retrieval (--target-project)
+  analyze   Analyze an objective and list clarifying questions
+  plan      Generate a plan for an analyzed objective
+  approve   Approve a plan and run its agents
+  pipeline  Validate and apply a mutation
+  mcp       Serve AOP's tools to an MCP client over stdio
+
+Flags set fields of the command's input: --target-project sets targetProject.
+Values that parse as JSON (numbers, booleans, arrays, objects) are passed as such and
+a flag without a value is true. --input reads the whole input as a JSON object from a
+file, or from stdin with `-`; flags override its fields. Results print as JSON.";
+
+/// Runs one CLI invocation and returns its exit code: 0 on success, 1 when the command
+/// fails and 2 on a usage error.
+pub fn run(args: Vec<String>) -> i32 {
+    match args.first().map(String::as_str) {
+        None => {
+            eprintln!("{USAGE}");
+            return 2;
+        }
+        Some("help" | "--help" | "-h") => {
+            println!("{USAGE}");
+            return 0;
+        }
+        Some(command) if !COMMANDS.contains(&command) => {
+            eprintln!("error: unknown command '{command}'\n\n{USAGE}");
+            return 2;
+        }
+        Some(_) => {}
+    }
+
+    let outcome = if args[0] == "mcp" {
+        serve_mcp()
+    } else {
+        execute(&args).map(|output| println!("{output}"))
+    };
+    match outcome {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("error: {error}");
+            1
+        }
+    }
+}
+
+fn open_state() -> Result<AppState, String> {
+    let workspace_root = crate::resolve_workspace_root()?;
+    crate::load_env_files(&workspace_root);
+    crate::build_state(&workspace_root, app_data_dir()?)
+}
+
+/// Stdout carries the protocol, so nothing else may print there.
+fn serve_mcp() -> Result<(), String> {
+    let state = open_state()?;
+    tauri::async_runtime::block_on(mcp_server::serve_stdio(&state))
+}
+
+fn execute(args: &[String]) -> Result<String, String> {
+    let (command, fields) = parse_args(args)?;
+    let fields = resolve_input(fields)?;
+    let state = open_state()?;
+
+    let result = tauri::async_runtime::block_on(async {
+        let _ = metrics::record_audit_event(
+            &state.db_pool,
+            "cli",
+            "headless_command_started",
+            None,
+            Some(&json!({ "command": command }).to_string()),
+        )
+        .await;
+        dispatch(&state, &command, fields).await
+    })?;
+    serde_json::to_string_pretty(&result)
+        .map_err(|error| format!("Failed to encode command result: {error}"))
+}
+
+async fn dispatch(
+    state: &AppState,
+    command: &str,
+    mut fields: Map<String, Value>,
+) -> Result<Value, String> {
+    let pool = &state.db_pool;
+    let model_registry = commands::current_model_registry(state)?;
+    match command {
+        "index" => {
+            let input: IndexProjectInput = decode(fields)?;
+            encode(indexer::index_project(pool, &input.target_project).await?)
+        }
+        "analyze" => {
+            encode(orchestrator::analyze_objective(pool, &model_registry, decode(fields)?).await?)
+        }
+        "plan" => {
+            fields.entry("answers").or_insert_with(|| json!({}));
+            encode(
+                orchestrator::generate_plan(
+                    pool,
+                    &state.secret_vault,
+                    &model_registry,
+                    decode(fields)?,
+                )
+                .await?,
+            )
+        }
+        "approve" => encode(
+            orchestrator::approve_plan_and_spawn(
+                pool,
+                &state.secret_vault,
+                &state.bridge_client,
+                &model_registry,
+                decode(fields)?,
+            )
+            .await?,
+        ),
+        "pipeline" => encode(
+            mutation_pipeline::run_mutation_pipeline(
+                pool,
+                &state.secret_vault,
+                &model_registry,
+                decode(fields)?,
+            )
+            .await?,
+        ),
+        other => Err(format!("unknown command '{other}'")),
+    }
+}
+
+/// Splits `<command> --flag value ...` into the command and its input fields, with flag
+/// names turned into camelCase field names.
+fn parse_args(args: &[String]) -> Result<(String, Map<String, Value>), String> {
+    let (command, rest) = args
+        .split_first()
+        .ok_or_else(|| "a command is required".to_string())?;
+    let mut fields = Map::new();
+    let mut rest = rest.iter().peekable();
+    while let Some(arg) = rest.next() {
+        let name = arg
+            .strip_prefix("--")
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| format!("unexpected argument '{arg}'"))?;
+        let value = match rest.next_if(|next| !next.starts_with("--")) {
+            Some(raw) => serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.clone())),
+            None => Value::Bool(true),
+        };
+        fields.insert(camel_case(name), value);
+    }
+    Ok((command.clone(), fields))
+}
+
+/// Loads the object named by `--input` and lays the remaining flags over it.
+fn resolve_input(mut fields: Map<String, Value>) -> Result<Map<String, Value>, String> {
+    let Some(source) = fields.remove("input") else {
+        return Ok(fields);
+    };
+    let source = source
+        .as_str()
+        .ok_or_else(|| "--input takes a file path or '-'".to_string())?
+        .to_string();
+    let raw = if source == "-" {
+        std::io::read_to_string(std::io::stdin())
+            .map_err(|error| format!("Failed to read input from stdin: {error}"))?
+    } else {
+        std::fs::read_to_string(&source)
+            .map_err(|error| format!("Failed to read input file '{source}': {error}"))?
+    };
+    let mut input = match serde_json::from_str::<Value>(&raw) {
+        Ok(Value::Object(input)) => input,
+        Ok(_) => return Err(format!("Input '{source}' must be a JSON object")),
+        Err(error) => return Err(format!("Input '{source}' is not valid JSON: {error}")),
+    };
+    input.extend(fields);
+    Ok(input)
+}
+
+fn decode<T: DeserializeOwned>(fields: Map<String, Value>) -> Result<T, String> {
+    serde_json::from_value(Value::Object(fields))
+        .map_err(|error| format!("Invalid command input: {error}"))
+}
+
+fn encode<T: Serialize>(value: T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|error| format!("Failed to encode command result: {error}"))
+}
+
+fn camel_case(flag: &str) -> String {
+    let mut name = String::with_capacity(flag.len());
+    let mut upper = false;
+    for ch in flag.chars() {
+        if ch == '-' || ch == '_' {
+            upper = !name.is_empty();
+        } else if upper {
+            name.extend(ch.to_uppercase());
+            upper = false;
+        } else {
+            name.push(ch);
+        }
+    }
+    name
+}
+
+/// `AOP_DATA_DIR`, or the directory Tauri uses as the app's local data dir.
+fn app_data_dir() -> Result<PathBuf, String> {
+    let configured = |key: &str| {
+        std::env::var(key)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    if let Some(dir) = configured("AOP_DATA_DIR") {
+        return Ok(dir);
+    }
+    let base = if cfg!(target_os = "windows") {
+        configured("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        configured("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        configured("XDG_DATA_HOME")
+            .or_else(|| configured("HOME").map(|home| home.join(".local").join("share")))
+    };
+    base.map(|base| base.join(APP_IDENTIFIER))
+        .ok_or_else(|| "Cannot determine the app data dir; set AOP_DATA_DIR".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_become_camel_case_fields_with_json_values() {
+        let args = [
+            "pipeline",
+            "--mutation-id",
+            "m-1",
+            "--target-project",
+            "/repo",
+            "--tier1-approved",
+            "--ci-args",
+            r#"["test","--quiet"]"#,
+            "--max-repair-attempts",
+            "2",
+        ]
+        .map(String::from);
+        let (command, fields) = parse_args(&args).expect("args should parse");
+        assert_eq!(command, "pipeline");
+        assert_eq!(fields["mutationId"], "m-1");
+        assert_eq!(fields["targetProject"], "/repo");
+        assert_eq!(fields["tier1Approved"], true);
+        assert_eq!(fields["ciArgs"], json!(["test", "--quiet"]));
+        assert_eq!(fields["maxRepairAttempts"], 2);
+
+        assert!(parse_args(&["plan", "stray"].map(String::from)).is_err());
+    }
+}
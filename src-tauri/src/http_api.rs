@@ -0,0 +1,311 @@
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpListener;
+use uuid::Uuid;
+
+use crate::commands;
+use crate::db::metrics;
+use crate::mutation_pipeline::RunMutationPipelineInput;
+use crate::AppState;
+
+/// Vault entry holding the bearer token clients must send.
+pub const TOKEN_SECRET: &str = "http_api";
+
+/// Tauri commands served at `POST /api/commands/<name>`, with the same input as `invoke`.
+/// Approving plans and mutations stays with the desktop UI.
+const MIRRORED_COMMANDS: &[&str] = &[
+    "create_task",
+    "get_tasks",
+    "control_task",
+    "orchestrate_objective",
+    "analyze_objective",
+    "submit_answers_and_plan",
+    "list_task_mutations",
+    "run_mutation_pipeline",
+    "list_task_activity",
+    "get_task_timeline",
+    "get_task_cost_rollup",
+    "get_mission_control_snapshot",
+    "list_task_budget_requests",
+    "resolve_task_budget_request",
+    "get_spend_status",
+    "index_target_project",
+    "get_index_job_status",
+];
+
+#[derive(Clone)]
+struct ApiState {
+    app: AppHandle,
+    token: String,
+}
+
+#[derive(Debug)]
+enum ApiError {
+    Unauthorized,
+    UnknownCommand(String),
+    BadInput(String),
+    Failed(String),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                "Missing or invalid bearer token".to_string(),
+            ),
+            ApiError::UnknownCommand(command) => (
+                StatusCode::NOT_FOUND,
+                format!("'{command}' is not served over HTTP"),
+            ),
+            ApiError::BadInput(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::Failed(message) => (StatusCode::UNPROCESSABLE_ENTITY, message),
+        };
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
+
+/// Serves the mirrored commands on `127.0.0.1:port` while the app runs. Requests need
+/// `Authorization: Bearer <token>`, where the token is the vault's `http_api` secret,
+/// generated on first start. A server that cannot start or stops is recorded as an
+/// `http_api_unavailable` audit event.
+pub fn spawn_http_api(app: AppHandle, port: u16) {
+    tauri::async_runtime::spawn(async move {
+        let token = match load_or_create_token(&app).await {
+            Ok(token) => token,
+            Err(error) => {
+                record_unavailable(&app, port, &error).await;
+                return;
+            }
+        };
+        let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => listener,
+            Err(error) => {
+                record_unavailable(&app, port, &format!("cannot bind port: {error}")).await;
+                return;
+            }
+        };
+        let router = Router::new()
+            .route("/api/health", get(health))
+            .route("/api/commands", get(list_commands))
+            .route("/api/commands/{command}", post(invoke))
+            .with_state(ApiState {
+                app: app.clone(),
+                token,
+            });
+        if let Err(error) = axum::serve(listener, router).await {
+            record_unavailable(&app, port, &format!("stopped: {error}")).await;
+        }
+    });
+}
+
+async fn record_unavailable(app: &AppHandle, port: u16, error: &str) {
+    let state = app.state::<AppState>();
+    let _ = metrics::record_audit_event(
+        &state.db_pool,
+        "http_api",
+        "http_api_unavailable",
+        None,
+        Some(&format!("port={port} error={error}")),
+    )
+    .await;
+}
+
+async fn load_or_create_token(app: &AppHandle) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let mut vault = state.secret_vault.lock().await;
+    if let Some(token) = vault.read_secret(TOKEN_SECRET, "http_api")? {
+        return Ok(token);
+    }
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    vault.set_secret(TOKEN_SECRET, &token, false, None)?;
+    Ok(token)
+}
+
+async fn health() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+async fn list_commands(
+    State(api): State<ApiState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, ApiError> {
+    authorize(&headers, &api.token)?;
+    Ok(Json(json!({ "commands": MIRRORED_COMMANDS })))
+}
+
+async fn invoke(
+    State(api): State<ApiState>,
+    Path(command): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    authorize(&headers, &api.token)?;
+    if !MIRRORED_COMMANDS.contains(&command.as_str()) {
+        return Err(ApiError::UnknownCommand(command));
+    }
+    let input = if body.iter().all(u8::is_ascii_whitespace) {
+        Value::Null
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|error| ApiError::BadInput(format!("Body is not valid JSON: {error}")))?
+    };
+
+    let state = api.app.state::<AppState>();
+    let _ = metrics::record_audit_event(
+        &state.db_pool,
+        "http_api",
+        "http_api_command",
+        None,
+        Some(&json!({ "command": command }).to_string()),
+    )
+    .await;
+
+    let output = dispatch(&api.app, &command, input).await?;
+    Ok(Json(output).into_response())
+}
+
+async fn dispatch(app: &AppHandle, command: &str, input: Value) -> Result<Value, ApiError> {
+    match command {
+        "create_task" => encode(commands::create_task(app.state(), decode(input)?).await),
+        "get_tasks" => encode(commands::get_tasks(app.state()).await),
+        "control_task" => encode(commands::control_task(app.state(), decode(input)?).await),
+        "orchestrate_objective" => {
+            encode(commands::orchestrate_objective(app.state(), decode(input)?).await)
+        }
+        "analyze_objective" => {
+            encode(commands::analyze_objective(app.state(), decode(input)?).await)
+        }
+        "submit_answers_and_plan" => {
+            encode(commands::submit_answers_and_plan(app.state(), decode(input)?).await)
+        }
+        "list_task_mutations" => {
+            encode(commands::list_task_mutations(app.state(), decode(input)?).await)
+        }
+        "run_mutation_pipeline" => encode(
+            commands::run_mutation_pipeline(app.state(), unapproved_pipeline_input(input)?).await,
+        ),
+        "list_task_activity" => {
+            encode(commands::list_task_activity(app.state(), decode(input)?).await)
+        }
+        "get_task_timeline" => {
+            encode(commands::get_task_timeline(app.state(), decode(input)?).await)
+        }
+        "get_task_cost_rollup" => {
+            encode(commands::get_task_cost_rollup(app.state(), decode(input)?).await)
+        }
+        "get_mission_control_snapshot" => {
+            encode(commands::get_mission_control_snapshot(app.state(), decode(input)?).await)
+        }
+        "list_task_budget_requests" => {
+            encode(commands::list_task_budget_requests(app.state(), decode(input)?).await)
+        }
+        "resolve_task_budget_request" => {
+            encode(commands::resolve_task_budget_request(app.state(), decode(input)?).await)
+        }
+        "get_spend_status" => encode(commands::get_spend_status(app.state()).await),
+        "index_target_project" => {
+            encode(commands::index_target_project(app.clone(), app.state(), decode(input)?).await)
+        }
+        "get_index_job_status" => {
+            encode(commands::get_index_job_status(app.state(), decode(input)?).await)
+        }
+        other => Err(ApiError::UnknownCommand(other.to_string())),
+    }
+}
+
+fn authorize(headers: &HeaderMap, token: &str) -> Result<(), ApiError> {
+    let presented = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if tokens_match(presented.trim(), token) {
+        Ok(())
+    } else {
+        Err(ApiError::Unauthorized)
+    }
+}
+
+/// Compares without stopping at the first differing byte.
+fn tokens_match(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0_u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Reads the `input` argument the way `invoke` passes it: `{ "input": { ... } }`, or the
+/// bare object.
+fn decode<T: DeserializeOwned>(body: Value) -> Result<T, ApiError> {
+    let input = match body {
+        Value::Object(mut fields) if fields.len() == 1 && fields.contains_key("input") => {
+            fields.remove("input").unwrap_or_default()
+        }
+        other => other,
+    };
+    serde_json::from_value(input)
+        .map_err(|error| ApiError::BadInput(format!("Invalid input: {error}")))
+}
+
+/// Like MCP clients, HTTP clients only propose and validate mutations, so any
+/// `tier1Approved` they send is overridden.
+fn unapproved_pipeline_input(body: Value) -> Result<RunMutationPipelineInput, ApiError> {
+    let mut input: Value = decode(body)?;
+    if let Some(object) = input.as_object_mut() {
+        object.insert("tier1Approved".to_string(), Value::Bool(false));
+    }
+    decode(input)
+}
+
+fn encode<T: Serialize>(result: Result<T, String>) -> Result<Value, ApiError> {
+    let value = result.map_err(ApiError::Failed)?;
+    serde_json::to_value(value)
+        .map_err(|error| ApiError::Failed(format!("Failed to encode result: {error}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::IndexProjectInput;
+
+    #[test]
+    fn tokens_and_inputs_are_checked_like_invoke() {
+        assert!(tokens_match("abc123", "abc123"));
+        assert!(!tokens_match("abc124", "abc123"));
+        assert!(!tokens_match("abc", "abc123"));
+
+        let mut headers = HeaderMap::new();
+        assert!(authorize(&headers, "secret").is_err());
+        headers.insert(
+            header::AUTHORIZATION,
+            "Bearer secret".parse().expect("header"),
+        );
+        assert!(authorize(&headers, "secret").is_ok());
+
+        for body in [
+            json!({ "input": { "targetProject": "/repo" } }),
+            json!({ "targetProject": "/repo" }),
+        ] {
+            let input: IndexProjectInput = decode(body).expect("input should decode");
+            assert_eq!(input.target_project, "/repo");
+        }
+
+        let input = unapproved_pipeline_input(json!({
+            "input": { "mutationId": "m-1", "targetProject": "/repo", "tier1Approved": true },
+        }))
+        .expect("pipeline input should decode");
+        assert!(!input.tier1_approved);
+        assert!(!MIRRORED_COMMANDS.contains(&"approve_orchestration_plan"));
+    }
+}
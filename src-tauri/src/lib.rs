@@ -1,16 +1,52 @@
 mod agents;
 mod commands;
+mod commit_message;
+mod compliance;
+mod config_bundle;
 mod db;
+mod execution_window;
+mod headless;
+mod http_api;
 mod llm_adapter;
+mod llm_cache;
+mod llm_pricing;
+mod llm_rate_limit;
+mod llm_stream;
+mod llm_transcript;
 mod mcp_bridge;
+mod mcp_server;
+mod mcp_servers;
+mod metrics_server;
+mod model_bandit;
+mod model_benchmark;
+mod model_discovery;
 mod model_intelligence;
 mod model_registry;
 mod mutation_pipeline;
 mod mutation_revision;
+mod notifications;
+mod otlp_export;
+mod patch_hunks;
+mod pipeline_batch;
+mod project_ignore;
+mod prompt_templates;
+mod protected_paths;
+mod provider_keys;
+mod pull_request;
+mod run_memory;
 mod runtime_config;
 mod secret_vault;
+mod semantic_judge;
+mod shadow_cache;
+mod shadow_sandbox;
+mod spend_cap;
+mod task_branch;
 mod task_runtime;
+mod telemetry_stream;
+mod token_count;
+mod token_ledger;
 mod vector;
+mod webhooks;
 
 use std::fs;
 use std::io;
@@ -18,21 +54,34 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
 use sqlx::SqlitePool;
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
 use tokio::sync::Mutex;
-use tauri::Manager;
 
 use mcp_bridge::client::BridgeClient;
+use mcp_bridge::resources::McpResources;
+use mcp_bridge::session::SessionContext;
 use model_registry::ModelRegistry;
 use runtime_config::RuntimeFlags;
 use secret_vault::SecretVault;
 use sha2::{Digest, Sha256};
+use telemetry_stream::TelemetrySubscriptions;
+use vector::jobs::IndexJobs;
+use vector::watcher::IndexWatchers;
 
 pub struct AppState {
     pub db_pool: SqlitePool,
+    /// Read-only connections for telemetry listings, search and analytics.
+    pub db_reader: SqlitePool,
     pub bridge_client: BridgeClient,
-    pub model_registry: ModelRegistry,
+    pub mcp_resources: Arc<Mutex<McpResources>>,
+    /// Swapped whole on reload or edit; callers hold the snapshot they started with.
+    pub model_registry: Arc<RwLock<Arc<ModelRegistry>>>,
     pub runtime_flags: Arc<RwLock<RuntimeFlags>>,
     pub secret_vault: Arc<Mutex<SecretVault>>,
+    pub index_watchers: Arc<Mutex<IndexWatchers>>,
+    pub index_jobs: Arc<Mutex<IndexJobs>>,
+    pub telemetry_subscriptions: Arc<Mutex<TelemetrySubscriptions>>,
     pub app_data_dir: PathBuf,
 }
 
@@ -60,22 +109,101 @@ fn initialize_state(app: &mut tauri::App) -> Result<(), String> {
         .app_local_data_dir()
         .map_err(|error| format!("Failed to determine app data dir: {error}"))?;
 
+    let app_handle = app.handle().clone();
+    llm_stream::set_listener(Arc::new(move |event| {
+        let _ = app_handle.emit(llm_stream::LLM_STREAM_EVENT, event);
+    }));
+    let notification_handle = app.handle().clone();
+    notifications::set_sink(Arc::new(move |title, body| {
+        let _ = notification_handle
+            .notification()
+            .builder()
+            .title(title)
+            .body(body)
+            .show();
+    }));
+
+    let state = build_state(&workspace_root, app_data_dir)?;
+    let http_api_port = state
+        .runtime_flags
+        .read()
+        .ok()
+        .filter(|flags| flags.http_api_enabled)
+        .map(|flags| flags.http_api_port as u16);
+    app.manage(state);
+    if let Some(port) = http_api_port {
+        http_api::spawn_http_api(app.handle().clone(), port);
+    }
+    Ok(())
+}
+
+/// Opens the database, vault and registry under `app_data_dir` and starts the background
+/// workers. Shared by the desktop app and the headless CLI.
+fn build_state(workspace_root: &Path, app_data_dir: PathBuf) -> Result<AppState, String> {
     fs::create_dir_all(&app_data_dir)
         .map_err(|error| format!("Failed to create app data dir: {error}"))?;
 
     let db_path = app_data_dir.join("aop_orchestrator.db");
-    let bridge_client = BridgeClient::new(&workspace_root);
-    let model_registry = ModelRegistry::load(&workspace_root);
-    let runtime_flags = Arc::new(RwLock::new(RuntimeFlags::from_env()));
+    vector::ann::set_index_dir(app_data_dir.join("vector-ann"));
+    prompt_templates::set_user_root(app_data_dir.join("prompts"));
+    mcp_servers::set_registry_path(app_data_dir.join("mcp_servers.json"));
+    let model_registry = ModelRegistry::load(workspace_root);
+    llm_rate_limit::configure(model_registry.provider_limits());
+    llm_pricing::configure(model_registry.model_pricing());
+    runtime_config::set_config_path(app_data_dir.join("runtime_flags.json"));
+    // A saved file that cannot be read falls back to the environment and is audited below.
+    let (runtime_flags, flags_load_error) = match runtime_config::load() {
+        Ok(flags) => (flags, None),
+        Err(error) => (RuntimeFlags::from_env(), Some(error)),
+    };
+    let runtime_flags = Arc::new(RwLock::new(runtime_flags));
     if let Ok(flags) = runtime_flags.read() {
         flags.sync_to_process_env();
     }
     let secret_vault = Arc::new(Mutex::new(SecretVault::new(app_data_dir.clone())));
 
-    let db_pool = tauri::async_runtime::block_on(async {
+    let replaced_db = db::backup::apply_pending_restore(&db_path, &app_data_dir.join("backups"))?;
+    let encrypt_db = runtime_flags
+        .read()
+        .map(|flags| flags.db_encryption)
+        .unwrap_or(false);
+    let (db_pool, db_reader) = tauri::async_runtime::block_on(async {
+        // An encrypted database needs its key even after the flag is turned off again.
+        if encrypt_db || (db_path.exists() && !db::encryption::is_plaintext(&db_path)?) {
+            let hex_key = secret_vault.lock().await.database_key()?;
+            if encrypt_db {
+                db::encryption::encrypt_database(&db_path, &hex_key).await?;
+            }
+            db::set_encryption_key(hex_key);
+        }
         let pool = db::connect_pool(&db_path).await?;
         db::run_migrations(&pool).await?;
-        Ok::<SqlitePool, String>(pool)
+        db::tasks::clear_stale_queued_tasks(&pool).await?;
+        if let Some(error) = &flags_load_error {
+            let details = serde_json::json!({ "error": error });
+            db::metrics::record_audit_event(
+                &pool,
+                "runtime_config",
+                "runtime_flags_load_failed",
+                None,
+                Some(&details.to_string()),
+            )
+            .await?;
+        }
+        if let Some(replaced) = &replaced_db {
+            let details = serde_json::json!({ "previousDatabase": replaced.to_string_lossy() });
+            db::metrics::record_audit_event(
+                &pool,
+                "database",
+                "database_restored",
+                None,
+                Some(&details.to_string()),
+            )
+            .await?;
+        }
+        let reader = db::connect_read_pool(&db_path).await?;
+        secret_vault.lock().await.set_access_log(pool.clone());
+        Ok::<(SqlitePool, SqlitePool), String>((pool, reader))
     })?;
     let retention_days = runtime_flags
         .read()
@@ -86,17 +214,56 @@ fn initialize_state(app: &mut tauri::App) -> Result<(), String> {
         app_data_dir.join("telemetry-archive"),
         retention_days,
     );
+    shadow_cache::spawn_shadow_janitor(db_pool.clone());
+    llm_adapter::set_attempt_listener(model_intelligence::adapter_attempt_listener(
+        db_pool.clone(),
+    ));
+    let mcp_resources = Arc::new(Mutex::new(McpResources::default()));
+    let bridge_client = BridgeClient::new(
+        workspace_root,
+        SessionContext {
+            secret_vault: secret_vault.clone(),
+            db_pool: db_pool.clone(),
+            resources: mcp_resources.clone(),
+        },
+    );
+    bridge_client.spawn_health_checks();
+    let model_vault = secret_vault.clone();
+    llm_adapter::set_credential_resolver(Arc::new(move |credential, accessor| {
+        // Planners call models straight from async tasks, so step off the worker first.
+        tokio::task::block_in_place(|| {
+            model_vault
+                .blocking_lock()
+                .read_secret(credential, accessor)
+        })
+    }));
+    task_runtime::spawn_mutation_sweeper(db_pool.clone());
+    task_runtime::spawn_deadline_watchdog(db_pool.clone());
+    task_runtime::spawn_stall_watchdog(db_pool.clone());
+    otlp_export::spawn_trace_exporter(db_pool.clone());
+    if let Ok(flags) = runtime_flags.read() {
+        if flags.metrics_enabled {
+            metrics_server::spawn_metrics_server(
+                db_reader.clone(),
+                db_pool.clone(),
+                flags.metrics_port as u16,
+            );
+        }
+    }
 
-    app.manage(AppState {
+    Ok(AppState {
         db_pool,
+        db_reader,
         bridge_client,
-        model_registry,
+        mcp_resources,
+        model_registry: Arc::new(RwLock::new(Arc::new(model_registry))),
         runtime_flags,
         secret_vault,
+        index_watchers: Arc::new(Mutex::new(IndexWatchers::default())),
+        index_jobs: Arc::new(Mutex::new(IndexJobs::default())),
+        telemetry_subscriptions: Arc::new(Mutex::new(TelemetrySubscriptions::default())),
         app_data_dir,
-    });
-
-    Ok(())
+    })
 }
 
 fn load_env_files(workspace_root: &Path) {
@@ -106,9 +273,16 @@ fn load_env_files(workspace_root: &Path) {
     let _ = dotenvy::from_path(root_local);
 }
 
+/// Runs one headless CLI subcommand against the same state as the desktop app and
+/// returns the process exit code.
+pub fn run_headless(args: Vec<String>) -> i32 {
+    headless::run(args)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .plugin(tauri_plugin_notification::init())
         .plugin(
             tauri_plugin_stronghold::Builder::new(|password| {
                 let mut hasher = Sha256::new();
@@ -126,18 +300,36 @@ pub fn run() {
             commands::get_tasks,
             commands::update_task_status,
             commands::control_task,
+            commands::set_task_priority,
+            commands::set_task_deadline,
             commands::request_task_budget_increase,
             commands::list_task_budget_requests,
             commands::resolve_task_budget_request,
+            commands::list_budget_policies,
+            commands::set_budget_policy,
+            commands::get_spend_status,
+            commands::attach_objective_images,
+            commands::list_task_attachments,
             commands::orchestrate_objective,
             commands::analyze_objective,
             commands::submit_answers_and_plan,
             commands::approve_orchestration_plan,
+            commands::cancel_llm_stream,
             commands::execute_domain_task,
             commands::list_task_mutations,
             commands::run_mutation_pipeline,
+            commands::run_mutation_pipeline_batch,
+            commands::rebase_mutation,
+            commands::purge_mutations,
             commands::set_mutation_status,
             commands::request_mutation_revision,
+            commands::edit_mutation_diff,
+            commands::finalize_branch,
+            commands::clean_shadow_cache,
+            commands::list_shadow_dirs,
+            commands::purge_shadow_dirs,
+            commands::clear_llm_cache,
+            commands::open_pull_request,
             commands::list_audit_log,
             commands::list_task_activity,
             commands::list_agent_terminals,
@@ -146,19 +338,82 @@ pub fn run() {
             commands::list_target_dir,
             commands::read_target_file,
             commands::search_target_files,
+            commands::write_target_file,
+            commands::apply_target_patch,
+            commands::run_target_command,
             commands::index_target_project,
+            commands::get_index_job_status,
+            commands::cancel_index_job,
+            commands::list_indexed_projects,
+            commands::get_project_index_stats,
+            commands::delete_project_index,
             commands::query_codebase,
+            commands::search_symbols,
+            commands::index_git_history,
+            commands::query_git_history,
+            commands::start_index_watch,
+            commands::stop_index_watch,
             commands::get_model_registry,
+            commands::reload_model_registry,
+            commands::add_registry_model,
+            commands::update_registry_model,
+            commands::remove_registry_model,
+            commands::discover_provider_models,
+            commands::benchmark_models,
+            commands::list_model_arms,
+            commands::list_model_pins,
+            commands::set_model_pin,
+            commands::create_model_experiment,
+            commands::list_model_experiments,
+            commands::stop_model_experiment,
+            commands::compare_model_experiment,
+            commands::get_prompt_templates,
+            commands::set_prompt_template,
+            commands::list_mcp_servers,
+            commands::save_mcp_server,
+            commands::remove_mcp_server,
+            commands::list_mcp_tools,
+            commands::subscribe_mcp_resources,
+            commands::unsubscribe_mcp_resources,
+            commands::list_mcp_resource_subscriptions,
             commands::get_mission_control_snapshot,
+            commands::recover_stalled_run,
+            commands::get_model_health_trends,
+            commands::subscribe_mission_control,
+            commands::unsubscribe_mission_control,
+            commands::get_task_cost_rollup,
+            commands::get_task_timeline,
+            commands::get_cost_breakdown,
+            commands::get_token_usage_trends,
             commands::list_agent_runs,
             commands::list_agent_events,
+            commands::page_agent_runs,
+            commands::page_agent_events,
+            commands::search_events,
+            commands::export_task_trace,
+            commands::list_llm_transcripts,
+            commands::export_llm_transcripts,
             commands::control_execution_scope,
             commands::get_runtime_flags,
             commands::set_runtime_flags,
+            commands::set_runtime_profile,
+            commands::list_runtime_profiles,
+            commands::export_configuration,
+            commands::import_configuration,
             commands::get_provider_secret_status,
             commands::set_provider_secret,
             commands::reveal_provider_secret,
-            commands::archive_telemetry
+            commands::list_provider_credentials,
+            commands::list_secret_access_log,
+            commands::rotate_provider_secret,
+            commands::validate_provider_secret,
+            commands::migrate_secret_backend,
+            commands::archive_telemetry,
+            commands::import_telemetry_archive,
+            commands::backup_database,
+            commands::list_database_backups,
+            commands::restore_database,
+            commands::check_database_integrity
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
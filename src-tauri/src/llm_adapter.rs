@@ -1,12 +1,70 @@
+use std::borrow::Cow;
+use std::cell::Cell;
 use std::collections::HashMap;
+use std::future::Future;
+use std::io::{BufRead, BufReader, Read};
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use sqlx::SqlitePool;
+
+use crate::llm_cache;
+use crate::llm_pricing;
+use crate::llm_rate_limit;
+use crate::llm_transcript;
+use crate::secret_vault;
+use crate::spend_cap;
+use crate::token_count;
+use crate::token_ledger;
+use crate::model_registry::{GenerationParams, ModelEndpoint, ModelSelection};
 
 const CLAUDE_CODE_PROVIDER_ALIASES: &[&str] =
     &["claude_code", "claude-code", "anthropic_claude_code"];
 const OPENAI_PROVIDER_ALIASES: &[&str] = &["openai", "openai_api"];
+/// Self-hosted or proxied OpenAI-compatible servers (vLLM, LM Studio); need a `baseUrl`.
+const OPENAI_COMPATIBLE_PROVIDER_ALIASES: &[&str] = &["openai_compatible", "vllm", "lm_studio"];
+const AZURE_OPENAI_PROVIDER_ALIASES: &[&str] = &["azure_openai", "azure"];
+pub const OPENAI_DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+const AZURE_OPENAI_DEFAULT_API_VERSION: &str = "2024-10-21";
+const OLLAMA_PROVIDER_ALIASES: &[&str] = &["ollama", "ollama_local"];
+/// Used when neither the model nor the role sets a temperature.
+const DEFAULT_TEMPERATURE: f32 = 0.2;
+const OLLAMA_DEFAULT_URL: &str = "http://127.0.0.1:11434";
+/// Default deadlines per provider family, overridable through `AOP_*_TIMEOUT_SECS` or a
+/// model's `timeoutSecs`. Local models on consumer hardware and the agentic Claude Code
+/// CLI can take minutes for a full file rewrite.
+const CLAUDE_CODE_TIMEOUT_SECS: u64 = 600;
+const OPENAI_TIMEOUT_SECS: u64 = 120;
+const OLLAMA_TIMEOUT_SECS: u64 = 600;
+const MAX_TIMEOUT_SECS: u64 = 7_200;
+const DEFAULT_MAX_RESPONSE_CHARS: usize = 400_000;
+const OLLAMA_PROBE_TIMEOUT_MS: u64 = 300;
+/// Error returned when the chunk callback asks a streaming generation to stop.
+pub const GENERATION_CANCELLED: &str = "Generation was cancelled";
+/// Prefix of the error returned when a call runs past its deadline.
+pub const REQUEST_TIMED_OUT: &str = "Model request timed out";
+const RETRY_MAX_DELAY_MS: u64 = 30_000;
+/// Requests [`generate_many`] runs at once unless `AOP_LLM_BATCH_PARALLELISM` says
+/// otherwise; provider rate limits still apply to every call.
+const DEFAULT_BATCH_PARALLELISM: usize = 4;
+const MAX_BATCH_PARALLELISM: usize = 16;
+
+pub type AttemptListener = Arc<dyn Fn(&AdapterAttemptEvent) + Send + Sync>;
+
+static ATTEMPT_LISTENER: OnceLock<AttemptListener> = OnceLock::new();
+
+/// Reads a vault credential such as `openai/work` on behalf of the named component. Model
+/// calls are synchronous, so it blocks until the vault is free.
+pub type CredentialResolver =
+    Arc<dyn Fn(&str, &str) -> Result<Option<String>, String> + Send + Sync>;
+
+static CREDENTIAL_RESOLVER: OnceLock<CredentialResolver> = OnceLock::new();
 
 #[derive(Debug, Clone)]
 pub struct AdapterRequest {
@@ -14,6 +72,97 @@ pub struct AdapterRequest {
     pub model_id: String,
     pub system_prompt: String,
     pub user_prompt: String,
+    pub endpoint: ModelEndpoint,
+    /// Sampling settings; Claude Code has no flags for them and ignores them.
+    pub generation: GenerationParams,
+    /// Task the call works for; only used to attribute retry and failover events.
+    pub task_id: Option<String>,
+    /// Models tried in order once retries against the primary model are exhausted.
+    pub fallbacks: Vec<ModelSelection>,
+    /// JSON schema the reply must follow, enforced natively where the provider can.
+    pub response_schema: Option<ResponseSchema>,
+    /// Always call the model, even when the response cache holds a reply.
+    pub bypass_cache: bool,
+    /// Images sent with the user prompt to providers that accept them.
+    pub attachments: Vec<ImageAttachment>,
+    /// Database holding the response cache, transcripts and task budgets; without one the
+    /// call is neither cached, transcribed nor debited.
+    pub pool: Option<SqlitePool>,
+}
+
+/// A base64-encoded image, such as a screenshot or design export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageAttachment {
+    pub name: String,
+    /// `image/png`, `image/jpeg`, `image/webp` or `image/gif`.
+    pub media_type: String,
+    pub data_base64: String,
+}
+
+impl ImageAttachment {
+    fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.media_type, self.data_base64)
+    }
+}
+
+/// A named JSON schema for structured output. Schemas follow OpenAI's strict mode: every
+/// property is required and optional values are nullable.
+#[derive(Debug, Clone)]
+pub struct ResponseSchema {
+    pub name: String,
+    pub schema: Value,
+}
+
+/// A retry or failover taken by the adapter. `action` is `llm_retry` or `llm_failover`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdapterAttemptEvent {
+    pub task_id: Option<String>,
+    pub action: String,
+    pub provider: String,
+    pub model_id: String,
+    pub attempt: u32,
+    pub delay_ms: Option<u64>,
+    pub error: String,
+    pub next_provider: Option<String>,
+    pub next_model_id: Option<String>,
+}
+
+/// Backoff for transient provider errors, read from `AOP_LLM_MAX_RETRIES` and
+/// `AOP_LLM_RETRY_BASE_MS`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay_ms: u64,
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Self {
+        let read = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        Self {
+            max_retries: read("AOP_LLM_MAX_RETRIES").unwrap_or(3).min(8) as u32,
+            base_delay_ms: read("AOP_LLM_RETRY_BASE_MS")
+                .unwrap_or(500)
+                .clamp(10, RETRY_MAX_DELAY_MS),
+        }
+    }
+
+    /// Exponential delay before retry number `retry` (1-based), with the upper half
+    /// jittered so parallel agents do not hit the provider in lockstep.
+    fn delay_for(&self, retry: u32) -> Duration {
+        let exponential = self
+            .base_delay_ms
+            .saturating_mul(1_u64 << retry.saturating_sub(1).min(16))
+            .min(RETRY_MAX_DELAY_MS);
+        let half = exponential / 2;
+        let jitter = (uuid::Uuid::new_v4().as_u128() % u128::from(half + 1)) as u64;
+        Duration::from_millis(half + jitter)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -21,7 +170,6 @@ pub struct AdapterResponse {
     pub text: String,
     pub input_tokens: Option<u32>,
     pub output_tokens: Option<u32>,
-    #[allow(dead_code)]
     pub total_cost_usd: Option<f64>,
     #[allow(dead_code)]
     pub resolved_model: Option<String>,
@@ -53,9 +201,249 @@ struct ClaudePrintResult {
     model_usage: HashMap<String, Value>,
     #[serde(default)]
     errors: Vec<Value>,
+    /// Reply validated against `--json-schema`, when one was passed.
+    #[serde(default, rename = "structured_output")]
+    structured_output: Option<Value>,
 }
 
+/// Sends retry and failover events to `listener`.
+pub fn set_attempt_listener(listener: AttemptListener) {
+    let _ = ATTEMPT_LISTENER.set(listener);
+}
+
+/// Resolves the `credential` of model endpoints. Without it only environment keys work.
+pub fn set_credential_resolver(resolver: CredentialResolver) {
+    let _ = CREDENTIAL_RESOLVER.set(resolver);
+}
+
+/// The API key for `endpoint`: its vault `credential` when set, else the variable named by
+/// `api_key_env` or `default_env`. Either read is logged against `accessor`; environment
+/// reads only when `pool` is given.
+pub fn endpoint_api_key(
+    endpoint: &ModelEndpoint,
+    default_env: Option<&str>,
+    accessor: &str,
+    pool: Option<&SqlitePool>,
+) -> Result<Option<String>, String> {
+    if let Some(credential) = endpoint.credential.as_deref() {
+        let resolver = CREDENTIAL_RESOLVER
+            .get()
+            .ok_or_else(|| "Secret vault is not initialized".to_string())?;
+        return resolver(credential, accessor)?
+            .map(|key| Some(key.trim().to_string()))
+            .ok_or_else(|| format!("Credential '{credential}' is not in the secret vault"));
+    }
+    let Some(name) = endpoint.api_key_env.as_deref().or(default_env) else {
+        return Ok(None);
+    };
+    let key = env_value(name);
+    let outcome = if key.is_some() { "found" } else { "missing" };
+    if let Some(pool) = pool {
+        secret_vault::record_access(pool, accessor, &format!("env:{name}"), "env", outcome);
+    }
+    Ok(key)
+}
+
+/// Generates a reply, retrying transient provider errors with backoff and then failing
+/// over to `request.fallbacks` in order. Identical requests are answered from the
+/// response cache when it is enabled; anything else is refused once a global spend cap
+/// or the task's token budget is used up. Usage is debited before the reply is returned,
+/// and a call that exhausts the task's budget fails instead.
 pub fn generate(request: &AdapterRequest) -> Result<AdapterResponse, String> {
+    if let Some(cached) = cached_reply(request)? {
+        return Ok(cached);
+    }
+    check_spend_cap(request)?;
+    check_task_budget(request)?;
+    let started = Instant::now();
+    let result = run_with_failover(request, RetryPolicy::from_env(), &|| true, &mut dispatch);
+    llm_transcript::record(request, &result, started.elapsed());
+    record_spend(request, &result);
+    let response = result?;
+    debit_task_budget(request, &response)?;
+    cache_reply(request, &response)?;
+    Ok(response)
+}
+
+/// The database and task a call is budgeted against, when it has both.
+fn budgeted_task(request: &AdapterRequest) -> Option<(SqlitePool, String)> {
+    let task_id = request.task_id.as_deref().map(str::trim)?;
+    if task_id.is_empty() {
+        return None;
+    }
+    Some((request.pool.clone()?, task_id.to_string()))
+}
+
+fn check_spend_cap(request: &AdapterRequest) -> Result<(), String> {
+    let Some(pool) = request.pool.clone() else {
+        return Ok(());
+    };
+    run_query(async move { spend_cap::check(&pool).await })
+}
+
+/// Adds a completed call's cost to the spend ledger in the background; the reply does not
+/// wait on cap warnings or the pausing of tasks.
+fn record_spend(request: &AdapterRequest, result: &Result<AdapterResponse, String>) {
+    let (Some(pool), Some(cost)) = (
+        request.pool.clone(),
+        result
+            .as_ref()
+            .ok()
+            .and_then(|response| response.total_cost_usd),
+    ) else {
+        return;
+    };
+    tauri::async_runtime::spawn(async move {
+        let _ = spend_cap::record(&pool, cost).await;
+    });
+}
+
+fn check_task_budget(request: &AdapterRequest) -> Result<(), String> {
+    let Some((pool, task_id)) = budgeted_task(request) else {
+        return Ok(());
+    };
+    run_query(async move { token_ledger::check(&pool, &task_id).await })
+}
+
+fn debit_task_budget(request: &AdapterRequest, response: &AdapterResponse) -> Result<(), String> {
+    let Some((pool, task_id)) = budgeted_task(request) else {
+        return Ok(());
+    };
+    let tokens = token_ledger::billed_tokens(request, response);
+    run_query(async move { token_ledger::debit(&pool, &task_id, tokens).await })
+}
+
+fn cached_reply(request: &AdapterRequest) -> Result<Option<AdapterResponse>, String> {
+    let Some(pool) = request.pool.clone().filter(|_| llm_cache::applies(request)) else {
+        return Ok(None);
+    };
+    let request = request.clone();
+    run_query(async move { llm_cache::lookup(&pool, &request).await })
+}
+
+fn cache_reply(request: &AdapterRequest, response: &AdapterResponse) -> Result<(), String> {
+    let Some(pool) = request.pool.clone().filter(|_| llm_cache::applies(request)) else {
+        return Ok(());
+    };
+    let (request, response) = (request.clone(), response.clone());
+    run_query(async move { llm_cache::store(&pool, &request, &response).await })
+}
+
+/// Runs a database query for the synchronous adapter on the async runtime and waits for
+/// it. The adapter is called from blocking threads and runtime workers alike, so the query
+/// never runs on the calling thread.
+fn run_query<T, F>(query: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: Future<Output = Result<T, String>> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    tauri::async_runtime::spawn(async move {
+        let _ = sender.send(query.await);
+    });
+    receiver
+        .recv()
+        .map_err(|_| "Model call query stopped before finishing".to_string())?
+}
+
+/// Replies to a [`generate_many`] batch, in request order.
+#[derive(Debug)]
+pub struct BatchOutcome {
+    pub results: Vec<Result<AdapterResponse, String>>,
+    /// Wall time of each request, including retries and failover.
+    pub latencies: Vec<Duration>,
+}
+
+impl BatchOutcome {
+    pub fn failed(&self) -> usize {
+        self.results.iter().filter(|result| result.is_err()).count()
+    }
+
+    /// Every reply, or one error naming each request that failed.
+    pub fn into_all(self) -> Result<Vec<AdapterResponse>, String> {
+        let total = self.results.len();
+        let mut responses = Vec::with_capacity(total);
+        let mut failures = Vec::new();
+        for (index, result) in self.results.into_iter().enumerate() {
+            match result {
+                Ok(response) => responses.push(response),
+                Err(error) => failures.push(format!("request {}: {error}", index + 1)),
+            }
+        }
+        if failures.is_empty() {
+            Ok(responses)
+        } else {
+            Err(format!(
+                "{} of {total} batched requests failed: {}",
+                failures.len(),
+                failures.join(" | ")
+            ))
+        }
+    }
+}
+
+/// Runs independent requests concurrently, at most `max_parallel` at a time. `call` is
+/// [`generate`] or `llm_stream::generate` when replies should stream to the UI. One
+/// failure does not stop the rest.
+pub fn generate_many<F>(requests: &[AdapterRequest], max_parallel: usize, call: F) -> BatchOutcome
+where
+    F: Fn(&AdapterRequest) -> Result<AdapterResponse, String> + Sync,
+{
+    let (results, latencies) = run_bounded(requests, max_parallel, |request| {
+        let started = Instant::now();
+        let result = call(request);
+        (result, started.elapsed())
+    })
+    .into_iter()
+    .unzip();
+    BatchOutcome { results, latencies }
+}
+
+/// Parallelism for batched model calls, from `AOP_LLM_BATCH_PARALLELISM`.
+pub fn batch_parallelism() -> usize {
+    std::env::var("AOP_LLM_BATCH_PARALLELISM")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_BATCH_PARALLELISM)
+        .clamp(1, MAX_BATCH_PARALLELISM)
+}
+
+/// Maps `items` through `run` on up to `max_parallel` scoped threads, keeping input order.
+fn run_bounded<T, R, F>(items: &[T], max_parallel: usize, run: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let workers = max_parallel.clamp(1, items.len().max(1));
+    if workers == 1 {
+        return items.iter().map(&run).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let slots: Vec<Mutex<Option<R>>> = items.iter().map(|_| Mutex::new(None)).collect();
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let result = run(item);
+                *slots[index].lock().unwrap_or_else(PoisonError::into_inner) = Some(result);
+            });
+        }
+    });
+    slots
+        .into_iter()
+        .map(|slot| {
+            slot.into_inner()
+                .unwrap_or_else(PoisonError::into_inner)
+                .expect("every batch slot is filled before the scope ends")
+        })
+        .collect()
+}
+
+fn dispatch(request: &AdapterRequest) -> Result<AdapterResponse, String> {
     let normalized = normalize_provider(&request.provider);
     if CLAUDE_CODE_PROVIDER_ALIASES
         .iter()
@@ -63,11 +451,14 @@ pub fn generate(request: &AdapterRequest) -> Result<AdapterResponse, String> {
     {
         return call_claude_code(request);
     }
-    if OPENAI_PROVIDER_ALIASES
+    if is_openai_family(&normalized) {
+        return call_openai_chat_completions(request);
+    }
+    if OLLAMA_PROVIDER_ALIASES
         .iter()
         .any(|alias| normalized == *alias)
     {
-        return call_openai_chat_completions(request);
+        return call_ollama_chat(&ollama_base_url(), request, None);
     }
 
     Err(format!(
@@ -76,6 +467,322 @@ pub fn generate(request: &AdapterRequest) -> Result<AdapterResponse, String> {
     ))
 }
 
+/// Like [`generate`], but hands each piece of output to `on_delta` as it arrives.
+/// Returning `false` from `on_delta` stops the model and fails with
+/// [`GENERATION_CANCELLED`]. Once output has been streamed a failure is final, since
+/// a retry would repeat text the caller already received.
+pub fn generate_streaming(
+    request: &AdapterRequest,
+    on_delta: &mut dyn FnMut(&str) -> bool,
+) -> Result<AdapterResponse, String> {
+    if let Some(cached) = cached_reply(request)? {
+        if !on_delta(&cached.text) {
+            return Err(GENERATION_CANCELLED.to_string());
+        }
+        return Ok(cached);
+    }
+    check_spend_cap(request)?;
+    check_task_budget(request)?;
+    let started = Instant::now();
+    let streamed = Cell::new(false);
+    let result = run_with_failover(
+        request,
+        RetryPolicy::from_env(),
+        &|| !streamed.get(),
+        &mut |attempt| {
+            dispatch_streaming(attempt, &mut |delta| {
+                streamed.set(true);
+                on_delta(delta)
+            })
+        },
+    );
+    llm_transcript::record(request, &result, started.elapsed());
+    record_spend(request, &result);
+    let response = result?;
+    debit_task_budget(request, &response)?;
+    cache_reply(request, &response)?;
+    Ok(response)
+}
+
+fn dispatch_streaming(
+    request: &AdapterRequest,
+    on_delta: &mut dyn FnMut(&str) -> bool,
+) -> Result<AdapterResponse, String> {
+    let normalized = normalize_provider(&request.provider);
+    if CLAUDE_CODE_PROVIDER_ALIASES
+        .iter()
+        .any(|alias| normalized == *alias)
+    {
+        return stream_claude_code(request, on_delta);
+    }
+    if is_openai_family(&normalized) {
+        return stream_openai_chat_completions(request, on_delta);
+    }
+    if OLLAMA_PROVIDER_ALIASES
+        .iter()
+        .any(|alias| normalized == *alias)
+    {
+        return call_ollama_chat(&ollama_base_url(), request, Some(on_delta));
+    }
+
+    Err(format!(
+        "No remote adapter configured for provider '{}'",
+        request.provider.trim()
+    ))
+}
+
+/// Calls the primary model and then each fallback until one succeeds. Transient errors
+/// are retried per model; `can_retry` returning `false` makes any failure final. Every
+/// call waits for its provider's rate and concurrency limits first.
+fn run_with_failover(
+    request: &AdapterRequest,
+    policy: RetryPolicy,
+    can_retry: &dyn Fn() -> bool,
+    call: &mut dyn FnMut(&AdapterRequest) -> Result<AdapterResponse, String>,
+) -> Result<AdapterResponse, String> {
+    let chain_len = request.fallbacks.len() + 1;
+    let mut failures = Vec::new();
+    for index in 0..chain_len {
+        let candidate = match index {
+            0 => Cow::Borrowed(request),
+            _ => Cow::Owned(fallback_request(request, &request.fallbacks[index - 1])),
+        };
+        let mut attempt = 0;
+        let error = loop {
+            attempt += 1;
+            let result = {
+                let _permit = llm_rate_limit::acquire(&candidate.provider);
+                call(&*candidate)
+            };
+            match result {
+                Ok(response) => return Ok(with_token_counts(&candidate, response)),
+                Err(error) if error == GENERATION_CANCELLED || !can_retry() => return Err(error),
+                Err(error) if attempt <= policy.max_retries && is_transient_error(&error) => {
+                    let delay = policy.delay_for(attempt);
+                    notify_attempt(AdapterAttemptEvent {
+                        task_id: request.task_id.clone(),
+                        action: "llm_retry".to_string(),
+                        provider: candidate.provider.clone(),
+                        model_id: candidate.model_id.clone(),
+                        attempt,
+                        delay_ms: Some(delay.as_millis() as u64),
+                        error: error.clone(),
+                        next_provider: None,
+                        next_model_id: None,
+                    });
+                    std::thread::sleep(delay);
+                }
+                Err(error) => break error,
+            }
+        };
+        if let Some(next) = request.fallbacks.get(index) {
+            notify_attempt(AdapterAttemptEvent {
+                task_id: request.task_id.clone(),
+                action: "llm_failover".to_string(),
+                provider: candidate.provider.clone(),
+                model_id: candidate.model_id.clone(),
+                attempt,
+                delay_ms: None,
+                error: error.clone(),
+                next_provider: Some(next.provider.clone()),
+                next_model_id: Some(next.model_id.clone()),
+            });
+        }
+        failures.push(format!(
+            "{}/{}: {}",
+            candidate.provider.trim(),
+            candidate.model_id.trim(),
+            error
+        ));
+    }
+
+    if failures.len() == 1 {
+        return Err(failures.remove(0));
+    }
+    Err(format!(
+        "All {} models in the fallback chain failed.\n{}",
+        failures.len(),
+        failures.join("\n")
+    ))
+}
+
+/// Counts tokens locally for whatever the provider did not report, and prices the call
+/// from the registry when the provider did not, so callers always get usage figures.
+fn with_token_counts(request: &AdapterRequest, mut response: AdapterResponse) -> AdapterResponse {
+    let input_tokens = *response
+        .input_tokens
+        .get_or_insert_with(|| token_count::count_prompt_tokens(request));
+    let output_tokens = *response
+        .output_tokens
+        .get_or_insert_with(|| token_count::count_tokens(&request.model_id, &response.text));
+    if response.total_cost_usd.is_none() {
+        response.total_cost_usd = llm_pricing::cost_usd(
+            &request.provider,
+            &request.model_id,
+            input_tokens,
+            output_tokens,
+        );
+    }
+    response
+}
+
+fn fallback_request(request: &AdapterRequest, fallback: &ModelSelection) -> AdapterRequest {
+    AdapterRequest {
+        provider: fallback.provider.clone(),
+        model_id: fallback.model_id.clone(),
+        system_prompt: request.system_prompt.clone(),
+        user_prompt: request.user_prompt.clone(),
+        endpoint: fallback.endpoint.clone(),
+        generation: fallback.generation.clone(),
+        task_id: request.task_id.clone(),
+        fallbacks: Vec::new(),
+        response_schema: request.response_schema.clone(),
+        bypass_cache: request.bypass_cache,
+        attachments: request.attachments.clone(),
+        pool: request.pool.clone(),
+    }
+}
+
+/// Rate limits, server errors and dropped connections; anything else fails the same
+/// way on a second try.
+fn is_transient_error(error: &str) -> bool {
+    // A model that hung once will likely hang again; fail over instead of waiting out
+    // another full deadline.
+    if is_timeout_error(error) {
+        return false;
+    }
+    let lowered = error.to_ascii_lowercase();
+    let transient_status = ["429", "500", "502", "503", "504", "529"]
+        .iter()
+        .any(|code| lowered.contains(&format!("status {code}")));
+    transient_status
+        || [
+            "rate limit",
+            "rate_limit",
+            "overloaded",
+            "timed out",
+            "timeout",
+            "connection reset",
+            "connection refused",
+            "failed to call openai api",
+        ]
+        .iter()
+        .any(|marker| lowered.contains(marker))
+}
+
+/// Whether `error` is an adapter deadline rather than a provider failure.
+pub fn is_timeout_error(error: &str) -> bool {
+    error.contains(REQUEST_TIMED_OUT)
+}
+
+/// The model's `timeoutSecs`, else the provider family's `AOP_*_TIMEOUT_SECS`.
+fn request_timeout(request: &AdapterRequest) -> Duration {
+    let normalized = normalize_provider(&request.provider);
+    let (key, default_secs) = if CLAUDE_CODE_PROVIDER_ALIASES.contains(&normalized.as_str()) {
+        ("AOP_CLAUDE_CODE_TIMEOUT_SECS", CLAUDE_CODE_TIMEOUT_SECS)
+    } else if OLLAMA_PROVIDER_ALIASES.contains(&normalized.as_str()) {
+        ("AOP_OLLAMA_TIMEOUT_SECS", OLLAMA_TIMEOUT_SECS)
+    } else {
+        ("AOP_OPENAI_TIMEOUT_SECS", OPENAI_TIMEOUT_SECS)
+    };
+    let secs = request.endpoint.timeout_secs.unwrap_or_else(|| {
+        std::env::var(key)
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .unwrap_or(default_secs)
+    });
+    Duration::from_secs(secs.clamp(1, MAX_TIMEOUT_SECS))
+}
+
+fn timeout_error(request: &AdapterRequest) -> String {
+    format!(
+        "{REQUEST_TIMED_OUT} after {}s waiting on {}/{}",
+        request_timeout(request).as_secs(),
+        request.provider.trim(),
+        request.model_id.trim()
+    )
+}
+
+/// reqwest reports an expired client timeout while reading the body as an I/O error.
+fn is_io_timeout(error: &std::io::Error) -> bool {
+    error.kind() == std::io::ErrorKind::TimedOut
+        || error
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<reqwest::Error>())
+            .is_some_and(reqwest::Error::is_timeout)
+}
+
+/// Caps the text one reply may produce at `AOP_LLM_MAX_RESPONSE_CHARS`, so a model stuck
+/// repeating itself cannot grow a reply without bound.
+struct ResponseGuard {
+    limit: usize,
+    seen: usize,
+}
+
+impl ResponseGuard {
+    fn from_env() -> Self {
+        let limit = std::env::var("AOP_LLM_MAX_RESPONSE_CHARS")
+            .ok()
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_RESPONSE_CHARS)
+            .max(1);
+        Self { limit, seen: 0 }
+    }
+
+    fn add(&mut self, text: &str) -> Result<(), String> {
+        self.seen += text.chars().count();
+        if self.seen > self.limit {
+            return Err(self.exceeded());
+        }
+        Ok(())
+    }
+
+    /// Bytes allowed for a raw body, leaving room for JSON framing and multi-byte text.
+    fn body_limit(&self) -> usize {
+        self.limit.saturating_mul(4).saturating_add(64 * 1024)
+    }
+
+    fn exceeded(&self) -> String {
+        format!(
+            "Model response exceeded {} characters and was cut off",
+            self.limit
+        )
+    }
+}
+
+/// Reads a whole HTTP body, failing past the response size limit or the deadline.
+fn read_limited_body(
+    request: &AdapterRequest,
+    guard: &ResponseGuard,
+    response: reqwest::blocking::Response,
+) -> Result<String, String> {
+    let limit = guard.body_limit();
+    let mut body = Vec::new();
+    response
+        .take(limit as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|error| {
+            if is_io_timeout(&error) {
+                timeout_error(request)
+            } else {
+                format!(
+                    "Failed to read {} response: {error}",
+                    request.provider.trim()
+                )
+            }
+        })?;
+    if body.len() > limit {
+        return Err(guard.exceeded());
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+fn notify_attempt(event: AdapterAttemptEvent) {
+    if let Some(listener) = ATTEMPT_LISTENER.get() {
+        listener(&event);
+    }
+}
+
 pub fn supports_provider(provider: &str) -> bool {
     let normalized = normalize_provider(provider);
     if CLAUDE_CODE_PROVIDER_ALIASES
@@ -93,12 +800,28 @@ pub fn supports_provider(provider: &str) -> bool {
             .map(|value| !value.trim().is_empty())
             .unwrap_or(false);
     }
+    // Configured per model, so their credentials are checked when the call is made.
+    if OPENAI_COMPATIBLE_PROVIDER_ALIASES
+        .iter()
+        .chain(AZURE_OPENAI_PROVIDER_ALIASES)
+        .any(|alias| normalized == *alias)
+    {
+        return true;
+    }
+    if OLLAMA_PROVIDER_ALIASES
+        .iter()
+        .any(|alias| normalized == *alias)
+    {
+        return ollama_reachable();
+    }
     false
 }
 
 pub fn supported_provider_aliases() -> Vec<String> {
     let mut values = CLAUDE_CODE_PROVIDER_ALIASES
         .iter()
+        .chain(OPENAI_COMPATIBLE_PROVIDER_ALIASES)
+        .chain(AZURE_OPENAI_PROVIDER_ALIASES)
         .map(|value| value.to_string())
         .collect::<Vec<_>>();
     if std::env::var("OPENAI_API_KEY")
@@ -108,6 +831,13 @@ pub fn supported_provider_aliases() -> Vec<String> {
     {
         values.extend(OPENAI_PROVIDER_ALIASES.iter().map(|value| value.to_string()));
     }
+    if ollama_reachable() {
+        values.extend(
+            OLLAMA_PROVIDER_ALIASES
+                .iter()
+                .map(|value| value.to_string()),
+        );
+    }
     values
 }
 
@@ -115,7 +845,198 @@ fn normalize_provider(provider: &str) -> String {
     provider.trim().to_ascii_lowercase()
 }
 
+/// The API family behind a provider alias.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderFamily {
+    ClaudeCode,
+    OpenAi,
+    OpenAiCompatible,
+    AzureOpenAi,
+    Ollama,
+}
+
+pub fn provider_family(provider: &str) -> Option<ProviderFamily> {
+    let normalized = normalize_provider(provider);
+    let is = |aliases: &[&str]| aliases.contains(&normalized.as_str());
+    if is(CLAUDE_CODE_PROVIDER_ALIASES) {
+        Some(ProviderFamily::ClaudeCode)
+    } else if is(OPENAI_PROVIDER_ALIASES) {
+        Some(ProviderFamily::OpenAi)
+    } else if is(OPENAI_COMPATIBLE_PROVIDER_ALIASES) {
+        Some(ProviderFamily::OpenAiCompatible)
+    } else if is(AZURE_OPENAI_PROVIDER_ALIASES) {
+        Some(ProviderFamily::AzureOpenAi)
+    } else if is(OLLAMA_PROVIDER_ALIASES) {
+        Some(ProviderFamily::Ollama)
+    } else {
+        None
+    }
+}
+
+fn is_openai_family(normalized: &str) -> bool {
+    OPENAI_PROVIDER_ALIASES
+        .iter()
+        .chain(OPENAI_COMPATIBLE_PROVIDER_ALIASES)
+        .chain(AZURE_OPENAI_PROVIDER_ALIASES)
+        .any(|alias| normalized == *alias)
+}
+
 fn call_claude_code(request: &AdapterRequest) -> Result<AdapterResponse, String> {
+    let child = spawn_claude_code(request, &["--output-format", "json"])?;
+    let mut guard = ResponseGuard::from_env();
+    let body_limit = guard.body_limit();
+    let mut stdout = String::new();
+    let (status, stderr) = drive_claude_code(request, child, &mut |line| {
+        if stdout.len() + line.len() > body_limit {
+            return Err(guard.exceeded());
+        }
+        stdout.push_str(&line);
+        stdout.push('\n');
+        Ok(())
+    })?;
+
+    if !status.success() {
+        return Err(format!(
+            "Claude Code adapter failed with status {}.\nstdout:\n{}\nstderr:\n{}",
+            status, stdout, stderr
+        ));
+    }
+
+    let response = claude_response(parse_last_json_line(&stdout)?)?;
+    guard.add(&response.text)?;
+    Ok(response)
+}
+
+/// Streams text deltas from `stream-json` output. CLI versions without partial
+/// messages only report the final result, which is then forwarded as one delta.
+fn stream_claude_code(
+    request: &AdapterRequest,
+    on_delta: &mut dyn FnMut(&str) -> bool,
+) -> Result<AdapterResponse, String> {
+    let child = spawn_claude_code(
+        request,
+        &[
+            "--output-format",
+            "stream-json",
+            "--verbose",
+            "--include-partial-messages",
+        ],
+    )?;
+
+    let mut guard = ResponseGuard::from_env();
+    let mut result = None;
+    let mut streamed = false;
+    let mut unparsed = Vec::new();
+    let (status, stderr) = drive_claude_code(request, child, &mut |line| {
+        let Ok(event) = serde_json::from_str::<Value>(line.trim()) else {
+            unparsed.push(line);
+            return Ok(());
+        };
+        if let Some(delta) = claude_stream_text_delta(&event) {
+            streamed = true;
+            guard.add(delta)?;
+            if !on_delta(delta) {
+                return Err(GENERATION_CANCELLED.to_string());
+            }
+        } else if event.get("type").and_then(Value::as_str) == Some("result") {
+            result = serde_json::from_value::<ClaudePrintResult>(event).ok();
+        }
+        Ok(())
+    })?;
+
+    if !status.success() {
+        return Err(format!(
+            "Claude Code adapter failed with status {}.\nstdout:\n{}\nstderr:\n{}",
+            status,
+            unparsed.join("\n"),
+            stderr
+        ));
+    }
+    let parsed = result.ok_or_else(|| {
+        format!(
+            "Claude Code stream ended without a result.\nstderr:\n{}",
+            stderr
+        )
+    })?;
+    let response = claude_response(parsed)?;
+    if !streamed {
+        guard.add(&response.text)?;
+    }
+    if !streamed && !on_delta(&response.text) {
+        return Err(GENERATION_CANCELLED.to_string());
+    }
+    Ok(response)
+}
+
+/// Feeds the CLI's stdout to `on_line` until it exits and returns the exit status and
+/// stderr. The process is killed when `on_line` fails or the request's deadline passes.
+fn drive_claude_code(
+    request: &AdapterRequest,
+    mut child: std::process::Child,
+    on_line: &mut dyn FnMut(String) -> Result<(), String>,
+) -> Result<(std::process::ExitStatus, String), String> {
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| "Claude Code CLI stdout was not captured".to_string())?;
+    let mut stderr = child.stderr.take();
+    // Drain stderr alongside stdout so a chatty CLI cannot block on a full pipe.
+    let stderr_reader = thread::spawn(move || {
+        let mut buffer = String::new();
+        if let Some(stderr) = stderr.as_mut() {
+            let _ = stderr.read_to_string(&mut buffer);
+        }
+        buffer
+    });
+    // Lines arrive over a channel so the wait for the next one can give up at the deadline.
+    let (line_sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            if line_sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let deadline = Instant::now() + request_timeout(request);
+    loop {
+        let next = match lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(line) => line.map_err(|error| format!("Failed to read Claude Code output: {error}")),
+            Err(RecvTimeoutError::Timeout) => Err(timeout_error(request)),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if let Err(error) = next.and_then(&mut *on_line) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(error);
+        }
+    }
+
+    let status = child
+        .wait()
+        .map_err(|error| format!("Failed to wait for Claude Code CLI: {error}"))?;
+    Ok((status, stderr_reader.join().unwrap_or_default()))
+}
+
+fn claude_stream_text_delta(event: &Value) -> Option<&str> {
+    if event.get("type").and_then(Value::as_str) != Some("stream_event") {
+        return None;
+    }
+    let inner = event.get("event")?;
+    if inner.get("type").and_then(Value::as_str) != Some("content_block_delta") {
+        return None;
+    }
+    let delta = inner.get("delta")?;
+    if delta.get("type").and_then(Value::as_str) != Some("text_delta") {
+        return None;
+    }
+    delta.get("text").and_then(Value::as_str)
+}
+
+fn spawn_claude_code(
+    request: &AdapterRequest,
+    output_args: &[&str],
+) -> Result<std::process::Child, String> {
     if request.model_id.trim().is_empty() {
         return Err("Model adapter requires non-empty modelId".to_string());
     }
@@ -123,8 +1044,7 @@ fn call_claude_code(request: &AdapterRequest) -> Result<AdapterResponse, String>
     let mut command = Command::new("claude");
     command
         .arg("-p")
-        .arg("--output-format")
-        .arg("json")
+        .args(output_args)
         .arg("--disable-slash-commands")
         .arg("--no-session-persistence")
         .arg("--model")
@@ -135,6 +1055,9 @@ fn call_claude_code(request: &AdapterRequest) -> Result<AdapterResponse, String>
     if let Some(max_budget) = read_optional_max_budget() {
         command.arg("--max-budget-usd").arg(max_budget);
     }
+    if let Some(schema) = &request.response_schema {
+        command.arg("--json-schema").arg(schema.schema.to_string());
+    }
 
     // Pipe the user prompt via stdin to avoid Windows command-line length limits.
     // CreateProcess has a ~32K char limit; the user prompt can include full file
@@ -153,27 +1076,34 @@ fn call_claude_code(request: &AdapterRequest) -> Result<AdapterResponse, String>
         stdin
             .write_all(request.user_prompt.as_bytes())
             .map_err(|error| format!("Failed to write prompt to Claude CLI stdin: {error}"))?;
+        if !request.attachments.is_empty() {
+            // `claude -p` reads a text prompt only; name the images so the model knows
+            // they exist. Route image tasks to an OpenAI or Ollama vision model instead.
+            let names: Vec<&str> = request
+                .attachments
+                .iter()
+                .map(|attachment| attachment.name.as_str())
+                .collect();
+            let note = format!(
+                "\n\n[{} image attachment(s) not shown to this model: {}]",
+                names.len(),
+                names.join(", ")
+            );
+            stdin
+                .write_all(note.as_bytes())
+                .map_err(|error| format!("Failed to write prompt to Claude CLI stdin: {error}"))?;
+        }
     }
 
-    let output = child
-        .wait_with_output()
-        .map_err(|error| format!("Failed to wait for Claude Code CLI: {error}"))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        return Err(format!(
-            "Claude Code adapter failed with status {}.\nstdout:\n{}\nstderr:\n{}",
-            output.status, stdout, stderr
-        ));
-    }
-
-    let stdout = String::from_utf8(output.stdout)
-        .map_err(|error| format!("Claude Code stdout is not UTF-8: {error}"))?;
-    let parsed: ClaudePrintResult = parse_last_json_line(&stdout)?;
+    Ok(child)
+}
 
+fn claude_response(parsed: ClaudePrintResult) -> Result<AdapterResponse, String> {
     let result_text = parsed
-        .result
+        .structured_output
+        .filter(|value| !value.is_null())
+        .map(|value| value.to_string())
+        .or(parsed.result)
         .map(|value| value.trim().to_string())
         .filter(|value| !value.is_empty());
 
@@ -206,38 +1136,11 @@ fn call_claude_code(request: &AdapterRequest) -> Result<AdapterResponse, String>
 }
 
 fn call_openai_chat_completions(request: &AdapterRequest) -> Result<AdapterResponse, String> {
-    if request.model_id.trim().is_empty() {
-        return Err("Model adapter requires non-empty modelId".to_string());
-    }
-    let api_key = std::env::var("OPENAI_API_KEY")
-        .ok()
-        .map(|value| value.trim().to_string())
-        .filter(|value| !value.is_empty())
-        .ok_or_else(|| "OPENAI_API_KEY is required for openai provider".to_string())?;
-
-    let client = reqwest::blocking::Client::builder()
-        .timeout(std::time::Duration::from_secs(60))
-        .build()
-        .map_err(|error| format!("Failed to build OpenAI HTTP client: {error}"))?;
-
-    let body = json!({
-        "model": request.model_id.trim(),
-        "messages": [
-            { "role": "system", "content": request.system_prompt.trim() },
-            { "role": "user", "content": request.user_prompt.trim() }
-        ],
-        "temperature": 0.2
-    });
-    let response = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .bearer_auth(api_key)
-        .json(&body)
-        .send()
-        .map_err(|error| format!("Failed to call OpenAI API: {error}"))?;
-
+    let response = send_openai_chat_completions(request, false)?;
     let status = response.status();
-    let payload: Value = response
-        .json()
+    let mut guard = ResponseGuard::from_env();
+    let body = read_limited_body(request, &guard, response)?;
+    let payload: Value = serde_json::from_str(&body)
         .map_err(|error| format!("Invalid OpenAI response payload: {error}"))?;
     if !status.is_success() {
         return Err(format!(
@@ -258,18 +1161,9 @@ fn call_openai_chat_completions(request: &AdapterRequest) -> Result<AdapterRespo
         .filter(|value| !value.is_empty())
         .ok_or_else(|| format!("OpenAI response did not include assistant content: {payload}"))?
         .to_string();
+    guard.add(&text)?;
 
-    let input_tokens = payload
-        .get("usage")
-        .and_then(|usage| usage.get("prompt_tokens"))
-        .and_then(Value::as_u64)
-        .map(|value| value as u32);
-    let output_tokens = payload
-        .get("usage")
-        .and_then(|usage| usage.get("completion_tokens"))
-        .and_then(Value::as_u64)
-        .map(|value| value as u32);
-
+    let (input_tokens, output_tokens) = openai_usage(&payload);
     Ok(AdapterResponse {
         text,
         input_tokens,
@@ -282,6 +1176,430 @@ fn call_openai_chat_completions(request: &AdapterRequest) -> Result<AdapterRespo
     })
 }
 
+/// Reads the server-sent events of a streamed chat completion; usage arrives in the
+/// last chunk because `include_usage` is requested.
+fn stream_openai_chat_completions(
+    request: &AdapterRequest,
+    on_delta: &mut dyn FnMut(&str) -> bool,
+) -> Result<AdapterResponse, String> {
+    let response = send_openai_chat_completions(request, true)?;
+    let status = response.status();
+    if !status.is_success() {
+        let payload = response.text().unwrap_or_default();
+        return Err(format!(
+            "OpenAI adapter failed with status {}: {}",
+            status, payload
+        ));
+    }
+
+    let mut guard = ResponseGuard::from_env();
+    let mut text = String::new();
+    let mut input_tokens = None;
+    let mut output_tokens = None;
+    let mut resolved_model = None;
+    for line in BufReader::new(response).lines() {
+        let line = line.map_err(|error| {
+            if is_io_timeout(&error) {
+                timeout_error(request)
+            } else {
+                format!("Failed to read OpenAI stream: {error}")
+            }
+        })?;
+        let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+            continue;
+        };
+        if data == "[DONE]" {
+            break;
+        }
+        let chunk: Value = serde_json::from_str(data)
+            .map_err(|error| format!("Invalid OpenAI stream chunk: {error}"))?;
+        if resolved_model.is_none() {
+            resolved_model = chunk
+                .get("model")
+                .and_then(Value::as_str)
+                .map(ToOwned::to_owned);
+        }
+        if chunk.get("usage").is_some_and(|usage| !usage.is_null()) {
+            (input_tokens, output_tokens) = openai_usage(&chunk);
+        }
+        let delta = chunk
+            .get("choices")
+            .and_then(Value::as_array)
+            .and_then(|choices| choices.first())
+            .and_then(|choice| choice.get("delta"))
+            .and_then(|delta| delta.get("content"))
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if delta.is_empty() {
+            continue;
+        }
+        guard.add(delta)?;
+        text.push_str(delta);
+        if !on_delta(delta) {
+            return Err(GENERATION_CANCELLED.to_string());
+        }
+    }
+
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err("OpenAI stream did not include assistant content".to_string());
+    }
+    Ok(AdapterResponse {
+        text,
+        input_tokens,
+        output_tokens,
+        total_cost_usd: None,
+        resolved_model,
+    })
+}
+
+fn send_openai_chat_completions(
+    request: &AdapterRequest,
+    stream: bool,
+) -> Result<reqwest::blocking::Response, String> {
+    if request.model_id.trim().is_empty() {
+        return Err("Model adapter requires non-empty modelId".to_string());
+    }
+    let target = openai_target(request)?;
+    let generation = &request.generation;
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(request_timeout(request))
+        .build()
+        .map_err(|error| format!("Failed to build OpenAI HTTP client: {error}"))?;
+
+    let mut body = json!({
+        "model": request.model_id.trim(),
+        "messages": [
+            { "role": "system", "content": request.system_prompt.trim() },
+            { "role": "user", "content": openai_user_content(request) }
+        ],
+        "temperature": generation.temperature.unwrap_or(DEFAULT_TEMPERATURE)
+    });
+    if let Some(top_p) = generation.top_p {
+        body["top_p"] = json!(top_p);
+    }
+    if let Some(max_tokens) = generation.max_output_tokens {
+        // Compatible servers only know the older `max_tokens` name.
+        let key = if OPENAI_COMPATIBLE_PROVIDER_ALIASES
+            .contains(&normalize_provider(&request.provider).as_str())
+        {
+            "max_tokens"
+        } else {
+            "max_completion_tokens"
+        };
+        body[key] = json!(max_tokens);
+    }
+    if !generation.stop.is_empty() {
+        body["stop"] = json!(generation.stop);
+    }
+    if stream {
+        body["stream"] = json!(true);
+        body["stream_options"] = json!({ "include_usage": true });
+    }
+    if let Some(schema) = &request.response_schema {
+        body["response_format"] = json!({
+            "type": "json_schema",
+            "json_schema": { "name": schema.name, "schema": schema.schema, "strict": true }
+        });
+    }
+    let mut http_request = client.post(&target.url).json(&body);
+    if let Some((header, value)) = target.auth {
+        http_request = http_request.header(header, value);
+    }
+    http_request.send().map_err(|error| {
+        if error.is_timeout() {
+            timeout_error(request)
+        } else {
+            format!("Failed to call OpenAI API at '{}': {error}", target.url)
+        }
+    })
+}
+
+struct OpenAiTarget {
+    url: String,
+    /// Header name and value carrying the API key, if any.
+    auth: Option<(&'static str, String)>,
+}
+
+/// Resolves the chat completions URL and credentials for OpenAI, Azure OpenAI or an
+/// OpenAI-compatible server from the model's endpoint settings.
+fn openai_target(request: &AdapterRequest) -> Result<OpenAiTarget, String> {
+    let endpoint = &request.endpoint;
+    let provider = normalize_provider(&request.provider);
+    let read_key = |default_env: Option<&str>| {
+        endpoint_api_key(endpoint, default_env, "llm_adapter", request.pool.as_ref())
+    };
+
+    if AZURE_OPENAI_PROVIDER_ALIASES.contains(&provider.as_str()) {
+        let base_url = endpoint
+            .base_url
+            .clone()
+            .or_else(|| env_value("AZURE_OPENAI_ENDPOINT"))
+            .ok_or_else(|| {
+                "Azure OpenAI requires a baseUrl in models.json or AZURE_OPENAI_ENDPOINT"
+                    .to_string()
+            })?;
+        let api_version = endpoint
+            .api_version
+            .clone()
+            .or_else(|| env_value("AZURE_OPENAI_API_VERSION"))
+            .unwrap_or_else(|| AZURE_OPENAI_DEFAULT_API_VERSION.to_string());
+        let deployment = endpoint
+            .deployment
+            .as_deref()
+            .unwrap_or(request.model_id.trim());
+        let api_key = read_key(Some("AZURE_OPENAI_API_KEY"))?.ok_or_else(|| {
+            format!(
+                "{} is required for azure_openai provider",
+                endpoint
+                    .api_key_env
+                    .as_deref()
+                    .unwrap_or("AZURE_OPENAI_API_KEY")
+            )
+        })?;
+        return Ok(OpenAiTarget {
+            url: format!(
+                "{}/openai/deployments/{}/chat/completions?api-version={}",
+                base_url.trim_end_matches('/'),
+                deployment,
+                api_version
+            ),
+            auth: Some(("api-key", api_key)),
+        });
+    }
+
+    if OPENAI_COMPATIBLE_PROVIDER_ALIASES.contains(&provider.as_str()) {
+        let base_url = endpoint.base_url.as_deref().ok_or_else(|| {
+            format!(
+                "Provider '{}' requires a baseUrl in models.json",
+                request.provider.trim()
+            )
+        })?;
+        return Ok(OpenAiTarget {
+            url: format!("{}/chat/completions", base_url.trim_end_matches('/')),
+            auth: read_key(None)?.map(|key| ("Authorization", format!("Bearer {key}"))),
+        });
+    }
+
+    let base_url = endpoint
+        .base_url
+        .as_deref()
+        .unwrap_or(OPENAI_DEFAULT_BASE_URL);
+    let api_key = read_key(Some("OPENAI_API_KEY"))?;
+    // A proxy in front of OpenAI may authenticate on its own.
+    if api_key.is_none() && endpoint.base_url.is_none() {
+        return Err(format!(
+            "{} is required for openai provider",
+            endpoint.api_key_env.as_deref().unwrap_or("OPENAI_API_KEY")
+        ));
+    }
+    Ok(OpenAiTarget {
+        url: format!("{}/chat/completions", base_url.trim_end_matches('/')),
+        auth: api_key.map(|key| ("Authorization", format!("Bearer {key}"))),
+    })
+}
+
+fn env_value(key: &str) -> Option<String> {
+    std::env::var(key)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn openai_usage(payload: &Value) -> (Option<u32>, Option<u32>) {
+    let usage = payload.get("usage");
+    let count = |field: &str| {
+        usage
+            .and_then(|usage| usage.get(field))
+            .and_then(Value::as_u64)
+            .map(|value| value as u32)
+    };
+    (count("prompt_tokens"), count("completion_tokens"))
+}
+
+fn ollama_options(generation: &GenerationParams) -> Value {
+    let mut options = json!({
+        "temperature": generation.temperature.unwrap_or(DEFAULT_TEMPERATURE)
+    });
+    if let Some(top_p) = generation.top_p {
+        options["top_p"] = json!(top_p);
+    }
+    if let Some(max_tokens) = generation.max_output_tokens {
+        options["num_predict"] = json!(max_tokens);
+    }
+    if !generation.stop.is_empty() {
+        options["stop"] = json!(generation.stop);
+    }
+    options
+}
+
+/// Plain text, or text followed by `image_url` parts when the request has attachments.
+fn openai_user_content(request: &AdapterRequest) -> Value {
+    if request.attachments.is_empty() {
+        return json!(request.user_prompt.trim());
+    }
+    let mut parts = vec![json!({ "type": "text", "text": request.user_prompt.trim() })];
+    parts.extend(request.attachments.iter().map(
+        |attachment| json!({ "type": "image_url", "image_url": { "url": attachment.data_url() } }),
+    ));
+    Value::Array(parts)
+}
+
+/// Ollama `/api/chat` on `AOP_OLLAMA_URL` (default `http://127.0.0.1:11434`). No API key
+/// is involved, so specialists can run fully offline. With `on_delta` the reply is read
+/// as newline-delimited JSON chunks.
+fn call_ollama_chat(
+    base_url: &str,
+    request: &AdapterRequest,
+    mut on_delta: Option<&mut dyn FnMut(&str) -> bool>,
+) -> Result<AdapterResponse, String> {
+    if request.model_id.trim().is_empty() {
+        return Err("Model adapter requires non-empty modelId".to_string());
+    }
+    let client = reqwest::blocking::Client::builder()
+        .timeout(request_timeout(request))
+        .build()
+        .map_err(|error| format!("Failed to build Ollama HTTP client: {error}"))?;
+    let mut user_message = json!({ "role": "user", "content": request.user_prompt.trim() });
+    if !request.attachments.is_empty() {
+        // Only vision models (llava, llama3.2-vision) look at these; others ignore them.
+        user_message["images"] = request
+            .attachments
+            .iter()
+            .map(|attachment| json!(attachment.data_base64))
+            .collect();
+    }
+    let mut body = json!({
+        "model": request.model_id.trim(),
+        "messages": [
+            { "role": "system", "content": request.system_prompt.trim() },
+            user_message
+        ],
+        "stream": on_delta.is_some(),
+        "options": ollama_options(&request.generation)
+    });
+    if let Some(schema) = &request.response_schema {
+        body["format"] = schema.schema.clone();
+    }
+    let response = client
+        .post(format!("{base_url}/api/chat"))
+        .json(&body)
+        .send()
+        .map_err(|error| {
+            if error.is_timeout() {
+                return timeout_error(request);
+            }
+            format!("Failed to reach Ollama at '{base_url}': {error}. Is `ollama serve` running?")
+        })?;
+    let status = response.status();
+    if !status.is_success() {
+        let payload = response.text().unwrap_or_default();
+        return Err(format!(
+            "Ollama adapter failed with status {}: {}",
+            status, payload
+        ));
+    }
+
+    let mut guard = ResponseGuard::from_env();
+    let mut text = String::new();
+    let mut last_chunk = Value::Null;
+    match on_delta.as_mut() {
+        Some(on_delta) => {
+            for line in BufReader::new(response).lines() {
+                let line = line.map_err(|error| {
+                    if is_io_timeout(&error) {
+                        timeout_error(request)
+                    } else {
+                        format!("Failed to read Ollama stream: {error}")
+                    }
+                })?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let chunk: Value = serde_json::from_str(line.trim())
+                    .map_err(|error| format!("Invalid Ollama stream chunk: {error}"))?;
+                if let Some(error) = chunk.get("error").and_then(Value::as_str) {
+                    return Err(format!("Ollama adapter failed: {error}"));
+                }
+                let delta = ollama_message_content(&chunk);
+                if !delta.is_empty() {
+                    guard.add(delta)?;
+                    text.push_str(delta);
+                    if !on_delta(delta) {
+                        return Err(GENERATION_CANCELLED.to_string());
+                    }
+                }
+                last_chunk = chunk;
+            }
+        }
+        None => {
+            let body = read_limited_body(request, &guard, response)?;
+            last_chunk = serde_json::from_str(&body)
+                .map_err(|error| format!("Invalid Ollama response payload: {error}"))?;
+            text = ollama_message_content(&last_chunk).to_string();
+            guard.add(&text)?;
+        }
+    }
+
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err(format!(
+            "Ollama response did not include assistant content: {last_chunk}"
+        ));
+    }
+    let count = |field: &str| {
+        last_chunk
+            .get(field)
+            .and_then(Value::as_u64)
+            .map(|value| value as u32)
+    };
+    Ok(AdapterResponse {
+        text,
+        input_tokens: count("prompt_eval_count"),
+        output_tokens: count("eval_count"),
+        total_cost_usd: Some(0.0),
+        resolved_model: last_chunk
+            .get("model")
+            .and_then(Value::as_str)
+            .map(ToOwned::to_owned),
+    })
+}
+
+fn ollama_message_content(chunk: &Value) -> &str {
+    chunk
+        .get("message")
+        .and_then(|message| message.get("content"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+pub fn ollama_base_url() -> String {
+    std::env::var("AOP_OLLAMA_URL")
+        .ok()
+        .map(|value| value.trim().trim_end_matches('/').to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| OLLAMA_DEFAULT_URL.to_string())
+}
+
+/// Whether something accepts connections at the Ollama URL. Only a TCP connect, so
+/// provider resolution stays fast when no local server runs.
+fn ollama_reachable() -> bool {
+    let Ok(url) = reqwest::Url::parse(&ollama_base_url()) else {
+        return false;
+    };
+    let Ok(addresses) = url.socket_addrs(|| Some(11434)) else {
+        return false;
+    };
+    addresses.iter().any(|address| {
+        std::net::TcpStream::connect_timeout(
+            address,
+            std::time::Duration::from_millis(OLLAMA_PROBE_TIMEOUT_MS),
+        )
+        .is_ok()
+    })
+}
+
 fn read_optional_max_budget() -> Option<String> {
     let raw = std::env::var("AOP_CLAUDE_MAX_BUDGET_USD").ok()?;
     let value = raw.trim();
@@ -313,6 +1631,91 @@ fn parse_last_json_line<T: for<'de> Deserialize<'de>>(raw_output: &str) -> Resul
 mod tests {
     use super::*;
 
+    #[test]
+    fn openai_user_content_adds_image_parts() {
+        let mut request = AdapterRequest {
+            provider: "openai".to_string(),
+            model_id: "gpt-5".to_string(),
+            system_prompt: "system".to_string(),
+            user_prompt: "prompt".to_string(),
+            endpoint: ModelEndpoint::default(),
+            generation: GenerationParams::default(),
+            task_id: None,
+            fallbacks: Vec::new(),
+            response_schema: None,
+            bypass_cache: false,
+            attachments: Vec::new(),
+            pool: None,
+        };
+        assert_eq!(openai_user_content(&request), json!("prompt"));
+        request.attachments.push(ImageAttachment {
+            name: "mockup.png".to_string(),
+            media_type: "image/png".to_string(),
+            data_base64: "iVBORw0KGgo=".to_string(),
+        });
+        let content = openai_user_content(&request);
+        assert_eq!(content[0]["text"], "prompt");
+        assert_eq!(content[1]["type"], "image_url");
+        assert_eq!(
+            content[1]["image_url"]["url"],
+            "data:image/png;base64,iVBORw0KGgo="
+        );
+    }
+
+    #[test]
+    fn run_bounded_keeps_order_and_caps_concurrency() {
+        let active = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let items: Vec<u64> = (0..12).collect();
+        let results = run_bounded(&items, 3, |item| {
+            let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(5 + (item % 3) * 5));
+            active.fetch_sub(1, Ordering::SeqCst);
+            item * 2
+        });
+        let doubled: Vec<u64> = items.iter().map(|item| item * 2).collect();
+        assert_eq!(results, doubled);
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    fn batch_outcome_aggregates_failures() {
+        let response = |text: &str| AdapterResponse {
+            text: text.to_string(),
+            input_tokens: None,
+            output_tokens: None,
+            total_cost_usd: None,
+            resolved_model: None,
+        };
+        let outcome = BatchOutcome {
+            results: vec![
+                Ok(response("a")),
+                Err("rate limited".to_string()),
+                Ok(response("c")),
+            ],
+            latencies: vec![Duration::ZERO; 3],
+        };
+        assert_eq!(outcome.failed(), 1);
+        let error = outcome.into_all().expect_err("one request failed");
+        assert_eq!(
+            error,
+            "1 of 3 batched requests failed: request 2: rate limited"
+        );
+
+        let outcome = BatchOutcome {
+            results: vec![Ok(response("a")), Ok(response("b"))],
+            latencies: vec![Duration::ZERO; 2],
+        };
+        let texts: Vec<String> = outcome
+            .into_all()
+            .expect("all requests succeeded")
+            .into_iter()
+            .map(|response| response.text)
+            .collect();
+        assert_eq!(texts, ["a", "b"]);
+    }
+
     #[test]
     fn parse_last_json_line_reads_json_payload() {
         let raw = "log line\n{\"subtype\":\"success\",\"result\":\"OK\"}\n";
@@ -322,6 +1725,277 @@ mod tests {
         assert_eq!(parsed.result.as_deref(), Some("OK"));
     }
 
+    #[test]
+    fn claude_response_prefers_structured_output() {
+        let parsed: ClaudePrintResult = serde_json::from_value(json!({
+            "subtype": "success",
+            "result": "Here is the plan.",
+            "structured_output": { "tasks": [] }
+        }))
+        .expect("result should parse");
+        let response = claude_response(parsed).expect("structured output should be used");
+        assert_eq!(response.text, r#"{"tasks":[]}"#);
+    }
+
+    #[test]
+    fn claude_stream_text_delta_reads_partial_text_only() {
+        let delta = json!({
+            "type": "stream_event",
+            "event": {
+                "type": "content_block_delta",
+                "delta": { "type": "text_delta", "text": "Hel" }
+            }
+        });
+        assert_eq!(claude_stream_text_delta(&delta), Some("Hel"));
+        let start = json!({
+            "type": "stream_event",
+            "event": { "type": "message_start" }
+        });
+        assert_eq!(claude_stream_text_delta(&start), None);
+        let result = json!({ "type": "result", "subtype": "success", "result": "Hello" });
+        assert_eq!(claude_stream_text_delta(&result), None);
+    }
+
+    #[test]
+    fn ollama_chat_streams_ndjson_chunks() {
+        use std::io::Write;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("listener should bind");
+        let base_url = format!("http://{}", listener.local_addr().expect("local addr"));
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("client should connect");
+            let mut reader = BufReader::new(stream.try_clone().expect("stream clone"));
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).expect("header should read");
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = header.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().expect("content length");
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).expect("body should read");
+            let chunks = [
+                r#"{"model":"qwen2.5-coder","message":{"role":"assistant","content":"Hel"},"done":false}"#,
+                r#"{"model":"qwen2.5-coder","message":{"role":"assistant","content":"lo"},"done":false}"#,
+                r#"{"model":"qwen2.5-coder","message":{"role":"assistant","content":""},"done":true,"prompt_eval_count":12,"eval_count":2}"#,
+            ]
+            .join("\n");
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                chunks.len(),
+                chunks
+            )
+            .expect("response should write");
+            String::from_utf8(body).expect("request body should be UTF-8")
+        });
+
+        let request = AdapterRequest {
+            provider: "ollama".to_string(),
+            model_id: "qwen2.5-coder".to_string(),
+            system_prompt: "system".to_string(),
+            user_prompt: "user".to_string(),
+            endpoint: ModelEndpoint::default(),
+            task_id: None,
+            fallbacks: Vec::new(),
+            response_schema: None,
+            bypass_cache: false,
+            attachments: Vec::new(),
+            pool: None,
+            generation: GenerationParams {
+                max_output_tokens: Some(256),
+                stop: vec!["<END>".to_string()],
+                ..GenerationParams::default()
+            },
+        };
+        let mut deltas = Vec::new();
+        let mut on_delta = |delta: &str| {
+            deltas.push(delta.to_string());
+            true
+        };
+        let response = call_ollama_chat(&base_url, &request, Some(&mut on_delta))
+            .expect("ollama chat should succeed");
+        let request_body = server.join().expect("server thread should finish");
+
+        assert!(request_body.contains(r#""stream":true"#));
+        assert!(request_body.contains(r#""num_predict":256"#));
+        assert!(request_body.contains(r#""stop":["<END>"]"#));
+        assert_eq!(deltas, vec!["Hel", "lo"]);
+        assert_eq!(response.text, "Hello");
+        assert_eq!(
+            (response.input_tokens, response.output_tokens),
+            (Some(12), Some(2))
+        );
+    }
+
+    #[test]
+    fn openai_target_points_at_azure_deployment_or_custom_base_url() {
+        let mut request = AdapterRequest {
+            provider: "azure_openai".to_string(),
+            model_id: "gpt-4o".to_string(),
+            system_prompt: "system".to_string(),
+            user_prompt: "user".to_string(),
+            endpoint: ModelEndpoint {
+                base_url: Some("https://corp.openai.azure.com".to_string()),
+                api_version: Some("2024-06-01".to_string()),
+                deployment: Some("gpt4o-prod".to_string()),
+                api_key_env: Some("AOP_TEST_AZURE_OPENAI_KEY".to_string()),
+                credential: None,
+                timeout_secs: None,
+            },
+            task_id: None,
+            fallbacks: Vec::new(),
+            response_schema: None,
+            bypass_cache: false,
+            attachments: Vec::new(),
+            pool: None,
+            generation: GenerationParams::default(),
+        };
+        std::env::set_var("AOP_TEST_AZURE_OPENAI_KEY", "secret");
+        let azure = openai_target(&request).expect("azure target should resolve");
+        assert_eq!(
+            azure.url,
+            "https://corp.openai.azure.com/openai/deployments/gpt4o-prod/chat/completions?api-version=2024-06-01"
+        );
+        assert_eq!(azure.auth.map(|(header, _)| header), Some("api-key"));
+
+        request.provider = "vllm".to_string();
+        request.endpoint = ModelEndpoint {
+            base_url: Some("http://localhost:8000/v1".to_string()),
+            ..ModelEndpoint::default()
+        };
+        let local = openai_target(&request).expect("compatible target should resolve");
+        assert_eq!(local.url, "http://localhost:8000/v1/chat/completions");
+        assert!(local.auth.is_none());
+
+        request.endpoint = ModelEndpoint::default();
+        assert!(openai_target(&request).is_err());
+    }
+
+    #[test]
+    fn transient_errors_retry_then_fail_over_to_next_model() {
+        let fallback = ModelSelection {
+            tier: 3,
+            persona: None,
+            provider: "ollama".to_string(),
+            model_id: "qwen2.5-coder".to_string(),
+            source: "scored".to_string(),
+            endpoint: ModelEndpoint::default(),
+            generation: GenerationParams::default(),
+        };
+        let request = AdapterRequest {
+            provider: "openai".to_string(),
+            model_id: "gpt-5-mini".to_string(),
+            system_prompt: "system".to_string(),
+            user_prompt: "user".to_string(),
+            endpoint: ModelEndpoint::default(),
+            task_id: Some("task-1".to_string()),
+            fallbacks: vec![fallback],
+            response_schema: None,
+            bypass_cache: false,
+            attachments: Vec::new(),
+            pool: None,
+            generation: GenerationParams::default(),
+        };
+        let policy = RetryPolicy {
+            max_retries: 2,
+            base_delay_ms: 1,
+        };
+        let mut calls = Vec::new();
+        let response = run_with_failover(&request, policy, &|| true, &mut |attempt| {
+            calls.push(attempt.provider.clone());
+            if attempt.provider == "openai" {
+                return Err(
+                    "OpenAI adapter failed with status 503 Service Unavailable".to_string(),
+                );
+            }
+            Ok(AdapterResponse {
+                text: "done".to_string(),
+                input_tokens: None,
+                output_tokens: None,
+                total_cost_usd: None,
+                resolved_model: Some(attempt.model_id.clone()),
+            })
+        })
+        .expect("fallback model should answer");
+
+        assert_eq!(calls, vec!["openai", "openai", "openai", "ollama"]);
+        assert_eq!(response.resolved_model.as_deref(), Some("qwen2.5-coder"));
+        // Counted locally because the provider reported no usage.
+        assert_eq!(response.input_tokens, Some(2));
+        assert_eq!(response.output_tokens, Some(1));
+
+        let mut calls = 0;
+        let error = run_with_failover(&request, policy, &|| false, &mut |_| {
+            calls += 1;
+            Err("OpenAI adapter failed with status 429 Too Many Requests".to_string())
+        })
+        .expect_err("streamed output is never retried");
+        assert_eq!(calls, 1);
+        assert!(error.contains("429"));
+    }
+
+    #[test]
+    fn is_transient_error_ignores_configuration_failures() {
+        assert!(is_transient_error(
+            "OpenAI adapter failed with status 429 Too Many Requests: {}"
+        ));
+        assert!(is_transient_error(
+            "Claude Code returned subtype 'error' without usable result. overloaded_error"
+        ));
+        assert!(!is_transient_error(
+            "OPENAI_API_KEY is required for openai provider"
+        ));
+        assert!(!is_transient_error(
+            "OpenAI adapter failed with status 400 Bad Request: {}"
+        ));
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay_ms: 400,
+        };
+        assert!(policy.delay_for(3) >= Duration::from_millis(800));
+        assert!(policy.delay_for(3) <= Duration::from_millis(1_600));
+    }
+
+    #[test]
+    fn timeouts_fail_over_without_retry_and_long_replies_are_cut_off() {
+        let request = AdapterRequest {
+            provider: "ollama".to_string(),
+            model_id: "qwen2.5-coder".to_string(),
+            system_prompt: "system".to_string(),
+            user_prompt: "user".to_string(),
+            endpoint: ModelEndpoint {
+                timeout_secs: Some(45),
+                ..ModelEndpoint::default()
+            },
+            task_id: None,
+            fallbacks: Vec::new(),
+            response_schema: None,
+            bypass_cache: false,
+            attachments: Vec::new(),
+            pool: None,
+            generation: GenerationParams::default(),
+        };
+        assert_eq!(request_timeout(&request), Duration::from_secs(45));
+        let error = timeout_error(&request);
+        assert!(error.contains("after 45s waiting on ollama/qwen2.5-coder"));
+        assert!(is_timeout_error(&error));
+        assert!(!is_transient_error(&error));
+        assert!(is_transient_error("connection timed out"));
+
+        let mut guard = ResponseGuard { limit: 8, seen: 0 };
+        guard.add("héllo").expect("five characters fit");
+        let error = guard
+            .add("world")
+            .expect_err("ten characters exceed the limit");
+        assert!(error.contains("exceeded 8 characters"));
+    }
+
     #[test]
     fn generate_rejects_unknown_provider() {
         let request = AdapterRequest {
@@ -329,6 +2003,14 @@ mod tests {
             model_id: "gpt-5-mini".to_string(),
             system_prompt: "system".to_string(),
             user_prompt: "user".to_string(),
+            endpoint: ModelEndpoint::default(),
+            task_id: None,
+            fallbacks: Vec::new(),
+            response_schema: None,
+            bypass_cache: false,
+            attachments: Vec::new(),
+            pool: None,
+            generation: GenerationParams::default(),
         };
 
         let error = generate(&request).expect_err("unknown provider should fail");
@@ -0,0 +1,223 @@
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+
+use crate::llm_adapter::{AdapterRequest, AdapterResponse};
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct CachedResponse {
+    response_text: String,
+    input_tokens: Option<i64>,
+    output_tokens: Option<i64>,
+    resolved_model: Option<String>,
+}
+
+/// Content address of a request: provider, model, endpoint, prompts, sampling settings
+/// and output schema.
+pub fn cache_key(request: &AdapterRequest) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        request.provider.trim().to_ascii_lowercase().as_str(),
+        request.model_id.trim(),
+        request.endpoint.base_url.as_deref().unwrap_or_default(),
+        request.endpoint.deployment.as_deref().unwrap_or_default(),
+        request.system_prompt.trim(),
+        request.user_prompt.trim(),
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update(serde_json::to_string(&request.generation).unwrap_or_default());
+    if let Some(schema) = &request.response_schema {
+        hasher.update(schema.schema.to_string().as_bytes());
+    }
+    for attachment in &request.attachments {
+        hasher.update(attachment.media_type.as_bytes());
+        hasher.update(attachment.data_base64.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Whether `request` may be answered from the cache: caching is on and the request does
+/// not bypass it.
+pub fn applies(request: &AdapterRequest) -> bool {
+    !request.bypass_cache && cache_enabled()
+}
+
+/// Cached reply for `request`; callers check [`applies`] first.
+pub async fn lookup(
+    pool: &SqlitePool,
+    request: &AdapterRequest,
+) -> Result<Option<AdapterResponse>, String> {
+    get(pool, &cache_key(request)).await
+}
+
+pub async fn store(
+    pool: &SqlitePool,
+    request: &AdapterRequest,
+    response: &AdapterResponse,
+) -> Result<(), String> {
+    put(pool, &cache_key(request), request, response, ttl_hours()).await
+}
+
+pub async fn get(pool: &SqlitePool, cache_key: &str) -> Result<Option<AdapterResponse>, String> {
+    let cached = sqlx::query_as::<_, CachedResponse>(
+        "SELECT response_text, input_tokens, output_tokens, resolved_model
+         FROM aop_llm_cache WHERE cache_key = ? AND expires_at > ?",
+    )
+    .bind(cache_key)
+    .bind(Utc::now().timestamp())
+    .fetch_optional(pool)
+    .await
+    .map_err(|error| format!("Failed to read LLM cache: {error}"))?;
+    let Some(cached) = cached else {
+        return Ok(None);
+    };
+
+    sqlx::query("UPDATE aop_llm_cache SET hit_count = hit_count + 1 WHERE cache_key = ?")
+        .bind(cache_key)
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to update LLM cache hit count: {error}"))?;
+    Ok(Some(AdapterResponse {
+        text: cached.response_text,
+        input_tokens: cached.input_tokens.map(|value| value as u32),
+        output_tokens: cached.output_tokens.map(|value| value as u32),
+        // Nothing was spent on a cache hit.
+        total_cost_usd: Some(0.0),
+        resolved_model: cached.resolved_model,
+    }))
+}
+
+/// Saves `response` for `ttl_hours` and drops entries that have expired.
+pub async fn put(
+    pool: &SqlitePool,
+    cache_key: &str,
+    request: &AdapterRequest,
+    response: &AdapterResponse,
+    ttl_hours: u32,
+) -> Result<(), String> {
+    let now = Utc::now().timestamp();
+    sqlx::query("DELETE FROM aop_llm_cache WHERE expires_at <= ?")
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(|error| format!("Failed to expire LLM cache entries: {error}"))?;
+    sqlx::query(
+        "INSERT INTO aop_llm_cache (
+            cache_key, provider, model_id, response_text, input_tokens, output_tokens,
+            resolved_model, hit_count, created_at, expires_at
+         ) VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?, ?)
+         ON CONFLICT(cache_key) DO UPDATE SET
+            response_text = excluded.response_text,
+            input_tokens = excluded.input_tokens,
+            output_tokens = excluded.output_tokens,
+            resolved_model = excluded.resolved_model,
+            hit_count = 0,
+            created_at = excluded.created_at,
+            expires_at = excluded.expires_at",
+    )
+    .bind(cache_key)
+    .bind(request.provider.trim())
+    .bind(request.model_id.trim())
+    .bind(&response.text)
+    .bind(response.input_tokens.map(i64::from))
+    .bind(response.output_tokens.map(i64::from))
+    .bind(&response.resolved_model)
+    .bind(now)
+    .bind(now + i64::from(ttl_hours) * 3_600)
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to write LLM cache: {error}"))?;
+    Ok(())
+}
+
+/// Removes every cached reply and returns how many there were.
+pub async fn clear(pool: &SqlitePool) -> Result<u64, String> {
+    sqlx::query("DELETE FROM aop_llm_cache")
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(|error| format!("Failed to clear LLM cache: {error}"))
+}
+
+fn cache_enabled() -> bool {
+    std::env::var("AOP_LLM_CACHE")
+        .ok()
+        .map(|value| {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false)
+}
+
+fn ttl_hours() -> u32 {
+    std::env::var("AOP_LLM_CACHE_TTL_HOURS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .unwrap_or(24)
+        .clamp(1, 720)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db;
+
+    fn request(user_prompt: &str) -> AdapterRequest {
+        AdapterRequest {
+            provider: "openai".to_string(),
+            model_id: "gpt-5-mini".to_string(),
+            system_prompt: "system".to_string(),
+            user_prompt: user_prompt.to_string(),
+            endpoint: Default::default(),
+            generation: Default::default(),
+            task_id: None,
+            fallbacks: Vec::new(),
+            response_schema: None,
+            bypass_cache: false,
+            attachments: Vec::new(),
+            pool: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn stores_and_returns_replies_by_prompt_hash() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+
+        let first = request("analyze the objective");
+        let key = cache_key(&first);
+        assert_eq!(key, cache_key(&request("  analyze the objective ")));
+        assert_ne!(key, cache_key(&request("analyze another objective")));
+
+        let response = AdapterResponse {
+            text: "{\"questions\":[]}".to_string(),
+            input_tokens: Some(120),
+            output_tokens: Some(8),
+            total_cost_usd: Some(0.01),
+            resolved_model: Some("gpt-5-mini".to_string()),
+        };
+        assert!(get(&pool, &key).await.expect("lookup").is_none());
+        put(&pool, &key, &first, &response, 1).await.expect("store");
+
+        let cached = get(&pool, &key)
+            .await
+            .expect("lookup")
+            .expect("reply should be cached");
+        assert_eq!(cached.text, response.text);
+        assert_eq!(cached.input_tokens, Some(120));
+        assert_eq!(cached.total_cost_usd, Some(0.0));
+        assert_eq!(clear(&pool).await.expect("clear"), 1);
+    }
+}
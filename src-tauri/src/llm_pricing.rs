@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock};
+
+use crate::model_registry::{pricing_key, ModelPricing};
+
+static PRICES: RwLock<Option<HashMap<String, ModelPricing>>> = RwLock::new(None);
+
+/// Installs the per-model prices from the model registry.
+pub fn configure(pricing: HashMap<String, ModelPricing>) {
+    *PRICES.write().unwrap_or_else(PoisonError::into_inner) = Some(pricing);
+}
+
+/// Cost of a call in USD, or `None` when the model has no configured price.
+pub fn cost_usd(provider: &str, model_id: &str, input_tokens: u32, output_tokens: u32) -> Option<f64> {
+    PRICES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()?
+        .get(&pricing_key(provider, model_id))
+        .map(|price| price.cost_usd(input_tokens, output_tokens))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prices_calls_per_million_tokens() {
+        configure(HashMap::from([(
+            pricing_key("openai", "gpt-5-mini"),
+            ModelPricing {
+                input_per_mtok: 0.25,
+                output_per_mtok: 2.0,
+            },
+        )]));
+        let cost = cost_usd(" OpenAI", "GPT-5-mini", 200_000, 10_000).expect("model is priced");
+        assert!((cost - 0.07).abs() < 1e-9);
+        assert_eq!(cost_usd("openai", "gpt-5", 1, 1), None);
+    }
+}
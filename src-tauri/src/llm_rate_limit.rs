@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model_registry::ProviderLimits;
+
+/// Longest a queued request sleeps before re-checking its gate; keeps waiters responsive
+/// to limit changes.
+const MAX_WAIT_SLICE: Duration = Duration::from_secs(1);
+
+static GATES: Mutex<BTreeMap<String, ProviderGate>> = Mutex::new(BTreeMap::new());
+static RELEASED: Condvar = Condvar::new();
+
+/// Queue state of one provider, shown in mission control.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderQueueMetrics {
+    pub provider: String,
+    pub requests_per_minute: Option<u32>,
+    pub max_concurrent: Option<u32>,
+    pub in_flight: u32,
+    pub queued: u32,
+    pub total_requests: u64,
+    /// Requests that had to wait for a slot or a token.
+    pub throttled_requests: u64,
+    pub avg_wait_ms: f64,
+    pub max_wait_ms: u64,
+}
+
+#[derive(Debug)]
+struct ProviderGate {
+    limits: ProviderLimits,
+    tokens: f64,
+    refilled_at: Instant,
+    in_flight: u32,
+    queued: u32,
+    total_requests: u64,
+    throttled_requests: u64,
+    total_wait_ms: u64,
+    max_wait_ms: u64,
+}
+
+impl ProviderGate {
+    fn new(limits: ProviderLimits) -> Self {
+        let tokens = f64::from(bucket_capacity(&limits));
+        Self {
+            limits,
+            tokens,
+            refilled_at: Instant::now(),
+            in_flight: 0,
+            queued: 0,
+            total_requests: 0,
+            throttled_requests: 0,
+            total_wait_ms: 0,
+            max_wait_ms: 0,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        if let Some(rpm) = self.limits.requests_per_minute {
+            let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * f64::from(rpm) / 60.0)
+                .min(f64::from(bucket_capacity(&self.limits)));
+        }
+        self.refilled_at = now;
+    }
+
+    /// How long until a request may start, or `None` if it may start now.
+    fn wait_needed(&self) -> Option<Duration> {
+        if let Some(max) = self.limits.max_concurrent {
+            if self.in_flight >= max {
+                return Some(MAX_WAIT_SLICE);
+            }
+        }
+        let rpm = self.limits.requests_per_minute?;
+        if self.tokens >= 1.0 {
+            return None;
+        }
+        let seconds = (1.0 - self.tokens) * 60.0 / f64::from(rpm);
+        Some(Duration::from_secs_f64(seconds).min(MAX_WAIT_SLICE))
+    }
+}
+
+fn bucket_capacity(limits: &ProviderLimits) -> u32 {
+    limits.burst.unwrap_or(1).max(1)
+}
+
+/// Held for the duration of one model call; frees the provider's concurrency slot when
+/// dropped.
+#[derive(Debug)]
+pub struct ProviderPermit {
+    provider: String,
+}
+
+impl Drop for ProviderPermit {
+    fn drop(&mut self) {
+        if let Some(gate) = gates().get_mut(&self.provider) {
+            gate.in_flight = gate.in_flight.saturating_sub(1);
+        }
+        RELEASED.notify_all();
+    }
+}
+
+/// Installs the per-provider limits from the model registry. Providers without an entry
+/// are not throttled but still show up in [`metrics`] once used.
+pub fn configure(limits: HashMap<String, ProviderLimits>) {
+    for gate in gates().values_mut() {
+        gate.limits = ProviderLimits::default();
+    }
+    for (provider, limits) in limits {
+        set_limits(&provider, limits);
+    }
+}
+
+fn set_limits(provider: &str, limits: ProviderLimits) {
+    let key = normalize_provider(provider);
+    let mut gates = gates();
+    match gates.get_mut(&key) {
+        Some(gate) => gate.limits = limits,
+        None => {
+            gates.insert(key, ProviderGate::new(limits));
+        }
+    }
+    RELEASED.notify_all();
+}
+
+/// Blocks until `provider` has both a free concurrency slot and a rate token.
+pub fn acquire(provider: &str) -> ProviderPermit {
+    let key = normalize_provider(provider);
+    let started = Instant::now();
+    let mut gates = gates();
+    gates
+        .entry(key.clone())
+        .or_insert_with(|| ProviderGate::new(ProviderLimits::default()))
+        .queued += 1;
+
+    let mut throttled = false;
+    loop {
+        let gate = gates
+            .get_mut(&key)
+            .expect("provider gate is never removed");
+        gate.refill(Instant::now());
+        match gate.wait_needed() {
+            None => {
+                if gate.limits.requests_per_minute.is_some() {
+                    gate.tokens -= 1.0;
+                }
+                let waited_ms = started.elapsed().as_millis() as u64;
+                gate.queued = gate.queued.saturating_sub(1);
+                gate.in_flight += 1;
+                gate.total_requests += 1;
+                if throttled {
+                    gate.throttled_requests += 1;
+                }
+                gate.total_wait_ms += waited_ms;
+                gate.max_wait_ms = gate.max_wait_ms.max(waited_ms);
+                return ProviderPermit { provider: key };
+            }
+            Some(wait) => {
+                throttled = true;
+                gates = RELEASED
+                    .wait_timeout(gates, wait)
+                    .map(|(guard, _)| guard)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner().0);
+            }
+        }
+    }
+}
+
+pub fn metrics() -> Vec<ProviderQueueMetrics> {
+    gates()
+        .iter()
+        .map(|(provider, gate)| ProviderQueueMetrics {
+            provider: provider.clone(),
+            requests_per_minute: gate.limits.requests_per_minute,
+            max_concurrent: gate.limits.max_concurrent,
+            in_flight: gate.in_flight,
+            queued: gate.queued,
+            total_requests: gate.total_requests,
+            throttled_requests: gate.throttled_requests,
+            avg_wait_ms: if gate.total_requests == 0 {
+                0.0
+            } else {
+                gate.total_wait_ms as f64 / gate.total_requests as f64
+            },
+            max_wait_ms: gate.max_wait_ms,
+        })
+        .collect()
+}
+
+fn normalize_provider(provider: &str) -> String {
+    provider.trim().to_ascii_lowercase().replace('-', "_")
+}
+
+fn gates() -> MutexGuard<'static, BTreeMap<String, ProviderGate>> {
+    GATES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics_for(provider: &str) -> ProviderQueueMetrics {
+        metrics()
+            .into_iter()
+            .find(|entry| entry.provider == provider)
+            .expect("provider should be tracked")
+    }
+
+    #[test]
+    fn concurrency_cap_queues_requests_until_a_permit_is_released() {
+        set_limits(
+            "test-concurrency",
+            ProviderLimits {
+                max_concurrent: Some(1),
+                ..ProviderLimits::default()
+            },
+        );
+        let first = acquire("test_concurrency");
+        let waiter = std::thread::spawn(|| {
+            let _permit = acquire("test_concurrency");
+        });
+        while metrics_for("test_concurrency").queued == 0 {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(metrics_for("test_concurrency").in_flight, 1);
+
+        drop(first);
+        waiter.join().expect("queued request should finish");
+        let metrics = metrics_for("test_concurrency");
+        assert_eq!((metrics.in_flight, metrics.queued), (0, 0));
+        assert_eq!(metrics.total_requests, 2);
+        assert_eq!(metrics.throttled_requests, 1);
+    }
+
+    #[test]
+    fn token_bucket_paces_requests_beyond_the_burst() {
+        set_limits(
+            "test_rate",
+            ProviderLimits {
+                requests_per_minute: Some(1_200),
+                burst: Some(1),
+                max_concurrent: None,
+            },
+        );
+        let started = Instant::now();
+        drop(acquire("test_rate"));
+        drop(acquire("test_rate"));
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        assert_eq!(metrics_for("test_rate").throttled_requests, 1);
+    }
+}
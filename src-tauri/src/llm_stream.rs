@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::llm_adapter::{self, AdapterRequest, AdapterResponse};
+
+/// Event carrying an [`LlmStreamEvent`] for every chunk and for the end of a stream.
+pub const LLM_STREAM_EVENT: &str = "llm-stream";
+
+pub type StreamListener = Arc<dyn Fn(&LlmStreamEvent) + Send + Sync>;
+
+static LISTENER: OnceLock<StreamListener> = OnceLock::new();
+/// Generations in flight, keyed by stream id.
+static ACTIVE: Mutex<BTreeMap<String, ActiveStream>> = Mutex::new(BTreeMap::new());
+
+struct ActiveStream {
+    task_id: Option<String>,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Partial output of one model call. `state` is `streaming` while chunks arrive, then
+/// `completed`, `failed` or `cancelled` on the last event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmStreamEvent {
+    pub stream_id: String,
+    pub task_id: Option<String>,
+    /// What the output is for, e.g. `analysis`, `plan` or `specialist`.
+    pub label: String,
+    pub delta: String,
+    pub received_chars: usize,
+    pub state: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelLlmStreamInput {
+    pub stream_id: String,
+}
+
+/// Sends stream events to `listener`. Without one, generations still stream but nobody
+/// sees the partial output.
+pub fn set_listener(listener: StreamListener) {
+    let _ = LISTENER.set(listener);
+}
+
+/// Generates with [`llm_adapter::generate_streaming`], publishing each chunk under a new
+/// stream id so the UI can follow along and cancel it with [`cancel`].
+pub fn generate(
+    request: &AdapterRequest,
+    task_id: Option<&str>,
+    label: &str,
+) -> Result<AdapterResponse, String> {
+    let stream_id = Uuid::new_v4().to_string();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    active_streams().insert(
+        stream_id.clone(),
+        ActiveStream {
+            task_id: task_id.map(str::to_string),
+            cancelled: cancelled.clone(),
+        },
+    );
+
+    let mut event = LlmStreamEvent {
+        stream_id: stream_id.clone(),
+        task_id: task_id.map(str::to_string),
+        label: label.to_string(),
+        delta: String::new(),
+        received_chars: 0,
+        state: "streaming".to_string(),
+        error: None,
+    };
+    let result = llm_adapter::generate_streaming(request, &mut |delta| {
+        if cancelled.load(Ordering::Relaxed) {
+            return false;
+        }
+        event.delta = delta.to_string();
+        event.received_chars += delta.chars().count();
+        publish(&event);
+        true
+    });
+    active_streams().remove(&stream_id);
+
+    event.delta.clear();
+    match &result {
+        Ok(_) => event.state = "completed".to_string(),
+        Err(_) if cancelled.load(Ordering::Relaxed) => event.state = "cancelled".to_string(),
+        Err(error) => {
+            event.state = "failed".to_string();
+            event.error = Some(error.clone());
+        }
+    }
+    publish(&event);
+    result
+}
+
+/// Stops a generation in flight; it fails with [`llm_adapter::GENERATION_CANCELLED`] at
+/// its next chunk.
+pub fn cancel(stream_id: &str) -> Result<(), String> {
+    let streams = active_streams();
+    let stream = streams
+        .get(stream_id.trim())
+        .ok_or_else(|| format!("LLM stream '{}' is not running", stream_id.trim()))?;
+    stream.cancelled.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Cancels every generation in flight for one of `task_ids`; returns how many were.
+pub fn cancel_for_tasks(task_ids: &[String]) -> usize {
+    let streams = active_streams();
+    let mut cancelled = 0;
+    for stream in streams.values() {
+        if stream
+            .task_id
+            .as_ref()
+            .is_some_and(|task_id| task_ids.contains(task_id))
+        {
+            stream.cancelled.store(true, Ordering::Relaxed);
+            cancelled += 1;
+        }
+    }
+    cancelled
+}
+
+fn publish(event: &LlmStreamEvent) {
+    if let Some(listener) = LISTENER.get() {
+        listener(event);
+    }
+}
+
+fn active_streams() -> std::sync::MutexGuard<'static, BTreeMap<String, ActiveStream>> {
+    ACTIVE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_generation_is_not_left_running() {
+        let request = AdapterRequest {
+            provider: "unsupported_provider".to_string(),
+            model_id: "model".to_string(),
+            system_prompt: "system".to_string(),
+            user_prompt: "user".to_string(),
+            endpoint: Default::default(),
+            generation: Default::default(),
+            task_id: None,
+            fallbacks: Vec::new(),
+            response_schema: None,
+            bypass_cache: false,
+            attachments: Vec::new(),
+            pool: None,
+        };
+        let error = generate(&request, Some("task-1"), "plan").expect_err("unknown provider");
+        assert!(error.contains("No remote adapter configured"));
+        assert!(active_streams().is_empty());
+        assert!(cancel("missing").is_err());
+    }
+}
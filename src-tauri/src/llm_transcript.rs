@@ -0,0 +1,385 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+
+use crate::compliance;
+use crate::llm_adapter::{AdapterRequest, AdapterResponse};
+
+const TRUNCATION_MARKER: &str = "\n… [transcript truncated]";
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmTranscriptRecord {
+    pub id: String,
+    pub root_task_id: Option<String>,
+    pub task_id: Option<String>,
+    pub provider: String,
+    pub model_id: String,
+    pub resolved_model: Option<String>,
+    pub system_prompt: String,
+    pub user_prompt: String,
+    pub response_schema_json: Option<String>,
+    pub response_text: Option<String>,
+    pub error: Option<String>,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub latency_ms: i64,
+    pub truncated: bool,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListLlmTranscriptsInput {
+    pub root_task_id: Option<String>,
+    pub task_id: Option<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportLlmTranscriptsInput {
+    pub root_task_id: Option<String>,
+    pub task_id: Option<String>,
+    /// JSONL file to write; parent directories are created.
+    pub output_path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportLlmTranscriptsResult {
+    pub output_path: String,
+    pub transcripts_exported: usize,
+}
+
+/// Saves one model call to the request's database in the background when transcript
+/// logging is enabled. Secrets are redacted and each text field is cut to the configured
+/// size cap.
+pub fn record(
+    request: &AdapterRequest,
+    result: &Result<AdapterResponse, String>,
+    latency: Duration,
+) {
+    if !transcripts_enabled() {
+        return;
+    }
+    let Some(pool) = request.pool.clone() else {
+        return;
+    };
+
+    let max_bytes = max_field_bytes();
+    let mut truncated = false;
+    let mut capture = |text: &str| {
+        let (text, cut) = cap_text(&compliance::redact_secrets(text), max_bytes);
+        truncated |= cut;
+        text
+    };
+    let system_prompt = capture(&request.system_prompt);
+    let user_prompt = capture(&request.user_prompt);
+    let (response_text, error) = match result {
+        Ok(response) => (Some(capture(&response.text)), None),
+        Err(error) => (None, Some(capture(error))),
+    };
+    let response = result.as_ref().ok();
+    let entry = LlmTranscriptRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        root_task_id: None,
+        task_id: request.task_id.clone(),
+        provider: request.provider.trim().to_string(),
+        model_id: request.model_id.trim().to_string(),
+        resolved_model: response.and_then(|response| response.resolved_model.clone()),
+        system_prompt,
+        user_prompt,
+        response_schema_json: request
+            .response_schema
+            .as_ref()
+            .map(|schema| schema.schema.to_string()),
+        response_text,
+        error,
+        input_tokens: response.and_then(|response| response.input_tokens.map(i64::from)),
+        output_tokens: response.and_then(|response| response.output_tokens.map(i64::from)),
+        latency_ms: latency.as_millis() as i64,
+        truncated,
+        created_at: Utc::now().timestamp(),
+    };
+    tauri::async_runtime::spawn(async move {
+        let _ = insert_transcript(&pool, &entry).await;
+    });
+}
+
+/// Stores `entry`, filling `root_task_id` by walking the task's parents.
+pub async fn insert_transcript(
+    pool: &SqlitePool,
+    entry: &LlmTranscriptRecord,
+) -> Result<(), String> {
+    sqlx::query(
+        r#"
+        INSERT INTO aop_llm_transcripts (
+            id, root_task_id, task_id, provider, model_id, resolved_model, system_prompt,
+            user_prompt, response_schema_json, response_text, error, input_tokens,
+            output_tokens, latency_ms, truncated, created_at
+        )
+        VALUES (
+            ?,
+            COALESCE(?, (
+                WITH RECURSIVE chain(id, parent_id) AS (
+                    SELECT id, parent_id FROM aop_tasks WHERE id = ?
+                    UNION ALL
+                    SELECT t.id, t.parent_id FROM aop_tasks t JOIN chain c ON t.id = c.parent_id
+                )
+                SELECT id FROM chain WHERE parent_id IS NULL
+            )),
+            ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?
+        )
+        "#,
+    )
+    .bind(&entry.id)
+    .bind(&entry.root_task_id)
+    .bind(&entry.task_id)
+    .bind(&entry.task_id)
+    .bind(&entry.provider)
+    .bind(&entry.model_id)
+    .bind(&entry.resolved_model)
+    .bind(&entry.system_prompt)
+    .bind(&entry.user_prompt)
+    .bind(&entry.response_schema_json)
+    .bind(&entry.response_text)
+    .bind(&entry.error)
+    .bind(entry.input_tokens)
+    .bind(entry.output_tokens)
+    .bind(entry.latency_ms)
+    .bind(entry.truncated)
+    .bind(entry.created_at)
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to record LLM transcript: {error}"))?;
+    Ok(())
+}
+
+pub async fn list_llm_transcripts(
+    pool: &SqlitePool,
+    input: ListLlmTranscriptsInput,
+) -> Result<Vec<LlmTranscriptRecord>, String> {
+    let limit = i64::from(input.limit.unwrap_or(100).clamp(1, 1_000));
+    query_transcripts(
+        pool,
+        input.root_task_id.as_deref(),
+        input.task_id.as_deref(),
+        Some(limit),
+    )
+    .await
+}
+
+/// Writes the matching transcripts, oldest first, as one JSON object per line.
+pub async fn export_llm_transcripts(
+    pool: &SqlitePool,
+    input: ExportLlmTranscriptsInput,
+) -> Result<ExportLlmTranscriptsResult, String> {
+    let output_path = input.output_path.trim();
+    if output_path.is_empty() {
+        return Err("outputPath is required".to_string());
+    }
+    let mut transcripts = query_transcripts(
+        pool,
+        input.root_task_id.as_deref(),
+        input.task_id.as_deref(),
+        None,
+    )
+    .await?;
+    transcripts.reverse();
+
+    let path = Path::new(output_path);
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|error| format!("Failed to create transcript export dir: {error}"))?;
+    }
+    let file = File::create(path)
+        .map_err(|error| format!("Failed to create transcript export file: {error}"))?;
+    let mut writer = BufWriter::new(file);
+    for transcript in &transcripts {
+        let line = serde_json::to_string(transcript)
+            .map_err(|error| format!("Failed to serialize transcript: {error}"))?;
+        writer
+            .write_all(line.as_bytes())
+            .and_then(|_| writer.write_all(b"\n"))
+            .map_err(|error| format!("Failed to write transcript export: {error}"))?;
+    }
+    writer
+        .flush()
+        .map_err(|error| format!("Failed to flush transcript export: {error}"))?;
+
+    Ok(ExportLlmTranscriptsResult {
+        output_path: path.to_string_lossy().to_string(),
+        transcripts_exported: transcripts.len(),
+    })
+}
+
+async fn query_transcripts(
+    pool: &SqlitePool,
+    root_task_id: Option<&str>,
+    task_id: Option<&str>,
+    limit: Option<i64>,
+) -> Result<Vec<LlmTranscriptRecord>, String> {
+    let mut query_builder: QueryBuilder<'_, Sqlite> = QueryBuilder::new(
+        "SELECT id, root_task_id, task_id, provider, model_id, resolved_model, system_prompt, user_prompt, response_schema_json, response_text, error, input_tokens, output_tokens, latency_ms, truncated, created_at FROM aop_llm_transcripts WHERE 1=1",
+    );
+    if let Some(root_task_id) = root_task_id.map(str::trim).filter(|value| !value.is_empty()) {
+        query_builder.push(" AND root_task_id = ").push_bind(root_task_id);
+    }
+    if let Some(task_id) = task_id.map(str::trim).filter(|value| !value.is_empty()) {
+        query_builder.push(" AND task_id = ").push_bind(task_id);
+    }
+    query_builder.push(" ORDER BY created_at DESC, rowid DESC");
+    if let Some(limit) = limit {
+        query_builder.push(" LIMIT ").push_bind(limit);
+    }
+
+    query_builder
+        .build_query_as::<LlmTranscriptRecord>()
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to list LLM transcripts: {error}"))
+}
+
+fn transcripts_enabled() -> bool {
+    std::env::var("AOP_LLM_TRANSCRIPTS")
+        .ok()
+        .map(|value| {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false)
+}
+
+fn max_field_bytes() -> usize {
+    let kb = std::env::var("AOP_LLM_TRANSCRIPT_MAX_KB")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(256)
+        .clamp(4, 16_384);
+    kb * 1_024
+}
+
+/// Cuts `text` to at most `max_bytes` on a char boundary; the flag reports a cut.
+fn cap_text(text: &str, max_bytes: usize) -> (String, bool) {
+    if text.len() <= max_bytes {
+        return (text.to_string(), false);
+    }
+    let mut end = max_bytes.saturating_sub(TRUNCATION_MARKER.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    (format!("{}{TRUNCATION_MARKER}", &text[..end]), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db;
+    use crate::db::tasks::{self, CreateTaskInput};
+
+    #[test]
+    fn caps_text_on_char_boundaries() {
+        assert_eq!(cap_text("short", 64), ("short".to_string(), false));
+        let (capped, cut) = cap_text(&"é".repeat(100), 64);
+        assert!(cut);
+        assert!(capped.len() <= 64);
+        assert!(capped.ends_with(TRUNCATION_MARKER));
+    }
+
+    #[tokio::test]
+    async fn lists_and_exports_transcripts_by_run() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+        let root = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 1,
+                domain: "platform".to_string(),
+                objective: "Root".to_string(),
+                token_budget: 1_000,
+            },
+        )
+        .await
+        .expect("root task should be created");
+        let child = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: Some(root.id.clone()),
+                tier: 3,
+                domain: "frontend".to_string(),
+                objective: "Child".to_string(),
+                token_budget: 500,
+            },
+        )
+        .await
+        .expect("child task should be created");
+
+        let entry = LlmTranscriptRecord {
+            id: "transcript-1".to_string(),
+            root_task_id: None,
+            task_id: Some(child.id.clone()),
+            provider: "openai".to_string(),
+            model_id: "gpt-5-mini".to_string(),
+            resolved_model: None,
+            system_prompt: "system".to_string(),
+            user_prompt: "user".to_string(),
+            response_schema_json: None,
+            response_text: Some("{}".to_string()),
+            error: None,
+            input_tokens: Some(10),
+            output_tokens: Some(1),
+            latency_ms: 42,
+            truncated: false,
+            created_at: 1,
+        };
+        insert_transcript(&pool, &entry)
+            .await
+            .expect("transcript should be stored");
+
+        let listed = list_llm_transcripts(
+            &pool,
+            ListLlmTranscriptsInput {
+                root_task_id: Some(root.id.clone()),
+                task_id: None,
+                limit: None,
+            },
+        )
+        .await
+        .expect("transcripts should list");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].root_task_id.as_deref(), Some(root.id.as_str()));
+
+        let output = std::env::temp_dir().join(format!("aop-transcripts-{}.jsonl", root.id));
+        let exported = export_llm_transcripts(
+            &pool,
+            ExportLlmTranscriptsInput {
+                root_task_id: Some(root.id.clone()),
+                task_id: None,
+                output_path: output.to_string_lossy().to_string(),
+            },
+        )
+        .await
+        .expect("transcripts should export");
+        assert_eq!(exported.transcripts_exported, 1);
+        let contents = fs::read_to_string(&output).expect("export should be readable");
+        assert!(contents.contains("\"latencyMs\":42"));
+        let _ = fs::remove_file(output);
+    }
+}
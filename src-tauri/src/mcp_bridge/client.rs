@@ -13,7 +13,9 @@ use serde_json::Value;
 use tokio::process::Command;
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
+use crate::mcp_bridge::session::{self, SessionContext, SessionPool};
 use crate::mcp_bridge::tool_caller::BridgeRequest;
+use crate::mcp_servers;
 
 #[derive(Debug, Clone)]
 pub struct BridgeClient {
@@ -22,13 +24,15 @@ pub struct BridgeClient {
     max_calls_per_minute: usize,
     concurrent_calls: Arc<Semaphore>,
     queued_calls: Arc<AtomicUsize>,
+    sessions: SessionPool,
+    context: SessionContext,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct BridgeEnvelope {
-    ok: bool,
-    data: Option<Value>,
-    error: Option<String>,
+pub(crate) struct BridgeEnvelope {
+    pub(crate) ok: bool,
+    pub(crate) data: Option<Value>,
+    pub(crate) error: Option<String>,
 }
 
 #[derive(Debug, Default)]
@@ -47,9 +51,10 @@ impl BridgeClient {
     const MAX_QUEUED_CALLS: usize = 50;
     const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
 
-    pub fn new(workspace_root: &Path) -> Self {
+    pub fn new(workspace_root: &Path, context: SessionContext) -> Self {
         Self::with_limits(
             workspace_root,
+            context,
             Self::DEFAULT_MAX_CALLS_PER_MINUTE,
             Self::DEFAULT_MAX_CONCURRENT_CALLS,
         )
@@ -57,6 +62,7 @@ impl BridgeClient {
 
     fn with_limits(
         workspace_root: &Path,
+        context: SessionContext,
         max_calls_per_minute: usize,
         max_concurrent_calls: usize,
     ) -> Self {
@@ -69,9 +75,26 @@ impl BridgeClient {
             max_calls_per_minute: effective_max_calls,
             concurrent_calls: Arc::new(Semaphore::new(effective_max_concurrent)),
             queued_calls: Arc::new(AtomicUsize::new(0)),
+            sessions: SessionPool::default(),
+            context,
         }
     }
 
+    /// Pings the sessions of registered servers in the background and restarts crashed
+    /// ones.
+    pub fn spawn_health_checks(&self) {
+        let client = self.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(session::HEALTH_CHECK_INTERVAL).await;
+                client
+                    .sessions
+                    .check_health(&client.bridge_dir, &client.context)
+                    .await;
+            }
+        });
+    }
+
     pub async fn call<T: DeserializeOwned>(&self, request: &BridgeRequest) -> Result<T, String> {
         let _call_guard = self.reserve_call_slot().await?;
 
@@ -84,9 +107,35 @@ impl BridgeClient {
 
         let request_json = serde_json::to_string(request)
             .map_err(|error| format!("Failed to encode bridge request: {error}"))?;
+
+        // Registered servers keep a long-lived session; ad hoc commands get a process per call.
+        if let Some((server, mcp)) = request
+            .mcp
+            .as_ref()
+            .and_then(|mcp| mcp.server_name.as_deref().map(|server| (server, mcp)))
+        {
+            let envelope = self
+                .sessions
+                .call(&self.bridge_dir, &self.context, server, mcp, &request_json)
+                .await?;
+            return decode_envelope(envelope);
+        }
+
         let request_json_base64 = BASE64_STANDARD.encode(request_json.as_bytes());
 
-        let output = Command::new("pnpm")
+        let mut command = Command::new("pnpm");
+        // The token travels in the bridge's environment so it never shows up in argv.
+        if let Some(secret) = request
+            .mcp
+            .as_ref()
+            .and_then(|mcp| mcp.auth_secret.as_deref())
+        {
+            command.env(
+                "AOP_MCP_AUTHORIZATION",
+                mcp_servers::authorization_header(&self.context.secret_vault, secret).await?,
+            );
+        }
+        let output = command
             .arg("--silent")
             .arg("--dir")
             .arg(self.bridge_dir.as_ref())
@@ -110,20 +159,7 @@ impl BridgeClient {
 
         let stdout = String::from_utf8(output.stdout)
             .map_err(|error| format!("MCP bridge stdout is not UTF-8: {error}"))?;
-        let envelope: BridgeEnvelope = parse_last_json_line(&stdout)?;
-
-        if !envelope.ok {
-            return Err(envelope
-                .error
-                .unwrap_or_else(|| "MCP bridge returned an unknown error".to_string()));
-        }
-
-        let payload = envelope
-            .data
-            .ok_or_else(|| "MCP bridge returned no data payload".to_string())?;
-
-        serde_json::from_value(payload)
-            .map_err(|error| format!("Failed to decode bridge payload: {error}"))
+        decode_envelope(parse_last_json_line(&stdout)?)
     }
 
     async fn reserve_call_slot(&self) -> Result<BridgeCallGuard, String> {
@@ -205,6 +241,21 @@ fn resolve_bridge_dir(runtime_cwd: &Path) -> PathBuf {
         .unwrap_or_else(|| runtime_cwd.join("mcp-bridge"))
 }
 
+fn decode_envelope<T: DeserializeOwned>(envelope: BridgeEnvelope) -> Result<T, String> {
+    if !envelope.ok {
+        return Err(envelope
+            .error
+            .unwrap_or_else(|| "MCP bridge returned an unknown error".to_string()));
+    }
+
+    let payload = envelope
+        .data
+        .ok_or_else(|| "MCP bridge returned no data payload".to_string())?;
+
+    serde_json::from_value(payload)
+        .map_err(|error| format!("Failed to decode bridge payload: {error}"))
+}
+
 fn parse_last_json_line<T: DeserializeOwned>(raw_output: &str) -> Result<T, String> {
     for line in raw_output.lines().rev() {
         let trimmed = line.trim();
@@ -226,12 +277,16 @@ fn parse_last_json_line<T: DeserializeOwned>(raw_output: &str) -> Result<T, Stri
 #[cfg(test)]
 mod tests {
     use std::fs;
+    use std::path::Path;
     use std::sync::atomic::Ordering;
+    use std::sync::Arc;
     use std::time::{Duration, Instant};
 
+    use sqlx::SqlitePool;
     use tempfile::tempdir;
 
-    use super::{resolve_bridge_dir, BridgeClient};
+    use super::{resolve_bridge_dir, BridgeClient, SessionContext};
+    use crate::secret_vault::SecretVault;
 
     #[test]
     fn resolves_bridge_when_runtime_is_workspace_root() {
@@ -257,11 +312,22 @@ mod tests {
         assert!(resolved.exists());
     }
 
-    #[test]
-    fn rate_limiter_blocks_call_121_within_a_minute() {
+    fn test_context(root: &Path) -> SessionContext {
+        SessionContext {
+            secret_vault: Arc::new(tokio::sync::Mutex::new(SecretVault::new(
+                root.to_path_buf(),
+            ))),
+            db_pool: SqlitePool::connect_lazy("sqlite::memory:")
+                .expect("in-memory sqlite should be configured"),
+            resources: Arc::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_blocks_call_121_within_a_minute() {
         let temp = tempdir().expect("temp dir should be created");
         let root = temp.path();
-        let client = BridgeClient::with_limits(root, 120, 10);
+        let client = BridgeClient::with_limits(root, test_context(root), 120, 10);
         let now = Instant::now();
 
         for _ in 0..120 {
@@ -276,11 +342,11 @@ mod tests {
         assert!(error.contains("RATE_LIMIT_EXCEEDED"));
     }
 
-    #[test]
-    fn rate_limiter_recovers_after_window_expires() {
+    #[tokio::test]
+    async fn rate_limiter_recovers_after_window_expires() {
         let temp = tempdir().expect("temp dir should be created");
         let root = temp.path();
-        let client = BridgeClient::with_limits(root, 2, 10);
+        let client = BridgeClient::with_limits(root, test_context(root), 2, 10);
         let now = Instant::now();
 
         client
@@ -302,7 +368,7 @@ mod tests {
     async fn queue_limit_rejects_when_backpressure_exceeds_capacity() {
         let temp = tempdir().expect("temp dir should be created");
         let root = temp.path();
-        let client = BridgeClient::with_limits(root, 120, 1);
+        let client = BridgeClient::with_limits(root, test_context(root), 120, 1);
 
         let permit = client
             .concurrent_calls
@@ -0,0 +1,223 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::SqlitePool;
+
+use crate::compliance;
+use crate::db::metrics;
+use crate::mcp_bridge::client::BridgeClient;
+use crate::mcp_bridge::tool_caller::{self, BridgeRequest};
+use crate::task_runtime;
+
+pub const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 120;
+pub const MAX_COMMAND_TIMEOUT_SECS: u64 = 900;
+/// Output is recorded as terminal events of at most this many characters each.
+const TRANSCRIPT_CHUNK_CHARS: usize = 4_000;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunTargetCommandInput {
+    pub target_project: String,
+    /// Program to run, or the whole command line when `args` is not given.
+    pub command: String,
+    pub args: Option<Vec<String>>,
+    /// Lowered to the project's `command_timeout_secs` when that is smaller.
+    pub timeout_secs: Option<u64>,
+    /// Task whose terminal receives the transcript.
+    pub task_id: Option<String>,
+    pub actor: Option<String>,
+    pub mcp_server: Option<String>,
+    pub mcp_command: Option<String>,
+    pub mcp_args: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandResult {
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    pub source: String,
+}
+
+impl CommandResult {
+    pub fn succeeded(&self) -> bool {
+        !self.timed_out && self.exit_code == Some(0)
+    }
+}
+
+/// Runs a command allowed by the target project's policy, locally or over the MCP
+/// server, and records its transcript as terminal events of `task_id` when one is given.
+pub async fn run_command(
+    pool: &SqlitePool,
+    client: &BridgeClient,
+    input: RunTargetCommandInput,
+) -> Result<CommandResult, String> {
+    let target_project = input.target_project.trim().to_string();
+    if target_project.is_empty() {
+        return Err("targetProject is required".to_string());
+    }
+    let command_line = command_line(&input.command, input.args.as_deref());
+    let Some((program, args)) = command_line.split_first() else {
+        return Err("command is required".to_string());
+    };
+    let policy = compliance::load_project_policy(Path::new(&target_project))?;
+    compliance::check_command(&policy, &command_line)?;
+    let timeout_secs = effective_timeout(input.timeout_secs, policy.command_timeout_secs);
+
+    let actor = input
+        .actor
+        .as_deref()
+        .map(str::trim)
+        .filter(|actor| !actor.is_empty())
+        .unwrap_or("run_command")
+        .to_string();
+    let task_id = input
+        .task_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|task_id| !task_id.is_empty())
+        .map(str::to_string);
+    let rendered = command_line.join(" ");
+    let server = input
+        .mcp_server
+        .clone()
+        .or_else(|| input.mcp_command.clone())
+        .unwrap_or_else(|| "local".to_string());
+
+    let request = BridgeRequest {
+        action: "run_command".to_string(),
+        target_project: target_project.clone(),
+        command: Some(program.clone()),
+        args: Some(args.to_vec()),
+        timeout_ms: Some(timeout_secs * 1_000),
+        mcp: tool_caller::optional_mcp(input.mcp_server, input.mcp_command, input.mcp_args)?,
+        ..Default::default()
+    };
+
+    if let Some(task_id) = task_id.as_deref() {
+        task_runtime::record_task_activity(
+            pool,
+            &actor,
+            "command_started",
+            task_id,
+            &format!("$ {rendered} (server={server} timeout={timeout_secs}s)"),
+        )
+        .await?;
+    }
+    let result = client.call::<CommandResult>(&request).await;
+
+    match task_id.as_deref() {
+        Some(task_id) => record_transcript(pool, &actor, task_id, &rendered, &result).await,
+        None => {
+            let _ = metrics::record_audit_event(
+                pool,
+                &actor,
+                "command_run",
+                None,
+                Some(
+                    &json!({
+                        "command": rendered,
+                        "server": server,
+                        "exitCode": result.as_ref().ok().and_then(|result| result.exit_code),
+                        "error": result.as_ref().err(),
+                    })
+                    .to_string(),
+                ),
+            )
+            .await;
+        }
+    }
+    result
+}
+
+/// `command` split on whitespace, or `command` followed by `args` when those are given.
+fn command_line(command: &str, args: Option<&[String]>) -> Vec<String> {
+    match args {
+        Some(args) => std::iter::once(command.trim().to_string())
+            .filter(|program| !program.is_empty())
+            .chain(args.iter().cloned())
+            .collect(),
+        None => command.split_whitespace().map(str::to_string).collect(),
+    }
+}
+
+fn effective_timeout(requested: Option<u64>, policy_cap: Option<u64>) -> u64 {
+    let cap = policy_cap
+        .unwrap_or(MAX_COMMAND_TIMEOUT_SECS)
+        .clamp(1, MAX_COMMAND_TIMEOUT_SECS);
+    requested
+        .unwrap_or(DEFAULT_COMMAND_TIMEOUT_SECS)
+        .clamp(1, cap)
+}
+
+async fn record_transcript(
+    pool: &SqlitePool,
+    actor: &str,
+    task_id: &str,
+    rendered: &str,
+    result: &Result<CommandResult, String>,
+) {
+    let outcome = match result {
+        Ok(result) => {
+            for (action, output) in [
+                ("command_stdout", &result.stdout),
+                ("command_stderr", &result.stderr),
+            ] {
+                for chunk in transcript_chunks(output) {
+                    let _ =
+                        task_runtime::record_task_activity(pool, actor, action, task_id, &chunk)
+                            .await;
+                }
+            }
+            let status = if result.timed_out {
+                "timed out".to_string()
+            } else {
+                result
+                    .exit_code
+                    .map(|code| format!("exit {code}"))
+                    .unwrap_or_else(|| "killed".to_string())
+            };
+            format!("$ {rendered} -> {status} in {}ms", result.duration_ms)
+        }
+        Err(error) => format!("$ {rendered} -> failed: {error}"),
+    };
+    let _ = task_runtime::record_task_activity(pool, actor, "command_finished", task_id, &outcome)
+        .await;
+}
+
+fn transcript_chunks(output: &str) -> Vec<String> {
+    let chars = output.trim_end().chars().collect::<Vec<_>>();
+    chars
+        .chunks(TRANSCRIPT_CHUNK_CHARS)
+        .map(|chunk| chunk.iter().collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_lines_and_timeouts_are_normalized() {
+        assert_eq!(command_line(" pnpm  lint ", None), ["pnpm", "lint"]);
+        assert_eq!(
+            command_line("pnpm", Some(&["run".to_string(), "a b".to_string()])),
+            ["pnpm", "run", "a b"]
+        );
+        assert!(command_line("  ", Some(&[])).is_empty());
+
+        assert_eq!(effective_timeout(None, None), DEFAULT_COMMAND_TIMEOUT_SECS);
+        assert_eq!(effective_timeout(Some(600), Some(60)), 60);
+        assert_eq!(
+            effective_timeout(Some(5_000), None),
+            MAX_COMMAND_TIMEOUT_SECS
+        );
+
+        assert_eq!(transcript_chunks(&"x".repeat(4_001)).len(), 2);
+        assert!(transcript_chunks("\n").is_empty());
+    }
+}
@@ -1,2 +1,7 @@
 pub mod client;
+pub mod command_runner;
+pub mod path_policy;
+pub mod resources;
+pub mod session;
+pub mod tool_cache;
 pub mod tool_caller;
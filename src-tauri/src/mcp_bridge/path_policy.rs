@@ -0,0 +1,242 @@
+use std::path::{Path, PathBuf};
+
+use glob::{MatchOptions, Pattern};
+use serde_json::json;
+use sqlx::SqlitePool;
+
+use crate::compliance::{self, ProjectPolicy, PROJECT_POLICY_PATH};
+use crate::db::metrics;
+
+/// Files agents may never read, list or search unless the project allow-lists them. Each
+/// entry is a glob relative to the project root and the reason shown in the violation.
+const DENIED_READ_PATHS: &[(&str, &str)] = &[
+    ("**/.env", "environment file"),
+    ("**/.env.*", "environment file"),
+    ("**/*.pem", "key file"),
+    ("**/*.key", "key file"),
+    ("**/*.p12", "key file"),
+    ("**/*.pfx", "key file"),
+    ("**/*.jks", "key file"),
+    ("**/*.keystore", "key file"),
+    ("**/id_rsa*", "SSH key"),
+    ("**/id_ecdsa*", "SSH key"),
+    ("**/id_ed25519*", "SSH key"),
+    ("**/.ssh", "SSH directory"),
+    ("**/.ssh/**", "SSH directory"),
+    ("**/.aws/credentials", "credentials file"),
+    ("**/.git-credentials", "credentials file"),
+    ("**/.netrc", "credentials file"),
+    ("**/.npmrc", "credentials file"),
+    ("**/.pypirc", "credentials file"),
+];
+
+/// Readable despite matching a deny glob: templates without real values and public keys.
+const ALLOWED_READ_PATHS: &[&str] = &[
+    "**/.env.example",
+    "**/.env.sample",
+    "**/.env.template",
+    "**/*.pub",
+];
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Which project files agents may read: the built-in deny list plus the project's
+/// `read_deny_paths`, minus anything `read_allow_paths` covers.
+#[derive(Debug, Clone)]
+pub struct ReadPathPolicy {
+    deny: Vec<(Pattern, String)>,
+    allow: Vec<Pattern>,
+    /// Canonical project root when the project is on this machine; paths are resolved
+    /// against it so symlinks cannot leave the project or dodge the deny list.
+    root: Option<PathBuf>,
+}
+
+impl ReadPathPolicy {
+    /// The policy of the project at `target_project`, from its `.aop/policy.toml`.
+    pub fn load(target_project: &str) -> Result<Self, String> {
+        let policy = compliance::load_project_policy(Path::new(target_project))?;
+        Ok(Self {
+            root: Path::new(target_project.trim()).canonicalize().ok(),
+            ..Self::new(&policy)
+        })
+    }
+
+    fn new(policy: &ProjectPolicy) -> Self {
+        let deny = DENIED_READ_PATHS
+            .iter()
+            .map(|(pattern, reason)| (*pattern, *reason))
+            .chain(
+                policy
+                    .read_deny_paths
+                    .iter()
+                    .map(|pattern| (pattern.as_str(), "project read-denied path")),
+            )
+            .filter_map(|(pattern, reason)| {
+                Pattern::new(pattern)
+                    .ok()
+                    .map(|pattern| (pattern, reason.to_string()))
+            })
+            .collect();
+        let allow = ALLOWED_READ_PATHS
+            .iter()
+            .copied()
+            .chain(policy.read_allow_paths.iter().map(String::as_str))
+            .filter_map(|pattern| Pattern::new(pattern).ok())
+            .collect();
+        Self {
+            deny,
+            allow,
+            root: None,
+        }
+    }
+
+    /// Returns why agents may not read `relative_path`, or `None` when they may.
+    pub fn denial_reason(&self, relative_path: &str) -> Option<&str> {
+        let normalized = relative_path.trim().trim_start_matches("./");
+        if self
+            .allow
+            .iter()
+            .any(|pattern| pattern.matches_with(normalized, MATCH_OPTIONS))
+        {
+            return None;
+        }
+        self.deny
+            .iter()
+            .find(|(pattern, _)| pattern.matches_with(normalized, MATCH_OPTIONS))
+            .map(|(_, reason)| reason.as_str())
+    }
+
+    /// The project-relative path agents may read, or a `SECURITY_VIOLATION` error. An empty
+    /// path is the project root. Existing local paths are resolved first: the target must
+    /// stay under the project root, and the deny list applies to it as well.
+    pub fn check(&self, raw_path: &str) -> Result<String, String> {
+        let path = relative_path(raw_path)?;
+        self.check_relative(&path)?;
+        if let Some(resolved) = self.resolve(&path)? {
+            self.check_relative(&resolved)?;
+        }
+        Ok(path)
+    }
+
+    fn check_relative(&self, path: &str) -> Result<(), String> {
+        match self.denial_reason(path) {
+            Some(reason) => Err(format!(
+                "SECURITY_VIOLATION: '{path}' is a {reason} and agents may not read it. Add it \
+                 (or a glob covering it) to read_allow_paths in '{PROJECT_POLICY_PATH}' to \
+                 permit access."
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// The project-relative path `path` resolves to through symlinks, or `None` when the
+    /// project is not local or the path does not exist.
+    fn resolve(&self, path: &str) -> Result<Option<String>, String> {
+        let Some(root) = &self.root else {
+            return Ok(None);
+        };
+        let Ok(resolved) = root.join(path).canonicalize() else {
+            return Ok(None);
+        };
+        let relative = resolved.strip_prefix(root).map_err(|_| {
+            format!("SECURITY_VIOLATION: '{path}' resolves outside the project root")
+        })?;
+        Ok(Some(relative.to_string_lossy().replace('\\', "/")))
+    }
+}
+
+/// `raw_path` with forward slashes, rejected when it could leave the project root.
+pub(crate) fn relative_path(raw_path: &str) -> Result<String, String> {
+    let path = raw_path.trim().replace('\\', "/");
+    if path.starts_with('/') || path.starts_with('~') || path.contains(':') || path.contains('\0') {
+        return Err(format!(
+            "SECURITY_VIOLATION: '{raw_path}' must be relative to the project root"
+        ));
+    }
+    if path.split('/').any(|segment| segment == "..") {
+        return Err(format!(
+            "SECURITY_VIOLATION: '{raw_path}' must not include '..' segments"
+        ));
+    }
+    Ok(path.trim_start_matches("./").to_string())
+}
+
+/// Records a `path_access_denied` audit event for `target_project`.
+pub(crate) async fn record_violation(
+    pool: &SqlitePool,
+    tool: &str,
+    target_project: &str,
+    paths: &[String],
+    error: &str,
+) {
+    let details = json!({ "tool": tool, "paths": paths, "error": error }).to_string();
+    let _ = metrics::record_audit_event(
+        pool,
+        "mcp_bridge",
+        "path_access_denied",
+        Some(target_project),
+        Some(&details),
+    )
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_files_are_denied_unless_allow_listed() {
+        let policy = ReadPathPolicy::new(&ProjectPolicy {
+            read_deny_paths: vec!["private/**".to_string()],
+            read_allow_paths: vec!["fixtures/test.pem".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(
+            policy.check(" src\\app.ts ").expect("readable"),
+            "src/app.ts"
+        );
+        assert_eq!(policy.check("").expect("project root"), "");
+        assert!(policy.check(".env").is_err());
+        assert!(policy.check("apps/web/.env.local").is_err());
+        assert!(policy.check("deploy/server.KEY").is_err());
+        assert!(policy.check(".ssh").is_err());
+        assert!(policy.check("private/notes.md").is_err());
+        assert!(policy.check("../outside.ts").is_err());
+        assert!(policy.check("/etc/passwd").is_err());
+        assert!(policy.check(".env.example").is_ok());
+        assert!(policy.check("keys/id_ed25519.pub").is_ok());
+        assert!(policy.check("fixtures/test.pem").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_checked_at_their_target() {
+        let outside = tempfile::tempdir().expect("outside dir should be created");
+        let project = tempfile::tempdir().expect("project dir should be created");
+        std::fs::write(outside.path().join("secret.txt"), "token").expect("write outside");
+        std::fs::write(project.path().join(".env"), "TOKEN=1").expect("write env");
+        std::fs::write(project.path().join("notes.md"), "notes").expect("write notes");
+        let link = |target: &Path, name: &str| {
+            std::os::unix::fs::symlink(target, project.path().join(name)).expect("symlink")
+        };
+        link(&outside.path().join("secret.txt"), "leak.txt");
+        link(outside.path(), "outside");
+        link(&project.path().join(".env"), "config.txt");
+        link(&project.path().join("notes.md"), "readme.md");
+
+        let policy =
+            ReadPathPolicy::load(&project.path().to_string_lossy()).expect("policy should load");
+        assert!(policy.check("leak.txt").is_err());
+        assert!(policy.check("outside/secret.txt").is_err());
+        assert!(policy.check("config.txt").is_err());
+        assert_eq!(policy.check("readme.md").expect("readable"), "readme.md");
+        assert_eq!(
+            policy.check("missing.md").expect("not yet written"),
+            "missing.md"
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+use crate::db::telemetry::{self, NewAgentEvent};
+use crate::mcp_bridge::client::BridgeClient;
+use crate::mcp_bridge::tool_caller::BridgeRequest;
+use crate::mcp_servers;
+use crate::vector::indexer;
+
+/// Quiet period after the last change notification before reindexing, so a burst of
+/// updates triggers one pass.
+const REINDEX_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Resource subscriptions by server, and the projects with a reindex pass queued.
+#[derive(Debug, Default)]
+pub struct McpResources {
+    subscriptions: BTreeMap<String, McpResourceSubscription>,
+    pending_reindex: HashSet<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscribeMcpResourcesInput {
+    pub mcp_server: String,
+    /// Resources to watch; all the server lists when empty.
+    pub uris: Option<Vec<String>>,
+    /// Project reindexed incrementally when a watched resource changes.
+    pub target_project: Option<String>,
+    pub reindex: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsubscribeMcpResourcesInput {
+    pub mcp_server: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResourceSubscription {
+    pub server: String,
+    pub uris: Vec<String>,
+    pub target_project: Option<String>,
+    pub reindex: bool,
+    pub subscribed_at: i64,
+    pub last_update_at: Option<i64>,
+    pub last_updated_uri: Option<String>,
+    pub update_count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscribedUris {
+    uris: Vec<String>,
+}
+
+/// Subscribes to resources of a registered server over its bridge session, replacing
+/// any earlier subscription to the same server.
+pub async fn subscribe(
+    client: &BridgeClient,
+    resources: &Mutex<McpResources>,
+    input: SubscribeMcpResourcesInput,
+) -> Result<McpResourceSubscription, String> {
+    let server = mcp_servers::get_server(&input.mcp_server)?;
+    let reindex = input.reindex.unwrap_or(false);
+    let target_project = input
+        .target_project
+        .map(|target| target.trim().to_string())
+        .filter(|target| !target.is_empty());
+    if reindex && target_project.is_none() {
+        return Err("targetProject is required to reindex on changes".to_string());
+    }
+    let request = BridgeRequest {
+        action: "subscribe_resources".to_string(),
+        target_project: target_project.clone().unwrap_or_default(),
+        uris: input.uris,
+        mcp: Some(server.bridge_config()),
+        ..Default::default()
+    };
+    let subscribed: SubscribedUris = client.call(&request).await?;
+    let subscription = McpResourceSubscription {
+        server: server.name.clone(),
+        uris: subscribed.uris,
+        target_project,
+        reindex,
+        subscribed_at: Utc::now().timestamp(),
+        last_update_at: None,
+        last_updated_uri: None,
+        update_count: 0,
+    };
+    resources
+        .lock()
+        .await
+        .subscriptions
+        .insert(server.name, subscription.clone());
+    Ok(subscription)
+}
+
+/// Returns whether the server had a subscription.
+pub async fn unsubscribe(
+    client: &BridgeClient,
+    resources: &Mutex<McpResources>,
+    input: UnsubscribeMcpResourcesInput,
+) -> Result<bool, String> {
+    let name = input.mcp_server.trim().to_string();
+    let Some(subscription) = resources.lock().await.subscriptions.remove(&name) else {
+        return Ok(false);
+    };
+    if let Ok(server) = mcp_servers::get_server(&name) {
+        let request = BridgeRequest {
+            action: "unsubscribe_resources".to_string(),
+            target_project: String::new(),
+            uris: Some(subscription.uris),
+            mcp: Some(server.bridge_config()),
+            ..Default::default()
+        };
+        client.call::<SubscribedUris>(&request).await?;
+    }
+    Ok(true)
+}
+
+pub async fn list_subscriptions(resources: &Mutex<McpResources>) -> Vec<McpResourceSubscription> {
+    resources
+        .lock()
+        .await
+        .subscriptions
+        .values()
+        .cloned()
+        .collect()
+}
+
+/// The request that restores `server`'s subscription on a freshly started session.
+pub(crate) async fn resubscribe_request(
+    resources: &Mutex<McpResources>,
+    server: &str,
+) -> Option<String> {
+    let uris = resources
+        .lock()
+        .await
+        .subscriptions
+        .get(server)?
+        .uris
+        .clone();
+    let config = mcp_servers::get_server(server).ok()?;
+    serde_json::to_string(&BridgeRequest {
+        action: "subscribe_resources".to_string(),
+        target_project: String::new(),
+        uris: Some(uris),
+        mcp: Some(config.bridge_config()),
+        ..Default::default()
+    })
+    .ok()
+}
+
+/// Handles a notification a session forwarded from `server`: resource updates and list
+/// changes become agent events in `pool`, and updates of a reindexing subscription queue
+/// a pass.
+pub(crate) async fn handle_notification(
+    pool: &SqlitePool,
+    resources: &Arc<Mutex<McpResources>>,
+    server: &str,
+    notification: &Value,
+) {
+    let method = notification["method"].as_str().unwrap_or_default();
+    let action = match method {
+        "notifications/resources/updated" => "mcp_resource_updated",
+        "notifications/resources/list_changed" => "mcp_resources_changed",
+        _ => return,
+    };
+    let uri = notification["params"]["uri"].as_str().map(str::to_string);
+    let reindex_target = {
+        let mut resources = resources.lock().await;
+        let Some(subscription) = resources.subscriptions.get_mut(server) else {
+            return;
+        };
+        subscription.last_update_at = Some(Utc::now().timestamp());
+        subscription.last_updated_uri = uri.clone();
+        subscription.update_count = subscription.update_count.saturating_add(1);
+        subscription
+            .target_project
+            .clone()
+            .filter(|_| subscription.reindex && action == "mcp_resource_updated")
+    };
+
+    let server = server.to_string();
+    let record_pool = pool.clone();
+    tauri::async_runtime::spawn(async move {
+        let _ = telemetry::record_agent_event(
+            &record_pool,
+            NewAgentEvent {
+                actor: "mcp_bridge".to_string(),
+                action: action.to_string(),
+                status: Some("completed".to_string()),
+                phase: Some("io".to_string()),
+                message: uri.clone(),
+                mcp_server: Some(server.clone()),
+                payload: Some(json!({ "server": server, "uri": uri })),
+                ..Default::default()
+            },
+        )
+        .await;
+    });
+    if let Some(target_project) = reindex_target {
+        queue_reindex(pool.clone(), resources.clone(), target_project).await;
+    }
+}
+
+async fn queue_reindex(
+    pool: SqlitePool,
+    resources: Arc<Mutex<McpResources>>,
+    target_project: String,
+) {
+    if !resources
+        .lock()
+        .await
+        .pending_reindex
+        .insert(target_project.clone())
+    {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        sleep(REINDEX_DEBOUNCE).await;
+        resources
+            .lock()
+            .await
+            .pending_reindex
+            .remove(&target_project);
+        let _ = indexer::index_project(&pool, &target_project).await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn updates_are_counted_against_the_server_subscription() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+        let resources = Arc::new(Mutex::new(McpResources::default()));
+        resources.lock().await.subscriptions.insert(
+            "resources-test".to_string(),
+            McpResourceSubscription {
+                server: "resources-test".to_string(),
+                uris: vec!["file:///repo/build.log".to_string()],
+                target_project: None,
+                reindex: false,
+                subscribed_at: 0,
+                last_update_at: None,
+                last_updated_uri: None,
+                update_count: 0,
+            },
+        );
+        let update = json!({
+            "method": "notifications/resources/updated",
+            "params": { "uri": "file:///repo/build.log" },
+        });
+        handle_notification(&pool, &resources, "resources-test", &update).await;
+        handle_notification(
+            &pool,
+            &resources,
+            "resources-test",
+            &json!({ "method": "notifications/progress" }),
+        )
+        .await;
+        handle_notification(&pool, &resources, "other-server", &update).await;
+
+        let subscriptions = list_subscriptions(&resources).await;
+        assert_eq!(subscriptions.len(), 1);
+        assert_eq!(subscriptions[0].update_count, 1);
+        assert_eq!(
+            subscriptions[0].last_updated_uri.as_deref(),
+            Some("file:///repo/build.log")
+        );
+    }
+}
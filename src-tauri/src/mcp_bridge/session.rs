@@ -0,0 +1,496 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{oneshot, Mutex};
+use tokio::time::timeout;
+
+use crate::mcp_bridge::client::BridgeEnvelope;
+use crate::mcp_bridge::resources::{self, McpResources};
+use crate::mcp_bridge::tool_caller::{BridgeMcpConfig, BridgeRequest};
+use crate::mcp_servers;
+use crate::secret_vault::SecretVault;
+
+pub const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+const BASE_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(60);
+
+static HEALTH: StdMutex<BTreeMap<String, McpSessionHealth>> = StdMutex::new(BTreeMap::new());
+
+/// State of the long-lived bridge session of one registered server, shown in mission
+/// control.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpSessionHealth {
+    pub server: String,
+    /// `starting`, `healthy` or `backoff` while waiting to restart.
+    pub status: String,
+    pub pid: Option<u32>,
+    pub started_at: Option<i64>,
+    pub last_ping_at: Option<i64>,
+    pub last_ping_ms: Option<u64>,
+    pub consecutive_failures: u32,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    /// When a crashed session may be started again.
+    pub retry_at: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionResponse {
+    id: u64,
+    #[serde(flatten)]
+    envelope: BridgeEnvelope,
+}
+
+/// A server-initiated MCP notification the session passes through, such as a resource
+/// update.
+#[derive(Debug, Deserialize)]
+struct SessionNotification {
+    notification: serde_json::Value,
+}
+
+type PendingCalls = StdMutex<HashMap<u64, oneshot::Sender<Result<BridgeEnvelope, String>>>>;
+
+/// A bridge process in `--serve` mode that keeps its MCP connection open. Requests are
+/// multiplexed over its stdin by id.
+struct SessionHandle {
+    stdin: Mutex<ChildStdin>,
+    child: Mutex<Child>,
+    pending: Arc<PendingCalls>,
+    next_id: AtomicU64,
+    alive: Arc<AtomicBool>,
+}
+
+struct SessionSlot {
+    fingerprint: String,
+    handle: Option<Arc<SessionHandle>>,
+    consecutive_failures: u32,
+    restarts: u32,
+    retry_at: Option<Instant>,
+}
+
+/// What sessions take from the app rather than from their server's registry entry.
+#[derive(Clone)]
+pub struct SessionContext {
+    /// Where the tokens of servers with an `auth_secret` are read from.
+    pub secret_vault: Arc<Mutex<SecretVault>>,
+    /// Where forwarded resource notifications are recorded.
+    pub db_pool: SqlitePool,
+    /// The subscriptions those notifications update and a new session restores.
+    pub resources: Arc<Mutex<McpResources>>,
+}
+
+impl std::fmt::Debug for SessionContext {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("SessionContext")
+            .finish_non_exhaustive()
+    }
+}
+
+/// Sessions of registered servers, keyed by server name.
+#[derive(Clone, Default)]
+pub struct SessionPool {
+    slots: Arc<Mutex<HashMap<String, SessionSlot>>>,
+}
+
+impl std::fmt::Debug for SessionPool {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("SessionPool")
+            .finish_non_exhaustive()
+    }
+}
+
+impl SessionPool {
+    /// Sends `request_json` over the session of `server`, starting or restarting it as
+    /// needed. A crashed session is restarted once its backoff has passed.
+    pub async fn call(
+        &self,
+        bridge_dir: &Path,
+        context: &SessionContext,
+        server: &str,
+        mcp: &BridgeMcpConfig,
+        request_json: &str,
+    ) -> Result<BridgeEnvelope, String> {
+        let handle = self.session(bridge_dir, context, server, mcp).await?;
+        match handle.send(request_json).await {
+            Ok(envelope) => {
+                self.record_success(server, None).await;
+                Ok(envelope)
+            }
+            Err(error) => {
+                if !handle.alive.load(Ordering::SeqCst) {
+                    self.record_failure(server, &error).await;
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// Pings every open session, and restarts crashed ones whose backoff has passed.
+    pub async fn check_health(&self, bridge_dir: &Path, secret_vault: &Arc<Mutex<SecretVault>>) {
+        let servers = self.slots.lock().await.keys().cloned().collect::<Vec<_>>();
+        for server in servers {
+            let Ok(config) = mcp_servers::get_server(&server) else {
+                self.close(&server).await;
+                continue;
+            };
+            let mcp = config.bridge_config();
+            let request = BridgeRequest {
+                action: "ping".to_string(),
+                target_project: String::new(),
+                mcp: Some(mcp.clone()),
+                ..Default::default()
+            };
+            let Ok(request_json) = serde_json::to_string(&request) else {
+                continue;
+            };
+            let Ok(handle) = self.session(bridge_dir, context, &server, &mcp).await else {
+                continue;
+            };
+            let started = Instant::now();
+            let outcome = match timeout(PING_TIMEOUT, handle.send(&request_json)).await {
+                Ok(Ok(envelope)) if envelope.ok => Ok(()),
+                Ok(Ok(envelope)) => Err(envelope
+                    .error
+                    .unwrap_or_else(|| "MCP ping failed".to_string())),
+                Ok(Err(error)) => Err(error),
+                Err(_) => Err(format!(
+                    "MCP ping timed out after {}s",
+                    PING_TIMEOUT.as_secs()
+                )),
+            };
+            match outcome {
+                Ok(()) => {
+                    let ping_ms = started.elapsed().as_millis() as u64;
+                    self.record_success(&server, Some(ping_ms)).await;
+                }
+                Err(error) => {
+                    handle.shutdown().await;
+                    self.record_failure(&server, &error).await;
+                }
+            }
+        }
+    }
+
+    pub async fn close(&self, server: &str) {
+        if let Some(slot) = self.slots.lock().await.remove(server) {
+            if let Some(handle) = slot.handle {
+                handle.shutdown().await;
+            }
+        }
+        health_records().remove(server);
+    }
+
+    async fn session(
+        &self,
+        bridge_dir: &Path,
+        context: &SessionContext,
+        server: &str,
+        mcp: &BridgeMcpConfig,
+    ) -> Result<Arc<SessionHandle>, String> {
+        let fingerprint = fingerprint(mcp);
+        let mut slots = self.slots.lock().await;
+        let slot = slots
+            .entry(server.to_string())
+            .or_insert_with(|| SessionSlot {
+                fingerprint: fingerprint.clone(),
+                handle: None,
+                consecutive_failures: 0,
+                restarts: 0,
+                retry_at: None,
+            });
+        if slot.fingerprint != fingerprint {
+            // The registry entry changed; the old process talks to the old server.
+            if let Some(handle) = slot.handle.take() {
+                handle.shutdown().await;
+            }
+            slot.fingerprint = fingerprint;
+            slot.consecutive_failures = 0;
+            slot.retry_at = None;
+        }
+        match slot.handle.as_ref() {
+            Some(handle) if handle.alive.load(Ordering::SeqCst) => return Ok(handle.clone()),
+            Some(_) => {
+                // Exited between calls.
+                slot.handle = None;
+                fail_slot(server, slot, "MCP bridge session exited");
+            }
+            None => {}
+        }
+        if let Some(retry_at) = slot.retry_at.filter(|retry_at| *retry_at > Instant::now()) {
+            return Err(format!(
+                "MCP session for '{server}' is restarting; retry in {}s",
+                retry_at.saturating_duration_since(Instant::now()).as_secs() + 1
+            ));
+        }
+
+        let restarting = slot.consecutive_failures > 0;
+        match SessionHandle::spawn(bridge_dir, context, server, mcp).await {
+            Ok((handle, pid)) => {
+                if restarting {
+                    slot.restarts += 1;
+                }
+                // Subscriptions live in the bridge process, so a new one has none.
+                if let Some(request_json) =
+                    resources::resubscribe_request(&context.resources, server).await
+                {
+                    let handle = handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let _ = handle.send(&request_json).await;
+                    });
+                }
+                slot.retry_at = None;
+                slot.handle = Some(handle.clone());
+                let restarts = slot.restarts;
+                update_health(server, |health| {
+                    health.status = "starting".to_string();
+                    health.pid = pid;
+                    health.started_at = Some(Utc::now().timestamp());
+                    health.restarts = restarts;
+                    health.retry_at = None;
+                });
+                Ok(handle)
+            }
+            Err(error) => {
+                slot.handle = None;
+                fail_slot(server, slot, &error);
+                Err(error)
+            }
+        }
+    }
+
+    async fn record_success(&self, server: &str, ping_ms: Option<u64>) {
+        if let Some(slot) = self.slots.lock().await.get_mut(server) {
+            slot.consecutive_failures = 0;
+        }
+        update_health(server, |health| {
+            health.status = "healthy".to_string();
+            health.consecutive_failures = 0;
+            health.last_error = None;
+            if ping_ms.is_some() {
+                health.last_ping_at = Some(Utc::now().timestamp());
+                health.last_ping_ms = ping_ms;
+            }
+        });
+    }
+
+    async fn record_failure(&self, server: &str, error: &str) {
+        let mut slots = self.slots.lock().await;
+        if let Some(slot) = slots.get_mut(server) {
+            slot.handle = None;
+            fail_slot(server, slot, error);
+        }
+    }
+}
+
+fn fail_slot(server: &str, slot: &mut SessionSlot, error: &str) {
+    slot.consecutive_failures += 1;
+    let backoff = restart_backoff(slot.consecutive_failures);
+    slot.retry_at = Some(Instant::now() + backoff);
+    let failures = slot.consecutive_failures;
+    update_health(server, |health| {
+        health.status = "backoff".to_string();
+        health.pid = None;
+        health.consecutive_failures = failures;
+        health.last_error = Some(error.to_string());
+        health.retry_at = Some(Utc::now().timestamp() + backoff.as_secs() as i64);
+    });
+}
+
+/// A first crash restarts at once; after that the wait doubles from one second, capped at
+/// a minute.
+fn restart_backoff(consecutive_failures: u32) -> Duration {
+    if consecutive_failures <= 1 {
+        return Duration::ZERO;
+    }
+    let exponent = (consecutive_failures - 2).min(16);
+    BASE_RESTART_BACKOFF
+        .saturating_mul(1 << exponent)
+        .min(MAX_RESTART_BACKOFF)
+}
+
+/// Everything that changes which server a session talks to.
+fn fingerprint(mcp: &BridgeMcpConfig) -> String {
+    format!(
+        "{}|{}",
+        serde_json::to_string(mcp).unwrap_or_default(),
+        mcp.auth_secret.as_deref().unwrap_or_default()
+    )
+}
+
+impl SessionHandle {
+    async fn spawn(
+        bridge_dir: &Path,
+        context: &SessionContext,
+        server: &str,
+        mcp: &BridgeMcpConfig,
+    ) -> Result<(Arc<Self>, Option<u32>), String> {
+        if !bridge_dir.exists() {
+            return Err(format!(
+                "MCP bridge directory does not exist: {}. Ensure `mcp-bridge/` is present.",
+                bridge_dir.display()
+            ));
+        }
+        let mut command = Command::new("pnpm");
+        if let Some(secret) = mcp.auth_secret.as_deref() {
+            command.env(
+                "AOP_MCP_AUTHORIZATION",
+                mcp_servers::authorization_header(&context.secret_vault, secret).await?,
+            );
+        }
+        let mut child = command
+            .arg("--silent")
+            .arg("--dir")
+            .arg(bridge_dir)
+            .arg("exec")
+            .arg("tsx")
+            .arg("src/index.ts")
+            .arg("--serve")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|error| format!("Failed to start MCP bridge session: {error}"))?;
+        let pid = child.id();
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| "MCP bridge session has no stdin".to_string())?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| "MCP bridge session has no stdout".to_string())?;
+
+        let pending = Arc::new(PendingCalls::default());
+        let alive = Arc::new(AtomicBool::new(true));
+        let (reader_pending, reader_alive) = (pending.clone(), alive.clone());
+        let server = server.to_string();
+        let context = context.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if let Ok(message) = serde_json::from_str::<SessionNotification>(line.trim()) {
+                    resources::handle_notification(
+                        &context.db_pool,
+                        &context.resources,
+                        &server,
+                        &message.notification,
+                    )
+                    .await;
+                    continue;
+                }
+                let Ok(response) = serde_json::from_str::<SessionResponse>(line.trim()) else {
+                    continue;
+                };
+                let waiter = lock(&reader_pending).remove(&response.id);
+                if let Some(waiter) = waiter {
+                    let _ = waiter.send(Ok(response.envelope));
+                }
+            }
+            reader_alive.store(false, Ordering::SeqCst);
+            for (_, waiter) in lock(&reader_pending).drain() {
+                let _ = waiter.send(Err("MCP bridge session exited".to_string()));
+            }
+        });
+
+        Ok((
+            Arc::new(Self {
+                stdin: Mutex::new(stdin),
+                child: Mutex::new(child),
+                pending,
+                next_id: AtomicU64::new(1),
+                alive,
+            }),
+            pid,
+        ))
+    }
+
+    async fn send(&self, request_json: &str) -> Result<BridgeEnvelope, String> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = oneshot::channel();
+        lock(&self.pending).insert(id, sender);
+
+        let line = format!("{{\"id\":{id},\"request\":{request_json}}}\n");
+        let written = {
+            let mut stdin = self.stdin.lock().await;
+            match stdin.write_all(line.as_bytes()).await {
+                Ok(()) => stdin.flush().await,
+                Err(error) => Err(error),
+            }
+        };
+        if let Err(error) = written {
+            lock(&self.pending).remove(&id);
+            self.alive.store(false, Ordering::SeqCst);
+            return Err(format!("Failed to write to MCP bridge session: {error}"));
+        }
+        receiver
+            .await
+            .map_err(|_| "MCP bridge session dropped the request".to_string())?
+    }
+
+    async fn shutdown(&self) {
+        self.alive.store(false, Ordering::SeqCst);
+        let _ = self.child.lock().await.kill().await;
+    }
+}
+
+/// Health of every server that has had a session.
+pub fn health() -> Vec<McpSessionHealth> {
+    health_records().values().cloned().collect()
+}
+
+fn health_records() -> MutexGuard<'static, BTreeMap<String, McpSessionHealth>> {
+    lock(&HEALTH)
+}
+
+fn update_health(server: &str, apply: impl FnOnce(&mut McpSessionHealth)) {
+    let mut health = health_records();
+    let entry = health
+        .entry(server.to_string())
+        .or_insert_with(|| McpSessionHealth {
+            server: server.to_string(),
+            status: "starting".to_string(),
+            pid: None,
+            started_at: None,
+            last_ping_at: None,
+            last_ping_ms: None,
+            consecutive_failures: 0,
+            restarts: 0,
+            last_error: None,
+            retry_at: None,
+        });
+    apply(entry);
+}
+
+fn lock<T>(mutex: &StdMutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restart_backoff_doubles_up_to_a_minute_after_the_first_crash() {
+        assert_eq!(restart_backoff(1), Duration::ZERO);
+        assert_eq!(restart_backoff(2), Duration::from_secs(1));
+        assert_eq!(restart_backoff(5), Duration::from_secs(8));
+        assert_eq!(restart_backoff(8), MAX_RESTART_BACKOFF);
+        assert_eq!(restart_backoff(u32::MAX), MAX_RESTART_BACKOFF);
+    }
+}
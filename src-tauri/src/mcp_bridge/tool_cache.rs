@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::mcp_bridge::client::BridgeClient;
+use crate::mcp_bridge::tool_caller::BridgeRequest;
+use crate::mcp_servers::{self, McpServerConfig};
+
+/// How long a discovered tool list is reused before the server is asked again.
+const CACHE_TTL: Duration = Duration::from_secs(15 * 60);
+const MAX_PROMPT_TOOLS: usize = 24;
+const MAX_PROMPT_DESCRIPTION_CHARS: usize = 200;
+
+static CACHE: OnceLock<Mutex<HashMap<String, CachedTools>>> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+struct CachedTools {
+    server_updated_at: i64,
+    fetched_at: i64,
+    cached_at: Instant,
+    tools: Vec<McpToolSchema>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToolSchema {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub input_schema: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListMcpToolsInput {
+    pub mcp_server: String,
+    /// Skips the cache and asks the server again.
+    pub refresh: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToolCatalog {
+    pub server: String,
+    pub tools: Vec<McpToolSchema>,
+    pub fetched_at: i64,
+    pub cached: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct BridgeToolList {
+    tools: Vec<McpToolSchema>,
+}
+
+/// Tools of a registered server, limited to its allowed tools. Discovery runs the MCP
+/// initialize and `tools/list` handshake through the bridge; results are cached until
+/// they expire or the server's registry entry changes.
+pub async fn list_tools(
+    client: &BridgeClient,
+    input: ListMcpToolsInput,
+) -> Result<McpToolCatalog, String> {
+    let server = mcp_servers::get_server(&input.mcp_server)?;
+    if !input.refresh.unwrap_or(false) {
+        if let Some(cached) = cached(&server) {
+            return Ok(McpToolCatalog {
+                server: server.name,
+                tools: cached.tools,
+                fetched_at: cached.fetched_at,
+                cached: true,
+            });
+        }
+    }
+
+    let request = BridgeRequest {
+        action: "list_tools".to_string(),
+        target_project: String::new(),
+        mcp: Some(server.bridge_config()),
+        ..Default::default()
+    };
+    let listed: BridgeToolList = client.call(&request).await?;
+    let tools = permitted(&server, listed.tools);
+    let fetched_at = Utc::now().timestamp();
+    if let Ok(mut cache) = cache().lock() {
+        cache.insert(
+            server.name.clone(),
+            CachedTools {
+                server_updated_at: server.updated_at,
+                fetched_at,
+                cached_at: Instant::now(),
+                tools: tools.clone(),
+            },
+        );
+    }
+    Ok(McpToolCatalog {
+        server: server.name,
+        tools,
+        fetched_at,
+        cached: false,
+    })
+}
+
+/// Tools for an agent prompt; a server that cannot be reached contributes none.
+pub async fn tools_for_prompt(client: &BridgeClient, server: Option<&str>) -> Vec<McpToolSchema> {
+    let Some(server) = server else {
+        return Vec::new();
+    };
+    list_tools(
+        client,
+        ListMcpToolsInput {
+            mcp_server: server.to_string(),
+            refresh: None,
+        },
+    )
+    .await
+    .map(|catalog| catalog.tools)
+    .unwrap_or_default()
+}
+
+/// One line per tool with its parameters, `?` marking optional ones.
+pub fn render_tools(tools: &[McpToolSchema]) -> String {
+    let mut lines = tools
+        .iter()
+        .take(MAX_PROMPT_TOOLS)
+        .map(|tool| {
+            let required = tool.input_schema["required"]
+                .as_array()
+                .map(|names| names.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+                .unwrap_or_default();
+            let params = tool.input_schema["properties"]
+                .as_object()
+                .map(|properties| {
+                    properties
+                        .iter()
+                        .map(|(name, schema)| {
+                            let kind = schema["type"].as_str().unwrap_or("any");
+                            let optional = if required.contains(&name.as_str()) {
+                                ""
+                            } else {
+                                "?"
+                            };
+                            format!("{name}{optional}: {kind}")
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+            let description = tool
+                .description
+                .as_deref()
+                .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+                .map(|text| {
+                    text.chars()
+                        .take(MAX_PROMPT_DESCRIPTION_CHARS)
+                        .collect::<String>()
+                })
+                .filter(|text| !text.is_empty())
+                .map(|text| format!(" - {text}"))
+                .unwrap_or_default();
+            format!("- {}({params}){description}", tool.name)
+        })
+        .collect::<Vec<_>>();
+    if tools.len() > MAX_PROMPT_TOOLS {
+        lines.push(format!("- ... {} more", tools.len() - MAX_PROMPT_TOOLS));
+    }
+    lines.join("\n")
+}
+
+fn cache() -> &'static Mutex<HashMap<String, CachedTools>> {
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cached(server: &McpServerConfig) -> Option<CachedTools> {
+    let cache = cache().lock().ok()?;
+    cache
+        .get(&server.name)
+        .filter(|entry| {
+            entry.server_updated_at == server.updated_at && entry.cached_at.elapsed() < CACHE_TTL
+        })
+        .cloned()
+}
+
+fn permitted(server: &McpServerConfig, tools: Vec<McpToolSchema>) -> Vec<McpToolSchema> {
+    if server.allowed_tools.is_empty() {
+        return tools;
+    }
+    tools
+        .into_iter()
+        .filter(|tool| server.allowed_tools.contains(&tool.name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn prompt_lists_allowed_tools_with_their_parameters() {
+        let server = McpServerConfig {
+            name: "fs".to_string(),
+            transport: Default::default(),
+            command: "npx".to_string(),
+            args: Vec::new(),
+            env: Default::default(),
+            url: None,
+            headers: Default::default(),
+            auth_secret: None,
+            allowed_tools: vec!["read_file".to_string()],
+            allowed_domains: Vec::new(),
+            updated_at: 0,
+        };
+        let tools = permitted(
+            &server,
+            vec![
+                McpToolSchema {
+                    name: "read_file".to_string(),
+                    description: Some("Read a file\nfrom disk".to_string()),
+                    input_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "path": { "type": "string" },
+                            "encoding": { "type": "string" },
+                        },
+                        "required": ["path"],
+                    }),
+                },
+                McpToolSchema {
+                    name: "delete_file".to_string(),
+                    description: None,
+                    input_schema: json!({}),
+                },
+            ],
+        );
+        assert_eq!(
+            render_tools(&tools),
+            "- read_file(encoding?: string, path: string) - Read a file from disk"
+        );
+    }
+}
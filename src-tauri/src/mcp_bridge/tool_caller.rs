@@ -1,15 +1,38 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
 
 use crate::mcp_bridge::client::BridgeClient;
+use crate::mcp_bridge::path_policy::{self, ReadPathPolicy};
+use crate::mcp_servers::{self, McpTransport};
+use crate::project_ignore::ProjectIgnore;
+use crate::protected_paths;
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BridgeMcpConfig {
+    pub transport: McpTransport,
     pub command: String,
     pub args: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Resolved by the client when it starts the bridge; never sent in the request.
+    #[serde(skip)]
+    pub auth_secret: Option<String>,
+    /// Registry name; calls to a registered server reuse its bridge session.
+    #[serde(skip)]
+    pub server_name: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_tools: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BridgeRequest {
     pub action: String,
@@ -17,6 +40,13 @@ pub struct BridgeRequest {
     pub path: Option<String>,
     pub pattern: Option<String>,
     pub limit: Option<u32>,
+    pub content: Option<String>,
+    pub patch: Option<String>,
+    pub dry_run: Option<bool>,
+    pub command: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub timeout_ms: Option<u64>,
+    pub uris: Option<Vec<String>>,
     pub mcp: Option<BridgeMcpConfig>,
 }
 
@@ -25,6 +55,8 @@ pub struct BridgeRequest {
 pub struct ListTargetDirInput {
     pub target_project: String,
     pub dir_path: Option<String>,
+    /// Registered server name; takes precedence over `mcp_command`.
+    pub mcp_server: Option<String>,
     pub mcp_command: Option<String>,
     pub mcp_args: Option<Vec<String>>,
 }
@@ -34,6 +66,8 @@ pub struct ListTargetDirInput {
 pub struct ReadTargetFileInput {
     pub target_project: String,
     pub file_path: String,
+    /// Registered server name; takes precedence over `mcp_command`.
+    pub mcp_server: Option<String>,
     pub mcp_command: Option<String>,
     pub mcp_args: Option<Vec<String>>,
 }
@@ -44,6 +78,37 @@ pub struct SearchTargetFilesInput {
     pub target_project: String,
     pub pattern: String,
     pub limit: Option<u32>,
+    /// Registered server name; takes precedence over `mcp_command`.
+    pub mcp_server: Option<String>,
+    pub mcp_command: Option<String>,
+    pub mcp_args: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteTargetFileInput {
+    pub target_project: String,
+    pub file_path: String,
+    pub content: String,
+    /// Checks the path and server without writing.
+    pub dry_run: Option<bool>,
+    /// Protected-path globs this write may touch, as in a plan's allow-list.
+    pub allowed_protected_paths: Option<Vec<String>>,
+    pub mcp_server: Option<String>,
+    pub mcp_command: Option<String>,
+    pub mcp_args: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyTargetPatchInput {
+    pub target_project: String,
+    /// Unified diff with paths relative to the project root.
+    pub patch: String,
+    /// Checks that the patch applies without changing anything.
+    pub dry_run: Option<bool>,
+    pub allowed_protected_paths: Option<Vec<String>>,
+    pub mcp_server: Option<String>,
     pub mcp_command: Option<String>,
     pub mcp_args: Option<Vec<String>>,
 }
@@ -87,6 +152,18 @@ pub struct SearchMatch {
     pub preview: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteResult {
+    pub root: String,
+    pub paths: Vec<String>,
+    pub dry_run: bool,
+    pub applied: bool,
+    pub bytes_written: Option<u64>,
+    pub source: String,
+    pub warnings: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResult {
@@ -97,42 +174,75 @@ pub struct SearchResult {
     pub warnings: Vec<String>,
 }
 
-fn optional_mcp(command: Option<String>, args: Option<Vec<String>>) -> Option<BridgeMcpConfig> {
+/// The registered server named `server`, else an ad hoc `command` with `args`.
+pub(crate) fn optional_mcp(
+    server: Option<String>,
+    command: Option<String>,
+    args: Option<Vec<String>>,
+) -> Result<Option<BridgeMcpConfig>, String> {
+    if let Some(name) = server.filter(|name| !name.trim().is_empty()) {
+        return mcp_servers::get_server(&name).map(|server| Some(server.bridge_config()));
+    }
+
     let Some(command) = command.map(|value| value.trim().to_string()) else {
-        return None;
+        return Ok(None);
     };
 
     if command.is_empty() {
-        return None;
+        return Ok(None);
     }
 
-    Some(BridgeMcpConfig {
+    Ok(Some(BridgeMcpConfig {
+        transport: McpTransport::Stdio,
         command,
         args: args.unwrap_or_default(),
-    })
+        env: BTreeMap::new(),
+        url: None,
+        headers: BTreeMap::new(),
+        auth_secret: None,
+        server_name: None,
+        allowed_tools: Vec::new(),
+    }))
 }
 
+/// Lists a project directory, leaving out ignored entries and files the read path
+/// policy denies.
 pub async fn list_dir(
+    pool: &SqlitePool,
     client: &BridgeClient,
     input: ListTargetDirInput,
 ) -> Result<DirectoryListing, String> {
     if input.target_project.trim().is_empty() {
         return Err("targetProject is required".to_string());
     }
+    let policy = ReadPathPolicy::load(&input.target_project)?;
+    let dir_path = match input.dir_path.as_deref() {
+        Some(raw_path) => {
+            Some(check_read_path(pool, &policy, "list_dir", &input.target_project, raw_path).await?)
+        }
+        None => None,
+    };
 
     let request = BridgeRequest {
         action: "list_dir".to_string(),
         target_project: input.target_project,
-        path: input.dir_path,
-        pattern: None,
-        limit: None,
-        mcp: optional_mcp(input.mcp_command, input.mcp_args),
+        path: dir_path,
+        mcp: optional_mcp(input.mcp_server, input.mcp_command, input.mcp_args)?,
+        ..Default::default()
     };
 
-    client.call(&request).await
+    let mut listing: DirectoryListing = client.call(&request).await?;
+    let ignore = ProjectIgnore::load(Path::new(&listing.root));
+    listing.entries.retain(|entry| {
+        !ignore.is_ignored(Path::new(&entry.path), entry.is_dir)
+            && policy.check(&entry.path).is_ok()
+    });
+    Ok(listing)
 }
 
+/// Reads one project file the read path policy allows.
 pub async fn read_file(
+    pool: &SqlitePool,
     client: &BridgeClient,
     input: ReadTargetFileInput,
 ) -> Result<TargetFileContent, String> {
@@ -142,20 +252,31 @@ pub async fn read_file(
     if input.file_path.trim().is_empty() {
         return Err("filePath is required".to_string());
     }
+    let policy = ReadPathPolicy::load(&input.target_project)?;
+    let file_path = check_read_path(
+        pool,
+        &policy,
+        "read_file",
+        &input.target_project,
+        &input.file_path,
+    )
+    .await?;
 
     let request = BridgeRequest {
         action: "read_file".to_string(),
         target_project: input.target_project,
-        path: Some(input.file_path),
-        pattern: None,
-        limit: None,
-        mcp: optional_mcp(input.mcp_command, input.mcp_args),
+        path: Some(file_path),
+        mcp: optional_mcp(input.mcp_server, input.mcp_command, input.mcp_args)?,
+        ..Default::default()
     };
 
     client.call(&request).await
 }
 
+/// Searches the project; matches in files the read path policy denies are dropped and
+/// logged as a violation.
 pub async fn search_files(
+    pool: &SqlitePool,
     client: &BridgeClient,
     input: SearchTargetFilesInput,
 ) -> Result<SearchResult, String> {
@@ -165,15 +286,143 @@ pub async fn search_files(
     if input.pattern.trim().is_empty() {
         return Err("pattern is required".to_string());
     }
+    let policy = ReadPathPolicy::load(&input.target_project)?;
+    let target_project = input.target_project.clone();
 
     let request = BridgeRequest {
         action: "search_files".to_string(),
         target_project: input.target_project,
-        path: None,
         pattern: Some(input.pattern),
         limit: input.limit,
-        mcp: optional_mcp(input.mcp_command, input.mcp_args),
+        mcp: optional_mcp(input.mcp_server, input.mcp_command, input.mcp_args)?,
+        ..Default::default()
+    };
+
+    let mut result: SearchResult = client.call(&request).await?;
+    let mut hidden = Vec::new();
+    result.matches.retain(|found| {
+        let denied = policy.check(&found.path).is_err();
+        if denied && !hidden.contains(&found.path) {
+            hidden.push(found.path.clone());
+        }
+        !denied
+    });
+    if !hidden.is_empty() {
+        let warning = format!(
+            "{} file(s) with matches are hidden by the read path policy",
+            hidden.len()
+        );
+        path_policy::record_violation(pool, "search_files", &target_project, &hidden, &warning)
+            .await;
+        result.warnings.push(warning);
+    }
+    Ok(result)
+}
+
+/// Writes `content` to one file, over the MCP server when one is given. Paths must stay
+/// inside the project and protected paths need an allow-list entry.
+pub async fn write_file(
+    client: &BridgeClient,
+    input: WriteTargetFileInput,
+) -> Result<WriteResult, String> {
+    if input.target_project.trim().is_empty() {
+        return Err("targetProject is required".to_string());
+    }
+    let allowlist = input.allowed_protected_paths.unwrap_or_default();
+    let file_path = check_write_path(&input.file_path, &allowlist)?;
+
+    let request = BridgeRequest {
+        action: "write_file".to_string(),
+        target_project: input.target_project,
+        path: Some(file_path),
+        content: Some(input.content),
+        dry_run: input.dry_run,
+        mcp: optional_mcp(input.mcp_server, input.mcp_command, input.mcp_args)?,
+        ..Default::default()
     };
 
     client.call(&request).await
 }
+
+/// Applies a unified diff, over the MCP server when one is given, after checking every
+/// path it touches like [`write_file`] does.
+pub async fn apply_patch(
+    client: &BridgeClient,
+    input: ApplyTargetPatchInput,
+) -> Result<WriteResult, String> {
+    if input.target_project.trim().is_empty() {
+        return Err("targetProject is required".to_string());
+    }
+    let allowlist = input.allowed_protected_paths.unwrap_or_default();
+    let paths = protected_paths::paths_in_diff(&input.patch);
+    if paths.is_empty() {
+        return Err("patch does not name any file".to_string());
+    }
+    for path in &paths {
+        check_write_path(path, &allowlist)?;
+    }
+
+    let request = BridgeRequest {
+        action: "apply_patch".to_string(),
+        target_project: input.target_project,
+        patch: Some(input.patch),
+        dry_run: input.dry_run,
+        mcp: optional_mcp(input.mcp_server, input.mcp_command, input.mcp_args)?,
+        ..Default::default()
+    };
+
+    client.call(&request).await
+}
+
+/// The project-relative path a write may target. The bridge checks containment again
+/// for local writes; remote servers only see paths that passed here.
+fn check_write_path(raw_path: &str, allowlist: &[String]) -> Result<String, String> {
+    let path = path_policy::relative_path(raw_path)?;
+    if path.is_empty() {
+        return Err("filePath is required".to_string());
+    }
+    protected_paths::check_path(&path, allowlist)?;
+    Ok(path)
+}
+
+/// Checks a path an agent asked to read, logging the violation when it is denied.
+async fn check_read_path(
+    pool: &SqlitePool,
+    policy: &ReadPathPolicy,
+    tool: &str,
+    target_project: &str,
+    raw_path: &str,
+) -> Result<String, String> {
+    match policy.check(raw_path) {
+        Ok(path) => Ok(path),
+        Err(error) => {
+            path_policy::record_violation(
+                pool,
+                tool,
+                target_project,
+                &[raw_path.to_string()],
+                &error,
+            )
+            .await;
+            Err(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_paths_stay_in_the_project_and_respect_protection() {
+        assert_eq!(
+            check_write_path(" src\\app.ts ", &[]).expect("relative path"),
+            "src/app.ts"
+        );
+        assert!(check_write_path("../outside.ts", &[]).is_err());
+        assert!(check_write_path("/etc/passwd", &[]).is_err());
+        assert!(check_write_path("C:/Windows/win.ini", &[]).is_err());
+        assert!(check_write_path("package-lock.json", &[]).is_err());
+        assert!(check_write_path("package-lock.json", &["package-lock.json".to_string()]).is_ok());
+    }
+}
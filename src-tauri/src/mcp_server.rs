@@ -0,0 +1,289 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use crate::agents::orchestrator;
+use crate::commands;
+use crate::db::metrics;
+use crate::db::mutations::{self, ListTaskMutationsInput};
+use crate::mutation_pipeline::{self, RunMutationPipelineInput};
+use crate::vector::search;
+use crate::vector::QueryCodebaseInput;
+use crate::AppState;
+
+/// Protocol revisions this server speaks, oldest first; the newest is offered when the
+/// client asks for one it does not know.
+const PROTOCOL_VERSIONS: [&str; 3] = ["2024-11-05", "2025-03-26", "2025-06-18"];
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+/// Answers MCP requests read line by line from stdin on stdout until stdin closes.
+/// Anything else the process prints must go to stderr.
+pub async fn serve_stdio(state: &AppState) -> Result<(), String> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|error| format!("Failed to read MCP request: {error}"))?
+    {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(state, message).await,
+            Err(error) => Some(error_response(
+                Value::Null,
+                PARSE_ERROR,
+                format!("Invalid JSON: {error}"),
+            )),
+        };
+        let Some(response) = response else {
+            continue;
+        };
+        let mut encoded = response.to_string();
+        encoded.push('\n');
+        stdout
+            .write_all(encoded.as_bytes())
+            .await
+            .map_err(|error| format!("Failed to write MCP response: {error}"))?;
+        stdout
+            .flush()
+            .await
+            .map_err(|error| format!("Failed to write MCP response: {error}"))?;
+    }
+    Ok(())
+}
+
+/// The response to one JSON-RPC message; notifications get none.
+async fn handle_message(state: &AppState, message: Value) -> Option<Value> {
+    let id = message.get("id").cloned()?;
+    let method = message["method"].as_str().unwrap_or_default();
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    let result = match method {
+        "initialize" => Ok(initialize_result(params["protocolVersion"].as_str())),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => call_tool(state, params).await,
+        other => Err((METHOD_NOT_FOUND, format!("Method not found: {other}"))),
+    };
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, message),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn initialize_result(requested: Option<&str>) -> Value {
+    let version = requested
+        .filter(|version| PROTOCOL_VERSIONS.contains(version))
+        .unwrap_or(PROTOCOL_VERSIONS[PROTOCOL_VERSIONS.len() - 1]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "aop", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "orchestrate_objective",
+            "description": "Plan an objective for a project and hold the plan for review.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "objective": { "type": "string" },
+                    "targetProject": { "type": "string", "description": "Absolute project path" },
+                    "globalTokenBudget": { "type": "integer", "minimum": 1 },
+                    "maxRiskTolerance": { "type": "number", "minimum": 0, "maximum": 1 },
+                },
+                "required": ["objective", "targetProject", "globalTokenBudget", "maxRiskTolerance"],
+            },
+        },
+        {
+            "name": "query_codebase",
+            "description": "Search an indexed project for the code most relevant to a query.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "targetProject": { "type": "string", "description": "Absolute project path" },
+                    "query": { "type": "string" },
+                    "topK": { "type": "integer", "minimum": 1 },
+                    "pathGlob": { "type": "string" },
+                    "language": { "type": "string" },
+                    "excludeTests": { "type": "boolean" },
+                },
+                "required": ["targetProject", "query"],
+            },
+        },
+        {
+            "name": "list_task_mutations",
+            "description": "List the proposed and applied diffs of a task.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "taskId": { "type": "string" } },
+                "required": ["taskId"],
+            },
+        },
+        {
+            "name": "run_mutation_pipeline",
+            "description": "Validate a mutation in a shadow copy and run the project's checks. Applying it needs approval in the desktop app.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "mutationId": { "type": "string" },
+                    "targetProject": { "type": "string", "description": "Absolute project path" },
+                },
+                "required": ["mutationId", "targetProject"],
+            },
+        },
+    ])
+}
+
+/// Runs a tool. Tool failures are results with `isError` so the client's model sees
+/// them; only an unknown tool or malformed arguments are protocol errors.
+async fn call_tool(state: &AppState, params: Value) -> Result<Value, (i64, String)> {
+    let name = params["name"].as_str().unwrap_or_default();
+    let arguments = params
+        .get("arguments")
+        .cloned()
+        .unwrap_or_else(|| json!({}));
+    let pool = &state.db_pool;
+    let model_registry =
+        commands::current_model_registry(state).map_err(|error| (INTERNAL_ERROR, error))?;
+    let output = match name {
+        "orchestrate_objective" => encode(
+            orchestrator::orchestrate_and_persist(
+                pool,
+                &state.secret_vault,
+                &model_registry,
+                decode(arguments)?,
+            )
+            .await,
+        ),
+        "query_codebase" => {
+            let input: QueryCodebaseInput = decode(arguments)?;
+            encode(
+                search::query_codebase(
+                    pool,
+                    &input.target_project,
+                    &input.query,
+                    input.top_k.unwrap_or(5),
+                    &input.filters,
+                )
+                .await,
+            )
+        }
+        "list_task_mutations" => {
+            let input: ListTaskMutationsInput = decode(arguments)?;
+            encode(mutations::list_mutations_for_task(pool, input).await)
+        }
+        "run_mutation_pipeline" => encode(
+            mutation_pipeline::run_mutation_pipeline(
+                pool,
+                &state.secret_vault,
+                &model_registry,
+                unapproved_pipeline_input(arguments)?,
+            )
+            .await,
+        ),
+        other => return Err((INVALID_PARAMS, format!("Unknown tool: {other}"))),
+    };
+
+    let _ = metrics::record_audit_event(
+        pool,
+        "mcp_server",
+        "mcp_tool_called",
+        None,
+        Some(&json!({ "tool": name, "ok": output.is_ok() }).to_string()),
+    )
+    .await;
+    let (text, is_error) = match output {
+        Ok(text) => (text, false),
+        Err(error) => (error, true),
+    };
+    Ok(json!({ "content": [{ "type": "text", "text": text }], "isError": is_error }))
+}
+
+/// MCP clients only propose and validate mutations; approval stays with the desktop UI,
+/// so any `tier1Approved` the client sends is overridden.
+fn unapproved_pipeline_input(
+    mut arguments: Value,
+) -> Result<RunMutationPipelineInput, (i64, String)> {
+    if let Some(object) = arguments.as_object_mut() {
+        object.insert("tier1Approved".to_string(), Value::Bool(false));
+    }
+    decode(arguments)
+}
+
+fn decode<T: DeserializeOwned>(arguments: Value) -> Result<T, (i64, String)> {
+    serde_json::from_value(arguments)
+        .map_err(|error| (INVALID_PARAMS, format!("Invalid arguments: {error}")))
+}
+
+fn encode<T: Serialize>(result: Result<T, String>) -> Result<String, String> {
+    serde_json::to_string_pretty(&result?)
+        .map_err(|error| format!("Failed to encode tool result: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initialize_negotiates_a_known_protocol_version() {
+        assert_eq!(
+            initialize_result(Some("2024-11-05"))["protocolVersion"],
+            "2024-11-05"
+        );
+        assert_eq!(
+            initialize_result(Some("1999-01-01"))["protocolVersion"],
+            "2025-06-18"
+        );
+        let tools = tool_definitions();
+        let names = tools
+            .as_array()
+            .expect("tools should be a list")
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "orchestrate_objective",
+                "query_codebase",
+                "list_task_mutations",
+                "run_mutation_pipeline"
+            ]
+        );
+        assert!(tools[3]["inputSchema"]["properties"]
+            .get("tier1Approved")
+            .is_none());
+    }
+
+    #[test]
+    fn mutation_pipeline_calls_are_never_approved() {
+        let input = unapproved_pipeline_input(json!({
+            "mutationId": "mutation-1",
+            "targetProject": "/tmp/project",
+            "tier1Approved": true,
+        }))
+        .expect("arguments should decode");
+        assert_eq!(input.mutation_id, "mutation-1");
+        assert!(!input.tier1_approved);
+
+        let input = unapproved_pipeline_input(json!({
+            "mutationId": "mutation-1",
+            "targetProject": "/tmp/project",
+        }))
+        .expect("arguments without approval should decode");
+        assert!(!input.tier1_approved);
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::mcp_bridge::tool_caller::BridgeMcpConfig;
+use crate::secret_vault::SecretVault;
+
+static REGISTRY_PATH: OnceLock<PathBuf> = OnceLock::new();
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// How the bridge reaches a server: a spawned process over stdio, or a hosted server over
+/// Streamable HTTP or the older HTTP+SSE transport.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum McpTransport {
+    #[default]
+    Stdio,
+    Http,
+    Sse,
+}
+
+/// A named MCP server that plans and tool calls can reference instead of passing a
+/// command line each time.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerConfig {
+    pub name: String,
+    #[serde(default)]
+    pub transport: McpTransport,
+    /// Process to spawn for stdio servers.
+    #[serde(default)]
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Endpoint of an HTTP or SSE server.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Extra request headers for HTTP and SSE servers; secrets belong in `auth_secret`.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Secret vault entry sent as a bearer token to HTTP and SSE servers.
+    #[serde(default)]
+    pub auth_secret: Option<String>,
+    /// Tools the bridge may call on this server; empty allows all.
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+    /// Agent domains that may use this server; empty allows all.
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveMcpServerInput {
+    pub name: String,
+    pub transport: Option<McpTransport>,
+    pub command: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub env: Option<BTreeMap<String, String>>,
+    pub url: Option<String>,
+    pub headers: Option<BTreeMap<String, String>>,
+    pub auth_secret: Option<String>,
+    pub allowed_tools: Option<Vec<String>>,
+    pub allowed_domains: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveMcpServerInput {
+    pub name: String,
+}
+
+impl McpServerConfig {
+    pub fn permits_domain(&self, domain: &str) -> bool {
+        self.allowed_domains.is_empty()
+            || self
+                .allowed_domains
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(domain.trim()))
+    }
+
+    pub fn bridge_config(&self) -> BridgeMcpConfig {
+        BridgeMcpConfig {
+            transport: self.transport,
+            command: self.command.clone(),
+            args: self.args.clone(),
+            env: self.env.clone(),
+            url: self.url.clone(),
+            headers: self.headers.clone(),
+            auth_secret: self.auth_secret.clone(),
+            server_name: Some(self.name.clone()),
+            allowed_tools: self.allowed_tools.clone(),
+        }
+    }
+}
+
+pub fn set_registry_path(path: PathBuf) {
+    let _ = REGISTRY_PATH.set(path);
+}
+
+/// The `Authorization` header value for a server's `auth_secret`, read from `secret_vault`.
+pub async fn authorization_header(
+    secret_vault: &Arc<tokio::sync::Mutex<SecretVault>>,
+    auth_secret: &str,
+) -> Result<String, String> {
+    let token = secret_vault
+        .lock()
+        .await
+        .read_secret(auth_secret, "mcp_bridge")?
+        .ok_or_else(|| format!("No secret configured for '{auth_secret}'"))?;
+    Ok(format!("Bearer {}", token.trim()))
+}
+
+pub fn list_servers() -> Result<Vec<McpServerConfig>, String> {
+    read_registry(registry_path()?)
+}
+
+pub fn get_server(name: &str) -> Result<McpServerConfig, String> {
+    let name = name.trim();
+    list_servers()?
+        .into_iter()
+        .find(|server| server.name == name)
+        .ok_or_else(|| format!("MCP server '{name}' is not registered"))
+}
+
+/// Looks up `name` and checks that agents of `domain` may use it.
+pub fn resolve_for_domain(name: &str, domain: &str) -> Result<McpServerConfig, String> {
+    let server = get_server(name)?;
+    if !server.permits_domain(domain) {
+        return Err(format!(
+            "MCP server '{}' is not allowed for the {domain} domain",
+            server.name
+        ));
+    }
+    Ok(server)
+}
+
+/// Adds `input` or replaces the server of the same name.
+pub fn save_server(input: SaveMcpServerInput) -> Result<McpServerConfig, String> {
+    let server = validate(input)?;
+    let path = registry_path()?;
+    let _guard = WRITE_LOCK
+        .lock()
+        .map_err(|_| "MCP server registry lock is poisoned".to_string())?;
+    let mut servers = read_registry(path)?;
+    match servers
+        .iter_mut()
+        .find(|existing| existing.name == server.name)
+    {
+        Some(existing) => *existing = server.clone(),
+        None => servers.push(server.clone()),
+    }
+    servers.sort_by(|a, b| a.name.cmp(&b.name));
+    write_registry(path, &servers)?;
+    Ok(server)
+}
+
+/// Returns whether a server was removed.
+pub fn remove_server(input: RemoveMcpServerInput) -> Result<bool, String> {
+    let path = registry_path()?;
+    let _guard = WRITE_LOCK
+        .lock()
+        .map_err(|_| "MCP server registry lock is poisoned".to_string())?;
+    let mut servers = read_registry(path)?;
+    let before = servers.len();
+    servers.retain(|server| server.name != input.name.trim());
+    if servers.len() == before {
+        return Ok(false);
+    }
+    write_registry(path, &servers)?;
+    Ok(true)
+}
+
+fn validate(input: SaveMcpServerInput) -> Result<McpServerConfig, String> {
+    let name = input.name.trim().to_string();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-' || ch == '_')
+    {
+        return Err("MCP server name must be lowercase letters, digits, '-' or '_'".to_string());
+    }
+    let transport = input.transport.unwrap_or_default();
+    let command = input.command.unwrap_or_default().trim().to_string();
+    let url = input
+        .url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+    let has_http_url = url
+        .as_deref()
+        .is_some_and(|url| url.starts_with("https://") || url.starts_with("http://"));
+    if transport == McpTransport::Stdio && command.is_empty() {
+        return Err("command is required for stdio servers".to_string());
+    }
+    if transport != McpTransport::Stdio && !has_http_url {
+        return Err("an http(s) url is required for HTTP and SSE servers".to_string());
+    }
+    let headers = input.headers.unwrap_or_default();
+    if headers
+        .keys()
+        .any(|name| name.trim().is_empty() || name.trim().eq_ignore_ascii_case("authorization"))
+    {
+        return Err("header names must be non-empty; use authSecret for tokens".to_string());
+    }
+    let trimmed = |values: Option<Vec<String>>| {
+        values
+            .unwrap_or_default()
+            .into_iter()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect::<Vec<_>>()
+    };
+    Ok(McpServerConfig {
+        name,
+        transport,
+        command,
+        args: input.args.unwrap_or_default(),
+        env: input.env.unwrap_or_default(),
+        url,
+        headers,
+        auth_secret: input
+            .auth_secret
+            .map(|secret| secret.trim().to_ascii_lowercase())
+            .filter(|secret| !secret.is_empty()),
+        allowed_tools: trimmed(input.allowed_tools),
+        allowed_domains: trimmed(input.allowed_domains),
+        updated_at: Utc::now().timestamp(),
+    })
+}
+
+fn registry_path() -> Result<&'static PathBuf, String> {
+    REGISTRY_PATH
+        .get()
+        .ok_or_else(|| "MCP server registry is not initialized".to_string())
+}
+
+fn read_registry(path: &Path) -> Result<Vec<McpServerConfig>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read MCP server registry: {error}"))?;
+    serde_json::from_str(&raw).map_err(|error| format!("Invalid MCP server registry: {error}"))
+}
+
+fn write_registry(path: &Path, servers: &[McpServerConfig]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| format!("Failed to create MCP server registry dir: {error}"))?;
+    }
+    let encoded = serde_json::to_string_pretty(servers)
+        .map_err(|error| format!("Failed to encode MCP server registry: {error}"))?;
+    let staging = path.with_extension("json.tmp");
+    fs::write(&staging, encoded)
+        .map_err(|error| format!("Failed to write MCP server registry: {error}"))?;
+    fs::rename(&staging, path)
+        .map_err(|error| format!("Failed to write MCP server registry: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(name: &str, domains: &[&str]) -> SaveMcpServerInput {
+        SaveMcpServerInput {
+            name: name.to_string(),
+            transport: None,
+            command: Some("npx".to_string()),
+            args: Some(vec!["-y".to_string(), "@scope/server".to_string()]),
+            env: None,
+            url: None,
+            headers: None,
+            auth_secret: None,
+            allowed_tools: Some(vec![" read_file ".to_string(), String::new()]),
+            allowed_domains: Some(domains.iter().map(|domain| domain.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn registry_round_trips_and_scopes_servers_to_domains() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("mcp_servers.json");
+
+        assert!(read_registry(&path).expect("empty registry").is_empty());
+        let server = validate(input("fs-tools", &["frontend"])).expect("valid server");
+        assert_eq!(server.allowed_tools, ["read_file"]);
+        write_registry(&path, &[server.clone()]).expect("write registry");
+        assert_eq!(
+            read_registry(&path).expect("read registry"),
+            [server.clone()]
+        );
+
+        assert!(server.permits_domain("Frontend"));
+        assert!(!server.permits_domain("backend"));
+        assert!(validate(input("fs-tools", &[]))
+            .expect("valid server")
+            .permits_domain("backend"));
+        assert!(validate(input("FS Tools", &[])).is_err());
+    }
+
+    #[test]
+    fn hosted_servers_need_a_url_and_keep_tokens_out_of_headers() {
+        let hosted = |url: Option<&str>, header: &str| SaveMcpServerInput {
+            transport: Some(McpTransport::Http),
+            command: None,
+            url: url.map(str::to_string),
+            headers: Some(BTreeMap::from([(header.to_string(), "1".to_string())])),
+            auth_secret: Some(" GitHub-MCP ".to_string()),
+            ..input("github", &[])
+        };
+
+        let server = validate(hosted(Some("https://api.example.com/mcp"), "X-Version"))
+            .expect("valid hosted server");
+        assert_eq!(server.auth_secret.as_deref(), Some("github-mcp"));
+        assert_eq!(server.bridge_config().transport, McpTransport::Http);
+        assert!(validate(hosted(None, "X-Version")).is_err());
+        assert!(validate(hosted(Some("ftp://example.com"), "X-Version")).is_err());
+        assert!(validate(hosted(Some("https://example.com"), "Authorization")).is_err());
+        assert!(validate(SaveMcpServerInput {
+            command: None,
+            ..input("fs", &[])
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,457 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use sqlx::SqlitePool;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+
+use crate::db::metrics;
+use crate::llm_rate_limit;
+use crate::task_runtime;
+
+/// Upper bounds in seconds of the pipeline step duration histogram buckets.
+const STEP_BUCKETS: [f64; 11] = [
+    0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0,
+];
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Durations of mutation pipeline steps since startup, keyed by step name.
+static PIPELINE_STEPS: Mutex<BTreeMap<&'static str, StepHistogram>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Default)]
+struct StepHistogram {
+    /// Non-cumulative counts per bucket of [`STEP_BUCKETS`], then one for larger values.
+    buckets: [u64; STEP_BUCKETS.len() + 1],
+    count: u64,
+    sum_secs: f64,
+}
+
+/// Records how long a pipeline step ran when dropped, so early returns are timed too.
+pub struct StepTimer {
+    step: &'static str,
+    started: Instant,
+}
+
+impl StepTimer {
+    pub fn start(step: &'static str) -> Self {
+        Self {
+            step,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Drop for StepTimer {
+    fn drop(&mut self) {
+        observe_pipeline_step(self.step, self.started.elapsed());
+    }
+}
+
+pub fn observe_pipeline_step(step: &'static str, duration: Duration) {
+    let secs = duration.as_secs_f64();
+    let mut steps = PIPELINE_STEPS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let histogram = steps.entry(step).or_default();
+    let bucket = STEP_BUCKETS
+        .iter()
+        .position(|bound| secs <= *bound)
+        .unwrap_or(STEP_BUCKETS.len());
+    histogram.buckets[bucket] += 1;
+    histogram.count += 1;
+    histogram.sum_secs += secs;
+}
+
+/// Serves [`render_metrics`] from `pool` at `GET /metrics` on `127.0.0.1:port` for
+/// Prometheus to scrape. Only loopback is bound; put a proxy in front to scrape from
+/// elsewhere. A port that cannot be bound is recorded as a `metrics_endpoint_unavailable`
+/// audit event in `audit_pool`.
+pub fn spawn_metrics_server(pool: SqlitePool, audit_pool: SqlitePool, port: u16) {
+    tauri::async_runtime::spawn(async move {
+        let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => listener,
+            Err(error) => {
+                let details = format!("port={port} error={error}");
+                let _ = metrics::record_audit_event(
+                    &audit_pool,
+                    "metrics_server",
+                    "metrics_endpoint_unavailable",
+                    None,
+                    Some(&details),
+                )
+                .await;
+                return;
+            }
+        };
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                continue;
+            };
+            let pool = pool.clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = timeout(REQUEST_TIMEOUT, serve_connection(&pool, stream)).await;
+            });
+        }
+    });
+}
+
+async fn serve_connection(pool: &SqlitePool, mut stream: TcpStream) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut chunk = [0_u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_BYTES {
+            break;
+        }
+        request.extend_from_slice(&chunk[..read]);
+    }
+    let request_line = String::from_utf8_lossy(&request)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next(), parts.next());
+
+    let (status, content_type, body) = match (method, path) {
+        (Some("GET"), Some("/metrics")) => match render_metrics(pool).await {
+            Ok(body) => ("200 OK", "text/plain; version=0.0.4; charset=utf-8", body),
+            Err(error) => ("500 Internal Server Error", "text/plain", error),
+        },
+        (Some("GET"), Some(_)) => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Only GET /metrics is served\n".to_string(),
+        ),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Current metrics in the Prometheus text format. Token, cost and mutation totals are
+/// read from the database, so they drop when telemetry is archived; Prometheus treats
+/// that as a counter reset.
+pub async fn render_metrics(pool: &SqlitePool) -> Result<String, String> {
+    let mut out = String::new();
+
+    let active_runs = sqlx::query_as::<_, (Option<i64>, String, i64)>(
+        r#"
+        SELECT tier, status, COUNT(*)
+        FROM aop_agent_runs
+        WHERE status IN ('pending', 'executing', 'paused')
+        GROUP BY tier, status
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to count active runs: {error}"))?;
+    header(
+        &mut out,
+        "aop_active_runs",
+        "gauge",
+        "Agent runs not yet finished.",
+    );
+    for (tier, status, count) in active_runs {
+        let tier = tier.map(|tier| tier.to_string()).unwrap_or_default();
+        sample(
+            &mut out,
+            "aop_active_runs",
+            &[("tier", &tier), ("status", &status)],
+            count as f64,
+        );
+    }
+
+    let usage = sqlx::query_as::<_, (Option<String>, Option<String>, i64, i64, f64)>(
+        r#"
+        SELECT provider, model_id,
+               COALESCE(SUM(tokens_in), 0), COALESCE(SUM(tokens_out), 0),
+               COALESCE(SUM(cost_usd), 0.0)
+        FROM aop_agent_events
+        WHERE cost_usd IS NOT NULL OR tokens_in IS NOT NULL OR tokens_out IS NOT NULL
+        GROUP BY provider, model_id
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to sum token usage: {error}"))?;
+    header(
+        &mut out,
+        "aop_tokens_total",
+        "counter",
+        "Tokens consumed by model calls.",
+    );
+    for (provider, model_id, tokens_in, tokens_out, _) in &usage {
+        let provider = provider.as_deref().unwrap_or("unknown");
+        let model = model_id.as_deref().unwrap_or("unknown");
+        for (direction, tokens) in [("in", tokens_in), ("out", tokens_out)] {
+            sample(
+                &mut out,
+                "aop_tokens_total",
+                &[
+                    ("provider", provider),
+                    ("model", model),
+                    ("direction", direction),
+                ],
+                *tokens as f64,
+            );
+        }
+    }
+    header(
+        &mut out,
+        "aop_cost_usd_total",
+        "counter",
+        "Spend on model calls in US dollars.",
+    );
+    for (provider, model_id, _, _, cost_usd) in &usage {
+        sample(
+            &mut out,
+            "aop_cost_usd_total",
+            &[
+                ("provider", provider.as_deref().unwrap_or("unknown")),
+                ("model", model_id.as_deref().unwrap_or("unknown")),
+            ],
+            *cost_usd,
+        );
+    }
+
+    let mutations = sqlx::query_as::<_, (String, i64)>(
+        r#"
+        SELECT status, COUNT(*)
+        FROM aop_mutations
+        WHERE status IN ('applied', 'rejected')
+        GROUP BY status
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to count mutations: {error}"))?;
+    header(
+        &mut out,
+        "aop_mutations_total",
+        "counter",
+        "Mutations applied to the target project or rejected.",
+    );
+    for (status, count) in mutations {
+        sample(
+            &mut out,
+            "aop_mutations_total",
+            &[("status", &status)],
+            count as f64,
+        );
+    }
+
+    let health = sqlx::query_as::<_, (String, String, i64, i64, i64)>(
+        r#"
+        SELECT provider, model_id, success_calls, failed_calls, timeout_calls
+        FROM aop_model_health
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to read model health: {error}"))?;
+    header(
+        &mut out,
+        "aop_model_calls_total",
+        "counter",
+        "Model calls by outcome; timeouts are also counted as failures.",
+    );
+    for (provider, model, success, failed, timed_out) in &health {
+        for (outcome, count) in [
+            ("success", success),
+            ("failure", failed),
+            ("timeout", timed_out),
+        ] {
+            sample(
+                &mut out,
+                "aop_model_calls_total",
+                &[
+                    ("provider", provider),
+                    ("model", model),
+                    ("outcome", outcome),
+                ],
+                *count as f64,
+            );
+        }
+    }
+    header(
+        &mut out,
+        "aop_model_error_rate",
+        "gauge",
+        "Share of a model's calls that failed.",
+    );
+    for (provider, model, success, failed, _) in &health {
+        let total = success + failed;
+        if total > 0 {
+            sample(
+                &mut out,
+                "aop_model_error_rate",
+                &[("provider", provider), ("model", model)],
+                *failed as f64 / total as f64,
+            );
+        }
+    }
+
+    header(
+        &mut out,
+        "aop_provider_queued_requests",
+        "gauge",
+        "Model requests waiting for a provider rate-limit slot.",
+    );
+    for queue in llm_rate_limit::metrics() {
+        sample(
+            &mut out,
+            "aop_provider_queued_requests",
+            &[("provider", &queue.provider)],
+            f64::from(queue.queued),
+        );
+    }
+
+    header(
+        &mut out,
+        "aop_execution_queued",
+        "gauge",
+        "Orchestrations, specialists and pipeline runs waiting for an execution slot.",
+    );
+    for lane in task_runtime::execution_queue().lanes {
+        sample(
+            &mut out,
+            "aop_execution_queued",
+            &[("lane", lane.lane.as_str())],
+            f64::from(lane.queued),
+        );
+    }
+
+    render_step_histograms(&mut out);
+    Ok(out)
+}
+
+fn render_step_histograms(out: &mut String) {
+    let steps = PIPELINE_STEPS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone();
+    header(
+        out,
+        "aop_pipeline_step_duration_seconds",
+        "histogram",
+        "Duration of mutation pipeline steps since startup.",
+    );
+    for (step, histogram) in steps {
+        let mut cumulative = 0;
+        for (index, count) in histogram.buckets.iter().enumerate() {
+            cumulative += count;
+            let bound = STEP_BUCKETS
+                .get(index)
+                .map(|bound| bound.to_string())
+                .unwrap_or_else(|| "+Inf".to_string());
+            sample(
+                out,
+                "aop_pipeline_step_duration_seconds_bucket",
+                &[("step", step), ("le", &bound)],
+                cumulative as f64,
+            );
+        }
+        sample(
+            out,
+            "aop_pipeline_step_duration_seconds_sum",
+            &[("step", step)],
+            histogram.sum_secs,
+        );
+        sample(
+            out,
+            "aop_pipeline_step_duration_seconds_count",
+            &[("step", step)],
+            histogram.count as f64,
+        );
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    let labels = labels
+        .iter()
+        .map(|(key, value)| format!("{key}=\"{}\"", escape_label(value)))
+        .collect::<Vec<_>>()
+        .join(",");
+    let _ = writeln!(out, "{name}{{{labels}}} {value}");
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db;
+    use crate::db::tasks::{self, CreateTaskInput};
+    use crate::db::telemetry;
+
+    #[tokio::test]
+    async fn renders_usage_runs_and_step_histograms_in_text_format() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+        let task = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 1,
+                domain: "platform".to_string(),
+                objective: "metrics".to_string(),
+                token_budget: 5000,
+            },
+        )
+        .await
+        .expect("task should be created");
+        telemetry::record_task_activity_event(
+            &pool,
+            "tier1_orchestrator",
+            "model_call",
+            Some(task.id.as_str()),
+            Some("model=openai/gpt-5 tokensIn=120 tokensOut=30 costUsd=0.002500"),
+        )
+        .await
+        .expect("telemetry should be recorded");
+        observe_pipeline_step("metrics_test_step", Duration::from_millis(700));
+
+        let text = render_metrics(&pool).await.expect("metrics should render");
+        assert!(text.contains("# TYPE aop_tokens_total counter"));
+        assert!(text.contains(
+            "aop_tokens_total{provider=\"openai\",model=\"gpt-5\",direction=\"in\"} 120"
+        ));
+        assert!(text.contains("aop_cost_usd_total{provider=\"openai\",model=\"gpt-5\"} 0.0025"));
+        assert!(text.contains("aop_active_runs{tier=\"1\",status=\"executing\"} 1"));
+        assert!(text.contains(
+            "aop_pipeline_step_duration_seconds_bucket{step=\"metrics_test_step\",le=\"0.5\"} 0"
+        ));
+        assert!(text.contains(
+            "aop_pipeline_step_duration_seconds_bucket{step=\"metrics_test_step\",le=\"1\"} 1"
+        ));
+        assert_eq!(escape_label("a\"b\\c"), "a\\\"b\\\\c");
+    }
+}
@@ -0,0 +1,136 @@
+pub const POLICY_UCB: &str = "ucb";
+pub const POLICY_EPSILON_GREEDY: &str = "epsilon_greedy";
+/// Health scores only, as before adaptive selection existed.
+pub const POLICY_STATIC: &str = "static";
+
+/// Observations the health prior is worth, so one lucky mutation cannot flip routing.
+const PRIOR_WEIGHT: f64 = 3.0;
+/// Scales the UCB exploration bonus; rewards are in 0..=1.
+const UCB_EXPLORATION: f64 = 0.35;
+const DEFAULT_EPSILON_PERCENT: u32 = 10;
+
+/// One candidate: its health score, used as a prior, plus the applied (1) and rejected
+/// (0) mutations it produced in this routing segment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArmEstimate {
+    pub prior: f64,
+    pub pulls: i64,
+    pub reward_sum: f64,
+}
+
+impl ArmEstimate {
+    /// Mean reward with the prior counted as `PRIOR_WEIGHT` observations.
+    pub fn mean(&self) -> f64 {
+        let pulls = self.pulls.max(0) as f64;
+        (self.prior * PRIOR_WEIGHT + self.reward_sum) / (PRIOR_WEIGHT + pulls)
+    }
+}
+
+/// The choice made by [`rank`]: candidate indexes best first with the value each was
+/// ranked by, and whether an epsilon-greedy exploration pick was taken.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ranking {
+    pub order: Vec<(usize, f64)>,
+    pub explored: bool,
+}
+
+pub fn normalize_policy(value: &str) -> Option<&'static str> {
+    match value.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+        "ucb" | "ucb1" => Some(POLICY_UCB),
+        "epsilon_greedy" | "epsilon" | "egreedy" => Some(POLICY_EPSILON_GREEDY),
+        "static" | "off" | "none" => Some(POLICY_STATIC),
+        _ => None,
+    }
+}
+
+/// `AOP_MODEL_SELECTION_POLICY`, defaulting to UCB.
+pub fn policy_from_env() -> &'static str {
+    std::env::var("AOP_MODEL_SELECTION_POLICY")
+        .ok()
+        .and_then(|value| normalize_policy(&value))
+        .unwrap_or(POLICY_UCB)
+}
+
+/// Share of epsilon-greedy selections that pick a random candidate.
+pub fn epsilon_from_env() -> f64 {
+    let percent = std::env::var("AOP_BANDIT_EPSILON_PERCENT")
+        .ok()
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .unwrap_or(DEFAULT_EPSILON_PERCENT)
+        .min(100);
+    f64::from(percent) / 100.0
+}
+
+/// Orders `arms` under `policy`. `roll` in `0..1` and `pick` are the random draws for
+/// epsilon-greedy, passed in so ranking stays deterministic for a given draw.
+pub fn rank(policy: &str, arms: &[ArmEstimate], epsilon: f64, roll: f64, pick: usize) -> Ranking {
+    let total_pulls: i64 = arms.iter().map(|arm| arm.pulls.max(0)).sum();
+    let value = |arm: &ArmEstimate| match policy {
+        POLICY_STATIC => arm.prior,
+        POLICY_UCB => {
+            let bonus = ((total_pulls as f64 + 1.0).ln() / (arm.pulls.max(0) as f64 + 1.0)).sqrt();
+            arm.mean() + UCB_EXPLORATION * bonus
+        }
+        _ => arm.mean(),
+    };
+    let mut order: Vec<(usize, f64)> = arms
+        .iter()
+        .enumerate()
+        .map(|(index, arm)| (index, value(arm)))
+        .collect();
+    order.sort_by(|left, right| {
+        right
+            .1
+            .partial_cmp(&left.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| left.0.cmp(&right.0))
+    });
+
+    let explored = policy == POLICY_EPSILON_GREEDY && order.len() > 1 && roll < epsilon;
+    if explored {
+        let chosen = order.remove(pick % order.len());
+        order.insert(0, chosen);
+    }
+    Ranking { order, explored }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arm(prior: f64, pulls: i64, reward_sum: f64) -> ArmEstimate {
+        ArmEstimate {
+            prior,
+            pulls,
+            reward_sum,
+        }
+    }
+
+    #[test]
+    fn observed_outcomes_override_the_health_prior() {
+        // The healthier model keeps getting its mutations rejected.
+        let arms = [arm(0.80, 12, 2.0), arm(0.65, 10, 9.0)];
+        let static_rank = rank(POLICY_STATIC, &arms, 0.0, 1.0, 0);
+        assert_eq!(static_rank.order[0].0, 0);
+        let greedy = rank(POLICY_EPSILON_GREEDY, &arms, 0.1, 0.5, 0);
+        assert_eq!(greedy.order[0].0, 1);
+        assert!(!greedy.explored);
+        let ucb = rank(POLICY_UCB, &arms, 0.0, 1.0, 0);
+        assert_eq!(ucb.order[0].0, 1);
+    }
+
+    #[test]
+    fn ucb_tries_unexplored_models_and_epsilon_explores_on_low_rolls() {
+        let arms = [arm(0.75, 40, 30.0), arm(0.70, 0, 0.0)];
+        assert_eq!(rank(POLICY_UCB, &arms, 0.0, 1.0, 0).order[0].0, 1);
+
+        let explored = rank(POLICY_EPSILON_GREEDY, &arms, 0.2, 0.05, 1);
+        assert!(explored.explored);
+        assert_eq!(explored.order[0].0, 1);
+        assert_eq!(
+            normalize_policy("Epsilon-Greedy"),
+            Some(POLICY_EPSILON_GREEDY)
+        );
+        assert_eq!(normalize_policy("thompson"), None);
+    }
+}
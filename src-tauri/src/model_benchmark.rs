@@ -0,0 +1,531 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use sqlx::SqlitePool;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::agents::specialist::{self, SpecialistTask};
+use crate::db::mutations::{self, CreateMutationInput, MutationStatus};
+use crate::db::tasks::{self, CreateTaskRecordInput, TaskStatus};
+use crate::db::telemetry::{self, ModelHealthRecord};
+use crate::llm_adapter;
+use crate::model_intelligence;
+use crate::model_registry::{ModelRegistry, ModelSelection};
+use crate::mutation_pipeline::{self, RunMutationPipelineInput};
+use crate::secret_vault::SecretVault;
+
+const BENCHMARK_PERSONA: &str = "benchmark_specialist";
+const BENCHMARK_TOKEN_BUDGET: u32 = 4_000;
+const GIT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Share of the gap between a model's quality score and its benchmark score closed per run.
+const QUALITY_BLEND: f64 = 0.5;
+
+/// A small project with one objective and markers that a good change contains.
+struct BenchmarkFixture {
+    name: &'static str,
+    domain: &'static str,
+    objective: &'static str,
+    file_path: &'static str,
+    content: &'static str,
+    expected: &'static [&'static str],
+    forbidden: &'static [&'static str],
+    /// Changed lines a focused change needs; larger diffs lose diff quality.
+    max_changed_lines: usize,
+}
+
+const FIXTURES: &[BenchmarkFixture] = &[
+    BenchmarkFixture {
+        name: "js_rename_constant",
+        domain: "frontend",
+        objective: "In src/retry.js, rename the constant MAX_RETRY to MAX_RETRIES and update \
+                    every use of it.",
+        file_path: "src/retry.js",
+        content: "const MAX_RETRY = 3;\n\nexport function shouldRetry(attempt) {\n  return \
+                  attempt < MAX_RETRY;\n}\n\nexport function remainingRetries(attempt) {\n  \
+                  return Math.max(0, MAX_RETRY - attempt);\n}\n",
+        expected: &[
+            "const MAX_RETRIES = 3",
+            "attempt < MAX_RETRIES",
+            "MAX_RETRIES - attempt",
+        ],
+        forbidden: &["MAX_RETRY =", "MAX_RETRY;", "MAX_RETRY -"],
+        max_changed_lines: 6,
+    },
+    BenchmarkFixture {
+        name: "py_empty_guard",
+        domain: "backend",
+        objective: "In calc.py, make average return 0.0 for an empty list instead of raising \
+                    ZeroDivisionError.",
+        file_path: "calc.py",
+        content: "def average(values):\n    return sum(values) / len(values)\n",
+        expected: &["def average(values)", "return 0.0", "sum(values)"],
+        forbidden: &[],
+        max_changed_lines: 4,
+    },
+    BenchmarkFixture {
+        name: "rust_doc_comment",
+        domain: "docs",
+        objective: "In src/lib.rs, add a /// doc comment to clamp_percent describing what it \
+                    returns. Do not change the function body.",
+        file_path: "src/lib.rs",
+        content: "pub fn clamp_percent(value: i32) -> i32 {\n    value.clamp(0, 100)\n}\n",
+        expected: &[
+            "///",
+            "pub fn clamp_percent(value: i32) -> i32",
+            "value.clamp(0, 100)",
+        ],
+        forbidden: &[],
+        max_changed_lines: 3,
+    },
+];
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkModelRef {
+    pub provider: String,
+    pub model_id: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkModelsInput {
+    /// Models to benchmark; every routed tier-3 model when empty.
+    #[serde(default)]
+    pub models: Vec<BenchmarkModelRef>,
+    /// Fixture names to run; all bundled fixtures when empty.
+    #[serde(default)]
+    pub fixtures: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkRun {
+    pub provider: String,
+    pub model_id: String,
+    pub fixture: String,
+    pub applied: bool,
+    /// Share of the fixture's markers the change got right, reduced for unfocused diffs.
+    pub diff_quality: f64,
+    pub latency_ms: i64,
+    pub tokens_used: u32,
+    pub cost_usd: Option<f64>,
+    pub score: f64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelBenchmarkSummary {
+    pub provider: String,
+    pub model_id: String,
+    pub runs: usize,
+    pub apply_rate: f64,
+    pub avg_diff_quality: f64,
+    pub avg_latency_ms: i64,
+    pub total_cost_usd: f64,
+    pub score: f64,
+    /// Model health after the benchmark was written into it.
+    pub health: Option<ModelHealthRecord>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkModelsResult {
+    pub runs: Vec<BenchmarkRun>,
+    pub summaries: Vec<ModelBenchmarkSummary>,
+}
+
+/// Names of the bundled fixtures.
+fn fixture_names() -> Vec<&'static str> {
+    FIXTURES.iter().map(|fixture| fixture.name).collect()
+}
+
+/// Runs every selected fixture through every selected model, end to end: the specialist
+/// proposes a change in a scratch project and the mutation pipeline validates and applies
+/// it. Each model's results are written into its health so routing starts from data.
+pub async fn benchmark_models(
+    pool: &SqlitePool,
+    secret_vault: &Arc<Mutex<SecretVault>>,
+    model_registry: &ModelRegistry,
+    input: BenchmarkModelsInput,
+) -> Result<BenchmarkModelsResult, String> {
+    if !specialist::remote_model_adapter_enabled() {
+        return Err(
+            "Model calls are disabled (AOP_MODEL_ADAPTER_ENABLED); benchmarks need them."
+                .to_string(),
+        );
+    }
+    let fixtures = selected_fixtures(&input.fixtures)?;
+    let models = selected_models(model_registry, &input.models)?;
+
+    let mut runs = Vec::new();
+    let mut summaries = Vec::new();
+    for model in &models {
+        let model_runs_start = runs.len();
+        for &fixture in &fixtures {
+            runs.push(run_fixture(pool, secret_vault, model_registry, model, fixture).await);
+        }
+        let model_runs = &runs[model_runs_start..];
+        for run in model_runs {
+            model_intelligence::record_model_call_outcome(
+                pool,
+                &run.provider,
+                &run.model_id,
+                run.applied,
+                Some(run.latency_ms),
+                run.cost_usd,
+                run.error.clone(),
+            )
+            .await;
+        }
+        summaries.push(summarize(pool, model, model_runs).await?);
+    }
+    Ok(BenchmarkModelsResult { runs, summaries })
+}
+
+fn selected_fixtures(names: &[String]) -> Result<Vec<&'static BenchmarkFixture>, String> {
+    if names.is_empty() {
+        return Ok(FIXTURES.iter().collect());
+    }
+    names
+        .iter()
+        .map(|name| {
+            FIXTURES
+                .iter()
+                .find(|fixture| fixture.name.eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| {
+                    format!(
+                        "Unknown benchmark fixture '{}'. Available: {}",
+                        name.trim(),
+                        fixture_names().join(", ")
+                    )
+                })
+        })
+        .collect()
+}
+
+fn selected_models(
+    model_registry: &ModelRegistry,
+    requested: &[BenchmarkModelRef],
+) -> Result<Vec<ModelSelection>, String> {
+    let refs = if requested.is_empty() {
+        let mut seen = HashSet::new();
+        model_registry
+            .routed_profiles()
+            .into_iter()
+            .filter(|(slot, profile)| {
+                slot == "tier 3" && llm_adapter::provider_family(&profile.provider).is_some()
+            })
+            .map(|(_, profile)| BenchmarkModelRef {
+                provider: profile.provider.clone(),
+                model_id: profile.model_id.clone(),
+            })
+            .filter(|model| {
+                seen.insert((
+                    model.provider.to_ascii_lowercase(),
+                    model.model_id.to_ascii_lowercase(),
+                ))
+            })
+            .collect()
+    } else {
+        requested.to_vec()
+    };
+    if refs.is_empty() {
+        return Err("No tier 3 models with a provider adapter to benchmark".to_string());
+    }
+
+    refs.into_iter()
+        .map(|model| {
+            let profile = model_registry
+                .known_profile(&model.provider, &model.model_id)
+                .ok_or_else(|| {
+                    format!(
+                        "Model {}/{} is not in models.json",
+                        model.provider, model.model_id
+                    )
+                })?;
+            Ok(ModelSelection {
+                tier: 3,
+                persona: Some(BENCHMARK_PERSONA.to_string()),
+                generation: model_registry.generation_for(3, None, profile),
+                provider: profile.provider.clone(),
+                model_id: profile.model_id.clone(),
+                source: "benchmark".to_string(),
+                endpoint: profile.endpoint.clone(),
+            })
+        })
+        .collect()
+}
+
+async fn run_fixture(
+    pool: &SqlitePool,
+    secret_vault: &Arc<Mutex<SecretVault>>,
+    model_registry: &ModelRegistry,
+    model: &ModelSelection,
+    fixture: &'static BenchmarkFixture,
+) -> BenchmarkRun {
+    let mut run = BenchmarkRun {
+        provider: model.provider.clone(),
+        model_id: model.model_id.clone(),
+        fixture: fixture.name.to_string(),
+        applied: false,
+        diff_quality: 0.0,
+        latency_ms: 0,
+        tokens_used: 0,
+        cost_usd: None,
+        score: 0.0,
+        error: None,
+    };
+    let project = std::env::temp_dir().join(format!("aop_benchmark_{}", Uuid::new_v4()));
+    if let Err(error) = execute_fixture(
+        pool,
+        secret_vault,
+        model_registry,
+        model,
+        fixture,
+        &project,
+        &mut run,
+    )
+    .await
+    {
+        run.error = Some(error);
+    }
+    let _ = fs::remove_dir_all(&project);
+    run.score = run_score(run.applied, run.diff_quality);
+    run
+}
+
+async fn execute_fixture(
+    pool: &SqlitePool,
+    secret_vault: &Arc<Mutex<SecretVault>>,
+    model_registry: &ModelRegistry,
+    model: &ModelSelection,
+    fixture: &'static BenchmarkFixture,
+    project: &Path,
+    run: &mut BenchmarkRun,
+) -> Result<(), String> {
+    create_fixture_project(project, fixture).await?;
+    let target_project = project.to_string_lossy().to_string();
+
+    let task = tasks::create_task_record(
+        pool,
+        CreateTaskRecordInput {
+            parent_id: None,
+            tier: 3,
+            domain: fixture.domain.to_string(),
+            objective: format!("[benchmark {}] {}", fixture.name, fixture.objective),
+            token_budget: i64::from(BENCHMARK_TOKEN_BUDGET),
+            risk_factor: 0.1,
+            status: TaskStatus::Executing,
+            target_files: Some(
+                serde_json::to_string(&[fixture.file_path])
+                    .map_err(|error| format!("Failed to encode target files: {error}"))?,
+            ),
+            priority: 0,
+        },
+    )
+    .await?;
+
+    let specialist_task = SpecialistTask {
+        task_id: task.id.clone(),
+        parent_id: task.id.clone(),
+        tier: 3,
+        persona: BENCHMARK_PERSONA.to_string(),
+        objective: fixture.objective.to_string(),
+        token_budget: BENCHMARK_TOKEN_BUDGET,
+        target_files: vec![fixture.file_path.to_string()],
+        code_context: Vec::new(),
+        file_tree: vec![fixture.file_path.to_string()],
+        constraints: vec!["keep diff focused to task objective".to_string()],
+        model_provider: Some(model.provider.clone()),
+        model_id: Some(model.model_id.clone()),
+        model_endpoint: model.endpoint.clone(),
+        model_generation: model.generation.clone(),
+        model_fallbacks: Vec::new(),
+        target_project: Some(target_project.clone()),
+        attachments: Vec::new(),
+        mcp_tools: Vec::new(),
+    };
+    let content = fixture.content;
+    let started_at = Instant::now();
+    let benchmark_pool = pool.clone();
+    let proposal = tokio::task::spawn_blocking(move || {
+        specialist::run_specialist_task(&specialist_task, Some(content), Some(&benchmark_pool))
+    })
+    .await
+    .map_err(|error| format!("Benchmark specialist task failed: {error}"))?;
+    run.latency_ms = started_at.elapsed().as_millis() as i64;
+    let proposal = proposal?;
+    run.tokens_used = proposal.tokens_used;
+    run.cost_usd = proposal.cost_usd;
+
+    let mutation = mutations::create_mutation(
+        pool,
+        CreateMutationInput {
+            task_id: task.id.clone(),
+            agent_uid: proposal.agent_uid.clone(),
+            file_path: proposal.file_path.clone(),
+            diff_content: proposal.diff_content.clone(),
+            intent_description: Some(proposal.intent_description.clone()),
+            intent_hash: Some(proposal.intent_hash.clone()),
+            confidence: proposal.confidence as f64,
+            parent_mutation_id: None,
+            modified_content: proposal.modified_content.clone(),
+            base_checksum: proposal.base_checksum.clone(),
+        },
+    )
+    .await?;
+    let result = mutation_pipeline::run_mutation_pipeline(
+        pool,
+        secret_vault,
+        model_registry,
+        RunMutationPipelineInput {
+            mutation_id: mutation.id,
+            target_project: target_project.clone(),
+            tier1_approved: true,
+            ci_command: None,
+            ci_args: None,
+            max_repair_attempts: Some(0),
+            use_task_branch: Some(false),
+            approved_by: None,
+            limits: None,
+            accept_partial_alignment: None,
+            mcp_server: None,
+        },
+    )
+    .await?;
+
+    run.applied = result.mutation.status == MutationStatus::Applied.as_str();
+    let changed = if run.applied {
+        mutation_pipeline::read_target_file_content(&target_project, fixture.file_path)
+    } else {
+        proposal.modified_content
+    };
+    run.diff_quality = diff_quality(fixture, changed.as_deref().unwrap_or(fixture.content));
+    if !run.applied {
+        run.error = result.mutation.rejection_reason;
+    }
+    Ok(())
+}
+
+/// Writes the fixture into a fresh git repository with one commit, as the pipeline applies
+/// and commits into git projects only.
+async fn create_fixture_project(project: &Path, fixture: &BenchmarkFixture) -> Result<(), String> {
+    let file = project.join(fixture.file_path);
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| format!("Failed to create benchmark project: {error}"))?;
+    }
+    fs::write(&file, fixture.content)
+        .map_err(|error| format!("Failed to write benchmark fixture: {error}"))?;
+
+    let commands: [&[&str]; 5] = [
+        &["init", "-q"],
+        &["config", "user.name", "AOP Benchmark"],
+        &["config", "user.email", "benchmark@aop.local"],
+        &["add", "-A"],
+        &["commit", "-q", "-m", "Benchmark fixture"],
+    ];
+    for args in commands {
+        mutation_pipeline::run_command(project, "git", args, GIT_TIMEOUT).await?;
+    }
+    Ok(())
+}
+
+/// 70% for the markers the change got right, 30% for keeping the diff within the
+/// fixture's budget of changed lines. An untouched file scores zero.
+fn diff_quality(fixture: &BenchmarkFixture, changed: &str) -> f64 {
+    let changed_lines = TextDiff::from_lines(fixture.content, changed)
+        .iter_all_changes()
+        .filter(|change| change.tag() != ChangeTag::Equal)
+        .count();
+    if changed_lines == 0 {
+        return 0.0;
+    }
+    let checks = fixture.expected.len() + fixture.forbidden.len();
+    let passed = fixture
+        .expected
+        .iter()
+        .filter(|marker| changed.contains(*marker))
+        .count()
+        + fixture
+            .forbidden
+            .iter()
+            .filter(|marker| !changed.contains(*marker))
+            .count();
+    let markers = passed as f64 / checks.max(1) as f64;
+    let focus = (fixture.max_changed_lines as f64 / changed_lines as f64).min(1.0);
+    0.7 * markers + 0.3 * focus
+}
+
+fn run_score(applied: bool, diff_quality: f64) -> f64 {
+    let applied = if applied { 1.0 } else { 0.0 };
+    0.6 * applied + 0.4 * diff_quality
+}
+
+/// Aggregates a model's runs and moves its quality score toward the benchmark score.
+async fn summarize(
+    pool: &SqlitePool,
+    model: &ModelSelection,
+    runs: &[BenchmarkRun],
+) -> Result<ModelBenchmarkSummary, String> {
+    let count = runs.len().max(1) as f64;
+    let mean = |value: fn(&BenchmarkRun) -> f64| runs.iter().map(value).sum::<f64>() / count;
+    let score = mean(|run| run.score);
+
+    let health = match telemetry::get_model_health(pool, &model.provider, &model.model_id).await? {
+        Some(current) => {
+            let delta = (score - current.quality_score) * QUALITY_BLEND;
+            telemetry::adjust_model_quality(pool, &model.provider, &model.model_id, delta).await?
+        }
+        None => None,
+    };
+
+    Ok(ModelBenchmarkSummary {
+        provider: model.provider.clone(),
+        model_id: model.model_id.clone(),
+        runs: runs.len(),
+        apply_rate: mean(|run| if run.applied { 1.0 } else { 0.0 }),
+        avg_diff_quality: mean(|run| run.diff_quality),
+        avg_latency_ms: mean(|run| run.latency_ms as f64).round() as i64,
+        total_cost_usd: runs.iter().filter_map(|run| run.cost_usd).sum(),
+        score,
+        health,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_quality_rewards_correct_focused_changes() {
+        let fixture = &FIXTURES[0];
+        let renamed = fixture.content.replace("MAX_RETRY", "MAX_RETRIES");
+        assert!((diff_quality(fixture, &renamed) - 1.0).abs() < f64::EPSILON);
+
+        let partial = fixture.content.replacen("MAX_RETRY", "MAX_RETRIES", 1);
+        let partial_quality = diff_quality(fixture, &partial);
+        assert!(partial_quality > 0.3 && partial_quality < 0.8);
+
+        assert_eq!(diff_quality(fixture, fixture.content), 0.0);
+        assert!(run_score(true, 1.0) > run_score(false, 1.0));
+    }
+
+    #[test]
+    fn selects_fixtures_by_name() {
+        assert_eq!(
+            selected_fixtures(&[]).expect("all fixtures").len(),
+            FIXTURES.len()
+        );
+        let picked = selected_fixtures(&["PY_EMPTY_GUARD".to_string()]).expect("known fixture");
+        assert_eq!(picked[0].name, "py_empty_guard");
+        assert!(selected_fixtures(&["missing".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+use crate::llm_adapter::{self, ProviderFamily};
+use crate::model_registry::{
+    GenerationParams, ModelEndpoint, ModelPricing, ModelProfile, ModelRegistry,
+};
+
+const DISCOVERY_TIMEOUT_SECS: u64 = 15;
+const ANTHROPIC_MODELS_URL: &str = "https://api.anthropic.com/v1/models?limit=1000";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// Names the Claude Code CLI resolves itself; they never appear in the API's model list.
+const CLAUDE_CODE_MODEL_ALIASES: &[&str] = &["opus", "sonnet", "haiku", "default", "opusplan"];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverProviderModelsInput {
+    /// Save models that are not routed yet to the registry catalog.
+    #[serde(default)]
+    pub merge: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredModel {
+    pub provider: String,
+    pub model_id: String,
+    pub capabilities: Vec<String>,
+    pub pricing: Option<ModelPricing>,
+    /// Already used by a tier or persona override.
+    pub routed: bool,
+    /// Already in `models.json`, routed or in the catalog.
+    pub configured: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderModelList {
+    pub provider: String,
+    pub models: Vec<DiscoveredModel>,
+    /// Why the provider could not be listed; its routed models are then not checked.
+    pub error: Option<String>,
+}
+
+/// A routed model its provider no longer lists.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeprecatedModelReference {
+    pub provider: String,
+    pub model_id: String,
+    pub slot: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverProviderModelsResult {
+    pub providers: Vec<ProviderModelList>,
+    pub deprecated: Vec<DeprecatedModelReference>,
+    /// Models added to the registry catalog.
+    pub merged: usize,
+}
+
+/// Lists the models of every provider routed to in `registry`. Blocking; each provider
+/// is queried with the endpoint of its first routed profile, and key reads are logged to
+/// `pool`.
+pub fn discover(registry: &ModelRegistry, pool: &SqlitePool) -> DiscoverProviderModelsResult {
+    let routed = registry.routed_profiles();
+    let mut endpoints: Vec<(String, ModelEndpoint)> = Vec::new();
+    for (_, profile) in &routed {
+        let provider = profile.provider.trim().to_ascii_lowercase();
+        if !endpoints.iter().any(|(known, _)| *known == provider) {
+            endpoints.push((provider, profile.endpoint.clone()));
+        }
+    }
+
+    let mut providers = Vec::new();
+    let mut deprecated = Vec::new();
+    for (provider, endpoint) in endpoints {
+        let family = llm_adapter::provider_family(&provider);
+        let listed = match family {
+            Some(family) => list_models(family, &endpoint, pool),
+            None => Err(format!("No model adapter for provider '{provider}'")),
+        };
+        let ids = match listed {
+            Ok(ids) => ids,
+            Err(error) => {
+                providers.push(ProviderModelList {
+                    provider,
+                    models: Vec::new(),
+                    error: Some(error),
+                });
+                continue;
+            }
+        };
+
+        for (slot, profile) in &routed {
+            if profile.provider.trim().eq_ignore_ascii_case(&provider)
+                && !is_listed(family, &profile.model_id, &ids)
+            {
+                deprecated.push(DeprecatedModelReference {
+                    provider: profile.provider.clone(),
+                    model_id: profile.model_id.clone(),
+                    slot: slot.clone(),
+                });
+            }
+        }
+
+        let models = ids
+            .into_iter()
+            .map(|model_id| {
+                let known = registry.known_profile(&provider, &model_id);
+                let routed = routed.iter().any(|(_, profile)| {
+                    profile.provider.trim().eq_ignore_ascii_case(&provider)
+                        && profile.model_id.trim().eq_ignore_ascii_case(&model_id)
+                });
+                DiscoveredModel {
+                    capabilities: known
+                        .map(|profile| profile.capabilities.clone())
+                        .unwrap_or_else(|| inferred_capabilities(&model_id)),
+                    pricing: known.and_then(|profile| profile.pricing),
+                    routed,
+                    configured: known.is_some(),
+                    provider: provider.clone(),
+                    model_id,
+                }
+            })
+            .collect();
+        providers.push(ProviderModelList {
+            provider,
+            models,
+            error: None,
+        });
+    }
+
+    DiscoverProviderModelsResult {
+        providers,
+        deprecated,
+        merged: 0,
+    }
+}
+
+/// Catalog entries for discovered models that `models.json` does not mention yet,
+/// inheriting the endpoint of the provider's routed profiles.
+pub fn catalog_additions(
+    registry: &ModelRegistry,
+    result: &DiscoverProviderModelsResult,
+) -> Vec<ModelProfile> {
+    let endpoints = registry
+        .routed_profiles()
+        .into_iter()
+        .rev()
+        .map(|(_, profile)| {
+            (
+                profile.provider.trim().to_ascii_lowercase(),
+                profile.endpoint.clone(),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+    result
+        .providers
+        .iter()
+        .flat_map(|list| &list.models)
+        .filter(|model| !model.configured)
+        .map(|model| ModelProfile {
+            provider: model.provider.clone(),
+            model_id: model.model_id.clone(),
+            generation: GenerationParams::default(),
+            pricing: model.pricing,
+            max_context_tokens: None,
+            capabilities: model.capabilities.clone(),
+            endpoint: endpoints.get(&model.provider).cloned().unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn list_models(
+    family: ProviderFamily,
+    endpoint: &ModelEndpoint,
+    pool: &SqlitePool,
+) -> Result<Vec<String>, String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(DISCOVERY_TIMEOUT_SECS))
+        .build()
+        .map_err(|error| format!("Failed to build discovery HTTP client: {error}"))?;
+    let api_key = |default_env: &str| {
+        llm_adapter::endpoint_api_key(endpoint, Some(default_env), "model_discovery", Some(pool))
+    };
+
+    let (request, field, key) = match family {
+        ProviderFamily::ClaudeCode => {
+            let key = api_key("ANTHROPIC_API_KEY")?
+                .ok_or("ANTHROPIC_API_KEY is required to list Claude models")?;
+            let request = client
+                .get(ANTHROPIC_MODELS_URL)
+                .header("x-api-key", key)
+                .header("anthropic-version", ANTHROPIC_VERSION);
+            (request, "data", "id")
+        }
+        ProviderFamily::OpenAi | ProviderFamily::OpenAiCompatible => {
+            let base_url = match (&endpoint.base_url, family) {
+                (Some(base_url), _) => base_url.as_str(),
+                (None, ProviderFamily::OpenAi) => llm_adapter::OPENAI_DEFAULT_BASE_URL,
+                (None, _) => return Err("A baseUrl is required to list models".to_string()),
+            };
+            let mut request = client.get(format!("{}/models", base_url.trim_end_matches('/')));
+            if let Some(key) = api_key("OPENAI_API_KEY")? {
+                request = request.bearer_auth(key);
+            }
+            (request, "data", "id")
+        }
+        ProviderFamily::Ollama => {
+            let base_url = endpoint
+                .base_url
+                .clone()
+                .unwrap_or_else(llm_adapter::ollama_base_url);
+            let request = client.get(format!("{}/api/tags", base_url.trim_end_matches('/')));
+            (request, "models", "name")
+        }
+        ProviderFamily::AzureOpenAi => {
+            return Err(
+                "Azure OpenAI deployments cannot be listed; check them in Azure".to_string(),
+            )
+        }
+    };
+
+    let response = request
+        .send()
+        .map_err(|error| format!("Failed to list models: {error}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Model list request failed with status {status}"));
+    }
+    let payload = response
+        .json::<Value>()
+        .map_err(|error| format!("Failed to parse model list: {error}"))?;
+    Ok(model_ids(&payload, field, key))
+}
+
+fn model_ids(payload: &Value, field: &str, key: &str) -> Vec<String> {
+    let mut ids = payload
+        .get(field)
+        .and_then(Value::as_array)
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| entry.get(key).and_then(Value::as_str))
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+/// Whether a routed model id is among the provider's listed ids. Claude ids may omit
+/// the date suffix and Ollama ids the `:latest` tag.
+fn is_listed(family: Option<ProviderFamily>, model_id: &str, ids: &[String]) -> bool {
+    let model_id = model_id.trim().to_ascii_lowercase();
+    if family == Some(ProviderFamily::ClaudeCode)
+        && CLAUDE_CODE_MODEL_ALIASES.contains(&model_id.as_str())
+    {
+        return true;
+    }
+    ids.iter().any(|id| {
+        let id = id.to_ascii_lowercase();
+        match family {
+            Some(ProviderFamily::ClaudeCode) => id.starts_with(&model_id),
+            Some(ProviderFamily::Ollama) => {
+                id == model_id || id.strip_suffix(":latest") == Some(model_id.as_str())
+            }
+            _ => id == model_id,
+        }
+    })
+}
+
+/// Best-effort tags for a model that is not in `models.json` yet.
+fn inferred_capabilities(model_id: &str) -> Vec<String> {
+    let id = model_id.to_ascii_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|needle| id.contains(needle));
+    let mut capabilities = Vec::new();
+    if has(&["embed"]) {
+        capabilities.push("embedding".to_string());
+        return capabilities;
+    }
+    if has(&["coder", "code", "gpt", "claude", "o3", "o4"]) {
+        capabilities.push("code".to_string());
+    }
+    if has(&["vision", "llava", "gpt-4o", "gpt-4.1", "gpt-5", "claude"]) {
+        capabilities.push("vision".to_string());
+    }
+    capabilities
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn matches_listed_ids_and_tags_unrouted_models() {
+        let openai = model_ids(
+            &json!({ "data": [{ "id": "gpt-5-mini" }, { "id": "text-embedding-3-small" }] }),
+            "data",
+            "id",
+        );
+        assert!(is_listed(
+            Some(ProviderFamily::OpenAi),
+            "GPT-5-mini",
+            &openai
+        ));
+        assert!(!is_listed(
+            Some(ProviderFamily::OpenAi),
+            "gpt-4-turbo",
+            &openai
+        ));
+
+        let claude = vec!["claude-sonnet-4-5-20250929".to_string()];
+        assert!(is_listed(
+            Some(ProviderFamily::ClaudeCode),
+            "sonnet",
+            &claude
+        ));
+        assert!(is_listed(
+            Some(ProviderFamily::ClaudeCode),
+            "claude-sonnet-4-5",
+            &claude
+        ));
+        assert!(!is_listed(
+            Some(ProviderFamily::ClaudeCode),
+            "claude-2.1",
+            &claude
+        ));
+
+        let ollama = model_ids(
+            &json!({ "models": [{ "name": "llava:latest" }] }),
+            "models",
+            "name",
+        );
+        assert!(is_listed(Some(ProviderFamily::Ollama), "llava", &ollama));
+
+        assert_eq!(
+            inferred_capabilities("text-embedding-3-small"),
+            vec!["embedding"]
+        );
+        assert_eq!(inferred_capabilities("gpt-5-mini"), vec!["code", "vision"]);
+    }
+}
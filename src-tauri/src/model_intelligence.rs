@@ -1,8 +1,20 @@
-use crate::db::telemetry::{self, ModelHealthRecord, NewAgentEvent};
-use crate::llm_adapter;
-use crate::model_registry::{ModelProfile, ModelRegistry, ModelSelection};
+use crate::db::model_arms::{self, ArmSegment, ModelPinRecord};
+use crate::db::model_experiments;
+use crate::db::telemetry::{self, CircuitBreakerPolicy, ModelHealthRecord, NewAgentEvent};
+use crate::llm_adapter::{self, AdapterAttemptEvent, AttemptListener};
+use crate::model_bandit::{self, ArmEstimate};
+use crate::model_registry::{
+    ModelProfile, ModelRegistry, ModelSelection, CAPABILITY_JSON_MODE, CAPABILITY_VISION,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 
+/// Blended USD per million tokens treated as "most expensive" when normalizing price.
+const PRICE_CEILING_PER_MTOK: f64 = 20.0;
+/// Below this many tokens left in the task budget, selection always prefers cheap models.
+const LOW_BUDGET_TOKENS: i64 = 2_000;
+
 #[derive(Debug, Clone)]
 pub struct ModelSelectionRequest<'a> {
     pub task_id: Option<&'a str>,
@@ -10,6 +22,130 @@ pub struct ModelSelectionRequest<'a> {
     pub tier: u8,
     pub persona: Option<&'a str>,
     pub skill: Option<&'a str>,
+    /// Tokens left in the task's budget, when the call works for a budgeted task.
+    pub remaining_budget: Option<i64>,
+    pub cost_preference: CostPreference,
+    pub requirements: ModelRequirements,
+}
+
+/// What a call needs from its model. Candidates that fall short are never selected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelRequirements {
+    /// Capability tags such as `json_mode` or `vision`.
+    pub capabilities: Vec<&'static str>,
+    /// Models with an unknown context window are assumed to be large enough.
+    pub min_context_tokens: Option<u32>,
+}
+
+impl ModelRequirements {
+    pub fn with(mut self, capability: &'static str) -> Self {
+        if !self.capabilities.contains(&capability) {
+            self.capabilities.push(capability);
+        }
+        self
+    }
+
+    /// Specialists return their patch as JSON; design images need a vision model.
+    pub fn for_specialist(with_images: bool) -> Self {
+        let requirements = Self::default().with(CAPABILITY_JSON_MODE);
+        if with_images {
+            requirements.with(CAPABILITY_VISION)
+        } else {
+            requirements
+        }
+    }
+
+    pub fn min_context(mut self, tokens: u32) -> Self {
+        self.min_context_tokens = Some(tokens);
+        self
+    }
+
+    /// The requirements `profile` does not meet, described for error messages.
+    fn unmet(&self, profile: &ModelProfile) -> Vec<String> {
+        let mut unmet = self
+            .capabilities
+            .iter()
+            .filter(|capability| !profile.supports(capability))
+            .map(|capability| capability.to_string())
+            .collect::<Vec<_>>();
+        if let (Some(required), Some(available)) =
+            (self.min_context_tokens, profile.context_tokens())
+        {
+            if available < required {
+                unmet.push(format!("{required} context tokens (has {available})"));
+            }
+        }
+        unmet
+    }
+}
+
+/// How much selection trades quality score for a lower per-token price.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostPreference {
+    Cheap,
+    #[default]
+    Balanced,
+    Best,
+}
+
+impl CostPreference {
+    /// Low-risk tier-3 work and docs go to cheap models; risky work to the best ones.
+    pub fn for_task(tier: u8, domain: &str, risk_factor: f64) -> Self {
+        if risk_factor > 0.7 {
+            Self::Best
+        } else if tier >= 3 && (domain == "docs" || risk_factor < 0.3) {
+            Self::Cheap
+        } else {
+            Self::Balanced
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "cheap" => Some(Self::Cheap),
+            "balanced" => Some(Self::Balanced),
+            "best" => Some(Self::Best),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cheap => "cheap",
+            Self::Balanced => "balanced",
+            Self::Best => "best",
+        }
+    }
+
+    /// Score points a model at the price ceiling gives up.
+    fn price_weight(self) -> f64 {
+        match self {
+            Self::Cheap => 0.45,
+            Self::Balanced => 0.15,
+            Self::Best => 0.0,
+        }
+    }
+
+    /// `AOP_MODEL_COST_PREFERENCE` overrides the caller unless it is `auto`, and a nearly
+    /// spent budget always asks for cheap models.
+    fn effective(self, remaining_budget: Option<i64>) -> Self {
+        if remaining_budget.is_some_and(|tokens| tokens < LOW_BUDGET_TOKENS) {
+            return Self::Cheap;
+        }
+        std::env::var("AOP_MODEL_COST_PREFERENCE")
+            .ok()
+            .and_then(|value| Self::parse(&value))
+            .unwrap_or(self)
+    }
+}
+
+/// Validates an `AOP_MODEL_COST_PREFERENCE` value; `auto` defers to each caller.
+pub fn normalize_cost_preference(value: &str) -> Option<&'static str> {
+    if value.trim().eq_ignore_ascii_case("auto") {
+        return Some("auto");
+    }
+    CostPreference::parse(value).map(CostPreference::as_str)
 }
 
 #[derive(Debug, Clone)]
@@ -17,6 +153,8 @@ pub struct ModelSelectionResult {
     pub selection: ModelSelection,
     pub score: f64,
     pub fallback_used: bool,
+    /// Remaining candidates, best first, for the adapter to fail over to.
+    pub fallbacks: Vec<ModelSelection>,
 }
 
 #[derive(Debug, Clone)]
@@ -31,17 +169,45 @@ pub async fn select_model(
     request: ModelSelectionRequest<'_>,
 ) -> Result<ModelSelectionResult, String> {
     let supported = llm_adapter::supported_provider_aliases();
-    let candidates = registry.candidates_with_supported_providers(
-        request.tier,
-        request.persona,
-        &supported,
-    )?;
+    let mut candidates =
+        registry.candidates_with_supported_providers(request.tier, request.persona, &supported)?;
+    if request.tier == 3 && offline_specialists_enabled() {
+        candidates.retain(|profile| profile.has_capability("offline"));
+        if candidates.is_empty() {
+            return Err(format!(
+                "Offline specialists are enabled but no available tier 3 model for persona {:?} is tagged 'offline'. Start Ollama or add an ollama profile to models.json.",
+                request.persona
+            ));
+        }
+    }
     if candidates.is_empty() {
         return Err(format!(
             "No model candidates available for tier {} persona {:?}",
             request.tier, request.persona
         ));
     }
+    let mut shortfalls = Vec::new();
+    candidates.retain(|profile| {
+        let unmet = request.requirements.unmet(profile);
+        if !unmet.is_empty() {
+            shortfalls.push(format!(
+                "{}/{} lacks {}",
+                profile.provider,
+                profile.model_id,
+                unmet.join(", ")
+            ));
+        }
+        unmet.is_empty()
+    });
+    if candidates.is_empty() {
+        return Err(format!(
+            "No tier {} model for persona {:?} meets the requirements of {}: {}. Add the missing capabilities or maxContextTokens to a model in models.json.",
+            request.tier,
+            request.persona,
+            request.skill.unwrap_or("this call"),
+            shortfalls.join("; ")
+        ));
+    }
     let first_candidate_key = candidates
         .first()
         .map(|profile| {
@@ -53,7 +219,10 @@ pub async fn select_model(
         })
         .unwrap_or_default();
 
+    let cost_preference = request.cost_preference.effective(request.remaining_budget);
+    let now = Utc::now().timestamp();
     let mut scored = Vec::new();
+    let mut tripped = Vec::new();
     for candidate in candidates {
         if !llm_adapter::supports_provider(candidate.provider.as_str()) {
             continue;
@@ -64,12 +233,37 @@ pub async fn select_model(
             candidate.model_id.as_str(),
         )
         .await?;
-        let score = score_candidate(health.as_ref());
-        scored.push(ScoredCandidate {
+        let score = (score_candidate(health.as_ref())
+            - cost_preference.price_weight() * price_norm(&candidate, health.as_ref()))
+        .clamp(0.0, 1.0);
+        let candidate = ScoredCandidate {
             profile: candidate,
             score,
-        });
+        };
+        if health.is_some_and(|health| health.circuit_open(now)) {
+            tripped.push(candidate);
+        } else {
+            scored.push(candidate);
+        }
+    }
+    // A pinned model is used even when its circuit is open; pinning is the operator's call.
+    let segment = ArmSegment::new(request.tier, request.persona, request.skill);
+    let pin = model_arms::pin_for_segment(pool, &segment).await?;
+    if let Some(pin) = &pin {
+        if let Some(index) = tripped
+            .iter()
+            .position(|candidate| is_pinned(candidate, pin))
+        {
+            scored.push(tripped.remove(index));
+        }
     }
+    // With every candidate tripped, still route somewhere rather than fail the task.
+    let circuit_skipped = if scored.is_empty() {
+        scored = tripped;
+        0
+    } else {
+        tripped.len()
+    };
     if scored.is_empty() {
         return Err(format!(
             "No provider adapters are available for tier {} persona {:?}",
@@ -90,6 +284,80 @@ pub async fn select_model(
             })
     });
 
+    let policy = model_bandit::policy_from_env();
+    let mut explored = false;
+    if policy != model_bandit::POLICY_STATIC {
+        let arms = model_arms::segment_arms(pool, &segment).await?;
+        let estimates: Vec<ArmEstimate> = scored
+            .iter()
+            .map(|candidate| {
+                let arm = arms.iter().find(|arm| {
+                    arm.provider
+                        .eq_ignore_ascii_case(&candidate.profile.provider)
+                        && arm
+                            .model_id
+                            .eq_ignore_ascii_case(&candidate.profile.model_id)
+                });
+                ArmEstimate {
+                    prior: candidate.score,
+                    pulls: arm.map(|arm| arm.pulls).unwrap_or(0),
+                    reward_sum: arm.map(|arm| arm.reward_sum).unwrap_or(0.0),
+                }
+            })
+            .collect();
+        let draw = uuid::Uuid::new_v4().as_u128();
+        let roll = (draw % 10_000) as f64 / 10_000.0;
+        let pick = ((draw >> 64) % estimates.len().max(1) as u128) as usize;
+        let ranking = model_bandit::rank(
+            policy,
+            &estimates,
+            model_bandit::epsilon_from_env(),
+            roll,
+            pick,
+        );
+        explored = ranking.explored;
+        let mut slots: Vec<Option<ScoredCandidate>> = scored.into_iter().map(Some).collect();
+        scored = ranking
+            .order
+            .into_iter()
+            .filter_map(|(index, value)| {
+                slots[index].take().map(|mut candidate| {
+                    candidate.score = value;
+                    candidate
+                })
+            })
+            .collect();
+    }
+    let pinned_index = pin.as_ref().and_then(|pin| {
+        scored
+            .iter()
+            .position(|candidate| is_pinned(candidate, pin))
+    });
+    if let Some(index) = pinned_index {
+        let candidate = scored.remove(index);
+        scored.insert(0, candidate);
+    }
+    // An experiment routes its arm's model first, unless the segment is pinned or that
+    // model is not an available candidate.
+    let mut experiment_arm = None;
+    if pinned_index.is_none() {
+        if let Some(experiment) = model_experiments::experiment_for_segment(pool, &segment).await? {
+            let key = request
+                .task_id
+                .map(ToOwned::to_owned)
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+            let arm = model_experiments::assign_arm(&experiment, &key);
+            if let Some(index) = scored.iter().position(|candidate| {
+                arm.matches(&candidate.profile.provider, &candidate.profile.model_id)
+            }) {
+                let candidate = scored.remove(index);
+                scored.insert(0, candidate);
+                model_experiments::record_assignment(pool, &arm, request.task_id).await?;
+                experiment_arm = Some(arm);
+            }
+        }
+    }
+
     let selected = scored
         .first()
         .cloned()
@@ -112,7 +380,22 @@ pub async fn select_model(
         "modelId": selected.profile.model_id,
         "score": selected.score,
         "candidates": scored.len(),
-        "source": "quality_first_dynamic"
+        "circuitOpenSkipped": circuit_skipped,
+        "source": if pinned_index.is_some() {
+            "pinned"
+        } else if experiment_arm.is_some() {
+            "experiment"
+        } else {
+            policy
+        },
+        "experimentId": experiment_arm.as_ref().map(|arm| arm.experiment_id.as_str()),
+        "experimentArm": experiment_arm.as_ref().map(|arm| arm.arm),
+        "explored": explored,
+        "costPreference": cost_preference.as_str(),
+        "remainingBudget": request.remaining_budget,
+        "requirements": request.requirements.capabilities,
+        "minContextTokens": request.requirements.min_context_tokens,
+        "requirementSkipped": shortfalls.len()
     });
     let _ = telemetry::record_agent_event(
         pool,
@@ -132,19 +415,93 @@ pub async fn select_model(
     )
     .await;
 
+    let to_selection = |profile: ModelProfile| ModelSelection {
+        tier: request.tier,
+        persona: request.persona.map(|value| value.to_ascii_lowercase()),
+        generation: registry.generation_for(request.tier, request.persona, &profile),
+        provider: profile.provider,
+        model_id: profile.model_id,
+        source: "scored".to_string(),
+        endpoint: profile.endpoint,
+    };
+    let fallbacks = scored
+        .into_iter()
+        .skip(1)
+        .map(|candidate| to_selection(candidate.profile))
+        .collect();
+
     Ok(ModelSelectionResult {
-        selection: ModelSelection {
-            tier: request.tier,
-            persona: request.persona.map(|value| value.to_ascii_lowercase()),
-            provider: selected.profile.provider,
-            model_id: selected.profile.model_id,
-            source: "scored".to_string(),
-        },
+        selection: to_selection(selected.profile),
         score: selected.score,
         fallback_used,
+        fallbacks,
+    })
+}
+
+fn is_pinned(candidate: &ScoredCandidate, pin: &ModelPinRecord) -> bool {
+    candidate
+        .profile
+        .provider
+        .eq_ignore_ascii_case(&pin.provider)
+        && candidate
+            .profile
+            .model_id
+            .eq_ignore_ascii_case(&pin.model_id)
+}
+
+/// Records adapter retries and failovers in `aop_agent_events`; a failover also counts
+/// as a failed call against the model that was given up on.
+pub fn adapter_attempt_listener(pool: SqlitePool) -> AttemptListener {
+    std::sync::Arc::new(move |event: &AdapterAttemptEvent| {
+        let pool = pool.clone();
+        let event = event.clone();
+        tauri::async_runtime::spawn(async move {
+            if event.action == "llm_failover" {
+                record_model_call_outcome(
+                    &pool,
+                    &event.provider,
+                    &event.model_id,
+                    false,
+                    None,
+                    None,
+                    Some(event.error.clone()),
+                )
+                .await;
+            }
+            let _ = telemetry::record_agent_event(
+                &pool,
+                NewAgentEvent {
+                    task_id: event.task_id.clone(),
+                    actor: "llm_adapter".to_string(),
+                    action: event.action.clone(),
+                    status: Some("executing".to_string()),
+                    phase: Some("model_call".to_string()),
+                    message: Some(event.error.clone()),
+                    provider: Some(event.provider.clone()),
+                    model_id: Some(event.model_id.clone()),
+                    retry_count: Some(i64::from(event.attempt)),
+                    payload: serde_json::to_value(&event).ok(),
+                    ..Default::default()
+                },
+            )
+            .await;
+        });
     })
 }
 
+/// With `AOP_OFFLINE_SPECIALISTS` on, tier-3 specialists only run on local models.
+fn offline_specialists_enabled() -> bool {
+    std::env::var("AOP_OFFLINE_SPECIALISTS")
+        .ok()
+        .map(|value| {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false)
+}
+
 pub async fn record_model_call_outcome(
     pool: &SqlitePool,
     provider: &str,
@@ -162,12 +519,47 @@ pub async fn record_model_call_outcome(
             success,
             latency_ms,
             cost_usd,
+            timed_out: error.as_deref().is_some_and(llm_adapter::is_timeout_error),
             error,
+            circuit_breaker: circuit_breaker_policy(),
         },
     )
     .await;
 }
 
+/// Trips after `AOP_CIRCUIT_BREAKER_FAILURES` consecutive failures (0 disables) and stays
+/// open for `AOP_CIRCUIT_BREAKER_OPEN_MINUTES`.
+fn circuit_breaker_policy() -> Option<CircuitBreakerPolicy> {
+    let env_u32 = |key: &str, default: u32| {
+        std::env::var(key)
+            .ok()
+            .and_then(|value| value.trim().parse::<u32>().ok())
+            .unwrap_or(default)
+    };
+    let failure_threshold = env_u32("AOP_CIRCUIT_BREAKER_FAILURES", 3).min(20);
+    let open_minutes = env_u32("AOP_CIRCUIT_BREAKER_OPEN_MINUTES", 5).clamp(1, 1_440);
+    (failure_threshold > 0).then_some(CircuitBreakerPolicy {
+        failure_threshold,
+        open_secs: i64::from(open_minutes) * 60,
+    })
+}
+
+/// Price on a 0..1 scale: the registry's per-token pricing weighted 3:1 toward input,
+/// else the observed average call cost. Local models are free.
+fn price_norm(profile: &ModelProfile, health: Option<&ModelHealthRecord>) -> f64 {
+    if profile.has_capability("local") {
+        return 0.0;
+    }
+    if let Some(pricing) = profile.pricing {
+        let blended = (3.0 * pricing.input_per_mtok + pricing.output_per_mtok) / 4.0;
+        return (blended / PRICE_CEILING_PER_MTOK).clamp(0.0, 1.0);
+    }
+    health
+        .filter(|value| value.total_calls > 0)
+        .map(|value| (value.avg_cost_usd / 0.25).clamp(0.0, 1.0))
+        .unwrap_or(0.5)
+}
+
 fn score_candidate(health: Option<&ModelHealthRecord>) -> f64 {
     let quality = health.map(|value| value.quality_score).unwrap_or(0.70);
     let success_rate = health
@@ -195,10 +587,74 @@ fn score_candidate(health: Option<&ModelHealthRecord>) -> f64 {
         })
         .unwrap_or(0.0);
 
+    // Timeouts already count as failures; the extra penalty pushes hanging models below
+    // ones that fail fast, since each timeout stalls a run for the full deadline.
+    let timeout_penalty = health
+        .map(|value| {
+            if value.total_calls <= 0 {
+                0.0
+            } else {
+                (value.timeout_calls as f64 / value.total_calls as f64).clamp(0.0, 1.0)
+            }
+        })
+        .unwrap_or(0.0);
+
     let score = (0.55 * quality)
         + (0.20 * success_rate)
         + (0.15 * (1.0 - latency_norm))
         + (0.10 * (1.0 - cost_norm))
-        - (0.20 * failure_penalty);
+        - (0.20 * failure_penalty)
+        - (0.15 * timeout_penalty);
     score.clamp(0.0, 1.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cost_preference_follows_tier_domain_and_risk() {
+        let cases = [
+            (3, "docs", 0.5, CostPreference::Cheap),
+            (3, "backend", 0.2, CostPreference::Cheap),
+            (2, "docs", 0.2, CostPreference::Balanced),
+            (3, "docs", 0.9, CostPreference::Best),
+        ];
+        for (tier, domain, risk, expected) in cases {
+            assert_eq!(CostPreference::for_task(tier, domain, risk), expected);
+        }
+        assert_eq!(
+            CostPreference::Best.effective(Some(LOW_BUDGET_TOKENS - 1)),
+            CostPreference::Cheap
+        );
+        assert_eq!(normalize_cost_preference(" AUTO "), Some("auto"));
+        assert_eq!(normalize_cost_preference("premium"), None);
+    }
+
+    #[test]
+    fn requirements_use_tags_adapter_defaults_and_context() {
+        let profile = |value: serde_json::Value| -> ModelProfile {
+            serde_json::from_value(value).expect("profile should parse")
+        };
+        let claude = profile(serde_json::json!({ "provider": "claude_code", "modelId": "haiku" }));
+        let local = profile(serde_json::json!({
+            "provider": "ollama",
+            "modelId": "qwen2.5-coder:7b",
+            "maxContextTokens": 32768
+        }));
+
+        let specialist = ModelRequirements::for_specialist(true);
+        assert!(specialist.unmet(&claude).is_empty());
+        assert_eq!(local.context_tokens(), Some(32_768));
+        assert_eq!(specialist.unmet(&local), vec!["vision"]);
+
+        let planner = ModelRequirements::default()
+            .with(CAPABILITY_JSON_MODE)
+            .min_context(100_000);
+        assert!(planner.unmet(&claude).is_empty());
+        assert_eq!(
+            planner.unmet(&local),
+            vec!["100000 context tokens (has 32768)"]
+        );
+    }
+}
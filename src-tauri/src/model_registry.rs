@@ -6,16 +6,174 @@ use serde::{Deserialize, Serialize};
 
 const CONFIG_FILE_NAME: &str = "models.json";
 const CONFIG_PATH_ENV: &str = "AOP_MODEL_CONFIG_PATH";
+/// Context window assumed for Claude models that do not set `maxContextTokens`.
+const CLAUDE_CONTEXT_TOKENS: u32 = 200_000;
+
+/// Capability tags model selection can require.
+pub const CAPABILITY_CODE: &str = "code";
+pub const CAPABILITY_JSON_MODE: &str = "json_mode";
+pub const CAPABILITY_VISION: &str = "vision";
+pub const CAPABILITY_TOOL_USE: &str = "tool_use";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModelProfile {
     pub provider: String,
     pub model_id: String,
+    /// Sampling defaults for this model; role overrides in `roleGeneration` win.
+    #[serde(flatten)]
+    pub generation: GenerationParams,
+    /// Price per million tokens, used to cost calls the provider does not price itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<ModelPricing>,
+    /// Context window in tokens; unset means unknown, which satisfies any requirement.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_context_tokens: Option<u32>,
+    /// Tags such as `code`, `json_mode`, `vision`, `tool_use`, `local` or `offline`.
+    /// Local providers always carry `local` and `offline`.
     #[serde(default)]
+    pub capabilities: Vec<String>,
+    #[serde(flatten)]
+    pub endpoint: ModelEndpoint,
+}
+
+/// Where an OpenAI-compatible model is served. Unset fields use the provider defaults,
+/// so Azure OpenAI, vLLM, LM Studio or a corporate proxy can be configured per model.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelEndpoint {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// Azure `api-version` query parameter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+    /// Azure deployment name; defaults to the model id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployment: Option<String>,
+    /// Environment variable holding the API key, instead of the provider's default one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+    /// Vault key holding the API key, `provider` or `provider/name` (e.g. `openai/work`);
+    /// takes precedence over `api_key_env`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential: Option<String>,
+    /// Seconds before a call is abandoned, over the provider's default timeout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl ModelEndpoint {
+    fn normalized(self) -> Self {
+        let clean = |value: Option<String>| {
+            value
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            base_url: clean(self.base_url).map(|value| value.trim_end_matches('/').to_string()),
+            api_version: clean(self.api_version),
+            deployment: clean(self.deployment),
+            api_key_env: clean(self.api_key_env),
+            credential: clean(self.credential).map(|value| value.to_ascii_lowercase()),
+            timeout_secs: self.timeout_secs.filter(|secs| *secs > 0),
+        }
+    }
+}
+
+/// Sampling settings sent with a model call. Unset fields use the provider's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub max_output_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+}
+
+impl GenerationParams {
+    /// These settings, with anything unset taken from `defaults`.
+    pub fn or(&self, defaults: &GenerationParams) -> GenerationParams {
+        GenerationParams {
+            temperature: self.temperature.or(defaults.temperature),
+            top_p: self.top_p.or(defaults.top_p),
+            max_output_tokens: self.max_output_tokens.or(defaults.max_output_tokens),
+            stop: if self.stop.is_empty() {
+                defaults.stop.clone()
+            } else {
+                self.stop.clone()
+            },
+        }
+    }
+
+    fn normalized(self) -> Self {
+        Self {
+            temperature: self.temperature.map(|value| value.clamp(0.0, 2.0)),
+            top_p: self.top_p.map(|value| value.clamp(0.0, 1.0)),
+            max_output_tokens: self.max_output_tokens.filter(|value| *value > 0),
+            stop: self
+                .stop
+                .into_iter()
+                .filter(|value| !value.is_empty())
+                .take(4)
+                .collect(),
+        }
+    }
+}
+
+/// USD per million input and output tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPricing {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+impl ModelPricing {
+    pub fn cost_usd(&self, input_tokens: u32, output_tokens: u32) -> f64 {
+        (f64::from(input_tokens) * self.input_per_mtok
+            + f64::from(output_tokens) * self.output_per_mtok)
+            / 1_000_000.0
+    }
+}
+
+/// Throttling applied by the adapter to every model of one provider. Unset fields mean
+/// no limit.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+    /// Requests allowed back to back before `requestsPerMinute` paces them; defaults to 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent: Option<u32>,
+}
+
+impl ModelProfile {
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.capabilities
+            .iter()
+            .any(|value| value.eq_ignore_ascii_case(capability.trim()))
+    }
+
+    /// A tagged capability, or one the provider's adapter guarantees for every model,
+    /// such as JSON output through a response schema.
+    pub fn supports(&self, capability: &str) -> bool {
+        self.has_capability(capability)
+            || adapter_capabilities(&self.provider)
+                .iter()
+                .any(|value| value.eq_ignore_ascii_case(capability.trim()))
+    }
+
+    pub fn context_tokens(&self) -> Option<u32> {
+        self.max_context_tokens
+            .or_else(|| is_claude_code(&self.provider).then_some(CLAUDE_CONTEXT_TOKENS))
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -58,6 +216,17 @@ pub struct ModelRoutingConfig {
     pub tiers: HashMap<String, Vec<ModelProfile>>,
     #[serde(default, deserialize_with = "deserialize_profile_map")]
     pub persona_overrides: HashMap<String, Vec<ModelProfile>>,
+    /// Rate and concurrency limits keyed by provider.
+    #[serde(default)]
+    pub provider_limits: HashMap<String, ProviderLimits>,
+    /// Sampling overrides keyed by persona or by tier role (`planner`, `domain_leader`,
+    /// `specialist`), applied over the model's own defaults.
+    #[serde(default)]
+    pub role_generation: HashMap<String, GenerationParams>,
+    /// Models known to be available but not routed to, e.g. found by provider discovery.
+    /// Their capabilities and pricing are kept for when they are added to a tier.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub catalog: Vec<ModelProfile>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,6 +237,10 @@ pub struct ModelSelection {
     pub provider: String,
     pub model_id: String,
     pub source: String,
+    #[serde(flatten)]
+    pub endpoint: ModelEndpoint,
+    #[serde(default)]
+    pub generation: GenerationParams,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,9 +272,83 @@ impl Default for ModelRegistry {
     }
 }
 
+/// Where a model sits in `models.json`: a tier's candidates or a persona override's.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryModelSlot {
+    pub tier: Option<u8>,
+    pub persona: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddRegistryModelInput {
+    #[serde(flatten)]
+    pub slot: RegistryModelSlot,
+    pub profile: ModelProfile,
+    /// Candidate position, 0 being tried first; defaults to last.
+    pub position: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRegistryModelInput {
+    #[serde(flatten)]
+    pub slot: RegistryModelSlot,
+    pub provider: String,
+    pub model_id: String,
+    pub profile: ModelProfile,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveRegistryModelInput {
+    #[serde(flatten)]
+    pub slot: RegistryModelSlot,
+    pub provider: String,
+    pub model_id: String,
+}
+
+impl RegistryModelSlot {
+    fn key(&self) -> Result<(bool, String), String> {
+        let persona = self
+            .persona
+            .as_deref()
+            .map(|value| value.trim().to_ascii_lowercase())
+            .filter(|value| !value.is_empty());
+        match (self.tier, persona) {
+            (Some(tier), None) if (1..=3).contains(&tier) => Ok((true, tier.to_string())),
+            (Some(_), None) => Err("tier must be 1, 2, or 3".to_string()),
+            (None, Some(persona)) => Ok((false, persona)),
+            _ => Err("Exactly one of tier or persona is required".to_string()),
+        }
+    }
+
+    fn profiles_mut<'a>(
+        &self,
+        config: &'a mut ModelRoutingConfig,
+    ) -> Result<&'a mut Vec<ModelProfile>, String> {
+        let (is_tier, key) = self.key()?;
+        let map = if is_tier {
+            &mut config.tiers
+        } else {
+            &mut config.persona_overrides
+        };
+        Ok(map.entry(key).or_default())
+    }
+}
+
 impl ModelRegistry {
     pub fn load(workspace_root: &Path) -> Self {
-        let config_path = resolve_config_path(workspace_root);
+        Self::load_from(resolve_config_path(workspace_root))
+    }
+
+    /// Re-reads the file this registry was loaded from.
+    pub fn reload(&self) -> Self {
+        Self::load_from(self.config_path.clone())
+    }
+
+    fn load_from(config_path: PathBuf) -> Self {
         if !config_path.exists() {
             return Self {
                 config_path,
@@ -148,6 +395,203 @@ impl ModelRegistry {
         }
     }
 
+    pub fn add_model(&self, input: AddRegistryModelInput) -> Result<Self, String> {
+        let profile = validated_profile(input.profile)?;
+        self.edit_file(|config| {
+            let profiles = input.slot.profiles_mut(config)?;
+            if let Some(existing) = find_profile(profiles, &profile.provider, &profile.model_id) {
+                return Err(format!(
+                    "{}/{} is already configured there",
+                    profiles[existing].provider, profiles[existing].model_id
+                ));
+            }
+            let position = input.position.unwrap_or(profiles.len()).min(profiles.len());
+            profiles.insert(position, profile);
+            Ok(())
+        })
+    }
+
+    pub fn update_model(&self, input: UpdateRegistryModelInput) -> Result<Self, String> {
+        let profile = validated_profile(input.profile)?;
+        self.edit_file(|config| {
+            let profiles = input.slot.profiles_mut(config)?;
+            let index = find_profile(profiles, &input.provider, &input.model_id)
+                .ok_or_else(|| missing_model(&input.provider, &input.model_id))?;
+            let renamed = find_profile(profiles, &profile.provider, &profile.model_id);
+            if renamed.is_some_and(|other| other != index) {
+                return Err(format!(
+                    "{}/{} is already configured there",
+                    profile.provider, profile.model_id
+                ));
+            }
+            profiles[index] = profile;
+            Ok(())
+        })
+    }
+
+    pub fn remove_model(&self, input: RemoveRegistryModelInput) -> Result<Self, String> {
+        self.edit_file(|config| {
+            let (is_tier, key) = input.slot.key()?;
+            let profiles = input.slot.profiles_mut(config)?;
+            let index = find_profile(profiles, &input.provider, &input.model_id)
+                .ok_or_else(|| missing_model(&input.provider, &input.model_id))?;
+            profiles.remove(index);
+            // An empty tier falls back to the built-in defaults; an empty persona is dropped.
+            if profiles.is_empty() {
+                if is_tier {
+                    config.tiers.remove(&key);
+                } else {
+                    config.persona_overrides.remove(&key);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Replaces the whole routing config, as an imported configuration bundle does.
+    pub fn replace_config(&self, config: ModelRoutingConfig) -> Result<Self, String> {
+        self.edit_file(|current| {
+            *current = config;
+            Ok(())
+        })
+    }
+
+    /// Every routed profile with the slot it is routed from, e.g. `tier 3` or
+    /// `persona react_specialist`.
+    pub fn routed_profiles(&self) -> Vec<(String, &ModelProfile)> {
+        let tiers = self
+            .config
+            .tiers
+            .iter()
+            .map(|(tier, profiles)| (format!("tier {tier}"), profiles));
+        let personas = self
+            .config
+            .persona_overrides
+            .iter()
+            .map(|(persona, profiles)| (format!("persona {persona}"), profiles));
+        let mut routed = tiers
+            .chain(personas)
+            .flat_map(|(slot, profiles)| {
+                profiles.iter().map(move |profile| (slot.clone(), profile))
+            })
+            .collect::<Vec<_>>();
+        routed.sort_by(|left, right| left.0.cmp(&right.0));
+        routed
+    }
+
+    /// The configured profile for a model, routed or in the catalog.
+    pub fn known_profile(&self, provider: &str, model_id: &str) -> Option<&ModelProfile> {
+        let key = pricing_key(provider, model_id);
+        self.config
+            .tiers
+            .values()
+            .chain(self.config.persona_overrides.values())
+            .flatten()
+            .chain(&self.config.catalog)
+            .find(|profile| pricing_key(&profile.provider, &profile.model_id) == key)
+    }
+
+    /// Adds models to the catalog, skipping any already routed or catalogued.
+    pub fn add_catalog_models(&self, profiles: Vec<ModelProfile>) -> Result<Self, String> {
+        self.edit_file(|config| {
+            let mut known = config
+                .tiers
+                .values()
+                .chain(config.persona_overrides.values())
+                .flatten()
+                .chain(&config.catalog)
+                .map(|profile| pricing_key(&profile.provider, &profile.model_id))
+                .collect::<HashSet<_>>();
+            for profile in profiles {
+                let profile = validated_profile(profile)?;
+                if known.insert(pricing_key(&profile.provider, &profile.model_id)) {
+                    config.catalog.push(profile);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Applies `edit` to the config file as written (or the defaults when there is none),
+    /// replaces the file atomically and returns the registry loaded from it.
+    fn edit_file(
+        &self,
+        edit: impl FnOnce(&mut ModelRoutingConfig) -> Result<(), String>,
+    ) -> Result<Self, String> {
+        let mut config = if self.config_path.exists() {
+            let raw = fs::read_to_string(&self.config_path)
+                .map_err(|error| format!("Failed to read {CONFIG_FILE_NAME}: {error}"))?;
+            serde_json::from_str::<ModelRoutingConfig>(&raw).map_err(|error| {
+                format!("Failed to parse {CONFIG_FILE_NAME}; fix it before editing: {error}")
+            })?
+        } else {
+            default_config()
+        };
+        edit(&mut config)?;
+
+        let serialized = serde_json::to_string_pretty(&config)
+            .map_err(|error| format!("Failed to serialize {CONFIG_FILE_NAME}: {error}"))?;
+        let staging = self.config_path.with_extension("json.tmp");
+        fs::write(&staging, format!("{serialized}\n"))
+            .map_err(|error| format!("Failed to write {CONFIG_FILE_NAME}: {error}"))?;
+        fs::rename(&staging, &self.config_path)
+            .map_err(|error| format!("Failed to replace {CONFIG_FILE_NAME}: {error}"))?;
+
+        let registry = self.reload();
+        match registry.load_error {
+            Some(error) => Err(error),
+            None => Ok(registry),
+        }
+    }
+
+    pub fn provider_limits(&self) -> HashMap<String, ProviderLimits> {
+        self.config.provider_limits.clone()
+    }
+
+    /// Pricing of every configured model keyed by `provider::model_id`, lowercase. A model
+    /// listed more than once keeps its first price.
+    pub fn model_pricing(&self) -> HashMap<String, ModelPricing> {
+        let mut pricing = HashMap::new();
+        for profile in self
+            .config
+            .tiers
+            .values()
+            .chain(self.config.persona_overrides.values())
+            .flatten()
+            .chain(&self.config.catalog)
+        {
+            if let Some(price) = profile.pricing {
+                pricing
+                    .entry(pricing_key(&profile.provider, &profile.model_id))
+                    .or_insert(price);
+            }
+        }
+        pricing
+    }
+
+    /// Sampling settings for `profile` when used at `tier` by `persona`: a persona
+    /// override, else the tier role override, over the model defaults.
+    pub fn generation_for(
+        &self,
+        tier: u8,
+        persona: Option<&str>,
+        profile: &ModelProfile,
+    ) -> GenerationParams {
+        let persona_key = persona
+            .map(|value| value.trim().to_ascii_lowercase())
+            .filter(|value| !value.is_empty());
+        let role_key = match tier {
+            1 => "planner",
+            2 => "domain_leader",
+            _ => "specialist",
+        };
+        persona_key
+            .and_then(|key| self.config.role_generation.get(&key))
+            .or_else(|| self.config.role_generation.get(role_key))
+            .map(|role| role.or(&profile.generation))
+            .unwrap_or_else(|| profile.generation.clone())
+    }
+
     pub fn resolve(&self, tier: u8, persona: Option<&str>) -> Result<ModelSelection, String> {
         self.resolve_with_supported_providers(tier, persona, &[])
     }
@@ -183,6 +627,8 @@ impl ModelRegistry {
                         provider: profile.provider.clone(),
                         model_id: profile.model_id.clone(),
                         source: "persona_override".to_string(),
+                        endpoint: profile.endpoint.clone(),
+                        generation: self.generation_for(tier, persona, profile),
                     });
                 }
             }
@@ -197,6 +643,8 @@ impl ModelRegistry {
                     provider: profile.provider.clone(),
                     model_id: profile.model_id.clone(),
                     source: "tier".to_string(),
+                    endpoint: profile.endpoint.clone(),
+                    generation: self.generation_for(tier, persona, profile),
                 });
             }
         }
@@ -231,6 +679,8 @@ impl ModelRegistry {
             provider: profile.provider.clone(),
             model_id: profile.model_id.clone(),
             source: "default".to_string(),
+            endpoint: profile.endpoint.clone(),
+            generation: self.generation_for(tier, persona, profile),
         })
     }
 
@@ -318,6 +768,32 @@ impl ModelRegistry {
     }
 }
 
+fn validated_profile(profile: ModelProfile) -> Result<ModelProfile, String> {
+    if profile.provider.trim().is_empty() || profile.model_id.trim().is_empty() {
+        return Err("provider and modelId are required".to_string());
+    }
+    Ok(ModelProfile {
+        provider: profile.provider.trim().to_string(),
+        model_id: profile.model_id.trim().to_string(),
+        ..profile
+    })
+}
+
+fn find_profile(profiles: &[ModelProfile], provider: &str, model_id: &str) -> Option<usize> {
+    let key = pricing_key(provider, model_id);
+    profiles
+        .iter()
+        .position(|profile| pricing_key(&profile.provider, &profile.model_id) == key)
+}
+
+fn missing_model(provider: &str, model_id: &str) -> String {
+    format!(
+        "{}/{} is not configured there",
+        provider.trim(),
+        model_id.trim()
+    )
+}
+
 fn resolve_config_path(workspace_root: &Path) -> PathBuf {
     if let Some(override_path) = std::env::var_os(CONFIG_PATH_ENV) {
         return PathBuf::from(override_path);
@@ -375,6 +851,37 @@ fn sanitize_config(config: ModelRoutingConfig) -> ModelRoutingConfig {
         persona_overrides.insert(persona_key, normalized);
     }
 
+    let provider_limits = config
+        .provider_limits
+        .into_iter()
+        .map(|(provider, limits)| {
+            let positive = |value: Option<u32>| value.filter(|value| *value > 0);
+            (
+                provider.trim().to_ascii_lowercase(),
+                ProviderLimits {
+                    requests_per_minute: positive(limits.requests_per_minute),
+                    burst: positive(limits.burst),
+                    max_concurrent: positive(limits.max_concurrent),
+                },
+            )
+        })
+        .filter(|(provider, _)| !provider.is_empty())
+        .collect();
+    let role_generation = config
+        .role_generation
+        .into_iter()
+        .map(|(role, params)| (role.trim().to_ascii_lowercase(), params.normalized()))
+        .filter(|(role, _)| !role.is_empty())
+        .collect();
+    let mut catalog_keys = HashSet::new();
+    let catalog = config
+        .catalog
+        .into_iter()
+        .filter(|profile| !profile.model_id.trim().is_empty())
+        .map(|profile| normalize_profile(profile, &tier3_fallback, &default_provider))
+        .filter(|profile| catalog_keys.insert(pricing_key(&profile.provider, &profile.model_id)))
+        .collect();
+
     ModelRoutingConfig {
         version: if config.version == 0 {
             default_version()
@@ -384,6 +891,9 @@ fn sanitize_config(config: ModelRoutingConfig) -> ModelRoutingConfig {
         default_provider,
         tiers,
         persona_overrides,
+        provider_limits,
+        role_generation,
+        catalog,
     }
 }
 
@@ -458,14 +968,64 @@ fn normalize_profile(
         profile.model_id.trim().to_string()
     };
 
-    let temperature = profile.temperature.map(|value| value.clamp(0.0, 2.0));
-    let max_output_tokens = profile.max_output_tokens;
+    let mut capabilities = Vec::new();
+    for capability in profile
+        .capabilities
+        .iter()
+        .map(|value| value.trim().to_ascii_lowercase())
+        .chain(
+            implied_capabilities(&provider)
+                .iter()
+                .map(|value| value.to_string()),
+        )
+    {
+        if !capability.is_empty() && !capabilities.contains(&capability) {
+            capabilities.push(capability);
+        }
+    }
 
     ModelProfile {
         provider,
         model_id,
-        temperature,
-        max_output_tokens,
+        generation: profile.generation.normalized(),
+        pricing: profile.pricing.filter(|price| {
+            price.input_per_mtok >= 0.0 && price.output_per_mtok >= 0.0
+        }),
+        max_context_tokens: profile.max_context_tokens.filter(|tokens| *tokens > 0),
+        capabilities,
+        endpoint: profile.endpoint.normalized(),
+    }
+}
+
+fn is_claude_code(provider: &str) -> bool {
+    matches!(
+        normalize_provider(provider).as_str(),
+        "claude_code" | "claude-code" | "anthropic_claude_code"
+    )
+}
+
+fn adapter_capabilities(provider: &str) -> &'static [&'static str] {
+    if is_claude_code(provider) {
+        return &[
+            CAPABILITY_CODE,
+            CAPABILITY_JSON_MODE,
+            CAPABILITY_VISION,
+            CAPABILITY_TOOL_USE,
+        ];
+    }
+    match normalize_provider(provider).as_str() {
+        "openai" | "openai_api" | "azure_openai" | "azure" => {
+            &[CAPABILITY_JSON_MODE, CAPABILITY_TOOL_USE]
+        }
+        "ollama" | "ollama_local" => &[CAPABILITY_JSON_MODE],
+        _ => &[],
+    }
+}
+
+fn implied_capabilities(provider: &str) -> &'static [&'static str] {
+    match normalize_provider(provider).as_str() {
+        "ollama" | "ollama_local" => &["local", "offline"],
+        _ => &[],
     }
 }
 
@@ -487,12 +1047,23 @@ fn normalize_provider(value: &str) -> String {
     value.trim().to_ascii_lowercase()
 }
 
+pub fn pricing_key(provider: &str, model_id: &str) -> String {
+    format!(
+        "{}::{}",
+        normalize_provider(provider),
+        model_id.trim().to_ascii_lowercase()
+    )
+}
+
 fn default_config() -> ModelRoutingConfig {
     ModelRoutingConfig {
         version: default_version(),
         default_provider: default_provider(),
         tiers: default_tier_profiles(),
         persona_overrides: HashMap::new(),
+        provider_limits: HashMap::new(),
+        role_generation: HashMap::new(),
+        catalog: Vec::new(),
     }
 }
 
@@ -500,7 +1071,10 @@ fn default_tier_profiles() -> HashMap<String, Vec<ModelProfile>> {
     HashMap::from([
         ("1".to_string(), vec![default_tier_profile(1)]),
         ("2".to_string(), vec![default_tier_profile(2)]),
-        ("3".to_string(), vec![default_tier_profile(3)]),
+        (
+            "3".to_string(),
+            vec![default_tier_profile(3), default_local_profile()],
+        ),
     ])
 }
 
@@ -508,8 +1082,34 @@ fn default_tier_profile(_tier: u8) -> ModelProfile {
     ModelProfile {
         provider: default_provider(),
         model_id: "sonnet".to_string(),
-        temperature: Some(0.2),
-        max_output_tokens: None,
+        generation: GenerationParams {
+            temperature: Some(0.2),
+            ..GenerationParams::default()
+        },
+        pricing: None,
+        max_context_tokens: None,
+        capabilities: vec!["code".to_string()],
+        endpoint: ModelEndpoint::default(),
+    }
+}
+
+/// Offline tier-3 candidate, used when Ollama is running and remote models are not.
+fn default_local_profile() -> ModelProfile {
+    ModelProfile {
+        provider: "ollama".to_string(),
+        model_id: "qwen2.5-coder:7b".to_string(),
+        generation: GenerationParams {
+            temperature: Some(0.2),
+            ..GenerationParams::default()
+        },
+        pricing: None,
+        max_context_tokens: Some(32_768),
+        capabilities: vec![
+            "code".to_string(),
+            "local".to_string(),
+            "offline".to_string(),
+        ],
+        endpoint: ModelEndpoint::default(),
     }
 }
 
@@ -584,6 +1184,74 @@ mod tests {
         assert_eq!(tier1.provider, "claude_code");
     }
 
+    #[test]
+    fn normalizes_provider_limits() {
+        let temp = tempdir().expect("temp directory should be created");
+        fs::write(
+            temp.path().join(CONFIG_FILE_NAME),
+            r#"{
+  "tiers": {},
+  "providerLimits": {
+    " OpenAI ": { "requestsPerMinute": 500, "maxConcurrent": 4 },
+    "ollama": { "maxConcurrent": 0, "burst": 2 }
+  }
+}"#,
+        )
+        .expect("config should be written");
+
+        let limits = ModelRegistry::load(temp.path()).provider_limits();
+
+        assert_eq!(
+            limits.get("openai"),
+            Some(&ProviderLimits {
+                requests_per_minute: Some(500),
+                burst: None,
+                max_concurrent: Some(4),
+            })
+        );
+        assert_eq!(limits.get("ollama").and_then(|value| value.max_concurrent), None);
+        assert_eq!(limits.get("ollama").and_then(|value| value.burst), Some(2));
+    }
+
+    #[test]
+    fn role_generation_overrides_model_defaults() {
+        let temp = tempdir().expect("temp directory should be created");
+        fs::write(
+            temp.path().join(CONFIG_FILE_NAME),
+            r#"{
+  "tiers": {
+    "1": { "provider": "openai", "modelId": "gpt-5", "temperature": 0.7, "maxOutputTokens": 4000 },
+    "3": { "provider": "openai", "modelId": "gpt-5-mini", "topP": 0.9, "stop": ["<END>"] }
+  },
+  "personaOverrides": {
+    "security_analyst": { "provider": "openai", "modelId": "o3" }
+  },
+  "roleGeneration": {
+    "Planner": { "temperature": 0.0 },
+    "specialist": { "temperature": 3.5 },
+    "security_analyst": { "maxOutputTokens": 1000 }
+  }
+}"#,
+        )
+        .expect("config should be written");
+        let registry = ModelRegistry::load(temp.path());
+
+        let planner = registry.resolve(1, None).expect("tier 1 should resolve");
+        assert_eq!(planner.generation.temperature, Some(0.0));
+        assert_eq!(planner.generation.max_output_tokens, Some(4000));
+
+        let specialist = registry.resolve(3, None).expect("tier 3 should resolve");
+        assert_eq!(specialist.generation.temperature, Some(2.0));
+        assert_eq!(specialist.generation.top_p, Some(0.9));
+        assert_eq!(specialist.generation.stop, vec!["<END>".to_string()]);
+
+        let security = registry
+            .resolve(3, Some("security_analyst"))
+            .expect("persona should resolve");
+        assert_eq!(security.generation.temperature, None);
+        assert_eq!(security.generation.max_output_tokens, Some(1000));
+    }
+
     #[test]
     fn falls_back_to_defaults_when_json_is_invalid() {
         let temp = tempdir().expect("temp directory should be created");
@@ -601,6 +1269,36 @@ mod tests {
         assert_eq!(tier3.model_id, "sonnet");
     }
 
+    #[test]
+    fn tags_local_providers_as_offline() {
+        let temp = tempdir().expect("temp directory should be created");
+        fs::write(
+            temp.path().join(CONFIG_FILE_NAME),
+            r#"{
+  "tiers": {
+    "3": [
+      { "provider": "claude_code", "modelId": "haiku", "capabilities": ["Code"] },
+      { "provider": "ollama", "modelId": "qwen2.5-coder:7b" }
+    ]
+  }
+}"#,
+        )
+        .expect("config should be written");
+
+        let registry = ModelRegistry::load(temp.path());
+        let available = vec!["ollama".to_string()];
+        let tier3 = registry
+            .candidates_with_supported_providers(3, None, &available)
+            .expect("tier 3 candidates should resolve");
+
+        assert_eq!(tier3.len(), 1);
+        assert_eq!(tier3[0].model_id, "qwen2.5-coder:7b");
+        assert!(tier3[0].has_capability("offline"));
+        let remote = &registry.snapshot().config.tiers["3"][0];
+        assert_eq!(remote.capabilities, vec!["code"]);
+        assert!(!remote.has_capability("offline"));
+    }
+
     #[test]
     fn rejects_invalid_tier_resolution() {
         let registry = ModelRegistry::default();
@@ -619,4 +1317,65 @@ mod tests {
             .expect_err("missing supported provider should fail");
         assert!(error.contains("No available provider adapter"));
     }
+
+    #[test]
+    fn edits_persist_to_the_config_file_and_reload() {
+        let temp = tempdir().expect("temp directory should be created");
+        let registry = ModelRegistry::load(temp.path());
+        let profile = |model_id: &str| -> ModelProfile {
+            serde_json::from_value(serde_json::json!({
+                "provider": "openai",
+                "modelId": model_id,
+                "capabilities": ["code"]
+            }))
+            .expect("profile should parse")
+        };
+        let slot = || RegistryModelSlot {
+            tier: None,
+            persona: Some("Docs_Writer".to_string()),
+        };
+
+        let registry = registry
+            .add_model(AddRegistryModelInput {
+                slot: slot(),
+                profile: profile("gpt-5-mini"),
+                position: None,
+            })
+            .expect("model should be added");
+        assert!(registry.snapshot().loaded_from_file);
+        let selected = registry
+            .resolve(3, Some("docs_writer"))
+            .expect("persona should resolve");
+        assert_eq!(selected.model_id, "gpt-5-mini");
+        assert!(registry
+            .add_model(AddRegistryModelInput {
+                slot: slot(),
+                profile: profile("GPT-5-mini"),
+                position: None,
+            })
+            .is_err());
+
+        let registry = registry
+            .update_model(UpdateRegistryModelInput {
+                slot: slot(),
+                provider: "openai".to_string(),
+                model_id: "gpt-5-mini".to_string(),
+                profile: profile("gpt-5"),
+            })
+            .expect("model should be updated");
+        let reloaded = ModelRegistry::load(temp.path());
+        assert_eq!(
+            reloaded.snapshot().config.persona_overrides["docs_writer"][0].model_id,
+            "gpt-5"
+        );
+
+        let registry = registry
+            .remove_model(RemoveRegistryModelInput {
+                slot: slot(),
+                provider: "openai".to_string(),
+                model_id: "gpt-5".to_string(),
+            })
+            .expect("model should be removed");
+        assert!(registry.snapshot().config.persona_overrides.is_empty());
+    }
 }
@@ -1,24 +1,50 @@
+use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use sqlx::SqlitePool;
 use tokio::process::Command;
+use tokio::sync::Mutex;
 use tokio::time::timeout;
 use uuid::Uuid;
 
+use crate::agents::specialist::compute_unified_diff;
+use crate::commit_message;
+use crate::compliance;
 use crate::db::metrics;
 use crate::db::mutations::{self, MutationRecord, MutationStatus, UpdateMutationStatusInput};
 use crate::db::tasks::{self, TaskRecord, TaskStatus, UpdateTaskOutcomeInput};
+use crate::mcp_bridge::client::BridgeClient;
+use crate::mcp_bridge::session::SessionContext;
+use crate::mcp_bridge::tool_caller::{self, ApplyTargetPatchInput};
+use crate::metrics_server::StepTimer;
+use crate::model_registry::ModelRegistry;
+use crate::mutation_revision;
+use crate::patch_hunks;
+use crate::protected_paths;
+use crate::secret_vault::SecretVault;
+use crate::semantic_judge::{self, JudgeVerdict};
+use crate::shadow_cache::{self, ShadowBase};
+use crate::shadow_sandbox::{self, SandboxConfig};
+use crate::task_branch;
+use crate::task_runtime::{self, ExecutionLane};
 use crate::vector::indexer;
 use crate::vector::indexer::embed_text;
+use crate::webhooks::{self, WebhookEvent};
 
-const SHADOW_TIMEOUT: Duration = Duration::from_secs(120);
-const APPLY_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_TEST_TIMEOUT_SECS: u64 = 120;
+const DEFAULT_APPLY_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_STATIC_CHECK_TIMEOUT_SECS: u64 = 180;
+const DEFAULT_MAX_OUTPUT_KB: u64 = 64;
 const SEMANTIC_THRESHOLD: f32 = 0.08;
+const FULL_SUITE_RISK_THRESHOLD: f64 = 0.7;
+const DEFAULT_REPAIR_ATTEMPTS: u32 = 2;
+const MAX_REPAIR_ATTEMPTS: u32 = 5;
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -28,6 +54,36 @@ pub struct RunMutationPipelineInput {
     pub tier1_approved: bool,
     pub ci_command: Option<String>,
     pub ci_args: Option<Vec<String>>,
+    pub max_repair_attempts: Option<u32>,
+    pub use_task_branch: Option<bool>,
+    /// Reviewers who signed off, checked against `required_reviewers` in the project policy.
+    pub approved_by: Option<Vec<String>>,
+    pub limits: Option<PipelineLimitsInput>,
+    /// Lets a mutation the semantic judge rated `partially_aligned` be applied.
+    pub accept_partial_alignment: Option<bool>,
+    /// Registered MCP server that applies the approved patch, for remote or containerized
+    /// targets. Validation still runs against the local checkout in `target_project`.
+    pub mcp_server: Option<String>,
+}
+
+/// Per-call overrides for the runtime flag defaults in `PipelineLimits`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineLimitsInput {
+    pub test_timeout_secs: Option<u64>,
+    pub static_check_timeout_secs: Option<u64>,
+    pub apply_timeout_secs: Option<u64>,
+    pub max_output_kb: Option<u64>,
+    /// Extra variables for CI commands, merged over `AOP_PIPELINE_CI_ENV`.
+    pub ci_env: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebaseMutationInput {
+    pub mutation_id: String,
+    pub target_project: String,
+    pub use_task_branch: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -47,6 +103,28 @@ pub struct MutationPipelineResult {
     pub shadow_dir: Option<String>,
 }
 
+/// How a patch ended up applied to the target project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ApplyStrategy {
+    Clean,
+    ThreeWay,
+    /// The stored diff no longer applied; this diff was rebuilt from `modified_content`
+    /// against the file's current content.
+    Regenerated(String),
+}
+
+impl ApplyStrategy {
+    fn describe(&self) -> &'static str {
+        match self {
+            ApplyStrategy::Clean => "Patch applied",
+            ApplyStrategy::ThreeWay => "Patch applied with a three-way merge",
+            ApplyStrategy::Regenerated(_) => {
+                "Patch regenerated against current content and applied"
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct ShadowOutcome {
     status: MutationStatus,
@@ -55,11 +133,123 @@ struct ShadowOutcome {
     shadow_dir: PathBuf,
 }
 
+/// Whether a run gets its own copy of the project or continues in a workspace that already
+/// holds the earlier mutations of a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ShadowMode {
+    Fresh,
+    Shared,
+}
+
+/// Timeouts, output cap and CI environment for one pipeline run.
+#[derive(Debug, Clone)]
+struct PipelineLimits {
+    test_timeout: Duration,
+    static_check_timeout: Duration,
+    apply_timeout: Duration,
+    max_output_bytes: usize,
+    ci_env: BTreeMap<String, String>,
+}
+
+impl PipelineLimits {
+    fn from_env() -> Self {
+        Self {
+            test_timeout: Duration::from_secs(env_u64(
+                "AOP_PIPELINE_TEST_TIMEOUT_SECS",
+                DEFAULT_TEST_TIMEOUT_SECS,
+            )),
+            static_check_timeout: Duration::from_secs(env_u64(
+                "AOP_PIPELINE_STATIC_CHECK_TIMEOUT_SECS",
+                DEFAULT_STATIC_CHECK_TIMEOUT_SECS,
+            )),
+            apply_timeout: Duration::from_secs(env_u64(
+                "AOP_PIPELINE_APPLY_TIMEOUT_SECS",
+                DEFAULT_APPLY_TIMEOUT_SECS,
+            )),
+            max_output_bytes: (env_u64("AOP_PIPELINE_MAX_OUTPUT_KB", DEFAULT_MAX_OUTPUT_KB) * 1024)
+                as usize,
+            ci_env: parse_ci_env(&std::env::var("AOP_PIPELINE_CI_ENV").unwrap_or_default()),
+        }
+    }
+
+    fn resolve(overrides: Option<&PipelineLimitsInput>) -> Self {
+        let mut limits = Self::from_env();
+        let Some(overrides) = overrides else {
+            return limits;
+        };
+        if let Some(value) = overrides.test_timeout_secs.filter(|value| *value > 0) {
+            limits.test_timeout = Duration::from_secs(value);
+        }
+        if let Some(value) = overrides
+            .static_check_timeout_secs
+            .filter(|value| *value > 0)
+        {
+            limits.static_check_timeout = Duration::from_secs(value);
+        }
+        if let Some(value) = overrides.apply_timeout_secs.filter(|value| *value > 0) {
+            limits.apply_timeout = Duration::from_secs(value);
+        }
+        if let Some(value) = overrides.max_output_kb.filter(|value| *value > 0) {
+            limits.max_output_bytes = (value * 1024) as usize;
+        }
+        if let Some(env) = &overrides.ci_env {
+            limits.ci_env.extend(
+                env.iter()
+                    .filter(|(key, _)| !key.trim().is_empty())
+                    .map(|(key, value)| (key.trim().to_string(), value.clone())),
+            );
+        }
+        limits
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ShadowWorkspace {
+    root: PathBuf,
+    base: ShadowBase,
+    static_checks: Vec<StaticCheckOutcome>,
+}
+
+#[derive(Debug, Clone)]
+struct StaticCheckPlan {
+    step: &'static str,
+    program: String,
+    args: Vec<String>,
+    env: BTreeMap<String, String>,
+    label: String,
+    error_markers: &'static [&'static str],
+}
+
+#[derive(Debug, Clone)]
+struct StaticCheckOutcome {
+    step: &'static str,
+    label: String,
+    baseline_errors: usize,
+    errors: usize,
+    skipped_reason: Option<String>,
+}
+
+impl StaticCheckOutcome {
+    fn skipped(plan: &StaticCheckPlan, reason: String) -> Self {
+        Self {
+            step: plan.step,
+            label: plan.label.clone(),
+            baseline_errors: 0,
+            errors: 0,
+            skipped_reason: Some(reason),
+        }
+    }
+
+    fn introduced_errors(&self) -> usize {
+        self.errors.saturating_sub(self.baseline_errors)
+    }
+}
+
 #[derive(Debug, Clone)]
-struct CommandResult {
-    exit_code: i32,
-    stdout: String,
-    stderr: String,
+pub struct CommandResult {
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
 }
 
 #[derive(Debug, Clone)]
@@ -72,11 +262,234 @@ enum CiPlan {
     NoTests,
 }
 
+/// Runs the pipeline for one mutation. Mutations rejected at `shadow_test` are handed back
+/// to the specialist together with the failing output for a bounded number of repair
+/// attempts; the originating task is only marked failed once those attempts run out.
 pub async fn run_mutation_pipeline(
     pool: &SqlitePool,
+    secret_vault: &Arc<Mutex<SecretVault>>,
+    model_registry: &ModelRegistry,
+    input: RunMutationPipelineInput,
+) -> Result<MutationPipelineResult, String> {
+    let _execution_permit = task_runtime::acquire_execution_slots(
+        pool,
+        ExecutionLane::PipelineRun,
+        input.mutation_id.trim(),
+        Some(input.target_project.as_str()),
+        1,
+    )
+    .await?;
+    let mutation = mutations::get_mutation_by_id(pool, input.mutation_id.trim()).await?;
+    task_runtime::ensure_within_budget(pool, &mutation.task_id, "mutation_pipeline", "start")
+        .await?;
+    let max_attempts = input
+        .max_repair_attempts
+        .unwrap_or_else(repair_attempts_from_env)
+        .min(MAX_REPAIR_ATTEMPTS);
+    let original = run_pipeline_attempt(
+        pool,
+        secret_vault,
+        model_registry,
+        input.clone(),
+        max_attempts > 0,
+    )
+    .await?;
+    if max_attempts == 0 || !is_shadow_test_rejection(&original.mutation) {
+        return Ok(original);
+    }
+
+    let original_task = original.task;
+    let mut steps = original.steps;
+    let mut current = original.mutation;
+    let mut last_result: Option<MutationPipelineResult> = None;
+    let mut give_up_reason: Option<String> = None;
+
+    for attempt in 1..=max_attempts {
+        let failure_output = current.rejection_reason.clone().unwrap_or_default();
+        if let Err(error) = task_runtime::ensure_within_budget(
+            pool,
+            &current.task_id,
+            "mutation_pipeline",
+            &format!("auto_repair_{attempt}"),
+        )
+        .await
+        {
+            steps.push(PipelineStepResult {
+                step: "auto_repair".to_string(),
+                status: "failed".to_string(),
+                details: format!("Attempt {attempt}/{max_attempts}: {error}"),
+            });
+            give_up_reason = Some(error);
+            break;
+        }
+        let repair_source = match resolve_task_branch(pool, &current.task_id, &input).await {
+            Ok(branch) => source_project_for(branch.as_ref(), &input),
+            Err(_) => input.target_project.clone(),
+        };
+        let target_file_content = read_target_file_content(&repair_source, &current.file_path);
+        let revision = match mutation_revision::request_repair_revision(
+            pool,
+            model_registry,
+            &current.id,
+            &failure_output,
+            attempt,
+            target_file_content.as_deref(),
+        )
+        .await
+        {
+            Ok(value) => value,
+            Err(error) => {
+                steps.push(PipelineStepResult {
+                    step: "auto_repair".to_string(),
+                    status: "failed".to_string(),
+                    details: format!("Attempt {attempt}/{max_attempts}: {error}"),
+                });
+                give_up_reason = Some(error);
+                break;
+            }
+        };
+
+        steps.push(PipelineStepResult {
+            step: "auto_repair".to_string(),
+            status: "passed".to_string(),
+            details: format!(
+                "Attempt {attempt}/{max_attempts}: proposed mutation {} to repair {}.",
+                revision.revised_mutation.id, current.id
+            ),
+        });
+
+        let result = run_pipeline_attempt(
+            pool,
+            secret_vault,
+            model_registry,
+            RunMutationPipelineInput {
+                mutation_id: revision.revised_mutation.id.clone(),
+                ..input.clone()
+            },
+            false,
+        )
+        .await?;
+        steps.extend(result.steps.iter().cloned());
+        current = result.mutation.clone();
+        let retry = is_shadow_test_rejection(&result.mutation);
+        last_result = Some(result);
+        if !retry {
+            break;
+        }
+    }
+
+    let repaired = last_result
+        .as_ref()
+        .filter(|result| result.mutation.status != MutationStatus::Rejected.as_str());
+    let original_task = match repaired {
+        Some(result) => {
+            let (status, error_message) =
+                if result.mutation.status == MutationStatus::Applied.as_str() {
+                    (TaskStatus::Completed, None)
+                } else {
+                    (
+                        TaskStatus::Paused,
+                        Some(format!(
+                            "Repaired by mutation {}; waiting for Tier 1 approval before apply.",
+                            result.mutation.id
+                        )),
+                    )
+                };
+            tasks::update_task_outcome(
+                pool,
+                UpdateTaskOutcomeInput {
+                    task_id: original_task.id.clone(),
+                    status,
+                    token_usage: None,
+                    context_efficiency_ratio: None,
+                    compliance_score: None,
+                    checksum_before: None,
+                    checksum_after: None,
+                    error_message,
+                },
+            )
+            .await?
+        }
+        None => {
+            let reason = give_up_reason
+                .or_else(|| current.rejection_reason.clone())
+                .unwrap_or_else(|| "repaired mutation was rejected".to_string());
+            tasks::update_task_outcome(
+                pool,
+                UpdateTaskOutcomeInput {
+                    task_id: original_task.id.clone(),
+                    status: TaskStatus::Failed,
+                    token_usage: None,
+                    context_efficiency_ratio: None,
+                    compliance_score: Some(0),
+                    checksum_before: None,
+                    checksum_after: None,
+                    error_message: Some(format!(
+                        "Auto-repair gave up after {max_attempts} attempt(s): {reason}"
+                    )),
+                },
+            )
+            .await?
+        }
+    };
+
+    Ok(match last_result {
+        Some(result) => MutationPipelineResult { steps, ..result },
+        None => MutationPipelineResult {
+            mutation: current,
+            task: original_task,
+            steps,
+            shadow_dir: None,
+        },
+    })
+}
+
+/// Runs one attempt in its own shadow workspace and removes the workspace afterwards. A
+/// failed attempt keeps it for debugging when `AOP_SHADOW_KEEP_ON_FAILURE` is set.
+async fn run_pipeline_attempt(
+    pool: &SqlitePool,
+    secret_vault: &Arc<Mutex<SecretVault>>,
+    model_registry: &ModelRegistry,
     input: RunMutationPipelineInput,
+    defer_shadow_failure: bool,
 ) -> Result<MutationPipelineResult, String> {
     validate_input(&input)?;
+    let shadow_root = shadow_cache::create_shadow_dir()?;
+    let result = run_pipeline_in_shadow(
+        pool,
+        secret_vault,
+        model_registry,
+        input,
+        defer_shadow_failure,
+        &shadow_root,
+        ShadowMode::Fresh,
+    )
+    .await;
+
+    let failed = match &result {
+        Ok(value) => value.mutation.status == MutationStatus::Rejected.as_str(),
+        Err(_) => true,
+    };
+    let kept = shadow_cache::release_shadow_dir(
+        &shadow_root,
+        failed && shadow_cache::keep_shadow_on_failure(),
+    );
+    result.map(|mut value| {
+        value.shadow_dir = kept.then(|| shadow_root.to_string_lossy().to_string());
+        value
+    })
+}
+
+pub(crate) async fn run_pipeline_in_shadow(
+    pool: &SqlitePool,
+    secret_vault: &Arc<Mutex<SecretVault>>,
+    model_registry: &ModelRegistry,
+    input: RunMutationPipelineInput,
+    defer_shadow_failure: bool,
+    shadow_root: &Path,
+    shadow_mode: ShadowMode,
+) -> Result<MutationPipelineResult, String> {
+    let limits = PipelineLimits::resolve(input.limits.as_ref());
     let mutation = mutations::get_mutation_by_id(pool, input.mutation_id.trim()).await?;
     let task = tasks::get_task_by_id(pool, mutation.task_id.trim()).await?;
     let mut steps: Vec<PipelineStepResult> = Vec::new();
@@ -87,6 +500,18 @@ pub async fn run_mutation_pipeline(
     if mutation.status == MutationStatus::Rejected.as_str() {
         return Err(format!("Mutation '{}' is already rejected.", mutation.id));
     }
+    if mutation.status == MutationStatus::Expired.as_str() {
+        return Err(format!("Mutation '{}' has expired.", mutation.id));
+    }
+    let mutation = approved_patch_view(&mutation)?;
+    let policy = compliance::load_project_policy(&normalize_target_root(&input.target_project)?)?;
+    if let Some(approved) = mutation.approved_hunks.as_deref() {
+        steps.push(PipelineStepResult {
+            step: "hunk_selection".to_string(),
+            status: "passed".to_string(),
+            details: format!("Validating and applying only approved hunks {approved}."),
+        });
+    }
 
     metrics::record_audit_event(
         pool,
@@ -97,18 +522,43 @@ pub async fn run_mutation_pipeline(
     )
     .await?;
 
-    let shadow = match run_shadow_test(&mutation, &input).await {
-        Ok(value) => {
+    let allowed_protected = protected_paths::plan_allowlist(pool, &task.id).await?;
+    if let Err(error) = check_protected_mutation(&mutation, &allowed_protected) {
+        steps.push(PipelineStepResult {
+            step: "protected_paths".to_string(),
+            status: "failed".to_string(),
+            details: error.clone(),
+        });
+        return reject_pipeline(
+            pool,
+            mutation,
+            task,
+            steps,
+            "protected_paths",
+            &error,
+            None,
+            None,
+        )
+        .await;
+    }
+
+    let task_branch = match resolve_task_branch(pool, &task.id, &input).await {
+        Ok(Some(branch)) => {
             steps.push(PipelineStepResult {
-                step: "shadow_test".to_string(),
+                step: "task_branch".to_string(),
                 status: "passed".to_string(),
-                details: value.test_result.clone(),
+                details: format!(
+                    "Using branch '{}' in worktree '{}'.",
+                    branch.name,
+                    branch.worktree_dir.display()
+                ),
             });
-            value
+            Some(branch)
         }
+        Ok(None) => None,
         Err(error) => {
             steps.push(PipelineStepResult {
-                step: "shadow_test".to_string(),
+                step: "task_branch".to_string(),
                 status: "failed".to_string(),
                 details: error.clone(),
             });
@@ -117,7 +567,7 @@ pub async fn run_mutation_pipeline(
                 mutation,
                 task,
                 steps,
-                "shadow_test",
+                "task_branch",
                 &error,
                 None,
                 None,
@@ -125,59 +575,196 @@ pub async fn run_mutation_pipeline(
             .await;
         }
     };
+    let source_project = source_project_for(task_branch.as_ref(), &input);
+
+    if let Some(base_checksum) = mutation.base_checksum.clone() {
+        let current_checksum = checksum_for_target_file(&source_project, &mutation.file_path)?;
+        if current_checksum != base_checksum {
+            return pause_stale_mutation(pool, mutation, task, steps, &current_checksum).await;
+        }
+        steps.push(PipelineStepResult {
+            step: "base_check".to_string(),
+            status: "passed".to_string(),
+            details: format!(
+                "'{}' is unchanged since the proposal was generated.",
+                mutation.file_path
+            ),
+        });
+    }
 
-    let semantic_score = match semantic_similarity_score(&mutation, &shadow.shadow_dir) {
+    let prepared = prepare_shadow_workspace(
+        &mutation,
+        &source_project,
+        shadow_root,
+        shadow_mode,
+        &limits,
+    )
+    .await;
+    let workspace = match prepared {
         Ok(value) => value,
         Err(error) => {
             steps.push(PipelineStepResult {
-                step: "semantic_regression".to_string(),
+                step: "shadow_test".to_string(),
                 status: "failed".to_string(),
                 details: error.clone(),
             });
+            if defer_shadow_failure {
+                return defer_shadow_rejection(pool, mutation, task, steps, &error).await;
+            }
             return reject_pipeline(
                 pool,
                 mutation,
                 task,
                 steps,
-                "semantic_regression",
+                "shadow_test",
                 &error,
-                Some(shadow.test_result),
-                shadow.test_exit_code,
+                None,
+                None,
             )
             .await;
         }
     };
 
-    if semantic_score < SEMANTIC_THRESHOLD {
-        let message = format!(
-            "Intent similarity {:.3} is below threshold {:.3}.",
-            semantic_score, SEMANTIC_THRESHOLD
-        );
+    steps.push(PipelineStepResult {
+        step: "shadow_base".to_string(),
+        status: "passed".to_string(),
+        details: workspace.base.describe().to_string(),
+    });
+
+    for step in ["lint", "typecheck"] {
+        let checks = workspace
+            .static_checks
+            .iter()
+            .filter(|check| check.step == step)
+            .collect::<Vec<_>>();
+        if checks.is_empty() {
+            steps.push(PipelineStepResult {
+                step: step.to_string(),
+                status: "skipped".to_string(),
+                details: format!("No {step} tool detected for '{}'.", mutation.file_path),
+            });
+            continue;
+        }
+
+        if let Some(regressed) = checks.iter().find(|check| check.introduced_errors() > 0) {
+            let message = format!(
+                "{} reported {} new error(s) in '{}' (baseline {}, after patch {}).",
+                regressed.label,
+                regressed.introduced_errors(),
+                mutation.file_path,
+                regressed.baseline_errors,
+                regressed.errors
+            );
+            steps.push(PipelineStepResult {
+                step: step.to_string(),
+                status: "failed".to_string(),
+                details: message.clone(),
+            });
+            return reject_pipeline(pool, mutation, task, steps, step, &message, None, None).await;
+        }
+
+        let details = checks
+            .iter()
+            .map(|check| match &check.skipped_reason {
+                Some(reason) => format!("{} skipped: {}", check.label, reason),
+                None => format!(
+                    "{}: {} error(s), baseline {}",
+                    check.label, check.errors, check.baseline_errors
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        let status = if checks.iter().all(|check| check.skipped_reason.is_some()) {
+            "skipped"
+        } else {
+            "passed"
+        };
         steps.push(PipelineStepResult {
-            step: "semantic_regression".to_string(),
-            status: "failed".to_string(),
-            details: message.clone(),
+            step: step.to_string(),
+            status: status.to_string(),
+            details,
         });
-        return reject_pipeline(
-            pool,
-            mutation,
-            task,
-            steps,
-            "semantic_regression",
-            &message,
-            Some(shadow.test_result),
-            shadow.test_exit_code,
-        )
-        .await;
     }
 
-    steps.push(PipelineStepResult {
-        step: "semantic_regression".to_string(),
-        status: "passed".to_string(),
-        details: format!("Intent similarity {:.3}.", semantic_score),
-    });
+    let shadow = match run_shadow_test(
+        &workspace.root,
+        &mutation,
+        task.risk_factor,
+        &input,
+        &limits,
+    )
+    .await
+    {
+        Ok(value) => {
+            steps.push(PipelineStepResult {
+                step: "shadow_test".to_string(),
+                status: "passed".to_string(),
+                details: value.test_result.clone(),
+            });
+            value
+        }
+        Err(error) => {
+            steps.push(PipelineStepResult {
+                step: "shadow_test".to_string(),
+                status: "failed".to_string(),
+                details: error.clone(),
+            });
+            if defer_shadow_failure {
+                return defer_shadow_rejection(pool, mutation, task, steps, &error).await;
+            }
+            return reject_pipeline(
+                pool,
+                mutation,
+                task,
+                steps,
+                "shadow_test",
+                &error,
+                None,
+                None,
+            )
+            .await;
+        }
+    };
+
+    let semantic_review =
+        match semantic_regression_check(pool, model_registry, &task, &mutation, &shadow).await {
+            Ok(SemanticGate::Passed(details)) => {
+                steps.push(PipelineStepResult {
+                    step: "semantic_regression".to_string(),
+                    status: "passed".to_string(),
+                    details,
+                });
+                None
+            }
+            Ok(SemanticGate::NeedsReview(details)) => {
+                steps.push(PipelineStepResult {
+                    step: "semantic_regression".to_string(),
+                    status: "warning".to_string(),
+                    details: details.clone(),
+                });
+                (!input.accept_partial_alignment.unwrap_or(false)).then_some(details)
+            }
+            Ok(SemanticGate::Rejected(message)) | Err(message) => {
+                steps.push(PipelineStepResult {
+                    step: "semantic_regression".to_string(),
+                    status: "failed".to_string(),
+                    details: message.clone(),
+                });
+                return reject_pipeline(
+                    pool,
+                    mutation,
+                    task,
+                    steps,
+                    "semantic_regression",
+                    &message,
+                    Some(shadow.test_result),
+                    shadow.test_exit_code,
+                )
+                .await;
+            }
+        };
 
-    if let Err(error) = run_tier2_compliance_check(&task, &mutation) {
+    if let Err(error) = compliance::run_tier2_compliance_check(&policy, &task, &mutation) {
         steps.push(PipelineStepResult {
             step: "tier2_compliance".to_string(),
             status: "failed".to_string(),
@@ -211,6 +798,7 @@ pub async fn run_mutation_pipeline(
             test_exit_code: shadow.test_exit_code,
             rejection_reason: None,
             rejected_at_step: None,
+            approved_hunks: None,
         },
     )
     .await?;
@@ -221,7 +809,27 @@ pub async fn run_mutation_pipeline(
         details: format!("Mutation marked as {}.", updated_mutation.status),
     });
 
-    if !input.tier1_approved {
+    let missing_reviewers =
+        compliance::missing_required_reviewers(&policy, input.approved_by.as_deref());
+    if !input.tier1_approved || !missing_reviewers.is_empty() || semantic_review.is_some() {
+        let (task_message, pending_reason) = if !input.tier1_approved {
+            (
+                "Waiting for Tier 1 approval before apply.".to_string(),
+                "Validation complete. Tier 1 approval required.".to_string(),
+            )
+        } else if !missing_reviewers.is_empty() {
+            let message = format!(
+                "Project policy requires approval from: {}.",
+                missing_reviewers.join(", ")
+            );
+            (message.clone(), message)
+        } else {
+            let message = format!(
+                "Semantic judge found the change only partially aligned; confirm it to apply. {}",
+                semantic_review.unwrap_or_default()
+            );
+            (message.clone(), message)
+        };
         let updated_task = tasks::update_task_outcome(
             pool,
             UpdateTaskOutcomeInput {
@@ -232,7 +840,7 @@ pub async fn run_mutation_pipeline(
                 compliance_score: Some(70),
                 checksum_before: None,
                 checksum_after: None,
-                error_message: Some("Waiting for Tier 1 approval before apply.".to_string()),
+                error_message: Some(task_message),
             },
         )
         .await?;
@@ -240,7 +848,7 @@ pub async fn run_mutation_pipeline(
         steps.push(PipelineStepResult {
             step: "tier1_final_approval".to_string(),
             status: "pending".to_string(),
-            details: "Validation complete. Tier 1 approval required.".to_string(),
+            details: pending_reason,
         });
 
         return Ok(MutationPipelineResult {
@@ -257,39 +865,92 @@ pub async fn run_mutation_pipeline(
         details: "Tier 1 approval granted.".to_string(),
     });
 
-    let checksum_before =
-        checksum_for_target_file(&input.target_project, &updated_mutation.file_path)?;
-    let apply_details =
-        match apply_and_commit_mutation(&input.target_project, &updated_mutation).await {
-            Ok(value) => value,
-            Err(error) => {
-                steps.push(PipelineStepResult {
-                    step: "apply".to_string(),
-                    status: "failed".to_string(),
-                    details: error.clone(),
-                });
-                return reject_pipeline(
-                    pool,
-                    updated_mutation,
-                    task,
-                    steps,
-                    "apply",
-                    &error,
-                    Some(shadow.test_result),
-                    shadow.test_exit_code,
-                )
-                .await;
-            }
-        };
-
+    let checksum_before = checksum_for_target_file(&source_project, &updated_mutation.file_path)?;
+    let remote_server = input
+        .mcp_server
+        .as_deref()
+        .map(str::trim)
+        .filter(|server| !server.is_empty());
+    let commit_message = if remote_server.is_some() {
+        None
+    } else if auto_commit_enabled() || task_branch.is_some() {
+        let config =
+            commit_message::load_commit_config(&normalize_target_root(&input.target_project)?)?;
+        Some(
+            commit_message::compose_commit_message(
+                pool,
+                model_registry,
+                &config,
+                &task,
+                &updated_mutation,
+            )
+            .await,
+        )
+    } else {
+        None
+    };
+    let approved_view = approved_patch_view(&updated_mutation)?;
+    let applied = match remote_server {
+        Some(server) => {
+            apply_through_mcp(
+                pool,
+                secret_vault,
+                server,
+                &input.target_project,
+                &approved_view,
+                &allowed_protected,
+            )
+            .await
+        }
+        None => {
+            apply_and_commit_mutation(
+                &source_project,
+                &approved_view,
+                task_branch.as_ref().map(|branch| branch.name.as_str()),
+                commit_message.as_deref(),
+                &allowed_protected,
+                limits.apply_timeout,
+            )
+            .await
+        }
+    };
+    let (apply_details, apply_strategy) = match applied {
+        Ok(value) => value,
+        Err(error) => {
+            steps.push(PipelineStepResult {
+                step: "apply".to_string(),
+                status: "failed".to_string(),
+                details: error.clone(),
+            });
+            return reject_pipeline(
+                pool,
+                updated_mutation,
+                task,
+                steps,
+                "apply",
+                &error,
+                Some(shadow.test_result),
+                shadow.test_exit_code,
+            )
+            .await;
+        }
+    };
+
     steps.push(PipelineStepResult {
         step: "apply".to_string(),
         status: "passed".to_string(),
         details: apply_details,
     });
+    if let ApplyStrategy::Regenerated(diff) = &apply_strategy {
+        updated_mutation =
+            mutations::update_mutation_diff_content(pool, &updated_mutation.id, diff).await?;
+    }
 
-    let checksum_after =
-        checksum_for_target_file(&input.target_project, &updated_mutation.file_path)?;
+    // A remote apply leaves the local checkout as it was, so hash the intended content.
+    let checksum_after = match (remote_server, updated_mutation.modified_content.as_deref()) {
+        (Some(_), Some(content)) => format!("{:x}", Sha256::digest(content.as_bytes())),
+        _ => checksum_for_target_file(&source_project, &updated_mutation.file_path)?,
+    };
     updated_mutation = mutations::update_mutation_status(
         pool,
         UpdateMutationStatusInput {
@@ -299,6 +960,7 @@ pub async fn run_mutation_pipeline(
             test_exit_code: shadow.test_exit_code,
             rejection_reason: None,
             rejected_at_step: None,
+            approved_hunks: None,
         },
     )
     .await?;
@@ -308,7 +970,7 @@ pub async fn run_mutation_pipeline(
         UpdateTaskOutcomeInput {
             task_id: task.id,
             status: TaskStatus::Completed,
-            token_usage: Some(task.token_usage.saturating_add(450)),
+            token_usage: None,
             context_efficiency_ratio: Some(1.0),
             compliance_score: Some(85),
             checksum_before: Some(checksum_before),
@@ -317,6 +979,17 @@ pub async fn run_mutation_pipeline(
         },
     )
     .await?;
+    webhooks::emit(
+        pool,
+        secret_vault,
+        WebhookEvent::MutationApplied,
+        &updated_task.id,
+        &updated_task.objective,
+        serde_json::json!({
+            "mutationId": updated_mutation.id,
+            "filePath": updated_mutation.file_path,
+        }),
+    );
 
     let _ = indexer::index_project(pool, &input.target_project).await;
 
@@ -328,6 +1001,200 @@ pub async fn run_mutation_pipeline(
     })
 }
 
+/// The patch the pipeline validates and applies: only the reviewer-approved hunks when a
+/// partial approval was recorded, otherwise the stored diff unchanged.
+fn approved_patch_view(mutation: &MutationRecord) -> Result<MutationRecord, String> {
+    let Some(raw) = mutation.approved_hunks.as_deref() else {
+        return Ok(mutation.clone());
+    };
+    let approved: Vec<usize> = serde_json::from_str(raw)
+        .map_err(|error| format!("Invalid approved hunks for '{}': {error}", mutation.id))?;
+    let selection = patch_hunks::select_hunks(&mutation.diff_content, &approved)?;
+    Ok(MutationRecord {
+        diff_content: selection.approved_diff,
+        // The stored full-file content includes the rejected hunks, so it cannot be used
+        // to regenerate a pruned patch.
+        modified_content: None,
+        ..mutation.clone()
+    })
+}
+
+/// Returns the task branch worktree when branch mode is on for this run.
+pub(crate) async fn resolve_task_branch(
+    pool: &SqlitePool,
+    task_id: &str,
+    input: &RunMutationPipelineInput,
+) -> Result<Option<task_branch::TaskBranch>, String> {
+    let use_task_branch = input
+        .use_task_branch
+        .unwrap_or_else(task_branch::task_branch_mode_enabled);
+    if !use_task_branch {
+        return Ok(None);
+    }
+    let root_task_id = tasks::resolve_root_task_id(pool, task_id).await?;
+    task_branch::ensure_task_branch(&input.target_project, &root_task_id)
+        .await
+        .map(Some)
+}
+
+pub(crate) fn source_project_for(
+    task_branch: Option<&task_branch::TaskBranch>,
+    input: &RunMutationPipelineInput,
+) -> String {
+    task_branch
+        .map(|branch| branch.worktree_dir.to_string_lossy().to_string())
+        .unwrap_or_else(|| input.target_project.clone())
+}
+
+/// Parks a mutation whose target file changed after the proposal was generated. The
+/// mutation stays `stale_base` until `rebase_mutation` rebuilds its diff.
+async fn pause_stale_mutation(
+    pool: &SqlitePool,
+    mutation: MutationRecord,
+    task: TaskRecord,
+    mut steps: Vec<PipelineStepResult>,
+    current_checksum: &str,
+) -> Result<MutationPipelineResult, String> {
+    let reason = format!(
+        "'{}' changed since the proposal was generated; rebase the mutation before applying.",
+        mutation.file_path
+    );
+    steps.push(PipelineStepResult {
+        step: "base_check".to_string(),
+        status: "paused".to_string(),
+        details: reason.clone(),
+    });
+
+    let updated_mutation = mutations::update_mutation_status(
+        pool,
+        UpdateMutationStatusInput {
+            mutation_id: mutation.id.clone(),
+            status: MutationStatus::StaleBase,
+            test_result: None,
+            test_exit_code: None,
+            rejection_reason: None,
+            rejected_at_step: None,
+            approved_hunks: None,
+        },
+    )
+    .await?;
+    let updated_task = tasks::update_task_outcome(
+        pool,
+        UpdateTaskOutcomeInput {
+            task_id: task.id.clone(),
+            status: TaskStatus::Paused,
+            token_usage: None,
+            context_efficiency_ratio: None,
+            compliance_score: None,
+            checksum_before: None,
+            checksum_after: None,
+            error_message: Some(reason),
+        },
+    )
+    .await?;
+
+    metrics::record_audit_event(
+        pool,
+        "mutation_pipeline",
+        "mutation_stale_base",
+        Some(&mutation.id),
+        Some(
+            &serde_json::json!({
+                "filePath": mutation.file_path,
+                "baseChecksum": mutation.base_checksum,
+                "currentChecksum": current_checksum,
+            })
+            .to_string(),
+        ),
+    )
+    .await?;
+
+    Ok(MutationPipelineResult {
+        mutation: updated_mutation,
+        task: updated_task,
+        steps,
+        shadow_dir: None,
+    })
+}
+
+/// Rebuilds a `stale_base` mutation's diff against the file's current content by merging
+/// its stored `modified_content`, then returns it to `proposed` for a fresh validation run.
+pub async fn rebase_mutation(
+    pool: &SqlitePool,
+    input: RebaseMutationInput,
+) -> Result<MutationRecord, String> {
+    if input.mutation_id.trim().is_empty() {
+        return Err("mutationId is required".to_string());
+    }
+    if input.target_project.trim().is_empty() {
+        return Err("targetProject is required".to_string());
+    }
+
+    let mutation = mutations::get_mutation_by_id(pool, input.mutation_id.trim()).await?;
+    if mutation.status != MutationStatus::StaleBase.as_str() {
+        return Err(format!(
+            "Mutation '{}' is '{}'; only stale_base mutations can be rebased.",
+            mutation.id, mutation.status
+        ));
+    }
+    let Some(modified_content) = mutation.modified_content.clone() else {
+        return Err(format!(
+            "Mutation '{}' has no stored modified content; request a revision instead.",
+            mutation.id
+        ));
+    };
+
+    let pipeline_input = RunMutationPipelineInput {
+        mutation_id: mutation.id.clone(),
+        target_project: input.target_project.clone(),
+        tier1_approved: false,
+        ci_command: None,
+        ci_args: None,
+        max_repair_attempts: None,
+        use_task_branch: input.use_task_branch,
+        approved_by: None,
+        limits: None,
+        accept_partial_alignment: None,
+        mcp_server: None,
+    };
+    let task_branch = resolve_task_branch(pool, &mutation.task_id, &pipeline_input).await?;
+    let source_project = source_project_for(task_branch.as_ref(), &pipeline_input);
+
+    let current =
+        read_target_file_content(&source_project, &mutation.file_path).unwrap_or_default();
+    let current = current.replace("\r\n", "\n");
+    let merged = merge_modified_content(
+        &mutation,
+        &current,
+        &modified_content,
+        PipelineLimits::from_env().apply_timeout,
+    )
+        .await
+        .map_err(|error| format!("Failed to rebase mutation '{}': {error}", mutation.id))?;
+    if merged == current {
+        return Err(format!(
+            "'{}' already contains the proposed change; nothing to rebase.",
+            mutation.file_path
+        ));
+    }
+
+    let diff = compute_unified_diff(&mutation.file_path, &current, &merged);
+    let base_checksum = checksum_for_target_file(&source_project, &mutation.file_path)?;
+    let rebased =
+        mutations::rebase_mutation_diff(pool, &mutation.id, &diff, &base_checksum).await?;
+
+    metrics::record_audit_event(
+        pool,
+        "ui",
+        "mutation_rebased",
+        Some(&rebased.id),
+        Some(&serde_json::json!({ "baseChecksum": base_checksum }).to_string()),
+    )
+    .await?;
+
+    Ok(rebased)
+}
+
 fn validate_input(input: &RunMutationPipelineInput) -> Result<(), String> {
     if input.mutation_id.trim().is_empty() {
         return Err("mutationId is required".to_string());
@@ -339,13 +1206,26 @@ fn validate_input(input: &RunMutationPipelineInput) -> Result<(), String> {
     Ok(())
 }
 
-async fn run_shadow_test(
+async fn prepare_shadow_workspace(
     mutation: &MutationRecord,
-    input: &RunMutationPipelineInput,
-) -> Result<ShadowOutcome, String> {
-    let target_root = normalize_target_root(&input.target_project)?;
-    let shadow_root = create_shadow_dir()?;
-    copy_project_for_shadow(&target_root, &shadow_root)?;
+    source_project: &str,
+    shadow_root: &Path,
+    shadow_mode: ShadowMode,
+    limits: &PipelineLimits,
+) -> Result<ShadowWorkspace, String> {
+    let _timer = StepTimer::start("shadow_prepare");
+    let target_root = normalize_target_root(source_project)?;
+    let shadow_root = shadow_root.to_path_buf();
+    let base = match shadow_mode {
+        ShadowMode::Fresh => {
+            shadow_cache::materialize_shadow(&target_root, &shadow_root, &mutation.file_path)
+                .await?
+        }
+        ShadowMode::Shared => {
+            shadow_cache::detach_shadow_file(&shadow_root, &mutation.file_path)?;
+            ShadowBase::Shared
+        }
+    };
 
     let patch_content = normalize_patch_line_endings(&mutation.diff_content);
     validate_patch_format(&patch_content)?;
@@ -353,61 +1233,562 @@ async fn run_shadow_test(
     let patch_path = shadow_root.join("aop_mutation.patch");
     fs::write(&patch_path, &patch_content)
         .map_err(|error| format!("Failed to write patch in shadow dir: {error}"))?;
-    let patch_value = patch_path.to_string_lossy().to_string();
 
     normalize_file_line_endings_in_dir(&shadow_root, &mutation.file_path)?;
+    link_node_modules(&target_root, &shadow_root);
+
+    // Baselines are recorded before the patch lands so that pre-existing
+    // diagnostics in the mutated file do not count against the mutation.
+    let static_plans = detect_static_checks(&shadow_root, &target_root, &mutation.file_path);
+    let mut baselines = Vec::with_capacity(static_plans.len());
+    for plan in &static_plans {
+        baselines.push(
+            run_static_check(
+                &shadow_root,
+                plan,
+                &mutation.file_path,
+                limits.static_check_timeout,
+            )
+            .await,
+        );
+    }
 
-    run_command(&shadow_root, "git", &["init", "-q"], SHADOW_TIMEOUT).await?;
-    run_command(
-        &shadow_root,
-        "git",
-        &["apply", "--check", "--whitespace=nowarn", patch_value.as_str()],
-        SHADOW_TIMEOUT,
-    )
-    .await?;
-    run_command(
-        &shadow_root,
-        "git",
-        &["apply", "--whitespace=nowarn", patch_value.as_str()],
-        SHADOW_TIMEOUT,
-    )
-    .await?;
+    run_command(&shadow_root, "git", &["init", "-q"], limits.apply_timeout).await?;
+    apply_patch_with_fallback(&shadow_root, mutation, &patch_path, limits.apply_timeout).await?;
+
+    let mut static_checks = Vec::with_capacity(static_plans.len());
+    for (plan, baseline) in static_plans.into_iter().zip(baselines) {
+        let outcome = match baseline {
+            Ok(baseline_errors) => {
+                match run_static_check(
+                    &shadow_root,
+                    &plan,
+                    &mutation.file_path,
+                    limits.static_check_timeout,
+                )
+                .await
+                {
+                    Ok(errors) => StaticCheckOutcome {
+                        step: plan.step,
+                        label: plan.label,
+                        baseline_errors,
+                        errors,
+                        skipped_reason: None,
+                    },
+                    Err(error) => StaticCheckOutcome::skipped(&plan, error),
+                }
+            }
+            Err(error) => StaticCheckOutcome::skipped(&plan, error),
+        };
+        static_checks.push(outcome);
+    }
+
+    Ok(ShadowWorkspace {
+        root: shadow_root,
+        base,
+        static_checks,
+    })
+}
+
+async fn run_shadow_test(
+    shadow_root: &Path,
+    mutation: &MutationRecord,
+    risk_factor: f64,
+    input: &RunMutationPipelineInput,
+    limits: &PipelineLimits,
+) -> Result<ShadowOutcome, String> {
+    let _timer = StepTimer::start("shadow_test");
+    let target_root = normalize_target_root(&input.target_project)?;
+    let has_override = input
+        .ci_command
+        .as_deref()
+        .map(|value| !value.trim().is_empty())
+        .unwrap_or(false);
+    let affected_plan = if has_override {
+        None
+    } else {
+        detect_affected_test_plan(shadow_root, &target_root, &mutation.file_path)?
+    };
+
+    let sandbox = shadow_sandbox::resolve_sandbox(&target_root)?;
+    let mut summaries = Vec::new();
+    if let Some(config) = &sandbox {
+        summaries.push(format!("Sandboxed in {}.", config.describe()));
+    }
+    let mut last_exit_code = None;
+    if let Some(CiPlan::Command {
+        program,
+        args,
+        label,
+    }) = affected_plan.clone()
+    {
+        let result =
+            run_test_command(shadow_root, sandbox.as_ref(), limits, &program, args).await?;
+        summaries.push(format!("{label} passed (exit code {}).", result.exit_code));
+        last_exit_code = Some(i64::from(result.exit_code));
+
+        if !requires_full_suite(mutation, risk_factor) {
+            summaries.push(format!(
+                "Full suite skipped (risk {:.2} below {:.2}).",
+                risk_factor, FULL_SUITE_RISK_THRESHOLD
+            ));
+            return Ok(ShadowOutcome {
+                status: MutationStatus::Validated,
+                test_result: summaries.join(" "),
+                test_exit_code: last_exit_code,
+                shadow_dir: shadow_root.to_path_buf(),
+            });
+        }
+    }
 
     let ci_plan = detect_ci_plan(
-        &shadow_root,
+        shadow_root,
+        &target_root,
         input.ci_command.as_deref(),
         input.ci_args.clone(),
     )?;
-    let (status, test_result, test_exit_code) = match ci_plan {
-        CiPlan::NoTests => (
-            MutationStatus::ValidatedNoTests,
-            "No automated tests detected. Marked as validated_no_tests.".to_string(),
-            None,
-        ),
+    let status = match ci_plan {
+        CiPlan::NoTests if summaries.is_empty() => {
+            summaries
+                .push("No automated tests detected. Marked as validated_no_tests.".to_string());
+            MutationStatus::ValidatedNoTests
+        }
+        CiPlan::NoTests => MutationStatus::Validated,
         CiPlan::Command {
             program,
             args,
             label,
         } => {
-            let result = run_command_owned(&shadow_root, &program, args, SHADOW_TIMEOUT).await?;
-            (
-                MutationStatus::Validated,
-                format!("{label} passed (exit code {}).", result.exit_code),
-                Some(i64::from(result.exit_code)),
-            )
+            let result =
+                run_test_command(shadow_root, sandbox.as_ref(), limits, &program, args).await?;
+            summaries.push(format!("{label} passed (exit code {}).", result.exit_code));
+            last_exit_code = Some(i64::from(result.exit_code));
+            MutationStatus::Validated
         }
     };
 
     Ok(ShadowOutcome {
         status,
-        test_result,
-        test_exit_code,
-        shadow_dir: shadow_root,
+        test_result: summaries.join(" "),
+        test_exit_code: last_exit_code,
+        shadow_dir: shadow_root.to_path_buf(),
+    })
+}
+
+/// Runs a shadow test command on the host, or inside the project's sandbox container when
+/// sandboxing is enabled, with the run's CI environment, timeout and output cap.
+async fn run_test_command(
+    shadow_root: &Path,
+    sandbox: Option<&SandboxConfig>,
+    limits: &PipelineLimits,
+    program: &str,
+    args: Vec<String>,
+) -> Result<CommandResult, String> {
+    let no_env = BTreeMap::new();
+    let (program, args, env) = match sandbox {
+        Some(config) => {
+            let (runtime, wrapped) =
+                config.wrap_command(shadow_root, program, args, &limits.ci_env);
+            (runtime, wrapped, &no_env)
+        }
+        None => (program.to_string(), args, &limits.ci_env),
+    };
+    let mut result = spawn_command(shadow_root, &program, &args, env, limits.test_timeout).await?;
+    result.stdout = truncate_output(result.stdout, limits.max_output_bytes);
+    result.stderr = truncate_output(result.stderr, limits.max_output_bytes);
+    ensure_command_success(&program, &args, result)
+}
+
+/// High-risk tasks and edits to build manifests always run the full suite after the
+/// affected subset, since their blast radius is not captured by the file-to-test mapping.
+fn requires_full_suite(mutation: &MutationRecord, risk_factor: f64) -> bool {
+    if risk_factor >= FULL_SUITE_RISK_THRESHOLD {
+        return true;
+    }
+
+    let file_name = Path::new(&mutation.file_path)
+        .file_name()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default();
+    matches!(
+        file_name,
+        "package.json"
+            | "pnpm-lock.yaml"
+            | "tsconfig.json"
+            | "Cargo.toml"
+            | "Cargo.lock"
+            | "build.rs"
+            | "vite.config.ts"
+            | "vitest.config.ts"
+            | "jest.config.js"
+    )
+}
+
+/// Maps the mutated file to a focused test command. Returns `None` when no mapping is
+/// possible, in which case the caller runs the full suite instead. Installed test runners
+/// are looked up in `target_root`, since the shadow copy only links its `node_modules`.
+fn detect_affected_test_plan(
+    root: &Path,
+    target_root: &Path,
+    relative_file_path: &str,
+) -> Result<Option<CiPlan>, String> {
+    let extension = Path::new(relative_file_path)
+        .extension()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    if extension == "rs" {
+        let Some(package) = find_cargo_package_name(root, relative_file_path) else {
+            return Ok(None);
+        };
+        return Ok(Some(CiPlan::Command {
+            program: "cargo".to_string(),
+            args: vec![
+                "test".to_string(),
+                "--quiet".to_string(),
+                "-p".to_string(),
+                package.clone(),
+            ],
+            label: format!("cargo test -p {package}"),
+        }));
+    }
+
+    let is_script = matches!(
+        extension.as_str(),
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts"
+    );
+    if !is_script || !target_root.join("node_modules").exists() {
+        return Ok(None);
+    }
+
+    if let Some(vitest) = node_bin(target_root, "vitest") {
+        return Ok(Some(CiPlan::Command {
+            program: vitest,
+            args: vec![
+                "related".to_string(),
+                "--run".to_string(),
+                "--passWithNoTests".to_string(),
+                relative_file_path.to_string(),
+            ],
+            label: "vitest related".to_string(),
+        }));
+    }
+    if let Some(jest) = node_bin(target_root, "jest") {
+        return Ok(Some(CiPlan::Command {
+            program: jest,
+            args: vec![
+                "--findRelatedTests".to_string(),
+                relative_file_path.to_string(),
+                "--passWithNoTests".to_string(),
+            ],
+            label: "jest --findRelatedTests".to_string(),
+        }));
+    }
+
+    let related = find_related_test_files(root, relative_file_path)?;
+    if related.is_empty() {
+        return Ok(None);
+    }
+    let mut args = vec!["test".to_string(), "--".to_string()];
+    args.extend(related.iter().cloned());
+    Ok(Some(CiPlan::Command {
+        program: "pnpm".to_string(),
+        args,
+        label: format!("pnpm test ({} related file(s))", related.len()),
+    }))
+}
+
+/// Walks up from the mutated file to the closest manifest that declares a `[package]`.
+fn find_cargo_package_name(root: &Path, relative_file_path: &str) -> Option<String> {
+    let mut current = Path::new(relative_file_path).parent();
+    while let Some(dir) = current {
+        let manifest = root.join(dir).join("Cargo.toml");
+        if let Ok(raw) = fs::read_to_string(&manifest) {
+            if let Some(name) = parse_cargo_package_name(&raw) {
+                return Some(name);
+            }
+        }
+        current = dir.parent();
+    }
+    None
+}
+
+fn parse_cargo_package_name(manifest: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in manifest.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_package = trimmed == "[package]";
+            continue;
+        }
+        if !in_package {
+            continue;
+        }
+        if let Some((key, value)) = trimmed.split_once('=') {
+            if key.trim() == "name" {
+                let name = value.trim().trim_matches('"').trim();
+                if !name.is_empty() {
+                    return Some(name.to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Finds test files tied to the mutated module by naming convention (`foo.test.ts`,
+/// `foo.spec.ts`, `__tests__/foo.ts`) or by a test file importing the module.
+fn find_related_test_files(root: &Path, relative_file_path: &str) -> Result<Vec<String>, String> {
+    let source = Path::new(relative_file_path);
+    let stem = source
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .to_string();
+    if stem.is_empty() {
+        return Ok(Vec::new());
+    }
+    if is_test_file_name(&stem) {
+        return Ok(vec![relative_file_path.to_string()]);
+    }
+
+    let import_markers = [
+        format!("/{stem}'"),
+        format!("/{stem}\""),
+        format!("/{stem}.js'"),
+        format!("/{stem}.js\""),
+    ];
+    let mut related = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let entries = fs::read_dir(&dir).map_err(|error| {
+            format!(
+                "Failed to scan '{}' for related tests: {error}",
+                dir.display()
+            )
+        })?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path.is_dir() {
+                if !should_skip_shadow_dir(&name) {
+                    stack.push(path);
+                }
+                continue;
+            }
+
+            let file_stem = Path::new(&name)
+                .file_stem()
+                .and_then(OsStr::to_str)
+                .unwrap_or_default();
+            let in_tests_dir = path
+                .parent()
+                .and_then(Path::file_name)
+                .map(|value| value == "__tests__")
+                .unwrap_or(false);
+            if !is_test_file_name(file_stem) && !in_tests_dir {
+                continue;
+            }
+
+            let matches_name = file_stem == format!("{stem}.test")
+                || file_stem == format!("{stem}.spec")
+                || (in_tests_dir && file_stem == stem);
+            let matches_import = !matches_name
+                && fs::read_to_string(&path)
+                    .map(|content| import_markers.iter().any(|marker| content.contains(marker)))
+                    .unwrap_or(false);
+            if matches_name || matches_import {
+                if let Ok(relative) = path.strip_prefix(root) {
+                    related.push(relative.to_string_lossy().replace('\\', "/"));
+                }
+            }
+        }
+    }
+
+    related.sort();
+    related.dedup();
+    Ok(related)
+}
+
+fn is_test_file_name(stem: &str) -> bool {
+    stem.ends_with(".test") || stem.ends_with(".spec")
+}
+
+/// Picks the linters and type checkers that apply to the mutated file. Only tools that are
+/// already installed in the target project are used; nothing is fetched on demand.
+fn detect_static_checks(
+    root: &Path,
+    target_root: &Path,
+    relative_file_path: &str,
+) -> Vec<StaticCheckPlan> {
+    let extension = Path::new(relative_file_path)
+        .extension()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let mut plans = Vec::new();
+
+    let is_script = matches!(
+        extension.as_str(),
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts"
+    );
+    let is_typescript = matches!(extension.as_str(), "ts" | "tsx" | "mts" | "cts");
+
+    if is_script {
+        if let Some(eslint) = node_bin(root, "eslint") {
+            plans.push(StaticCheckPlan {
+                step: "lint",
+                program: eslint,
+                args: vec![
+                    "--no-color".to_string(),
+                    "--format".to_string(),
+                    "compact".to_string(),
+                    relative_file_path.to_string(),
+                ],
+                env: BTreeMap::new(),
+                label: "eslint".to_string(),
+                error_markers: &[", Error - "],
+            });
+        }
+    }
+
+    if is_typescript && root.join("tsconfig.json").exists() {
+        if let Some(tsc) = node_bin(root, "tsc") {
+            plans.push(StaticCheckPlan {
+                step: "typecheck",
+                program: tsc,
+                args: vec![
+                    "--noEmit".to_string(),
+                    "--pretty".to_string(),
+                    "false".to_string(),
+                    "-p".to_string(),
+                    "tsconfig.json".to_string(),
+                ],
+                env: BTreeMap::new(),
+                label: "tsc --noEmit".to_string(),
+                error_markers: &["error TS"],
+            });
+        }
+    }
+
+    // Clippy reuses the target project's own build cache so only the mutated crate is
+    // rechecked instead of compiling every dependency from scratch in the cold shadow.
+    if extension == "rs" && root.join("Cargo.toml").exists() {
+        plans.push(StaticCheckPlan {
+            step: "lint",
+            program: "cargo".to_string(),
+            args: vec![
+                "clippy".to_string(),
+                "--quiet".to_string(),
+                "--message-format".to_string(),
+                "short".to_string(),
+            ],
+            env: BTreeMap::from([(
+                "CARGO_TARGET_DIR".to_string(),
+                target_root.join("target").to_string_lossy().to_string(),
+            )]),
+            label: "cargo clippy".to_string(),
+            error_markers: &[": error"],
+        });
+    }
+
+    plans
+}
+
+/// Links the target's `node_modules`, which the shadow copy skips, into the shadow so the
+/// project's own linters, type checkers and test runners resolve their packages and configs.
+/// Where no link can be made (e.g. Windows without symlink rights) those checks are skipped.
+fn link_node_modules(target_root: &Path, shadow_root: &Path) {
+    let source = target_root.join("node_modules");
+    let link = shadow_root.join("node_modules");
+    if !source.is_dir() || link.symlink_metadata().is_ok() {
+        return;
+    }
+    #[cfg(unix)]
+    let _ = std::os::unix::fs::symlink(&source, &link);
+    #[cfg(windows)]
+    let _ = std::os::windows::fs::symlink_dir(&source, &link);
+}
+
+fn node_bin(root: &Path, name: &str) -> Option<String> {
+    let bin_dir = root.join("node_modules").join(".bin");
+    [name.to_string(), format!("{name}.cmd")]
+        .into_iter()
+        .map(|candidate| bin_dir.join(candidate))
+        .find(|path| path.is_file())
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+async fn run_static_check(
+    root: &Path,
+    plan: &StaticCheckPlan,
+    relative_file_path: &str,
+    timeout_duration: Duration,
+) -> Result<usize, String> {
+    let result =
+        spawn_command(root, &plan.program, &plan.args, &plan.env, timeout_duration).await?;
+    let combined = format!("{}\n{}", result.stdout, result.stderr);
+    Ok(count_file_diagnostics(
+        &combined,
+        root,
+        relative_file_path,
+        plan.error_markers,
+    ))
+}
+
+/// Counts diagnostic lines that mention the mutated file and carry one of the error markers.
+/// Tools print paths relative to `root` or absolute under it, so the path must start there:
+/// `src/session.ts` matches neither `src/session.tsx` nor `other/src/session.ts`.
+fn count_file_diagnostics(
+    output: &str,
+    root: &Path,
+    relative_file_path: &str,
+    markers: &[&str],
+) -> usize {
+    let needle = relative_file_path
+        .trim()
+        .trim_start_matches("./")
+        .replace('\\', "/");
+    if needle.is_empty() {
+        return 0;
+    }
+    let mut candidates = vec![needle.clone(), format!("./{needle}")];
+    let mut roots = vec![root.to_path_buf()];
+    if let Ok(canonical) = root.canonicalize() {
+        roots.push(canonical);
+    }
+    for root in roots {
+        let root = root.to_string_lossy().replace('\\', "/");
+        candidates.push(format!("{}/{needle}", root.trim_end_matches('/')));
+    }
+
+    output
+        .lines()
+        .map(|line| line.replace('\\', "/"))
+        .filter(|line| {
+            candidates
+                .iter()
+                .any(|candidate| mentions_path(line, candidate))
+        })
+        .filter(|line| markers.iter().any(|marker| line.contains(marker)))
+        .count()
+}
+
+/// Whether `path` appears in `line` as a whole path, starting at a token boundary rather
+/// than partway through a longer path.
+fn mentions_path(line: &str, path: &str) -> bool {
+    let is_path_char = |ch: char| ch.is_alphanumeric() || matches!(ch, '.' | '_' | '-' | '/');
+    line.match_indices(path).any(|(start, _)| {
+        let before = line[..start].chars().next_back();
+        let after = line[start + path.len()..].chars().next();
+        !before.is_some_and(is_path_char) && !after.is_some_and(is_path_char)
     })
 }
 
 fn detect_ci_plan(
     root: &Path,
+    target_root: &Path,
     override_command: Option<&str>,
     override_args: Option<Vec<String>>,
 ) -> Result<CiPlan, String> {
@@ -436,7 +1817,7 @@ fn detect_ci_plan(
             .unwrap_or(false);
 
         if has_test_script {
-            if root.join("node_modules").exists() {
+            if target_root.join("node_modules").exists() {
                 return Ok(CiPlan::Command {
                     program: "pnpm".to_string(),
                     args: vec!["test".to_string()],
@@ -459,8 +1840,86 @@ fn detect_ci_plan(
     Ok(CiPlan::NoTests)
 }
 
+enum SemanticGate {
+    Passed(String),
+    /// Judged `partially_aligned`: validated, but only applied once a human confirms.
+    NeedsReview(String),
+    Rejected(String),
+}
+
+/// Asks the semantic judge whether the diff meets the task objective given the test
+/// outcome. When the judge is off or unreachable the intent-embedding heuristic is used
+/// instead, unless the model adapter is strict.
+async fn semantic_regression_check(
+    pool: &SqlitePool,
+    model_registry: &ModelRegistry,
+    task: &TaskRecord,
+    mutation: &MutationRecord,
+    shadow: &ShadowOutcome,
+) -> Result<SemanticGate, String> {
+    let _timer = StepTimer::start("semantic_regression");
+    let mut fallback_note = String::new();
+    if semantic_judge::judge_enabled() {
+        let test_outcome = format!(
+            "status: {}, exit code: {}\n{}",
+            shadow.status.as_str(),
+            shadow
+                .test_exit_code
+                .map(|code| code.to_string())
+                .unwrap_or_else(|| "none".to_string()),
+            shadow.test_result
+        );
+        match semantic_judge::judge_mutation(pool, model_registry, task, mutation, &test_outcome)
+            .await
+        {
+            Ok(judgement) => {
+                metrics::record_audit_event(
+                    pool,
+                    "mutation_pipeline",
+                    "semantic_judgement",
+                    Some(&mutation.id),
+                    Some(
+                        &serde_json::json!({
+                            "verdict": judgement.verdict.as_str(),
+                            "model": judgement.model,
+                            "rationale": judgement.rationale,
+                        })
+                        .to_string(),
+                    ),
+                )
+                .await?;
+                let details = format!(
+                    "Judge {} rated the change {}: {}",
+                    judgement.model,
+                    judgement.verdict.as_str(),
+                    judgement.rationale
+                );
+                return Ok(match judgement.verdict {
+                    JudgeVerdict::Aligned => SemanticGate::Passed(details),
+                    JudgeVerdict::PartiallyAligned => SemanticGate::NeedsReview(details),
+                    JudgeVerdict::OffObjective => SemanticGate::Rejected(details),
+                });
+            }
+            Err(error) if semantic_judge::judge_required() => return Err(error),
+            Err(error) => {
+                fallback_note = format!(" Semantic judge unavailable, used heuristic: {error}");
+            }
+        }
+    }
+
+    let score = semantic_similarity_score(mutation, &shadow.shadow_dir)?;
+    if score < SEMANTIC_THRESHOLD {
+        return Ok(SemanticGate::Rejected(format!(
+            "Intent similarity {score:.3} is below threshold {SEMANTIC_THRESHOLD:.3}.{fallback_note}"
+        )));
+    }
+    Ok(SemanticGate::Passed(format!(
+        "Intent similarity {score:.3}.{fallback_note}"
+    )))
+}
+
 fn semantic_similarity_score(mutation: &MutationRecord, shadow_root: &Path) -> Result<f32, String> {
-    let target_file = resolve_target_file(shadow_root, &mutation.file_path)?;
+    let target_file = resolve_project_path(shadow_root, &mutation.file_path)?;
     let content = fs::read_to_string(target_file).unwrap_or_default();
     let preview = content.chars().take(1200).collect::<String>();
     let before = mutation
@@ -476,116 +1935,365 @@ fn semantic_similarity_score(mutation: &MutationRecord, shadow_root: &Path) -> R
     Ok(cosine_similarity(&left, &right))
 }
 
-fn run_tier2_compliance_check(task: &TaskRecord, mutation: &MutationRecord) -> Result<(), String> {
-    let extension = Path::new(&mutation.file_path)
-        .extension()
-        .and_then(OsStr::to_str)
-        .unwrap_or_default()
-        .to_ascii_lowercase();
-    let allowed = [
-        "ts", "tsx", "js", "jsx", "rs", "json", "css", "md", "py", "go", "java", "toml",
-    ];
-    if !allowed.iter().any(|value| *value == extension) {
+fn auto_commit_enabled() -> bool {
+    std::env::var("AOP_AUTO_COMMIT_MUTATIONS")
+        .map(|v| matches!(v.trim(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
+
+/// Applies the patch to the target project and commits it with `commit_message` when one
+/// is given. With a task branch the target is the branch worktree and every applied
+/// mutation is committed there, regardless of the auto-commit flag, so the branch history
+/// mirrors the pipeline runs.
+async fn apply_and_commit_mutation(
+    target_project: &str,
+    mutation: &MutationRecord,
+    task_branch: Option<&str>,
+    commit_message: Option<&str>,
+    allowed_protected: &[String],
+    apply_timeout: Duration,
+) -> Result<(String, ApplyStrategy), String> {
+    let _timer = StepTimer::start("apply");
+    let target_root = normalize_target_root(target_project)?;
+    if !target_root.join(".git").exists() {
         return Err(format!(
-            "File extension '.{}' is not allowed by compliance rules.",
-            extension
+            "Target project '{}' is not a git repository (.git missing).",
+            target_root.display()
         ));
     }
+    resolve_target_file(&target_root, &mutation.file_path, allowed_protected)?;
+    check_protected_mutation(mutation, allowed_protected)?;
+
+    let patch_content = normalize_patch_line_endings(&mutation.diff_content);
+    let patch_path = target_root.join(format!(".aop_apply_{}.patch", mutation.id));
+    fs::write(&patch_path, &patch_content)
+        .map_err(|error| format!("Failed to write apply patch file: {error}"))?;
+
+    normalize_file_line_endings_in_dir(&target_root, &mutation.file_path)?;
+
+    let strategy =
+        apply_patch_with_fallback(&target_root, mutation, &patch_path, apply_timeout).await;
+    let _ = fs::remove_file(&patch_path);
+    let strategy = strategy?;
+
+    if let Some(commit_message) = commit_message {
+        run_command_owned(
+            &target_root,
+            "git",
+            vec!["add".to_string(), mutation.file_path.clone()],
+            apply_timeout,
+        )
+        .await?;
+        run_command_owned(
+            &target_root,
+            "git",
+            vec![
+                "commit".to_string(),
+                "-m".to_string(),
+                commit_message.to_string(),
+            ],
+            apply_timeout,
+        )
+        .await?;
+
+        let details = match task_branch {
+            Some(branch) => format!(
+                "{} and committed on '{}' for '{}'.",
+                strategy.describe(),
+                branch,
+                mutation.file_path
+            ),
+            None => format!(
+                "{} and committed for '{}'.",
+                strategy.describe(),
+                mutation.file_path
+            ),
+        };
+        Ok((details, strategy))
+    } else {
+        Ok((
+            format!(
+                "{} for '{}' (auto-commit disabled).",
+                strategy.describe(),
+                mutation.file_path
+            ),
+            strategy,
+        ))
+    }
+}
+
+/// Sends the approved patch to a registered MCP server: a dry run first, so a patch that
+/// does not apply changes nothing, then the real apply. Commits are left to the remote side.
+async fn apply_through_mcp(
+    pool: &SqlitePool,
+    secret_vault: &Arc<Mutex<SecretVault>>,
+    server: &str,
+    target_project: &str,
+    mutation: &MutationRecord,
+    allowed_protected: &[String],
+) -> Result<(String, ApplyStrategy), String> {
+    let _timer = StepTimer::start("apply");
+    check_protected_mutation(mutation, allowed_protected)?;
+    let client = BridgeClient::new(
+        &crate::resolve_workspace_root()?,
+        SessionContext {
+            secret_vault: secret_vault.clone(),
+            db_pool: pool.clone(),
+            resources: Arc::default(),
+        },
+    );
+    let request = |dry_run: bool| ApplyTargetPatchInput {
+        target_project: target_project.to_string(),
+        patch: normalize_patch_line_endings(&mutation.diff_content),
+        dry_run: Some(dry_run),
+        allowed_protected_paths: Some(allowed_protected.to_vec()),
+        mcp_server: Some(server.to_string()),
+        mcp_command: None,
+        mcp_args: None,
+    };
+    tool_caller::apply_patch(&client, request(true))
+        .await
+        .map_err(|error| format!("MCP server '{server}' rejected the patch: {error}"))?;
+    tool_caller::apply_patch(&client, request(false))
+        .await
+        .map_err(|error| format!("MCP server '{server}' failed to apply the patch: {error}"))?;
+    Ok((
+        format!(
+            "Patch applied through MCP server '{server}' for '{}'.",
+            mutation.file_path
+        ),
+        ApplyStrategy::Clean,
+    ))
+}
 
-    let diff = mutation.diff_content.to_ascii_lowercase();
-    if diff.contains("<<<<<<<") || diff.contains(">>>>>>>") {
-        return Err("Diff contains unresolved conflict markers.".to_string());
+/// Applies the stored patch. When the file drifted since the proposal, retries with
+/// `git apply --3way` and, failing that, rebuilds the diff from the stored
+/// `modified_content` by merging it into the current file content.
+async fn apply_patch_with_fallback(
+    target_root: &Path,
+    mutation: &MutationRecord,
+    patch_path: &Path,
+    apply_timeout: Duration,
+) -> Result<ApplyStrategy, String> {
+    let patch_value = patch_path.to_string_lossy().to_string();
+    let check = run_command_capture(
+        target_root,
+        "git",
+        vec![
+            "apply".to_string(),
+            "--check".to_string(),
+            "--whitespace=nowarn".to_string(),
+            patch_value.clone(),
+        ],
+        apply_timeout,
+    )
+    .await?;
+    if check.exit_code == 0 {
+        run_command(
+            target_root,
+            "git",
+            &["apply", "--whitespace=nowarn", patch_value.as_str()],
+            apply_timeout,
+        )
+        .await?;
+        return Ok(ApplyStrategy::Clean);
     }
-    if task.domain == "auth"
-        && (diff.contains("bypass") || diff.contains("disable_auth") || diff.contains("skip auth"))
-    {
-        return Err("Auth mutation appears to bypass authentication controls.".to_string());
+
+    let target_file = resolve_project_path(target_root, &mutation.file_path)?;
+    let snapshot = fs::read(&target_file).ok();
+    let three_way = run_command_capture(
+        target_root,
+        "git",
+        vec![
+            "apply".to_string(),
+            "--3way".to_string(),
+            "--whitespace=nowarn".to_string(),
+            patch_value.clone(),
+        ],
+        apply_timeout,
+    )
+    .await?;
+    if three_way.exit_code == 0 {
+        return Ok(ApplyStrategy::ThreeWay);
     }
-    if task.domain == "database" && (diff.contains("drop table") || diff.contains("truncate ")) {
-        return Err("Database mutation contains destructive statements.".to_string());
+    restore_target_file(
+        target_root,
+        mutation,
+        &target_file,
+        snapshot.as_deref(),
+        apply_timeout,
+    )
+    .await?;
+
+    let apply_errors = format!(
+        "git apply:\n{}\ngit apply --3way:\n{}",
+        check.stderr.trim(),
+        three_way.stderr.trim()
+    );
+    let Some(modified_content) = mutation.modified_content.as_deref() else {
+        return Err(format!(
+            "Patch no longer applies to '{}' and no modified content is stored to regenerate it.\n{apply_errors}",
+            mutation.file_path
+        ));
+    };
+
+    let current = match snapshot {
+        Some(bytes) => String::from_utf8(bytes)
+            .map_err(|error| format!("Target file is not UTF-8: {error}"))?,
+        None => String::new(),
+    };
+    let merged = merge_modified_content(mutation, &current, modified_content, apply_timeout)
+        .await
+        .map_err(|error| {
+            format!(
+                "Patch no longer applies to '{}' and could not be regenerated: {error}\n{apply_errors}",
+                mutation.file_path
+            )
+        })?;
+    if merged == current {
+        return Err(format!(
+            "Regenerated diff for '{}' is empty; the file already contains the proposed change.",
+            mutation.file_path
+        ));
     }
 
-    Ok(())
+    let regenerated = compute_unified_diff(&mutation.file_path, &current, &merged);
+    fs::write(patch_path, normalize_patch_line_endings(&regenerated))
+        .map_err(|error| format!("Failed to write regenerated patch file: {error}"))?;
+    run_command(
+        target_root,
+        "git",
+        &["apply", "--whitespace=nowarn", patch_value.as_str()],
+        apply_timeout,
+    )
+    .await?;
+
+    Ok(ApplyStrategy::Regenerated(regenerated))
 }
 
-async fn apply_and_commit_mutation(
-    target_project: &str,
+/// Undoes a failed `git apply --3way`, which can leave conflict markers in the file and
+/// conflict stages in the index.
+async fn restore_target_file(
+    target_root: &Path,
     mutation: &MutationRecord,
-) -> Result<String, String> {
-    let target_root = normalize_target_root(target_project)?;
-    if !target_root.join(".git").exists() {
-        return Err(format!(
-            "Target project '{}' is not a git repository (.git missing).",
-            target_root.display()
-        ));
+    target_file: &Path,
+    snapshot: Option<&[u8]>,
+    apply_timeout: Duration,
+) -> Result<(), String> {
+    match snapshot {
+        Some(bytes) => fs::write(target_file, bytes)
+            .map_err(|error| format!("Failed to restore target file: {error}"))?,
+        None if target_file.exists() => fs::remove_file(target_file)
+            .map_err(|error| format!("Failed to remove partially applied file: {error}"))?,
+        None => {}
     }
+    let _ = run_command_capture(
+        target_root,
+        "git",
+        vec![
+            "reset".to_string(),
+            "-q".to_string(),
+            "--".to_string(),
+            mutation.file_path.clone(),
+        ],
+        apply_timeout,
+    )
+    .await;
+    Ok(())
+}
 
-    let patch_content = normalize_patch_line_endings(&mutation.diff_content);
-    let patch_path = target_root.join(format!(".aop_apply_{}.patch", mutation.id));
-    fs::write(&patch_path, &patch_content)
-        .map_err(|error| format!("Failed to write apply patch file: {error}"))?;
-    let patch_value = patch_path.to_string_lossy().to_string();
+/// Reconstructs the content the proposal was generated against by reverse-applying the
+/// stored diff to `modified_content`, then three-way merges the proposal into `current`.
+async fn merge_modified_content(
+    mutation: &MutationRecord,
+    current: &str,
+    modified_content: &str,
+    apply_timeout: Duration,
+) -> Result<String, String> {
+    let scratch = std::env::temp_dir().join(format!("aop_merge_{}", Uuid::new_v4()));
+    let result =
+        merge_in_scratch(&scratch, mutation, current, modified_content, apply_timeout).await;
+    let _ = fs::remove_dir_all(&scratch);
+    result
+}
 
-    normalize_file_line_endings_in_dir(&target_root, &mutation.file_path)?;
+async fn merge_in_scratch(
+    scratch: &Path,
+    mutation: &MutationRecord,
+    current: &str,
+    modified_content: &str,
+    apply_timeout: Duration,
+) -> Result<String, String> {
+    let proposal_file = resolve_project_path(&scratch.join("proposal"), &mutation.file_path)?;
+    if let Some(parent) = proposal_file.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| format!("Failed to create merge scratch directory: {error}"))?;
+    }
+    fs::write(&proposal_file, modified_content)
+        .map_err(|error| format!("Failed to write proposal content: {error}"))?;
+    let patch_path = scratch.join("proposal.patch");
+    fs::write(
+        &patch_path,
+        normalize_patch_line_endings(&mutation.diff_content),
+    )
+    .map_err(|error| format!("Failed to write merge patch: {error}"))?;
 
-    run_command(
-        &target_root,
+    let reverse = run_command_capture(
+        &scratch.join("proposal"),
         "git",
-        &["apply", "--check", "--whitespace=nowarn", patch_value.as_str()],
-        APPLY_TIMEOUT,
+        vec![
+            "apply".to_string(),
+            "-R".to_string(),
+            "--whitespace=nowarn".to_string(),
+            patch_path.to_string_lossy().to_string(),
+        ],
+        apply_timeout,
     )
     .await?;
-    run_command(
-        &target_root,
+    if reverse.exit_code != 0 {
+        return Err(format!(
+            "unable to reconstruct the proposal base: {}",
+            reverse.stderr.trim()
+        ));
+    }
+    let base = fs::read_to_string(&proposal_file).unwrap_or_default();
+
+    for (name, content) in [
+        ("current", current),
+        ("base", base.as_str()),
+        ("modified", modified_content),
+    ] {
+        fs::write(scratch.join(name), content)
+            .map_err(|error| format!("Failed to write merge input '{name}': {error}"))?;
+    }
+    let merge = run_command_capture(
+        scratch,
         "git",
-        &["apply", "--whitespace=nowarn", patch_value.as_str()],
-        APPLY_TIMEOUT,
+        vec![
+            "merge-file".to_string(),
+            "-p".to_string(),
+            "current".to_string(),
+            "base".to_string(),
+            "modified".to_string(),
+        ],
+        apply_timeout,
     )
     .await?;
-    let _ = fs::remove_file(&patch_path);
-
-    let auto_commit = std::env::var("AOP_AUTO_COMMIT_MUTATIONS")
-        .map(|v| matches!(v.trim(), "1" | "true" | "yes" | "on"))
-        .unwrap_or(false);
-
-    if auto_commit {
-        run_command_owned(
-            &target_root,
-            "git",
-            vec!["add".to_string(), mutation.file_path.clone()],
-            APPLY_TIMEOUT,
-        )
-        .await?;
-        run_command_owned(
-            &target_root,
-            "git",
-            vec![
-                "commit".to_string(),
-                "-m".to_string(),
-                format!("chore(aop): apply mutation {}", mutation.id),
-            ],
-            APPLY_TIMEOUT,
-        )
-        .await?;
-
-        Ok(format!(
-            "Patch applied and committed for '{}'.",
-            mutation.file_path
-        ))
-    } else {
-        Ok(format!(
-            "Patch applied for '{}' (auto-commit disabled).",
-            mutation.file_path
-        ))
+    match merge.exit_code {
+        0 => Ok(merge.stdout),
+        code if code > 0 => Err(format!(
+            "the proposal conflicts with the current content ({code} conflict(s))"
+        )),
+        _ => Err(format!("git merge-file failed: {}", merge.stderr.trim())),
     }
 }
 
-fn checksum_for_target_file(
+pub fn checksum_for_target_file(
     target_project: &str,
     relative_file_path: &str,
 ) -> Result<String, String> {
     let root = normalize_target_root(target_project)?;
-    let target_file = resolve_target_file(&root, relative_file_path)?;
+    let target_file = resolve_project_path(&root, relative_file_path)?;
     if !target_file.exists() {
         // New file — no content to checksum yet
         return Ok("new_file".to_string());
@@ -597,7 +2305,7 @@ fn checksum_for_target_file(
     Ok(format!("{:x}", hasher.finalize()))
 }
 
-fn normalize_target_root(target_project: &str) -> Result<PathBuf, String> {
+pub fn normalize_target_root(target_project: &str) -> Result<PathBuf, String> {
     let root = PathBuf::from(target_project.trim());
     let normalized = strip_unc_prefix(
         fs::canonicalize(root)
@@ -624,7 +2332,33 @@ fn strip_unc_prefix(path: PathBuf) -> PathBuf {
     }
 }
 
-fn resolve_target_file(project_root: &Path, relative_file_path: &str) -> Result<PathBuf, String> {
+/// Resolves the file a mutation writes to, refusing protected paths the plan did not
+/// allow-list.
+pub(crate) fn resolve_target_file(
+    project_root: &Path,
+    relative_file_path: &str,
+    allowed_protected: &[String],
+) -> Result<PathBuf, String> {
+    protected_paths::check_path(relative_file_path, allowed_protected)?;
+    resolve_project_path(project_root, relative_file_path)
+}
+
+/// Checks the mutation's file and every file its diff touches.
+fn check_protected_mutation(
+    mutation: &MutationRecord,
+    allowed_protected: &[String],
+) -> Result<(), String> {
+    protected_paths::check_path(&mutation.file_path, allowed_protected)?;
+    for path in protected_paths::paths_in_diff(&mutation.diff_content) {
+        protected_paths::check_path(&path, allowed_protected)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn resolve_project_path(
+    project_root: &Path,
+    relative_file_path: &str,
+) -> Result<PathBuf, String> {
     if relative_file_path.trim().is_empty() {
         return Err("mutation file path is empty".to_string());
     }
@@ -661,18 +2395,10 @@ fn resolve_target_file(project_root: &Path, relative_file_path: &str) -> Result<
     }
 }
 
-fn create_shadow_dir() -> Result<PathBuf, String> {
-    let dir = std::env::temp_dir().join(format!("aop_shadow_{}", Uuid::new_v4()));
-    fs::create_dir_all(&dir).map_err(|error| {
-        format!(
-            "Failed to create shadow directory '{}': {error}",
-            dir.display()
-        )
-    })?;
-    Ok(dir)
-}
-
-fn copy_project_for_shadow(source_root: &Path, destination_root: &Path) -> Result<(), String> {
+pub(crate) fn copy_project_for_shadow(
+    source_root: &Path,
+    destination_root: &Path,
+) -> Result<(), String> {
     let mut stack = vec![source_root.to_path_buf()];
 
     while let Some(dir) = stack.pop() {
@@ -753,7 +2479,7 @@ fn should_skip_shadow_dir(name: &str) -> bool {
     )
 }
 
-async fn run_command(
+pub async fn run_command(
     working_dir: &Path,
     program: &str,
     args: &[&str],
@@ -773,12 +2499,47 @@ async fn run_command_owned(
     program: &str,
     args: Vec<String>,
     timeout_duration: Duration,
+) -> Result<CommandResult, String> {
+    let result = spawn_command(
+        working_dir,
+        program,
+        &args,
+        &BTreeMap::new(),
+        timeout_duration,
+    )
+    .await?;
+    ensure_command_success(program, &args, result)
+}
+
+/// Like `run_command_owned`, but returns the captured output for any exit code. Linters
+/// signal findings through non-zero exits, which is not a failure to run the tool.
+pub async fn run_command_capture(
+    working_dir: &Path,
+    program: &str,
+    args: Vec<String>,
+    timeout_duration: Duration,
+) -> Result<CommandResult, String> {
+    spawn_command(
+        working_dir,
+        program,
+        &args,
+        &BTreeMap::new(),
+        timeout_duration,
+    )
+    .await
+}
+
+async fn spawn_command(
+    working_dir: &Path,
+    program: &str,
+    args: &[String],
+    env: &BTreeMap<String, String>,
+    timeout_duration: Duration,
 ) -> Result<CommandResult, String> {
     let mut command = Command::new(program);
     command.current_dir(working_dir);
-    for arg in &args {
-        command.arg(arg);
-    }
+    command.args(args);
+    command.envs(env);
 
     let output = timeout(timeout_duration, command.output())
         .await
@@ -791,12 +2552,19 @@ async fn run_command_owned(
         })?
         .map_err(|error| format!("Failed to run command '{program}': {error}"))?;
 
-    let result = CommandResult {
+    Ok(CommandResult {
         exit_code: output.status.code().unwrap_or(-1),
         stdout: String::from_utf8_lossy(&output.stdout).to_string(),
         stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-    };
-    if !output.status.success() {
+    })
+}
+
+fn ensure_command_success(
+    program: &str,
+    args: &[String],
+    result: CommandResult,
+) -> Result<CommandResult, String> {
+    if result.exit_code != 0 {
         return Err(format!(
             "Command '{program} {}' failed with exit code {}.\nstdout:\n{}\nstderr:\n{}",
             args.join(" "),
@@ -805,10 +2573,21 @@ async fn run_command_owned(
             result.stderr
         ));
     }
-
     Ok(result)
 }
 
+/// Keeps the tail of a command's output, where test runners print their summary.
+fn truncate_output(output: String, max_bytes: usize) -> String {
+    if output.len() <= max_bytes {
+        return output;
+    }
+    let mut start = output.len() - max_bytes;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("[... {} bytes truncated ...]\n{}", start, &output[start..])
+}
+
 fn normalize_patch_line_endings(patch: &str) -> String {
     let normalized = patch.replace("\r\n", "\n");
     if normalized.ends_with('\n') {
@@ -818,7 +2597,7 @@ fn normalize_patch_line_endings(patch: &str) -> String {
     }
 }
 
-fn validate_patch_format(patch: &str) -> Result<(), String> {
+pub fn validate_patch_format(patch: &str) -> Result<(), String> {
     if patch.trim().is_empty() {
         return Err("Patch content is empty.".to_string());
     }
@@ -884,6 +2663,67 @@ async fn reject_pipeline(
     test_result: Option<String>,
     test_exit_code: Option<i64>,
 ) -> Result<MutationPipelineResult, String> {
+    let updated_mutation = record_mutation_rejection(
+        pool,
+        &mutation,
+        rejected_step,
+        reason,
+        test_result,
+        test_exit_code,
+    )
+    .await?;
+
+    let updated_task = tasks::update_task_outcome(
+        pool,
+        UpdateTaskOutcomeInput {
+            task_id: task.id,
+            status: TaskStatus::Failed,
+            token_usage: None,
+            context_efficiency_ratio: None,
+            compliance_score: Some(0),
+            checksum_before: None,
+            checksum_after: None,
+            error_message: Some(reason.to_string()),
+        },
+    )
+    .await?;
+
+    Ok(MutationPipelineResult {
+        mutation: updated_mutation,
+        task: updated_task,
+        steps,
+        shadow_dir: None,
+    })
+}
+
+/// Rejects the mutation but leaves the task untouched, because an automatic repair
+/// attempt is about to follow.
+async fn defer_shadow_rejection(
+    pool: &SqlitePool,
+    mutation: MutationRecord,
+    task: TaskRecord,
+    steps: Vec<PipelineStepResult>,
+    reason: &str,
+) -> Result<MutationPipelineResult, String> {
+    let updated_mutation =
+        record_mutation_rejection(pool, &mutation, "shadow_test", reason, None, None).await?;
+
+    Ok(MutationPipelineResult {
+        mutation: updated_mutation,
+        task,
+        steps,
+        shadow_dir: None,
+    })
+}
+
+async fn record_mutation_rejection(
+    pool: &SqlitePool,
+    mutation: &MutationRecord,
+    rejected_step: &str,
+    reason: &str,
+    test_result: Option<String>,
+    test_exit_code: Option<i64>,
+) -> Result<MutationRecord, String> {
     let updated_mutation = mutations::update_mutation_status(
         pool,
         UpdateMutationStatusInput {
@@ -893,6 +2733,7 @@ async fn reject_pipeline(
             test_exit_code,
             rejection_reason: Some(reason.to_string()),
             rejected_at_step: Some(rejected_step.to_string()),
+            approved_hunks: None,
         },
     )
     .await?;
@@ -910,25 +2751,389 @@ async fn reject_pipeline(
     )
     .await?;
 
-    let updated_task = tasks::update_task_outcome(
-        pool,
-        UpdateTaskOutcomeInput {
-            task_id: task.id,
-            status: TaskStatus::Failed,
-            token_usage: None,
-            context_efficiency_ratio: None,
-            compliance_score: Some(0),
-            checksum_before: None,
-            checksum_after: None,
-            error_message: Some(reason.to_string()),
-        },
-    )
-    .await?;
+    Ok(updated_mutation)
+}
 
-    Ok(MutationPipelineResult {
-        mutation: updated_mutation,
-        task: updated_task,
-        steps,
-        shadow_dir: None,
-    })
+fn is_shadow_test_rejection(mutation: &MutationRecord) -> bool {
+    mutation.status == MutationStatus::Rejected.as_str()
+        && mutation.rejected_at_step.as_deref() == Some("shadow_test")
+}
+
+fn env_u64(key: &str, default: u64) -> u64 {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(default)
+}
+
+/// Parses `KEY=VALUE` pairs, one per line; blank lines and `#` comments are ignored.
+fn parse_ci_env(raw: &str) -> BTreeMap<String, String> {
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .filter(|(key, _)| !key.trim().is_empty())
+        .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+        .collect()
+}
+
+fn repair_attempts_from_env() -> u32 {
+    std::env::var("AOP_MUTATION_REPAIR_ATTEMPTS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .unwrap_or(DEFAULT_REPAIR_ATTEMPTS)
+}
+
+pub fn read_target_file_content(target_project: &str, relative_file_path: &str) -> Option<String> {
+    let root = normalize_target_root(target_project).ok()?;
+    let path = resolve_project_path(&root, relative_file_path).ok()?;
+    fs::read_to_string(path).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::db::mutations::CreateMutationInput;
+    use crate::db::tasks::CreateTaskInput;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+
+        pool
+    }
+
+    #[test]
+    fn counts_only_diagnostics_for_mutated_file() {
+        let output = "\
+/tmp/shadow/src/session.ts: line 3, col 7, Error - 'x' is unused (no-unused-vars)
+/tmp/shadow/src/session.ts: line 9, col 1, Warning - Unexpected console (no-console)
+/tmp/shadow/src/other.ts: line 1, col 1, Error - Parsing error
+/tmp/shadow/src/session.tsx: line 2, col 1, Error - Parsing error
+/tmp/shadow/lib/src/session.ts.bak: line 2, col 1, Error - Parsing error
+/tmp/shadow/other/src/session.ts: line 4, col 1, Error - Parsing error
+other/src/session.ts: line 5, col 1, Error - Parsing error
+./src/session.ts: line 6, col 1, Error - Parsing error";
+        let shadow = Path::new("/tmp/shadow");
+
+        assert_eq!(
+            count_file_diagnostics(output, shadow, "src/session.ts", &[", Error - "]),
+            2
+        );
+        assert_eq!(
+            count_file_diagnostics(output, shadow, "", &[", Error - "]),
+            0
+        );
+
+        let clippy = "\
+src/lib.rs:3:9: warning: unused variable: `x`
+src/lib.rs:7:5: error[E0308]: mismatched types
+src/lib.rs:9:1: error: this loop never actually loops";
+        let plans = {
+            let root = tempfile::tempdir().expect("tempdir should be created");
+            fs::write(
+                root.path().join("Cargo.toml"),
+                "[package]\nname = \"demo\"\n",
+            )
+            .expect("manifest should be written");
+            detect_static_checks(root.path(), root.path(), "src/lib.rs")
+        };
+        assert_eq!(
+            count_file_diagnostics(clippy, shadow, "src/lib.rs", plans[0].error_markers),
+            2
+        );
+    }
+
+    #[test]
+    fn detects_clippy_for_rust_files_only() {
+        let root = tempfile::tempdir().expect("tempdir should be created");
+        fs::write(
+            root.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\n",
+        )
+        .expect("manifest should be written");
+
+        let target = tempfile::tempdir().expect("tempdir should be created");
+        let rust_plans = detect_static_checks(root.path(), target.path(), "src/lib.rs");
+        assert_eq!(rust_plans.len(), 1);
+        assert_eq!(rust_plans[0].step, "lint");
+        assert_eq!(rust_plans[0].label, "cargo clippy");
+        assert_eq!(
+            rust_plans[0]
+                .env
+                .get("CARGO_TARGET_DIR")
+                .map(String::as_str),
+            Some(target.path().join("target").to_string_lossy().as_ref())
+        );
+
+        assert!(detect_static_checks(root.path(), target.path(), "src/app.ts").is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn runs_the_target_projects_linter_and_type_checker_in_the_shadow() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let target = tempfile::tempdir().expect("tempdir should be created");
+        let shadow = tempfile::tempdir().expect("tempdir should be created");
+        let bin_dir = target.path().join("node_modules").join(".bin");
+        fs::create_dir_all(&bin_dir).expect("bin dir should be created");
+        fs::create_dir_all(target.path().join("src")).expect("src dir should be created");
+        fs::write(
+            target.path().join("src").join("app.ts"),
+            "const x: number = 1;\n",
+        )
+        .expect("source write");
+        fs::write(target.path().join("tsconfig.json"), "{}\n").expect("tsconfig write");
+        for (name, output) in [
+            (
+                "eslint",
+                "$PWD/src/app.ts: line 1, col 7, Error - 'x' is unused (no-unused-vars)",
+            ),
+            (
+                "tsc",
+                "src/app.ts(1,7): error TS6133: 'x' is declared but never read.",
+            ),
+        ] {
+            let path = bin_dir.join(name);
+            fs::write(&path, format!("#!/bin/sh\necho \"{output}\"\n")).expect("stub write");
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+                .expect("stub should be executable");
+        }
+
+        copy_project_for_shadow(target.path(), shadow.path()).expect("shadow should copy");
+        assert!(detect_static_checks(shadow.path(), target.path(), "src/app.ts").is_empty());
+
+        link_node_modules(target.path(), shadow.path());
+        let plans = detect_static_checks(shadow.path(), target.path(), "src/app.ts");
+        assert_eq!(
+            plans
+                .iter()
+                .map(|plan| plan.label.as_str())
+                .collect::<Vec<_>>(),
+            vec!["eslint", "tsc --noEmit"]
+        );
+        for plan in &plans {
+            let errors = run_static_check(
+                shadow.path(),
+                plan,
+                "src/app.ts",
+                Duration::from_secs(DEFAULT_APPLY_TIMEOUT_SECS),
+            )
+            .await
+            .expect("stubbed check should run");
+            assert_eq!(errors, 1, "{} should report one error", plan.label);
+        }
+    }
+
+    #[test]
+    fn parses_package_name_from_manifest() {
+        let manifest =
+            "[workspace]\nmembers = []\n\n[package]\nname = \"aop\"\nversion = \"0.1.0\"\n";
+        assert_eq!(parse_cargo_package_name(manifest).as_deref(), Some("aop"));
+        assert_eq!(
+            parse_cargo_package_name("[workspace]\nname = \"x\"\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn finds_related_tests_by_name_and_import() {
+        let root = tempfile::tempdir().expect("tempdir should be created");
+        let src = root.path().join("src");
+        fs::create_dir_all(src.join("__tests__")).expect("test dir should be created");
+        fs::write(src.join("session.ts"), "export const x = 1;\n").expect("source write");
+        fs::write(src.join("session.test.ts"), "test('x', () => {});\n").expect("test write");
+        fs::write(
+            src.join("__tests__").join("flow.spec.ts"),
+            "import { x } from '../session';\n",
+        )
+        .expect("spec write");
+        fs::write(src.join("other.test.ts"), "import { y } from './other';\n")
+            .expect("unrelated write");
+
+        let related =
+            find_related_test_files(root.path(), "src/session.ts").expect("scan should succeed");
+        assert_eq!(
+            related,
+            vec![
+                "src/__tests__/flow.spec.ts".to_string(),
+                "src/session.test.ts".to_string(),
+            ]
+        );
+    }
+
+    fn test_mutation(file_path: &str, diff_content: String, modified: &str) -> MutationRecord {
+        MutationRecord {
+            id: "mutation-1".to_string(),
+            task_id: "task-1".to_string(),
+            agent_uid: "agent-1".to_string(),
+            file_path: file_path.to_string(),
+            diff_content,
+            intent_description: None,
+            intent_hash: None,
+            confidence: 0.8,
+            test_result: None,
+            test_exit_code: None,
+            rejection_reason: None,
+            rejected_at_step: None,
+            status: "validated".to_string(),
+            proposed_at: 0,
+            applied_at: None,
+            parent_mutation_id: None,
+            modified_content: Some(modified.to_string()),
+            base_checksum: None,
+            approved_hunks: None,
+            rejected_hunks_diff: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn regenerates_patch_when_context_drifted() {
+        let root = tempfile::tempdir().expect("tempdir should be created");
+        let original = "one\ntwo\nthree\nfour\nfive\nsix\nseven\n";
+        let modified = "one\ntwo\nthree\nfour\nFIVE\nsix\nseven\n";
+        let drifted = "one\ntwo\nTHREE\nfour\nfive\nsix\nseven\n";
+        fs::write(root.path().join("notes.txt"), drifted).expect("target write");
+        run_command(
+            root.path(),
+            "git",
+            &["init", "-q"],
+            Duration::from_secs(DEFAULT_APPLY_TIMEOUT_SECS),
+        )
+            .await
+            .expect("git init should succeed");
+
+        let mutation = test_mutation(
+            "notes.txt",
+            compute_unified_diff("notes.txt", original, modified),
+            modified,
+        );
+        let patch_path = root.path().join("mutation.patch");
+        fs::write(&patch_path, &mutation.diff_content).expect("patch write");
+
+        let strategy = apply_patch_with_fallback(
+            root.path(),
+            &mutation,
+            &patch_path,
+            Duration::from_secs(DEFAULT_APPLY_TIMEOUT_SECS),
+        )
+            .await
+            .expect("drifted patch should be regenerated");
+        assert!(matches!(strategy, ApplyStrategy::Regenerated(_)));
+        assert_eq!(
+            fs::read_to_string(root.path().join("notes.txt")).expect("target read"),
+            "one\ntwo\nTHREE\nfour\nFIVE\nsix\nseven\n"
+        );
+    }
+
+    #[test]
+    fn pipeline_limits_merge_overrides_and_truncate_output() {
+        let limits = PipelineLimits::resolve(Some(&PipelineLimitsInput {
+            test_timeout_secs: Some(5),
+            max_output_kb: Some(1),
+            ci_env: Some(BTreeMap::from([("CI".to_string(), "true".to_string())])),
+            ..PipelineLimitsInput::default()
+        }));
+        assert_eq!(limits.test_timeout, Duration::from_secs(5));
+        assert_eq!(limits.max_output_bytes, 1024);
+        assert_eq!(limits.ci_env.get("CI").map(String::as_str), Some("true"));
+
+        let output = format!("{}summary", "x".repeat(2000));
+        let truncated = truncate_output(output, 16);
+        assert!(truncated.starts_with("[... 1991 bytes truncated ...]"));
+        assert!(truncated.ends_with("xxxxxxxxxsummary"));
+
+        assert_eq!(
+            parse_ci_env("# comment\nNODE_ENV=test\n\nbad\nTOKEN=a=b\n"),
+            BTreeMap::from([
+                ("NODE_ENV".to_string(), "test".to_string()),
+                ("TOKEN".to_string(), "a=b".to_string()),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn rebase_mutation_rebuilds_stale_diff() {
+        let pool = setup_test_pool().await;
+        let root = tempfile::tempdir().expect("tempdir should be created");
+        let original = "one\ntwo\nthree\nfour\nfive\nsix\nseven\n";
+        let modified = "one\ntwo\nthree\nfour\nFIVE\nsix\nseven\n";
+        let drifted = "one\ntwo\nTHREE\nfour\nfive\nsix\nseven\n";
+        fs::write(root.path().join("notes.txt"), drifted).expect("target write");
+
+        let task = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 3,
+                domain: "platform".to_string(),
+                objective: "Shout five".to_string(),
+                token_budget: 1000,
+            },
+        )
+        .await
+        .expect("task should be created");
+        let mutation = mutations::create_mutation(
+            &pool,
+            CreateMutationInput {
+                task_id: task.id,
+                agent_uid: "agent-1".to_string(),
+                file_path: "notes.txt".to_string(),
+                diff_content: compute_unified_diff("notes.txt", original, modified),
+                intent_description: None,
+                intent_hash: None,
+                confidence: 0.8,
+                parent_mutation_id: None,
+                modified_content: Some(modified.to_string()),
+                base_checksum: Some("stale".to_string()),
+            },
+        )
+        .await
+        .expect("mutation should be created");
+        mutations::update_mutation_status(
+            &pool,
+            UpdateMutationStatusInput {
+                mutation_id: mutation.id.clone(),
+                status: MutationStatus::StaleBase,
+                test_result: None,
+                test_exit_code: None,
+                rejection_reason: None,
+                rejected_at_step: None,
+                approved_hunks: None,
+            },
+        )
+        .await
+        .expect("status should update");
+
+        let target_project = root.path().to_string_lossy().to_string();
+        let rebased = rebase_mutation(
+            &pool,
+            RebaseMutationInput {
+                mutation_id: mutation.id,
+                target_project: target_project.clone(),
+                use_task_branch: Some(false),
+            },
+        )
+        .await
+        .expect("stale mutation should rebase");
+
+        assert_eq!(rebased.status, "proposed");
+        assert_eq!(
+            rebased.base_checksum,
+            Some(checksum_for_target_file(&target_project, "notes.txt").expect("checksum"))
+        );
+        assert!(!rebased.diff_content.contains("-THREE"));
+        assert!(rebased.diff_content.contains("+FIVE"));
+    }
 }
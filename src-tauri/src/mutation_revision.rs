@@ -3,13 +3,21 @@ use sqlx::SqlitePool;
 
 use crate::agents::specialist::{self, SpecialistTask};
 use crate::agents::CodeBlock;
+use crate::db::attachments;
 use crate::db::metrics;
+use crate::db::model_arms::{self, ArmSegment};
 use crate::db::mutations::{
-    self, CreateMutationInput, MutationRecord, MutationStatus, UpdateMutationStatusInput,
+    self, CreateMutationInput, MutationRecord, MutationStatus, ReplaceMutationContentInput,
+    UpdateMutationStatusInput,
 };
 use crate::db::tasks::{self, CreateTaskRecordInput, TaskRecord, TaskStatus};
 use crate::llm_adapter;
 use crate::model_registry::ModelRegistry;
+use crate::mutation_pipeline;
+
+const REPAIR_OUTPUT_MAX_CHARS: usize = 4_000;
+const REVISION_PERSONA: &str = "revision_specialist";
+const REVISION_SKILL: &str = "mutation_revision";
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -18,6 +26,18 @@ pub struct RequestMutationRevisionInput {
     pub note: String,
 }
 
+/// A reviewer's hand edit. Exactly one of `diff_content` or `modified_content` is given;
+/// modified content is diffed against the current file in `target_project`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditMutationDiffInput {
+    pub mutation_id: String,
+    pub diff_content: Option<String>,
+    pub modified_content: Option<String>,
+    pub target_project: Option<String>,
+    pub intent_description: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MutationRevisionResult {
@@ -34,20 +54,273 @@ pub async fn request_mutation_revision(
     validate_input(&input)?;
 
     let base_mutation = mutations::get_mutation_by_id(pool, input.mutation_id.trim()).await?;
-    if base_mutation.status == MutationStatus::Applied.as_str() {
+    let applied = base_mutation.status == MutationStatus::Applied.as_str();
+    if applied && base_mutation.rejected_hunks_diff.is_none() {
         return Err(
             "Cannot request revision for an already applied mutation. Propose a new mutation instead."
                 .to_string(),
         );
     }
 
-    let parent_task = tasks::get_task_by_id(pool, base_mutation.task_id.trim()).await?;
     let revision_note = normalized_note(&input.note);
+    let mut constraints = vec![
+        "apply reviewer-requested revision".to_string(),
+        format!("reviewer_note: {}", revision_note),
+    ];
+    let mut extra_context = Vec::new();
+    if let Some(rejected_diff) = base_mutation.rejected_hunks_diff.as_deref() {
+        constraints.push(
+            "rework only the hunks in rejected_hunks; approved hunks are kept as they are"
+                .to_string(),
+        );
+        extra_context.push(CodeBlock {
+            file_path: "rejected_hunks".to_string(),
+            start_line: 1,
+            end_line: rejected_diff.lines().count().max(1) as u32,
+            content: rejected_diff.to_string(),
+            embedding: None,
+        });
+    }
+    let (revised_task, revised_mutation) = create_revision(
+        pool,
+        model_registry,
+        &base_mutation,
+        RevisionRequest {
+            objective: format!(
+                "Revision requested for mutation {} on {}. Note: {}",
+                base_mutation.id, base_mutation.file_path, revision_note
+            ),
+            constraints,
+            extra_context,
+            target_file_content: None,
+        },
+    )
+    .await?;
+
+    // A partially applied mutation keeps its approved hunks; only the remainder is revised.
+    let original_mutation = if applied {
+        base_mutation
+    } else {
+        mutations::update_mutation_status(
+            pool,
+            UpdateMutationStatusInput {
+                mutation_id: base_mutation.id.clone(),
+                status: MutationStatus::Rejected,
+                test_result: None,
+                test_exit_code: None,
+                rejection_reason: Some(format!("Revision requested: {}", revision_note)),
+                rejected_at_step: Some("diff_reviewer_revision_requested".to_string()),
+                approved_hunks: None,
+            },
+        )
+        .await?
+    };
+
+    metrics::record_audit_event(
+        pool,
+        "ui",
+        "mutation_revision_requested",
+        Some(original_mutation.id.as_str()),
+        Some(&format!(
+            "{{\"revisedTaskId\":\"{}\",\"revisedMutationId\":\"{}\"}}",
+            revised_task.id, revised_mutation.id
+        )),
+    )
+    .await?;
+
+    Ok(MutationRevisionResult {
+        original_mutation,
+        revised_task,
+        revised_mutation,
+    })
+}
+
+/// Stores a reviewer-edited diff. Validation results are cleared so the shadow tests run
+/// again against the edited patch.
+pub async fn edit_mutation_diff(
+    pool: &SqlitePool,
+    input: EditMutationDiffInput,
+) -> Result<MutationRecord, String> {
+    if input.mutation_id.trim().is_empty() {
+        return Err("mutationId is required".to_string());
+    }
+    let mutation = mutations::get_mutation_by_id(pool, input.mutation_id.trim()).await?;
+    if mutation.status == MutationStatus::Applied.as_str() {
+        return Err("Cannot edit an already applied mutation.".to_string());
+    }
+
+    let (diff_content, modified_content, base_checksum, source) = match (
+        input.diff_content.as_deref(),
+        input.modified_content.as_deref(),
+    ) {
+        (Some(diff), None) => {
+            let diff = diff.replace("\r\n", "\n");
+            (diff, None, mutation.base_checksum.clone(), "diff")
+        }
+        (None, Some(modified)) => {
+            let target_project = input
+                .target_project
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| {
+                    "targetProject is required when editing modified content".to_string()
+                })?;
+            let current =
+                mutation_pipeline::read_target_file_content(target_project, &mutation.file_path)
+                    .unwrap_or_default()
+                    .replace("\r\n", "\n");
+            let modified = modified.replace("\r\n", "\n");
+            let diff = specialist::compute_unified_diff(&mutation.file_path, &current, &modified);
+            let checksum =
+                mutation_pipeline::checksum_for_target_file(target_project, &mutation.file_path)?;
+            (diff, Some(modified), Some(checksum), "content")
+        }
+        _ => return Err("Provide exactly one of diffContent or modifiedContent.".to_string()),
+    };
+
+    mutation_pipeline::validate_patch_format(&diff_content)?;
+    if !diff_targets_file(&diff_content, &mutation.file_path) {
+        return Err(format!("Edited diff must target '{}'.", mutation.file_path));
+    }
+
+    let intent_description = input
+        .intent_description
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(ToOwned::to_owned)
+        .or_else(|| mutation.intent_description.clone())
+        .unwrap_or_else(|| format!("Human edit of {}", mutation.file_path));
+    let intent_hash = specialist::hash_intent_embedding(&intent_description);
+
+    let edited = mutations::replace_mutation_content(
+        pool,
+        ReplaceMutationContentInput {
+            mutation_id: mutation.id.clone(),
+            diff_content,
+            intent_description: Some(intent_description),
+            intent_hash: Some(intent_hash.clone()),
+            modified_content,
+            base_checksum,
+        },
+    )
+    .await?;
+
+    metrics::record_audit_event(
+        pool,
+        "ui",
+        "mutation_human_modified",
+        Some(edited.id.as_str()),
+        Some(
+            &serde_json::json!({
+                "source": source,
+                "previousStatus": mutation.status,
+                "previousIntentHash": mutation.intent_hash,
+                "intentHash": intent_hash,
+            })
+            .to_string(),
+        ),
+    )
+    .await?;
+
+    Ok(edited)
+}
+
+fn diff_targets_file(diff: &str, file_path: &str) -> bool {
+    let expected = file_path.trim().trim_start_matches("./");
+    diff.lines()
+        .filter_map(|line| line.strip_prefix("+++ "))
+        .map(|path| path.split('\t').next().unwrap_or_default().trim())
+        .any(|path| path.strip_prefix("b/").unwrap_or(path) == expected)
+}
+
+/// Asks the specialist to fix a mutation that failed shadow testing. The failing output and
+/// the rejected diff are handed back as context, and the new mutation is linked to the
+/// rejected one through `parent_mutation_id`.
+pub async fn request_repair_revision(
+    pool: &SqlitePool,
+    model_registry: &ModelRegistry,
+    mutation_id: &str,
+    failure_output: &str,
+    attempt: u32,
+    target_file_content: Option<&str>,
+) -> Result<MutationRevisionResult, String> {
+    let base_mutation = mutations::get_mutation_by_id(pool, mutation_id.trim()).await?;
+    if base_mutation.status != MutationStatus::Rejected.as_str() {
+        return Err(format!(
+            "Mutation '{}' must be rejected before an automatic repair can be requested.",
+            base_mutation.id
+        ));
+    }
+
+    let failure_excerpt = tail_chars(failure_output.trim(), REPAIR_OUTPUT_MAX_CHARS);
+    let (revised_task, revised_mutation) = create_revision(
+        pool,
+        model_registry,
+        &base_mutation,
+        RevisionRequest {
+            objective: format!(
+                "Repair attempt {} for mutation {} on {}: the previous diff failed shadow testing. Fix the failure while keeping the original intent: {}",
+                attempt,
+                base_mutation.id,
+                base_mutation.file_path,
+                base_mutation
+                    .intent_description
+                    .as_deref()
+                    .unwrap_or("no intent recorded")
+            ),
+            constraints: vec![
+                "fix the failing tests reported in shadow_test_output".to_string(),
+                "do not weaken or delete existing tests to make them pass".to_string(),
+                format!("repair_attempt: {attempt}"),
+            ],
+            extra_context: vec![CodeBlock {
+                file_path: "shadow_test_output".to_string(),
+                start_line: 1,
+                end_line: failure_excerpt.lines().count().max(1) as u32,
+                content: failure_excerpt,
+                embedding: None,
+            }],
+            target_file_content,
+        },
+    )
+    .await?;
+
+    metrics::record_audit_event(
+        pool,
+        "mutation_pipeline",
+        "mutation_repair_requested",
+        Some(base_mutation.id.as_str()),
+        Some(&format!(
+            "{{\"attempt\":{},\"revisedTaskId\":\"{}\",\"revisedMutationId\":\"{}\"}}",
+            attempt, revised_task.id, revised_mutation.id
+        )),
+    )
+    .await?;
+
+    Ok(MutationRevisionResult {
+        original_mutation: base_mutation,
+        revised_task,
+        revised_mutation,
+    })
+}
+
+struct RevisionRequest<'a> {
+    objective: String,
+    constraints: Vec<String>,
+    extra_context: Vec<CodeBlock>,
+    target_file_content: Option<&'a str>,
+}
+
+async fn create_revision(
+    pool: &SqlitePool,
+    model_registry: &ModelRegistry,
+    base_mutation: &MutationRecord,
+    request: RevisionRequest<'_>,
+) -> Result<(TaskRecord, MutationRecord), String> {
+    let parent_task = tasks::get_task_by_id(pool, base_mutation.task_id.trim()).await?;
     let revision_budget = revision_budget(parent_task.token_budget);
-    let revision_objective = format!(
-        "Revision requested for mutation {} on {}. Note: {}",
-        base_mutation.id, base_mutation.file_path, revision_note
-    );
 
     let revised_task = tasks::create_task_record(
         pool,
@@ -55,7 +328,7 @@ pub async fn request_mutation_revision(
             parent_id: Some(parent_task.id.clone()),
             tier: 3,
             domain: parent_task.domain.clone(),
-            objective: revision_objective.clone(),
+            objective: request.objective.clone(),
             token_budget: revision_budget,
             risk_factor: parent_task.risk_factor,
             status: TaskStatus::Pending,
@@ -63,44 +336,53 @@ pub async fn request_mutation_revision(
                 serde_json::to_string(&vec![base_mutation.file_path.clone()])
                     .unwrap_or_default(),
             ),
+            priority: parent_task.priority,
         },
     )
     .await?;
 
     let revision_model = model_registry.resolve_with_supported_providers(
         3,
-        Some("revision_specialist"),
+        Some(REVISION_PERSONA),
         &llm_adapter::supported_provider_aliases(),
     )?;
+    let mut code_context = vec![CodeBlock {
+        file_path: base_mutation.file_path.clone(),
+        start_line: 1,
+        end_line: 1,
+        content: base_mutation.diff_content.chars().take(1200).collect(),
+        embedding: None,
+    }];
+    code_context.extend(request.extra_context);
+    let mut constraints = request.constraints;
+    constraints.push(format!(
+        "revision_model: {}/{}",
+        revision_model.provider.as_str(),
+        revision_model.model_id.as_str()
+    ));
     let specialist_task = SpecialistTask {
         task_id: revised_task.id.clone(),
         parent_id: parent_task.id.clone(),
         tier: 3,
-        persona: "revision_specialist".to_string(),
-        objective: revision_objective.clone(),
+        persona: REVISION_PERSONA.to_string(),
+        objective: request.objective,
         token_budget: revision_budget.max(1) as u32,
         target_files: vec![base_mutation.file_path.clone()],
-        code_context: vec![CodeBlock {
-            file_path: base_mutation.file_path.clone(),
-            start_line: 1,
-            end_line: 1,
-            content: base_mutation.diff_content.chars().take(1200).collect(),
-            embedding: None,
-        }],
-        constraints: vec![
-            "apply reviewer-requested revision".to_string(),
-            format!("reviewer_note: {}", revision_note),
-            format!(
-                "revision_model: {}/{}",
-                revision_model.provider.as_str(),
-                revision_model.model_id.as_str()
-            ),
-        ],
+        code_context,
+        file_tree: Vec::new(),
+        constraints,
         model_provider: Some(revision_model.provider.clone()),
         model_id: Some(revision_model.model_id.clone()),
+        model_endpoint: revision_model.endpoint.clone(),
+        model_generation: revision_model.generation.clone(),
+        model_fallbacks: Vec::new(),
+        target_project: None,
+        attachments: attachments::design_images_for(pool, &revised_task).await?,
+        mcp_tools: Vec::new(),
     };
-    let proposal = specialist::run_specialist_task(&specialist_task, None)
-        .map_err(|error| format!("Failed to generate revised specialist proposal: {error}"))?;
+    let proposal =
+        specialist::run_specialist_task(&specialist_task, request.target_file_content, Some(pool))
+            .map_err(|error| format!("Failed to generate revised specialist proposal: {error}"))?;
 
     let revised_mutation = mutations::create_mutation(
         pool,
@@ -112,40 +394,22 @@ pub async fn request_mutation_revision(
             intent_description: Some(proposal.intent_description),
             intent_hash: Some(proposal.intent_hash),
             confidence: (proposal.confidence as f64).clamp(0.10, 1.0),
+            parent_mutation_id: Some(base_mutation.id.clone()),
+            modified_content: proposal.modified_content,
+            base_checksum: proposal.base_checksum,
         },
     )
     .await?;
-
-    let original_mutation = mutations::update_mutation_status(
+    model_arms::record_mutation_arm(
         pool,
-        UpdateMutationStatusInput {
-            mutation_id: base_mutation.id.clone(),
-            status: MutationStatus::Rejected,
-            test_result: None,
-            test_exit_code: None,
-            rejection_reason: Some(format!("Revision requested: {}", revision_note)),
-            rejected_at_step: Some("diff_reviewer_revision_requested".to_string()),
-        },
+        &revised_mutation.id,
+        &ArmSegment::new(3, Some(REVISION_PERSONA), Some(REVISION_SKILL)),
+        &revision_model.provider,
+        &revision_model.model_id,
     )
     .await?;
 
-    metrics::record_audit_event(
-        pool,
-        "ui",
-        "mutation_revision_requested",
-        Some(original_mutation.id.as_str()),
-        Some(&format!(
-            "{{\"revisedTaskId\":\"{}\",\"revisedMutationId\":\"{}\"}}",
-            revised_task.id, revised_mutation.id
-        )),
-    )
-    .await?;
-
-    Ok(MutationRevisionResult {
-        original_mutation,
-        revised_task,
-        revised_mutation,
-    })
+    Ok((revised_task, revised_mutation))
 }
 
 fn validate_input(input: &RequestMutationRevisionInput) -> Result<(), String> {
@@ -163,6 +427,16 @@ fn normalized_note(note: &str) -> String {
     note.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
+/// Keeps the end of the output, where test runners print the failure summary.
+fn tail_chars(value: &str, max_chars: usize) -> String {
+    let count = value.chars().count();
+    if count <= max_chars {
+        return value.to_string();
+    }
+    let tail = value.chars().skip(count - max_chars).collect::<String>();
+    format!("...[truncated]\n{tail}")
+}
+
 fn revision_budget(parent_budget: i64) -> i64 {
     let proportional = ((parent_budget.max(1) as f32) * 0.35).round() as i64;
     proportional.clamp(250, 2_000)
@@ -220,6 +494,9 @@ mod tests {
                 intent_description: Some("Add session loading checks".to_string()),
                 intent_hash: Some("abc123".to_string()),
                 confidence: 0.74,
+                parent_mutation_id: None,
+                modified_content: None,
+                base_checksum: None,
             },
         )
         .await
@@ -247,5 +524,181 @@ mod tests {
         assert_eq!(result.revised_mutation.status, "proposed");
         assert!(result.revised_mutation.diff_content.contains("AOP("));
         assert_ne!(result.revised_mutation.diff_content, mutation.diff_content);
+        assert_eq!(
+            result.revised_mutation.parent_mutation_id.as_deref(),
+            Some(mutation.id.as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn repair_revision_requires_rejected_mutation_and_links_parent() {
+        let pool = setup_test_pool().await;
+        let task = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 2,
+                domain: "frontend".to_string(),
+                objective: "Fix session flow".to_string(),
+                token_budget: 3400,
+            },
+        )
+        .await
+        .expect("task should be created");
+
+        let mutation = mutations::create_mutation(
+            &pool,
+            CreateMutationInput {
+                task_id: task.id.clone(),
+                agent_uid: Uuid::new_v4().to_string(),
+                file_path: "src/session.ts".to_string(),
+                diff_content: "--- a/src/session.ts\n+++ b/src/session.ts\n".to_string(),
+                intent_description: Some("Add session loading checks".to_string()),
+                intent_hash: Some("abc123".to_string()),
+                confidence: 0.74,
+                parent_mutation_id: None,
+                modified_content: None,
+                base_checksum: None,
+            },
+        )
+        .await
+        .expect("mutation should be created");
+
+        let model_registry = ModelRegistry::default();
+        let error = request_repair_revision(
+            &pool,
+            &model_registry,
+            &mutation.id,
+            "1 test failed",
+            1,
+            None,
+        )
+        .await
+        .expect_err("proposed mutation should not be repairable");
+        assert!(error.contains("must be rejected"));
+
+        mutations::update_mutation_status(
+            &pool,
+            UpdateMutationStatusInput {
+                mutation_id: mutation.id.clone(),
+                status: MutationStatus::Rejected,
+                test_result: None,
+                test_exit_code: None,
+                rejection_reason: Some("1 test failed".to_string()),
+                rejected_at_step: Some("shadow_test".to_string()),
+                approved_hunks: None,
+            },
+        )
+        .await
+        .expect("mutation should be rejected");
+
+        let result = request_repair_revision(
+            &pool,
+            &model_registry,
+            &mutation.id,
+            "FAIL src/session.test.ts\n  expected true, received false",
+            1,
+            Some("export const ready = false;\n"),
+        )
+        .await
+        .expect("repair request should succeed");
+
+        assert_eq!(
+            result.revised_mutation.parent_mutation_id.as_deref(),
+            Some(mutation.id.as_str())
+        );
+        assert_eq!(
+            result.revised_task.parent_id.as_deref(),
+            Some(task.id.as_str())
+        );
+        assert!(result
+            .revised_task
+            .objective
+            .starts_with("Repair attempt 1"));
+    }
+
+    #[tokio::test]
+    async fn edit_mutation_diff_resets_validation_and_rehashes_intent() {
+        let pool = setup_test_pool().await;
+        let task = tasks::create_task(
+            &pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 2,
+                domain: "frontend".to_string(),
+                objective: "Hand edit session diff".to_string(),
+                token_budget: 2000,
+            },
+        )
+        .await
+        .expect("task should be created");
+        let mutation = mutations::create_mutation(
+            &pool,
+            CreateMutationInput {
+                task_id: task.id,
+                agent_uid: Uuid::new_v4().to_string(),
+                file_path: "src/session.ts".to_string(),
+                diff_content: "--- a/src/session.ts\n+++ b/src/session.ts\n".to_string(),
+                intent_description: Some("Initial intent".to_string()),
+                intent_hash: Some("initial".to_string()),
+                confidence: 0.7,
+                parent_mutation_id: None,
+                modified_content: None,
+                base_checksum: None,
+            },
+        )
+        .await
+        .expect("mutation should be created");
+        mutations::update_mutation_status(
+            &pool,
+            UpdateMutationStatusInput {
+                mutation_id: mutation.id.clone(),
+                status: MutationStatus::Validated,
+                test_result: Some("ok".to_string()),
+                test_exit_code: Some(0),
+                rejection_reason: None,
+                rejected_at_step: None,
+                approved_hunks: None,
+            },
+        )
+        .await
+        .expect("status should update");
+
+        let edited_diff = "--- a/src/session.ts\n+++ b/src/session.ts\n@@ -1 +1 @@\n-a\n+b\n";
+        let wrong_file = edit_mutation_diff(
+            &pool,
+            EditMutationDiffInput {
+                mutation_id: mutation.id.clone(),
+                diff_content: Some(edited_diff.replace("session.ts", "other.ts")),
+                modified_content: None,
+                target_project: None,
+                intent_description: None,
+            },
+        )
+        .await;
+        assert!(wrong_file.is_err());
+
+        let edited = edit_mutation_diff(
+            &pool,
+            EditMutationDiffInput {
+                mutation_id: mutation.id.clone(),
+                diff_content: Some(edited_diff.to_string()),
+                modified_content: None,
+                target_project: None,
+                intent_description: Some("Reviewer tightened the change".to_string()),
+            },
+        )
+        .await
+        .expect("edit should succeed");
+
+        assert_eq!(edited.status, "proposed");
+        assert_eq!(edited.diff_content, edited_diff);
+        assert_eq!(edited.test_result, None);
+        assert_eq!(
+            edited.intent_hash,
+            Some(specialist::hash_intent_embedding(
+                "Reviewer tightened the change"
+            ))
+        );
     }
 }
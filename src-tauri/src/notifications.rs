@@ -0,0 +1,87 @@
+use std::sync::{Arc, OnceLock};
+
+/// Shows an OS notification with a title and a body.
+pub type NotificationSink = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+const MAX_BODY_CHARS: usize = 180;
+
+static SINK: OnceLock<NotificationSink> = OnceLock::new();
+
+/// Human-gate events worth interrupting the user for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    PlanReady,
+    ConsensusFailed,
+    BudgetRequest,
+    RunFinished,
+}
+
+impl NotificationKind {
+    fn env_key(self) -> &'static str {
+        match self {
+            NotificationKind::PlanReady => "AOP_NOTIFY_PLAN_READY",
+            NotificationKind::ConsensusFailed => "AOP_NOTIFY_CONSENSUS_FAILED",
+            NotificationKind::BudgetRequest => "AOP_NOTIFY_BUDGET_REQUEST",
+            NotificationKind::RunFinished => "AOP_NOTIFY_RUN_FINISHED",
+        }
+    }
+}
+
+/// Sends notifications to `sink`. Without one, nothing is shown.
+pub fn set_sink(sink: NotificationSink) {
+    let _ = SINK.set(sink);
+}
+
+/// Whether `kind` is switched on, through `AOP_NOTIFICATIONS` and its own flag.
+pub fn is_enabled(kind: NotificationKind) -> bool {
+    flag_enabled(std::env::var("AOP_NOTIFICATIONS").ok())
+        && flag_enabled(std::env::var(kind.env_key()).ok())
+}
+
+/// Shows a notification for `kind` unless the user turned it off. `body` is shortened
+/// to fit a notification banner.
+pub fn notify(kind: NotificationKind, title: &str, body: &str) {
+    if !is_enabled(kind) {
+        return;
+    }
+    if let Some(sink) = SINK.get() {
+        sink(title, &shorten(body));
+    }
+}
+
+/// Unset counts as on.
+fn flag_enabled(value: Option<String>) -> bool {
+    value.is_none_or(|value| {
+        !matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "0" | "false" | "no" | "off"
+        )
+    })
+}
+
+fn shorten(body: &str) -> String {
+    let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    if body.chars().count() <= MAX_BODY_CHARS {
+        return body;
+    }
+    let mut shortened = body.chars().take(MAX_BODY_CHARS - 1).collect::<String>();
+    shortened.push('…');
+    shortened
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_default_on_and_bodies_are_shortened() {
+        assert!(flag_enabled(None));
+        assert!(flag_enabled(Some("1".to_string())));
+        assert!(!flag_enabled(Some(" Off ".to_string())));
+
+        assert_eq!(shorten("Ship  the\nlogin page"), "Ship the login page");
+        let long = shorten(&"x".repeat(400));
+        assert_eq!(long.chars().count(), MAX_BODY_CHARS);
+        assert!(long.ends_with('…'));
+    }
+}
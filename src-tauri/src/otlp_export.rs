@@ -0,0 +1,583 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tokio::time::sleep;
+
+use crate::db::metrics::AuditLogEntry;
+use crate::db::tasks::{self, TaskRecord};
+use crate::db::telemetry::{AgentEventRecord, AgentRunRecord};
+
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(15);
+const EXPORT_INTERVAL: Duration = Duration::from_secs(30);
+/// Events per trace; longer runs keep their earliest events.
+const MAX_TRACE_EVENTS: i64 = 10_000;
+const SERVICE_NAME: &str = "aop";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTaskTraceInput {
+    pub root_task_id: String,
+    /// OTLP/HTTP collector base URL; defaults to `AOP_OTLP_ENDPOINT`.
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTaskTraceResult {
+    pub root_task_id: String,
+    pub trace_id: String,
+    pub span_count: usize,
+    pub endpoint: String,
+}
+
+/// Everything recorded under one root task, read once and turned into a trace.
+struct TraceSource {
+    tasks: Vec<TaskRecord>,
+    runs: Vec<AgentRunRecord>,
+    events: Vec<AgentEventRecord>,
+    /// Mutation pipeline audit entries, each with the task its mutation belongs to.
+    pipeline_entries: Vec<(String, AuditLogEntry)>,
+}
+
+/// The configured collector, or `None` when export is off.
+pub fn configured_endpoint() -> Option<String> {
+    std::env::var("AOP_OTLP_ENDPOINT")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Sends the trace of one root task to an OTLP/HTTP collector such as Jaeger or Tempo.
+pub async fn export_task_trace(
+    pool: &SqlitePool,
+    input: ExportTaskTraceInput,
+) -> Result<ExportTaskTraceResult, String> {
+    let root_task_id = input.root_task_id.trim();
+    if root_task_id.is_empty() {
+        return Err("rootTaskId is required".to_string());
+    }
+    let endpoint = input
+        .endpoint
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .or_else(configured_endpoint)
+        .ok_or_else(|| "No OTLP endpoint configured; set AOP_OTLP_ENDPOINT".to_string())?;
+
+    let source = load_trace_source(pool, root_task_id).await?;
+    let (request, span_count) = build_trace_request(root_task_id, &source, Utc::now().timestamp());
+    let url = traces_url(&endpoint);
+
+    let client = reqwest::Client::builder()
+        .timeout(EXPORT_TIMEOUT)
+        .build()
+        .map_err(|error| format!("Failed to build OTLP HTTP client: {error}"))?;
+    let mut builder = client.post(&url).json(&request);
+    for (name, value) in otlp_headers() {
+        builder = builder.header(name, value);
+    }
+    let response = builder
+        .send()
+        .await
+        .map_err(|error| format!("Failed to send trace to {url}: {error}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "OTLP collector rejected trace with status {status}: {}",
+            body.chars().take(500).collect::<String>()
+        ));
+    }
+
+    Ok(ExportTaskTraceResult {
+        root_task_id: root_task_id.to_string(),
+        trace_id: trace_id(root_task_id),
+        span_count,
+        endpoint: url,
+    })
+}
+
+/// Exports each root task once it completes or fails, while `AOP_OTLP_ENDPOINT` is set.
+/// Tasks finished before startup are left alone; a failed export is retried next tick.
+pub fn spawn_trace_exporter(pool: SqlitePool) {
+    tauri::async_runtime::spawn(async move {
+        let mut exported_until = Utc::now().timestamp();
+        loop {
+            sleep(EXPORT_INTERVAL).await;
+            let Some(endpoint) = configured_endpoint() else {
+                continue;
+            };
+            let Ok(finished) = sqlx::query_as::<_, (String, i64)>(
+                r#"
+                SELECT id, updated_at
+                FROM aop_tasks
+                WHERE parent_id IS NULL
+                  AND status IN ('completed', 'failed')
+                  AND updated_at > ?
+                ORDER BY updated_at ASC
+                "#,
+            )
+            .bind(exported_until)
+            .fetch_all(&pool)
+            .await
+            else {
+                continue;
+            };
+            for (root_task_id, updated_at) in finished {
+                let input = ExportTaskTraceInput {
+                    root_task_id,
+                    endpoint: Some(endpoint.clone()),
+                };
+                if export_task_trace(&pool, input).await.is_err() {
+                    break;
+                }
+                exported_until = updated_at;
+            }
+        }
+    });
+}
+
+async fn load_trace_source(pool: &SqlitePool, root_task_id: &str) -> Result<TraceSource, String> {
+    let mut tasks = Vec::new();
+    for task_id in tasks::collect_task_tree_ids(pool, root_task_id).await? {
+        tasks.push(tasks::get_task_by_id(pool, &task_id).await?);
+    }
+
+    let runs = sqlx::query_as::<_, AgentRunRecord>(
+        r#"
+        SELECT id, root_task_id, task_id, tier, actor, persona, skill, provider, model_id,
+               adapter_kind, status, started_at, ended_at, tokens_in, tokens_out, token_delta,
+               cost_usd, metadata_json
+        FROM aop_agent_runs
+        WHERE root_task_id = ?
+        ORDER BY started_at ASC
+        "#,
+    )
+    .bind(root_task_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to load agent runs for trace: {error}"))?;
+
+    let events = sqlx::query_as::<_, AgentEventRecord>(
+        r#"
+        SELECT id, run_id, root_task_id, task_id, tier, actor, action, status, phase, message,
+               provider, model_id, persona, skill, mcp_server, mcp_tool, latency_ms, retry_count,
+               tokens_in, tokens_out, token_delta, cost_usd, payload_json, created_at
+        FROM aop_agent_events
+        WHERE root_task_id = ?
+        ORDER BY id ASC
+        LIMIT ?
+        "#,
+    )
+    .bind(root_task_id)
+    .bind(MAX_TRACE_EVENTS)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to load agent events for trace: {error}"))?;
+
+    let mut pipeline_entries = Vec::new();
+    for task in &tasks {
+        let entries = sqlx::query_as::<_, (String, i64, i64, String, String, Option<String>)>(
+            r#"
+            SELECT m.task_id, a.id, a.timestamp, a.actor, a.action, a.details
+            FROM aop_audit_log a
+            JOIN aop_mutations m ON m.id = a.target_id
+            WHERE m.task_id = ? AND a.actor = 'mutation_pipeline'
+            ORDER BY a.id ASC
+            "#,
+        )
+        .bind(task.id.as_str())
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to load pipeline steps for trace: {error}"))?;
+        pipeline_entries.extend(entries.into_iter().map(
+            |(task_id, id, timestamp, actor, action, details)| {
+                let entry = AuditLogEntry {
+                    id,
+                    timestamp,
+                    actor,
+                    action,
+                    target_id: None,
+                    details,
+                };
+                (task_id, entry)
+            },
+        ));
+    }
+
+    Ok(TraceSource {
+        tasks,
+        runs,
+        events,
+        pipeline_entries,
+    })
+}
+
+/// An OTLP `ExportTraceServiceRequest` in JSON encoding. Tasks become spans nested like
+/// the task tree, runs become spans under their task, events with a latency become spans
+/// under their run and the remaining events and pipeline steps become span events.
+/// Span ids derive from record ids, so re-exporting a task replaces rather than duplicates.
+fn build_trace_request(root_task_id: &str, source: &TraceSource, now: i64) -> (Value, usize) {
+    let trace = trace_id(root_task_id);
+    let root_span = span_id("task", root_task_id);
+    let mut span_events: HashMap<String, Vec<Value>> = HashMap::new();
+    let mut spans = Vec::new();
+
+    let run_spans = source
+        .runs
+        .iter()
+        .map(|run| (run.id.as_str(), span_id("run", &run.id)))
+        .collect::<HashMap<_, _>>();
+    for event in &source.events {
+        let parent = event
+            .run_id
+            .as_deref()
+            .and_then(|run_id| run_spans.get(run_id).cloned())
+            .or_else(|| event.task_id.as_deref().map(|id| span_id("task", id)))
+            .unwrap_or_else(|| root_span.clone());
+        let attributes = event_attributes(event);
+        match event.latency_ms.filter(|latency| *latency > 0) {
+            Some(latency_ms) => {
+                let end = seconds_to_nanos(event.created_at);
+                spans.push(json!({
+                    "traceId": trace,
+                    "spanId": span_id("event", &event.id.to_string()),
+                    "parentSpanId": parent,
+                    "name": event.action,
+                    "kind": 1,
+                    "startTimeUnixNano": (end - latency_ms * 1_000_000).to_string(),
+                    "endTimeUnixNano": end.to_string(),
+                    "attributes": attributes,
+                    "status": span_status(event.status.as_deref(), event.message.as_deref()),
+                }));
+            }
+            None => span_events.entry(parent).or_default().push(json!({
+                "timeUnixNano": seconds_to_nanos(event.created_at).to_string(),
+                "name": event.action,
+                "attributes": attributes,
+            })),
+        }
+    }
+    for (task_id, entry) in &source.pipeline_entries {
+        let mut attributes = vec![attribute("aop.pipeline.actor", &entry.actor)];
+        if let Some(details) = &entry.details {
+            attributes.push(attribute("aop.pipeline.details", details));
+        }
+        span_events
+            .entry(span_id("task", task_id))
+            .or_default()
+            .push(json!({
+                "timeUnixNano": seconds_to_nanos(entry.timestamp).to_string(),
+                "name": entry.action,
+                "attributes": attributes,
+            }));
+    }
+
+    for task in &source.tasks {
+        let own_span = span_id("task", &task.id);
+        let finished = matches!(task.status.as_str(), "completed" | "failed");
+        let mut attributes = vec![
+            attribute("aop.task.id", &task.id),
+            attribute("aop.task.objective", &task.objective),
+            attribute("aop.task.status", &task.status),
+            int_attribute("aop.task.tier", task.tier),
+            int_attribute("aop.task.token_budget", task.token_budget),
+            int_attribute("aop.task.token_usage", task.token_usage),
+        ];
+        if let Some(error) = &task.error_message {
+            attributes.push(attribute("aop.task.error", error));
+        }
+        let parent_span = task
+            .parent_id
+            .as_deref()
+            .map(|id| span_id("task", id))
+            .unwrap_or_default();
+        let end = if finished { task.updated_at } else { now };
+        spans.push(json!({
+            "traceId": trace,
+            "spanId": own_span,
+            "parentSpanId": parent_span,
+            "name": format!("tier{} {}", task.tier, task.domain),
+            "kind": 1,
+            "startTimeUnixNano": seconds_to_nanos(task.created_at).to_string(),
+            "endTimeUnixNano": seconds_to_nanos(end).to_string(),
+            "attributes": attributes,
+            "events": span_events.remove(&own_span).unwrap_or_default(),
+            "status": span_status(Some(&task.status), task.error_message.as_deref()),
+        }));
+    }
+
+    for run in &source.runs {
+        let own_span = run_spans[run.id.as_str()].clone();
+        let mut attributes = vec![
+            attribute("aop.run.id", &run.id),
+            attribute("aop.run.status", &run.status),
+            int_attribute("gen_ai.usage.input_tokens", run.tokens_in),
+            int_attribute("gen_ai.usage.output_tokens", run.tokens_out),
+        ];
+        for (key, value) in [
+            ("aop.persona", &run.persona),
+            ("aop.skill", &run.skill),
+            ("gen_ai.system", &run.provider),
+            ("gen_ai.request.model", &run.model_id),
+            ("aop.adapter_kind", &run.adapter_kind),
+        ] {
+            if let Some(value) = value {
+                attributes.push(attribute(key, value));
+            }
+        }
+        if let Some(cost_usd) = run.cost_usd {
+            attributes.push(json!({
+                "key": "aop.cost_usd",
+                "value": { "doubleValue": cost_usd },
+            }));
+        }
+        let parent_span = span_id("task", run.task_id.as_deref().unwrap_or(root_task_id));
+        spans.push(json!({
+            "traceId": trace,
+            "spanId": own_span,
+            "parentSpanId": parent_span,
+            "name": run.actor,
+            "kind": 1,
+            "startTimeUnixNano": seconds_to_nanos(run.started_at).to_string(),
+            "endTimeUnixNano": seconds_to_nanos(run.ended_at.unwrap_or(now)).to_string(),
+            "attributes": attributes,
+            "events": span_events.remove(&own_span).unwrap_or_default(),
+            "status": span_status(Some(&run.status), None),
+        }));
+    }
+
+    let span_count = spans.len();
+    let request = json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [attribute("service.name", SERVICE_NAME)],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "aop.orchestrator" },
+                "spans": spans,
+            }],
+        }],
+    });
+    (request, span_count)
+}
+
+fn event_attributes(event: &AgentEventRecord) -> Vec<Value> {
+    let mut attributes = vec![attribute("aop.actor", &event.actor)];
+    for (key, value) in [
+        ("aop.status", &event.status),
+        ("aop.phase", &event.phase),
+        ("aop.message", &event.message),
+        ("gen_ai.system", &event.provider),
+        ("gen_ai.request.model", &event.model_id),
+        ("aop.mcp.server", &event.mcp_server),
+        ("aop.mcp.tool", &event.mcp_tool),
+    ] {
+        if let Some(value) = value {
+            attributes.push(attribute(key, value));
+        }
+    }
+    for (key, value) in [
+        ("gen_ai.usage.input_tokens", event.tokens_in),
+        ("gen_ai.usage.output_tokens", event.tokens_out),
+        ("aop.retry_count", event.retry_count),
+    ] {
+        if let Some(value) = value {
+            attributes.push(int_attribute(key, value));
+        }
+    }
+    attributes
+}
+
+/// `ERROR` for failures, `OK` for completions and unset otherwise.
+fn span_status(status: Option<&str>, message: Option<&str>) -> Value {
+    match status {
+        Some("failed") => json!({ "code": 2, "message": message.unwrap_or_default() }),
+        Some("completed") => json!({ "code": 1 }),
+        _ => json!({}),
+    }
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+fn int_attribute(key: &str, value: i64) -> Value {
+    json!({ "key": key, "value": { "intValue": value.to_string() } })
+}
+
+fn trace_id(root_task_id: &str) -> String {
+    hex_prefix(&format!("trace:{root_task_id}"), 16)
+}
+
+fn span_id(kind: &str, id: &str) -> String {
+    hex_prefix(&format!("{kind}:{id}"), 8)
+}
+
+fn hex_prefix(seed: &str, bytes: usize) -> String {
+    Sha256::digest(seed.as_bytes())[..bytes]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn seconds_to_nanos(seconds: i64) -> i64 {
+    seconds.saturating_mul(1_000_000_000)
+}
+
+/// Accepts a collector base URL or the full traces path.
+fn traces_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim().trim_end_matches('/');
+    if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{endpoint}/v1/traces")
+    }
+}
+
+/// `OTEL_EXPORTER_OTLP_HEADERS`, e.g. `authorization=Bearer abc,x-scope-orgid=team`.
+fn otlp_headers() -> Vec<(String, String)> {
+    std::env::var("OTEL_EXPORTER_OTLP_HEADERS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, parent_id: Option<&str>, status: &str) -> TaskRecord {
+        TaskRecord {
+            id: id.to_string(),
+            parent_id: parent_id.map(str::to_string),
+            tier: if parent_id.is_some() { 3 } else { 1 },
+            domain: "platform".to_string(),
+            objective: "ship it".to_string(),
+            status: status.to_string(),
+            token_budget: 1000,
+            token_usage: 100,
+            context_efficiency_ratio: 0.0,
+            risk_factor: 0.0,
+            compliance_score: 0,
+            checksum_before: None,
+            checksum_after: None,
+            error_message: None,
+            retry_count: 0,
+            created_at: 100,
+            updated_at: 160,
+            target_files: None,
+            allowed_protected_paths: None,
+            priority: 0,
+            deadline_seconds: None,
+            started_at: None,
+            queued_at: None,
+        }
+    }
+
+    fn event(id: i64, action: &str, latency_ms: Option<i64>) -> AgentEventRecord {
+        AgentEventRecord {
+            id,
+            run_id: Some("specialist::child".to_string()),
+            root_task_id: Some("root".to_string()),
+            task_id: Some("child".to_string()),
+            tier: Some(3),
+            actor: "specialist".to_string(),
+            action: action.to_string(),
+            status: Some("executing".to_string()),
+            phase: None,
+            message: None,
+            provider: Some("openai".to_string()),
+            model_id: Some("gpt-5".to_string()),
+            persona: None,
+            skill: None,
+            mcp_server: None,
+            mcp_tool: None,
+            latency_ms,
+            retry_count: None,
+            tokens_in: Some(10),
+            tokens_out: Some(5),
+            token_delta: None,
+            cost_usd: None,
+            payload_json: None,
+            created_at: 130,
+        }
+    }
+
+    #[test]
+    fn maps_tasks_runs_and_timed_events_to_nested_spans() {
+        let source = TraceSource {
+            tasks: vec![
+                task("root", None, "completed"),
+                task("child", Some("root"), "failed"),
+            ],
+            runs: vec![AgentRunRecord {
+                id: "specialist::child".to_string(),
+                root_task_id: Some("root".to_string()),
+                task_id: Some("child".to_string()),
+                tier: Some(3),
+                actor: "specialist".to_string(),
+                persona: None,
+                skill: None,
+                provider: Some("openai".to_string()),
+                model_id: Some("gpt-5".to_string()),
+                adapter_kind: None,
+                status: "completed".to_string(),
+                started_at: 110,
+                ended_at: Some(150),
+                tokens_in: 10,
+                tokens_out: 5,
+                token_delta: 0,
+                cost_usd: Some(0.01),
+                metadata_json: None,
+            }],
+            events: vec![event(1, "model_call", Some(2_000)), event(2, "note", None)],
+            pipeline_entries: Vec::new(),
+        };
+
+        let (request, span_count) = build_trace_request("root", &source, 200);
+        assert_eq!(span_count, 4);
+        let spans = request["resourceSpans"][0]["scopeSpans"][0]["spans"]
+            .as_array()
+            .expect("spans");
+        let by_name = |name: &str| {
+            spans
+                .iter()
+                .find(|span| span["name"] == name)
+                .unwrap_or_else(|| panic!("span {name}"))
+        };
+        let root = by_name("tier1 platform");
+        let child = by_name("tier3 platform");
+        let run = by_name("specialist");
+        let call = by_name("model_call");
+
+        assert_eq!(root["parentSpanId"], "");
+        assert_eq!(child["parentSpanId"], root["spanId"]);
+        assert_eq!(run["parentSpanId"], child["spanId"]);
+        assert_eq!(call["parentSpanId"], run["spanId"]);
+        assert_eq!(child["status"]["code"], 2);
+        assert_eq!(call["startTimeUnixNano"], "128000000000");
+        assert_eq!(run["events"][0]["name"], "note");
+        assert_eq!(trace_id("root").len(), 32);
+        assert!(spans.iter().all(|span| span["traceId"] == trace_id("root")));
+    }
+
+    #[test]
+    fn accepts_base_or_full_traces_url() {
+        assert_eq!(
+            traces_url("http://localhost:4318/"),
+            "http://localhost:4318/v1/traces"
+        );
+        assert_eq!(
+            traces_url("https://tempo.example/v1/traces"),
+            "https://tempo.example/v1/traces"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+/// Result of splitting a single-file unified diff into reviewer-approved and rejected parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkSelection {
+    pub approved_diff: String,
+    pub rejected_diff: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Hunk {
+    old_start: i64,
+    old_len: i64,
+    new_len: i64,
+    section: String,
+    body: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct ParsedPatch {
+    header: Vec<String>,
+    hunks: Vec<Hunk>,
+}
+
+/// Builds a patch containing only the hunks at `approved` (zero-based) and a second patch
+/// with the remainder. New-file line numbers are recomputed so each patch applies cleanly
+/// on its own against the original file.
+pub fn select_hunks(diff: &str, approved: &[usize]) -> Result<HunkSelection, String> {
+    let patch = parse_patch(diff)?;
+    if approved.is_empty() {
+        return Err("approvedHunks must contain at least one hunk index".to_string());
+    }
+    if let Some(index) = approved.iter().find(|index| **index >= patch.hunks.len()) {
+        return Err(format!(
+            "Hunk index {index} is out of range; the diff has {} hunk(s).",
+            patch.hunks.len()
+        ));
+    }
+
+    let mut approved_hunks = Vec::new();
+    let mut rejected_hunks = Vec::new();
+    for (index, hunk) in patch.hunks.iter().enumerate() {
+        if approved.contains(&index) {
+            approved_hunks.push(hunk);
+        } else {
+            rejected_hunks.push(hunk);
+        }
+    }
+
+    Ok(HunkSelection {
+        approved_diff: render_patch(&patch.header, &approved_hunks),
+        rejected_diff: (!rejected_hunks.is_empty())
+            .then(|| render_patch(&patch.header, &rejected_hunks)),
+    })
+}
+
+fn parse_patch(diff: &str) -> Result<ParsedPatch, String> {
+    let normalized = diff.replace("\r\n", "\n");
+    let mut header = Vec::new();
+    let mut hunks: Vec<Hunk> = Vec::new();
+
+    for line in normalized.lines() {
+        if line.starts_with("@@") {
+            hunks.push(parse_hunk_header(line)?);
+            continue;
+        }
+        match hunks.last_mut() {
+            None => header.push(line.to_string()),
+            Some(_) if line.starts_with("--- ") || line.starts_with("diff --git ") => {
+                return Err("Hunk review supports single-file diffs only.".to_string());
+            }
+            Some(hunk) => hunk.body.push(line.to_string()),
+        }
+    }
+
+    if hunks.is_empty() {
+        return Err("Diff does not contain any hunks.".to_string());
+    }
+    Ok(ParsedPatch { header, hunks })
+}
+
+/// Parses `@@ -old_start[,old_len] +new_start[,new_len] @@ section`.
+fn parse_hunk_header(line: &str) -> Result<Hunk, String> {
+    let invalid = || format!("Invalid hunk header '{line}'");
+    let rest = line.strip_prefix("@@ ").ok_or_else(invalid)?;
+    let (ranges, section) = rest.split_once(" @@").ok_or_else(invalid)?;
+    let mut parts = ranges.split_whitespace();
+    let old = parts
+        .next()
+        .and_then(|value| value.strip_prefix('-'))
+        .ok_or_else(invalid)?;
+    let new = parts
+        .next()
+        .and_then(|value| value.strip_prefix('+'))
+        .ok_or_else(invalid)?;
+    let (old_start, old_len) = parse_range(old).ok_or_else(invalid)?;
+    let (_, new_len) = parse_range(new).ok_or_else(invalid)?;
+
+    Ok(Hunk {
+        old_start,
+        old_len,
+        new_len,
+        section: section.to_string(),
+        body: Vec::new(),
+    })
+}
+
+fn parse_range(value: &str) -> Option<(i64, i64)> {
+    match value.split_once(',') {
+        Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+        None => Some((value.parse().ok()?, 1)),
+    }
+}
+
+fn render_patch(header: &[String], hunks: &[&Hunk]) -> String {
+    let mut output = header.join("\n");
+    if !output.is_empty() {
+        output.push('\n');
+    }
+
+    let mut offset = 0_i64;
+    for hunk in hunks {
+        let mut new_start = hunk.old_start + offset;
+        if hunk.old_len == 0 {
+            new_start += 1;
+        }
+        if hunk.new_len == 0 {
+            new_start -= 1;
+        }
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@{}\n",
+            hunk.old_start,
+            hunk.old_len,
+            new_start.max(0),
+            hunk.new_len,
+            hunk.section
+        ));
+        for line in &hunk.body {
+            output.push_str(line);
+            output.push('\n');
+        }
+        offset += hunk.new_len - hunk.old_len;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,4 @@
+ fn a() {}
++fn a2() {}
+ fn b() {}
+ fn c() {}
+@@ -10,3 +11,3 @@ impl X
+ fn j() {}
+-fn k() {}
++fn k2() {}
+ fn l() {}
+";
+
+    #[test]
+    fn select_hunks_recomputes_new_line_numbers() {
+        assert_eq!(parse_patch(DIFF).expect("diff should parse").hunks.len(), 2);
+
+        let selection = select_hunks(DIFF, &[1]).expect("selection should succeed");
+        assert!(selection
+            .approved_diff
+            .contains("@@ -10,3 +10,3 @@ impl X\n fn j() {}\n-fn k() {}\n+fn k2() {}\n"));
+        assert!(!selection.approved_diff.contains("fn a2"));
+        assert!(selection
+            .approved_diff
+            .starts_with("--- a/src/lib.rs\n+++ b/src/lib.rs\n"));
+
+        let rejected = selection.rejected_diff.expect("remainder should be kept");
+        assert!(rejected.contains("@@ -1,3 +1,4 @@\n fn a() {}\n+fn a2() {}\n"));
+        assert!(!rejected.contains("fn k2"));
+    }
+
+    #[test]
+    fn select_hunks_rejects_invalid_indices() {
+        assert!(select_hunks(DIFF, &[]).is_err());
+        assert!(select_hunks(DIFF, &[2]).is_err());
+
+        let full = select_hunks(DIFF, &[0, 1]).expect("full selection should succeed");
+        assert_eq!(full.rejected_diff, None);
+    }
+}
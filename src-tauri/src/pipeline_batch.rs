@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tokio::sync::Mutex;
+
+use crate::db::metrics;
+use crate::db::mutations::{self, ListTaskMutationsInput, MutationRecord, MutationStatus};
+use crate::db::tasks;
+use crate::model_registry::ModelRegistry;
+use crate::mutation_pipeline::{
+    normalize_target_root, resolve_project_path, resolve_task_branch, run_pipeline_in_shadow,
+    source_project_for, PipelineLimitsInput, PipelineStepResult, RunMutationPipelineInput,
+    ShadowMode,
+};
+use crate::secret_vault::SecretVault;
+use crate::shadow_cache;
+use crate::task_runtime::{self, ExecutionLane};
+
+const PENDING_STATUSES: &[&str] = &["proposed", "validated", "validated_no_tests"];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunMutationPipelineBatchInput {
+    /// A single task or a root task; every task in the tree is included.
+    pub task_id: String,
+    pub target_project: String,
+    pub tier1_approved: bool,
+    pub ci_command: Option<String>,
+    pub ci_args: Option<Vec<String>>,
+    pub use_task_branch: Option<bool>,
+    pub approved_by: Option<Vec<String>>,
+    pub limits: Option<PipelineLimitsInput>,
+    pub accept_partial_alignment: Option<bool>,
+    pub mcp_server: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchMutationResult {
+    pub mutation_id: String,
+    pub file_path: String,
+    /// Mutation status after the run, or `skipped` / `error`.
+    pub outcome: String,
+    pub details: String,
+    pub steps: Vec<PipelineStepResult>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MutationPipelineBatchResult {
+    pub task_id: String,
+    pub results: Vec<BatchMutationResult>,
+    /// Number of mutations per outcome.
+    pub summary: BTreeMap<String, usize>,
+    pub shadow_dir: Option<String>,
+}
+
+/// Runs every pending mutation of a task tree through one shared shadow workspace, so later
+/// mutations are validated on top of the earlier ones. Mutations are grouped by file in
+/// proposal order; once a mutation fails, later mutations of the same file are skipped
+/// because they were likely proposed on top of it. Auto-repair is not attempted in a batch.
+pub async fn run_mutation_pipeline_batch(
+    pool: &SqlitePool,
+    secret_vault: &Arc<Mutex<SecretVault>>,
+    model_registry: &ModelRegistry,
+    input: RunMutationPipelineBatchInput,
+) -> Result<MutationPipelineBatchResult, String> {
+    let task_id = input.task_id.trim().to_string();
+    if task_id.is_empty() {
+        return Err("taskId is required".to_string());
+    }
+    if input.target_project.trim().is_empty() {
+        return Err("targetProject is required".to_string());
+    }
+    let _execution_permit = task_runtime::acquire_execution_slots(
+        pool,
+        ExecutionLane::PipelineRun,
+        &task_id,
+        Some(input.target_project.as_str()),
+        1,
+    )
+    .await?;
+
+    let mut pending = Vec::new();
+    for tree_task_id in tasks::collect_task_tree_ids(pool, &task_id).await? {
+        let task_mutations = mutations::list_mutations_for_task(
+            pool,
+            ListTaskMutationsInput {
+                task_id: tree_task_id,
+            },
+        )
+        .await?;
+        pending.extend(
+            task_mutations
+                .into_iter()
+                .filter(|mutation| PENDING_STATUSES.contains(&mutation.status.as_str())),
+        );
+    }
+    let ordered = order_batch(pending);
+
+    let mut result = MutationPipelineBatchResult {
+        task_id: task_id.clone(),
+        results: Vec::new(),
+        summary: BTreeMap::new(),
+        shadow_dir: None,
+    };
+    let Some(first) = ordered.first() else {
+        return Ok(result);
+    };
+
+    let first_input = pipeline_input(&input, first);
+    let task_branch = resolve_task_branch(pool, &first.task_id, &first_input).await?;
+    let source_root =
+        normalize_target_root(&source_project_for(task_branch.as_ref(), &first_input))?;
+    let shadow_root = shadow_cache::create_shadow_dir()?;
+    if let Err(error) =
+        shadow_cache::materialize_shadow(&source_root, &shadow_root, &first.file_path).await
+    {
+        shadow_cache::release_shadow_dir(&shadow_root, false);
+        return Err(error);
+    }
+
+    let mut blocked_files = HashSet::new();
+    for mutation in &ordered {
+        if blocked_files.contains(&mutation.file_path) {
+            result.results.push(BatchMutationResult {
+                mutation_id: mutation.id.clone(),
+                file_path: mutation.file_path.clone(),
+                outcome: "skipped".to_string(),
+                details: "An earlier mutation of this file did not pass the pipeline.".to_string(),
+                steps: Vec::new(),
+            });
+            continue;
+        }
+
+        let snapshot = resolve_project_path(&shadow_root, &mutation.file_path)
+            .ok()
+            .and_then(|path| fs::read(path).ok());
+        let outcome = run_pipeline_in_shadow(
+            pool,
+            secret_vault,
+            model_registry,
+            pipeline_input(&input, mutation),
+            false,
+            &shadow_root,
+            ShadowMode::Shared,
+        )
+        .await;
+
+        let item = match outcome {
+            Ok(value) => BatchMutationResult {
+                mutation_id: value.mutation.id.clone(),
+                file_path: value.mutation.file_path.clone(),
+                outcome: value.mutation.status.clone(),
+                details: value
+                    .mutation
+                    .rejection_reason
+                    .clone()
+                    .or_else(|| value.task.error_message.clone())
+                    .unwrap_or_default(),
+                steps: value.steps,
+            },
+            Err(error) => BatchMutationResult {
+                mutation_id: mutation.id.clone(),
+                file_path: mutation.file_path.clone(),
+                outcome: "error".to_string(),
+                details: error,
+                steps: Vec::new(),
+            },
+        };
+        if !passed(&item.outcome) {
+            restore_shadow_file(&shadow_root, &mutation.file_path, snapshot.as_deref())?;
+            blocked_files.insert(mutation.file_path.clone());
+        }
+        result.results.push(item);
+    }
+
+    for item in &result.results {
+        *result.summary.entry(item.outcome.clone()).or_insert(0) += 1;
+    }
+    let failed = result.results.iter().any(|item| !passed(&item.outcome));
+    let kept = shadow_cache::release_shadow_dir(
+        &shadow_root,
+        failed && shadow_cache::keep_shadow_on_failure(),
+    );
+    result.shadow_dir = kept.then(|| shadow_root.to_string_lossy().to_string());
+
+    metrics::record_audit_event(
+        pool,
+        "mutation_pipeline",
+        "pipeline_batch_completed",
+        Some(&task_id),
+        Some(&serde_json::json!({ "summary": result.summary }).to_string()),
+    )
+    .await?;
+
+    Ok(result)
+}
+
+fn pipeline_input(
+    input: &RunMutationPipelineBatchInput,
+    mutation: &MutationRecord,
+) -> RunMutationPipelineInput {
+    RunMutationPipelineInput {
+        mutation_id: mutation.id.clone(),
+        target_project: input.target_project.clone(),
+        tier1_approved: input.tier1_approved,
+        ci_command: input.ci_command.clone(),
+        ci_args: input.ci_args.clone(),
+        max_repair_attempts: Some(0),
+        use_task_branch: input.use_task_branch,
+        approved_by: input.approved_by.clone(),
+        limits: input.limits.clone(),
+        accept_partial_alignment: input.accept_partial_alignment,
+        mcp_server: input.mcp_server.clone(),
+    }
+}
+
+fn passed(outcome: &str) -> bool {
+    outcome == MutationStatus::Applied.as_str()
+        || outcome == MutationStatus::Validated.as_str()
+        || outcome == MutationStatus::ValidatedNoTests.as_str()
+}
+
+/// Proposal order, with all mutations of one file kept together at the position of the
+/// file's first proposal.
+fn order_batch(mut pending: Vec<MutationRecord>) -> Vec<MutationRecord> {
+    pending.sort_by(|left, right| {
+        left.proposed_at
+            .cmp(&right.proposed_at)
+            .then_with(|| left.id.cmp(&right.id))
+    });
+    let mut file_order: Vec<String> = Vec::new();
+    for mutation in &pending {
+        if !file_order.contains(&mutation.file_path) {
+            file_order.push(mutation.file_path.clone());
+        }
+    }
+    pending.sort_by_key(|mutation| {
+        file_order
+            .iter()
+            .position(|file_path| *file_path == mutation.file_path)
+            .unwrap_or(usize::MAX)
+    });
+    pending
+}
+
+/// Drops a failed mutation's edit so it does not leak into the next run of the batch.
+fn restore_shadow_file(
+    shadow_root: &Path,
+    relative_file_path: &str,
+    snapshot: Option<&[u8]>,
+) -> Result<(), String> {
+    let path = resolve_project_path(shadow_root, relative_file_path)?;
+    match snapshot {
+        Some(bytes) => fs::write(&path, bytes)
+            .map_err(|error| format!("Failed to restore shadow file: {error}")),
+        None if path.exists() => {
+            fs::remove_file(&path).map_err(|error| format!("Failed to remove shadow file: {error}"))
+        }
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mutation(id: &str, file_path: &str, proposed_at: i64) -> MutationRecord {
+        MutationRecord {
+            id: id.to_string(),
+            task_id: "task".to_string(),
+            agent_uid: "agent".to_string(),
+            file_path: file_path.to_string(),
+            diff_content: String::new(),
+            intent_description: None,
+            intent_hash: None,
+            confidence: 0.9,
+            test_result: None,
+            test_exit_code: None,
+            rejection_reason: None,
+            rejected_at_step: None,
+            status: "proposed".to_string(),
+            proposed_at,
+            applied_at: None,
+            parent_mutation_id: None,
+            modified_content: None,
+            base_checksum: None,
+            approved_hunks: None,
+            rejected_hunks_diff: None,
+        }
+    }
+
+    #[test]
+    fn order_batch_groups_mutations_by_file_in_proposal_order() {
+        let ordered = order_batch(vec![
+            mutation("c", "src/a.ts", 30),
+            mutation("b", "src/b.ts", 20),
+            mutation("a", "src/a.ts", 10),
+            mutation("d", "src/b.ts", 40),
+        ]);
+        let ids = ordered
+            .iter()
+            .map(|mutation| mutation.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["a", "c", "b", "d"]);
+    }
+}
@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::WalkBuilder;
+
+/// Project-local ignore file for paths that are tracked in git but should stay out of
+/// file trees, plans and retrieval (fixtures, vendored code, generated clients).
+/// Same syntax as `.gitignore`.
+pub const AOP_IGNORE_FILE: &str = ".aopignore";
+
+/// Skipped even when the project has no ignore files.
+const ALWAYS_SKIPPED_DIRS: &[&str] = &[
+    ".git",
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    ".next",
+    ".turbo",
+];
+
+pub fn is_always_skipped_dir(name: &str) -> bool {
+    ALWAYS_SKIPPED_DIRS.contains(&name)
+}
+
+/// Files under `root` that `.gitignore` (at any depth, plus `.git/info/exclude`) and
+/// `.aopignore` do not exclude, in a stable order. Symlinks are not followed.
+pub fn project_files(root: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    let walker = WalkBuilder::new(root)
+        .hidden(false)
+        .parents(false)
+        .git_global(false)
+        .require_git(false)
+        .add_custom_ignore_filename(AOP_IGNORE_FILE)
+        .filter_entry(|entry| {
+            !(entry.depth() > 0
+                && entry.file_type().is_some_and(|kind| kind.is_dir())
+                && is_always_skipped_dir(&entry.file_name().to_string_lossy()))
+        })
+        .sort_by_file_path(|a, b| a.cmp(b))
+        .build();
+
+    for entry in walker {
+        let entry = entry
+            .map_err(|error| format!("Failed to walk project '{}': {error}", root.display()))?;
+        if entry.file_type().is_some_and(|kind| kind.is_file()) {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+/// Root-level `.gitignore` and `.aopignore` rules, for checking single paths (e.g. from
+/// file change events) without walking the tree.
+pub struct ProjectIgnore {
+    root: PathBuf,
+    matcher: Gitignore,
+}
+
+impl ProjectIgnore {
+    pub fn load(root: &Path) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        for file_name in [".gitignore", AOP_IGNORE_FILE] {
+            let path = root.join(file_name);
+            if path.is_file() {
+                // A malformed line only drops that rule; the rest still apply.
+                let _ = builder.add(path);
+            }
+        }
+        let matcher = builder.build().unwrap_or_else(|_| Gitignore::empty());
+        Self {
+            root: root.to_path_buf(),
+            matcher,
+        }
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        if relative
+            .components()
+            .any(|component| is_always_skipped_dir(&component.as_os_str().to_string_lossy()))
+        {
+            return true;
+        }
+        self.matcher
+            .matched_path_or_any_parents(relative, is_dir)
+            .is_ignore()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn honors_gitignore_and_aopignore() {
+        let root = tempdir().expect("temp dir should exist");
+        let write = |relative: &str, content: &str| {
+            let path = root.path().join(relative);
+            std::fs::create_dir_all(path.parent().expect("parent")).expect("dirs");
+            std::fs::write(path, content).expect("file should be written");
+        };
+        write(".gitignore", "generated/\n*.log\n");
+        write(".aopignore", "fixtures/\n");
+        write("src/app.ts", "");
+        write("src/.gitignore", "local.ts\n");
+        write("src/local.ts", "");
+        write("generated/client.ts", "");
+        write("fixtures/big.json", "");
+        write("debug.log", "");
+        write("node_modules/react/index.js", "");
+
+        let files = project_files(root.path())
+            .expect("walk should succeed")
+            .into_iter()
+            .map(|path| {
+                path.strip_prefix(root.path())
+                    .expect("inside root")
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            vec![".aopignore", ".gitignore", "src/.gitignore", "src/app.ts"]
+        );
+
+        let ignore = ProjectIgnore::load(root.path());
+        assert!(ignore.is_ignored(&root.path().join("generated/client.ts"), false));
+        assert!(ignore.is_ignored(&root.path().join("fixtures/big.json"), false));
+        assert!(ignore.is_ignored(&root.path().join("node_modules/a.js"), false));
+        assert!(!ignore.is_ignored(&root.path().join("src/app.ts"), false));
+    }
+}
@@ -0,0 +1,272 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use minijinja::Environment;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const ANALYSIS: &str = "analysis";
+pub const PLANNER: &str = "planner";
+pub const SPECIALIST: &str = "specialist";
+
+/// Per-project overrides, relative to the target project root.
+const PROJECT_PROMPTS_DIR: &str = ".aop/prompts";
+const TEMPLATE_EXTENSION: &str = "j2";
+
+struct BuiltinTemplate {
+    name: &'static str,
+    version: u32,
+    description: &'static str,
+    content: &'static str,
+}
+
+const BUILTINS: &[BuiltinTemplate] = &[
+    BuiltinTemplate {
+        name: ANALYSIS,
+        version: 1,
+        description: "Tier-1 objective analysis and clarifying questions. Variables: objective.",
+        content: include_str!("../prompts/analysis.j2"),
+    },
+    BuiltinTemplate {
+        name: PLANNER,
+        version: 1,
+        description: "Tier-1 plan generation. Variables: objective, token_budget, risk_tolerance.",
+        content: include_str!("../prompts/planner.j2"),
+    },
+    BuiltinTemplate {
+        name: SPECIALIST,
+        version: 1,
+        description: "Tier-3 specialist file edit. Variables: persona, objective, file_path.",
+        content: include_str!("../prompts/specialist.j2"),
+    },
+];
+
+static USER_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplate {
+    pub name: String,
+    pub description: String,
+    /// `builtin`, `user` (saved under app data) or `project` (`.aop/prompts` override).
+    pub source: String,
+    /// Version of the active template; user versions count up from the builtin one.
+    pub version: u32,
+    pub content: String,
+    pub path: Option<String>,
+    pub builtin_version: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPromptTemplatesInput {
+    pub target_project: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetPromptTemplateInput {
+    pub name: String,
+    pub content: String,
+    /// Save as an override for this project instead of a new app-wide version.
+    pub target_project: Option<String>,
+}
+
+/// Directory holding saved template versions, one folder per template.
+pub fn set_user_root(path: PathBuf) {
+    let _ = USER_ROOT.set(path);
+}
+
+/// Active templates: the project override, else the newest saved version, else the
+/// builtin.
+pub fn get_prompt_templates(input: GetPromptTemplatesInput) -> Result<Vec<PromptTemplate>, String> {
+    BUILTINS
+        .iter()
+        .map(|builtin| active_template(builtin, input.target_project.as_deref()))
+        .collect()
+}
+
+/// Saves `content` after checking that it parses. Without a project it becomes the next
+/// app-wide version; earlier versions stay on disk.
+pub fn set_prompt_template(input: SetPromptTemplateInput) -> Result<PromptTemplate, String> {
+    let builtin = builtin(input.name.trim())?;
+    if input.content.trim().is_empty() {
+        return Err("Prompt template content is required".to_string());
+    }
+    Environment::new()
+        .template_from_str(&input.content)
+        .map_err(|error| format!("Invalid prompt template '{}': {error}", builtin.name))?;
+
+    let path = match project_root(input.target_project.as_deref()) {
+        Some(root) => root
+            .join(PROJECT_PROMPTS_DIR)
+            .join(format!("{}.{TEMPLATE_EXTENSION}", builtin.name)),
+        None => {
+            let dir = user_root()?.join(builtin.name);
+            let next = latest_user_version(&dir)
+                .map(|(version, _)| version)
+                .unwrap_or(builtin.version)
+                + 1;
+            dir.join(format!("v{next}.{TEMPLATE_EXTENSION}"))
+        }
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| format!("Failed to create prompt template dir: {error}"))?;
+    }
+    fs::write(&path, &input.content)
+        .map_err(|error| format!("Failed to save prompt template: {error}"))?;
+
+    active_template(builtin, input.target_project.as_deref())
+}
+
+/// Renders the active `name` template with `context`. A broken override falls back to
+/// the builtin so a bad edit cannot stop runs.
+pub fn render(name: &str, target_project: Option<&str>, context: Value) -> String {
+    let Ok(builtin) = builtin(name) else {
+        return String::new();
+    };
+    let env = Environment::new();
+    active_template(builtin, target_project)
+        .ok()
+        .and_then(|template| env.render_str(&template.content, &context).ok())
+        .or_else(|| env.render_str(builtin.content, &context).ok())
+        .unwrap_or_else(|| builtin.content.trim_end().to_string())
+}
+
+fn active_template(
+    builtin: &BuiltinTemplate,
+    target_project: Option<&str>,
+) -> Result<PromptTemplate, String> {
+    let template =
+        |source: &str, version: u32, content: String, path: Option<&Path>| PromptTemplate {
+            name: builtin.name.to_string(),
+            description: builtin.description.to_string(),
+            source: source.to_string(),
+            version,
+            content,
+            path: path.map(|path| path.to_string_lossy().to_string()),
+            builtin_version: builtin.version,
+        };
+    let user_version = USER_ROOT
+        .get()
+        .and_then(|root| latest_user_version(&root.join(builtin.name)));
+
+    if let Some(root) = project_root(target_project) {
+        let path = root
+            .join(PROJECT_PROMPTS_DIR)
+            .join(format!("{}.{TEMPLATE_EXTENSION}", builtin.name));
+        if path.is_file() {
+            let content = fs::read_to_string(&path)
+                .map_err(|error| format!("Failed to read prompt template: {error}"))?;
+            let version = user_version
+                .as_ref()
+                .map(|(version, _)| *version)
+                .unwrap_or(builtin.version);
+            return Ok(template("project", version, content, Some(&path)));
+        }
+    }
+    if let Some((version, path)) = user_version {
+        let content = fs::read_to_string(&path)
+            .map_err(|error| format!("Failed to read prompt template: {error}"))?;
+        return Ok(template("user", version, content, Some(&path)));
+    }
+    Ok(template(
+        "builtin",
+        builtin.version,
+        builtin.content.to_string(),
+        None,
+    ))
+}
+
+/// Highest `v<N>.j2` in `dir`.
+fn latest_user_version(dir: &Path) -> Option<(u32, PathBuf)> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()?.to_str()? != TEMPLATE_EXTENSION {
+                return None;
+            }
+            let version = path
+                .file_stem()?
+                .to_str()?
+                .strip_prefix('v')?
+                .parse()
+                .ok()?;
+            Some((version, path))
+        })
+        .max_by_key(|(version, _)| *version)
+}
+
+fn builtin(name: &str) -> Result<&'static BuiltinTemplate, String> {
+    BUILTINS
+        .iter()
+        .find(|builtin| builtin.name == name)
+        .ok_or_else(|| format!("Unknown prompt template '{name}'"))
+}
+
+fn project_root(target_project: Option<&str>) -> Option<PathBuf> {
+    target_project
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+fn user_root() -> Result<&'static PathBuf, String> {
+    USER_ROOT
+        .get()
+        .ok_or_else(|| "Prompt template storage is not initialized".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn builtins_render_unchanged_and_project_overrides_win() {
+        let project = tempdir().expect("temp directory should be created");
+        let target = project.path().to_string_lossy().to_string();
+        let builtin_text = render(SPECIALIST, Some(&target), json!({}));
+        assert!(builtin_text.starts_with("You are a Tier-3 software specialist"));
+        assert!(!builtin_text.ends_with('\n'));
+
+        set_prompt_template(SetPromptTemplateInput {
+            name: SPECIALIST.to_string(),
+            content: "You are {{ persona }} editing {{ file_path }}.".to_string(),
+            target_project: Some(target.clone()),
+        })
+        .expect("override should save");
+        let rendered = render(
+            SPECIALIST,
+            Some(&target),
+            json!({ "persona": "react_specialist", "file_path": "src/app.tsx" }),
+        );
+        assert_eq!(rendered, "You are react_specialist editing src/app.tsx.");
+
+        let error = set_prompt_template(SetPromptTemplateInput {
+            name: SPECIALIST.to_string(),
+            content: "{% if persona %}unterminated".to_string(),
+            target_project: Some(target),
+        })
+        .expect_err("invalid template should be rejected");
+        assert!(error.contains("Invalid prompt template"));
+    }
+
+    #[test]
+    fn saved_versions_count_up_from_the_builtin() {
+        let dir = tempdir().expect("temp directory should be created");
+        assert!(latest_user_version(dir.path()).is_none());
+        fs::write(dir.path().join("v2.j2"), "two").expect("version should be written");
+        fs::write(dir.path().join("v10.j2"), "ten").expect("version should be written");
+        fs::write(dir.path().join("notes.txt"), "ignored").expect("file should be written");
+        let (version, path) = latest_user_version(dir.path()).expect("a version should exist");
+        assert_eq!(version, 10);
+        assert!(path.ends_with("v10.j2"));
+    }
+}
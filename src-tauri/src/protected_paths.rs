@@ -0,0 +1,169 @@
+use glob::{MatchOptions, Pattern};
+use sqlx::SqlitePool;
+
+use crate::db::tasks;
+
+/// Paths agents may never change unless the plan allow-lists them. Each entry is a glob
+/// relative to the project root and the reason shown to the reviewer.
+const PROTECTED_PATHS: &[(&str, &str)] = &[
+    ("**/package-lock.json", "lockfile"),
+    ("**/pnpm-lock.yaml", "lockfile"),
+    ("**/yarn.lock", "lockfile"),
+    ("**/bun.lockb", "lockfile"),
+    ("**/Cargo.lock", "lockfile"),
+    ("**/poetry.lock", "lockfile"),
+    ("**/Pipfile.lock", "lockfile"),
+    ("**/Gemfile.lock", "lockfile"),
+    ("**/composer.lock", "lockfile"),
+    ("**/go.sum", "lockfile"),
+    ("**/.env", "environment file"),
+    ("**/.env.*", "environment file"),
+    (".github/workflows/**", "CI workflow"),
+    ("**/migrations/**", "database migration"),
+];
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Returns the reason a path is protected, or `None` when agents may change it.
+pub fn protection_reason(relative_path: &str) -> Option<&'static str> {
+    let normalized = normalize(relative_path);
+    PROTECTED_PATHS.iter().find_map(|(pattern, reason)| {
+        Pattern::new(pattern)
+            .ok()
+            .filter(|pattern| pattern.matches_with(&normalized, MATCH_OPTIONS))
+            .map(|_| *reason)
+    })
+}
+
+/// Fails for protected paths that no allow-list glob covers. The message tells the
+/// reviewer how to unblock the change.
+pub fn check_path(relative_path: &str, allowlist: &[String]) -> Result<(), String> {
+    let Some(reason) = protection_reason(relative_path) else {
+        return Ok(());
+    };
+    let normalized = normalize(relative_path);
+    let allowed = allowlist.iter().any(|entry| {
+        Pattern::new(entry.trim())
+            .map(|pattern| pattern.matches_with(&normalized, MATCH_OPTIONS))
+            .unwrap_or(false)
+    });
+    if allowed {
+        return Ok(());
+    }
+    Err(format!(
+        "'{normalized}' is a protected {reason} and agents may not change it. Add it (or a glob \
+         covering it) to the plan's allowedProtectedPaths to permit this change."
+    ))
+}
+
+/// Keeps the paths agents may change and returns the dropped ones separately.
+pub fn partition_allowed(paths: &[String], allowlist: &[String]) -> (Vec<String>, Vec<String>) {
+    paths
+        .iter()
+        .cloned()
+        .partition(|path| check_path(path, allowlist).is_ok())
+}
+
+/// Paths a unified diff touches, taken from its `---`/`+++` headers.
+pub fn paths_in_diff(diff: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    for line in diff.lines() {
+        let Some(rest) = line
+            .strip_prefix("+++ ")
+            .or_else(|| line.strip_prefix("--- "))
+        else {
+            continue;
+        };
+        let path = rest.split('\t').next().unwrap_or_default().trim();
+        if path == "/dev/null" {
+            continue;
+        }
+        let path = path
+            .strip_prefix("a/")
+            .or_else(|| path.strip_prefix("b/"))
+            .unwrap_or(path);
+        if !path.is_empty() && !paths.iter().any(|known| known == path) {
+            paths.push(path.to_string());
+        }
+    }
+    paths
+}
+
+pub fn validate_allowlist(allowlist: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized = Vec::new();
+    for entry in allowlist {
+        let entry = normalize(entry);
+        if entry.is_empty() {
+            continue;
+        }
+        Pattern::new(&entry)
+            .map_err(|error| format!("Invalid allowed protected path '{entry}': {error}"))?;
+        if !normalized.contains(&entry) {
+            normalized.push(entry);
+        }
+    }
+    Ok(normalized)
+}
+
+/// The allow-list stored on the root task of the plan `task_id` belongs to.
+pub async fn plan_allowlist(pool: &SqlitePool, task_id: &str) -> Result<Vec<String>, String> {
+    let root_task_id = tasks::resolve_root_task_id(pool, task_id).await?;
+    let root = tasks::get_task_by_id(pool, &root_task_id).await?;
+    Ok(root
+        .allowed_protected_paths
+        .as_deref()
+        .and_then(|json| serde_json::from_str::<Vec<String>>(json).ok())
+        .unwrap_or_default())
+}
+
+fn normalize(path: &str) -> String {
+    path.trim()
+        .replace('\\', "/")
+        .trim_start_matches("./")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protects_lockfiles_env_workflows_and_migrations_unless_allowed() {
+        assert_eq!(protection_reason("pnpm-lock.yaml"), Some("lockfile"));
+        assert_eq!(
+            protection_reason("apps/web/package-lock.json"),
+            Some("lockfile")
+        );
+        assert_eq!(protection_reason(".env"), Some("environment file"));
+        assert_eq!(
+            protection_reason("server/.env.production"),
+            Some("environment file")
+        );
+        assert_eq!(
+            protection_reason(".github/workflows/ci.yml"),
+            Some("CI workflow")
+        );
+        assert_eq!(
+            protection_reason("src-tauri/migrations/001_initial.sql"),
+            Some("database migration")
+        );
+        assert_eq!(protection_reason("src/env.ts"), None);
+
+        let error = check_path("src-tauri/migrations/011_x.sql", &[]).expect_err("protected");
+        assert!(error.contains("allowedProtectedPaths"));
+        assert!(check_path(
+            "src-tauri/migrations/011_x.sql",
+            &["src-tauri/migrations/*".to_string()]
+        )
+        .is_ok());
+
+        assert_eq!(
+            paths_in_diff("--- a/.env\n+++ b/.env\n@@ -1 +1 @@\n-A=1\n+A=2\n"),
+            vec![".env".to_string()]
+        );
+    }
+}
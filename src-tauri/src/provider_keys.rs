@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::llm_adapter::OPENAI_DEFAULT_BASE_URL;
+
+const CHECK_TIMEOUT_SECS: u64 = 15;
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// Prefixes of the response headers providers report rate limits and quotas in.
+const QUOTA_HEADER_PREFIXES: &[&str] = &[
+    "x-ratelimit-",
+    "x-rate-limit-",
+    "ratelimit-",
+    "anthropic-ratelimit-",
+];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateProviderSecretInput {
+    pub provider: String,
+    pub name: Option<String>,
+    /// A key to test before storing it; the stored key is tested when omitted.
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderSecretValidation {
+    pub provider: String,
+    pub valid: bool,
+    /// HTTP status of the test call.
+    pub status: u16,
+    pub latency_ms: u64,
+    /// Rate-limit and quota headers of the response, such as `x-ratelimit-remaining-requests`.
+    pub quota_headers: BTreeMap<String, String>,
+}
+
+/// Makes the cheapest authenticated call `provider` offers (a models list or the current
+/// user) with `key` and reports how it went. `provider` may be a credential reference such
+/// as `openai/work`. Fails when the provider cannot be reached or no check is known for it;
+/// a rejected key is a result with `valid: false`.
+pub async fn probe_key(provider: &str, key: &str) -> Result<ProviderSecretValidation, String> {
+    let provider = provider
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let key = key.trim();
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(CHECK_TIMEOUT_SECS))
+        .build()
+        .map_err(|error| format!("Failed to build HTTP client: {error}"))?;
+
+    let request = match provider.as_str() {
+        "openai" => client
+            .get(format!("{OPENAI_DEFAULT_BASE_URL}/models"))
+            .bearer_auth(key),
+        "anthropic" => client
+            .get("https://api.anthropic.com/v1/models?limit=1")
+            .header("x-api-key", key)
+            .header("anthropic-version", ANTHROPIC_VERSION),
+        "gemini" => client
+            .get("https://generativelanguage.googleapis.com/v1beta/models?pageSize=1")
+            .header("x-goog-api-key", key),
+        "xai" => client.get("https://api.x.ai/v1/models").bearer_auth(key),
+        "github" => client
+            .get("https://api.github.com/user")
+            .bearer_auth(key)
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", "aop"),
+        "gitlab" => client
+            .get("https://gitlab.com/api/v4/user")
+            .header("PRIVATE-TOKEN", key),
+        _ => return Err(format!("No test call is known for provider '{provider}'")),
+    };
+
+    let started = Instant::now();
+    let response = request
+        .send()
+        .await
+        .map_err(|error| format!("Failed to reach {provider}: {error}"))?;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let quota_headers = response
+        .headers()
+        .iter()
+        .filter(|(name, _)| is_quota_header(name.as_str()))
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.as_str().to_string(), value.to_string()))
+        })
+        .collect();
+    let status = response.status();
+    Ok(ProviderSecretValidation {
+        provider,
+        valid: status.is_success(),
+        status: status.as_u16(),
+        latency_ms,
+        quota_headers,
+    })
+}
+
+/// Like [`probe_key`], but fails when the provider rejects the key.
+pub async fn check_key(provider: &str, key: &str) -> Result<(), String> {
+    let validation = probe_key(provider, key).await?;
+    if validation.valid {
+        return Ok(());
+    }
+    Err(format!(
+        "{} rejected the key with HTTP {}",
+        validation.provider, validation.status
+    ))
+}
+
+fn is_quota_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name == "retry-after"
+        || QUOTA_HEADER_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn providers_without_a_check_are_refused_before_any_call() {
+        let error = check_key("webhook/signing", "secret")
+            .await
+            .expect_err("webhook keys cannot be checked");
+        assert!(error.contains("'webhook'"));
+    }
+
+    #[test]
+    fn rate_limit_headers_of_each_provider_are_kept() {
+        assert!(is_quota_header("x-ratelimit-remaining-requests"));
+        assert!(is_quota_header("anthropic-ratelimit-tokens-remaining"));
+        assert!(is_quota_header("RateLimit-Remaining"));
+        assert!(is_quota_header("retry-after"));
+        assert!(!is_quota_header("content-type"));
+        assert!(!is_quota_header("x-request-id"));
+    }
+}
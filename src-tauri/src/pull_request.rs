@@ -0,0 +1,552 @@
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+
+use crate::db::metrics;
+use crate::db::mutations::{list_mutations_for_task, ListTaskMutationsInput, MutationRecord};
+use crate::db::tasks::{collect_task_tree_ids, get_task_by_id, TaskRecord};
+use crate::mutation_pipeline::{normalize_target_root, run_command, run_command_capture};
+use crate::task_branch::{branch_exists, branch_name_for_root_task};
+
+const GIT_PUSH_TIMEOUT: Duration = Duration::from_secs(120);
+const API_TIMEOUT: Duration = Duration::from_secs(30);
+const PENDING_MUTATION_STATUSES: &[&str] =
+    &["proposed", "validated", "validated_no_tests", "stale_base"];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitProvider {
+    Github,
+    Gitlab,
+}
+
+impl GitProvider {
+    /// Key under which the provider token is stored in the secret vault.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GitProvider::Github => "github",
+            GitProvider::Gitlab => "gitlab",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenPullRequestInput {
+    pub target_project: String,
+    pub root_task_id: String,
+    pub provider: Option<GitProvider>,
+    pub remote: Option<String>,
+    pub base_branch: Option<String>,
+    pub title: Option<String>,
+    pub draft: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenPullRequestResult {
+    pub provider: GitProvider,
+    pub branch: String,
+    pub base_branch: String,
+    pub url: String,
+    pub number: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RemoteRepo {
+    host: String,
+    path: String,
+}
+
+/// Resolves the hosting provider for a project remote. An explicit provider wins; otherwise
+/// the remote host name decides.
+pub async fn resolve_provider(
+    target_project: &str,
+    remote: Option<&str>,
+    provider: Option<GitProvider>,
+) -> Result<GitProvider, String> {
+    if let Some(provider) = provider {
+        return Ok(provider);
+    }
+    let target_root = normalize_target_root(target_project)?;
+    let repo = read_remote_repo(&target_root, remote_name(remote)).await?;
+    detect_provider(&repo.host).ok_or_else(|| {
+        format!(
+            "Unable to detect the git provider for host '{}'; pass provider explicitly.",
+            repo.host
+        )
+    })
+}
+
+/// Pushes the `aop/<root-task-id>` branch and opens a pull request (GitHub) or merge
+/// request (GitLab) whose body is the execution report of the root task tree.
+pub async fn open_pull_request(
+    pool: &SqlitePool,
+    input: OpenPullRequestInput,
+    token: &str,
+) -> Result<OpenPullRequestResult, String> {
+    let root_task_id = input.root_task_id.trim();
+    if root_task_id.is_empty() {
+        return Err("rootTaskId is required".to_string());
+    }
+    if token.trim().is_empty() {
+        return Err("A provider token is required to open a pull request".to_string());
+    }
+
+    let target_root = normalize_target_root(&input.target_project)?;
+    let branch = branch_name_for_root_task(root_task_id);
+    if !branch_exists(&target_root, &branch).await? {
+        return Err(format!(
+            "Branch '{branch}' does not exist; enable task branch mode before opening a pull request."
+        ));
+    }
+
+    let (root_task, tasks, mutations) = load_task_tree(pool, root_task_id).await?;
+    let pending = mutations
+        .iter()
+        .filter(|mutation| PENDING_MUTATION_STATUSES.contains(&mutation.status.as_str()))
+        .count();
+    if pending > 0 {
+        return Err(format!(
+            "Root task '{root_task_id}' still has {pending} mutation(s) waiting to be applied."
+        ));
+    }
+    if !mutations
+        .iter()
+        .any(|mutation| mutation.status == "applied")
+    {
+        return Err(format!(
+            "Root task '{root_task_id}' has no applied mutations to open a pull request for."
+        ));
+    }
+
+    let remote = remote_name(input.remote.as_deref());
+    let repo = read_remote_repo(&target_root, remote).await?;
+    let provider = match input.provider {
+        Some(provider) => provider,
+        None => detect_provider(&repo.host).ok_or_else(|| {
+            format!(
+                "Unable to detect the git provider for host '{}'; pass provider explicitly.",
+                repo.host
+            )
+        })?,
+    };
+    let base_branch = match input
+        .base_branch
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        Some(value) => value.to_string(),
+        None => resolve_base_branch(&target_root, remote).await?,
+    };
+
+    let refspec = format!("{branch}:{branch}");
+    run_command(
+        &target_root,
+        "git",
+        &["push", "--set-upstream", remote, refspec.as_str()],
+        GIT_PUSH_TIMEOUT,
+    )
+    .await?;
+
+    let title = input
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(ToOwned::to_owned)
+        .unwrap_or_else(|| format!("AOP: {}", root_task.objective.trim()));
+    let body = build_execution_report(&root_task, &tasks, &mutations);
+    let draft = input.draft.unwrap_or(false);
+
+    let (url, number) = match provider {
+        GitProvider::Github => {
+            create_github_pull_request(&repo, token, &branch, &base_branch, &title, &body, draft)
+                .await?
+        }
+        GitProvider::Gitlab => {
+            create_gitlab_merge_request(&repo, token, &branch, &base_branch, &title, &body, draft)
+                .await?
+        }
+    };
+
+    metrics::record_audit_event(
+        pool,
+        "ui",
+        "pull_request_opened",
+        Some(root_task_id),
+        Some(
+            &json!({
+                "provider": provider.as_str(),
+                "branch": branch,
+                "baseBranch": base_branch,
+                "url": url,
+            })
+            .to_string(),
+        ),
+    )
+    .await?;
+
+    Ok(OpenPullRequestResult {
+        provider,
+        branch,
+        base_branch,
+        url,
+        number,
+    })
+}
+
+async fn load_task_tree(
+    pool: &SqlitePool,
+    root_task_id: &str,
+) -> Result<(TaskRecord, Vec<TaskRecord>, Vec<MutationRecord>), String> {
+    let root_task = get_task_by_id(pool, root_task_id).await?;
+    let mut tasks = Vec::new();
+    let mut mutations = Vec::new();
+    for task_id in collect_task_tree_ids(pool, root_task_id).await? {
+        tasks.push(get_task_by_id(pool, &task_id).await?);
+        mutations.extend(list_mutations_for_task(pool, ListTaskMutationsInput { task_id }).await?);
+    }
+    Ok((root_task, tasks, mutations))
+}
+
+/// Markdown summary of a root task run: the task tree, the applied mutations and anything
+/// that was rejected along the way.
+fn build_execution_report(
+    root_task: &TaskRecord,
+    tasks: &[TaskRecord],
+    mutations: &[MutationRecord],
+) -> String {
+    let mut report = String::new();
+    report.push_str("## AOP execution report\n\n");
+    report.push_str(&format!(
+        "**Objective:** {}\n\n",
+        root_task.objective.trim()
+    ));
+    report.push_str(&format!(
+        "**Root task:** `{}` ({})\n\n",
+        root_task.id, root_task.status
+    ));
+
+    let token_usage: i64 = tasks.iter().map(|task| task.token_usage).sum();
+    report.push_str("### Tasks\n\n");
+    report.push_str("| Task | Tier | Domain | Status | Tokens |\n");
+    report.push_str("| --- | --- | --- | --- | --- |\n");
+    for task in tasks {
+        report.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            escape_table_cell(task.objective.trim()),
+            task.tier,
+            escape_table_cell(&task.domain),
+            task.status,
+            task.token_usage
+        ));
+    }
+    report.push_str(&format!("\nTotal token usage: {token_usage}\n\n"));
+
+    report.push_str("### Applied mutations\n\n");
+    for mutation in mutations
+        .iter()
+        .filter(|mutation| mutation.status == "applied")
+    {
+        let intent = mutation
+            .intent_description
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .unwrap_or("No intent recorded");
+        report.push_str(&format!(
+            "- `{}` by {} (confidence {:.2}): {}\n",
+            mutation.file_path, mutation.agent_uid, mutation.confidence, intent
+        ));
+    }
+
+    let rejected = mutations
+        .iter()
+        .filter(|mutation| mutation.status == "rejected")
+        .collect::<Vec<_>>();
+    if !rejected.is_empty() {
+        report.push_str("\n### Rejected mutations\n\n");
+        for mutation in rejected {
+            report.push_str(&format!(
+                "- `{}` at {}: {}\n",
+                mutation.file_path,
+                mutation
+                    .rejected_at_step
+                    .as_deref()
+                    .unwrap_or("unknown step"),
+                mutation
+                    .rejection_reason
+                    .as_deref()
+                    .map(first_line)
+                    .unwrap_or("no reason recorded")
+            ));
+        }
+    }
+
+    report
+}
+
+async fn create_github_pull_request(
+    repo: &RemoteRepo,
+    token: &str,
+    branch: &str,
+    base_branch: &str,
+    title: &str,
+    body: &str,
+    draft: bool,
+) -> Result<(String, Option<i64>), String> {
+    let api_base = if repo.host.eq_ignore_ascii_case("github.com") {
+        "https://api.github.com".to_string()
+    } else {
+        format!("https://{}/api/v3", repo.host)
+    };
+    let response = http_client()?
+        .post(format!("{api_base}/repos/{}/pulls", repo.path))
+        .bearer_auth(token.trim())
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", "aop")
+        .json(&json!({
+            "title": title,
+            "head": branch,
+            "base": base_branch,
+            "body": body,
+            "draft": draft,
+        }))
+        .send()
+        .await
+        .map_err(|error| format!("Failed to call GitHub API: {error}"))?;
+    let payload = read_api_payload(response, "GitHub").await?;
+
+    let url = payload
+        .get("html_url")
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("GitHub response did not include html_url: {payload}"))?
+        .to_string();
+    Ok((url, payload.get("number").and_then(Value::as_i64)))
+}
+
+async fn create_gitlab_merge_request(
+    repo: &RemoteRepo,
+    token: &str,
+    branch: &str,
+    base_branch: &str,
+    title: &str,
+    body: &str,
+    draft: bool,
+) -> Result<(String, Option<i64>), String> {
+    let title = if draft {
+        format!("Draft: {title}")
+    } else {
+        title.to_string()
+    };
+    let response = http_client()?
+        .post(format!(
+            "https://{}/api/v4/projects/{}/merge_requests",
+            repo.host,
+            encode_project_path(&repo.path)
+        ))
+        .header("PRIVATE-TOKEN", token.trim())
+        .json(&json!({
+            "source_branch": branch,
+            "target_branch": base_branch,
+            "title": title,
+            "description": body,
+            "remove_source_branch": true,
+        }))
+        .send()
+        .await
+        .map_err(|error| format!("Failed to call GitLab API: {error}"))?;
+    let payload = read_api_payload(response, "GitLab").await?;
+
+    let url = payload
+        .get("web_url")
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("GitLab response did not include web_url: {payload}"))?
+        .to_string();
+    Ok((url, payload.get("iid").and_then(Value::as_i64)))
+}
+
+fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(API_TIMEOUT)
+        .build()
+        .map_err(|error| format!("Failed to build provider HTTP client: {error}"))
+}
+
+async fn read_api_payload(response: reqwest::Response, provider: &str) -> Result<Value, String> {
+    let status = response.status();
+    let payload: Value = response
+        .json()
+        .await
+        .map_err(|error| format!("Invalid {provider} response payload: {error}"))?;
+    if !status.is_success() {
+        return Err(format!(
+            "{provider} API request failed with status {status}: {payload}"
+        ));
+    }
+    Ok(payload)
+}
+
+async fn read_remote_repo(target_root: &Path, remote: &str) -> Result<RemoteRepo, String> {
+    let output = run_command_capture(
+        target_root,
+        "git",
+        vec![
+            "remote".to_string(),
+            "get-url".to_string(),
+            remote.to_string(),
+        ],
+        GIT_PUSH_TIMEOUT,
+    )
+    .await?;
+    if output.exit_code != 0 {
+        return Err(format!(
+            "Remote '{remote}' is not configured: {}",
+            output.stderr.trim()
+        ));
+    }
+    parse_remote_url(output.stdout.trim())
+        .ok_or_else(|| format!("Unsupported remote URL '{}'", output.stdout.trim()))
+}
+
+/// Uses the remote's default branch when known, otherwise the branch checked out in the
+/// target project.
+async fn resolve_base_branch(target_root: &Path, remote: &str) -> Result<String, String> {
+    let remote_head = run_command_capture(
+        target_root,
+        "git",
+        vec![
+            "symbolic-ref".to_string(),
+            "--short".to_string(),
+            format!("refs/remotes/{remote}/HEAD"),
+        ],
+        GIT_PUSH_TIMEOUT,
+    )
+    .await?;
+    if remote_head.exit_code == 0 {
+        let prefix = format!("{remote}/");
+        if let Some(branch) = remote_head.stdout.trim().strip_prefix(prefix.as_str()) {
+            return Ok(branch.to_string());
+        }
+    }
+
+    let current = run_command_capture(
+        target_root,
+        "git",
+        vec![
+            "rev-parse".to_string(),
+            "--abbrev-ref".to_string(),
+            "HEAD".to_string(),
+        ],
+        GIT_PUSH_TIMEOUT,
+    )
+    .await?;
+    let branch = current.stdout.trim();
+    if current.exit_code != 0 || branch.is_empty() || branch == "HEAD" {
+        return Err("Unable to determine the base branch; pass baseBranch explicitly.".to_string());
+    }
+    Ok(branch.to_string())
+}
+
+fn remote_name(remote: Option<&str>) -> &str {
+    remote
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unwrap_or("origin")
+}
+
+fn detect_provider(host: &str) -> Option<GitProvider> {
+    let host = host.to_ascii_lowercase();
+    if host.contains("github") {
+        Some(GitProvider::Github)
+    } else if host.contains("gitlab") {
+        Some(GitProvider::Gitlab)
+    } else {
+        None
+    }
+}
+
+/// Accepts `https://host/owner/repo(.git)`, `ssh://git@host[:port]/owner/repo.git` and the
+/// scp-like `git@host:owner/repo.git` forms.
+fn parse_remote_url(url: &str) -> Option<RemoteRepo> {
+    let url = url.trim();
+    let (host, path) = if let Some((_, rest)) = url.split_once("://") {
+        let (authority, path) = rest.split_once('/')?;
+        let host = authority.rsplit('@').next()?;
+        let host = host.split(':').next()?;
+        (host, path)
+    } else {
+        let (authority, path) = url.split_once(':')?;
+        (authority.rsplit('@').next()?, path)
+    };
+
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    if host.is_empty() || !path.contains('/') {
+        return None;
+    }
+    Some(RemoteRepo {
+        host: host.to_string(),
+        path: path.to_string(),
+    })
+}
+
+fn encode_project_path(path: &str) -> String {
+    path.replace('%', "%25").replace('/', "%2F")
+}
+
+fn escape_table_cell(value: &str) -> String {
+    first_line(value).replace('|', "\\|")
+}
+
+fn first_line(value: &str) -> &str {
+    value.lines().next().unwrap_or_default().trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_remote_url_supports_https_and_ssh_forms() {
+        let expected = RemoteRepo {
+            host: "github.com".to_string(),
+            path: "acme/widgets".to_string(),
+        };
+        assert_eq!(
+            parse_remote_url("https://github.com/acme/widgets.git"),
+            Some(expected.clone())
+        );
+        assert_eq!(
+            parse_remote_url("git@github.com:acme/widgets.git"),
+            Some(expected.clone())
+        );
+        assert_eq!(
+            parse_remote_url("ssh://git@github.com:22/acme/widgets"),
+            Some(expected)
+        );
+
+        let nested = parse_remote_url("https://oauth2@gitlab.example.com/group/sub/app.git")
+            .expect("nested GitLab path should parse");
+        assert_eq!(nested.host, "gitlab.example.com");
+        assert_eq!(nested.path, "group/sub/app");
+        assert_eq!(encode_project_path(&nested.path), "group%2Fsub%2Fapp");
+
+        assert_eq!(parse_remote_url("/local/path/repo"), None);
+    }
+
+    #[test]
+    fn detect_provider_uses_remote_host() {
+        assert_eq!(detect_provider("github.com"), Some(GitProvider::Github));
+        assert_eq!(
+            detect_provider("gitlab.example.com"),
+            Some(GitProvider::Gitlab)
+        );
+        assert_eq!(detect_provider("git.example.com"), None);
+    }
+}
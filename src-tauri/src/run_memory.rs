@@ -0,0 +1,360 @@
+use std::cmp::Ordering;
+
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::db::mutations::{self, ListTaskMutationsInput, MutationStatus};
+use crate::db::tasks;
+use crate::mutation_pipeline::normalize_target_root;
+use crate::vector::embedding;
+use crate::vector::search::cosine_similarity;
+
+/// Prefix `analyze_objective` puts in front of the objective on root tasks.
+const ANALYSIS_PREFIX: &str = "Analyze objective: ";
+const MAX_KEY_DIFFS: usize = 8;
+/// Below this similarity a past run is more noise than precedent.
+const MIN_SIMILARITY: f32 = 0.25;
+
+/// What a finished root task set out to do, how it was split up and how it ended.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunMemory {
+    pub root_task_id: String,
+    pub target_project: String,
+    pub objective: String,
+    /// One line per assignment: tier, domain, objective, files and final status.
+    pub plan_summary: String,
+    pub outcome: String,
+    pub status: String,
+    /// Applied changes as `path: intent (+added -removed)`.
+    pub key_diffs: Vec<String>,
+    pub recorded_at: i64,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct StoredMemory {
+    root_task_id: String,
+    target_project: String,
+    objective: String,
+    plan_summary: String,
+    outcome: String,
+    status: String,
+    key_diffs_json: String,
+    vector_json: String,
+    recorded_at: i64,
+}
+
+/// Summarizes the root task's tree and stores it with an embedding of the objective, plan
+/// and applied changes. Recording a run again replaces the earlier summary.
+pub async fn record_run(
+    pool: &SqlitePool,
+    root_task_id: &str,
+    target_project: &str,
+    outcome: &str,
+) -> Result<RunMemory, String> {
+    let root = tasks::get_task_by_id(pool, root_task_id).await?;
+    let objective = root
+        .objective
+        .strip_prefix(ANALYSIS_PREFIX)
+        .unwrap_or(&root.objective)
+        .trim()
+        .to_string();
+
+    let mut plan_lines = Vec::new();
+    let mut key_diffs = Vec::new();
+    for task_id in tasks::collect_task_tree_ids(pool, root_task_id).await? {
+        let task = tasks::get_task_by_id(pool, &task_id).await?;
+        if task.parent_id.as_deref() == Some(root_task_id) {
+            let files = task
+                .target_files
+                .as_deref()
+                .and_then(|raw| serde_json::from_str::<Vec<String>>(raw).ok())
+                .filter(|files| !files.is_empty())
+                .map(|files| format!(" [{}]", files.join(", ")))
+                .unwrap_or_default();
+            plan_lines.push(format!(
+                "- tier{} {}: {}{} -> {}",
+                task.tier,
+                task.domain,
+                task.objective.trim(),
+                files,
+                task.status
+            ));
+        }
+        let task_mutations =
+            mutations::list_mutations_for_task(pool, ListTaskMutationsInput { task_id }).await?;
+        for mutation in task_mutations
+            .iter()
+            .filter(|mutation| mutation.status == MutationStatus::Applied.as_str())
+        {
+            let (added, removed) = diff_line_counts(&mutation.diff_content);
+            key_diffs.push(format!(
+                "{}: {} (+{added} -{removed})",
+                mutation.file_path,
+                mutation
+                    .intent_description
+                    .as_deref()
+                    .unwrap_or("no intent recorded")
+                    .trim()
+            ));
+        }
+    }
+    key_diffs.truncate(MAX_KEY_DIFFS);
+
+    let memory = RunMemory {
+        root_task_id: root.id.clone(),
+        // Recalled by canonical root; a project that was removed keeps the path as given.
+        target_project: normalize_target_root(target_project)
+            .map(|root| root.to_string_lossy().to_string())
+            .unwrap_or_else(|_| target_project.trim().to_string()),
+        objective,
+        plan_summary: plan_lines.join("\n"),
+        outcome: outcome.trim().to_string(),
+        status: root.status.clone(),
+        key_diffs,
+        recorded_at: Utc::now().timestamp(),
+        score: 1.0,
+    };
+
+    let provider = embedding::active_provider()?;
+    let provider_id = provider.id();
+    let texts = vec![format!(
+        "{}\n{}\n{}",
+        memory.objective,
+        memory.plan_summary,
+        memory.key_diffs.join("\n")
+    )];
+    let vector =
+        tokio::task::spawn_blocking(move || embedding::embed_all(provider.as_ref(), &texts))
+            .await
+            .map_err(|error| format!("Embedding task panicked: {error}"))??
+            .pop()
+            .unwrap_or_default();
+    let vector_json = serde_json::to_string(&vector)
+        .map_err(|error| format!("Failed to serialize vector embedding: {error}"))?;
+    let key_diffs_json = serde_json::to_string(&memory.key_diffs)
+        .map_err(|error| format!("Failed to serialize key diffs: {error}"))?;
+
+    sqlx::query(
+        r#"
+        INSERT OR REPLACE INTO aop_run_memories (
+            root_task_id, target_project, objective, plan_summary, outcome, status,
+            key_diffs_json, embedding_provider, vector_json, recorded_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&memory.root_task_id)
+    .bind(&memory.target_project)
+    .bind(&memory.objective)
+    .bind(&memory.plan_summary)
+    .bind(&memory.outcome)
+    .bind(&memory.status)
+    .bind(key_diffs_json)
+    .bind(provider_id)
+    .bind(vector_json)
+    .bind(memory.recorded_at)
+    .execute(pool)
+    .await
+    .map_err(|error| format!("Failed to store run memory: {error}"))?;
+
+    Ok(memory)
+}
+
+/// Past runs most similar to `objective`, best first. Runs on the same project rank ahead
+/// of equally similar runs elsewhere; only memories embedded with the active provider are
+/// comparable.
+pub async fn similar_runs(
+    pool: &SqlitePool,
+    target_project: &str,
+    objective: &str,
+    limit: usize,
+) -> Result<Vec<RunMemory>, String> {
+    if objective.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let provider = embedding::active_provider()?;
+    let rows = sqlx::query_as::<_, StoredMemory>(
+        r#"
+        SELECT root_task_id, target_project, objective, plan_summary, outcome, status,
+               key_diffs_json, vector_json, recorded_at
+        FROM aop_run_memories
+        WHERE embedding_provider = ?
+        "#,
+    )
+    .bind(provider.id())
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to query run memories: {error}"))?;
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query_text = vec![objective.to_string()];
+    let query_vector =
+        tokio::task::spawn_blocking(move || embedding::embed_all(provider.as_ref(), &query_text))
+            .await
+            .map_err(|error| format!("Embedding task panicked: {error}"))??
+            .pop()
+            .unwrap_or_default();
+
+    let mut memories = Vec::new();
+    for row in rows {
+        let vector = serde_json::from_str::<Vec<f32>>(&row.vector_json).map_err(|error| {
+            format!(
+                "Failed to decode stored embedding for run '{}': {error}",
+                row.root_task_id
+            )
+        })?;
+        let score = cosine_similarity(&query_vector, &vector);
+        if score < MIN_SIMILARITY {
+            continue;
+        }
+        let same_project = row.target_project == target_project.trim();
+        memories.push((
+            same_project,
+            RunMemory {
+                root_task_id: row.root_task_id,
+                target_project: row.target_project,
+                objective: row.objective,
+                plan_summary: row.plan_summary,
+                outcome: row.outcome,
+                status: row.status,
+                key_diffs: serde_json::from_str(&row.key_diffs_json).unwrap_or_default(),
+                recorded_at: row.recorded_at,
+                score,
+            },
+        ));
+    }
+    memories.sort_by(|(a_same, a), (b_same, b)| {
+        let a_score = a.score + if *a_same { 0.05 } else { 0.0 };
+        let b_score = b.score + if *b_same { 0.05 } else { 0.0 };
+        b_score.partial_cmp(&a_score).unwrap_or(Ordering::Equal)
+    });
+    Ok(memories
+        .into_iter()
+        .take(limit)
+        .map(|(_, memory)| memory)
+        .collect())
+}
+
+/// Prompt section describing past runs, or `None` when there are none.
+pub fn render_similar_runs(memories: &[RunMemory]) -> Option<String> {
+    if memories.is_empty() {
+        return None;
+    }
+    let sections = memories
+        .iter()
+        .map(|memory| {
+            let days_ago = (Utc::now().timestamp() - memory.recorded_at).max(0) / 86_400;
+            let mut section = format!(
+                "Run {} ({} days ago, {}, similarity {:.2}): {}\nPlan:\n{}",
+                memory.root_task_id,
+                days_ago,
+                memory.status,
+                memory.score,
+                memory.objective,
+                memory.plan_summary
+            );
+            if !memory.key_diffs.is_empty() {
+                section.push_str(&format!("\nChanges:\n- {}", memory.key_diffs.join("\n- ")));
+            }
+            if !memory.outcome.is_empty() {
+                section.push_str(&format!("\nOutcome: {}", memory.outcome));
+            }
+            section
+        })
+        .collect::<Vec<_>>();
+    Some(sections.join("\n\n"))
+}
+
+fn diff_line_counts(diff: &str) -> (usize, usize) {
+    diff.lines().fold((0, 0), |(added, removed), line| {
+        if line.starts_with("+++") || line.starts_with("---") {
+            (added, removed)
+        } else if line.starts_with('+') {
+            (added + 1, removed)
+        } else if line.starts_with('-') {
+            (added, removed + 1)
+        } else {
+            (added, removed)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use crate::db;
+    use crate::db::tasks::{create_task_record, CreateTaskRecordInput, TaskStatus};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn recalls_similar_runs_with_their_plan() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+
+        let task = |parent_id: Option<String>, objective: &str, files: Option<&str>| {
+            CreateTaskRecordInput {
+                parent_id,
+                tier: if files.is_some() { 3 } else { 1 },
+                domain: "frontend".to_string(),
+                objective: objective.to_string(),
+                token_budget: 1000,
+                risk_factor: 0.2,
+                status: TaskStatus::Completed,
+                target_files: files.map(str::to_string),
+                priority: 0,
+            }
+        };
+        let root = create_task_record(
+            &pool,
+            task(
+                None,
+                "Analyze objective: Add dark mode toggle to settings page",
+                None,
+            ),
+        )
+        .await
+        .expect("root task should be created");
+        create_task_record(
+            &pool,
+            task(
+                Some(root.id.clone()),
+                "Add theme toggle component",
+                Some(r#"["src/Settings.tsx"]"#),
+            ),
+        )
+        .await
+        .expect("assignment should be created");
+
+        let recorded = record_run(&pool, &root.id, "/work/app", "appliedMutations=1")
+            .await
+            .expect("run should be recorded");
+        assert_eq!(recorded.objective, "Add dark mode toggle to settings page");
+        assert_eq!(
+            recorded.plan_summary,
+            "- tier3 frontend: Add theme toggle component [src/Settings.tsx] -> completed"
+        );
+
+        let similar = similar_runs(&pool, "/work/app", "dark mode toggle for settings", 3)
+            .await
+            .expect("similar runs should load");
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].root_task_id, root.id);
+        let unrelated = similar_runs(&pool, "/work/app", "migrate database schema", 3)
+            .await
+            .expect("similar runs should load");
+        assert!(unrelated.is_empty());
+    }
+}
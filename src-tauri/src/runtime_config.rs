@@ -1,19 +1,124 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
 use serde::{Deserialize, Serialize};
 
+use crate::execution_window;
+use crate::model_bandit;
+use crate::model_intelligence;
+use crate::secret_vault::SecretBackend;
+use crate::vector::embedding::{self, DEFAULT_EMBEDDING_PROVIDER};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RuntimeFlags {
     pub dev_mode: bool,
     pub model_adapter_enabled: bool,
     pub model_adapter_strict: bool,
+    /// Restricts tier-3 specialists to models tagged `offline`, such as Ollama.
+    pub offline_specialists: bool,
     pub auto_approve_budget_requests: bool,
     pub auto_commit_mutations: bool,
     pub budget_headroom_percent: f64,
     pub budget_auto_max_percent: f64,
     pub budget_min_increment: i64,
+    /// Org-wide model spend caps in USD per UTC day and month; 0 leaves a period uncapped.
+    /// At the cap new model calls are refused and executing root tasks pause.
+    pub spend_cap_daily_usd: f64,
+    pub spend_cap_monthly_usd: f64,
+    /// Share of a spend cap at which a warning is audited.
+    pub spend_cap_warn_percent: u32,
+    /// Local `HH:MM-HH:MM` hours in which approved plans may start; empty allows any time.
+    pub execution_window: String,
+    /// Also hold approved plans while the machine runs on battery.
+    pub execution_window_ac_power: bool,
+    /// Plans with fewer assignments ignore the window; 0 holds every plan.
+    pub execution_window_min_tasks: u32,
+    /// Desktop notifications for events waiting on a human; each kind can be muted.
+    pub notifications_enabled: bool,
+    pub notify_plan_ready: bool,
+    pub notify_consensus_failed: bool,
+    pub notify_budget_request: bool,
+    pub notify_run_finished: bool,
+    /// Comma-separated URLs that receive run lifecycle webhooks; empty sends none.
+    pub webhook_urls: String,
     pub telemetry_retention_days: u32,
+    pub mutation_repair_attempts: u32,
+    /// Retries per model for rate limits and server errors before failing over.
+    pub llm_max_retries: u32,
+    pub llm_retry_base_ms: u32,
+    /// Model calls a batch, such as a domain's specialists, runs at once.
+    pub llm_batch_parallelism: u32,
+    /// Seconds before a model call is abandoned, per provider family; a model's
+    /// `timeoutSecs` in `models.json` takes precedence.
+    pub claude_code_timeout_secs: u32,
+    pub openai_timeout_secs: u32,
+    pub ollama_timeout_secs: u32,
+    /// Replies longer than this many characters are cut off and fail the call.
+    pub llm_max_response_chars: u32,
+    /// `ucb`, `epsilon_greedy` or `static`; the adaptive policies learn from applied
+    /// mutations per tier, persona and skill.
+    pub model_selection_policy: String,
+    pub bandit_epsilon_percent: u32,
+    /// `auto` lets each call weigh price by task risk and budget; `cheap`, `balanced` or
+    /// `best` applies one preference everywhere.
+    pub model_cost_preference: String,
+    /// Answer repeated identical model requests from the SQLite response cache.
+    pub llm_cache_enabled: bool,
+    pub llm_cache_ttl_hours: u32,
+    /// Store full, secret-redacted prompts and replies for debugging; each field is capped
+    /// at `llm_transcript_max_kb`.
+    pub llm_transcripts_enabled: bool,
+    pub llm_transcript_max_kb: u32,
+    /// Consecutive failures that open a model's circuit; 0 disables the breaker.
+    pub circuit_breaker_failures: u32,
+    pub circuit_breaker_open_minutes: u32,
+    /// Hours before unreviewed mutations expire; 0 disables expiry.
+    pub mutation_ttl_hours: u32,
+    pub task_branch_mode: bool,
+    pub shadow_keep_on_failure: bool,
+    pub shadow_disk_quota_mb: u32,
+    pub shadow_sandbox: bool,
+    pub semantic_judge: bool,
+    pub pipeline_test_timeout_secs: u32,
+    pub pipeline_static_check_timeout_secs: u32,
+    pub pipeline_apply_timeout_secs: u32,
+    pub pipeline_max_output_kb: u32,
+    /// `KEY=VALUE` entries injected into CI commands run by the mutation pipeline.
+    pub pipeline_ci_env: Vec<String>,
+    /// `hash`, `openai`, `ollama` or `fastembed`; changing it reindexes projects on next query.
+    pub embedding_provider: String,
+    /// Provider model id; empty uses the provider default.
+    pub embedding_model: String,
+    /// OTLP/HTTP collector that finished root tasks are exported to as traces; empty
+    /// disables export.
+    pub otlp_endpoint: String,
+    /// Serve Prometheus metrics at `http://127.0.0.1:<metrics_port>/metrics`; takes
+    /// effect on restart.
+    pub metrics_enabled: bool,
+    pub metrics_port: u32,
+    /// Serve the HTTP API at `http://127.0.0.1:<http_api_port>/api`; takes effect on
+    /// restart.
+    pub http_api_enabled: bool,
+    pub http_api_port: u32,
+    /// Encrypt the database with SQLCipher, keyed from the Stronghold vault; takes effect
+    /// on restart. Needs a build with the `sqlcipher` feature, and an encrypted database
+    /// stays encrypted when this is turned off again.
+    pub db_encryption: bool,
+    /// `stronghold` or `keychain` (the OS keychain). Switching without
+    /// `migrate_secret_backend` leaves stored secrets behind in the old backend.
+    pub secret_backend: String,
 }
 
+/// Profiles every install has. Each sets the same flags, so switching between them fully
+/// replaces the previous one's choices.
+const BUILTIN_PROFILES: &[&str] = &["balanced", "cautious", "fast-and-cheap"];
+
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RuntimeFlagsUpdateResult {
@@ -23,30 +128,232 @@ pub struct RuntimeFlagsUpdateResult {
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
+pub struct SetRuntimeProfileInput {
+    pub name: String,
+    /// Save the current flags under `name` instead of applying it.
+    pub save_current: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeProfile {
+    pub name: String,
+    pub builtin: bool,
+    pub active: bool,
+}
+
+/// The flags file under app data: the flags as last set, the profile they came from, and
+/// profiles saved by name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedRuntimeConfig {
+    #[serde(default)]
+    profile: Option<String>,
+    #[serde(default)]
+    flags: SetRuntimeFlagsInput,
+    #[serde(default)]
+    profiles: BTreeMap<String, SetRuntimeFlagsInput>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SetRuntimeFlagsInput {
     pub dev_mode: Option<bool>,
     pub model_adapter_enabled: Option<bool>,
     pub model_adapter_strict: Option<bool>,
+    pub offline_specialists: Option<bool>,
     pub auto_approve_budget_requests: Option<bool>,
     pub auto_commit_mutations: Option<bool>,
     pub budget_headroom_percent: Option<f64>,
     pub budget_auto_max_percent: Option<f64>,
     pub budget_min_increment: Option<i64>,
+    pub spend_cap_daily_usd: Option<f64>,
+    pub spend_cap_monthly_usd: Option<f64>,
+    pub spend_cap_warn_percent: Option<u32>,
+    pub execution_window: Option<String>,
+    pub execution_window_ac_power: Option<bool>,
+    pub execution_window_min_tasks: Option<u32>,
+    pub notifications_enabled: Option<bool>,
+    pub notify_plan_ready: Option<bool>,
+    pub notify_consensus_failed: Option<bool>,
+    pub notify_budget_request: Option<bool>,
+    pub notify_run_finished: Option<bool>,
+    pub webhook_urls: Option<String>,
     pub telemetry_retention_days: Option<u32>,
+    pub mutation_repair_attempts: Option<u32>,
+    pub llm_max_retries: Option<u32>,
+    pub llm_retry_base_ms: Option<u32>,
+    pub llm_batch_parallelism: Option<u32>,
+    pub claude_code_timeout_secs: Option<u32>,
+    pub openai_timeout_secs: Option<u32>,
+    pub ollama_timeout_secs: Option<u32>,
+    pub llm_max_response_chars: Option<u32>,
+    pub model_selection_policy: Option<String>,
+    pub bandit_epsilon_percent: Option<u32>,
+    pub model_cost_preference: Option<String>,
+    pub llm_cache_enabled: Option<bool>,
+    pub llm_cache_ttl_hours: Option<u32>,
+    pub llm_transcripts_enabled: Option<bool>,
+    pub llm_transcript_max_kb: Option<u32>,
+    pub circuit_breaker_failures: Option<u32>,
+    pub circuit_breaker_open_minutes: Option<u32>,
+    pub mutation_ttl_hours: Option<u32>,
+    pub task_branch_mode: Option<bool>,
+    pub shadow_keep_on_failure: Option<bool>,
+    pub shadow_disk_quota_mb: Option<u32>,
+    pub shadow_sandbox: Option<bool>,
+    pub semantic_judge: Option<bool>,
+    pub pipeline_test_timeout_secs: Option<u32>,
+    pub pipeline_static_check_timeout_secs: Option<u32>,
+    pub pipeline_apply_timeout_secs: Option<u32>,
+    pub pipeline_max_output_kb: Option<u32>,
+    pub pipeline_ci_env: Option<Vec<String>>,
+    pub embedding_provider: Option<String>,
+    pub embedding_model: Option<String>,
+    pub otlp_endpoint: Option<String>,
+    pub metrics_enabled: Option<bool>,
+    pub metrics_port: Option<u32>,
+    pub http_api_enabled: Option<bool>,
+    pub http_api_port: Option<u32>,
+    pub db_encryption: Option<bool>,
+    pub secret_backend: Option<String>,
 }
 
 impl RuntimeFlags {
     pub fn from_env() -> Self {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// The flags `var` gives values for, each read like its environment variable.
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
         Self {
-            dev_mode: env_bool("AOP_DEV_MODE", false),
-            model_adapter_enabled: env_bool("AOP_MODEL_ADAPTER_ENABLED", true),
-            model_adapter_strict: env_bool("AOP_MODEL_ADAPTER_STRICT", false),
-            auto_approve_budget_requests: env_bool("AOP_AUTO_APPROVE_BUDGET_REQUESTS", true),
-            auto_commit_mutations: env_bool("AOP_AUTO_COMMIT_MUTATIONS", false),
-            budget_headroom_percent: env_f64("AOP_BUDGET_HEADROOM_PERCENT", 25.0, 1.0, 95.0),
-            budget_auto_max_percent: env_f64("AOP_BUDGET_AUTO_MAX_PERCENT", 40.0, 5.0, 100.0),
-            budget_min_increment: env_i64("AOP_BUDGET_MIN_INCREMENT", 250, 50, 100_000),
-            telemetry_retention_days: env_u32("AOP_TELEMETRY_RETENTION_DAYS", 7, 1, 365),
+            dev_mode: parse_bool(var("AOP_DEV_MODE"), false),
+            model_adapter_enabled: parse_bool(var("AOP_MODEL_ADAPTER_ENABLED"), true),
+            model_adapter_strict: parse_bool(var("AOP_MODEL_ADAPTER_STRICT"), false),
+            offline_specialists: parse_bool(var("AOP_OFFLINE_SPECIALISTS"), false),
+            auto_approve_budget_requests: parse_bool(var("AOP_AUTO_APPROVE_BUDGET_REQUESTS"), true),
+            auto_commit_mutations: parse_bool(var("AOP_AUTO_COMMIT_MUTATIONS"), false),
+            budget_headroom_percent: parse_f64(var("AOP_BUDGET_HEADROOM_PERCENT"), 25.0, 1.0, 95.0),
+            budget_auto_max_percent: parse_f64(
+                var("AOP_BUDGET_AUTO_MAX_PERCENT"),
+                40.0,
+                5.0,
+                100.0,
+            ),
+            budget_min_increment: parse_i64(var("AOP_BUDGET_MIN_INCREMENT"), 250, 50, 100_000),
+            spend_cap_daily_usd: parse_f64(var("AOP_SPEND_CAP_DAILY_USD"), 0.0, 0.0, 1_000_000.0),
+            spend_cap_monthly_usd: parse_f64(
+                var("AOP_SPEND_CAP_MONTHLY_USD"),
+                0.0,
+                0.0,
+                1_000_000.0,
+            ),
+            spend_cap_warn_percent: parse_u32(var("AOP_SPEND_CAP_WARN_PERCENT"), 80, 1, 100),
+            execution_window: var("AOP_EXECUTION_WINDOW")
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default(),
+            execution_window_ac_power: parse_bool(var("AOP_EXECUTION_WINDOW_AC_POWER"), false),
+            execution_window_min_tasks: parse_u32(
+                var("AOP_EXECUTION_WINDOW_MIN_TASKS"),
+                0,
+                0,
+                1_000,
+            ),
+            notifications_enabled: parse_bool(var("AOP_NOTIFICATIONS"), true),
+            notify_plan_ready: parse_bool(var("AOP_NOTIFY_PLAN_READY"), true),
+            notify_consensus_failed: parse_bool(var("AOP_NOTIFY_CONSENSUS_FAILED"), true),
+            notify_budget_request: parse_bool(var("AOP_NOTIFY_BUDGET_REQUEST"), true),
+            notify_run_finished: parse_bool(var("AOP_NOTIFY_RUN_FINISHED"), true),
+            webhook_urls: var("AOP_WEBHOOK_URLS")
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default(),
+            telemetry_retention_days: parse_u32(var("AOP_TELEMETRY_RETENTION_DAYS"), 7, 1, 365),
+            mutation_repair_attempts: parse_u32(var("AOP_MUTATION_REPAIR_ATTEMPTS"), 2, 0, 5),
+            llm_max_retries: parse_u32(var("AOP_LLM_MAX_RETRIES"), 3, 0, 8),
+            llm_retry_base_ms: parse_u32(var("AOP_LLM_RETRY_BASE_MS"), 500, 10, 30_000),
+            llm_batch_parallelism: parse_u32(var("AOP_LLM_BATCH_PARALLELISM"), 4, 1, 16),
+            claude_code_timeout_secs: parse_u32(
+                var("AOP_CLAUDE_CODE_TIMEOUT_SECS"),
+                600,
+                10,
+                7_200,
+            ),
+            openai_timeout_secs: parse_u32(var("AOP_OPENAI_TIMEOUT_SECS"), 120, 10, 7_200),
+            ollama_timeout_secs: parse_u32(var("AOP_OLLAMA_TIMEOUT_SECS"), 600, 10, 7_200),
+            llm_max_response_chars: parse_u32(
+                var("AOP_LLM_MAX_RESPONSE_CHARS"),
+                400_000,
+                10_000,
+                10_000_000,
+            ),
+            model_selection_policy: var("AOP_MODEL_SELECTION_POLICY")
+                .and_then(|value| model_bandit::normalize_policy(&value))
+                .unwrap_or(model_bandit::POLICY_UCB)
+                .to_string(),
+            bandit_epsilon_percent: parse_u32(var("AOP_BANDIT_EPSILON_PERCENT"), 10, 0, 100),
+            model_cost_preference: var("AOP_MODEL_COST_PREFERENCE")
+                .and_then(|value| model_intelligence::normalize_cost_preference(&value))
+                .unwrap_or("auto")
+                .to_string(),
+            llm_cache_enabled: parse_bool(var("AOP_LLM_CACHE"), false),
+            llm_cache_ttl_hours: parse_u32(var("AOP_LLM_CACHE_TTL_HOURS"), 24, 1, 720),
+            llm_transcripts_enabled: parse_bool(var("AOP_LLM_TRANSCRIPTS"), false),
+            llm_transcript_max_kb: parse_u32(var("AOP_LLM_TRANSCRIPT_MAX_KB"), 256, 4, 16_384),
+            circuit_breaker_failures: parse_u32(var("AOP_CIRCUIT_BREAKER_FAILURES"), 3, 0, 20),
+            circuit_breaker_open_minutes: parse_u32(
+                var("AOP_CIRCUIT_BREAKER_OPEN_MINUTES"),
+                5,
+                1,
+                1_440,
+            ),
+            mutation_ttl_hours: parse_u32(var("AOP_MUTATION_TTL_HOURS"), 168, 0, 8_760),
+            task_branch_mode: parse_bool(var("AOP_TASK_BRANCH_MODE"), false),
+            shadow_keep_on_failure: parse_bool(var("AOP_SHADOW_KEEP_ON_FAILURE"), false),
+            shadow_disk_quota_mb: parse_u32(var("AOP_SHADOW_DISK_QUOTA_MB"), 2_048, 256, 102_400),
+            shadow_sandbox: parse_bool(var("AOP_SHADOW_SANDBOX"), false),
+            semantic_judge: parse_bool(var("AOP_SEMANTIC_JUDGE"), true),
+            pipeline_test_timeout_secs: parse_u32(
+                var("AOP_PIPELINE_TEST_TIMEOUT_SECS"),
+                120,
+                10,
+                7_200,
+            ),
+            pipeline_static_check_timeout_secs: parse_u32(
+                var("AOP_PIPELINE_STATIC_CHECK_TIMEOUT_SECS"),
+                180,
+                10,
+                3_600,
+            ),
+            pipeline_apply_timeout_secs: parse_u32(
+                var("AOP_PIPELINE_APPLY_TIMEOUT_SECS"),
+                60,
+                5,
+                600,
+            ),
+            pipeline_max_output_kb: parse_u32(var("AOP_PIPELINE_MAX_OUTPUT_KB"), 64, 4, 8_192),
+            pipeline_ci_env: var("AOP_PIPELINE_CI_ENV")
+                .map(|value| normalize_env_entries(value.lines().map(str::to_string).collect()))
+                .unwrap_or_default(),
+            embedding_provider: var("AOP_EMBEDDING_PROVIDER")
+                .and_then(|value| embedding::normalize_provider_name(&value))
+                .unwrap_or(DEFAULT_EMBEDDING_PROVIDER)
+                .to_string(),
+            embedding_model: var("AOP_EMBEDDING_MODEL")
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default(),
+            otlp_endpoint: var("AOP_OTLP_ENDPOINT")
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default(),
+            metrics_enabled: parse_bool(var("AOP_METRICS_ENABLED"), false),
+            metrics_port: parse_u32(var("AOP_METRICS_PORT"), 9464, 1024, 65_535),
+            http_api_enabled: parse_bool(var("AOP_HTTP_API_ENABLED"), false),
+            http_api_port: parse_u32(var("AOP_HTTP_API_PORT"), 7878, 1024, 65_535),
+            db_encryption: parse_bool(var("AOP_DB_ENCRYPTION"), false),
+            secret_backend: var("AOP_SECRET_BACKEND")
+                .and_then(|name| SecretBackend::parse(&name))
+                .unwrap_or(SecretBackend::Stronghold)
+                .as_str()
+                .to_string(),
         }
     }
 
@@ -60,6 +367,9 @@ impl RuntimeFlags {
         if let Some(value) = input.model_adapter_strict {
             self.model_adapter_strict = value;
         }
+        if let Some(value) = input.offline_specialists {
+            self.offline_specialists = value;
+        }
         if let Some(value) = input.auto_approve_budget_requests {
             self.auto_approve_budget_requests = value;
         }
@@ -75,51 +385,601 @@ impl RuntimeFlags {
         if let Some(value) = input.budget_min_increment {
             self.budget_min_increment = value.clamp(50, 100_000);
         }
+        if let Some(value) = input.spend_cap_daily_usd {
+            self.spend_cap_daily_usd = value.clamp(0.0, 1_000_000.0);
+        }
+        if let Some(value) = input.spend_cap_monthly_usd {
+            self.spend_cap_monthly_usd = value.clamp(0.0, 1_000_000.0);
+        }
+        if let Some(value) = input.spend_cap_warn_percent {
+            self.spend_cap_warn_percent = value.clamp(1, 100);
+        }
+        if let Some(value) = input
+            .execution_window
+            .filter(|value| value.trim().is_empty() || execution_window::is_valid_hours(value))
+        {
+            self.execution_window = value.trim().to_string();
+        }
+        if let Some(value) = input.execution_window_ac_power {
+            self.execution_window_ac_power = value;
+        }
+        if let Some(value) = input.execution_window_min_tasks {
+            self.execution_window_min_tasks = value.min(1_000);
+        }
+        if let Some(value) = input.notifications_enabled {
+            self.notifications_enabled = value;
+        }
+        if let Some(value) = input.notify_plan_ready {
+            self.notify_plan_ready = value;
+        }
+        if let Some(value) = input.notify_consensus_failed {
+            self.notify_consensus_failed = value;
+        }
+        if let Some(value) = input.notify_budget_request {
+            self.notify_budget_request = value;
+        }
+        if let Some(value) = input.notify_run_finished {
+            self.notify_run_finished = value;
+        }
+        if let Some(value) = input.webhook_urls {
+            self.webhook_urls = value.trim().to_string();
+        }
         if let Some(value) = input.telemetry_retention_days {
             self.telemetry_retention_days = value.clamp(1, 365);
         }
+        if let Some(value) = input.mutation_repair_attempts {
+            self.mutation_repair_attempts = value.min(5);
+        }
+        if let Some(value) = input.llm_max_retries {
+            self.llm_max_retries = value.min(8);
+        }
+        if let Some(value) = input.llm_retry_base_ms {
+            self.llm_retry_base_ms = value.clamp(10, 30_000);
+        }
+        if let Some(value) = input.llm_batch_parallelism {
+            self.llm_batch_parallelism = value.clamp(1, 16);
+        }
+        if let Some(value) = input.claude_code_timeout_secs {
+            self.claude_code_timeout_secs = value.clamp(10, 7_200);
+        }
+        if let Some(value) = input.openai_timeout_secs {
+            self.openai_timeout_secs = value.clamp(10, 7_200);
+        }
+        if let Some(value) = input.ollama_timeout_secs {
+            self.ollama_timeout_secs = value.clamp(10, 7_200);
+        }
+        if let Some(value) = input.llm_max_response_chars {
+            self.llm_max_response_chars = value.clamp(10_000, 10_000_000);
+        }
+        if let Some(policy) = input
+            .model_selection_policy
+            .as_deref()
+            .and_then(model_bandit::normalize_policy)
+        {
+            self.model_selection_policy = policy.to_string();
+        }
+        if let Some(value) = input.bandit_epsilon_percent {
+            self.bandit_epsilon_percent = value.min(100);
+        }
+        if let Some(preference) = input
+            .model_cost_preference
+            .as_deref()
+            .and_then(model_intelligence::normalize_cost_preference)
+        {
+            self.model_cost_preference = preference.to_string();
+        }
+        if let Some(value) = input.llm_cache_enabled {
+            self.llm_cache_enabled = value;
+        }
+        if let Some(value) = input.llm_cache_ttl_hours {
+            self.llm_cache_ttl_hours = value.clamp(1, 720);
+        }
+        if let Some(value) = input.llm_transcripts_enabled {
+            self.llm_transcripts_enabled = value;
+        }
+        if let Some(value) = input.llm_transcript_max_kb {
+            self.llm_transcript_max_kb = value.clamp(4, 16_384);
+        }
+        if let Some(value) = input.circuit_breaker_failures {
+            self.circuit_breaker_failures = value.min(20);
+        }
+        if let Some(value) = input.circuit_breaker_open_minutes {
+            self.circuit_breaker_open_minutes = value.clamp(1, 1_440);
+        }
+        if let Some(value) = input.mutation_ttl_hours {
+            self.mutation_ttl_hours = value.min(8_760);
+        }
+        if let Some(value) = input.task_branch_mode {
+            self.task_branch_mode = value;
+        }
+        if let Some(value) = input.shadow_keep_on_failure {
+            self.shadow_keep_on_failure = value;
+        }
+        if let Some(value) = input.shadow_disk_quota_mb {
+            self.shadow_disk_quota_mb = value.clamp(256, 102_400);
+        }
+        if let Some(value) = input.shadow_sandbox {
+            self.shadow_sandbox = value;
+        }
+        if let Some(value) = input.semantic_judge {
+            self.semantic_judge = value;
+        }
+        if let Some(value) = input.pipeline_test_timeout_secs {
+            self.pipeline_test_timeout_secs = value.clamp(10, 7_200);
+        }
+        if let Some(value) = input.pipeline_static_check_timeout_secs {
+            self.pipeline_static_check_timeout_secs = value.clamp(10, 3_600);
+        }
+        if let Some(value) = input.pipeline_apply_timeout_secs {
+            self.pipeline_apply_timeout_secs = value.clamp(5, 600);
+        }
+        if let Some(value) = input.pipeline_max_output_kb {
+            self.pipeline_max_output_kb = value.clamp(4, 8_192);
+        }
+        if let Some(value) = input.pipeline_ci_env {
+            self.pipeline_ci_env = normalize_env_entries(value);
+        }
+        if let Some(name) = input
+            .embedding_provider
+            .as_deref()
+            .and_then(embedding::normalize_provider_name)
+        {
+            self.embedding_provider = name.to_string();
+        }
+        if let Some(value) = input.embedding_model {
+            self.embedding_model = value.trim().to_string();
+        }
+        if let Some(value) = input.otlp_endpoint {
+            self.otlp_endpoint = value.trim().to_string();
+        }
+        if let Some(value) = input.metrics_enabled {
+            self.metrics_enabled = value;
+        }
+        if let Some(value) = input.metrics_port {
+            self.metrics_port = value.clamp(1024, 65_535);
+        }
+        if let Some(value) = input.http_api_enabled {
+            self.http_api_enabled = value;
+        }
+        if let Some(value) = input.http_api_port {
+            self.http_api_port = value.clamp(1024, 65_535);
+        }
+        if let Some(value) = input.db_encryption {
+            self.db_encryption = value;
+        }
+        if let Some(backend) = input
+            .secret_backend
+            .as_deref()
+            .and_then(SecretBackend::parse)
+        {
+            self.secret_backend = backend.as_str().to_string();
+        }
+    }
+
+    /// The `AOP_*` variable behind each flag, with its value in this set of flags.
+    fn env_vars(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("AOP_DEV_MODE", bool_to_env(self.dev_mode).to_string()),
+            (
+                "AOP_MODEL_ADAPTER_ENABLED",
+                bool_to_env(self.model_adapter_enabled).to_string(),
+            ),
+            (
+                "AOP_MODEL_ADAPTER_STRICT",
+                bool_to_env(self.model_adapter_strict).to_string(),
+            ),
+            (
+                "AOP_OFFLINE_SPECIALISTS",
+                bool_to_env(self.offline_specialists).to_string(),
+            ),
+            (
+                "AOP_AUTO_APPROVE_BUDGET_REQUESTS",
+                bool_to_env(self.auto_approve_budget_requests).to_string(),
+            ),
+            (
+                "AOP_AUTO_COMMIT_MUTATIONS",
+                bool_to_env(self.auto_commit_mutations).to_string(),
+            ),
+            (
+                "AOP_BUDGET_HEADROOM_PERCENT",
+                self.budget_headroom_percent.to_string(),
+            ),
+            (
+                "AOP_BUDGET_AUTO_MAX_PERCENT",
+                self.budget_auto_max_percent.to_string(),
+            ),
+            (
+                "AOP_BUDGET_MIN_INCREMENT",
+                self.budget_min_increment.to_string(),
+            ),
+            (
+                "AOP_SPEND_CAP_DAILY_USD",
+                self.spend_cap_daily_usd.to_string(),
+            ),
+            (
+                "AOP_SPEND_CAP_MONTHLY_USD",
+                self.spend_cap_monthly_usd.to_string(),
+            ),
+            (
+                "AOP_SPEND_CAP_WARN_PERCENT",
+                self.spend_cap_warn_percent.to_string(),
+            ),
+            ("AOP_EXECUTION_WINDOW", self.execution_window.clone()),
+            (
+                "AOP_EXECUTION_WINDOW_AC_POWER",
+                bool_to_env(self.execution_window_ac_power).to_string(),
+            ),
+            (
+                "AOP_EXECUTION_WINDOW_MIN_TASKS",
+                self.execution_window_min_tasks.to_string(),
+            ),
+            (
+                "AOP_NOTIFICATIONS",
+                bool_to_env(self.notifications_enabled).to_string(),
+            ),
+            (
+                "AOP_NOTIFY_PLAN_READY",
+                bool_to_env(self.notify_plan_ready).to_string(),
+            ),
+            (
+                "AOP_NOTIFY_CONSENSUS_FAILED",
+                bool_to_env(self.notify_consensus_failed).to_string(),
+            ),
+            (
+                "AOP_NOTIFY_BUDGET_REQUEST",
+                bool_to_env(self.notify_budget_request).to_string(),
+            ),
+            (
+                "AOP_NOTIFY_RUN_FINISHED",
+                bool_to_env(self.notify_run_finished).to_string(),
+            ),
+            ("AOP_WEBHOOK_URLS", self.webhook_urls.clone()),
+            (
+                "AOP_TELEMETRY_RETENTION_DAYS",
+                self.telemetry_retention_days.to_string(),
+            ),
+            (
+                "AOP_MUTATION_REPAIR_ATTEMPTS",
+                self.mutation_repair_attempts.to_string(),
+            ),
+            ("AOP_LLM_MAX_RETRIES", self.llm_max_retries.to_string()),
+            ("AOP_LLM_RETRY_BASE_MS", self.llm_retry_base_ms.to_string()),
+            (
+                "AOP_LLM_BATCH_PARALLELISM",
+                self.llm_batch_parallelism.to_string(),
+            ),
+            (
+                "AOP_CLAUDE_CODE_TIMEOUT_SECS",
+                self.claude_code_timeout_secs.to_string(),
+            ),
+            (
+                "AOP_OPENAI_TIMEOUT_SECS",
+                self.openai_timeout_secs.to_string(),
+            ),
+            (
+                "AOP_OLLAMA_TIMEOUT_SECS",
+                self.ollama_timeout_secs.to_string(),
+            ),
+            (
+                "AOP_LLM_MAX_RESPONSE_CHARS",
+                self.llm_max_response_chars.to_string(),
+            ),
+            (
+                "AOP_MODEL_SELECTION_POLICY",
+                self.model_selection_policy.clone(),
+            ),
+            (
+                "AOP_BANDIT_EPSILON_PERCENT",
+                self.bandit_epsilon_percent.to_string(),
+            ),
+            (
+                "AOP_MODEL_COST_PREFERENCE",
+                self.model_cost_preference.clone(),
+            ),
+            (
+                "AOP_LLM_CACHE",
+                bool_to_env(self.llm_cache_enabled).to_string(),
+            ),
+            (
+                "AOP_LLM_CACHE_TTL_HOURS",
+                self.llm_cache_ttl_hours.to_string(),
+            ),
+            (
+                "AOP_LLM_TRANSCRIPTS",
+                bool_to_env(self.llm_transcripts_enabled).to_string(),
+            ),
+            (
+                "AOP_LLM_TRANSCRIPT_MAX_KB",
+                self.llm_transcript_max_kb.to_string(),
+            ),
+            (
+                "AOP_CIRCUIT_BREAKER_FAILURES",
+                self.circuit_breaker_failures.to_string(),
+            ),
+            (
+                "AOP_CIRCUIT_BREAKER_OPEN_MINUTES",
+                self.circuit_breaker_open_minutes.to_string(),
+            ),
+            (
+                "AOP_MUTATION_TTL_HOURS",
+                self.mutation_ttl_hours.to_string(),
+            ),
+            (
+                "AOP_TASK_BRANCH_MODE",
+                bool_to_env(self.task_branch_mode).to_string(),
+            ),
+            (
+                "AOP_SHADOW_KEEP_ON_FAILURE",
+                bool_to_env(self.shadow_keep_on_failure).to_string(),
+            ),
+            (
+                "AOP_SHADOW_DISK_QUOTA_MB",
+                self.shadow_disk_quota_mb.to_string(),
+            ),
+            (
+                "AOP_SHADOW_SANDBOX",
+                bool_to_env(self.shadow_sandbox).to_string(),
+            ),
+            (
+                "AOP_SEMANTIC_JUDGE",
+                bool_to_env(self.semantic_judge).to_string(),
+            ),
+            (
+                "AOP_PIPELINE_TEST_TIMEOUT_SECS",
+                self.pipeline_test_timeout_secs.to_string(),
+            ),
+            (
+                "AOP_PIPELINE_STATIC_CHECK_TIMEOUT_SECS",
+                self.pipeline_static_check_timeout_secs.to_string(),
+            ),
+            (
+                "AOP_PIPELINE_APPLY_TIMEOUT_SECS",
+                self.pipeline_apply_timeout_secs.to_string(),
+            ),
+            (
+                "AOP_PIPELINE_MAX_OUTPUT_KB",
+                self.pipeline_max_output_kb.to_string(),
+            ),
+            ("AOP_PIPELINE_CI_ENV", self.pipeline_ci_env.join("\n")),
+            ("AOP_EMBEDDING_PROVIDER", self.embedding_provider.clone()),
+            ("AOP_EMBEDDING_MODEL", self.embedding_model.clone()),
+            ("AOP_OTLP_ENDPOINT", self.otlp_endpoint.clone()),
+            (
+                "AOP_METRICS_ENABLED",
+                bool_to_env(self.metrics_enabled).to_string(),
+            ),
+            ("AOP_METRICS_PORT", self.metrics_port.to_string()),
+            (
+                "AOP_HTTP_API_ENABLED",
+                bool_to_env(self.http_api_enabled).to_string(),
+            ),
+            ("AOP_HTTP_API_PORT", self.http_api_port.to_string()),
+            (
+                "AOP_DB_ENCRYPTION",
+                bool_to_env(self.db_encryption).to_string(),
+            ),
+            ("AOP_SECRET_BACKEND", self.secret_backend.clone()),
+        ]
     }
 
     pub fn sync_to_process_env(&self) {
-        std::env::set_var("AOP_DEV_MODE", bool_to_env(self.dev_mode));
-        std::env::set_var(
-            "AOP_MODEL_ADAPTER_ENABLED",
-            bool_to_env(self.model_adapter_enabled),
-        );
-        std::env::set_var(
-            "AOP_MODEL_ADAPTER_STRICT",
-            bool_to_env(self.model_adapter_strict),
-        );
-        std::env::set_var(
-            "AOP_AUTO_APPROVE_BUDGET_REQUESTS",
-            bool_to_env(self.auto_approve_budget_requests),
-        );
-        std::env::set_var(
-            "AOP_AUTO_COMMIT_MUTATIONS",
-            bool_to_env(self.auto_commit_mutations),
-        );
-        std::env::set_var(
-            "AOP_BUDGET_HEADROOM_PERCENT",
-            self.budget_headroom_percent.to_string(),
-        );
-        std::env::set_var(
-            "AOP_BUDGET_AUTO_MAX_PERCENT",
-            self.budget_auto_max_percent.to_string(),
-        );
-        std::env::set_var(
-            "AOP_BUDGET_MIN_INCREMENT",
-            self.budget_min_increment.to_string(),
-        );
-        std::env::set_var(
-            "AOP_TELEMETRY_RETENTION_DAYS",
-            self.telemetry_retention_days.to_string(),
-        );
+        for (name, value) in self.env_vars() {
+            std::env::set_var(name, value);
+        }
+    }
+}
+
+/// File the flags are saved to. Without it changes only last until restart.
+pub fn set_config_path(path: PathBuf) {
+    let _ = CONFIG_PATH.set(path);
+}
+
+fn config_path() -> Option<&'static Path> {
+    CONFIG_PATH.get().map(PathBuf::as_path)
+}
+
+/// The saved flags, with any flag whose variable the environment (or `.env`) sets taking
+/// the environment's value instead. The process environment is left as it is.
+pub fn load() -> Result<RuntimeFlags, String> {
+    let saved = current_saved(config_path())?;
+    Ok(merge_saved(saved.flags, |name| std::env::var(name).ok()))
+}
+
+/// `saved` over the defaults, then every variable `env` sets over that.
+fn merge_saved(saved: SetRuntimeFlagsInput, env: impl Fn(&str) -> Option<String>) -> RuntimeFlags {
+    let mut flags = RuntimeFlags::from_vars(&env);
+    flags.apply_update(saved);
+    let saved_vars = flags.env_vars().into_iter().collect::<HashMap<_, _>>();
+    RuntimeFlags::from_vars(|name| env(name).or_else(|| saved_vars.get(name).cloned()))
+}
+
+/// Saves `flags` as the current ones. A profile name marks them as that profile's; `None`
+/// means they were edited by hand.
+pub fn save(flags: &RuntimeFlags, profile: Option<&str>) -> Result<(), String> {
+    save_at(config_path(), flags, profile)
+}
+
+fn save_at(path: Option<&Path>, flags: &RuntimeFlags, profile: Option<&str>) -> Result<(), String> {
+    update_saved(path, |saved| {
+        saved.profile = profile.map(str::to_string);
+        saved.flags = flags_input(flags)?;
+        Ok(())
+    })
+}
+
+/// Applies the profile `name` over `flags` and saves the result.
+pub fn apply_profile(flags: &mut RuntimeFlags, name: &str) -> Result<(), String> {
+    apply_profile_at(config_path(), flags, name)
+}
+
+fn apply_profile_at(
+    path: Option<&Path>,
+    flags: &mut RuntimeFlags,
+    name: &str,
+) -> Result<(), String> {
+    let name = profile_name(name)?;
+    let profile = match builtin_profile(&name) {
+        Some(profile) => profile,
+        None => current_saved(path)?
+            .profiles
+            .remove(&name)
+            .ok_or_else(|| format!("Unknown runtime profile '{name}'"))?,
+    };
+    flags.apply_update(profile);
+    save_at(path, flags, Some(&name))
+}
+
+/// Saves `flags` as the profile `name`, replacing an earlier one of that name.
+pub fn save_profile(flags: &RuntimeFlags, name: &str) -> Result<(), String> {
+    save_profile_at(config_path(), flags, name)
+}
+
+fn save_profile_at(path: Option<&Path>, flags: &RuntimeFlags, name: &str) -> Result<(), String> {
+    let name = profile_name(name)?;
+    if BUILTIN_PROFILES.contains(&name.as_str()) {
+        return Err(format!(
+            "'{name}' is a built-in profile and cannot be replaced"
+        ));
+    }
+    update_saved(path, |saved| {
+        let input = flags_input(flags)?;
+        saved.profiles.insert(name.clone(), input.clone());
+        saved.profile = Some(name.clone());
+        saved.flags = input;
+        Ok(())
+    })
+}
+
+pub fn list_profiles() -> Result<Vec<RuntimeProfile>, String> {
+    list_profiles_at(config_path())
+}
+
+fn list_profiles_at(path: Option<&Path>) -> Result<Vec<RuntimeProfile>, String> {
+    let saved = current_saved(path)?;
+    let active = saved.profile.as_deref();
+    Ok(BUILTIN_PROFILES
+        .iter()
+        .map(|name| (name.to_string(), true))
+        .chain(saved.profiles.keys().map(|name| (name.clone(), false)))
+        .map(|(name, builtin)| RuntimeProfile {
+            active: active == Some(name.as_str()),
+            name,
+            builtin,
+        })
+        .collect())
+}
+
+fn builtin_profile(name: &str) -> Option<SetRuntimeFlagsInput> {
+    let profile = match name {
+        "balanced" => SetRuntimeFlagsInput {
+            model_adapter_strict: Some(false),
+            auto_approve_budget_requests: Some(true),
+            budget_auto_max_percent: Some(40.0),
+            mutation_repair_attempts: Some(2),
+            llm_batch_parallelism: Some(4),
+            model_cost_preference: Some("auto".to_string()),
+            llm_cache_enabled: Some(false),
+            shadow_sandbox: Some(false),
+            semantic_judge: Some(true),
+            ..Default::default()
+        },
+        // Asks before spending, prefers the strongest models and checks every change.
+        "cautious" => SetRuntimeFlagsInput {
+            model_adapter_strict: Some(true),
+            auto_approve_budget_requests: Some(false),
+            budget_auto_max_percent: Some(15.0),
+            mutation_repair_attempts: Some(2),
+            llm_batch_parallelism: Some(2),
+            model_cost_preference: Some("best".to_string()),
+            llm_cache_enabled: Some(false),
+            shadow_sandbox: Some(true),
+            semantic_judge: Some(true),
+            ..Default::default()
+        },
+        // Cheap models, cached replies and more parallel calls, with fewer checks.
+        "fast-and-cheap" => SetRuntimeFlagsInput {
+            model_adapter_strict: Some(false),
+            auto_approve_budget_requests: Some(true),
+            budget_auto_max_percent: Some(60.0),
+            mutation_repair_attempts: Some(1),
+            llm_batch_parallelism: Some(8),
+            model_cost_preference: Some("cheap".to_string()),
+            llm_cache_enabled: Some(true),
+            shadow_sandbox: Some(false),
+            semantic_judge: Some(false),
+            ..Default::default()
+        },
+        _ => return None,
+    };
+    Some(profile)
+}
+
+fn profile_name(name: &str) -> Result<String, String> {
+    let name = name.trim().to_ascii_lowercase();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+    {
+        return Err("Profile name must be letters, digits, '-' or '_'".to_string());
     }
+    Ok(name)
 }
 
-fn env_bool(key: &str, default: bool) -> bool {
-    std::env::var(key)
-        .ok()
+/// Every flag of `flags`, in the form the file stores.
+fn flags_input(flags: &RuntimeFlags) -> Result<SetRuntimeFlagsInput, String> {
+    serde_json::to_value(flags)
+        .and_then(serde_json::from_value)
+        .map_err(|error| format!("Failed to encode runtime flags: {error}"))
+}
+
+fn current_saved(path: Option<&Path>) -> Result<SavedRuntimeConfig, String> {
+    match path {
+        Some(path) => read_saved(path),
+        None => Ok(SavedRuntimeConfig::default()),
+    }
+}
+
+fn update_saved(
+    path: Option<&Path>,
+    edit: impl FnOnce(&mut SavedRuntimeConfig) -> Result<(), String>,
+) -> Result<(), String> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    let _guard = WRITE_LOCK
+        .lock()
+        .map_err(|_| "Runtime flags file lock is poisoned".to_string())?;
+    let mut saved = read_saved(path)?;
+    edit(&mut saved)?;
+    write_saved(path, &saved)
+}
+
+fn read_saved(path: &Path) -> Result<SavedRuntimeConfig, String> {
+    if !path.exists() {
+        return Ok(SavedRuntimeConfig::default());
+    }
+    let raw = fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read runtime flags file: {error}"))?;
+    serde_json::from_str(&raw).map_err(|error| format!("Invalid runtime flags file: {error}"))
+}
+
+fn write_saved(path: &Path, saved: &SavedRuntimeConfig) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| format!("Failed to create runtime flags dir: {error}"))?;
+    }
+    let encoded = serde_json::to_string_pretty(saved)
+        .map_err(|error| format!("Failed to encode runtime flags file: {error}"))?;
+    let staging = path.with_extension("json.tmp");
+    fs::write(&staging, encoded)
+        .map_err(|error| format!("Failed to write runtime flags file: {error}"))?;
+    fs::rename(&staging, path)
+        .map_err(|error| format!("Failed to write runtime flags file: {error}"))
+}
+
+fn parse_bool(value: Option<String>, default: bool) -> bool {
+    value
         .map(|value| {
             matches!(
                 value.trim().to_ascii_lowercase().as_str(),
@@ -129,30 +989,41 @@ fn env_bool(key: &str, default: bool) -> bool {
         .unwrap_or(default)
 }
 
-fn env_f64(key: &str, default: f64, min: f64, max: f64) -> f64 {
-    std::env::var(key)
-        .ok()
+fn parse_f64(value: Option<String>, default: f64, min: f64, max: f64) -> f64 {
+    value
         .and_then(|value| value.trim().parse::<f64>().ok())
         .map(|value| value.clamp(min, max))
         .unwrap_or(default)
 }
 
-fn env_i64(key: &str, default: i64, min: i64, max: i64) -> i64 {
-    std::env::var(key)
-        .ok()
+fn parse_i64(value: Option<String>, default: i64, min: i64, max: i64) -> i64 {
+    value
         .and_then(|value| value.trim().parse::<i64>().ok())
         .map(|value| value.clamp(min, max))
         .unwrap_or(default)
 }
 
-fn env_u32(key: &str, default: u32, min: u32, max: u32) -> u32 {
-    std::env::var(key)
-        .ok()
+fn parse_u32(value: Option<String>, default: u32, min: u32, max: u32) -> u32 {
+    value
         .and_then(|value| value.trim().parse::<u32>().ok())
         .map(|value| value.clamp(min, max))
         .unwrap_or(default)
 }
 
+/// Keeps only well-formed `KEY=VALUE` entries, trimmed.
+fn normalize_env_entries(entries: Vec<String>) -> Vec<String> {
+    entries
+        .into_iter()
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| {
+            entry
+                .split_once('=')
+                .map(|(key, _)| !key.trim().is_empty())
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
 fn bool_to_env(value: bool) -> &'static str {
     if value {
         "1"
@@ -160,3 +1031,112 @@ fn bool_to_env(value: bool) -> &'static str {
         "0"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn saved_flags_and_profiles_round_trip() {
+        let dir = tempdir().expect("temp dir should be created");
+        let path = dir.path().join("runtime_flags.json");
+        let mut flags = RuntimeFlags::from_env();
+        flags.llm_max_retries = 6;
+        let mut saved = SavedRuntimeConfig {
+            profile: None,
+            flags: flags_input(&flags).expect("flags should encode"),
+            profiles: BTreeMap::new(),
+        };
+        saved
+            .profiles
+            .insert("night".to_string(), SetRuntimeFlagsInput::default());
+        write_saved(&path, &saved).expect("file should be written");
+
+        let read = read_saved(&path).expect("file should be read");
+        assert_eq!(read.flags.llm_max_retries, Some(6));
+        assert!(read.profiles.contains_key("night"));
+        assert!(read_saved(&dir.path().join("missing.json"))
+            .expect("a missing file is empty")
+            .profile
+            .is_none());
+
+        let mut cautious = RuntimeFlags::from_env();
+        cautious.apply_update(builtin_profile("cautious").expect("built-in profile"));
+        assert!(!cautious.auto_approve_budget_requests);
+        assert_eq!(cautious.model_cost_preference, "best");
+        assert_eq!(
+            cautious.llm_max_retries,
+            RuntimeFlags::from_env().llm_max_retries
+        );
+        assert!(profile_name("Fast-And-Cheap").is_ok());
+        assert!(profile_name("../x").is_err());
+    }
+
+    #[test]
+    fn environment_values_win_over_saved_flags_without_touching_the_environment() {
+        let env_before = std::env::var("AOP_METRICS_PORT").ok();
+        let saved = SetRuntimeFlagsInput {
+            llm_max_retries: Some(6),
+            metrics_port: Some(9_500),
+            model_cost_preference: Some("cheap".to_string()),
+            ..Default::default()
+        };
+        let env = HashMap::from([("AOP_LLM_MAX_RETRIES", "2".to_string())]);
+
+        let flags = merge_saved(saved, |name| env.get(name).cloned());
+        assert_eq!(flags.llm_max_retries, 2);
+        assert_eq!(flags.metrics_port, 9_500);
+        assert_eq!(flags.model_cost_preference, "cheap");
+        assert_eq!(flags.telemetry_retention_days, 7);
+        assert_eq!(std::env::var("AOP_METRICS_PORT").ok(), env_before);
+
+        let round_trip = RuntimeFlags::from_vars(|name| {
+            flags
+                .env_vars()
+                .into_iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value)
+        });
+        assert_eq!(
+            serde_json::to_value(&round_trip).expect("flags should encode"),
+            serde_json::to_value(&flags).expect("flags should encode")
+        );
+    }
+
+    #[test]
+    fn flags_are_saved_and_profiles_switch_over_them() {
+        let dir = tempdir().expect("temp dir should be created");
+        let path = dir.path().join("runtime_flags.json");
+        let at = Some(path.as_path());
+
+        let mut flags = RuntimeFlags::from_env();
+        flags.llm_max_retries = 6;
+        save_at(at, &flags, None).expect("flags should save");
+        let saved = read_saved(&path).expect("file should be read");
+        assert_eq!(saved.flags.llm_max_retries, Some(6));
+        assert!(saved.profile.is_none());
+
+        save_profile_at(at, &flags, "night").expect("profile should save");
+        assert!(save_profile_at(at, &flags, "balanced").is_err());
+        let night_preference = flags.model_cost_preference.clone();
+
+        apply_profile_at(at, &mut flags, "cautious").expect("built-in profile should apply");
+        assert_eq!(flags.model_cost_preference, "best");
+        assert_eq!(flags.llm_max_retries, 6);
+        let active = list_profiles_at(at)
+            .expect("profiles should list")
+            .into_iter()
+            .filter(|profile| profile.active)
+            .map(|profile| profile.name)
+            .collect::<Vec<_>>();
+        assert_eq!(active, ["cautious"]);
+
+        apply_profile_at(at, &mut flags, "night").expect("saved profile should apply");
+        assert_eq!(flags.model_cost_preference, night_preference);
+        let saved = read_saved(&path).expect("file should be read");
+        assert_eq!(saved.profile.as_deref(), Some("night"));
+        assert!(apply_profile_at(at, &mut flags, "missing").is_err());
+    }
+}
@@ -2,27 +2,83 @@ use std::path::PathBuf;
 
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
 use tauri_plugin_stronghold::stronghold::Stronghold;
 use uuid::Uuid;
 
+use crate::db::metrics::{self, SECRET_ACCESS_ACTION};
+
+/// Store key of the database encryption key, which provider names may not use.
+const DATABASE_KEY_RECORD: &str = "aop:database_key";
+/// Service name of the OS keychain entries.
+const KEYCHAIN_SERVICE: &str = "aop";
+/// Keychain account listing the stored names, since keychains cannot be enumerated.
+const KEYCHAIN_INDEX: &str = "aop:index";
+
+/// Where secrets are stored: the Stronghold snapshot in the app data folder, or the OS
+/// keychain (macOS Keychain, Windows Credential Manager, or libsecret on Linux).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretBackend {
+    Stronghold,
+    Keychain,
+}
+
+impl SecretBackend {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "stronghold" => Some(Self::Stronghold),
+            "keychain" | "keyring" | "os" => Some(Self::Keychain),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Stronghold => "stronghold",
+            Self::Keychain => "keychain",
+        }
+    }
+
+    /// The backend `AOP_SECRET_BACKEND` selects, Stronghold by default.
+    pub fn active() -> Self {
+        std::env::var("AOP_SECRET_BACKEND")
+            .ok()
+            .and_then(|name| Self::parse(&name))
+            .unwrap_or(Self::Stronghold)
+    }
+
+    fn other(self) -> Self {
+        match self {
+            Self::Stronghold => Self::Keychain,
+            Self::Keychain => Self::Stronghold,
+        }
+    }
+}
+
 pub struct SecretVault {
     app_data_dir: PathBuf,
     stronghold: Option<Stronghold>,
     client_id: Vec<u8>,
     confirmation: Option<(String, i64)>,
+    /// Where reads are logged; see [`SecretVault::set_access_log`].
+    access_log: Option<SqlitePool>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetProviderSecretStatusInput {
     pub provider: String,
+    /// Named key of the provider, such as `work`; the provider's default key when unset.
+    pub name: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SetProviderSecretInput {
     pub provider: String,
+    pub name: Option<String>,
     pub secret: String,
     pub session_token: Option<String>,
 }
@@ -31,6 +87,7 @@ pub struct SetProviderSecretInput {
 #[serde(rename_all = "camelCase")]
 pub struct RevealProviderSecretInput {
     pub provider: String,
+    pub name: Option<String>,
     pub session_token: Option<String>,
 }
 
@@ -59,6 +116,56 @@ pub struct RevealProviderSecretResult {
     pub secret: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListProviderCredentialsInput {
+    pub provider: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderCredential {
+    /// `provider` or `provider/name`, as models reference it with `credential`.
+    pub reference: String,
+    pub provider: String,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotateProviderSecretInput {
+    pub provider: String,
+    pub name: Option<String>,
+    pub secret: String,
+    /// Store the key without a test call, for providers that have none.
+    pub skip_validation: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretRotationResult {
+    pub provider: String,
+    pub validated: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrateSecretBackendInput {
+    /// `stronghold` or `keychain`.
+    pub backend: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretMigrationResult {
+    pub from: String,
+    pub to: String,
+    /// Names of the moved secrets; values never leave the vault.
+    pub migrated: Vec<String>,
+    /// Secrets copied to the new backend whose old copy could not be deleted.
+    pub left_in_source: Vec<String>,
+}
+
 impl SecretVault {
     pub fn new(app_data_dir: PathBuf) -> Self {
         Self {
@@ -66,9 +173,16 @@ impl SecretVault {
             stronghold: None,
             client_id: b"aop_provider_secrets".to_vec(),
             confirmation: None,
+            access_log: None,
         }
     }
 
+    /// Logs reads of stored keys to `pool` as `secret_access` audit events. Reads before
+    /// it is set, such as the database key at startup, go unrecorded.
+    pub fn set_access_log(&mut self, pool: SqlitePool) {
+        self.access_log = Some(pool);
+    }
+
     fn ensure_stronghold(&mut self) -> Result<(), String> {
         if self.stronghold.is_some() {
             return Ok(());
@@ -111,7 +225,7 @@ impl SecretVault {
         Ok(ProviderSecretStatus {
             provider: normalized,
             configured,
-            backend: "stronghold".to_string(),
+            backend: SecretBackend::active().as_str().to_string(),
             developer_mode,
         })
     }
@@ -140,21 +254,11 @@ impl SecretVault {
             });
         }
 
-        self.ensure_stronghold()?;
-        let client = self.stronghold()
-            .get_client(self.client_id.clone())
-            .map_err(|error| format!("Failed to access Stronghold client: {error}"))?;
-        client
-            .store()
-            .insert(
-                normalized.as_bytes().to_vec(),
-                trimmed_secret.as_bytes().to_vec(),
-                None,
-            )
-            .map_err(|error| format!("Failed to persist provider secret: {error}"))?;
-        self.stronghold()
-            .save()
-            .map_err(|error| format!("Failed to save Stronghold snapshot: {error}"))?;
+        self.write_to(
+            SecretBackend::active(),
+            normalized.as_str(),
+            trimmed_secret.as_bytes(),
+        )?;
 
         Ok(SecretOperationResult {
             provider: normalized,
@@ -197,15 +301,236 @@ impl SecretVault {
         })
     }
 
+    /// Reads a stored secret for backend integrations. Unlike `reveal_secret` this never
+    /// leaves the process, so it does not require developer mode. `accessor` names the
+    /// component asking, for the access log.
+    pub fn read_secret(
+        &mut self,
+        provider: &str,
+        accessor: &str,
+    ) -> Result<Option<String>, String> {
+        let normalized = normalize_provider(provider)?;
+        let secret = self.get_secret_bytes(normalized.as_str());
+        let outcome = match &secret {
+            Ok(Some(_)) => "found",
+            Ok(None) => "missing",
+            Err(_) => "failed",
+        };
+        if let Some(pool) = &self.access_log {
+            record_access(pool, accessor, &normalized, "vault", outcome);
+        }
+        secret?
+            .map(|secret| {
+                String::from_utf8(secret).map_err(|error| {
+                    format!("Stored secret for '{normalized}' is not UTF-8: {error}")
+                })
+            })
+            .transpose()
+    }
+
+    /// Overwrites a configured secret without the confirmation `set_secret` asks for. The
+    /// caller vouches for the new value, as rotation does with a test call.
+    pub fn replace_secret(&mut self, provider: &str, secret: &str) -> Result<(), String> {
+        let normalized = normalize_provider(provider)?;
+        let trimmed_secret = secret.trim();
+        if trimmed_secret.is_empty() {
+            return Err("secret is required".to_string());
+        }
+        if self.get_secret_bytes(normalized.as_str())?.is_none() {
+            return Err(format!(
+                "No secret configured for provider '{normalized}'; store one first"
+            ));
+        }
+        self.write_to(
+            SecretBackend::active(),
+            normalized.as_str(),
+            trimmed_secret.as_bytes(),
+        )
+    }
+
+    /// Stored provider keys, optionally of one provider. Values are never included.
+    pub fn list_credentials(
+        &mut self,
+        provider: Option<&str>,
+    ) -> Result<Vec<ProviderCredential>, String> {
+        let filter = provider.map(normalize_provider).transpose()?;
+        let mut credentials = self
+            .names_in(SecretBackend::active())?
+            .into_iter()
+            .filter(|reference| reference != DATABASE_KEY_RECORD)
+            .map(|reference| {
+                let (provider, name) = match reference.split_once('/') {
+                    Some((provider, name)) => (provider.to_string(), Some(name.to_string())),
+                    None => (reference.clone(), None),
+                };
+                ProviderCredential {
+                    reference,
+                    provider,
+                    name,
+                }
+            })
+            .filter(|credential| {
+                filter
+                    .as_ref()
+                    .is_none_or(|provider| &credential.provider == provider)
+            })
+            .collect::<Vec<_>>();
+        credentials.sort_by(|left, right| left.reference.cmp(&right.reference));
+        Ok(credentials)
+    }
+
+    /// The SQLCipher key for the orchestration database as 64 hex characters, generated
+    /// and stored on first use. It never leaves the process. A key only found in the other
+    /// backend is reused, so switching backends without migrating cannot orphan the database.
+    pub fn database_key(&mut self) -> Result<String, String> {
+        let backend = SecretBackend::active();
+        let stored = match self.read_from(backend, DATABASE_KEY_RECORD)? {
+            Some(key) => Some(key),
+            None => self
+                .read_from(backend.other(), DATABASE_KEY_RECORD)
+                .ok()
+                .flatten(),
+        };
+        if let Some(key) = stored {
+            return String::from_utf8(key)
+                .map_err(|error| format!("Stored database key is not UTF-8: {error}"));
+        }
+
+        let key = [Uuid::new_v4(), Uuid::new_v4()]
+            .iter()
+            .flat_map(|uuid| uuid.as_bytes().to_vec())
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        self.write_to(backend, DATABASE_KEY_RECORD, key.as_bytes())?;
+        Ok(key)
+    }
+
+    /// Moves every stored secret, the database key included, from the active backend to
+    /// `target`. All secrets are copied and read back before any source copy is deleted, so
+    /// a failure leaves the active backend complete. The caller switches the backend.
+    pub fn migrate_to(&mut self, target: SecretBackend) -> Result<SecretMigrationResult, String> {
+        let source = SecretBackend::active();
+        if source == target {
+            return Err(format!("Secrets are already stored in {}", target.as_str()));
+        }
+
+        let mut migrated = Vec::new();
+        for name in self.names_in(source)? {
+            let Some(value) = self.read_from(source, &name)? else {
+                continue;
+            };
+            self.write_to(target, &name, &value)?;
+            if self.read_from(target, &name)?.as_deref() != Some(value.as_slice()) {
+                return Err(format!(
+                    "Secret '{name}' did not read back from {}; nothing was removed from {}",
+                    target.as_str(),
+                    source.as_str()
+                ));
+            }
+            migrated.push(name);
+        }
+
+        let left_in_source = migrated
+            .iter()
+            .filter(|name| self.delete_from(source, name).is_err())
+            .cloned()
+            .collect();
+        Ok(SecretMigrationResult {
+            from: source.as_str().to_string(),
+            to: target.as_str().to_string(),
+            migrated,
+            left_in_source,
+        })
+    }
+
     fn get_secret_bytes(&mut self, provider: &str) -> Result<Option<Vec<u8>>, String> {
-        self.ensure_stronghold()?;
-        let client = self.stronghold()
-            .get_client(self.client_id.clone())
-            .map_err(|error| format!("Failed to access Stronghold client: {error}"))?;
-        client
-            .store()
-            .get(provider.as_bytes())
-            .map_err(|error| format!("Failed to read provider secret: {error}"))
+        self.read_from(SecretBackend::active(), provider)
+    }
+
+    fn read_from(&mut self, backend: SecretBackend, name: &str) -> Result<Option<Vec<u8>>, String> {
+        match backend {
+            SecretBackend::Stronghold => {
+                self.ensure_stronghold()?;
+                let client = self
+                    .stronghold()
+                    .get_client(self.client_id.clone())
+                    .map_err(|error| format!("Failed to access Stronghold client: {error}"))?;
+                client
+                    .store()
+                    .get(name.as_bytes())
+                    .map_err(|error| format!("Failed to read provider secret: {error}"))
+            }
+            SecretBackend::Keychain => Ok(keychain_get(name)?.map(String::into_bytes)),
+        }
+    }
+
+    fn write_to(&mut self, backend: SecretBackend, name: &str, value: &[u8]) -> Result<(), String> {
+        match backend {
+            SecretBackend::Stronghold => {
+                self.ensure_stronghold()?;
+                let client = self
+                    .stronghold()
+                    .get_client(self.client_id.clone())
+                    .map_err(|error| format!("Failed to access Stronghold client: {error}"))?;
+                client
+                    .store()
+                    .insert(name.as_bytes().to_vec(), value.to_vec(), None)
+                    .map_err(|error| format!("Failed to persist provider secret: {error}"))?;
+                self.stronghold()
+                    .save()
+                    .map_err(|error| format!("Failed to save Stronghold snapshot: {error}"))
+            }
+            SecretBackend::Keychain => {
+                let value = std::str::from_utf8(value)
+                    .map_err(|error| format!("Secret '{name}' is not UTF-8: {error}"))?;
+                keychain_set(name, value)?;
+                update_keychain_index(name, true)
+            }
+        }
+    }
+
+    fn delete_from(&mut self, backend: SecretBackend, name: &str) -> Result<(), String> {
+        match backend {
+            SecretBackend::Stronghold => {
+                self.ensure_stronghold()?;
+                let client = self
+                    .stronghold()
+                    .get_client(self.client_id.clone())
+                    .map_err(|error| format!("Failed to access Stronghold client: {error}"))?;
+                client
+                    .store()
+                    .delete(name.as_bytes())
+                    .map_err(|error| format!("Failed to delete provider secret: {error}"))?;
+                self.stronghold()
+                    .save()
+                    .map_err(|error| format!("Failed to save Stronghold snapshot: {error}"))
+            }
+            SecretBackend::Keychain => {
+                keychain_delete(name)?;
+                update_keychain_index(name, false)
+            }
+        }
+    }
+
+    fn names_in(&mut self, backend: SecretBackend) -> Result<Vec<String>, String> {
+        match backend {
+            SecretBackend::Stronghold => {
+                self.ensure_stronghold()?;
+                let client = self
+                    .stronghold()
+                    .get_client(self.client_id.clone())
+                    .map_err(|error| format!("Failed to access Stronghold client: {error}"))?;
+                let keys = client
+                    .store()
+                    .keys()
+                    .map_err(|error| format!("Failed to list provider secrets: {error}"))?;
+                Ok(keys
+                    .into_iter()
+                    .filter_map(|key| String::from_utf8(key).ok())
+                    .collect())
+            }
+            SecretBackend::Keychain => keychain_index(),
+        }
     }
 
     fn rotate_confirmation_token(&mut self) -> String {
@@ -232,16 +557,147 @@ impl SecretVault {
     }
 }
 
+/// Records to `pool` in the background that `accessor` read the key `reference` from
+/// `source`: `vault`, `env`, or `reveal` when it was shown in the UI. Only the key's name
+/// is logged, never its value.
+pub fn record_access(
+    pool: &SqlitePool,
+    accessor: &str,
+    reference: &str,
+    source: &str,
+    outcome: &str,
+) {
+    let pool = pool.clone();
+    let accessor = accessor.to_string();
+    let reference = reference.to_string();
+    let details = json!({ "source": source, "outcome": outcome }).to_string();
+    tauri::async_runtime::spawn(async move {
+        let _ = metrics::record_audit_event(
+            &pool,
+            &accessor,
+            SECRET_ACCESS_ACTION,
+            Some(&reference),
+            Some(&details),
+        )
+        .await;
+    });
+}
+
+/// The vault record of `provider`'s key called `name`, e.g. `openai/work`. A provider's
+/// default key is stored under the provider alone.
+pub fn credential_reference(provider: &str, name: Option<&str>) -> Result<String, String> {
+    let provider = normalize_provider(provider)?;
+    if provider.contains('/') {
+        return Err(format!(
+            "provider '{provider}' may not contain '/'; pass the key name as name"
+        ));
+    }
+    let Some(name) = name
+        .map(|name| name.trim().to_ascii_lowercase())
+        .filter(|name| !name.is_empty())
+    else {
+        return Ok(provider);
+    };
+    if !name
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+    {
+        return Err(format!(
+            "Credential name '{name}' may only use letters, digits, '-', '_' and '.'"
+        ));
+    }
+    Ok(format!("{provider}/{name}"))
+}
+
 fn normalize_provider(provider: &str) -> Result<String, String> {
     let normalized = provider.trim().to_ascii_lowercase();
     if normalized.is_empty() {
         return Err("provider is required".to_string());
     }
+    if normalized == DATABASE_KEY_RECORD || normalized == KEYCHAIN_INDEX {
+        return Err(format!("'{normalized}' is reserved"));
+    }
     Ok(normalized)
 }
 
+fn keychain_entry(name: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, name)
+        .map_err(|error| format!("Failed to open OS keychain entry '{name}': {error}"))
+}
+
+fn keychain_get(name: &str) -> Result<Option<String>, String> {
+    match keychain_entry(name)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(error) => Err(format!(
+            "Failed to read '{name}' from the OS keychain: {error}"
+        )),
+    }
+}
+
+fn keychain_set(name: &str, value: &str) -> Result<(), String> {
+    keychain_entry(name)?
+        .set_password(value)
+        .map_err(|error| format!("Failed to store '{name}' in the OS keychain: {error}"))
+}
+
+fn keychain_delete(name: &str) -> Result<(), String> {
+    match keychain_entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(error) => Err(format!(
+            "Failed to delete '{name}' from the OS keychain: {error}"
+        )),
+    }
+}
+
+fn keychain_index() -> Result<Vec<String>, String> {
+    keychain_get(KEYCHAIN_INDEX)?
+        .map(|raw| {
+            serde_json::from_str(&raw)
+                .map_err(|error| format!("OS keychain index is not valid JSON: {error}"))
+        })
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+fn update_keychain_index(name: &str, present: bool) -> Result<(), String> {
+    let mut names = keychain_index()?;
+    if present == names.iter().any(|known| known == name) {
+        return Ok(());
+    }
+    if present {
+        names.push(name.to_string());
+    } else {
+        names.retain(|known| known != name);
+    }
+    let raw = serde_json::to_string(&names)
+        .map_err(|error| format!("Failed to encode OS keychain index: {error}"))?;
+    keychain_set(KEYCHAIN_INDEX, &raw)
+}
+
 fn hash_password(password: &str) -> Vec<u8> {
     let mut hasher = Sha256::new();
     hasher.update(password.as_bytes());
     hasher.finalize().to_vec()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_credentials_are_stored_under_the_provider() {
+        assert_eq!(
+            credential_reference(" OpenAI ", None).expect("default"),
+            "openai"
+        );
+        assert_eq!(
+            credential_reference("openai", Some(" Work ")).expect("named"),
+            "openai/work"
+        );
+        assert!(credential_reference("openai/work", None).is_err());
+        assert!(credential_reference("openai", Some("eu west")).is_err());
+        assert!(credential_reference("aop:database_key", None).is_err());
+        assert!(credential_reference(" AOP:Index ", None).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::db::mutations::MutationRecord;
+use crate::db::tasks::TaskRecord;
+use crate::llm_adapter::{self, AdapterRequest};
+use crate::model_intelligence::{
+    self, CostPreference, ModelRequirements, ModelSelectionRequest, ModelSelectionResult,
+};
+use crate::model_registry::ModelRegistry;
+
+const MAX_DIFF_CHARS: usize = 12_000;
+const MAX_TEST_OUTPUT_CHARS: usize = 3_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JudgeVerdict {
+    Aligned,
+    PartiallyAligned,
+    OffObjective,
+}
+
+impl JudgeVerdict {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JudgeVerdict::Aligned => "aligned",
+            JudgeVerdict::PartiallyAligned => "partially_aligned",
+            JudgeVerdict::OffObjective => "off_objective",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SemanticJudgement {
+    pub verdict: JudgeVerdict,
+    pub rationale: String,
+    /// `provider/model` that produced the verdict.
+    pub model: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct JudgeResponse {
+    verdict: JudgeVerdict,
+    #[serde(default)]
+    rationale: String,
+}
+
+/// The judge runs whenever the remote model adapter is on, unless `AOP_SEMANTIC_JUDGE`
+/// turns it off. The pipeline falls back to the embedding heuristic when it does not run.
+pub fn judge_enabled() -> bool {
+    let adapter_enabled = env_flag("AOP_MODEL_ADAPTER_ENABLED").unwrap_or(!cfg!(test));
+    adapter_enabled && env_flag("AOP_SEMANTIC_JUDGE").unwrap_or(true)
+}
+
+/// With a strict adapter a judge failure rejects the mutation instead of falling back.
+pub fn judge_required() -> bool {
+    env_flag("AOP_MODEL_ADAPTER_STRICT").unwrap_or(false)
+}
+
+fn env_flag(key: &str) -> Option<bool> {
+    std::env::var(key).ok().map(|value| {
+        matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        )
+    })
+}
+
+/// Asks a Tier 2 reviewer whether the validated diff does what the task asked for, given
+/// the shadow test outcome.
+pub async fn judge_mutation(
+    pool: &SqlitePool,
+    model_registry: &ModelRegistry,
+    task: &TaskRecord,
+    mutation: &MutationRecord,
+    test_outcome: &str,
+) -> Result<SemanticJudgement, String> {
+    let ModelSelectionResult {
+        selection,
+        fallbacks,
+        ..
+    } = model_intelligence::select_model(
+        pool,
+        model_registry,
+        ModelSelectionRequest {
+            task_id: Some(&task.id),
+            actor: "mutation_pipeline",
+            tier: 2,
+            persona: Some("semantic_judge"),
+            skill: Some("semantic_review"),
+            remaining_budget: None,
+            cost_preference: CostPreference::Balanced,
+            requirements: ModelRequirements::default(),
+        },
+    )
+    .await?;
+
+    let model = format!("{}/{}", selection.provider, selection.model_id);
+    let request = AdapterRequest {
+        provider: selection.provider,
+        model_id: selection.model_id,
+        system_prompt: SYSTEM_PROMPT.to_string(),
+        user_prompt: build_user_prompt(task, mutation, test_outcome),
+        endpoint: selection.endpoint,
+        generation: selection.generation,
+        task_id: Some(task.id.clone()),
+        fallbacks,
+        response_schema: None,
+        bypass_cache: false,
+        attachments: Vec::new(),
+        pool: Some(pool.clone()),
+    };
+    let response = tokio::task::spawn_blocking(move || llm_adapter::generate(&request))
+        .await
+        .map_err(|error| format!("Semantic judge task panicked: {error}"))?
+        .map_err(|error| format!("Semantic judge failed: {error}"))?;
+
+    let parsed = parse_judge_response(&response.text)?;
+    Ok(SemanticJudgement {
+        verdict: parsed.verdict,
+        rationale: parsed.rationale.trim().to_string(),
+        model,
+    })
+}
+
+const SYSTEM_PROMPT: &str = r#"You review code changes for the Autonomous Orchestration Platform (AOP).
+You receive a task objective, the unified diff proposed for it and the outcome of the test run.
+Judge whether the change actually accomplishes the objective. Ignore style; focus on behavior.
+
+Respond with ONLY a JSON object:
+{
+  "verdict": "aligned" | "partially_aligned" | "off_objective",
+  "rationale": "One or two sentences explaining the verdict"
+}
+
+- aligned: the diff implements the objective and nothing unrelated.
+- partially_aligned: the diff covers part of the objective, or also changes unrelated behavior.
+- off_objective: the diff does not address the objective, or works against it."#;
+
+fn build_user_prompt(task: &TaskRecord, mutation: &MutationRecord, test_outcome: &str) -> String {
+    let intent = mutation
+        .intent_description
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unwrap_or("(none)");
+    format!(
+        "OBJECTIVE:\n{}\n\nSTATED INTENT:\n{}\n\nFILE: {}\n\nDIFF:\n{}\n\nTEST OUTCOME:\n{}",
+        task.objective.trim(),
+        intent,
+        mutation.file_path,
+        head_chars(&mutation.diff_content, MAX_DIFF_CHARS),
+        tail_chars(test_outcome, MAX_TEST_OUTPUT_CHARS),
+    )
+}
+
+fn parse_judge_response(text: &str) -> Result<JudgeResponse, String> {
+    let trimmed = text.trim();
+    let start = trimmed.find('{');
+    let end = trimmed.rfind('}');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &trimmed[start..=end],
+        _ => trimmed,
+    };
+    serde_json::from_str::<JudgeResponse>(json)
+        .map_err(|error| format!("Failed to parse semantic judge response: {error}\nRaw: {text}"))
+}
+
+fn head_chars(value: &str, max_chars: usize) -> String {
+    if value.chars().count() <= max_chars {
+        return value.to_string();
+    }
+    let head = value.chars().take(max_chars).collect::<String>();
+    format!("{head}\n...[truncated]")
+}
+
+/// Keeps the end of the output, where test runners print the summary.
+fn tail_chars(value: &str, max_chars: usize) -> String {
+    let count = value.chars().count();
+    if count <= max_chars {
+        return value.to_string();
+    }
+    let tail = value.chars().skip(count - max_chars).collect::<String>();
+    format!("...[truncated]\n{tail}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_verdict_from_fenced_or_wrapped_json() {
+        let fenced =
+            "```json\n{\"verdict\": \"partially_aligned\", \"rationale\": \"Only half.\"}\n```";
+        let parsed = parse_judge_response(fenced).expect("fenced verdict should parse");
+        assert_eq!(parsed.verdict, JudgeVerdict::PartiallyAligned);
+        assert_eq!(parsed.rationale, "Only half.");
+
+        let wrapped = "Verdict follows: {\"verdict\": \"off_objective\"}";
+        let parsed = parse_judge_response(wrapped).expect("wrapped verdict should parse");
+        assert_eq!(parsed.verdict, JudgeVerdict::OffObjective);
+
+        assert!(parse_judge_response("{\"verdict\": \"maybe\"}").is_err());
+    }
+}
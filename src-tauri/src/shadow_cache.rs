@@ -0,0 +1,607 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+
+use uuid::Uuid;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+
+use crate::db::metrics;
+use crate::mutation_pipeline::{
+    copy_project_for_shadow, normalize_target_root, resolve_project_path, run_command_capture,
+};
+
+const GIT_TIMEOUT: Duration = Duration::from_secs(30);
+const CACHE_KEY_FILE: &str = "cache_key";
+const BASE_DIR: &str = "base";
+const SHADOW_DIR_PREFIX: &str = "aop_shadow_";
+const CACHE_ROOT_NAME: &str = "aop_shadow_cache";
+const DEFAULT_DISK_QUOTA_MB: u64 = 2_048;
+const JANITOR_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Serializes base rebuilds so two pipelines never copy into (or link from) the same base
+/// while it is being replaced.
+static CACHE_LOCK: Mutex<()> = Mutex::new(());
+/// Shadow workspaces owned by a running pipeline; the janitor and purges leave them alone.
+static ACTIVE_SHADOW_DIRS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// How a shadow workspace got its copy of the target project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadowBase {
+    /// Linked from a cached base that still matches the project's git state.
+    Cached,
+    /// The cached base was missing or stale and was rebuilt before linking.
+    Rebuilt,
+    /// The project is not a git repository, so it was copied without caching.
+    FullCopy,
+    /// A batch workspace that already holds the batch's earlier mutations.
+    Shared,
+}
+
+impl ShadowBase {
+    pub fn describe(self) -> &'static str {
+        match self {
+            ShadowBase::Cached => "Reused cached shadow base.",
+            ShadowBase::Rebuilt => "Rebuilt shadow base cache for the current git state.",
+            ShadowBase::FullCopy => "Copied project without caching (not a git repository).",
+            ShadowBase::Shared => "Continued in the batch's shared shadow workspace.",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanShadowCacheInput {
+    /// Limits cleanup to one project's cached base; all caches and leftover shadow
+    /// workspaces are removed when omitted.
+    pub target_project: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeShadowDirsInput {
+    /// Specific entries from `list_shadow_dirs`; every idle workspace when omitted.
+    pub paths: Option<Vec<String>>,
+    /// Also drop cached project bases when purging without explicit paths.
+    pub include_cache: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShadowCleanupResult {
+    pub removed_entries: usize,
+    pub freed_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShadowDirKind {
+    Workspace,
+    Cache,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShadowDirEntry {
+    pub path: String,
+    pub kind: ShadowDirKind,
+    pub size_bytes: u64,
+    pub modified_at: i64,
+    pub in_use: bool,
+}
+
+/// Creates a fresh shadow workspace and marks it as in use until `release_shadow_dir`.
+pub fn create_shadow_dir() -> Result<PathBuf, String> {
+    let dir = std::env::temp_dir().join(format!("{SHADOW_DIR_PREFIX}{}", Uuid::new_v4()));
+    fs::create_dir_all(&dir).map_err(|error| {
+        format!(
+            "Failed to create shadow directory '{}': {error}",
+            dir.display()
+        )
+    })?;
+    if let Ok(mut active) = ACTIVE_SHADOW_DIRS.lock() {
+        active.push(dir.clone());
+    }
+    Ok(dir)
+}
+
+/// Ends a pipeline's ownership of its workspace and deletes it unless `keep` is set.
+/// Returns whether the directory is still on disk.
+pub fn release_shadow_dir(shadow_root: &Path, keep: bool) -> bool {
+    if let Ok(mut active) = ACTIVE_SHADOW_DIRS.lock() {
+        active.retain(|path| path != shadow_root);
+    }
+    if keep {
+        return true;
+    }
+    fs::remove_dir_all(shadow_root).is_err() && shadow_root.exists()
+}
+
+pub fn keep_shadow_on_failure() -> bool {
+    std::env::var("AOP_SHADOW_KEEP_ON_FAILURE")
+        .ok()
+        .map(|value| {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false)
+}
+
+/// Fills `shadow_root` with the target project. Git projects are copied once into a cached
+/// base keyed by HEAD and the working tree status; each workspace is then an overlay of hard
+/// links to that base. The mutated file is always copied fresh, so writes to it (line ending
+/// normalization, `git apply`) never reach the shared base.
+pub async fn materialize_shadow(
+    target_root: &Path,
+    shadow_root: &Path,
+    mutated_file: &str,
+) -> Result<ShadowBase, String> {
+    let Some(key) = cache_key(target_root).await? else {
+        copy_project_for_shadow(target_root, shadow_root)?;
+        return Ok(ShadowBase::FullCopy);
+    };
+
+    let cache_dir = cache_dir_for_project(target_root);
+    let base_dir = cache_dir.join(BASE_DIR);
+    let shadow_base = {
+        let _guard = CACHE_LOCK
+            .lock()
+            .map_err(|_| "Shadow cache lock is poisoned".to_string())?;
+        let cached_key = fs::read_to_string(cache_dir.join(CACHE_KEY_FILE)).ok();
+        let shadow_base = if cached_key.as_deref() == Some(key.as_str()) && base_dir.is_dir() {
+            ShadowBase::Cached
+        } else {
+            rebuild_base(target_root, &cache_dir, &key)?;
+            ShadowBase::Rebuilt
+        };
+        link_tree(&base_dir, shadow_root)?;
+        shadow_base
+    };
+
+    refresh_mutated_file(target_root, shadow_root, mutated_file)?;
+    Ok(shadow_base)
+}
+
+pub async fn clean_shadow_cache(
+    pool: &SqlitePool,
+    input: CleanShadowCacheInput,
+) -> Result<ShadowCleanupResult, String> {
+    let targets = match input
+        .target_project
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        Some(target_project) => {
+            vec![cache_dir_for_project(&normalize_target_root(
+                target_project,
+            )?)]
+        }
+        None => {
+            let mut targets = vec![cache_root()];
+            targets.extend(
+                list_shadow_dirs()?
+                    .into_iter()
+                    .filter(|entry| entry.kind == ShadowDirKind::Workspace && !entry.in_use)
+                    .map(|entry| PathBuf::from(entry.path)),
+            );
+            targets
+        }
+    };
+
+    let result = remove_entries(&targets)?;
+    metrics::record_audit_event(
+        pool,
+        "ui",
+        "shadow_cache_cleaned",
+        input.target_project.as_deref(),
+        Some(&cleanup_details(&result)),
+    )
+    .await?;
+
+    Ok(result)
+}
+
+/// Shadow workspaces under the OS temp dir plus cached project bases, newest first.
+pub fn list_shadow_dirs() -> Result<Vec<ShadowDirEntry>, String> {
+    let active = ACTIVE_SHADOW_DIRS
+        .lock()
+        .map(|active| active.clone())
+        .unwrap_or_default();
+    let mut entries = Vec::new();
+
+    let temp_entries = fs::read_dir(std::env::temp_dir())
+        .map_err(|error| format!("Failed to scan temp directory: {error}"))?;
+    for entry in temp_entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        if name.starts_with(SHADOW_DIR_PREFIX) && name != CACHE_ROOT_NAME && path.is_dir() {
+            let in_use = active.contains(&path);
+            entries.push(shadow_dir_entry(path, ShadowDirKind::Workspace, in_use));
+        }
+    }
+    if let Ok(cache_entries) = fs::read_dir(cache_root()) {
+        for entry in cache_entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+        {
+            entries.push(shadow_dir_entry(entry.path(), ShadowDirKind::Cache, false));
+        }
+    }
+
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.modified_at));
+    Ok(entries)
+}
+
+pub async fn purge_shadow_dirs(
+    pool: &SqlitePool,
+    input: PurgeShadowDirsInput,
+) -> Result<ShadowCleanupResult, String> {
+    let listed = list_shadow_dirs()?;
+    let targets = match &input.paths {
+        Some(paths) => paths
+            .iter()
+            .map(|path| {
+                let entry = listed
+                    .iter()
+                    .find(|entry| entry.path == path.trim())
+                    .ok_or_else(|| format!("'{path}' is not a shadow directory."))?;
+                if entry.in_use {
+                    return Err(format!("'{path}' is used by a running pipeline."));
+                }
+                Ok(PathBuf::from(&entry.path))
+            })
+            .collect::<Result<Vec<_>, String>>()?,
+        None => {
+            let include_cache = input.include_cache.unwrap_or(false);
+            listed
+                .iter()
+                .filter(|entry| !entry.in_use)
+                .filter(|entry| include_cache || entry.kind == ShadowDirKind::Workspace)
+                .map(|entry| PathBuf::from(&entry.path))
+                .collect()
+        }
+    };
+
+    let result = remove_entries(&targets)?;
+    metrics::record_audit_event(
+        pool,
+        "ui",
+        "shadow_dirs_purged",
+        None,
+        Some(&cleanup_details(&result)),
+    )
+    .await?;
+    Ok(result)
+}
+
+/// Periodically evicts the oldest idle shadow dirs once their total size exceeds
+/// `AOP_SHADOW_DISK_QUOTA_MB`. The quota is re-read each round so flag changes apply
+/// without a restart.
+pub fn spawn_shadow_janitor(pool: SqlitePool) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let quota_bytes = disk_quota_mb_from_env().saturating_mul(1024 * 1024);
+            if let Ok(entries) = list_shadow_dirs() {
+                let evicted = select_for_eviction(&entries, quota_bytes)
+                    .into_iter()
+                    .map(|entry| PathBuf::from(&entry.path))
+                    .collect::<Vec<_>>();
+                if let Ok(result) = remove_entries(&evicted) {
+                    if result.removed_entries > 0 {
+                        let _ = metrics::record_audit_event(
+                            &pool,
+                            "shadow_janitor",
+                            "shadow_quota_enforced",
+                            None,
+                            Some(&cleanup_details(&result)),
+                        )
+                        .await;
+                    }
+                }
+            }
+            tokio::time::sleep(JANITOR_INTERVAL).await;
+        }
+    });
+}
+
+fn disk_quota_mb_from_env() -> u64 {
+    std::env::var("AOP_SHADOW_DISK_QUOTA_MB")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_DISK_QUOTA_MB)
+}
+
+/// Oldest idle entries to drop so the total fits in `quota_bytes`. Entries held by a
+/// running pipeline still count towards the total but are never selected.
+fn select_for_eviction(entries: &[ShadowDirEntry], quota_bytes: u64) -> Vec<&ShadowDirEntry> {
+    let mut total = entries.iter().map(|entry| entry.size_bytes).sum::<u64>();
+    let mut candidates = entries
+        .iter()
+        .filter(|entry| !entry.in_use)
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|entry| entry.modified_at);
+
+    let mut evicted = Vec::new();
+    for entry in candidates {
+        if total <= quota_bytes {
+            break;
+        }
+        total = total.saturating_sub(entry.size_bytes);
+        evicted.push(entry);
+    }
+    evicted
+}
+
+fn shadow_dir_entry(path: PathBuf, kind: ShadowDirKind, in_use: bool) -> ShadowDirEntry {
+    let modified_at = fs::metadata(&path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default();
+    ShadowDirEntry {
+        size_bytes: dir_size(&path),
+        path: path.to_string_lossy().to_string(),
+        kind,
+        modified_at,
+        in_use,
+    }
+}
+
+fn remove_entries(targets: &[PathBuf]) -> Result<ShadowCleanupResult, String> {
+    let mut result = ShadowCleanupResult {
+        removed_entries: 0,
+        freed_bytes: 0,
+    };
+    let _guard = CACHE_LOCK
+        .lock()
+        .map_err(|_| "Shadow cache lock is poisoned".to_string())?;
+    for target in targets.iter().filter(|target| target.exists()) {
+        let size = dir_size(target);
+        fs::remove_dir_all(target)
+            .map_err(|error| format!("Failed to remove '{}': {error}", target.display()))?;
+        result.removed_entries += 1;
+        result.freed_bytes += size;
+    }
+    Ok(result)
+}
+
+fn cleanup_details(result: &ShadowCleanupResult) -> String {
+    serde_json::json!({
+        "removedEntries": result.removed_entries,
+        "freedBytes": result.freed_bytes,
+    })
+    .to_string()
+}
+
+fn cache_root() -> PathBuf {
+    std::env::temp_dir().join(CACHE_ROOT_NAME)
+}
+
+fn cache_dir_for_project(target_root: &Path) -> PathBuf {
+    let digest = Sha256::digest(target_root.to_string_lossy().as_bytes());
+    let name = digest
+        .iter()
+        .take(8)
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    cache_root().join(name)
+}
+
+/// HEAD plus the porcelain status and the size/mtime of every dirty path, so uncommitted
+/// edits invalidate the base as well as new commits. `None` when the project is not a git
+/// repository.
+async fn cache_key(target_root: &Path) -> Result<Option<String>, String> {
+    if !target_root.join(".git").exists() {
+        return Ok(None);
+    }
+
+    let head = run_command_capture(
+        target_root,
+        "git",
+        vec!["rev-parse".to_string(), "HEAD".to_string()],
+        GIT_TIMEOUT,
+    )
+    .await?;
+    if head.exit_code != 0 {
+        return Ok(None);
+    }
+    let status = run_command_capture(
+        target_root,
+        "git",
+        vec![
+            "status".to_string(),
+            "--porcelain".to_string(),
+            "--untracked-files=all".to_string(),
+        ],
+        GIT_TIMEOUT,
+    )
+    .await?;
+    if status.exit_code != 0 {
+        return Ok(None);
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(head.stdout.trim().as_bytes());
+    for line in status.stdout.lines() {
+        hasher.update(line.as_bytes());
+        let path = line.get(3..).unwrap_or_default();
+        let path = path.rsplit(" -> ").next().unwrap_or(path).trim_matches('"');
+        if let Ok(metadata) = fs::metadata(target_root.join(path)) {
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_nanos())
+                .unwrap_or_default();
+            hasher.update(format!(":{}:{modified}\n", metadata.len()).as_bytes());
+        }
+    }
+    Ok(Some(format!("{:x}", hasher.finalize())))
+}
+
+fn rebuild_base(target_root: &Path, cache_dir: &Path, key: &str) -> Result<(), String> {
+    if cache_dir.exists() {
+        fs::remove_dir_all(cache_dir).map_err(|error| {
+            format!(
+                "Failed to clear stale shadow cache '{}': {error}",
+                cache_dir.display()
+            )
+        })?;
+    }
+    let base_dir = cache_dir.join(BASE_DIR);
+    fs::create_dir_all(&base_dir)
+        .map_err(|error| format!("Failed to create shadow cache directory: {error}"))?;
+    copy_project_for_shadow(target_root, &base_dir)?;
+    // The key is written last so an interrupted copy is never treated as a valid base.
+    fs::write(cache_dir.join(CACHE_KEY_FILE), key)
+        .map_err(|error| format!("Failed to write shadow cache key: {error}"))
+}
+
+/// Recreates the directory tree of `source` under `destination` with hard links, falling
+/// back to a copy where linking is not possible (e.g. across file systems).
+fn link_tree(source: &Path, destination: &Path) -> Result<(), String> {
+    let mut stack = vec![source.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|error| format!("Failed to read shadow cache '{}': {error}", dir.display()))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let relative = path
+                .strip_prefix(source)
+                .map_err(|error| format!("Failed to compute shadow overlay path: {error}"))?;
+            let target = destination.join(relative);
+            if path.is_dir() {
+                fs::create_dir_all(&target).map_err(|error| {
+                    format!("Failed to create shadow overlay directory: {error}")
+                })?;
+                stack.push(path);
+            } else if fs::hard_link(&path, &target).is_err() {
+                fs::copy(&path, &target).map_err(|error| {
+                    format!(
+                        "Failed to copy '{}' into shadow overlay: {error}",
+                        path.display()
+                    )
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Turns a hard-linked file in a shared workspace into a private copy of itself, keeping
+/// whatever earlier batch mutations wrote to it.
+pub fn detach_shadow_file(shadow_root: &Path, relative_file_path: &str) -> Result<(), String> {
+    let shadow_file = resolve_project_path(shadow_root, relative_file_path)?;
+    let Ok(content) = fs::read(&shadow_file) else {
+        return Ok(());
+    };
+    fs::remove_file(&shadow_file).map_err(|error| {
+        format!("Failed to unlink shadow copy of '{relative_file_path}': {error}")
+    })?;
+    fs::write(&shadow_file, content)
+        .map_err(|error| format!("Failed to detach shadow copy of '{relative_file_path}': {error}"))
+}
+
+/// Replaces the linked copy of the mutated file with a private copy of the current source.
+fn refresh_mutated_file(
+    target_root: &Path,
+    shadow_root: &Path,
+    mutated_file: &str,
+) -> Result<(), String> {
+    let shadow_file = resolve_project_path(shadow_root, mutated_file)?;
+    if shadow_file.exists() {
+        fs::remove_file(&shadow_file).map_err(|error| {
+            format!("Failed to unlink shadow copy of '{mutated_file}': {error}")
+        })?;
+    }
+    let source_file = resolve_project_path(target_root, mutated_file)?;
+    if source_file.is_file() {
+        if let Some(parent) = shadow_file.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|error| format!("Failed to create shadow parent directory: {error}"))?;
+        }
+        fs::copy(&source_file, &shadow_file)
+            .map_err(|error| format!("Failed to copy '{mutated_file}' into shadow: {error}"))?;
+    }
+    Ok(())
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let mut total = 0;
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            match entry.metadata() {
+                Ok(metadata) if metadata.is_dir() => stack.push(entry.path()),
+                Ok(metadata) => total += metadata.len(),
+                Err(_) => {}
+            }
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlay_links_base_and_keeps_mutated_file_private() {
+        let source = tempfile::tempdir().expect("source dir");
+        let base = tempfile::tempdir().expect("base dir");
+        let overlay = tempfile::tempdir().expect("overlay dir");
+        fs::create_dir_all(source.path().join("src")).expect("source src");
+        fs::write(source.path().join("src/lib.ts"), "current\n").expect("source file");
+        fs::create_dir_all(base.path().join("src")).expect("base src");
+        fs::write(base.path().join("src/lib.ts"), "cached\n").expect("base file");
+        fs::write(base.path().join("README.md"), "readme\n").expect("base readme");
+
+        link_tree(base.path(), overlay.path()).expect("overlay should link");
+        refresh_mutated_file(source.path(), overlay.path(), "src/lib.ts")
+            .expect("mutated file should refresh");
+        fs::write(overlay.path().join("src/lib.ts"), "patched\n").expect("overlay write");
+
+        assert_eq!(
+            fs::read_to_string(base.path().join("src/lib.ts")).expect("base file"),
+            "cached\n"
+        );
+        assert_eq!(
+            fs::read_to_string(overlay.path().join("README.md")).expect("overlay readme"),
+            "readme\n"
+        );
+    }
+
+    #[test]
+    fn eviction_drops_oldest_idle_entries_until_under_quota() {
+        let entry = |path: &str, size_bytes, modified_at, in_use| ShadowDirEntry {
+            path: path.to_string(),
+            kind: ShadowDirKind::Workspace,
+            size_bytes,
+            modified_at,
+            in_use,
+        };
+        let entries = vec![
+            entry("newest", 40, 300, false),
+            entry("running", 50, 100, true),
+            entry("oldest", 30, 50, false),
+            entry("middle", 20, 200, false),
+        ];
+
+        let evicted = select_for_eviction(&entries, 90)
+            .into_iter()
+            .map(|entry| entry.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(evicted, vec!["oldest", "middle"]);
+        assert!(select_for_eviction(&entries, 140).is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// Sandbox settings, relative to the target project root. Read from the real project rather
+/// than the shadow copy so a mutation cannot loosen its own isolation.
+pub const SANDBOX_CONFIG_PATH: &str = ".aop/sandbox.toml";
+const CONTAINER_WORKDIR: &str = "/workspace";
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxRuntime {
+    #[default]
+    Docker,
+    Podman,
+}
+
+impl SandboxRuntime {
+    pub fn program(self) -> &'static str {
+        match self {
+            SandboxRuntime::Docker => "docker",
+            SandboxRuntime::Podman => "podman",
+        }
+    }
+}
+
+/// Container used to run shadow tests, e.g.
+///
+/// ```toml
+/// image = "node:20-bookworm"
+/// runtime = "podman"
+/// memory = "2g"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SandboxConfig {
+    pub image: String,
+    #[serde(default)]
+    pub runtime: SandboxRuntime,
+    /// Network stays disabled unless the project opts in (e.g. tests that download fixtures).
+    #[serde(default)]
+    pub network: bool,
+    pub memory: Option<String>,
+    pub cpus: Option<String>,
+}
+
+pub fn sandbox_enabled() -> bool {
+    std::env::var("AOP_SHADOW_SANDBOX")
+        .ok()
+        .map(|value| {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false)
+}
+
+/// Returns the sandbox to use for shadow tests, or `None` when sandboxing is off. Once the
+/// flag is on a missing config is an error, so tests never silently fall back to the host.
+pub fn resolve_sandbox(target_root: &Path) -> Result<Option<SandboxConfig>, String> {
+    if !sandbox_enabled() {
+        return Ok(None);
+    }
+    let path = target_root.join(SANDBOX_CONFIG_PATH);
+    if !path.exists() {
+        return Err(format!(
+            "Shadow sandboxing is enabled but '{SANDBOX_CONFIG_PATH}' is missing in the target project."
+        ));
+    }
+    let raw = std::fs::read_to_string(&path)
+        .map_err(|error| format!("Failed to read '{}': {error}", path.display()))?;
+    parse_sandbox_config(&raw)
+        .map(Some)
+        .map_err(|error| format!("Invalid '{SANDBOX_CONFIG_PATH}': {error}"))
+}
+
+fn parse_sandbox_config(raw: &str) -> Result<SandboxConfig, String> {
+    let config: SandboxConfig = toml::from_str(raw).map_err(|error| error.to_string())?;
+    if config.image.trim().is_empty() {
+        return Err("image is required".to_string());
+    }
+    Ok(config)
+}
+
+impl SandboxConfig {
+    /// Rewrites `program args` into a `docker run`/`podman run` invocation that mounts only
+    /// the shadow workspace, drops capabilities and removes the container afterwards. `env`
+    /// is passed into the container rather than inherited from the host.
+    pub fn wrap_command(
+        &self,
+        shadow_root: &Path,
+        program: &str,
+        args: Vec<String>,
+        env: &BTreeMap<String, String>,
+    ) -> (String, Vec<String>) {
+        let mut wrapped = vec![
+            "run".to_string(),
+            "--rm".to_string(),
+            "--cap-drop".to_string(),
+            "ALL".to_string(),
+            "--security-opt".to_string(),
+            "no-new-privileges".to_string(),
+            "--volume".to_string(),
+            format!("{}:{CONTAINER_WORKDIR}", shadow_root.display()),
+            "--workdir".to_string(),
+            CONTAINER_WORKDIR.to_string(),
+        ];
+        if !self.network {
+            wrapped.extend(["--network".to_string(), "none".to_string()]);
+        }
+        if let Some(memory) = self.memory.as_deref() {
+            wrapped.extend(["--memory".to_string(), memory.to_string()]);
+        }
+        if let Some(cpus) = self.cpus.as_deref() {
+            wrapped.extend(["--cpus".to_string(), cpus.to_string()]);
+        }
+        for (key, value) in env {
+            wrapped.extend(["--env".to_string(), format!("{key}={value}")]);
+        }
+        wrapped.push(self.image.trim().to_string());
+        wrapped.push(program.to_string());
+        wrapped.extend(args);
+        (self.runtime.program().to_string(), wrapped)
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{} image '{}'{}",
+            self.runtime.program(),
+            self.image.trim(),
+            if self.network { "" } else { " without network" }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_test_command_in_isolated_container() {
+        let config =
+            parse_sandbox_config("image = \"node:20\"\nruntime = \"podman\"\nmemory = \"2g\"")
+                .expect("config should parse");
+        let (program, args) = config.wrap_command(
+            Path::new("/tmp/aop_shadow_x"),
+            "pnpm",
+            vec!["test".to_string()],
+            &BTreeMap::from([("CI".to_string(), "true".to_string())]),
+        );
+
+        assert_eq!(program, "podman");
+        assert_eq!(args[0], "run");
+        assert!(args.windows(2).any(|pair| pair == ["--network", "none"]));
+        assert!(args.windows(2).any(|pair| pair == ["--memory", "2g"]));
+        assert!(args.windows(2).any(|pair| pair == ["--env", "CI=true"]));
+        assert!(args.contains(&"/tmp/aop_shadow_x:/workspace".to_string()));
+        assert_eq!(&args[args.len() - 3..], ["node:20", "pnpm", "test"]);
+
+        assert!(parse_sandbox_config("image = \"  \"").is_err());
+        assert!(parse_sandbox_config("image = \"node\"\nprivileged = true").is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::db::metrics;
+use crate::db::spend;
+use crate::db::tasks::{self, ControlTaskInput, TaskControlAction};
+use crate::llm_adapter::AdapterResponse;
+
+pub const SPEND_CAP_EXCEEDED: &str = "spend_cap_exceeded";
+const DEFAULT_WARN_PERCENT: u32 = 80;
+
+/// Spend so far today and this calendar month, in USD.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SpendTotals {
+    daily_usd: f64,
+    monthly_usd: f64,
+}
+
+impl SpendTotals {
+    /// Today's and this month's spend as recorded in the ledger, so every process sharing
+    /// the database counts against the same caps.
+    async fn load(pool: &SqlitePool) -> Result<Self, String> {
+        let (daily_usd, monthly_usd) = spend::spend_totals(pool, Utc::now().date_naive()).await?;
+        Ok(Self {
+            daily_usd,
+            monthly_usd,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SpendLevel {
+    Ok,
+    Warning,
+    Exceeded,
+}
+
+impl SpendLevel {
+    fn as_str(self) -> &'static str {
+        match self {
+            SpendLevel::Ok => "ok",
+            SpendLevel::Warning => "warning",
+            SpendLevel::Exceeded => SPEND_CAP_EXCEEDED,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SpendCaps {
+    daily_usd: f64,
+    monthly_usd: f64,
+    warn_percent: u32,
+}
+
+impl SpendCaps {
+    fn from_env() -> Self {
+        let cap = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|value| value.is_finite())
+                .unwrap_or(0.0)
+                .max(0.0)
+        };
+        Self {
+            daily_usd: cap("AOP_SPEND_CAP_DAILY_USD"),
+            monthly_usd: cap("AOP_SPEND_CAP_MONTHLY_USD"),
+            warn_percent: std::env::var("AOP_SPEND_CAP_WARN_PERCENT")
+                .ok()
+                .and_then(|value| value.trim().parse::<u32>().ok())
+                .unwrap_or(DEFAULT_WARN_PERCENT)
+                .clamp(1, 100),
+        }
+    }
+
+    /// Where `spent` stands against `cap`; a cap of 0 is unset.
+    fn level(self, spent: f64, cap: f64) -> SpendLevel {
+        if cap <= 0.0 {
+            SpendLevel::Ok
+        } else if spent >= cap {
+            SpendLevel::Exceeded
+        } else if spent >= cap * f64::from(self.warn_percent) / 100.0 {
+            SpendLevel::Warning
+        } else {
+            SpendLevel::Ok
+        }
+    }
+
+    /// Each period with its spend and cap.
+    fn periods(self, totals: SpendTotals) -> [(&'static str, f64, f64); 2] {
+        [
+            ("daily", totals.daily_usd, self.daily_usd),
+            ("monthly", totals.monthly_usd, self.monthly_usd),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendStatus {
+    pub daily_usd: f64,
+    pub monthly_usd: f64,
+    /// 0 when no daily cap is set.
+    pub daily_cap_usd: f64,
+    /// 0 when no monthly cap is set.
+    pub monthly_cap_usd: f64,
+    pub warn_percent: u32,
+    /// `ok`, `warning` or `spend_cap_exceeded`, for the tighter of the two caps.
+    pub status: String,
+}
+
+pub async fn status(pool: &SqlitePool) -> Result<SpendStatus, String> {
+    let caps = SpendCaps::from_env();
+    let current = SpendTotals::load(pool).await?;
+    let level = caps
+        .periods(current)
+        .into_iter()
+        .map(|(_, spent, cap)| caps.level(spent, cap))
+        .max()
+        .unwrap_or(SpendLevel::Ok);
+    Ok(SpendStatus {
+        daily_usd: current.daily_usd,
+        monthly_usd: current.monthly_usd,
+        daily_cap_usd: caps.daily_usd,
+        monthly_cap_usd: caps.monthly_usd,
+        warn_percent: caps.warn_percent,
+        status: level.as_str().to_string(),
+    })
+}
+
+/// Refuses new model calls while the daily or monthly spend cap is used up.
+pub async fn check(pool: &SqlitePool) -> Result<(), String> {
+    let caps = SpendCaps::from_env();
+    if caps.daily_usd <= 0.0 && caps.monthly_usd <= 0.0 {
+        return Ok(());
+    }
+    let current = SpendTotals::load(pool).await?;
+    for (period, spent, cap) in caps.periods(current) {
+        if caps.level(spent, cap) == SpendLevel::Exceeded {
+            return Err(format!(
+                "{SPEND_CAP_EXCEEDED}: {period} model spend ${spent:.2} reached the ${cap:.2} cap"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Adds a completed call's cost to the spend ledger. Crossing the warning threshold of a
+/// cap is audited; crossing the cap itself pauses every executing root task. Calls to
+/// models without a price cost nothing here.
+pub async fn record(pool: &SqlitePool, cost_usd: f64) -> Result<(), String> {
+    if !cost_usd.is_finite() || cost_usd <= 0.0 {
+        return Ok(());
+    }
+    spend::add_spend(pool, Utc::now().date_naive(), cost_usd).await?;
+    let after = SpendTotals::load(pool).await?;
+    let before = SpendTotals {
+        daily_usd: after.daily_usd - cost_usd,
+        monthly_usd: after.monthly_usd - cost_usd,
+    };
+    let caps = SpendCaps::from_env();
+    for ((period, spent_before, cap), (_, spent, _)) in
+        caps.periods(before).into_iter().zip(caps.periods(after))
+    {
+        let level = caps.level(spent, cap);
+        if level <= caps.level(spent_before, cap) {
+            continue;
+        }
+        let details = format!(
+            "period={period} spentUsd={spent:.4} capUsd={cap:.2} warnPercent={}",
+            caps.warn_percent
+        );
+        match level {
+            SpendLevel::Warning => {
+                metrics::record_audit_event(
+                    pool,
+                    "spend_cap",
+                    "spend_cap_warning",
+                    None,
+                    Some(&details),
+                )
+                .await?;
+            }
+            SpendLevel::Exceeded => pause_executing_roots(pool, &details).await?,
+            SpendLevel::Ok => {}
+        }
+    }
+    Ok(())
+}
+
+async fn pause_executing_roots(pool: &SqlitePool, details: &str) -> Result<(), String> {
+    metrics::record_audit_event(pool, "spend_cap", SPEND_CAP_EXCEEDED, None, Some(details)).await?;
+    for root_task_id in tasks::executing_root_task_ids(pool).await? {
+        tasks::control_task(
+            pool,
+            ControlTaskInput {
+                task_id: root_task_id.clone(),
+                action: TaskControlAction::Pause,
+                include_descendants: Some(true),
+                reason: Some(SPEND_CAP_EXCEEDED.to_string()),
+            },
+        )
+        .await?;
+        metrics::record_audit_event(
+            pool,
+            "spend_cap",
+            "task_paused_spend_cap_exceeded",
+            Some(&root_task_id),
+            Some(details),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn caps_warn_then_exceed() {
+        let caps = SpendCaps {
+            daily_usd: 10.0,
+            monthly_usd: 0.0,
+            warn_percent: 80,
+        };
+        assert_eq!(caps.level(7.99, caps.daily_usd), SpendLevel::Ok);
+        assert_eq!(caps.level(8.0, caps.daily_usd), SpendLevel::Warning);
+        assert_eq!(caps.level(10.0, caps.daily_usd), SpendLevel::Exceeded);
+        assert_eq!(caps.level(1_000.0, caps.monthly_usd), SpendLevel::Ok);
+    }
+
+    #[tokio::test]
+    async fn totals_come_from_the_shared_ledger() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+
+        record(&pool, 1.25).await.expect("spend should record");
+        record(&pool, f64::NAN)
+            .await
+            .expect("unpriced call is ignored");
+        // Another process sharing the database records its spend directly in the ledger.
+        spend::add_spend(&pool, Utc::now().date_naive(), 0.5)
+            .await
+            .expect("spend should record");
+
+        let status = status(&pool).await.expect("status should load");
+        assert_eq!((status.daily_usd, status.monthly_usd), (1.75, 1.75));
+    }
+}
@@ -0,0 +1,246 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+use crate::db::metrics;
+use crate::mutation_pipeline::{normalize_target_root, run_command, run_command_capture};
+
+const GIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Dedicated branch for one root task. The branch lives in the target repository but is
+/// checked out in a linked worktree, so the user's own checkout never changes while AOP
+/// applies and commits mutations.
+#[derive(Debug, Clone)]
+pub struct TaskBranch {
+    pub name: String,
+    pub worktree_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FinalizeBranchAction {
+    Merge,
+    Discard,
+}
+
+impl FinalizeBranchAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FinalizeBranchAction::Merge => "merge",
+            FinalizeBranchAction::Discard => "discard",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FinalizeBranchInput {
+    pub target_project: String,
+    pub root_task_id: String,
+    pub action: FinalizeBranchAction,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FinalizeBranchResult {
+    pub branch: String,
+    pub action: String,
+    pub details: String,
+}
+
+pub fn branch_name_for_root_task(root_task_id: &str) -> String {
+    format!("aop/{}", root_task_id.trim())
+}
+
+pub fn task_branch_mode_enabled() -> bool {
+    std::env::var("AOP_TASK_BRANCH_MODE")
+        .ok()
+        .map(|value| {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false)
+}
+
+/// Creates the `aop/<root-task-id>` branch from the current HEAD on first use and returns
+/// its worktree. Later calls for the same root task reuse the existing worktree.
+pub async fn ensure_task_branch(
+    target_project: &str,
+    root_task_id: &str,
+) -> Result<TaskBranch, String> {
+    if root_task_id.trim().is_empty() {
+        return Err("rootTaskId is required".to_string());
+    }
+
+    let target_root = normalize_target_root(target_project)?;
+    if !target_root.join(".git").exists() {
+        return Err(format!(
+            "Target project '{}' is not a git repository (.git missing).",
+            target_root.display()
+        ));
+    }
+
+    let name = branch_name_for_root_task(root_task_id);
+    let worktree_dir = worktree_dir_for_root_task(root_task_id);
+    if worktree_dir.join(".git").exists() {
+        return Ok(TaskBranch { name, worktree_dir });
+    }
+
+    run_command(&target_root, "git", &["worktree", "prune"], GIT_TIMEOUT).await?;
+    if let Some(parent) = worktree_dir.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|error| format!("Failed to create task worktree parent: {error}"))?;
+    }
+
+    let worktree_value = worktree_dir.to_string_lossy().to_string();
+    if branch_exists(&target_root, &name).await? {
+        run_command(
+            &target_root,
+            "git",
+            &["worktree", "add", worktree_value.as_str(), name.as_str()],
+            GIT_TIMEOUT,
+        )
+        .await?;
+    } else {
+        run_command(
+            &target_root,
+            "git",
+            &[
+                "worktree",
+                "add",
+                "-b",
+                name.as_str(),
+                worktree_value.as_str(),
+                "HEAD",
+            ],
+            GIT_TIMEOUT,
+        )
+        .await?;
+    }
+
+    Ok(TaskBranch { name, worktree_dir })
+}
+
+/// Merges the task branch into whatever the user has checked out, or throws it away.
+/// Either way the worktree and the branch are removed afterwards.
+pub async fn finalize_branch(
+    pool: &SqlitePool,
+    input: FinalizeBranchInput,
+) -> Result<FinalizeBranchResult, String> {
+    let root_task_id = input.root_task_id.trim();
+    if root_task_id.is_empty() {
+        return Err("rootTaskId is required".to_string());
+    }
+
+    let target_root = normalize_target_root(&input.target_project)?;
+    let name = branch_name_for_root_task(root_task_id);
+    if !branch_exists(&target_root, &name).await? {
+        return Err(format!(
+            "Branch '{name}' does not exist in the target project."
+        ));
+    }
+
+    let worktree_dir = worktree_dir_for_root_task(root_task_id);
+    if worktree_dir.exists() {
+        let worktree_value = worktree_dir.to_string_lossy().to_string();
+        run_command(
+            &target_root,
+            "git",
+            &["worktree", "remove", "--force", worktree_value.as_str()],
+            GIT_TIMEOUT,
+        )
+        .await?;
+    }
+    run_command(&target_root, "git", &["worktree", "prune"], GIT_TIMEOUT).await?;
+
+    let details = match input.action {
+        FinalizeBranchAction::Merge => {
+            let message = format!("Merge {name} (AOP root task {root_task_id})");
+            let merge = run_command_capture(
+                &target_root,
+                "git",
+                vec![
+                    "merge".to_string(),
+                    "--no-ff".to_string(),
+                    "-m".to_string(),
+                    message,
+                    name.clone(),
+                ],
+                GIT_TIMEOUT,
+            )
+            .await?;
+            if merge.exit_code != 0 {
+                let _ = run_command(&target_root, "git", &["merge", "--abort"], GIT_TIMEOUT).await;
+                return Err(format!(
+                    "Failed to merge '{name}'; the merge was aborted and the branch was kept.\nstdout:\n{}\nstderr:\n{}",
+                    merge.stdout, merge.stderr
+                ));
+            }
+            run_command(
+                &target_root,
+                "git",
+                &["branch", "-d", name.as_str()],
+                GIT_TIMEOUT,
+            )
+            .await?;
+            format!("Merged '{name}' into the current branch and deleted it.")
+        }
+        FinalizeBranchAction::Discard => {
+            run_command(
+                &target_root,
+                "git",
+                &["branch", "-D", name.as_str()],
+                GIT_TIMEOUT,
+            )
+            .await?;
+            format!("Discarded '{name}' without merging.")
+        }
+    };
+
+    metrics::record_audit_event(
+        pool,
+        "ui",
+        "task_branch_finalized",
+        Some(root_task_id),
+        Some(
+            &serde_json::json!({
+                "branch": name,
+                "action": input.action.as_str(),
+            })
+            .to_string(),
+        ),
+    )
+    .await?;
+
+    Ok(FinalizeBranchResult {
+        branch: name,
+        action: input.action.as_str().to_string(),
+        details,
+    })
+}
+
+fn worktree_dir_for_root_task(root_task_id: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join("aop_branches")
+        .join(root_task_id.trim())
+}
+
+pub(crate) async fn branch_exists(target_root: &Path, name: &str) -> Result<bool, String> {
+    let result = run_command_capture(
+        target_root,
+        "git",
+        vec![
+            "rev-parse".to_string(),
+            "--verify".to_string(),
+            "--quiet".to_string(),
+            format!("refs/heads/{name}"),
+        ],
+        GIT_TIMEOUT,
+    )
+    .await?;
+    Ok(result.exit_code == 0)
+}
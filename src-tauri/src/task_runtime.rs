@@ -1,12 +1,504 @@
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
 
-use sqlx::SqlitePool;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio::time::sleep;
 
-use crate::db::budget_requests::{self, CreateBudgetRequestInput};
+use crate::db::budget_requests::{self, CreateBudgetRequestInput, ListTaskBudgetRequestsInput};
 use crate::db::metrics;
-use crate::db::tasks;
+use crate::db::mutations::{self, PurgeMutationsInput, PurgeMutationsResult};
+use crate::db::tasks::{
+    self, ControlTaskInput, TaskControlAction, TaskRecord, TaskStatus, UpdateTaskStatusInput,
+};
 use crate::db::telemetry;
+use crate::execution_window::ExecutionWindow;
+use crate::llm_adapter;
+use crate::llm_stream;
+use crate::notifications::{self, NotificationKind};
+
+const MUTATION_SWEEP_INTERVAL: Duration = Duration::from_secs(900);
+const DEADLINE_SWEEP_INTERVAL: Duration = Duration::from_secs(5);
+const STALL_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+const WINDOW_POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// Prefix of the error message of a task failed by its deadline.
+pub const DEADLINE_EXCEEDED: &str = "deadline_exceeded";
+const MAX_CONCURRENCY_LIMIT: u32 = 64;
+const MAX_RETRY_ATTEMPTS: u32 = 10;
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(300);
+
+static EXECUTION_GOVERNOR: OnceLock<ExecutionGovernor> = OnceLock::new();
+
+/// Kinds of work the execution governor caps separately.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionLane {
+    RootOrchestration,
+    Specialist,
+    PipelineRun,
+}
+
+impl ExecutionLane {
+    const ALL: [ExecutionLane; 3] = [
+        ExecutionLane::RootOrchestration,
+        ExecutionLane::Specialist,
+        ExecutionLane::PipelineRun,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExecutionLane::RootOrchestration => "root_orchestration",
+            ExecutionLane::Specialist => "specialist",
+            ExecutionLane::PipelineRun => "pipeline_run",
+        }
+    }
+}
+
+/// Concurrency caps; 0 turns a cap off. The per-project cap applies to root orchestrations
+/// and pipeline runs, which both write into the target project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionLimits {
+    pub root_orchestrations: u32,
+    pub specialists: u32,
+    pub pipeline_runs: u32,
+    pub per_project: u32,
+}
+
+impl ExecutionLimits {
+    pub fn from_env() -> Self {
+        Self {
+            root_orchestrations: concurrency_limit("AOP_MAX_CONCURRENT_ORCHESTRATIONS", 2),
+            specialists: concurrency_limit("AOP_MAX_CONCURRENT_SPECIALISTS", 6),
+            pipeline_runs: concurrency_limit("AOP_MAX_CONCURRENT_PIPELINE_RUNS", 2),
+            per_project: concurrency_limit("AOP_MAX_CONCURRENT_PER_PROJECT", 1),
+        }
+    }
+
+    fn for_lane(self, lane: ExecutionLane) -> (u32, u32) {
+        match lane {
+            ExecutionLane::RootOrchestration => (self.root_orchestrations, self.per_project),
+            ExecutionLane::Specialist => (self.specialists, 0),
+            ExecutionLane::PipelineRun => (self.pipeline_runs, self.per_project),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionQueueSnapshot {
+    pub lanes: Vec<ExecutionLaneStatus>,
+    /// Waiting work in admission order.
+    pub queued: Vec<QueuedExecution>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionLaneStatus {
+    pub lane: ExecutionLane,
+    /// `None` when the lane is uncapped.
+    pub limit: Option<u32>,
+    pub per_project_limit: Option<u32>,
+    pub running: u32,
+    pub queued: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedExecution {
+    pub lane: ExecutionLane,
+    /// Root task, coordinating task or mutation id, depending on the lane.
+    pub subject_id: String,
+    pub target_project: Option<String>,
+    /// 1-based position within the lane.
+    pub position: u32,
+    pub slots: u32,
+    pub queued_at: i64,
+}
+
+/// Holds execution slots until dropped.
+#[derive(Debug)]
+pub struct ExecutionPermit {
+    slots: u32,
+    running: Arc<AtomicU32>,
+    _global: Option<OwnedSemaphorePermit>,
+    _project: Option<OwnedSemaphorePermit>,
+}
+
+impl ExecutionPermit {
+    /// Slots actually granted, which may be fewer than requested when the lane cap is lower.
+    pub fn slots(&self) -> u32 {
+        self.slots
+    }
+}
+
+impl Drop for ExecutionPermit {
+    fn drop(&mut self) {
+        self.running.fetch_sub(self.slots, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug)]
+struct LaneGate {
+    limit: u32,
+    per_project_limit: u32,
+    global: Option<Arc<Semaphore>>,
+    projects: Mutex<HashMap<String, Arc<Semaphore>>>,
+    running: Arc<AtomicU32>,
+}
+
+impl LaneGate {
+    fn new((limit, per_project_limit): (u32, u32)) -> Self {
+        Self {
+            limit,
+            per_project_limit,
+            global: (limit > 0).then(|| Arc::new(Semaphore::new(limit as usize))),
+            projects: Mutex::new(HashMap::new()),
+            running: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    /// Never asks for more slots than the tightest cap could ever hand out.
+    fn clamp_slots(&self, project: Option<&str>, slots: u32) -> u32 {
+        let mut slots = slots.max(1);
+        if self.limit > 0 {
+            slots = slots.min(self.limit);
+        }
+        if project.is_some() && self.per_project_limit > 0 {
+            slots = slots.min(self.per_project_limit);
+        }
+        slots
+    }
+
+    fn project_semaphore(&self, project: Option<&str>) -> Option<Arc<Semaphore>> {
+        let project = project?;
+        if self.per_project_limit == 0 {
+            return None;
+        }
+        let mut projects = self
+            .projects
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        Some(
+            projects
+                .entry(project.to_string())
+                .or_insert_with(|| Arc::new(Semaphore::new(self.per_project_limit as usize)))
+                .clone(),
+        )
+    }
+
+    fn try_acquire(&self, project: Option<&str>, slots: u32) -> Option<ExecutionPermit> {
+        let slots = self.clamp_slots(project, slots);
+        let project_permit = match self.project_semaphore(project) {
+            Some(semaphore) => Some(semaphore.try_acquire_many_owned(slots).ok()?),
+            None => None,
+        };
+        let global_permit = match &self.global {
+            Some(semaphore) => Some(semaphore.clone().try_acquire_many_owned(slots).ok()?),
+            None => None,
+        };
+        Some(self.permit(slots, global_permit, project_permit))
+    }
+
+    /// Takes the project slot before the global one, so a project at its cap does not hold
+    /// global slots other projects could use.
+    async fn acquire(&self, project: Option<&str>, slots: u32) -> Result<ExecutionPermit, String> {
+        let slots = self.clamp_slots(project, slots);
+        let project_permit = match self.project_semaphore(project) {
+            Some(semaphore) => Some(
+                semaphore
+                    .acquire_many_owned(slots)
+                    .await
+                    .map_err(|error| format!("Execution governor closed: {error}"))?,
+            ),
+            None => None,
+        };
+        let global_permit = match &self.global {
+            Some(semaphore) => Some(
+                semaphore
+                    .clone()
+                    .acquire_many_owned(slots)
+                    .await
+                    .map_err(|error| format!("Execution governor closed: {error}"))?,
+            ),
+            None => None,
+        };
+        Ok(self.permit(slots, global_permit, project_permit))
+    }
+
+    fn permit(
+        &self,
+        slots: u32,
+        global: Option<OwnedSemaphorePermit>,
+        project: Option<OwnedSemaphorePermit>,
+    ) -> ExecutionPermit {
+        self.running.fetch_add(slots, Ordering::SeqCst);
+        ExecutionPermit {
+            slots,
+            running: self.running.clone(),
+            _global: global,
+            _project: project,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct QueueEntry {
+    ticket: u64,
+    lane: ExecutionLane,
+    subject_id: String,
+    target_project: Option<String>,
+    slots: u32,
+    queued_at: i64,
+}
+
+/// Semaphore-backed admission control for orchestrations, specialists and pipeline runs.
+/// Work beyond a cap waits in FIFO order and is listed in [`ExecutionGovernor::snapshot`].
+#[derive(Debug)]
+pub struct ExecutionGovernor {
+    lanes: HashMap<ExecutionLane, LaneGate>,
+    queue: Mutex<Vec<QueueEntry>>,
+    next_ticket: AtomicU64,
+}
+
+impl ExecutionGovernor {
+    pub fn new(limits: ExecutionLimits) -> Self {
+        Self {
+            lanes: ExecutionLane::ALL
+                .into_iter()
+                .map(|lane| (lane, LaneGate::new(limits.for_lane(lane))))
+                .collect(),
+            queue: Mutex::new(Vec::new()),
+            next_ticket: AtomicU64::new(1),
+        }
+    }
+
+    fn lane(&self, lane: ExecutionLane) -> &LaneGate {
+        self.lanes.get(&lane).expect("every lane has a gate")
+    }
+
+    fn queue(&self) -> MutexGuard<'_, Vec<QueueEntry>> {
+        self.queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn enqueue(
+        &self,
+        lane: ExecutionLane,
+        subject_id: &str,
+        project: Option<&str>,
+        slots: u32,
+    ) -> QueueTicket<'_> {
+        let ticket = self.next_ticket.fetch_add(1, Ordering::SeqCst);
+        let mut queue = self.queue();
+        queue.push(QueueEntry {
+            ticket,
+            lane,
+            subject_id: subject_id.to_string(),
+            target_project: project.map(str::to_string),
+            slots,
+            queued_at: Utc::now().timestamp(),
+        });
+        let position = queue.iter().filter(|entry| entry.lane == lane).count() as u32;
+        QueueTicket {
+            governor: self,
+            ticket,
+            position,
+        }
+    }
+
+    pub fn snapshot(&self) -> ExecutionQueueSnapshot {
+        let queue = self.queue();
+        let mut positions: HashMap<ExecutionLane, u32> = HashMap::new();
+        let queued = queue
+            .iter()
+            .map(|entry| {
+                let position = positions.entry(entry.lane).or_insert(0);
+                *position += 1;
+                QueuedExecution {
+                    lane: entry.lane,
+                    subject_id: entry.subject_id.clone(),
+                    target_project: entry.target_project.clone(),
+                    position: *position,
+                    slots: entry.slots,
+                    queued_at: entry.queued_at,
+                }
+            })
+            .collect();
+        let lanes = ExecutionLane::ALL
+            .into_iter()
+            .map(|lane| {
+                let gate = self.lane(lane);
+                ExecutionLaneStatus {
+                    lane,
+                    limit: (gate.limit > 0).then_some(gate.limit),
+                    per_project_limit: (gate.per_project_limit > 0)
+                        .then_some(gate.per_project_limit),
+                    running: gate.running.load(Ordering::SeqCst),
+                    queued: positions.get(&lane).copied().unwrap_or(0),
+                }
+            })
+            .collect();
+        ExecutionQueueSnapshot { lanes, queued }
+    }
+}
+
+/// A waiter's place in the visible queue; leaves the queue when dropped, including when the
+/// waiting future is cancelled.
+struct QueueTicket<'a> {
+    governor: &'a ExecutionGovernor,
+    ticket: u64,
+    position: u32,
+}
+
+impl Drop for QueueTicket<'_> {
+    fn drop(&mut self) {
+        self.governor
+            .queue()
+            .retain(|entry| entry.ticket != self.ticket);
+    }
+}
+
+fn execution_governor() -> &'static ExecutionGovernor {
+    EXECUTION_GOVERNOR.get_or_init(|| ExecutionGovernor::new(ExecutionLimits::from_env()))
+}
+
+pub fn execution_queue() -> ExecutionQueueSnapshot {
+    execution_governor().snapshot()
+}
+
+/// Waits for `slots` slots in `lane`, recording an audit event when the work has to queue.
+/// `target_project` is only used for the per-project cap.
+pub async fn acquire_execution_slots(
+    pool: &SqlitePool,
+    lane: ExecutionLane,
+    subject_id: &str,
+    target_project: Option<&str>,
+    slots: u32,
+) -> Result<ExecutionPermit, String> {
+    acquire_from(
+        execution_governor(),
+        pool,
+        lane,
+        subject_id,
+        target_project,
+        slots,
+    )
+    .await
+}
+
+async fn acquire_from(
+    governor: &ExecutionGovernor,
+    pool: &SqlitePool,
+    lane: ExecutionLane,
+    subject_id: &str,
+    target_project: Option<&str>,
+    slots: u32,
+) -> Result<ExecutionPermit, String> {
+    let project = target_project
+        .map(|value| value.trim().trim_end_matches(['/', '\\']))
+        .filter(|value| !value.is_empty());
+    let gate = governor.lane(lane);
+    if let Some(permit) = gate.try_acquire(project, slots) {
+        return Ok(permit);
+    }
+
+    let started = Instant::now();
+    let ticket = governor.enqueue(lane, subject_id, project, slots);
+    let details = serde_json::json!({
+        "lane": lane.as_str(),
+        "position": ticket.position,
+        "targetProject": project,
+    });
+    let _ = metrics::record_audit_event(
+        pool,
+        "execution_governor",
+        "execution_queued",
+        Some(subject_id),
+        Some(&details.to_string()),
+    )
+    .await;
+
+    let permit = gate.acquire(project, slots).await?;
+    drop(ticket);
+    let details = serde_json::json!({
+        "lane": lane.as_str(),
+        "waitedMs": started.elapsed().as_millis() as u64,
+        "slots": permit.slots(),
+    });
+    let _ = metrics::record_audit_event(
+        pool,
+        "execution_governor",
+        "execution_admitted",
+        Some(subject_id),
+        Some(&details.to_string()),
+    )
+    .await;
+    Ok(permit)
+}
+
+/// Holds an approved plan of `planned_tasks` assignments, marked queued, until the
+/// execution window opens. The window is re-read on every poll, so changing it takes
+/// effect while plans wait. Fails if the root task is stopped while it waits.
+pub async fn wait_for_execution_window(
+    pool: &SqlitePool,
+    root_task_id: &str,
+    planned_tasks: usize,
+) -> Result<(), String> {
+    let window = ExecutionWindow::from_env();
+    if !window.applies_to(planned_tasks) || window.is_open() {
+        return Ok(());
+    }
+
+    let started = Instant::now();
+    tasks::set_task_queued(pool, root_task_id, true).await?;
+    let details = serde_json::json!({
+        "window": window.describe(),
+        "plannedAssignments": planned_tasks,
+    });
+    let _ = metrics::record_audit_event(
+        pool,
+        "execution_window",
+        "plan_queued_for_window",
+        Some(root_task_id),
+        Some(&details.to_string()),
+    )
+    .await;
+
+    loop {
+        sleep(WINDOW_POLL_INTERVAL).await;
+        let root = tasks::get_task_by_id(pool, root_task_id).await?;
+        if matches!(root.status.as_str(), "completed" | "failed") {
+            tasks::set_task_queued(pool, root_task_id, false).await?;
+            return Err(format!(
+                "Root task '{root_task_id}' was {} while waiting for its execution window",
+                root.status
+            ));
+        }
+        let window = ExecutionWindow::from_env();
+        if !window.applies_to(planned_tasks) || window.is_open() {
+            break;
+        }
+    }
+
+    tasks::set_task_queued(pool, root_task_id, false).await?;
+    let details = serde_json::json!({
+        "waitedSeconds": started.elapsed().as_secs(),
+    });
+    let _ = metrics::record_audit_event(
+        pool,
+        "execution_window",
+        "plan_window_opened",
+        Some(root_task_id),
+        Some(&details.to_string()),
+    )
+    .await;
+    Ok(())
+}
 
 pub async fn record_task_activity(
     pool: &SqlitePool,
@@ -34,8 +526,35 @@ pub async fn cooperative_checkpoint(
     stage: &str,
 ) -> Result<(), String> {
     let mut observed_pause = false;
+    let _ = telemetry::record_run_heartbeat(pool, task_id).await;
 
     loop {
+        if let Some(reason) = enforce_deadline(pool, task_id).await? {
+            let details = format!("deadline_observed stage={stage} reason={reason}");
+            let _ = metrics::record_audit_event(
+                pool,
+                actor,
+                "task_stop_observed",
+                Some(task_id),
+                Some(&details),
+            )
+            .await;
+            return Err(format!("Task '{task_id}' stopped: {reason}"));
+        }
+        if let Some(exhausted) = check_token_budget(pool, task_id, actor, stage).await? {
+            if matches!(exhausted.status.as_str(), "pending" | "executing") {
+                tasks::control_task(
+                    pool,
+                    ControlTaskInput {
+                        task_id: exhausted.id.clone(),
+                        action: TaskControlAction::Pause,
+                        include_descendants: Some(true),
+                        reason: Some("token_budget_exhausted".to_string()),
+                    },
+                )
+                .await?;
+            }
+        }
         let task = tasks::get_task_by_id(pool, task_id).await?;
         match task.status.as_str() {
             "paused" => {
@@ -95,6 +614,383 @@ pub async fn cooperative_checkpoint(
     }
 }
 
+/// Failures worth another attempt; anything else fails the same way on a rerun.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryableFailure {
+    Timeout,
+    RateLimited,
+    ServerError,
+    Connection,
+}
+
+impl RetryableFailure {
+    const ALL: [RetryableFailure; 4] = [
+        RetryableFailure::Timeout,
+        RetryableFailure::RateLimited,
+        RetryableFailure::ServerError,
+        RetryableFailure::Connection,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RetryableFailure::Timeout => "timeout",
+            RetryableFailure::RateLimited => "rate_limited",
+            RetryableFailure::ServerError => "server_error",
+            RetryableFailure::Connection => "connection",
+        }
+    }
+
+    pub fn classify(error: &str) -> Option<Self> {
+        let lowered = error.to_ascii_lowercase();
+        let has_status = |codes: &[&str]| {
+            codes
+                .iter()
+                .any(|code| lowered.contains(&format!("status {code}")))
+        };
+        if llm_adapter::is_timeout_error(error)
+            || lowered.contains("timed out")
+            || lowered.contains("timeout")
+        {
+            Some(RetryableFailure::Timeout)
+        } else if has_status(&["429", "529"])
+            || ["rate limit", "rate_limit", "overloaded"]
+                .iter()
+                .any(|marker| lowered.contains(marker))
+        {
+            Some(RetryableFailure::RateLimited)
+        } else if has_status(&["500", "502", "503", "504"]) {
+            Some(RetryableFailure::ServerError)
+        } else if [
+            "connection reset",
+            "connection refused",
+            "failed to call",
+            "failed to reach",
+        ]
+        .iter()
+        .any(|marker| lowered.contains(marker))
+        {
+            Some(RetryableFailure::Connection)
+        } else {
+            None
+        }
+    }
+}
+
+/// Automatic retries for failed tasks of one tier.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Reruns allowed after the first attempt.
+    pub max_attempts: u32,
+    pub base_backoff: Duration,
+    pub retry_on: Vec<RetryableFailure>,
+}
+
+impl RetryPolicy {
+    /// `AOP_TIER{n}_RETRY_MAX_ATTEMPTS`, `AOP_TIER{n}_RETRY_BACKOFF_MS` and a comma-separated
+    /// `AOP_TIER{n}_RETRY_ON`. Only tier 3 retries unless configured.
+    pub fn for_tier(tier: i64) -> Self {
+        let default_attempts = if tier == 3 { 2 } else { 0 };
+        let max_attempts = std::env::var(format!("AOP_TIER{tier}_RETRY_MAX_ATTEMPTS"))
+            .ok()
+            .and_then(|value| value.trim().parse::<u32>().ok())
+            .unwrap_or(default_attempts)
+            .min(MAX_RETRY_ATTEMPTS);
+        let base_backoff = std::env::var(format!("AOP_TIER{tier}_RETRY_BACKOFF_MS"))
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(Duration::from_secs(2))
+            .min(MAX_RETRY_BACKOFF);
+        let retry_on = match std::env::var(format!("AOP_TIER{tier}_RETRY_ON")) {
+            Ok(value) => value
+                .split(',')
+                .filter_map(|class| {
+                    RetryableFailure::ALL
+                        .into_iter()
+                        .find(|failure| failure.as_str() == class.trim())
+                })
+                .collect(),
+            Err(_) => RetryableFailure::ALL.to_vec(),
+        };
+        Self {
+            max_attempts,
+            base_backoff,
+            retry_on,
+        }
+    }
+
+    /// Doubles per attempt, starting at `base_backoff` for the first rerun.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1).min(16));
+        self.base_backoff
+            .saturating_mul(factor)
+            .min(MAX_RETRY_BACKOFF)
+    }
+}
+
+/// Requeues a failed attempt of `task_id` when its tier's policy retries this kind of error,
+/// returning how long to wait before running it again.
+pub async fn schedule_retry(
+    pool: &SqlitePool,
+    task_id: &str,
+    actor: &str,
+    error: &str,
+) -> Result<Option<Duration>, String> {
+    let task = tasks::get_task_by_id(pool, task_id).await?;
+    let policy = RetryPolicy::for_tier(task.tier);
+    let Some(failure) =
+        RetryableFailure::classify(error).filter(|failure| policy.retry_on.contains(failure))
+    else {
+        return Ok(None);
+    };
+    let cause = error.chars().take(240).collect::<String>();
+    if task.retry_count >= i64::from(policy.max_attempts) {
+        if policy.max_attempts > 0 {
+            record_task_activity(
+                pool,
+                actor,
+                "task_retry_exhausted",
+                task_id,
+                &format!(
+                    "attempts={} class={} cause={cause}",
+                    task.retry_count,
+                    failure.as_str()
+                ),
+            )
+            .await?;
+        }
+        return Ok(None);
+    }
+
+    let attempt = task.retry_count as u32 + 1;
+    let backoff = policy.backoff(attempt);
+    tasks::requeue_task(
+        pool,
+        task_id,
+        &format!(
+            "retry_scheduled: attempt {attempt}/{} after {}",
+            policy.max_attempts,
+            failure.as_str()
+        ),
+    )
+    .await?;
+    record_task_activity(
+        pool,
+        actor,
+        "task_retry_scheduled",
+        task_id,
+        &format!(
+            "attempt={attempt}/{} class={} backoffMs={} cause={cause}",
+            policy.max_attempts,
+            failure.as_str(),
+            backoff.as_millis()
+        ),
+    )
+    .await?;
+    Ok(Some(backoff))
+}
+
+/// Returns why `task_id` may not continue when it or an ancestor ran out of wall-clock time.
+/// A deadline that passed is expired on the spot.
+pub async fn enforce_deadline(pool: &SqlitePool, task_id: &str) -> Result<Option<String>, String> {
+    let lineage = tasks::lineage_task_ids(pool, task_id).await?;
+    let mut builder = QueryBuilder::<Sqlite>::new(
+        "SELECT id, status, deadline_seconds, started_at, error_message FROM aop_tasks WHERE id IN (",
+    );
+    {
+        let mut separated = builder.separated(", ");
+        for id in &lineage {
+            separated.push_bind(id);
+        }
+    }
+    builder.push(")");
+    let rows = builder
+        .build_query_as::<(String, String, Option<i64>, Option<i64>, Option<String>)>()
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to check task deadlines: {error}"))?;
+
+    let now = Utc::now().timestamp();
+    // Root first, so an expired ancestor wins over its descendants.
+    for id in &lineage {
+        let Some((_, status, deadline_seconds, started_at, error_message)) =
+            rows.iter().find(|row| &row.0 == id)
+        else {
+            continue;
+        };
+        if let Some(message) = error_message
+            .as_deref()
+            .filter(|message| status == "failed" && message.starts_with(DEADLINE_EXCEEDED))
+        {
+            return Ok(Some(message.to_string()));
+        }
+        if let (Some(deadline), Some(started)) = (deadline_seconds, started_at) {
+            if matches!(status.as_str(), "pending" | "executing") && started + deadline <= now {
+                return expire_task_deadline(pool, id, *deadline).await.map(Some);
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Fails `task_id` with a `deadline_exceeded` reason, pauses its descendants and cancels
+/// model calls still streaming for the tree.
+pub async fn expire_task_deadline(
+    pool: &SqlitePool,
+    task_id: &str,
+    deadline_seconds: i64,
+) -> Result<String, String> {
+    let reason = format!("{DEADLINE_EXCEEDED}: ran past its {deadline_seconds}s deadline");
+    tasks::update_task_status(
+        pool,
+        UpdateTaskStatusInput {
+            task_id: task_id.to_string(),
+            status: TaskStatus::Failed,
+            error_message: Some(reason.clone()),
+        },
+    )
+    .await?;
+    let paused = tasks::pause_descendants(pool, task_id).await?;
+    let tree_ids = tasks::collect_task_tree_ids(pool, task_id).await?;
+    let cancelled_streams = llm_stream::cancel_for_tasks(&tree_ids);
+    record_task_activity(
+        pool,
+        "deadline_watchdog",
+        "task_deadline_exceeded",
+        task_id,
+        &format!(
+            "deadlineSeconds={deadline_seconds} pausedDescendants={} cancelledStreams={cancelled_streams}",
+            paused.len()
+        ),
+    )
+    .await?;
+    Ok(reason)
+}
+
+/// Expires tasks past their deadline even when no checkpoint runs, e.g. during a long
+/// model call.
+pub fn spawn_deadline_watchdog(pool: SqlitePool) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let _ = expire_overdue_tasks(&pool).await;
+            sleep(DEADLINE_SWEEP_INTERVAL).await;
+        }
+    });
+}
+
+/// Flags executing runs that stop sending heartbeats for `AOP_RUN_STALL_MINUTES`.
+pub fn spawn_stall_watchdog(pool: SqlitePool) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let _ = flag_stalled_runs(&pool).await;
+            sleep(STALL_SWEEP_INTERVAL).await;
+        }
+    });
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverStalledRunInput {
+    pub run_id: String,
+    /// `stop` or `restart`.
+    pub action: TaskControlAction,
+}
+
+/// Stops the task behind a stalled run and its descendants, then requeues it on `restart`.
+pub async fn recover_stalled_run(
+    pool: &SqlitePool,
+    input: RecoverStalledRunInput,
+) -> Result<Vec<TaskRecord>, String> {
+    if !matches!(
+        input.action,
+        TaskControlAction::Stop | TaskControlAction::Restart
+    ) {
+        return Err("Stalled runs can only be stopped or restarted".to_string());
+    }
+    let run = telemetry::get_stalled_run(pool, &input.run_id).await?;
+    let mut updated = tasks::control_task(
+        pool,
+        ControlTaskInput {
+            task_id: run.task_id.clone(),
+            action: TaskControlAction::Stop,
+            include_descendants: Some(true),
+            reason: Some(format!("stalled_run:{}", run.run_id)),
+        },
+    )
+    .await?;
+    telemetry::close_run(pool, &run.run_id, "failed").await?;
+    if matches!(input.action, TaskControlAction::Restart) {
+        updated = tasks::control_task(
+            pool,
+            ControlTaskInput {
+                task_id: run.task_id.clone(),
+                action: TaskControlAction::Restart,
+                include_descendants: Some(true),
+                reason: None,
+            },
+        )
+        .await?;
+    }
+    Ok(updated)
+}
+
+/// A threshold of 0 turns stall detection off.
+pub async fn flag_stalled_runs(pool: &SqlitePool) -> Result<Vec<String>, String> {
+    let stall_minutes = run_stall_minutes();
+    if stall_minutes == 0 {
+        return Ok(Vec::new());
+    }
+
+    let flagged = telemetry::flag_stalled_runs(pool, i64::from(stall_minutes) * 60).await?;
+    for run in &flagged {
+        let details = format!(
+            "runId={} actor={} silentSeconds={}",
+            run.run_id,
+            run.actor,
+            run.stalled_at - run.heartbeat_at
+        );
+        let _ = metrics::record_audit_event(
+            pool,
+            "run_watchdog",
+            "run_stalled",
+            Some(run.task_id.as_str()),
+            Some(&details),
+        )
+        .await;
+    }
+    Ok(flagged.into_iter().map(|run| run.run_id).collect())
+}
+
+pub async fn expire_overdue_tasks(pool: &SqlitePool) -> Result<Vec<String>, String> {
+    let overdue = sqlx::query_as::<_, (String, i64)>(
+        r#"
+        SELECT id, deadline_seconds
+        FROM aop_tasks
+        WHERE deadline_seconds IS NOT NULL
+          AND started_at IS NOT NULL
+          AND status IN ('pending', 'executing')
+          AND started_at + deadline_seconds <= ?
+        ORDER BY LENGTH(lineage_path) ASC
+        "#,
+    )
+    .bind(Utc::now().timestamp())
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to find overdue tasks: {error}"))?;
+
+    let mut expired = Vec::new();
+    for (task_id, deadline_seconds) in overdue {
+        // An ancestor expired earlier in this sweep may have paused it already.
+        let task = tasks::get_task_by_id(pool, &task_id).await?;
+        if !matches!(task.status.as_str(), "pending" | "executing") {
+            continue;
+        }
+        expire_task_deadline(pool, &task_id, deadline_seconds).await?;
+        expired.push(task_id);
+    }
+    Ok(expired)
+}
+
 pub async fn ensure_budget_headroom(
     pool: &SqlitePool,
     task_id: &str,
@@ -110,8 +1006,6 @@ pub async fn ensure_budget_headroom(
     let remaining = task.token_budget.saturating_sub(task.token_usage);
     let required = i64::from(planned_tokens.max(80));
     let headroom_percent = budget_headroom_percent();
-    let auto_cap_percent = budget_auto_max_percent();
-    let min_increment = budget_min_increment();
     let headroom_floor = ((task.token_budget.max(1) as f64) * (headroom_percent / 100.0)).ceil() as i64;
     let threshold = required.max(headroom_floor);
 
@@ -119,6 +1013,98 @@ pub async fn ensure_budget_headroom(
         return Ok(());
     }
 
+    file_budget_request(pool, &task, actor, stage, required, threshold).await
+}
+
+/// The hard stop for token budgets, checked against `task_id` and each of its ancestors.
+/// Files a budget request once a task crosses `AOP_BUDGET_REQUEST_THRESHOLD_PERCENT` of its
+/// budget and returns the first task that has used all of it.
+pub async fn check_token_budget(
+    pool: &SqlitePool,
+    task_id: &str,
+    actor: &str,
+    stage: &str,
+) -> Result<Option<TaskRecord>, String> {
+    let threshold_percent = budget_request_threshold_percent();
+    for id in tasks::lineage_task_ids(pool, task_id).await? {
+        let task = tasks::get_task_by_id(pool, &id).await?;
+        if task.token_budget <= 0 {
+            continue;
+        }
+        let request_at = ((task.token_budget as f64) * (threshold_percent / 100.0)).ceil() as i64;
+        if task.token_usage < request_at {
+            continue;
+        }
+
+        let latest = budget_requests::list_task_budget_requests(
+            pool,
+            ListTaskBudgetRequestsInput {
+                task_id: task.id.clone(),
+                include_descendants: Some(false),
+                status: None,
+                limit: Some(1),
+            },
+        )
+        .await?;
+        // A rejection stands until the budget changes; asking again at every checkpoint
+        // would only repeat it.
+        let rejected_at_this_budget = latest.first().is_some_and(|request| {
+            request.status == "rejected" && request.current_budget == task.token_budget
+        });
+        if !rejected_at_this_budget {
+            let headroom = task.token_budget.saturating_sub(request_at).max(1);
+            file_budget_request(pool, &task, actor, stage, headroom, headroom).await?;
+        }
+
+        let task = tasks::get_task_by_id(pool, &id).await?;
+        if task.token_usage >= task.token_budget {
+            let details = format!(
+                "stage={stage} task={} usage={} budget={}",
+                task.id, task.token_usage, task.token_budget
+            );
+            let _ = metrics::record_audit_event(
+                pool,
+                actor,
+                "token_budget_exhausted",
+                Some(task_id),
+                Some(&details),
+            )
+            .await;
+            return Ok(Some(task));
+        }
+    }
+    Ok(None)
+}
+
+/// Like [`check_token_budget`], for callers that stop instead of waiting at a checkpoint.
+pub async fn ensure_within_budget(
+    pool: &SqlitePool,
+    task_id: &str,
+    actor: &str,
+    stage: &str,
+) -> Result<(), String> {
+    match check_token_budget(pool, task_id, actor, stage).await? {
+        Some(task) => Err(format!(
+            "token_budget_exhausted: task '{}' used {} of {} tokens; approve its budget request to continue",
+            task.id, task.token_usage, task.token_budget
+        )),
+        None => Ok(()),
+    }
+}
+
+async fn file_budget_request(
+    pool: &SqlitePool,
+    task: &TaskRecord,
+    actor: &str,
+    stage: &str,
+    required: i64,
+    threshold: i64,
+) -> Result<(), String> {
+    let task_id = task.id.as_str();
+    let remaining = task.token_budget.saturating_sub(task.token_usage);
+    let headroom_percent = budget_headroom_percent();
+    let auto_cap_percent = budget_auto_max_percent();
+    let min_increment = budget_min_increment();
     if budget_requests::get_latest_pending_request_for_task(pool, task_id)
         .await?
         .is_some()
@@ -160,6 +1146,14 @@ pub async fn ensure_budget_headroom(
     let action = if request.status == "approved" {
         "token_budget_auto_increase_applied"
     } else {
+        notifications::notify(
+            NotificationKind::BudgetRequest,
+            "Budget request needs approval",
+            &format!(
+                "{} more tokens for: {}",
+                request.requested_increment, task.objective
+            ),
+        );
         "token_budget_increase_requested"
     };
     let details = format!(
@@ -178,6 +1172,63 @@ pub async fn ensure_budget_headroom(
     Ok(())
 }
 
+/// Periodically expires proposals that nobody reviewed within `AOP_MUTATION_TTL_HOURS`.
+pub fn spawn_mutation_sweeper(pool: SqlitePool) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let _ = expire_unreviewed_mutations(&pool).await;
+            sleep(MUTATION_SWEEP_INTERVAL).await;
+        }
+    });
+}
+
+/// A TTL of 0 turns expiry off.
+pub async fn expire_unreviewed_mutations(pool: &SqlitePool) -> Result<Vec<String>, String> {
+    let ttl_hours = mutation_ttl_hours();
+    if ttl_hours == 0 {
+        return Ok(Vec::new());
+    }
+    let cutoff = Utc::now().timestamp() - i64::from(ttl_hours) * 3_600;
+    let reason = format!("Expired after {ttl_hours}h without review.");
+    let expired = mutations::expire_stale_mutations(pool, cutoff, &reason).await?;
+    if !expired.is_empty() {
+        let details = serde_json::json!({ "ttlHours": ttl_hours, "mutationIds": expired });
+        metrics::record_audit_event(
+            pool,
+            "mutation_sweeper",
+            "mutations_expired",
+            None,
+            Some(&details.to_string()),
+        )
+        .await?;
+    }
+    Ok(expired)
+}
+
+pub async fn purge_mutations(
+    pool: &SqlitePool,
+    input: PurgeMutationsInput,
+) -> Result<PurgeMutationsResult, String> {
+    let mut details = serde_json::json!({
+        "statuses": input.statuses,
+        "olderThanDays": input.older_than_days,
+        "rootTaskId": input.root_task_id,
+    });
+    let result = mutations::purge_mutations(pool, input).await?;
+    if !result.dry_run && !result.mutation_ids.is_empty() {
+        details["count"] = result.mutation_ids.len().into();
+        metrics::record_audit_event(
+            pool,
+            "ui",
+            "mutations_purged",
+            None,
+            Some(&details.to_string()),
+        )
+        .await?;
+    }
+    Ok(result)
+}
+
 fn suggested_increment(current_budget: i64, remaining: i64, required: i64, min_increment: i64) -> i64 {
     let deficit = required.saturating_sub(remaining).max(0);
     let floor = ((current_budget.max(1) as f64) * 0.25).ceil() as i64;
@@ -204,6 +1255,14 @@ fn budget_headroom_percent() -> f64 {
         .unwrap_or(25.0)
 }
 
+fn budget_request_threshold_percent() -> f64 {
+    std::env::var("AOP_BUDGET_REQUEST_THRESHOLD_PERCENT")
+        .ok()
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .map(|value| value.clamp(10.0, 100.0))
+        .unwrap_or(85.0)
+}
+
 fn budget_auto_max_percent() -> f64 {
     std::env::var("AOP_BUDGET_AUTO_MAX_PERCENT")
         .ok()
@@ -219,3 +1278,231 @@ fn budget_min_increment() -> i64 {
         .map(|value| value.max(50))
         .unwrap_or(250)
 }
+
+fn concurrency_limit(key: &str, default: u32) -> u32 {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .unwrap_or(default)
+        .min(MAX_CONCURRENCY_LIMIT)
+}
+
+fn run_stall_minutes() -> u32 {
+    std::env::var("AOP_RUN_STALL_MINUTES")
+        .ok()
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .map(|value| value.min(1_440))
+        .unwrap_or(10)
+}
+
+fn mutation_ttl_hours() -> u32 {
+    std::env::var("AOP_MUTATION_TTL_HOURS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .map(|value| value.min(8_760))
+        .unwrap_or(168)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db;
+
+    async fn setup_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+        pool
+    }
+
+    #[tokio::test]
+    async fn governor_queues_work_beyond_the_project_cap() {
+        let pool = setup_test_pool().await;
+        let governor = ExecutionGovernor::new(ExecutionLimits {
+            root_orchestrations: 2,
+            specialists: 3,
+            pipeline_runs: 2,
+            per_project: 1,
+        });
+        let lane = ExecutionLane::RootOrchestration;
+
+        let first = acquire_from(&governor, &pool, lane, "root-a", Some("/repo/"), 1)
+            .await
+            .expect("first root should start");
+        let _other = acquire_from(&governor, &pool, lane, "root-b", Some("/other"), 1)
+            .await
+            .expect("another project should start");
+
+        let waiter = acquire_from(&governor, &pool, lane, "root-c", Some("/repo"), 1);
+        tokio::pin!(waiter);
+        assert!(tokio::time::timeout(Duration::from_millis(50), &mut waiter)
+            .await
+            .is_err());
+        let snapshot = governor.snapshot();
+        assert_eq!(snapshot.queued.len(), 1);
+        assert_eq!(snapshot.queued[0].subject_id, "root-c");
+        assert_eq!(snapshot.queued[0].position, 1);
+        let root_lane = snapshot
+            .lanes
+            .iter()
+            .find(|status| status.lane == lane)
+            .expect("lane should be listed");
+        assert_eq!((root_lane.running, root_lane.queued), (2, 1));
+
+        drop(first);
+        let _third = waiter.await.expect("queued root should start");
+        assert!(governor.snapshot().queued.is_empty());
+
+        let batch = acquire_from(
+            &governor,
+            &pool,
+            ExecutionLane::Specialist,
+            "tier2",
+            None,
+            10,
+        )
+        .await
+        .expect("specialist batch should start");
+        assert_eq!(batch.slots(), 3);
+    }
+
+    async fn create_task(pool: &SqlitePool, parent_id: Option<&str>, tier: i64) -> String {
+        tasks::create_task_record(
+            pool,
+            tasks::CreateTaskRecordInput {
+                parent_id: parent_id.map(str::to_string),
+                tier,
+                domain: "platform".to_string(),
+                objective: format!("tier {tier} work"),
+                token_budget: 1_000,
+                risk_factor: 0.2,
+                status: TaskStatus::Executing,
+                target_files: None,
+                priority: 0,
+            },
+        )
+        .await
+        .expect("task should be created")
+        .id
+    }
+
+    #[tokio::test]
+    async fn expired_deadline_fails_task_and_pauses_descendants() {
+        let pool = setup_test_pool().await;
+        let root_id = create_task(&pool, None, 1).await;
+        let child_id = create_task(&pool, Some(&root_id), 2).await;
+        tasks::set_task_deadline(
+            &pool,
+            tasks::SetTaskDeadlineInput {
+                task_id: root_id.clone(),
+                deadline_seconds: Some(60),
+            },
+        )
+        .await
+        .expect("deadline should be set");
+
+        assert!(enforce_deadline(&pool, &child_id)
+            .await
+            .expect("deadline check should run")
+            .is_none());
+
+        sqlx::query("UPDATE aop_tasks SET started_at = started_at - 120 WHERE id = ?")
+            .bind(&root_id)
+            .execute(&pool)
+            .await
+            .expect("start time should be rewound");
+
+        let error = cooperative_checkpoint(&pool, &child_id, "tier2", "before_dispatch")
+            .await
+            .expect_err("child should stop once the root deadline passed");
+        assert!(error.contains(DEADLINE_EXCEEDED));
+
+        let root = tasks::get_task_by_id(&pool, &root_id).await.unwrap();
+        assert_eq!(root.status, "failed");
+        assert!(root
+            .error_message
+            .as_deref()
+            .is_some_and(|message| message.starts_with(DEADLINE_EXCEEDED)));
+        let child = tasks::get_task_by_id(&pool, &child_id).await.unwrap();
+        assert_eq!(child.status, "paused");
+        assert!(expire_overdue_tasks(&pool).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn tier3_failures_retry_with_backoff_until_exhausted() {
+        let pool = setup_test_pool().await;
+        let root_id = create_task(&pool, None, 1).await;
+        let task_id = create_task(&pool, Some(&root_id), 3).await;
+
+        assert_eq!(
+            schedule_retry(&pool, &task_id, "tier3_test", "invalid diff output")
+                .await
+                .unwrap(),
+            None
+        );
+        let first = schedule_retry(&pool, &task_id, "tier3_test", "OpenAI returned status 503")
+            .await
+            .unwrap();
+        assert_eq!(first, Some(Duration::from_secs(2)));
+        let task = tasks::get_task_by_id(&pool, &task_id).await.unwrap();
+        assert_eq!(task.status, "pending");
+        assert_eq!(task.retry_count, 1);
+
+        let second = schedule_retry(&pool, &task_id, "tier3_test", "request timed out")
+            .await
+            .unwrap();
+        assert_eq!(second, Some(Duration::from_secs(4)));
+        assert_eq!(
+            schedule_retry(&pool, &task_id, "tier3_test", "request timed out")
+                .await
+                .unwrap(),
+            None
+        );
+        let root_retry = schedule_retry(&pool, &root_id, "tier1_test", "request timed out")
+            .await
+            .unwrap();
+        assert_eq!(root_retry, None);
+    }
+
+    #[tokio::test]
+    async fn token_budget_files_requests_then_hard_stops() {
+        let pool = setup_test_pool().await;
+        let root_id = create_task(&pool, None, 1).await;
+        let child_id = create_task(&pool, Some(&root_id), 3).await;
+
+        tasks::debit_task_tokens(&pool, &child_id, 900)
+            .await
+            .unwrap();
+        assert!(
+            check_token_budget(&pool, &child_id, "tier3_test", "post_call")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let root = tasks::get_task_by_id(&pool, &root_id).await.unwrap();
+        assert_eq!(root.token_usage, 900);
+        assert!(root.token_budget > 1_000);
+
+        tasks::debit_task_tokens(&pool, &child_id, 5_000)
+            .await
+            .unwrap();
+        let error = ensure_within_budget(&pool, &child_id, "tier3_test", "post_call")
+            .await
+            .expect_err("an overrun beyond the auto-approval cap should stop");
+        assert!(error.starts_with("token_budget_exhausted"));
+        assert!(error.contains(&root_id));
+        assert!(
+            budget_requests::get_latest_pending_request_for_task(&pool, &root_id)
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+}
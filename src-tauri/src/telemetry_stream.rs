@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::async_runtime::JoinHandle;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+use crate::db::telemetry::{AgentEventRecord, AgentRunRecord, ModelHealthRecord};
+
+/// Event carrying a [`MissionControlUpdate`] whenever a subscription sees new telemetry.
+pub const MISSION_CONTROL_EVENT: &str = "mission-control-update";
+const DEFAULT_INTERVAL_MS: u64 = 1_000;
+const MIN_INTERVAL_MS: u64 = 250;
+const MAX_INTERVAL_MS: u64 = 30_000;
+/// Events sent per push; a larger backlog drains over the following ticks.
+const MAX_EVENTS_PER_PUSH: i64 = 500;
+
+pub type UpdateListener = Arc<dyn Fn(&MissionControlUpdate) + Send + Sync>;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscribeMissionControlInput {
+    pub root_task_id: Option<String>,
+    /// Resume after this event id; without it the subscription starts at the newest event.
+    pub since_id: Option<i64>,
+    pub interval_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissionControlSubscriptionInput {
+    pub subscription_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissionControlSubscription {
+    pub subscription_id: String,
+    pub root_task_id: Option<String>,
+    /// Id of the last event already seen; pass it back as `sinceId` after a reconnect.
+    pub cursor: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissionControlUpdate {
+    pub subscription_id: String,
+    pub root_task_id: Option<String>,
+    pub cursor: i64,
+    pub generated_at: i64,
+    /// New events, oldest first.
+    pub events: Vec<AgentEventRecord>,
+    /// Runs that started or changed status since the previous push.
+    pub runs: Vec<AgentRunRecord>,
+    /// Health rows updated since the previous push; not scoped to the root task.
+    pub model_health: Vec<ModelHealthRecord>,
+}
+
+/// Mission control subscriptions, each polling the telemetry tables in the background
+/// and pushing only what changed so the UI does not have to poll snapshots.
+#[derive(Default)]
+pub struct TelemetrySubscriptions {
+    subscriptions: HashMap<String, Subscription>,
+}
+
+struct Subscription {
+    cancelled: Arc<AtomicBool>,
+    _task: JoinHandle<()>,
+}
+
+impl TelemetrySubscriptions {
+    pub async fn subscribe(
+        &mut self,
+        pool: SqlitePool,
+        input: SubscribeMissionControlInput,
+        listener: UpdateListener,
+    ) -> Result<MissionControlSubscription, String> {
+        let subscription_id = Uuid::new_v4().to_string();
+        let root_task_id = input
+            .root_task_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(ToOwned::to_owned);
+        let interval = Duration::from_millis(
+            input
+                .interval_ms
+                .unwrap_or(DEFAULT_INTERVAL_MS)
+                .clamp(MIN_INTERVAL_MS, MAX_INTERVAL_MS),
+        );
+        let mut cursor =
+            SubscriptionCursor::start(&pool, root_task_id.clone(), input.since_id).await?;
+        let subscription = MissionControlSubscription {
+            subscription_id: subscription_id.clone(),
+            root_task_id,
+            cursor: cursor.last_event_id,
+        };
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let task_cancelled = cancelled.clone();
+        let task_subscription_id = subscription_id.clone();
+        let task = tauri::async_runtime::spawn(async move {
+            loop {
+                sleep(interval).await;
+                if task_cancelled.load(Ordering::Relaxed) {
+                    break;
+                }
+                if let Ok(Some(update)) = cursor.poll(&pool, &task_subscription_id).await {
+                    listener(&update);
+                }
+            }
+        });
+
+        self.subscriptions.insert(
+            subscription_id,
+            Subscription {
+                cancelled,
+                _task: task,
+            },
+        );
+        Ok(subscription)
+    }
+
+    pub fn unsubscribe(&mut self, subscription_id: &str) -> Result<(), String> {
+        let subscription = self
+            .subscriptions
+            .remove(subscription_id.trim())
+            .ok_or_else(|| format!("Subscription '{}' not found", subscription_id.trim()))?;
+        subscription.cancelled.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// What a subscription has already pushed: the last event id, the status of each run it
+/// watches and the health rows as last sent.
+struct SubscriptionCursor {
+    root_task_id: Option<String>,
+    last_event_id: i64,
+    run_statuses: HashMap<String, String>,
+    runs_checked_at: i64,
+    health: HashMap<(String, String), (i64, i64)>,
+    health_checked_at: i64,
+}
+
+impl SubscriptionCursor {
+    /// Starts after `since_id` or at the newest event, treating current runs and health as
+    /// already seen.
+    async fn start(
+        pool: &SqlitePool,
+        root_task_id: Option<String>,
+        since_id: Option<i64>,
+    ) -> Result<Self, String> {
+        let last_event_id = match since_id {
+            Some(since_id) => since_id.max(0),
+            None => {
+                sqlx::query_scalar::<_, i64>("SELECT COALESCE(MAX(id), 0) FROM aop_agent_events")
+                    .fetch_one(pool)
+                    .await
+                    .map_err(|error| format!("Failed to read telemetry cursor: {error}"))?
+            }
+        };
+        let now = Utc::now().timestamp();
+        let mut cursor = Self {
+            root_task_id,
+            last_event_id,
+            run_statuses: HashMap::new(),
+            runs_checked_at: now,
+            health: HashMap::new(),
+            health_checked_at: 0,
+        };
+        cursor.changed_runs(pool, now).await?;
+        cursor.changed_health(pool).await?;
+        Ok(cursor)
+    }
+
+    async fn poll(
+        &mut self,
+        pool: &SqlitePool,
+        subscription_id: &str,
+    ) -> Result<Option<MissionControlUpdate>, String> {
+        let now = Utc::now().timestamp();
+        let events = self.new_events(pool).await?;
+        let runs = self.changed_runs(pool, now).await?;
+        let model_health = self.changed_health(pool).await?;
+        if events.is_empty() && runs.is_empty() && model_health.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(MissionControlUpdate {
+            subscription_id: subscription_id.to_string(),
+            root_task_id: self.root_task_id.clone(),
+            cursor: self.last_event_id,
+            generated_at: now,
+            events,
+            runs,
+            model_health,
+        }))
+    }
+
+    async fn new_events(&mut self, pool: &SqlitePool) -> Result<Vec<AgentEventRecord>, String> {
+        let events = sqlx::query_as::<_, AgentEventRecord>(
+            r#"
+            SELECT id, run_id, root_task_id, task_id, tier, actor, action, status, phase, message,
+                   provider, model_id, persona, skill, mcp_server, mcp_tool, latency_ms,
+                   retry_count, tokens_in, tokens_out, token_delta, cost_usd, payload_json,
+                   created_at
+            FROM aop_agent_events
+            WHERE id > ? AND (? IS NULL OR root_task_id = ?)
+            ORDER BY id ASC
+            LIMIT ?
+            "#,
+        )
+        .bind(self.last_event_id)
+        .bind(self.root_task_id.as_deref())
+        .bind(self.root_task_id.as_deref())
+        .bind(MAX_EVENTS_PER_PUSH)
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to read new agent events: {error}"))?;
+
+        if let Some(last) = events.last() {
+            self.last_event_id = last.id;
+        }
+        Ok(events)
+    }
+
+    /// Active runs plus those that ended since the last check, keeping the ones whose
+    /// status differs from what was last seen.
+    async fn changed_runs(
+        &mut self,
+        pool: &SqlitePool,
+        now: i64,
+    ) -> Result<Vec<AgentRunRecord>, String> {
+        let runs = sqlx::query_as::<_, AgentRunRecord>(
+            r#"
+            SELECT id, root_task_id, task_id, tier, actor, persona, skill, provider, model_id,
+                   adapter_kind, status, started_at, ended_at, tokens_in, tokens_out, token_delta,
+                   cost_usd, metadata_json
+            FROM aop_agent_runs
+            WHERE (? IS NULL OR root_task_id = ?)
+              AND (status IN ('pending', 'executing', 'paused') OR ended_at >= ?)
+            ORDER BY started_at ASC
+            "#,
+        )
+        .bind(self.root_task_id.as_deref())
+        .bind(self.root_task_id.as_deref())
+        .bind(self.runs_checked_at)
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to read agent runs: {error}"))?;
+        self.runs_checked_at = now;
+
+        let mut statuses = HashMap::with_capacity(runs.len());
+        let mut changed = Vec::new();
+        for run in runs {
+            statuses.insert(run.id.clone(), run.status.clone());
+            if self.run_statuses.get(&run.id) != Some(&run.status) {
+                changed.push(run);
+            }
+        }
+        // Runs that ended before the last check fall out of the query and are forgotten.
+        self.run_statuses = statuses;
+        Ok(changed)
+    }
+
+    /// Health rows touched since the last check. Timestamps are whole seconds, so rows
+    /// from the boundary second are compared against what was already sent.
+    async fn changed_health(
+        &mut self,
+        pool: &SqlitePool,
+    ) -> Result<Vec<ModelHealthRecord>, String> {
+        let rows = sqlx::query_as::<_, ModelHealthRecord>(
+            r#"
+            SELECT
+                provider, model_id, total_calls, success_calls, failed_calls,
+                avg_latency_ms, avg_cost_usd, quality_score, last_error, last_used_at, updated_at,
+                consecutive_failures, circuit_open_until, timeout_calls
+            FROM aop_model_health
+            WHERE updated_at >= ?
+            ORDER BY updated_at ASC
+            "#,
+        )
+        .bind(self.health_checked_at)
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to read model health: {error}"))?;
+
+        let mut changed = Vec::new();
+        for row in rows {
+            self.health_checked_at = self.health_checked_at.max(row.updated_at);
+            let seen = (row.updated_at, row.total_calls);
+            let key = (row.provider.clone(), row.model_id.clone());
+            if self.health.insert(key, seen) != Some(seen) {
+                changed.push(row);
+            }
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db;
+    use crate::db::tasks::{self, CreateTaskInput};
+    use crate::db::telemetry;
+
+    async fn setup_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+
+        pool
+    }
+
+    async fn create_root(pool: &SqlitePool, objective: &str) -> String {
+        tasks::create_task(
+            pool,
+            CreateTaskInput {
+                parent_id: None,
+                tier: 1,
+                domain: "platform".to_string(),
+                objective: objective.to_string(),
+                token_budget: 5000,
+            },
+        )
+        .await
+        .expect("task should be created")
+        .id
+    }
+
+    #[tokio::test]
+    async fn pushes_only_new_events_for_the_root_and_resumes_from_since_id() {
+        let pool = setup_test_pool().await;
+        let watched = create_root(&pool, "watched").await;
+        let other = create_root(&pool, "other").await;
+        telemetry::record_task_activity_event(
+            &pool,
+            "tier1_orchestrator",
+            "model_call",
+            Some(watched.as_str()),
+            Some("status=executing"),
+        )
+        .await
+        .expect("telemetry should be recorded");
+
+        let mut cursor = SubscriptionCursor::start(&pool, Some(watched.clone()), None)
+            .await
+            .expect("cursor should start");
+        let resume_from = cursor.last_event_id;
+        assert!(cursor
+            .poll(&pool, "sub")
+            .await
+            .expect("poll should work")
+            .is_none());
+
+        for root in [&other, &watched] {
+            telemetry::record_task_activity_event(
+                &pool,
+                "tier1_orchestrator",
+                "model_call",
+                Some(root.as_str()),
+                Some("model=openai/gpt-5 tokensIn=10 tokensOut=5"),
+            )
+            .await
+            .expect("telemetry should be recorded");
+        }
+
+        let update = cursor
+            .poll(&pool, "sub")
+            .await
+            .expect("poll should work")
+            .expect("new events should be pushed");
+        assert_eq!(update.events.len(), 1);
+        assert_eq!(
+            update.events[0].root_task_id.as_deref(),
+            Some(watched.as_str())
+        );
+        assert_eq!(update.cursor, update.events[0].id);
+
+        let mut resumed = SubscriptionCursor::start(&pool, Some(watched), Some(resume_from))
+            .await
+            .expect("cursor should start");
+        let replayed = resumed
+            .poll(&pool, "sub")
+            .await
+            .expect("poll should work")
+            .expect("missed events should be replayed");
+        assert_eq!(replayed.events.len(), 1);
+        assert_eq!(replayed.cursor, update.cursor);
+    }
+}
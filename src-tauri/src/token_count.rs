@@ -0,0 +1,125 @@
+use std::sync::OnceLock;
+
+use serde_json::{json, Value};
+use tiktoken_rs::CoreBPE;
+
+use crate::llm_adapter::AdapterRequest;
+
+const ANTHROPIC_COUNT_TOKENS_URL: &str = "https://api.anthropic.com/v1/messages/count_tokens";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const ANTHROPIC_COUNT_TIMEOUT_SECS: u64 = 10;
+/// Claude Code accepts short aliases; the count API needs a concrete model.
+const CLAUDE_MODEL_ALIASES: &[(&str, &str)] = &[
+    ("opus", "claude-opus-4-1"),
+    ("sonnet", "claude-sonnet-4-5"),
+    ("haiku", "claude-haiku-4-5"),
+];
+
+static O200K: OnceLock<Option<CoreBPE>> = OnceLock::new();
+static CL100K: OnceLock<Option<CoreBPE>> = OnceLock::new();
+
+/// Tokens of `request`'s system and user prompts as the provider would bill them.
+pub fn count_prompt_tokens(request: &AdapterRequest) -> u32 {
+    if is_claude_provider(&request.provider) {
+        if let Some(count) = anthropic_count_tokens(
+            &request.model_id,
+            request.system_prompt.trim(),
+            request.user_prompt.trim(),
+        ) {
+            return count;
+        }
+    }
+    count_tokens(&request.model_id, request.system_prompt.trim())
+        + count_tokens(&request.model_id, request.user_prompt.trim())
+}
+
+/// Tokens in `text` for `model_id`. OpenAI-compatible models use their tiktoken
+/// encoding; other providers use `o200k_base`, which is within a few percent for
+/// Claude and Llama-family vocabularies.
+pub fn count_tokens(model_id: &str, text: &str) -> u32 {
+    if text.is_empty() {
+        return 0;
+    }
+    match encoder_for(model_id) {
+        Some(encoder) => encoder.encode_with_special_tokens(text).len() as u32,
+        None => (text.chars().count() as u32).div_ceil(4),
+    }
+}
+
+fn encoder_for(model_id: &str) -> Option<&'static CoreBPE> {
+    let model = model_id.trim().to_ascii_lowercase();
+    let legacy = (model.starts_with("gpt-4") || model.starts_with("gpt-3.5"))
+        && !model.starts_with("gpt-4o")
+        && !model.starts_with("gpt-4.1");
+    if legacy {
+        CL100K
+            .get_or_init(|| tiktoken_rs::cl100k_base().ok())
+            .as_ref()
+    } else {
+        O200K
+            .get_or_init(|| tiktoken_rs::o200k_base().ok())
+            .as_ref()
+    }
+}
+
+fn is_claude_provider(provider: &str) -> bool {
+    matches!(
+        provider.trim().to_ascii_lowercase().as_str(),
+        "claude_code" | "claude-code" | "anthropic_claude_code"
+    )
+}
+
+/// Exact prompt count from Anthropic's `count_tokens` endpoint. Needs
+/// `ANTHROPIC_API_KEY`; without it the caller falls back to the local encoder.
+fn anthropic_count_tokens(model_id: &str, system_prompt: &str, user_prompt: &str) -> Option<u32> {
+    let api_key = std::env::var("ANTHROPIC_API_KEY")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())?;
+    let model = model_id.trim();
+    let model = CLAUDE_MODEL_ALIASES
+        .iter()
+        .find(|(alias, _)| model.eq_ignore_ascii_case(alias))
+        .map(|(_, concrete)| *concrete)
+        .unwrap_or(model);
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(ANTHROPIC_COUNT_TIMEOUT_SECS))
+        .build()
+        .ok()?;
+    let response = client
+        .post(ANTHROPIC_COUNT_TOKENS_URL)
+        .header("x-api-key", api_key)
+        .header("anthropic-version", ANTHROPIC_VERSION)
+        .json(&json!({
+            "model": model,
+            "system": system_prompt,
+            "messages": [{ "role": "user", "content": user_prompt }]
+        }))
+        .send()
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    response
+        .json::<Value>()
+        .ok()?
+        .get("input_tokens")
+        .and_then(Value::as_u64)
+        .map(|value| value as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_with_the_model_encoding() {
+        let source = "fn main() { println!(\"hello\"); }";
+        assert_eq!(count_tokens("gpt-5-mini", ""), 0);
+        let modern = count_tokens("gpt-4o", source);
+        let legacy = count_tokens("gpt-4-turbo", source);
+        assert!(modern > 0 && modern < 20);
+        assert!(legacy > 0 && legacy < 20);
+        assert_eq!(count_tokens("qwen2.5-coder", "hello world"), 2);
+    }
+}
@@ -0,0 +1,92 @@
+use sqlx::SqlitePool;
+
+use crate::db::tasks;
+use crate::llm_adapter::{AdapterRequest, AdapterResponse};
+use crate::task_runtime;
+use crate::token_count;
+
+const ACTOR: &str = "llm_adapter";
+
+/// Refuses a model call for a task that, or whose ancestor, has used its whole budget.
+pub async fn check(pool: &SqlitePool, task_id: &str) -> Result<(), String> {
+    task_runtime::ensure_within_budget(pool, task_id, ACTOR, "pre_call").await
+}
+
+/// Charges `tokens` of a completed call to its task and the task's ancestors, then applies
+/// the hard stop, so a call that used up the budget fails before its reply is handed out.
+/// Cache hits never reach here, so they stay free.
+pub async fn debit(pool: &SqlitePool, task_id: &str, tokens: u32) -> Result<(), String> {
+    tasks::debit_task_tokens(pool, task_id, i64::from(tokens)).await?;
+    task_runtime::ensure_within_budget(pool, task_id, ACTOR, "post_call").await
+}
+
+/// The provider's reported usage; a provider that reports none is counted locally so the
+/// call still draws down the budget.
+pub fn billed_tokens(request: &AdapterRequest, response: &AdapterResponse) -> u32 {
+    match (response.input_tokens, response.output_tokens) {
+        (None, None) => token_count::count_tokens(&request.model_id, request.system_prompt.trim())
+            .saturating_add(token_count::count_tokens(
+                &request.model_id,
+                request.user_prompt.trim(),
+            ))
+            .saturating_add(token_count::count_tokens(&request.model_id, &response.text)),
+        (input, output) => input.unwrap_or(0).saturating_add(output.unwrap_or(0)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db;
+    use crate::db::tasks::{CreateTaskRecordInput, TaskStatus};
+
+    #[tokio::test]
+    async fn debit_lands_before_the_budget_is_checked() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should run in tests");
+        let task_id = tasks::create_task_record(
+            &pool,
+            CreateTaskRecordInput {
+                parent_id: None,
+                tier: 3,
+                domain: "platform".to_string(),
+                objective: "tier 3 work".to_string(),
+                token_budget: 1_000,
+                risk_factor: 0.2,
+                status: TaskStatus::Executing,
+                target_files: None,
+                priority: 0,
+            },
+        )
+        .await
+        .expect("task should be created")
+        .id;
+
+        check(&pool, &task_id)
+            .await
+            .expect("fresh task is in budget");
+        debit(&pool, &task_id, 300)
+            .await
+            .expect("a call within budget passes");
+        let error = debit(&pool, &task_id, 5_000)
+            .await
+            .expect_err("the call that overruns the budget fails");
+        assert!(error.starts_with("token_budget_exhausted"));
+        assert_eq!(
+            tasks::get_task_by_id(&pool, &task_id)
+                .await
+                .expect("task should load")
+                .token_usage,
+            5_300
+        );
+        assert!(check(&pool, &task_id).await.is_err());
+    }
+}
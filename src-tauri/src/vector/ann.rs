@@ -0,0 +1,753 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::fs;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tokio::sync::Mutex;
+
+/// Below this many chunks an exact scan answers quickly and no graph is built.
+pub const ANN_MIN_CHUNKS: usize = 5_000;
+const MAX_NEIGHBORS: usize = 16;
+const MAX_BASE_NEIGHBORS: usize = 32;
+const MAX_LEVEL: usize = 12;
+const EF_CONSTRUCTION: usize = 100;
+const MIN_EF_SEARCH: usize = 64;
+/// Share of deleted nodes after which the graph is rebuilt instead of patched.
+const MAX_TOMBSTONE_RATIO: f32 = 0.25;
+const FETCH_BATCH: usize = 500;
+const FILE_MAGIC: &[u8; 8] = b"AOPANN01";
+
+static INDEX_DIR: OnceLock<PathBuf> = OnceLock::new();
+/// Graphs loaded this session, keyed by project root.
+static LOADED: Mutex<BTreeMap<String, AnnIndex>> = Mutex::const_new(BTreeMap::new());
+
+/// Persists graphs under `dir`. Without it, graphs live only in memory.
+pub fn set_index_dir(dir: PathBuf) {
+    let _ = INDEX_DIR.set(dir);
+}
+
+/// What a graph was built from. Another provider or dimension needs a rebuild; a new
+/// generation means chunks were added or removed and the graph is patched.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnSignature {
+    pub embedding_provider: String,
+    pub dimension: usize,
+    pub generation: i64,
+}
+
+/// Ids of the `k` chunks closest to `query` by cosine similarity, best first. Brings the
+/// project's graph up to date with `signature` first.
+pub async fn nearest(
+    pool: &SqlitePool,
+    project_root: &str,
+    signature: &AnnSignature,
+    query: &[f32],
+    k: usize,
+) -> Result<Vec<(String, f32)>, String> {
+    let mut loaded = LOADED.lock().await;
+    let cached = match loaded.remove(project_root) {
+        Some(index) => Some(index),
+        None => {
+            let path = index_path(project_root);
+            tokio::task::spawn_blocking(move || path.and_then(|path| AnnIndex::load(&path).ok()))
+                .await
+                .map_err(|error| format!("ANN load task panicked: {error}"))?
+        }
+    };
+    let index = sync_index(pool, project_root, cached, signature).await?;
+    let nearest = index.search(query, k);
+    loaded.insert(project_root.to_string(), index);
+    Ok(nearest)
+}
+
+/// Drops the project's graph from memory and disk.
+pub async fn forget(project_root: &str) {
+    LOADED.lock().await.remove(project_root);
+    if let Some(path) = index_path(project_root) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+async fn sync_index(
+    pool: &SqlitePool,
+    project_root: &str,
+    cached: Option<AnnIndex>,
+    signature: &AnnSignature,
+) -> Result<AnnIndex, String> {
+    let mut index = match cached {
+        Some(index) if index.signature == *signature => return Ok(index),
+        Some(index)
+            if index.signature.embedding_provider == signature.embedding_provider
+                && index.signature.dimension == signature.dimension =>
+        {
+            index
+        }
+        _ => return rebuild(pool, project_root, signature).await,
+    };
+
+    let current =
+        sqlx::query_scalar::<_, String>("SELECT id FROM aop_vector_chunks WHERE project_root = ?")
+            .bind(project_root)
+            .fetch_all(pool)
+            .await
+            .map_err(|error| format!("Failed to list vector chunk ids: {error}"))?
+            .into_iter()
+            .collect::<HashSet<_>>();
+    let removed = index
+        .live_ids()
+        .filter(|id| !current.contains(*id))
+        .cloned()
+        .collect::<Vec<_>>();
+    let added = current
+        .into_iter()
+        .filter(|id| !index.contains(id))
+        .collect::<Vec<_>>();
+
+    let tombstones = index.deleted_count + removed.len();
+    let nodes = index.ids.len() + added.len();
+    if tombstones as f32 > MAX_TOMBSTONE_RATIO * nodes as f32 {
+        return rebuild(pool, project_root, signature).await;
+    }
+
+    let mut vectors = Vec::with_capacity(added.len());
+    for batch in added.chunks(FETCH_BATCH) {
+        vectors.extend(fetch_vectors(pool, project_root, Some(batch)).await?);
+    }
+    let signature = signature.clone();
+    let path = index_path(project_root);
+    tokio::task::spawn_blocking(move || {
+        for id in &removed {
+            index.remove(id);
+        }
+        for (id, vector) in vectors {
+            index.insert(id, &vector)?;
+        }
+        index.signature = signature;
+        index.persist(path.as_deref());
+        Ok(index)
+    })
+    .await
+    .map_err(|error| format!("ANN update task panicked: {error}"))?
+}
+
+async fn rebuild(
+    pool: &SqlitePool,
+    project_root: &str,
+    signature: &AnnSignature,
+) -> Result<AnnIndex, String> {
+    let vectors = fetch_vectors(pool, project_root, None).await?;
+    let signature = signature.clone();
+    let path = index_path(project_root);
+    tokio::task::spawn_blocking(move || {
+        let mut index = AnnIndex::new(signature);
+        for (id, vector) in vectors {
+            index.insert(id, &vector)?;
+        }
+        index.persist(path.as_deref());
+        Ok(index)
+    })
+    .await
+    .map_err(|error| format!("ANN build task panicked: {error}"))?
+}
+
+/// Stored vectors of the project's chunks, or of the chunks in `ids` when given.
+async fn fetch_vectors(
+    pool: &SqlitePool,
+    project_root: &str,
+    ids: Option<&[String]>,
+) -> Result<Vec<(String, Vec<f32>)>, String> {
+    let rows = match ids {
+        Some(ids) => {
+            let placeholders = vec!["?"; ids.len()].join(", ");
+            let sql = format!(
+                "SELECT id, vector_json FROM aop_vector_chunks \
+                 WHERE project_root = ? AND id IN ({placeholders})"
+            );
+            let mut query = sqlx::query_as::<_, (String, String)>(&sql).bind(project_root);
+            for id in ids {
+                query = query.bind(id);
+            }
+            query.fetch_all(pool).await
+        }
+        None => {
+            sqlx::query_as::<_, (String, String)>(
+                "SELECT id, vector_json FROM aop_vector_chunks WHERE project_root = ?",
+            )
+            .bind(project_root)
+            .fetch_all(pool)
+            .await
+        }
+    }
+    .map_err(|error| format!("Failed to read vectors for ANN index: {error}"))?;
+
+    rows.into_iter()
+        .map(|(id, vector_json)| {
+            let vector = serde_json::from_str::<Vec<f32>>(&vector_json).map_err(|error| {
+                format!("Failed to decode stored embedding for chunk '{id}': {error}")
+            })?;
+            Ok((id, vector))
+        })
+        .collect()
+}
+
+fn index_path(project_root: &str) -> Option<PathBuf> {
+    let digest = Sha256::digest(project_root.as_bytes());
+    let name = digest
+        .iter()
+        .take(8)
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    INDEX_DIR.get().map(|dir| dir.join(format!("{name}.ann")))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Scored {
+    distance: f32,
+    node: u32,
+}
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scored {}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .partial_cmp(&other.distance)
+            .unwrap_or(Ordering::Equal)
+            .then(self.node.cmp(&other.node))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileHeader {
+    signature: AnnSignature,
+    node_count: usize,
+    entry: Option<u32>,
+}
+
+/// Hierarchical navigable small world graph over unit-length vectors. Removed chunks
+/// stay in the graph as tombstones so their links keep routing searches.
+pub struct AnnIndex {
+    signature: AnnSignature,
+    ids: Vec<String>,
+    positions: HashMap<String, u32>,
+    vectors: Vec<f32>,
+    /// `links[node][level]` are the node's neighbours on that level.
+    links: Vec<Vec<Vec<u32>>>,
+    deleted: Vec<bool>,
+    deleted_count: usize,
+    entry: Option<u32>,
+}
+
+impl AnnIndex {
+    pub fn new(signature: AnnSignature) -> Self {
+        Self {
+            signature,
+            ids: Vec::new(),
+            positions: HashMap::new(),
+            vectors: Vec::new(),
+            links: Vec::new(),
+            deleted: Vec::new(),
+            deleted_count: 0,
+            entry: None,
+        }
+    }
+
+    fn contains(&self, id: &str) -> bool {
+        self.positions.contains_key(id)
+    }
+
+    fn live_ids(&self) -> impl Iterator<Item = &String> {
+        self.positions.keys()
+    }
+
+    pub fn insert(&mut self, id: String, vector: &[f32]) -> Result<(), String> {
+        if vector.len() != self.signature.dimension {
+            return Err(format!(
+                "Chunk '{id}' has {} dimensions, ANN index expects {}",
+                vector.len(),
+                self.signature.dimension
+            ));
+        }
+        if self.contains(&id) {
+            return Ok(());
+        }
+
+        let query = normalized(vector);
+        let node = self.ids.len() as u32;
+        let level = random_level(node);
+        self.positions.insert(id.clone(), node);
+        self.ids.push(id);
+        self.vectors.extend_from_slice(&query);
+        self.links.push(vec![Vec::new(); level + 1]);
+        self.deleted.push(false);
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(node);
+            return Ok(());
+        };
+        let top = self.links[entry as usize].len() - 1;
+        let mut current = entry;
+        for layer in (level + 1..=top).rev() {
+            current = self.greedy_closest(&query, current, layer);
+        }
+
+        let mut entry_points = vec![current];
+        for layer in (0..=level.min(top)).rev() {
+            let candidates = self.search_layer(&query, &entry_points, EF_CONSTRUCTION, layer);
+            let selected = self.select_neighbors(&candidates, MAX_NEIGHBORS);
+            for &neighbor in &selected {
+                let limit = if layer == 0 {
+                    MAX_BASE_NEIGHBORS
+                } else {
+                    MAX_NEIGHBORS
+                };
+                self.links[neighbor as usize][layer].push(node);
+                if self.links[neighbor as usize][layer].len() > limit {
+                    self.prune(neighbor, layer, limit);
+                }
+            }
+            self.links[node as usize][layer] = selected;
+            entry_points = candidates.iter().map(|scored| scored.node).collect();
+        }
+        if level > top {
+            self.entry = Some(node);
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        if let Some(node) = self.positions.remove(id) {
+            self.deleted[node as usize] = true;
+            self.deleted_count += 1;
+        }
+    }
+
+    /// Ids and cosine similarities of the `k` live nodes closest to `query`.
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<(String, f32)> {
+        let Some(entry) = self.entry else {
+            return Vec::new();
+        };
+        if query.len() != self.signature.dimension || k == 0 {
+            return Vec::new();
+        }
+
+        let query = normalized(query);
+        let top = self.links[entry as usize].len() - 1;
+        let mut current = entry;
+        for layer in (1..=top).rev() {
+            current = self.greedy_closest(&query, current, layer);
+        }
+        // Tombstones take up result slots, so widen the beam by their share.
+        let ef = (k + self.deleted_count * k / self.ids.len().max(1)).max(MIN_EF_SEARCH);
+        self.search_layer(&query, &[current], ef, 0)
+            .into_iter()
+            .filter(|scored| !self.deleted[scored.node as usize])
+            .take(k)
+            .map(|scored| {
+                (
+                    self.ids[scored.node as usize].clone(),
+                    1.0 - scored.distance,
+                )
+            })
+            .collect()
+    }
+
+    fn vector(&self, node: u32) -> &[f32] {
+        let dimension = self.signature.dimension;
+        let start = node as usize * dimension;
+        &self.vectors[start..start + dimension]
+    }
+
+    fn distance_to(&self, query: &[f32], node: u32) -> f32 {
+        1.0 - dot(query, self.vector(node))
+    }
+
+    fn greedy_closest(&self, query: &[f32], start: u32, layer: usize) -> u32 {
+        let mut current = start;
+        let mut best = self.distance_to(query, current);
+        loop {
+            let mut improved = false;
+            for &neighbor in self.neighbors(current, layer) {
+                let distance = self.distance_to(query, neighbor);
+                if distance < best {
+                    best = distance;
+                    current = neighbor;
+                    improved = true;
+                }
+            }
+            if !improved {
+                return current;
+            }
+        }
+    }
+
+    fn neighbors(&self, node: u32, layer: usize) -> &[u32] {
+        self.links[node as usize]
+            .get(layer)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Beam search on one layer; returns up to `ef` nodes, closest first.
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entry_points: &[u32],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Scored> {
+        let mut visited: HashSet<u32> = entry_points.iter().copied().collect();
+        let mut candidates = BinaryHeap::new();
+        let mut results = BinaryHeap::new();
+        for &node in entry_points {
+            let scored = Scored {
+                distance: self.distance_to(query, node),
+                node,
+            };
+            candidates.push(Reverse(scored));
+            results.push(scored);
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(Reverse(closest)) = candidates.pop() {
+            let furthest = results
+                .peek()
+                .map_or(f32::INFINITY, |scored| scored.distance);
+            if closest.distance > furthest && results.len() >= ef {
+                break;
+            }
+            for &neighbor in self.neighbors(closest.node, layer) {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let distance = self.distance_to(query, neighbor);
+                let furthest = results
+                    .peek()
+                    .map_or(f32::INFINITY, |scored| scored.distance);
+                if results.len() < ef || distance < furthest {
+                    let scored = Scored {
+                        distance,
+                        node: neighbor,
+                    };
+                    candidates.push(Reverse(scored));
+                    results.push(scored);
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    /// Keeps candidates (closest first) that are nearer to the base node than to any
+    /// neighbour already kept, so links spread across clusters, then fills up to `limit`.
+    fn select_neighbors(&self, candidates: &[Scored], limit: usize) -> Vec<u32> {
+        let mut selected: Vec<u32> = Vec::with_capacity(limit);
+        for candidate in candidates {
+            if selected.len() >= limit {
+                break;
+            }
+            let diverse = selected.iter().all(|&kept| {
+                self.distance_to(self.vector(candidate.node), kept) > candidate.distance
+            });
+            if diverse {
+                selected.push(candidate.node);
+            }
+        }
+        for candidate in candidates {
+            if selected.len() >= limit {
+                break;
+            }
+            if !selected.contains(&candidate.node) {
+                selected.push(candidate.node);
+            }
+        }
+        selected
+    }
+
+    fn prune(&mut self, node: u32, layer: usize, limit: usize) {
+        let base = self.vector(node).to_vec();
+        let mut scored = self.links[node as usize][layer]
+            .iter()
+            .map(|&neighbor| Scored {
+                distance: self.distance_to(&base, neighbor),
+                node: neighbor,
+            })
+            .collect::<Vec<_>>();
+        scored.sort();
+        self.links[node as usize][layer] = self.select_neighbors(&scored, limit);
+    }
+
+    /// Best effort: a graph that cannot be written is rebuilt next session.
+    fn persist(&self, path: Option<&Path>) {
+        if let Some(path) = path {
+            let _ = self.save(path);
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|error| format!("Failed to create ANN index dir: {error}"))?;
+        }
+        let temp_path = path.with_extension("ann.tmp");
+        let file = fs::File::create(&temp_path)
+            .map_err(|error| format!("Failed to create ANN index file: {error}"))?;
+        let mut writer = BufWriter::new(file);
+        let header = serde_json::to_vec(&FileHeader {
+            signature: self.signature.clone(),
+            node_count: self.ids.len(),
+            entry: self.entry,
+        })
+        .map_err(|error| format!("Failed to serialize ANN header: {error}"))?;
+
+        let mut write = |bytes: &[u8]| {
+            writer
+                .write_all(bytes)
+                .map_err(|error| format!("Failed to write ANN index: {error}"))
+        };
+        write(FILE_MAGIC)?;
+        write(&(header.len() as u32).to_le_bytes())?;
+        write(&header)?;
+        for (node, id) in self.ids.iter().enumerate() {
+            write(&(id.len() as u32).to_le_bytes())?;
+            write(id.as_bytes())?;
+            write(&[u8::from(self.deleted[node]), self.links[node].len() as u8])?;
+            for neighbors in &self.links[node] {
+                write(&(neighbors.len() as u32).to_le_bytes())?;
+                for neighbor in neighbors {
+                    write(&neighbor.to_le_bytes())?;
+                }
+            }
+        }
+        for value in &self.vectors {
+            write(&value.to_le_bytes())?;
+        }
+        writer
+            .flush()
+            .map_err(|error| format!("Failed to write ANN index: {error}"))?;
+        drop(writer);
+        fs::rename(&temp_path, path).map_err(|error| format!("Failed to store ANN index: {error}"))
+    }
+
+    fn load(path: &Path) -> Result<Self, String> {
+        let file =
+            fs::File::open(path).map_err(|error| format!("Failed to open ANN index: {error}"))?;
+        let mut reader = BufReader::new(file);
+        let mut read = |length: usize| -> Result<Vec<u8>, String> {
+            let mut buffer = vec![0u8; length];
+            reader
+                .read_exact(&mut buffer)
+                .map_err(|error| format!("Failed to read ANN index: {error}"))?;
+            Ok(buffer)
+        };
+        let read_u32 =
+            |bytes: Vec<u8>| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+        if read(FILE_MAGIC.len())? != FILE_MAGIC {
+            return Err("ANN index file has an unknown format".to_string());
+        }
+        let header_length = read_u32(read(4)?) as usize;
+        let header = serde_json::from_slice::<FileHeader>(&read(header_length)?)
+            .map_err(|error| format!("Failed to parse ANN header: {error}"))?;
+
+        let mut index = AnnIndex::new(header.signature);
+        index.entry = header.entry;
+        for node in 0..header.node_count {
+            let id_length = read_u32(read(4)?) as usize;
+            let id = String::from_utf8(read(id_length)?)
+                .map_err(|error| format!("ANN index has an invalid chunk id: {error}"))?;
+            let flags = read(2)?;
+            let mut links = Vec::with_capacity(flags[1] as usize);
+            for _ in 0..flags[1] {
+                let count = read_u32(read(4)?) as usize;
+                let bytes = read(count * 4)?;
+                links.push(
+                    bytes
+                        .chunks_exact(4)
+                        .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                        .collect(),
+                );
+            }
+            let deleted = flags[0] == 1;
+            if deleted {
+                index.deleted_count += 1;
+            } else {
+                index.positions.insert(id.clone(), node as u32);
+            }
+            index.ids.push(id);
+            index.links.push(links);
+            index.deleted.push(deleted);
+        }
+        let bytes = read(header.node_count * index.signature.dimension * 4)?;
+        index.vectors = bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        Ok(index)
+    }
+}
+
+fn normalized(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|value| value / norm).collect()
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Level drawn from the usual exponential distribution with `1 / ln(M)` scale, seeded by
+/// the node number so builds are reproducible.
+fn random_level(node: u32) -> usize {
+    let mut state = u64::from(node).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    state = (state ^ (state >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    state = (state ^ (state >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    state ^= state >> 31;
+    let uniform = ((state >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+    let level = -uniform.ln() / (MAX_NEIGHBORS as f64).ln();
+    (level as usize).min(MAX_LEVEL)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db;
+    use crate::vector::indexer;
+
+    use super::*;
+
+    fn pseudo_random_vectors(count: usize, dimension: usize) -> Vec<Vec<f32>> {
+        let mut state = 42u64;
+        (0..count)
+            .map(|_| {
+                (0..dimension)
+                    .map(|_| {
+                        state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                        ((state >> 33) as f32 / u32::MAX as f32) - 0.25
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_exact_neighbors_and_survives_a_save_round_trip() {
+        let vectors = pseudo_random_vectors(2_000, 32);
+        let mut index = AnnIndex::new(AnnSignature {
+            embedding_provider: "hash/32".to_string(),
+            dimension: 32,
+            generation: 1,
+        });
+        for (position, vector) in vectors.iter().enumerate() {
+            index
+                .insert(format!("chunk-{position}"), vector)
+                .expect("insert should succeed");
+        }
+        index.remove("chunk-7");
+
+        let mut hits = 0;
+        for probe in (0..2_000).step_by(40) {
+            let query = &vectors[probe];
+            let mut exact = vectors
+                .iter()
+                .enumerate()
+                .filter(|(position, _)| *position != 7)
+                .map(|(position, vector)| (position, dot(&normalized(query), &normalized(vector))))
+                .collect::<Vec<_>>();
+            exact.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+            let expected = exact
+                .iter()
+                .take(10)
+                .map(|(position, _)| format!("chunk-{position}"))
+                .collect::<HashSet<_>>();
+            let found = index.search(query, 10);
+            assert!(found.iter().all(|(id, _)| id != "chunk-7"));
+            hits += found.iter().filter(|(id, _)| expected.contains(id)).count();
+        }
+        // 50 probes x 10 neighbours; HNSW recall at these settings is well above 90%.
+        assert!(hits >= 450, "recall too low: {hits}/500");
+
+        let dir = tempfile::tempdir().expect("temp dir should exist");
+        let path = dir.path().join("index.ann");
+        index.save(&path).expect("save should succeed");
+        let loaded = AnnIndex::load(&path).expect("load should succeed");
+        assert_eq!(loaded.signature, index.signature);
+        assert_eq!(loaded.search(&vectors[3], 5), index.search(&vectors[3], 5));
+        assert!(!loaded.contains("chunk-7"));
+    }
+
+    #[tokio::test]
+    async fn patches_the_graph_when_the_index_generation_moves() {
+        let project = tempfile::tempdir().expect("project temp dir should exist");
+        let root = project.path().canonicalize().expect("root should resolve");
+        let root = root.to_string_lossy().to_string();
+        std::fs::write(project.path().join("a.rs"), "pub fn alpha() {}\n").expect("fixture");
+        std::fs::write(project.path().join("b.rs"), "pub fn beta() {}\n").expect("fixture");
+
+        let db_dir = tempfile::tempdir().expect("db temp dir should exist");
+        let pool = db::connect_pool(&db_dir.path().join("ann.db"))
+            .await
+            .expect("sqlite pool should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should initialize");
+        let signature = |generation| AnnSignature {
+            embedding_provider: "hash/256".to_string(),
+            dimension: 256,
+            generation,
+        };
+        let chunk_ids = || async {
+            sqlx::query_scalar::<_, String>("SELECT id FROM aop_vector_chunks ORDER BY file_path")
+                .fetch_all(&pool)
+                .await
+                .expect("ids should load")
+        };
+
+        indexer::index_project(&pool, &root)
+            .await
+            .expect("indexing should succeed");
+        let query = indexer::embed_text("pub fn beta");
+        let found = nearest(&pool, &root, &signature(1), &query, 5)
+            .await
+            .expect("search should succeed");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].0, chunk_ids().await[1]);
+
+        std::fs::remove_file(project.path().join("a.rs")).expect("fixture removed");
+        indexer::index_project(&pool, &root)
+            .await
+            .expect("reindexing should succeed");
+        let found = nearest(&pool, &root, &signature(2), &query, 5)
+            .await
+            .expect("search should succeed");
+        assert_eq!(
+            found.into_iter().map(|(id, _)| id).collect::<Vec<_>>(),
+            chunk_ids().await
+        );
+    }
+}
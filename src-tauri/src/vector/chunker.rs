@@ -0,0 +1,485 @@
+use std::path::Path;
+
+use tree_sitter::{Language, Node, Parser};
+
+/// A chunk boundary found in the syntax tree. Lines are 1-based and inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxSpan {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub chunk_type: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Grammar {
+    TypeScript,
+    Tsx,
+    JavaScript,
+    Rust,
+    Python,
+    Go,
+}
+
+impl Grammar {
+    fn for_path(path: &str) -> Option<Self> {
+        match Path::new(path)
+            .extension()
+            .and_then(|value| value.to_str())?
+        {
+            "ts" | "mts" | "cts" => Some(Self::TypeScript),
+            "tsx" => Some(Self::Tsx),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
+            "rs" => Some(Self::Rust),
+            "py" => Some(Self::Python),
+            "go" => Some(Self::Go),
+            _ => None,
+        }
+    }
+
+    fn language(self) -> Language {
+        match self {
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+}
+
+/// Splits a source file along its top-level definitions. Returns `None` for languages
+/// without a grammar so the caller can fall back to line windows. Definitions longer than
+/// `max_lines` are split into their members when they have any (impl blocks, classes),
+/// and into line windows otherwise.
+pub fn syntax_spans(
+    relative_path: &str,
+    content: &str,
+    max_lines: usize,
+) -> Option<Vec<SyntaxSpan>> {
+    let grammar = Grammar::for_path(relative_path)?;
+    let mut parser = Parser::new();
+    parser.set_language(&grammar.language()).ok()?;
+    let tree = parser.parse(content, None)?;
+
+    let mut collector = SpanCollector {
+        grammar,
+        source: content.as_bytes(),
+        max_lines: max_lines.max(1),
+        spans: Vec::new(),
+    };
+    collector.collect_scope(tree.root_node(), None);
+    Some(collector.spans)
+}
+
+struct SpanCollector<'a> {
+    grammar: Grammar,
+    source: &'a [u8],
+    max_lines: usize,
+    spans: Vec<SyntaxSpan>,
+}
+
+/// A run of non-definition nodes (imports, statements, comments) kept together.
+struct ScopeRun {
+    start_line: usize,
+    end_line: usize,
+    imports_only: bool,
+}
+
+impl SpanCollector<'_> {
+    fn collect_scope(&mut self, scope: Node, parent: Option<&str>) {
+        let mut run: Option<ScopeRun> = None;
+        // Comments and attributes directly above a definition belong to it.
+        let mut leading: Option<(usize, usize)> = None;
+        let mut cursor = scope.walk();
+
+        for node in scope.named_children(&mut cursor) {
+            let (start_line, end_line) = line_range(node);
+            if self.is_trivia(node) {
+                let leading_start = leading.map_or(start_line, |(start, _)| start);
+                leading = Some((leading_start, end_line));
+                continue;
+            }
+
+            let start_line = leading.take().map_or(start_line, |(start, _)| start);
+            match self.definition(node) {
+                Some((chunk_type, name)) => {
+                    self.flush_run(run.take(), parent);
+                    let name = match parent {
+                        Some(parent) => format!("{parent}.{name}"),
+                        None => name,
+                    };
+                    self.push_definition(node, start_line, end_line, &chunk_type, &name);
+                }
+                None => {
+                    let is_import = self.is_import(node);
+                    match run.as_mut() {
+                        Some(run) => {
+                            run.end_line = end_line;
+                            run.imports_only &= is_import;
+                        }
+                        None => {
+                            run = Some(ScopeRun {
+                                start_line,
+                                end_line,
+                                imports_only: is_import,
+                            })
+                        }
+                    }
+                }
+            }
+        }
+
+        // Trailing comments stay with the scope.
+        if let Some((leading_start, leading_end)) = leading {
+            match run.as_mut() {
+                Some(run) => run.end_line = leading_end,
+                None => {
+                    run = Some(ScopeRun {
+                        start_line: leading_start,
+                        end_line: leading_end,
+                        imports_only: false,
+                    })
+                }
+            }
+        }
+        self.flush_run(run, parent);
+    }
+
+    fn flush_run(&mut self, run: Option<ScopeRun>, parent: Option<&str>) {
+        let Some(run) = run else {
+            return;
+        };
+        let (chunk_type, name) = match parent {
+            // Fields and other members between methods of a split container.
+            Some(parent) => ("members", parent),
+            None if run.imports_only => ("imports", "file_scope"),
+            None => ("module", "file_scope"),
+        };
+        self.push_windows(run.start_line, run.end_line, chunk_type, name);
+    }
+
+    fn push_definition(
+        &mut self,
+        node: Node,
+        start_line: usize,
+        end_line: usize,
+        chunk_type: &str,
+        name: &str,
+    ) {
+        if end_line + 1 - start_line <= self.max_lines {
+            self.spans.push(SyntaxSpan {
+                start_line,
+                end_line,
+                chunk_type: chunk_type.to_string(),
+                name: name.to_string(),
+            });
+            return;
+        }
+
+        if let Some(body) = self.member_body(node) {
+            let members_before = self.spans.len();
+            self.collect_scope(body, Some(name));
+            if self.spans.len() > members_before {
+                // Keep the header (signature, fields before the first member) searchable.
+                let first_member = self.spans[members_before].start_line;
+                if first_member > start_line {
+                    let header = SyntaxSpan {
+                        start_line,
+                        end_line: first_member - 1,
+                        chunk_type: chunk_type.to_string(),
+                        name: name.to_string(),
+                    };
+                    self.spans.insert(members_before, header);
+                }
+                return;
+            }
+        }
+        self.push_windows(start_line, end_line, chunk_type, name);
+    }
+
+    fn push_windows(&mut self, start_line: usize, end_line: usize, chunk_type: &str, name: &str) {
+        let mut window_start = start_line;
+        while window_start <= end_line {
+            let window_end = (window_start + self.max_lines - 1).min(end_line);
+            self.spans.push(SyntaxSpan {
+                start_line: window_start,
+                end_line: window_end,
+                chunk_type: chunk_type.to_string(),
+                name: name.to_string(),
+            });
+            window_start = window_end + 1;
+        }
+    }
+
+    fn is_trivia(&self, node: Node) -> bool {
+        matches!(
+            node.kind(),
+            "comment"
+                | "line_comment"
+                | "block_comment"
+                | "attribute_item"
+                | "inner_attribute_item"
+        )
+    }
+
+    fn is_import(&self, node: Node) -> bool {
+        matches!(
+            node.kind(),
+            "import_statement"
+                | "import_from_statement"
+                | "future_import_statement"
+                | "import_declaration"
+                | "use_declaration"
+                | "extern_crate_declaration"
+                | "package_clause"
+        )
+    }
+
+    /// The chunk type and name for nodes that start a chunk of their own.
+    fn definition(&self, node: Node) -> Option<(String, String)> {
+        let kind = node.kind();
+        let named = |chunk_type: &str| {
+            Some((
+                chunk_type.to_string(),
+                self.field_text(node, "name")
+                    .unwrap_or_else(|| "anonymous".to_string()),
+            ))
+        };
+
+        match self.grammar {
+            Grammar::Rust => match kind {
+                "function_item" | "function_signature_item" => named("function"),
+                "struct_item" | "enum_item" | "union_item" | "type_item" => named("type"),
+                "trait_item" => named("trait"),
+                "mod_item" => named("module"),
+                "macro_definition" => named("macro"),
+                "const_item" | "static_item" => named("constant"),
+                "impl_item" => {
+                    let target = self.field_text(node, "type")?;
+                    let name = match self.field_text(node, "trait") {
+                        Some(trait_name) => format!("{trait_name} for {target}"),
+                        None => target,
+                    };
+                    Some(("impl".to_string(), name))
+                }
+                _ => None,
+            },
+            Grammar::TypeScript | Grammar::Tsx | Grammar::JavaScript => match kind {
+                "function_declaration"
+                | "generator_function_declaration"
+                | "function_signature" => named("function"),
+                "class_declaration" | "abstract_class_declaration" => named("class"),
+                "interface_declaration" | "type_alias_declaration" | "enum_declaration" => {
+                    named("type")
+                }
+                "method_definition" | "method_signature" | "abstract_method_signature" => {
+                    named("method")
+                }
+                "internal_module" | "module" => named("module"),
+                "lexical_declaration" | "variable_declaration" => self.function_binding(node),
+                "export_statement" => self.export_definition(node),
+                _ => None,
+            },
+            Grammar::Python => match kind {
+                "function_definition" => named("function"),
+                "class_definition" => named("class"),
+                "decorated_definition" => node
+                    .child_by_field_name("definition")
+                    .and_then(|definition| self.definition(definition)),
+                _ => None,
+            },
+            Grammar::Go => match kind {
+                "function_declaration" => named("function"),
+                "method_declaration" => {
+                    let method = self.field_text(node, "name")?;
+                    let receiver = node
+                        .child_by_field_name("receiver")
+                        .and_then(|receiver| self.receiver_type(receiver));
+                    let name = match receiver {
+                        Some(receiver) => format!("{receiver}.{method}"),
+                        None => method,
+                    };
+                    Some(("method".to_string(), name))
+                }
+                "type_declaration" => {
+                    let mut cursor = node.walk();
+                    let spec = node
+                        .named_children(&mut cursor)
+                        .find(|child| matches!(child.kind(), "type_spec" | "type_alias"))?;
+                    Some((
+                        "type".to_string(),
+                        self.field_text(spec, "name")
+                            .unwrap_or_else(|| "anonymous".to_string()),
+                    ))
+                }
+                _ => None,
+            },
+        }
+    }
+
+    /// `export function f`, `export class C` and `export default ...` take the exported
+    /// declaration's type; other exports stay with the module scope.
+    fn export_definition(&self, node: Node) -> Option<(String, String)> {
+        if let Some(declaration) = node.child_by_field_name("declaration") {
+            return self.definition(declaration);
+        }
+        let value = node.child_by_field_name("value")?;
+        match value.kind() {
+            "arrow_function" | "function_expression" | "function" => {
+                Some(("function".to_string(), "default".to_string()))
+            }
+            "class" => Some(("class".to_string(), "default".to_string())),
+            _ => None,
+        }
+    }
+
+    /// `const handler = () => {}` and `const f = function () {}` are functions.
+    fn function_binding(&self, node: Node) -> Option<(String, String)> {
+        let mut cursor = node.walk();
+        let declarator = node
+            .named_children(&mut cursor)
+            .find(|child| child.kind() == "variable_declarator")?;
+        let value = declarator.child_by_field_name("value")?;
+        if !matches!(
+            value.kind(),
+            "arrow_function" | "function_expression" | "function" | "generator_function"
+        ) {
+            return None;
+        }
+        Some(("function".to_string(), self.field_text(declarator, "name")?))
+    }
+
+    fn receiver_type(&self, receiver: Node) -> Option<String> {
+        let mut cursor = receiver.walk();
+        let parameter = receiver.named_children(&mut cursor).next()?;
+        let text = self.field_text(parameter, "type")?;
+        Some(text.trim_start_matches('*').to_string())
+    }
+
+    /// The node holding the members of a container definition, if it has one.
+    fn member_body<'tree>(&self, node: Node<'tree>) -> Option<Node<'tree>> {
+        let node = match node.kind() {
+            "export_statement" => node.child_by_field_name("declaration")?,
+            "decorated_definition" => node.child_by_field_name("definition")?,
+            _ => node,
+        };
+        match node.kind() {
+            "impl_item"
+            | "trait_item"
+            | "mod_item"
+            | "class_declaration"
+            | "abstract_class_declaration"
+            | "class_definition"
+            | "interface_declaration"
+            | "internal_module"
+            | "module" => node.child_by_field_name("body"),
+            _ => None,
+        }
+    }
+
+    fn field_text(&self, node: Node, field: &str) -> Option<String> {
+        let child = node.child_by_field_name(field)?;
+        let text = child.utf8_text(self.source).ok()?;
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        (!text.is_empty()).then_some(text)
+    }
+}
+
+/// 1-based inclusive line range. A node ending at column 0 ends on the previous line.
+fn line_range(node: Node) -> (usize, usize) {
+    let start = node.start_position();
+    let end = node.end_position();
+    let end_row = if end.column == 0 && end.row > start.row {
+        end.row - 1
+    } else {
+        end.row
+    };
+    (start.row + 1, end_row + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(spans: &[SyntaxSpan]) -> Vec<(String, String, usize, usize)> {
+        spans
+            .iter()
+            .map(|span| {
+                (
+                    span.chunk_type.clone(),
+                    span.name.clone(),
+                    span.start_line,
+                    span.end_line,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chunks_rust_along_items_with_leading_docs() {
+        let source = "use std::fmt;\n\n/// A point.\n#[derive(Debug)]\npub struct Point {\n    x: i32,\n}\n\nimpl fmt::Display for Point {\n    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {\n        write!(f, \"{}\", self.x)\n    }\n}\n\nfn main() {}\n";
+        let spans = syntax_spans("src/point.rs", source, 180).expect("rust is supported");
+        assert_eq!(
+            summary(&spans),
+            vec![
+                ("imports".into(), "file_scope".into(), 1, 1),
+                ("type".into(), "Point".into(), 3, 7),
+                ("impl".into(), "fmt::Display for Point".into(), 9, 13),
+                ("function".into(), "main".into(), 15, 15),
+            ]
+        );
+    }
+
+    #[test]
+    fn chunks_typescript_python_and_go_definitions() {
+        let ts = "import { api } from './api'\n\nexport const useSession = () => {\n  return api.session()\n}\n\nexport interface Session {\n  id: string\n}\n\nexport class Store {\n  load() {}\n}\n";
+        let spans = syntax_spans("src/session.ts", ts, 180).expect("ts is supported");
+        assert_eq!(
+            summary(&spans),
+            vec![
+                ("imports".into(), "file_scope".into(), 1, 1),
+                ("function".into(), "useSession".into(), 3, 5),
+                ("type".into(), "Session".into(), 7, 9),
+                ("class".into(), "Store".into(), 11, 13),
+            ]
+        );
+
+        let py = "import os\n\n@cache\ndef load(path):\n    return os.read(path)\n\nclass Config:\n    pass\n";
+        let spans = syntax_spans("app/config.py", py, 180).expect("python is supported");
+        assert_eq!(
+            summary(&spans)[1..],
+            [
+                ("function".into(), "load".into(), 3, 5),
+                ("class".into(), "Config".into(), 7, 8),
+            ]
+        );
+
+        let go = "package store\n\ntype Cache struct{}\n\nfunc (c *Cache) Get(key string) string {\n\treturn key\n}\n";
+        let spans = syntax_spans("store/cache.go", go, 180).expect("go is supported");
+        assert_eq!(
+            summary(&spans)[1..],
+            [
+                ("type".into(), "Cache".into(), 3, 3),
+                ("method".into(), "Cache.Get".into(), 5, 7),
+            ]
+        );
+
+        assert!(syntax_spans("styles/app.css", "a {}", 180).is_none());
+    }
+
+    #[test]
+    fn splits_oversized_containers_into_members() {
+        let ts = "class Big {\n  a() {\n    return 1\n  }\n  b() {\n    return 2\n  }\n}\n";
+        let spans = syntax_spans("big.ts", ts, 4).expect("ts is supported");
+        assert_eq!(
+            summary(&spans),
+            vec![
+                ("class".into(), "Big".into(), 1, 1),
+                ("method".into(), "Big.a".into(), 2, 4),
+                ("method".into(), "Big.b".into(), 5, 7),
+            ]
+        );
+    }
+}
@@ -0,0 +1,378 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use super::indexer::embed_text;
+use super::VECTOR_DIM;
+
+/// Provider names accepted by `AOP_EMBEDDING_PROVIDER`.
+pub const EMBEDDING_PROVIDERS: &[&str] = &["hash", "openai", "ollama", "fastembed"];
+pub const DEFAULT_EMBEDDING_PROVIDER: &str = "hash";
+
+const OPENAI_DEFAULT_MODEL: &str = "text-embedding-3-small";
+const OLLAMA_DEFAULT_MODEL: &str = "nomic-embed-text";
+const OLLAMA_DEFAULT_URL: &str = "http://127.0.0.1:11434";
+const FASTEMBED_DEFAULT_MODEL: &str = "BAAI/bge-small-en-v1.5";
+const FASTEMBED_DEFAULT_URL: &str = "http://127.0.0.1:8080";
+const REQUEST_TIMEOUT_SECS: u64 = 120;
+
+/// Turns text into vectors for the codebase index. Calls are blocking; async callers run
+/// them on `spawn_blocking`.
+pub trait EmbeddingProvider: Send + Sync {
+    /// Stored with the index, e.g. `openai/text-embedding-3-small`. A different id means the
+    /// stored vectors are not comparable and the project must be reindexed.
+    fn id(&self) -> String;
+
+    /// Known output dimension, if the provider can tell before the first call.
+    fn dimension(&self) -> Option<usize>;
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String>;
+
+    /// Largest number of texts sent in one request.
+    fn batch_size(&self) -> usize {
+        64
+    }
+}
+
+/// The built-in token-hashing embedding. Needs no network and is the default.
+pub struct HashEmbedding;
+
+impl EmbeddingProvider for HashEmbedding {
+    fn id(&self) -> String {
+        format!("hash/{VECTOR_DIM}")
+    }
+
+    fn dimension(&self) -> Option<usize> {
+        Some(VECTOR_DIM)
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        Ok(texts.iter().map(|text| embed_text(text)).collect())
+    }
+
+    fn batch_size(&self) -> usize {
+        usize::MAX
+    }
+}
+
+/// OpenAI `/v1/embeddings`, authenticated with `OPENAI_API_KEY`.
+pub struct OpenAiEmbedding {
+    model: String,
+}
+
+impl EmbeddingProvider for OpenAiEmbedding {
+    fn id(&self) -> String {
+        format!("openai/{}", self.model)
+    }
+
+    fn dimension(&self) -> Option<usize> {
+        match self.model.as_str() {
+            "text-embedding-3-small" | "text-embedding-ada-002" => Some(1536),
+            "text-embedding-3-large" => Some(3072),
+            _ => None,
+        }
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let api_key = std::env::var("OPENAI_API_KEY")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| "OPENAI_API_KEY is required for openai embeddings".to_string())?;
+        let response = http_client()?
+            .post("https://api.openai.com/v1/embeddings")
+            .bearer_auth(api_key)
+            .json(&json!({ "model": self.model, "input": texts }))
+            .send()
+            .map_err(|error| format!("Failed to call OpenAI embeddings API: {error}"))?;
+        let payload = read_payload(response, "OpenAI embeddings")?;
+        parse_openai_embeddings(&payload, texts.len())
+    }
+}
+
+/// Ollama `/api/embed` on `AOP_OLLAMA_URL` (default `http://127.0.0.1:11434`).
+pub struct OllamaEmbedding {
+    model: String,
+    base_url: String,
+}
+
+impl EmbeddingProvider for OllamaEmbedding {
+    fn id(&self) -> String {
+        format!("ollama/{}", self.model)
+    }
+
+    fn dimension(&self) -> Option<usize> {
+        None
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let response = http_client()?
+            .post(format!("{}/api/embed", self.base_url))
+            .json(&json!({ "model": self.model, "input": texts }))
+            .send()
+            .map_err(|error| {
+                format!(
+                    "Failed to reach Ollama at '{}': {error}. Is `ollama serve` running?",
+                    self.base_url
+                )
+            })?;
+        let payload = read_payload(response, "Ollama embeddings")?;
+        let embeddings = payload
+            .get("embeddings")
+            .ok_or_else(|| format!("Ollama response did not include embeddings: {payload}"))?;
+        parse_vectors(embeddings, texts.len(), "Ollama")
+    }
+
+    fn batch_size(&self) -> usize {
+        32
+    }
+}
+
+/// A local fastembed/ONNX model served over the text-embeddings-inference `/embed` API on
+/// `AOP_EMBEDDING_LOCAL_URL` (default `http://127.0.0.1:8080`). The model is whatever the
+/// server loaded; `AOP_EMBEDDING_MODEL` only names it in the index metadata.
+pub struct FastEmbedEmbedding {
+    model: String,
+    base_url: String,
+}
+
+impl EmbeddingProvider for FastEmbedEmbedding {
+    fn id(&self) -> String {
+        format!("fastembed/{}", self.model)
+    }
+
+    fn dimension(&self) -> Option<usize> {
+        match self.model.as_str() {
+            "BAAI/bge-small-en-v1.5" | "sentence-transformers/all-MiniLM-L6-v2" => Some(384),
+            "BAAI/bge-base-en-v1.5" | "nomic-ai/nomic-embed-text-v1.5" => Some(768),
+            "BAAI/bge-large-en-v1.5" => Some(1024),
+            _ => None,
+        }
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let response = http_client()?
+            .post(format!("{}/embed", self.base_url))
+            .json(&json!({ "inputs": texts, "truncate": true }))
+            .send()
+            .map_err(|error| {
+                format!(
+                    "Failed to reach local embedding server at '{}': {error}",
+                    self.base_url
+                )
+            })?;
+        let payload = read_payload(response, "Local embeddings")?;
+        parse_vectors(&payload, texts.len(), "Local embedding server")
+    }
+
+    fn batch_size(&self) -> usize {
+        32
+    }
+}
+
+/// Maps aliases to a provider name from [`EMBEDDING_PROVIDERS`].
+pub fn normalize_provider_name(value: &str) -> Option<&'static str> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "" | "hash" | "builtin" => Some("hash"),
+        "openai" | "openai_api" => Some("openai"),
+        "ollama" => Some("ollama"),
+        "fastembed" | "onnx" | "local" => Some("fastembed"),
+        _ => None,
+    }
+}
+
+/// The provider selected by `AOP_EMBEDDING_PROVIDER` and `AOP_EMBEDDING_MODEL`, which the
+/// runtime flags keep in sync.
+pub fn active_provider() -> Result<Arc<dyn EmbeddingProvider>, String> {
+    let name = std::env::var("AOP_EMBEDDING_PROVIDER").unwrap_or_default();
+    let model = std::env::var("AOP_EMBEDDING_MODEL").unwrap_or_default();
+    provider_for(&name, &model)
+}
+
+pub fn provider_for(name: &str, model: &str) -> Result<Arc<dyn EmbeddingProvider>, String> {
+    let provider = normalize_provider_name(name).ok_or_else(|| {
+        format!(
+            "Unknown embedding provider '{}'. Expected one of: {}",
+            name.trim(),
+            EMBEDDING_PROVIDERS.join(", ")
+        )
+    })?;
+    let model = model.trim();
+    let model_or = |default: &str| {
+        if model.is_empty() {
+            default.to_string()
+        } else {
+            model.to_string()
+        }
+    };
+
+    Ok(match provider {
+        "openai" => Arc::new(OpenAiEmbedding {
+            model: model_or(OPENAI_DEFAULT_MODEL),
+        }),
+        "ollama" => Arc::new(OllamaEmbedding {
+            model: model_or(OLLAMA_DEFAULT_MODEL),
+            base_url: base_url("AOP_OLLAMA_URL", OLLAMA_DEFAULT_URL),
+        }),
+        "fastembed" => Arc::new(FastEmbedEmbedding {
+            model: model_or(FASTEMBED_DEFAULT_MODEL),
+            base_url: base_url("AOP_EMBEDDING_LOCAL_URL", FASTEMBED_DEFAULT_URL),
+        }),
+        _ => Arc::new(HashEmbedding),
+    })
+}
+
+/// Embeds `texts` in provider-sized batches and checks that every vector has the same
+/// dimension.
+pub fn embed_all(
+    provider: &dyn EmbeddingProvider,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    let mut vectors = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(provider.batch_size().max(1)) {
+        vectors.extend(provider.embed(batch)?);
+    }
+
+    let expected = provider
+        .dimension()
+        .or_else(|| vectors.first().map(Vec::len));
+    if let Some(expected) = expected {
+        if let Some(vector) = vectors.iter().find(|vector| vector.len() != expected) {
+            return Err(format!(
+                "Embedding provider '{}' returned a {}-dimensional vector; expected {expected}",
+                provider.id(),
+                vector.len()
+            ));
+        }
+    }
+    Ok(vectors)
+}
+
+fn base_url(key: &str, default: &str) -> String {
+    std::env::var(key)
+        .ok()
+        .map(|value| value.trim().trim_end_matches('/').to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| default.to_string())
+}
+
+fn http_client() -> Result<reqwest::blocking::Client, String> {
+    reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|error| format!("Failed to build embedding HTTP client: {error}"))
+}
+
+fn read_payload(response: reqwest::blocking::Response, label: &str) -> Result<Value, String> {
+    let status = response.status();
+    let payload: Value = response
+        .json()
+        .map_err(|error| format!("Invalid {label} response payload: {error}"))?;
+    if !status.is_success() {
+        return Err(format!("{label} failed with status {status}: {payload}"));
+    }
+    Ok(payload)
+}
+
+fn parse_openai_embeddings(payload: &Value, expected: usize) -> Result<Vec<Vec<f32>>, String> {
+    let data = payload
+        .get("data")
+        .and_then(Value::as_array)
+        .ok_or_else(|| format!("OpenAI response did not include embeddings: {payload}"))?;
+    let mut indexed = data
+        .iter()
+        .enumerate()
+        .map(|(position, item)| {
+            let index = item
+                .get("index")
+                .and_then(Value::as_u64)
+                .map(|value| value as usize)
+                .unwrap_or(position);
+            let vector = item
+                .get("embedding")
+                .ok_or_else(|| format!("OpenAI embedding {index} is missing its vector"))
+                .and_then(|value| parse_vector(value, "OpenAI"))?;
+            Ok((index, vector))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    // The API documents `index` as the input position, not the response order.
+    indexed.sort_by_key(|(index, _)| *index);
+    let vectors = indexed
+        .into_iter()
+        .map(|(_, vector)| vector)
+        .collect::<Vec<_>>();
+    check_count(vectors, expected, "OpenAI")
+}
+
+fn parse_vectors(value: &Value, expected: usize, label: &str) -> Result<Vec<Vec<f32>>, String> {
+    let vectors = value
+        .as_array()
+        .ok_or_else(|| format!("{label} returned embeddings in an unexpected shape"))?
+        .iter()
+        .map(|vector| parse_vector(vector, label))
+        .collect::<Result<Vec<_>, String>>()?;
+    check_count(vectors, expected, label)
+}
+
+fn parse_vector(value: &Value, label: &str) -> Result<Vec<f32>, String> {
+    value
+        .as_array()
+        .ok_or_else(|| format!("{label} returned a non-array embedding"))?
+        .iter()
+        .map(|number| {
+            number
+                .as_f64()
+                .map(|number| number as f32)
+                .ok_or_else(|| format!("{label} returned a non-numeric embedding value"))
+        })
+        .collect()
+}
+
+fn check_count(
+    vectors: Vec<Vec<f32>>,
+    expected: usize,
+    label: &str,
+) -> Result<Vec<Vec<f32>>, String> {
+    if vectors.len() != expected {
+        return Err(format!(
+            "{label} returned {} embeddings for {expected} inputs",
+            vectors.len()
+        ));
+    }
+    Ok(vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_provider_payloads_and_selects_defaults() {
+        let payload = json!({
+            "data": [
+                { "index": 1, "embedding": [0.0, 1.0] },
+                { "index": 0, "embedding": [1.0, 0.0] }
+            ]
+        });
+        assert_eq!(
+            parse_openai_embeddings(&payload, 2).expect("openai payload should parse"),
+            vec![vec![1.0, 0.0], vec![0.0, 1.0]]
+        );
+        assert!(parse_vectors(&json!([[0.5, 0.5]]), 2, "Ollama").is_err());
+
+        let provider = provider_for("", "").expect("default provider");
+        assert_eq!(provider.id(), format!("hash/{VECTOR_DIM}"));
+        assert_eq!(
+            provider_for("local", "").expect("fastembed alias").id(),
+            "fastembed/BAAI/bge-small-en-v1.5"
+        );
+        assert_eq!(
+            provider_for("openai", "text-embedding-3-large")
+                .expect("openai provider")
+                .dimension(),
+            Some(3072)
+        );
+        assert!(provider_for("word2vec", "").is_err());
+    }
+}
@@ -0,0 +1,491 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use sqlx::SqlitePool;
+
+use crate::mutation_pipeline::run_command_capture;
+use crate::vector::embedding::{self, EmbeddingProvider};
+use crate::vector::{GitHistoryEntry, IndexGitHistoryResult};
+
+use super::indexer;
+use super::search::cosine_similarity;
+
+/// Commits indexed per project; older ones are dropped as new ones arrive.
+const HISTORY_DEPTH: usize = 200;
+/// Files of a single commit that get their own row; the rest only appear in its summary.
+const MAX_FILES_PER_COMMIT: usize = 40;
+const MAX_BODY_CHARS: usize = 600;
+const GIT_TIMEOUT: Duration = Duration::from_secs(30);
+const RECORD_SEPARATOR: char = '\u{1e}';
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+#[derive(Debug, Clone, PartialEq)]
+struct Commit {
+    hash: String,
+    author: String,
+    committed_at: i64,
+    subject: String,
+    body: String,
+    files: Vec<FileChange>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct FileChange {
+    path: String,
+    /// `None` for binary files, which git reports without line counts.
+    lines: Option<(u32, u32)>,
+}
+
+#[derive(Debug, Clone)]
+struct HistoryRow {
+    commit_hash: String,
+    file_path: String,
+    author: String,
+    committed_at: i64,
+    subject: String,
+    summary: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct StoredRow {
+    commit_hash: String,
+    file_path: String,
+    author: String,
+    committed_at: i64,
+    subject: String,
+    summary: String,
+    vector_json: String,
+}
+
+pub async fn index_git_history(
+    pool: &SqlitePool,
+    target_project: &str,
+) -> Result<IndexGitHistoryResult, String> {
+    let provider = embedding::active_provider()?;
+    index_git_history_with(pool, target_project, provider).await
+}
+
+/// Embeds the project's recent commits that are not indexed yet and forgets those that
+/// fell out of the window or were rewritten. Paths are relative to the project, so a
+/// project inside a larger repository only sees its own changes.
+async fn index_git_history_with(
+    pool: &SqlitePool,
+    target_project: &str,
+    provider: Arc<dyn EmbeddingProvider>,
+) -> Result<IndexGitHistoryResult, String> {
+    let project_root = indexer::normalize_project_root(target_project)?;
+    let project_root_str = project_root.to_string_lossy().to_string();
+    let output = run_command_capture(
+        &project_root,
+        "git",
+        vec![
+            "log".to_string(),
+            "--no-merges".to_string(),
+            "--relative".to_string(),
+            "--numstat".to_string(),
+            format!("--max-count={HISTORY_DEPTH}"),
+            "--format=%x1e%H%x1f%an%x1f%ct%x1f%s%x1f%b%x1f".to_string(),
+            "--".to_string(),
+            ".".to_string(),
+        ],
+        GIT_TIMEOUT,
+    )
+    .await?;
+    if output.exit_code != 0 {
+        return Err(format!(
+            "Failed to read git history of '{project_root_str}': {}",
+            output.stderr.trim()
+        ));
+    }
+    let commits = parse_git_log(&output.stdout);
+
+    let provider_id = provider.id();
+    let stored = sqlx::query_as::<_, (String, String)>(
+        "SELECT DISTINCT commit_hash, embedding_provider FROM aop_vector_git_history \
+         WHERE project_root = ?",
+    )
+    .bind(&project_root_str)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to read indexed git history: {error}"))?;
+    // Vectors from another provider cannot be compared with new queries.
+    let reusable = stored
+        .iter()
+        .filter(|(_, stored_provider)| *stored_provider == provider_id)
+        .map(|(hash, _)| hash.clone())
+        .collect::<HashSet<_>>();
+    let current = commits
+        .iter()
+        .map(|commit| commit.hash.clone())
+        .collect::<HashSet<_>>();
+    let stale = stored
+        .iter()
+        .filter(|(hash, stored_provider)| {
+            *stored_provider != provider_id || !current.contains(hash)
+        })
+        .map(|(hash, _)| hash.clone())
+        .collect::<HashSet<_>>();
+
+    let rows = commits
+        .iter()
+        .filter(|commit| !reusable.contains(&commit.hash))
+        .flat_map(history_rows)
+        .collect::<Vec<_>>();
+    let texts = rows.iter().map(embedding_text).collect::<Vec<_>>();
+    let vectors =
+        tokio::task::spawn_blocking(move || embedding::embed_all(provider.as_ref(), &texts))
+            .await
+            .map_err(|error| format!("Embedding task panicked: {error}"))??;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|error| format!("Failed to start git history transaction: {error}"))?;
+    for hash in &stale {
+        sqlx::query(
+            "DELETE FROM aop_vector_git_history WHERE project_root = ? AND commit_hash = ?",
+        )
+        .bind(&project_root_str)
+        .bind(hash)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|error| format!("Failed to forget commit '{hash}': {error}"))?;
+    }
+    let indexed_at = Utc::now().timestamp();
+    for (row, vector) in rows.iter().zip(&vectors) {
+        let vector_json = serde_json::to_string(vector)
+            .map_err(|error| format!("Failed to serialize vector embedding: {error}"))?;
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO aop_vector_git_history (
+                project_root, commit_hash, file_path, author, committed_at,
+                subject, summary, embedding_provider, vector_json, indexed_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&project_root_str)
+        .bind(&row.commit_hash)
+        .bind(&row.file_path)
+        .bind(&row.author)
+        .bind(row.committed_at)
+        .bind(&row.subject)
+        .bind(&row.summary)
+        .bind(&provider_id)
+        .bind(vector_json)
+        .bind(indexed_at)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|error| format!("Failed to insert commit '{}': {error}", row.commit_hash))?;
+    }
+    transaction
+        .commit()
+        .await
+        .map_err(|error| format!("Failed to commit git history index: {error}"))?;
+
+    Ok(IndexGitHistoryResult {
+        target_project: project_root_str,
+        commits: commits.len() as u32,
+        indexed_commits: commits
+            .iter()
+            .filter(|commit| !reusable.contains(&commit.hash))
+            .count() as u32,
+        removed_commits: stale.iter().filter(|hash| !current.contains(*hash)).count() as u32,
+        embedding_provider: provider_id,
+    })
+}
+
+/// Recent changes relevant to a plan: every indexed change to `target_files`, newest
+/// first, then the commits and file changes closest to `query`. The history is brought up
+/// to date first.
+pub async fn query_git_history(
+    pool: &SqlitePool,
+    target_project: &str,
+    query: &str,
+    target_files: &[String],
+    limit: u32,
+) -> Result<Vec<GitHistoryEntry>, String> {
+    let provider = embedding::active_provider()?;
+    let indexed = index_git_history_with(pool, target_project, provider.clone()).await?;
+    let limit = usize::try_from(limit.max(1)).unwrap_or(10);
+
+    let mut entries = file_history(pool, &indexed.target_project, target_files, limit).await?;
+    if query.trim().is_empty() || entries.len() >= limit {
+        entries.truncate(limit);
+        return Ok(entries);
+    }
+
+    let query_text = vec![query.to_string()];
+    let query_vector =
+        tokio::task::spawn_blocking(move || embedding::embed_all(provider.as_ref(), &query_text))
+            .await
+            .map_err(|error| format!("Embedding task panicked: {error}"))??
+            .pop()
+            .unwrap_or_default();
+    let rows = sqlx::query_as::<_, StoredRow>(
+        "SELECT commit_hash, file_path, author, committed_at, subject, summary, vector_json \
+         FROM aop_vector_git_history WHERE project_root = ?",
+    )
+    .bind(&indexed.target_project)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to query git history: {error}"))?;
+    let mut scored = Vec::with_capacity(rows.len());
+    for row in rows {
+        let vector = serde_json::from_str::<Vec<f32>>(&row.vector_json).map_err(|error| {
+            format!(
+                "Failed to decode stored embedding for commit '{}': {error}",
+                row.commit_hash
+            )
+        })?;
+        let score = cosine_similarity(&query_vector, &vector);
+        scored.push(entry_from_row(row, score));
+    }
+    scored.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+
+    for entry in scored {
+        if entries.len() >= limit {
+            break;
+        }
+        let duplicate = entries.iter().any(|existing| {
+            existing.commit_hash == entry.commit_hash && existing.file_path == entry.file_path
+        });
+        if !duplicate && entry.score > 0.0 {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Indexed changes to `files`, newest first, without refreshing the index.
+pub async fn file_history(
+    pool: &SqlitePool,
+    project_root: &str,
+    files: &[String],
+    limit: usize,
+) -> Result<Vec<GitHistoryEntry>, String> {
+    let mut entries = Vec::new();
+    for file in files {
+        let rows = sqlx::query_as::<_, StoredRow>(
+            "SELECT commit_hash, file_path, author, committed_at, subject, summary, vector_json \
+             FROM aop_vector_git_history WHERE project_root = ? AND file_path = ? \
+             ORDER BY committed_at DESC LIMIT ?",
+        )
+        .bind(project_root)
+        .bind(file)
+        .bind(limit as i64)
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to query history of '{file}': {error}"))?;
+        entries.extend(rows.into_iter().map(|row| entry_from_row(row, 1.0)));
+    }
+    entries.sort_by_key(|entry| Reverse(entry.committed_at));
+    Ok(entries)
+}
+
+fn entry_from_row(row: StoredRow, score: f32) -> GitHistoryEntry {
+    GitHistoryEntry {
+        commit_hash: row.commit_hash,
+        file_path: (!row.file_path.is_empty()).then_some(row.file_path),
+        author: row.author,
+        committed_at: row.committed_at,
+        subject: row.subject,
+        summary: row.summary,
+        score,
+    }
+}
+
+/// One row for the commit message, one per changed file.
+fn history_rows(commit: &Commit) -> Vec<HistoryRow> {
+    let row = |file_path: &str, summary: String| HistoryRow {
+        commit_hash: commit.hash.clone(),
+        file_path: file_path.to_string(),
+        author: commit.author.clone(),
+        committed_at: commit.committed_at,
+        subject: commit.subject.clone(),
+        summary,
+    };
+
+    let mut body = commit.body.chars().take(MAX_BODY_CHARS).collect::<String>();
+    let file_list = commit
+        .files
+        .iter()
+        .map(|file| file.path.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    if !file_list.is_empty() {
+        if !body.is_empty() {
+            body.push_str("\n\n");
+        }
+        body.push_str(&format!("files: {file_list}"));
+    }
+
+    let mut rows = vec![row("", body)];
+    rows.extend(
+        commit
+            .files
+            .iter()
+            .take(MAX_FILES_PER_COMMIT)
+            .map(|file| row(&file.path, change_summary(file))),
+    );
+    rows
+}
+
+fn change_summary(file: &FileChange) -> String {
+    match file.lines {
+        Some((0, deleted)) if deleted > 0 => format!("{}: -{deleted} lines", file.path),
+        Some((added, 0)) => format!("{}: +{added} lines", file.path),
+        Some((added, deleted)) => format!("{}: +{added} -{deleted} lines", file.path),
+        None => format!("{}: binary change", file.path),
+    }
+}
+
+fn embedding_text(row: &HistoryRow) -> String {
+    if row.file_path.is_empty() {
+        format!("{}\n{}", row.subject, row.summary)
+    } else {
+        format!("{}\n{}", row.summary, row.subject)
+    }
+}
+
+/// Parses `git log --numstat` output in the format `index_git_history` requests.
+fn parse_git_log(output: &str) -> Vec<Commit> {
+    let mut commits = Vec::new();
+    for record in output.split(RECORD_SEPARATOR) {
+        let fields = record.splitn(6, FIELD_SEPARATOR).collect::<Vec<_>>();
+        let [hash, author, committed_at, subject, body, numstat] = fields[..] else {
+            continue;
+        };
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+        for line in numstat.lines() {
+            let mut parts = line.splitn(3, '\t');
+            let (Some(added), Some(deleted), Some(path)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            let path = renamed_path(path);
+            if !seen.insert(path.clone()) {
+                continue;
+            }
+            files.push(FileChange {
+                path,
+                lines: added.parse().ok().zip(deleted.parse().ok()),
+            });
+        }
+        commits.push(Commit {
+            hash: hash.trim().to_string(),
+            author: author.trim().to_string(),
+            committed_at: committed_at.trim().parse().unwrap_or_default(),
+            subject: subject.trim().to_string(),
+            body: body.trim().to_string(),
+            files,
+        });
+    }
+    commits
+}
+
+/// The new path of a numstat rename, written `old => new` or `dir/{old => new}/file`.
+fn renamed_path(path: &str) -> String {
+    let Some((before, after)) = path.split_once(" => ") else {
+        return path.to_string();
+    };
+    match (before.rfind('{'), after.find('}')) {
+        (Some(open), Some(close)) => {
+            let joined = format!(
+                "{}{}{}",
+                &before[..open],
+                &after[..close],
+                &after[close + 1..]
+            );
+            joined.replace("//", "/")
+        }
+        _ => after.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::process::Command;
+
+    use tempfile::tempdir;
+
+    use crate::db;
+
+    use super::*;
+
+    fn git(root: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .current_dir(root)
+            .args(["-c", "user.name=Dev", "-c", "user.email=dev@example.com"])
+            .args(["-c", "commit.gpgsign=false"])
+            .args(args)
+            .output()
+            .expect("git should run");
+        assert!(status.status.success(), "git {args:?} failed");
+    }
+
+    #[tokio::test]
+    async fn indexes_commits_and_returns_changes_to_target_files() {
+        let project = tempdir().expect("project temp dir should exist");
+        let root = project.path();
+        git(root, &["init", "-q"]);
+        std::fs::create_dir_all(root.join("src")).expect("src dir");
+        std::fs::write(root.join("src/cache.rs"), "pub fn evict() {}\n").expect("fixture");
+        std::fs::write(root.join("README.md"), "cache\n").expect("fixture");
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "Add cache eviction"]);
+        std::fs::rename(root.join("src/cache.rs"), root.join("src/lru.rs")).expect("rename");
+        git(root, &["add", "-A"]);
+        git(
+            root,
+            &[
+                "commit",
+                "-q",
+                "-m",
+                "Rename cache module to lru",
+                "-m",
+                "Eviction now lives with the LRU policy.",
+            ],
+        );
+
+        let db_dir = tempdir().expect("db temp dir should exist");
+        let pool = db::connect_pool(&db_dir.path().join("history.db"))
+            .await
+            .expect("sqlite pool should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should initialize");
+        let target = root.to_string_lossy().to_string();
+
+        let first = index_git_history(&pool, &target)
+            .await
+            .expect("history should index");
+        assert_eq!((first.commits, first.indexed_commits), (2, 2));
+        let again = index_git_history(&pool, &target)
+            .await
+            .expect("history should index");
+        assert_eq!(again.indexed_commits, 0);
+
+        let entries = query_git_history(
+            &pool,
+            &target,
+            "readme wording",
+            &["src/lru.rs".to_string()],
+            3,
+        )
+        .await
+        .expect("history should query");
+        assert_eq!(entries[0].file_path.as_deref(), Some("src/lru.rs"));
+        assert_eq!(entries[0].subject, "Rename cache module to lru");
+        assert_eq!(entries.len(), 3);
+        assert!(entries[1..]
+            .iter()
+            .all(|entry| entry.file_path.as_deref() != Some("src/lru.rs")));
+    }
+}
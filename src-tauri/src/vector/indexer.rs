@@ -1,15 +1,61 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
 
 use chrono::Utc;
 use sha2::{Digest, Sha256};
-use sqlx::SqlitePool;
+use sqlx::{SqliteConnection, SqlitePool};
 use uuid::Uuid;
 
+use crate::project_ignore;
+use crate::vector::chunker;
+use crate::vector::embedding::{self, EmbeddingProvider};
 use crate::vector::{IndexProjectResult, VECTOR_DIM};
 
 const MAX_LINES_PER_CHUNK: usize = 180;
+/// Chunks embedded per blocking call, so progress and cancellation are observed between
+/// calls even for providers that accept any batch size.
+const EMBED_PROGRESS_BATCH: usize = 256;
+
+/// Where an indexing run is, reported to an [`IndexControl`] observer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexPhase {
+    Scanning {
+        files_processed: u32,
+        total_files: u32,
+    },
+    Embedding {
+        chunks_embedded: u32,
+        total_chunks: u32,
+    },
+    Writing,
+}
+
+/// Progress observer and cancellation flag for an indexing run. Cancellation is checked
+/// between files and embedding batches; a cancelled run leaves the stored index as it was.
+#[derive(Clone, Default)]
+pub struct IndexControl {
+    pub cancelled: Arc<AtomicBool>,
+    pub on_progress: Option<Arc<dyn Fn(IndexPhase) + Send + Sync>>,
+}
+
+impl IndexControl {
+    fn report(&self, phase: IndexPhase) {
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(phase);
+        }
+    }
+
+    fn check_cancelled(&self) -> Result<(), String> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err("Indexing was cancelled".to_string());
+        }
+        Ok(())
+    }
+}
 
 #[derive(Debug, Clone)]
 struct ChunkRow {
@@ -21,50 +67,183 @@ struct ChunkRow {
     chunk_type: String,
     name: String,
     content: String,
-    vector: Vec<f32>,
+}
+
+#[derive(Debug, Clone)]
+struct FileState {
+    file_path: String,
+    content_hash: String,
+    modified_at: Option<i64>,
 }
 
 pub async fn index_project(
     pool: &SqlitePool,
     target_project: &str,
+) -> Result<IndexProjectResult, String> {
+    let provider = embedding::active_provider()?;
+    index_project_with(pool, target_project, provider).await
+}
+
+pub async fn index_project_with(
+    pool: &SqlitePool,
+    target_project: &str,
+    provider: Arc<dyn EmbeddingProvider>,
+) -> Result<IndexProjectResult, String> {
+    index_project_controlled(pool, target_project, provider, &IndexControl::default()).await
+}
+
+pub async fn index_project_controlled(
+    pool: &SqlitePool,
+    target_project: &str,
+    provider: Arc<dyn EmbeddingProvider>,
+    control: &IndexControl,
 ) -> Result<IndexProjectResult, String> {
     let target_root = normalize_project_root(target_project)?;
     let project_root_str = target_root.to_string_lossy().to_string();
-    let table_name = table_name_for_project(&target_root);
     let files = collect_source_files(&target_root)?;
 
+    // Vectors from another provider cannot sit next to new ones, so a stale index is rebuilt.
+    let full_rebuild = stale_index_reason(pool, &project_root_str, provider.as_ref())
+        .await?
+        .is_some();
+    let known = if full_rebuild {
+        HashMap::new()
+    } else {
+        load_file_states(pool, &project_root_str).await?
+    };
+
+    let mut present = HashSet::new();
+    let mut changed: Vec<FileState> = Vec::new();
+    let mut unchanged: Vec<FileState> = Vec::new();
+    let mut skipped = 0u32;
     let mut chunks: Vec<ChunkRow> = Vec::new();
-    for file in &files {
+    let total_files = files.len() as u32;
+    for (processed, file) in files.iter().enumerate() {
+        control.check_cancelled()?;
+        control.report(IndexPhase::Scanning {
+            files_processed: processed as u32,
+            total_files,
+        });
+        let relative_path = to_posix_relative(&target_root, file)?;
+        let modified_at = modified_millis(file);
+        let previous = known.get(&relative_path);
+        if modified_at.is_some() && previous.is_some_and(|state| state.modified_at == modified_at) {
+            present.insert(relative_path);
+            skipped += 1;
+            continue;
+        }
+
         let content = match fs::read_to_string(file) {
             Ok(data) => data,
             Err(_) => continue,
         };
-        let relative_path = to_posix_relative(&target_root, file)?;
+        let state = FileState {
+            file_path: relative_path.clone(),
+            content_hash: content_hash(&content),
+            modified_at,
+        };
+        present.insert(relative_path.clone());
+        if previous.is_some_and(|known| known.content_hash == state.content_hash) {
+            skipped += 1;
+            unchanged.push(state);
+            continue;
+        }
         chunks.extend(chunk_file(&project_root_str, &relative_path, &content));
+        changed.push(state);
+    }
+    let removed = known
+        .keys()
+        .filter(|path| !present.contains(*path))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let provider_id = provider.id();
+    let expected_dimension = provider.dimension();
+    control.report(IndexPhase::Scanning {
+        files_processed: total_files,
+        total_files,
+    });
+    let total_chunks = chunks.len() as u32;
+    let mut vectors: Vec<Vec<f32>> = Vec::with_capacity(chunks.len());
+    for batch in chunks.chunks(EMBED_PROGRESS_BATCH) {
+        control.check_cancelled()?;
+        control.report(IndexPhase::Embedding {
+            chunks_embedded: vectors.len() as u32,
+            total_chunks,
+        });
+        let texts = batch
+            .iter()
+            .map(|chunk| chunk.content.clone())
+            .collect::<Vec<_>>();
+        let provider = provider.clone();
+        let embedded =
+            tokio::task::spawn_blocking(move || embedding::embed_all(provider.as_ref(), &texts))
+                .await
+                .map_err(|error| format!("Embedding task panicked: {error}"))??;
+        if let (Some(first), Some(next)) = (vectors.first(), embedded.first()) {
+            if first.len() != next.len() {
+                return Err(format!(
+                    "Embedding provider '{provider_id}' returned {}-dimensional vectors after {}",
+                    next.len(),
+                    first.len()
+                ));
+            }
+        }
+        vectors.extend(embedded);
     }
+    control.check_cancelled()?;
+    control.report(IndexPhase::Writing);
 
     let mut transaction = pool
         .begin()
         .await
         .map_err(|error| format!("Failed to start vector indexing transaction: {error}"))?;
+    let table_name = assign_table_name(&mut transaction, &target_root).await?;
+
+    if full_rebuild {
+        for table in ["aop_vector_chunks", "aop_vector_files"] {
+            sqlx::query(&format!("DELETE FROM {table} WHERE project_root = ?"))
+                .bind(&project_root_str)
+                .execute(&mut *transaction)
+                .await
+                .map_err(|error| format!("Failed to clear stale vector index: {error}"))?;
+        }
+    }
 
-    sqlx::query("DELETE FROM aop_vector_chunks WHERE project_root = ?")
-        .bind(&project_root_str)
-        .execute(&mut *transaction)
-        .await
-        .map_err(|error| format!("Failed to clear old vector chunks: {error}"))?;
+    for file_path in changed
+        .iter()
+        .map(|state| &state.file_path)
+        .chain(removed.iter())
+    {
+        sqlx::query("DELETE FROM aop_vector_chunks WHERE project_root = ? AND file_path = ?")
+            .bind(&project_root_str)
+            .bind(file_path)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|error| {
+                format!("Failed to clear old vector chunks for '{file_path}': {error}")
+            })?;
+    }
+    for file_path in &removed {
+        sqlx::query("DELETE FROM aop_vector_files WHERE project_root = ? AND file_path = ?")
+            .bind(&project_root_str)
+            .bind(file_path)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|error| format!("Failed to forget removed file '{file_path}': {error}"))?;
+    }
 
-    for chunk in &chunks {
-        let vector_json = serde_json::to_string(&chunk.vector)
+    for (chunk, vector) in chunks.iter().zip(&vectors) {
+        let vector_json = serde_json::to_string(vector)
             .map_err(|error| format!("Failed to serialize vector embedding: {error}"))?;
 
         sqlx::query(
             r#"
             INSERT INTO aop_vector_chunks (
                 id, project_root, file_path, start_line, end_line,
-                chunk_type, name, content, vector_json, indexed_at
+                chunk_type, name, content, vector_json, dimension, indexed_at
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&chunk.id)
@@ -76,12 +255,81 @@ pub async fn index_project(
         .bind(&chunk.name)
         .bind(&chunk.content)
         .bind(vector_json)
+        .bind(vector.len() as i64)
         .bind(Utc::now().timestamp())
         .execute(&mut *transaction)
         .await
         .map_err(|error| format!("Failed to insert vector chunk '{}': {error}", chunk.id))?;
     }
 
+    for state in changed.iter().chain(unchanged.iter()) {
+        sqlx::query(
+            r#"
+            INSERT INTO aop_vector_files (
+                project_root, file_path, content_hash, modified_at, indexed_at
+            )
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(project_root, file_path) DO UPDATE SET
+                content_hash = excluded.content_hash,
+                modified_at = excluded.modified_at,
+                indexed_at = excluded.indexed_at
+            "#,
+        )
+        .bind(&project_root_str)
+        .bind(&state.file_path)
+        .bind(&state.content_hash)
+        .bind(state.modified_at)
+        .bind(Utc::now().timestamp())
+        .execute(&mut *transaction)
+        .await
+        .map_err(|error| {
+            format!(
+                "Failed to record content hash for '{}': {error}",
+                state.file_path
+            )
+        })?;
+    }
+
+    let (chunk_count, stored_dimension) = sqlx::query_as::<_, (i64, Option<i64>)>(
+        "SELECT COUNT(*), MAX(dimension) FROM aop_vector_chunks WHERE project_root = ?",
+    )
+    .bind(&project_root_str)
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(|error| format!("Failed to count vector chunks: {error}"))?;
+    let dimension = stored_dimension
+        .map(|value| value as usize)
+        .or(expected_dimension)
+        .unwrap_or(VECTOR_DIM);
+
+    sqlx::query(
+        r#"
+        INSERT INTO aop_vector_indexes (
+            project_root, embedding_provider, dimension, chunk_count, indexed_at, generation,
+            table_name
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(project_root) DO UPDATE SET
+            embedding_provider = excluded.embedding_provider,
+            dimension = excluded.dimension,
+            chunk_count = excluded.chunk_count,
+            indexed_at = excluded.indexed_at,
+            generation = aop_vector_indexes.generation + excluded.generation,
+            table_name = excluded.table_name
+        "#,
+    )
+    .bind(&project_root_str)
+    .bind(&provider_id)
+    .bind(dimension as i64)
+    .bind(chunk_count)
+    .bind(Utc::now().timestamp())
+    .bind(i64::from(
+        full_rebuild || !changed.is_empty() || !removed.is_empty(),
+    ))
+    .execute(&mut *transaction)
+    .await
+    .map_err(|error| format!("Failed to record vector index metadata: {error}"))?;
+
     transaction
         .commit()
         .await
@@ -90,25 +338,191 @@ pub async fn index_project(
     Ok(IndexProjectResult {
         target_project: project_root_str,
         table_name,
-        indexed_files: files.len() as u32,
-        indexed_chunks: chunks.len() as u32,
+        indexed_files: present.len() as u32,
+        indexed_chunks: chunk_count.max(0) as u32,
         index_path: "sqlite:aop_vector_chunks".to_string(),
+        embedding_provider: provider_id,
+        dimension: dimension as u32,
+        skipped,
+        updated: changed.len() as u32,
+        removed: removed.len() as u32,
     })
 }
 
-pub fn table_name_for_project(project_root: &Path) -> String {
+/// Stored hashes for indexed files. Files that only have chunks (indexed before hashes
+/// were recorded) get an empty hash, so they are re-embedded or removed.
+async fn load_file_states(
+    pool: &SqlitePool,
+    project_root: &str,
+) -> Result<HashMap<String, FileState>, String> {
+    let rows = sqlx::query_as::<_, (String, String, Option<i64>)>(
+        "SELECT file_path, content_hash, modified_at FROM aop_vector_files WHERE project_root = ?",
+    )
+    .bind(project_root)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to read indexed file hashes: {error}"))?;
+    let mut states = rows
+        .into_iter()
+        .map(|(file_path, content_hash, modified_at)| {
+            (
+                file_path.clone(),
+                FileState {
+                    file_path,
+                    content_hash,
+                    modified_at,
+                },
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    let chunk_paths = sqlx::query_scalar::<_, String>(
+        "SELECT DISTINCT file_path FROM aop_vector_chunks WHERE project_root = ?",
+    )
+    .bind(project_root)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to read indexed file paths: {error}"))?;
+    for file_path in chunk_paths {
+        states
+            .entry(file_path.clone())
+            .or_insert_with(|| FileState {
+                file_path,
+                content_hash: String::new(),
+                modified_at: None,
+            });
+    }
+    Ok(states)
+}
+
+fn modified_millis(path: &Path) -> Option<i64> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as i64)
+}
+
+fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Why the stored index for `project_root` cannot be searched with `provider`, or `None`
+/// when it can. Projects that were never indexed are not stale.
+pub async fn stale_index_reason(
+    pool: &SqlitePool,
+    project_root: &str,
+    provider: &dyn EmbeddingProvider,
+) -> Result<Option<String>, String> {
+    let chunk_dimensions = sqlx::query_scalar::<_, i64>(
+        "SELECT DISTINCT dimension FROM aop_vector_chunks WHERE project_root = ?",
+    )
+    .bind(project_root)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to read vector chunk dimensions: {error}"))?;
+    if chunk_dimensions.is_empty() {
+        return Ok(None);
+    }
+    if chunk_dimensions.len() > 1 {
+        return Ok(Some(format!(
+            "index mixes {} embedding dimensions",
+            chunk_dimensions.len()
+        )));
+    }
+
+    let metadata = sqlx::query_as::<_, (String, i64)>(
+        "SELECT embedding_provider, dimension FROM aop_vector_indexes WHERE project_root = ?",
+    )
+    .bind(project_root)
+    .fetch_optional(pool)
+    .await
+    .map_err(|error| format!("Failed to read vector index metadata: {error}"))?;
+    let Some((stored_provider, stored_dimension)) = metadata else {
+        return Ok(Some("index has no embedding metadata".to_string()));
+    };
+
+    let provider_id = provider.id();
+    if stored_provider != provider_id {
+        return Ok(Some(format!(
+            "index was built with '{stored_provider}', active provider is '{provider_id}'"
+        )));
+    }
+    if stored_dimension != chunk_dimensions[0] {
+        return Ok(Some(format!(
+            "index metadata says {stored_dimension} dimensions, chunks have {}",
+            chunk_dimensions[0]
+        )));
+    }
+    if let Some(expected) = provider.dimension() {
+        if expected as i64 != stored_dimension {
+            return Ok(Some(format!(
+                "index has {stored_dimension} dimensions, provider produces {expected}"
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// The table name recorded for the project, or else the shortest hash-derived name no
+/// other project holds. Six hash bytes separate almost every pair of roots; longer
+/// suffixes and then a counter cover collisions.
+async fn assign_table_name(
+    connection: &mut SqliteConnection,
+    project_root: &Path,
+) -> Result<String, String> {
+    let project_root_str = project_root.to_string_lossy().to_string();
+    let assigned = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT table_name FROM aop_vector_indexes WHERE project_root = ?",
+    )
+    .bind(&project_root_str)
+    .fetch_optional(&mut *connection)
+    .await
+    .map_err(|error| format!("Failed to read vector table name: {error}"))?;
+    if let Some(Some(name)) = assigned {
+        return Ok(name);
+    }
+
+    let full = table_name_for_project(project_root, 32);
+    let candidates = [6, 8, 12, 16]
+        .into_iter()
+        .map(|bytes| table_name_for_project(project_root, bytes))
+        .chain(std::iter::once(full.clone()))
+        .chain((2..100).map(|counter| format!("{full}_{counter}")));
+    for candidate in candidates {
+        let taken = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM aop_vector_indexes WHERE table_name = ? AND project_root != ?",
+        )
+        .bind(&candidate)
+        .bind(&project_root_str)
+        .fetch_one(&mut *connection)
+        .await
+        .map_err(|error| format!("Failed to check vector table name: {error}"))?;
+        if taken == 0 {
+            return Ok(candidate);
+        }
+    }
+    Err(format!(
+        "No free vector table name for project '{project_root_str}'"
+    ))
+}
+
+pub fn table_name_for_project(project_root: &Path, suffix_bytes: usize) -> String {
     let mut hasher = Sha256::new();
     hasher.update(project_root.to_string_lossy().as_bytes());
     let digest = hasher.finalize();
     let suffix = digest
         .iter()
-        .take(6)
+        .take(suffix_bytes)
         .map(|byte| format!("{byte:02x}"))
         .collect::<String>();
     format!("aop_vector_chunks_{suffix}")
 }
 
-fn normalize_project_root(target_project: &str) -> Result<PathBuf, String> {
+pub(crate) fn normalize_project_root(target_project: &str) -> Result<PathBuf, String> {
     if target_project.trim().is_empty() {
         return Err("targetProject is required".to_string());
     }
@@ -128,51 +542,13 @@ fn normalize_project_root(target_project: &str) -> Result<PathBuf, String> {
 }
 
 fn collect_source_files(root: &Path) -> Result<Vec<PathBuf>, String> {
-    let mut queue = VecDeque::from([root.to_path_buf()]);
-    let mut files = Vec::new();
-
-    while let Some(current_dir) = queue.pop_front() {
-        let entries = fs::read_dir(&current_dir).map_err(|error| {
-            format!(
-                "Failed to read directory '{}': {error}",
-                current_dir.display()
-            )
-        })?;
-
-        for entry in entries {
-            let entry =
-                entry.map_err(|error| format!("Failed to read directory entry: {error}"))?;
-            let path = entry.path();
-            let file_type = entry
-                .file_type()
-                .map_err(|error| format!("Failed to inspect '{}': {error}", path.display()))?;
-
-            if file_type.is_dir() {
-                let name = entry.file_name().to_string_lossy().to_string();
-                if should_skip_dir(&name) {
-                    continue;
-                }
-                queue.push_back(path);
-                continue;
-            }
-
-            if file_type.is_file() && is_supported_extension(&path) {
-                files.push(path);
-            }
-        }
-    }
-
-    Ok(files)
-}
-
-fn should_skip_dir(name: &str) -> bool {
-    matches!(
-        name,
-        ".git" | "node_modules" | "target" | "dist" | "build" | ".next" | ".turbo"
-    )
+    Ok(project_ignore::project_files(root)?
+        .into_iter()
+        .filter(|path| is_supported_extension(path))
+        .collect())
 }
 
-fn is_supported_extension(path: &Path) -> bool {
+pub(crate) fn is_supported_extension(path: &Path) -> bool {
     matches!(
         path.extension().and_then(|value| value.to_str()),
         Some("ts")
@@ -180,6 +556,10 @@ fn is_supported_extension(path: &Path) -> bool {
             | Some("js")
             | Some("jsx")
             | Some("rs")
+            | Some("py")
+            | Some("go")
+            | Some("mjs")
+            | Some("cjs")
             | Some("json")
             | Some("css")
             | Some("md")
@@ -208,6 +588,32 @@ fn chunk_file(project_root: &str, relative_path: &str, content: &str) -> Vec<Chu
         return Vec::new();
     }
 
+    let spans = chunker::syntax_spans(relative_path, content, MAX_LINES_PER_CHUNK)
+        .filter(|spans| !spans.is_empty());
+    if let Some(spans) = spans {
+        return spans
+            .into_iter()
+            .filter(|span| span.start_line <= lines.len())
+            .map(|span| {
+                let end_line = span.end_line.min(lines.len());
+                build_chunk(
+                    project_root,
+                    relative_path,
+                    span.start_line,
+                    end_line,
+                    &span.chunk_type,
+                    &span.name,
+                    &lines[span.start_line - 1..end_line].join("\n"),
+                )
+            })
+            .collect();
+    }
+    line_window_chunks(project_root, relative_path, &lines)
+}
+
+/// Fallback for files without a tree-sitter grammar: splits on lines that look like
+/// definitions and caps chunks at `MAX_LINES_PER_CHUNK` lines.
+fn line_window_chunks(project_root: &str, relative_path: &str, lines: &[&str]) -> Vec<ChunkRow> {
     let mut chunks: Vec<ChunkRow> = Vec::new();
     let mut current_start = 1usize;
     let mut current_type = "imports".to_string();
@@ -352,7 +758,6 @@ fn build_chunk(
         chunk_type: chunk_type.to_string(),
         name: name.to_string(),
         content: content.to_string(),
-        vector: embed_text(content),
     }
 }
 
@@ -379,3 +784,61 @@ pub fn embed_text(text: &str) -> Vec<f32> {
 
     vector
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::db;
+
+    use super::index_project;
+
+    #[tokio::test]
+    async fn reembeds_only_changed_files_and_drops_removed_ones() {
+        let project = tempdir().expect("project temp dir should exist");
+        std::fs::write(project.path().join("a.rs"), "pub fn alpha() {}\n")
+            .expect("fixture should be written");
+        std::fs::write(project.path().join("b.rs"), "pub fn beta() {}\n")
+            .expect("fixture should be written");
+
+        let db_dir = tempdir().expect("db temp dir should exist");
+        let pool = db::connect_pool(&db_dir.path().join("vector-incremental.db"))
+            .await
+            .expect("sqlite pool should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should initialize");
+        let target = project.path().to_string_lossy().to_string();
+
+        let first = index_project(&pool, &target)
+            .await
+            .expect("first index should succeed");
+        assert_eq!((first.updated, first.skipped, first.removed), (2, 0, 0));
+
+        let second = index_project(&pool, &target)
+            .await
+            .expect("second index should succeed");
+        assert_eq!((second.updated, second.skipped, second.removed), (0, 2, 0));
+        assert_eq!(second.indexed_chunks, first.indexed_chunks);
+
+        std::fs::write(
+            project.path().join("a.rs"),
+            "pub fn alpha() {}\n\npub fn gamma() {}\n",
+        )
+        .expect("fixture should be rewritten");
+        std::fs::remove_file(project.path().join("b.rs")).expect("fixture should be removed");
+
+        let third = index_project(&pool, &target)
+            .await
+            .expect("third index should succeed");
+        assert_eq!((third.updated, third.skipped, third.removed), (1, 0, 1));
+        let paths = sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT file_path FROM aop_vector_chunks WHERE project_root = ?",
+        )
+        .bind(&third.target_project)
+        .fetch_all(&pool)
+        .await
+        .expect("paths should load");
+        assert_eq!(paths, vec!["a.rs".to_string()]);
+    }
+}
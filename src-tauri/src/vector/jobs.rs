@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use sqlx::SqlitePool;
+use tauri::async_runtime::JoinHandle;
+use uuid::Uuid;
+
+use crate::vector::embedding;
+use crate::vector::indexer::{self, IndexControl, IndexPhase};
+use crate::vector::IndexJobStatus;
+
+/// Event carrying an [`IndexJobStatus`] whenever a job makes progress or finishes.
+pub const INDEX_JOB_EVENT: &str = "index-job-progress";
+/// Finished jobs kept for status queries; older ones are forgotten as new jobs start.
+const MAX_FINISHED_JOBS: usize = 16;
+
+pub type JobListener = Arc<dyn Fn(&IndexJobStatus) + Send + Sync>;
+
+/// Indexing runs started from the UI, which run in the background and report progress
+/// instead of blocking the invoking command.
+#[derive(Default)]
+pub struct IndexJobs {
+    jobs: HashMap<String, IndexJob>,
+}
+
+struct IndexJob {
+    status: Arc<Mutex<IndexJobStatus>>,
+    cancelled: Arc<AtomicBool>,
+    _task: JoinHandle<()>,
+}
+
+impl IndexJobs {
+    /// Starts indexing `target_project` in the background. A project that already has a
+    /// running job gets that job back instead of a second one.
+    pub fn start(
+        &mut self,
+        pool: SqlitePool,
+        target_project: &str,
+        listener: JobListener,
+    ) -> Result<IndexJobStatus, String> {
+        let root = indexer::normalize_project_root(target_project)?;
+        let key = root.to_string_lossy().to_string();
+        if let Some(running) = self
+            .jobs
+            .values()
+            .map(|job| snapshot(&job.status))
+            .find(|status| status.target_project == key && status.state == "running")
+        {
+            return Ok(running);
+        }
+        let provider = embedding::active_provider()?;
+        self.prune_finished();
+
+        let status = Arc::new(Mutex::new(IndexJobStatus {
+            job_id: Uuid::new_v4().to_string(),
+            target_project: key.clone(),
+            state: "running".to_string(),
+            phase: "scanning".to_string(),
+            files_processed: 0,
+            total_files: 0,
+            chunks_embedded: 0,
+            total_chunks: 0,
+            started_at: Utc::now().timestamp(),
+            finished_at: None,
+            result: None,
+            error: None,
+        }));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let control = IndexControl {
+            cancelled: cancelled.clone(),
+            on_progress: Some(progress_reporter(status.clone(), listener.clone())),
+        };
+
+        let job_status = status.clone();
+        let task = tauri::async_runtime::spawn(async move {
+            let result = indexer::index_project_controlled(&pool, &key, provider, &control).await;
+            let finished = {
+                let mut current = lock(&job_status);
+                current.finished_at = Some(Utc::now().timestamp());
+                match result {
+                    Ok(indexed) => {
+                        current.state = "completed".to_string();
+                        current.result = Some(indexed);
+                    }
+                    Err(_) if control.cancelled.load(Ordering::Relaxed) => {
+                        current.state = "cancelled".to_string();
+                    }
+                    Err(error) => {
+                        current.state = "failed".to_string();
+                        current.error = Some(error);
+                    }
+                }
+                current.clone()
+            };
+            listener(&finished);
+        });
+
+        let started = snapshot(&status);
+        self.jobs.insert(
+            started.job_id.clone(),
+            IndexJob {
+                status,
+                cancelled,
+                _task: task,
+            },
+        );
+        Ok(started)
+    }
+
+    pub fn status(&self, job_id: &str) -> Result<IndexJobStatus, String> {
+        self.jobs
+            .get(job_id.trim())
+            .map(|job| snapshot(&job.status))
+            .ok_or_else(|| format!("Index job '{}' not found", job_id.trim()))
+    }
+
+    /// Asks a running job to stop; it finishes as `cancelled` at its next checkpoint
+    /// without touching the stored index. Cancelling a finished job is a no-op.
+    pub fn cancel(&self, job_id: &str) -> Result<IndexJobStatus, String> {
+        let job = self
+            .jobs
+            .get(job_id.trim())
+            .ok_or_else(|| format!("Index job '{}' not found", job_id.trim()))?;
+        job.cancelled.store(true, Ordering::Relaxed);
+        Ok(snapshot(&job.status))
+    }
+
+    fn prune_finished(&mut self) {
+        let mut finished = self
+            .jobs
+            .iter()
+            .filter_map(|(id, job)| lock(&job.status).finished_at.map(|at| (at, id.clone())))
+            .collect::<Vec<_>>();
+        if finished.len() < MAX_FINISHED_JOBS {
+            return;
+        }
+        finished.sort();
+        for (_, id) in finished.iter().take(finished.len() + 1 - MAX_FINISHED_JOBS) {
+            self.jobs.remove(id);
+        }
+    }
+}
+
+/// Records each phase in the job status and notifies the listener on phase changes and
+/// about every percent of progress, so large projects do not flood the UI with events.
+fn progress_reporter(
+    status: Arc<Mutex<IndexJobStatus>>,
+    listener: JobListener,
+) -> Arc<dyn Fn(IndexPhase) + Send + Sync> {
+    Arc::new(move |phase| {
+        let updated = {
+            let mut current = lock(&status);
+            let previous_phase = current.phase.clone();
+            let previous_files = current.files_processed;
+            match phase {
+                IndexPhase::Scanning {
+                    files_processed,
+                    total_files,
+                } => {
+                    current.phase = "scanning".to_string();
+                    current.files_processed = files_processed;
+                    current.total_files = total_files;
+                }
+                IndexPhase::Embedding {
+                    chunks_embedded,
+                    total_chunks,
+                } => {
+                    current.phase = "embedding".to_string();
+                    current.chunks_embedded = chunks_embedded;
+                    current.total_chunks = total_chunks;
+                }
+                IndexPhase::Writing => {
+                    current.phase = "writing".to_string();
+                    current.chunks_embedded = current.total_chunks;
+                }
+            }
+            let step = (current.total_files / 100).max(1);
+            let notify = current.phase != previous_phase
+                || current.phase == "embedding"
+                || current.files_processed / step != previous_files / step
+                || current.files_processed == current.total_files;
+            notify.then(|| current.clone())
+        };
+        if let Some(updated) = updated {
+            listener(&updated);
+        }
+    })
+}
+
+fn lock(status: &Mutex<IndexJobStatus>) -> std::sync::MutexGuard<'_, IndexJobStatus> {
+    status
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn snapshot(status: &Mutex<IndexJobStatus>) -> IndexJobStatus {
+    lock(status).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tempfile::tempdir;
+
+    use crate::db;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn runs_index_job_in_background_and_reports_progress() {
+        let project = tempdir().expect("project temp dir should exist");
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            std::fs::write(project.path().join(name), "pub fn run() {}\n")
+                .expect("fixture should be written");
+        }
+        let db_dir = tempdir().expect("db temp dir should exist");
+        let pool = db::connect_pool(&db_dir.path().join("jobs.db"))
+            .await
+            .expect("sqlite pool should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should initialize");
+
+        let events = Arc::new(Mutex::new(Vec::<IndexJobStatus>::new()));
+        let recorded = events.clone();
+        let mut jobs = IndexJobs::default();
+        let started = jobs
+            .start(
+                pool,
+                &project.path().to_string_lossy(),
+                Arc::new(move |status| recorded.lock().expect("events lock").push(status.clone())),
+            )
+            .expect("job should start");
+        assert_eq!(started.state, "running");
+
+        let mut finished = started.clone();
+        for _ in 0..200 {
+            finished = jobs.status(&started.job_id).expect("job should be known");
+            if finished.state != "running" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(finished.state, "completed");
+        assert_eq!((finished.files_processed, finished.total_files), (3, 3));
+        assert_eq!(finished.result.map(|result| result.indexed_chunks), Some(3));
+
+        let events = events.lock().expect("events lock");
+        let phases = events
+            .iter()
+            .map(|status| status.phase.as_str())
+            .collect::<Vec<_>>();
+        assert!(phases.contains(&"scanning") && phases.contains(&"writing"));
+        assert_eq!(
+            events.last().map(|status| status.state.as_str()),
+            Some("completed")
+        );
+        assert!(jobs.cancel("missing").is_err());
+    }
+}
@@ -1,5 +1,13 @@
+pub mod ann;
+pub mod chunker;
+pub mod embedding;
+pub mod git_history;
 pub mod indexer;
+pub mod jobs;
+pub mod projects;
 pub mod search;
+pub mod symbols;
+pub mod watcher;
 
 use serde::{Deserialize, Serialize};
 
@@ -17,8 +25,123 @@ pub struct IndexProjectResult {
     pub target_project: String,
     pub table_name: String,
     pub indexed_files: u32,
+    /// Chunks in the index after this run, including those of skipped files.
     pub indexed_chunks: u32,
     pub index_path: String,
+    pub embedding_provider: String,
+    pub dimension: u32,
+    /// Files whose content hash was unchanged and were not re-embedded.
+    pub skipped: u32,
+    pub updated: u32,
+    /// Files that disappeared from the project and had their chunks deleted.
+    pub removed: u32,
+}
+
+/// A background indexing run and how far it got.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexJobStatus {
+    pub job_id: String,
+    pub target_project: String,
+    /// `running`, `completed`, `failed` or `cancelled`.
+    pub state: String,
+    /// `scanning`, `embedding` or `writing`.
+    pub phase: String,
+    pub files_processed: u32,
+    pub total_files: u32,
+    pub chunks_embedded: u32,
+    pub total_chunks: u32,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub result: Option<IndexProjectResult>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexJobInput {
+    pub job_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedProjectStats {
+    pub target_project: String,
+    /// `None` for indexes built before table names were recorded.
+    pub table_name: Option<String>,
+    pub embedding_provider: Option<String>,
+    pub dimension: Option<u32>,
+    pub chunk_count: u32,
+    pub file_count: u32,
+    pub indexed_at: Option<i64>,
+    /// Whether the project directory still exists; stale indexes can be deleted.
+    pub exists: bool,
+    pub watching: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteProjectIndexResult {
+    pub target_project: String,
+    pub deleted_chunks: u32,
+    pub deleted_files: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryGitHistoryInput {
+    pub target_project: String,
+    #[serde(default)]
+    pub query: String,
+    #[serde(default)]
+    pub target_files: Vec<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexGitHistoryResult {
+    pub target_project: String,
+    /// Commits in the indexed window.
+    pub commits: u32,
+    /// Commits embedded by this run.
+    pub indexed_commits: u32,
+    /// Commits dropped because they left the window or were rewritten.
+    pub removed_commits: u32,
+    pub embedding_provider: String,
+}
+
+/// A commit, or one file it changed, from the project's recent history.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHistoryEntry {
+    pub commit_hash: String,
+    /// `None` for the commit as a whole.
+    pub file_path: Option<String>,
+    pub author: String,
+    pub committed_at: i64,
+    pub subject: String,
+    /// Commit body and changed files, or the file's line counts.
+    pub summary: String,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexWatchInput {
+    pub target_project: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexWatchStatus {
+    pub target_project: String,
+    pub watching: bool,
+    pub started_at: i64,
+    pub last_indexed_at: Option<i64>,
+    /// Error from the most recent reindex pass, cleared by the next successful one.
+    pub last_error: Option<String>,
+    pub reindex_count: u32,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -27,6 +150,21 @@ pub struct QueryCodebaseInput {
     pub target_project: String,
     pub query: String,
     pub top_k: Option<u32>,
+    #[serde(flatten)]
+    pub filters: QueryFilters,
+}
+
+/// Restricts which chunks a codebase query may return.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryFilters {
+    /// Glob over project-relative paths, e.g. `src/components/**`.
+    pub path_glob: Option<String>,
+    /// Language name (`typescript`) or file extension (`tsx`, `.rs`).
+    pub language: Option<String>,
+    pub chunk_type: Option<String>,
+    #[serde(default)]
+    pub exclude_tests: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -41,3 +179,34 @@ pub struct ContextChunk {
     pub content: String,
     pub score: f32,
 }
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSymbolsInput {
+    pub target_project: String,
+    pub symbol: String,
+    /// Maximum number of references; definitions are always returned in full.
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolLocation {
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Chunk kind of the definition, or of the chunk enclosing a reference.
+    pub kind: String,
+    pub name: String,
+    /// Full definition source, or the referencing line.
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolSearchResult {
+    pub target_project: String,
+    pub symbol: String,
+    pub definitions: Vec<SymbolLocation>,
+    pub references: Vec<SymbolLocation>,
+}
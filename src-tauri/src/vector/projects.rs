@@ -0,0 +1,203 @@
+use std::path::Path;
+
+use sqlx::SqlitePool;
+
+use crate::vector::{DeleteProjectIndexResult, IndexedProjectStats};
+
+use super::ann;
+use super::indexer;
+
+type StatsRow = (
+    String,
+    Option<String>,
+    Option<String>,
+    Option<i64>,
+    i64,
+    i64,
+    Option<i64>,
+);
+
+// Legacy indexes have chunks but no metadata row, so roots come from both tables.
+const STATS_QUERY: &str = r#"
+    SELECT roots.project_root, meta.table_name, meta.embedding_provider, meta.dimension,
+           (SELECT COUNT(*) FROM aop_vector_chunks AS chunk
+            WHERE chunk.project_root = roots.project_root) AS chunk_count,
+           (SELECT COUNT(DISTINCT chunk.file_path) FROM aop_vector_chunks AS chunk
+            WHERE chunk.project_root = roots.project_root) AS file_count,
+           COALESCE(meta.indexed_at,
+                    (SELECT MAX(chunk.indexed_at) FROM aop_vector_chunks AS chunk
+                     WHERE chunk.project_root = roots.project_root)) AS indexed_at
+    FROM (
+        SELECT project_root FROM aop_vector_indexes
+        UNION
+        SELECT DISTINCT project_root FROM aop_vector_chunks
+    ) AS roots
+    LEFT JOIN aop_vector_indexes AS meta ON meta.project_root = roots.project_root
+"#;
+
+/// Every project with a vector index, most recently indexed first.
+pub async fn list_indexed_projects(pool: &SqlitePool) -> Result<Vec<IndexedProjectStats>, String> {
+    let rows = sqlx::query_as::<_, StatsRow>(&format!(
+        "{STATS_QUERY} ORDER BY indexed_at DESC, roots.project_root"
+    ))
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to list indexed projects: {error}"))?;
+    Ok(rows.into_iter().map(stats_from_row).collect())
+}
+
+pub async fn project_index_stats(
+    pool: &SqlitePool,
+    target_project: &str,
+) -> Result<IndexedProjectStats, String> {
+    let project_root = resolve_indexed_root(target_project)?;
+    sqlx::query_as::<_, StatsRow>(&format!("{STATS_QUERY} WHERE roots.project_root = ?"))
+        .bind(&project_root)
+        .fetch_optional(pool)
+        .await
+        .map_err(|error| format!("Failed to read project index stats: {error}"))?
+        .map(stats_from_row)
+        .ok_or_else(|| format!("Project '{project_root}' is not indexed"))
+}
+
+/// Drops the project's chunks, file hashes, metadata, git history and ANN graph. Works for projects
+/// whose directory no longer exists.
+pub async fn delete_project_index(
+    pool: &SqlitePool,
+    target_project: &str,
+) -> Result<DeleteProjectIndexResult, String> {
+    let project_root = resolve_indexed_root(target_project)?;
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|error| format!("Failed to start index deletion transaction: {error}"))?;
+
+    let mut deleted = Vec::with_capacity(4);
+    for table in [
+        "aop_vector_chunks",
+        "aop_vector_files",
+        "aop_vector_indexes",
+        "aop_vector_git_history",
+    ] {
+        let result = sqlx::query(&format!("DELETE FROM {table} WHERE project_root = ?"))
+            .bind(&project_root)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|error| format!("Failed to delete rows from {table}: {error}"))?;
+        deleted.push(result.rows_affected());
+    }
+    transaction
+        .commit()
+        .await
+        .map_err(|error| format!("Failed to commit index deletion: {error}"))?;
+    ann::forget(&project_root).await;
+
+    Ok(DeleteProjectIndexResult {
+        target_project: project_root,
+        deleted_chunks: deleted[0] as u32,
+        deleted_files: deleted[1] as u32,
+    })
+}
+
+/// Canonical root when the directory still exists, else the path as given, which is how
+/// indexes of moved or deleted projects are addressed.
+fn resolve_indexed_root(target_project: &str) -> Result<String, String> {
+    if target_project.trim().is_empty() {
+        return Err("targetProject is required".to_string());
+    }
+    Ok(indexer::normalize_project_root(target_project)
+        .map(|root| root.to_string_lossy().to_string())
+        .unwrap_or_else(|_| target_project.trim().to_string()))
+}
+
+fn stats_from_row(row: StatsRow) -> IndexedProjectStats {
+    let (target_project, table_name, embedding_provider, dimension, chunks, files, indexed_at) =
+        row;
+    IndexedProjectStats {
+        exists: Path::new(&target_project).is_dir(),
+        target_project,
+        table_name,
+        embedding_provider,
+        dimension: dimension.map(|value| value.max(0) as u32),
+        chunk_count: chunks.max(0) as u32,
+        file_count: files.max(0) as u32,
+        indexed_at,
+        watching: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::db;
+    use crate::vector::indexer::{index_project, table_name_for_project};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn lists_and_deletes_projects_with_distinct_table_names() {
+        let first = tempdir().expect("project temp dir should exist");
+        let second = tempdir().expect("project temp dir should exist");
+        for project in [&first, &second] {
+            std::fs::write(project.path().join("lib.rs"), "pub fn run() {}\n")
+                .expect("fixture should be written");
+        }
+
+        let db_dir = tempdir().expect("db temp dir should exist");
+        let pool = db::connect_pool(&db_dir.path().join("projects.db"))
+            .await
+            .expect("sqlite pool should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should initialize");
+
+        // Another root already holds the short name the second project would get.
+        let second_root = second.path().canonicalize().expect("root should resolve");
+        sqlx::query(
+            "INSERT INTO aop_vector_indexes \
+             (project_root, embedding_provider, dimension, chunk_count, indexed_at, table_name) \
+             VALUES ('/gone/project', 'hash/256', 256, 0, 0, ?)",
+        )
+        .bind(table_name_for_project(&second_root, 6))
+        .execute(&pool)
+        .await
+        .expect("colliding row should insert");
+
+        index_project(&pool, &first.path().to_string_lossy())
+            .await
+            .expect("indexing should succeed");
+        let indexed = index_project(&pool, &second.path().to_string_lossy())
+            .await
+            .expect("indexing should succeed");
+        assert_eq!(indexed.table_name, table_name_for_project(&second_root, 8));
+
+        let projects = list_indexed_projects(&pool)
+            .await
+            .expect("listing should succeed");
+        assert_eq!(projects.len(), 3);
+        let stats = project_index_stats(&pool, &second.path().to_string_lossy())
+            .await
+            .expect("stats should load");
+        assert_eq!((stats.chunk_count, stats.file_count), (1, 1));
+        assert!(stats.exists);
+
+        let stale = projects
+            .iter()
+            .find(|project| !project.exists)
+            .expect("the missing project should be listed");
+        delete_project_index(&pool, &stale.target_project)
+            .await
+            .expect("stale index should delete");
+        let deleted = delete_project_index(&pool, &second.path().to_string_lossy())
+            .await
+            .expect("index should delete");
+        assert_eq!((deleted.deleted_chunks, deleted.deleted_files), (1, 1));
+
+        let remaining = list_indexed_projects(&pool)
+            .await
+            .expect("listing should succeed");
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].file_count, 1);
+    }
+}
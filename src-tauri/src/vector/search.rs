@@ -1,12 +1,22 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use glob::{MatchOptions, Pattern};
 use sqlx::SqlitePool;
 
-use crate::vector::ContextChunk;
+use crate::vector::{ContextChunk, QueryFilters};
 
-use super::indexer::embed_text;
+use super::ann::{self, AnnSignature};
+use super::embedding::{self, EmbeddingProvider};
+use super::indexer;
+
+/// Standard reciprocal-rank fusion constant; dampens the weight of the top few ranks.
+const RRF_K: f32 = 60.0;
+const MIN_FUSION_CANDIDATES: usize = 20;
+const FILTERED_ANN_OVERSAMPLING: usize = 8;
 
 #[derive(Debug, Clone, sqlx::FromRow)]
 struct StoredChunk {
@@ -25,6 +35,7 @@ pub async fn query_codebase(
     target_project: &str,
     query: &str,
     top_k: u32,
+    filters: &QueryFilters,
 ) -> Result<Vec<ContextChunk>, String> {
     if query.trim().is_empty() {
         return Err("query is required".to_string());
@@ -32,23 +43,81 @@ pub async fn query_codebase(
 
     let project_root = normalize_project_root(target_project)?;
     let project_root_str = project_root.to_string_lossy().to_string();
-    let query_vector = embed_text(query);
+    let provider = embedding::active_provider()?;
+    query_codebase_with(pool, &project_root_str, query, top_k, filters, provider).await
+}
+
+async fn query_codebase_with(
+    pool: &SqlitePool,
+    project_root_str: &str,
+    query: &str,
+    top_k: u32,
+    filters: &QueryFilters,
+    provider: Arc<dyn EmbeddingProvider>,
+) -> Result<Vec<ContextChunk>, String> {
+    let filter = ChunkFilter::compile(filters)?;
+    // Vectors from another provider or dimension cannot be compared with the query.
+    let stale = indexer::stale_index_reason(pool, project_root_str, provider.as_ref()).await?;
+    if stale.is_some() {
+        indexer::index_project_with(pool, project_root_str, provider.clone()).await?;
+    }
+
+    let query_text = vec![query.to_string()];
+    let query_vector =
+        tokio::task::spawn_blocking(move || embedding::embed_all(provider.as_ref(), &query_text))
+            .await
+            .map_err(|error| format!("Embedding task panicked: {error}"))??
+            .pop()
+            .unwrap_or_default();
     let limit = usize::try_from(top_k.max(1)).unwrap_or(5);
+    let candidates = (limit * 4).max(MIN_FUSION_CANDIDATES);
+    let tokens = query_tokens(query);
+    let keyword_ids = keyword_ranking(pool, project_root_str, &tokens, candidates).await?;
 
-    let rows = sqlx::query_as::<_, StoredChunk>(
-        r#"
-        SELECT id, file_path, start_line, end_line, chunk_type, name, content, vector_json
-        FROM aop_vector_chunks
-        WHERE project_root = ?
-        "#,
-    )
-    .bind(&project_root_str)
-    .fetch_all(pool)
-    .await
-    .map_err(|error| format!("Failed to query vector chunks from SQLite: {error}"))?;
+    let signature = ann_signature(pool, project_root_str).await?;
+    let rows = match signature {
+        // Large indexes go through the ANN graph and only load the candidate rows.
+        Some((signature, chunk_count)) if chunk_count >= ann::ANN_MIN_CHUNKS => {
+            // Filters drop neighbours after the graph search, so ask for more of them.
+            let neighbours = if filter.is_empty() {
+                candidates
+            } else {
+                candidates * FILTERED_ANN_OVERSAMPLING
+            };
+            let nearest = ann::nearest(
+                pool,
+                project_root_str,
+                &signature,
+                &query_vector,
+                neighbours,
+            )
+            .await?;
+            let mut ids = nearest.into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+            for id in &keyword_ids {
+                if !ids.contains(id) {
+                    ids.push(id.clone());
+                }
+            }
+            load_chunks_by_id(pool, project_root_str, &ids).await?
+        }
+        _ => sqlx::query_as::<_, StoredChunk>(
+            r#"
+                SELECT id, file_path, start_line, end_line, chunk_type, name, content, vector_json
+                FROM aop_vector_chunks
+                WHERE project_root = ?
+                "#,
+        )
+        .bind(project_root_str)
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to query vector chunks from SQLite: {error}"))?,
+    };
 
     let mut scored = Vec::new();
     for row in rows {
+        if !filter.accepts(&row.file_path, &row.chunk_type, &row.name) {
+            continue;
+        }
         let vector = serde_json::from_str::<Vec<f32>>(&row.vector_json).map_err(|error| {
             format!(
                 "Failed to decode stored embedding for chunk '{}': {error}",
@@ -70,9 +139,271 @@ pub async fn query_codebase(
     }
 
     scored.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
-    scored.truncate(limit);
+    Ok(fuse_rankings(
+        scored,
+        &keyword_ids,
+        &tokens,
+        candidates,
+        limit,
+    ))
+}
+
+/// The ANN signature and chunk count recorded for the project's index, if it has one.
+async fn ann_signature(
+    pool: &SqlitePool,
+    project_root: &str,
+) -> Result<Option<(AnnSignature, usize)>, String> {
+    let metadata = sqlx::query_as::<_, (String, i64, i64, i64)>(
+        r#"
+        SELECT embedding_provider, dimension, chunk_count, generation
+        FROM aop_vector_indexes
+        WHERE project_root = ?
+        "#,
+    )
+    .bind(project_root)
+    .fetch_optional(pool)
+    .await
+    .map_err(|error| format!("Failed to read vector index metadata: {error}"))?;
+    Ok(
+        metadata.map(|(embedding_provider, dimension, chunk_count, generation)| {
+            (
+                AnnSignature {
+                    embedding_provider,
+                    dimension: dimension.max(0) as usize,
+                    generation,
+                },
+                chunk_count.max(0) as usize,
+            )
+        }),
+    )
+}
+
+async fn load_chunks_by_id(
+    pool: &SqlitePool,
+    project_root: &str,
+    ids: &[String],
+) -> Result<Vec<StoredChunk>, String> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let sql = format!(
+        "SELECT id, file_path, start_line, end_line, chunk_type, name, content, vector_json \
+         FROM aop_vector_chunks WHERE project_root = ? AND id IN ({placeholders})"
+    );
+    let mut query = sqlx::query_as::<_, StoredChunk>(&sql).bind(project_root);
+    for id in ids {
+        query = query.bind(id);
+    }
+    query
+        .fetch_all(pool)
+        .await
+        .map_err(|error| format!("Failed to query vector chunks from SQLite: {error}"))
+}
+
+/// [`QueryFilters`] with the glob parsed and the language resolved to extensions.
+struct ChunkFilter {
+    path: Option<Pattern>,
+    extensions: Option<Vec<String>>,
+    chunk_type: Option<String>,
+    exclude_tests: bool,
+}
+
+impl ChunkFilter {
+    fn compile(filters: &QueryFilters) -> Result<Self, String> {
+        let non_empty = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let path = non_empty(&filters.path_glob)
+            .map(|glob| {
+                Pattern::new(glob.trim_start_matches("./"))
+                    .map_err(|error| format!("Invalid pathGlob '{glob}': {error}"))
+            })
+            .transpose()?;
+        Ok(Self {
+            path,
+            extensions: non_empty(&filters.language).map(|language| language_extensions(&language)),
+            chunk_type: non_empty(&filters.chunk_type),
+            exclude_tests: filters.exclude_tests,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.path.is_none()
+            && self.extensions.is_none()
+            && self.chunk_type.is_none()
+            && !self.exclude_tests
+    }
+
+    fn accepts(&self, file_path: &str, chunk_type: &str, name: &str) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        if self
+            .path
+            .as_ref()
+            .is_some_and(|pattern| !pattern.matches_with(file_path, options))
+        {
+            return false;
+        }
+        if let Some(extensions) = &self.extensions {
+            let extension = Path::new(file_path)
+                .extension()
+                .and_then(|value| value.to_str())
+                .map(str::to_ascii_lowercase)
+                .unwrap_or_default();
+            if !extensions.contains(&extension) {
+                return false;
+            }
+        }
+        if self
+            .chunk_type
+            .as_ref()
+            .is_some_and(|kind| !kind.eq_ignore_ascii_case(chunk_type))
+        {
+            return false;
+        }
+        !(self.exclude_tests && is_test_chunk(file_path, name))
+    }
+}
+
+fn language_extensions(language: &str) -> Vec<String> {
+    let language = language.trim_start_matches('.').to_ascii_lowercase();
+    let extensions: &[&str] = match language.as_str() {
+        "typescript" => &["ts", "tsx"],
+        "javascript" => &["js", "jsx", "mjs", "cjs"],
+        "rust" => &["rs"],
+        "python" => &["py"],
+        "golang" => &["go"],
+        "markdown" => &["md"],
+        _ => return vec![language],
+    };
+    extensions.iter().map(|value| value.to_string()).collect()
+}
+
+/// Test files by the usual naming conventions, plus Rust `mod tests` blocks.
+fn is_test_chunk(file_path: &str, name: &str) -> bool {
+    let lower = file_path.to_ascii_lowercase();
+    let file_name = lower.rsplit('/').next().unwrap_or_default();
+    let in_test_dir = lower
+        .split('/')
+        .rev()
+        .skip(1)
+        .any(|segment| matches!(segment, "test" | "tests" | "__tests__" | "spec" | "e2e"));
+    in_test_dir
+        || file_name.contains(".test.")
+        || file_name.contains(".spec.")
+        || file_name.starts_with("test_")
+        || file_name
+            .rsplit_once('.')
+            .is_some_and(|(stem, _)| stem.ends_with("_test"))
+        || name == "tests"
+        || name.starts_with("tests.")
+}
+
+/// Chunk ids ordered by BM25 over the FTS5 keyword index. Chunk names weigh most, so the
+/// chunk that defines a symbol outranks the ones that only mention it.
+async fn keyword_ranking(
+    pool: &SqlitePool,
+    project_root: &str,
+    tokens: &[String],
+    limit: usize,
+) -> Result<Vec<String>, String> {
+    if tokens.is_empty() {
+        return Ok(Vec::new());
+    }
+    // Quoting every token keeps FTS5 operators in the query from being interpreted.
+    let match_expression = tokens
+        .iter()
+        .map(|token| format!("\"{token}\""))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT chunk.id
+        FROM aop_vector_chunks_fts
+        JOIN aop_vector_chunks AS chunk ON chunk.rowid = aop_vector_chunks_fts.rowid
+        WHERE aop_vector_chunks_fts MATCH ? AND chunk.project_root = ?
+        ORDER BY bm25(aop_vector_chunks_fts, 10.0, 2.0, 1.0)
+        LIMIT ?
+        "#,
+    )
+    .bind(match_expression)
+    .bind(project_root)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to query keyword index: {error}"))
+}
+
+/// Lower-cased identifier-like tokens of the query, without duplicates.
+fn query_tokens(query: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    for token in query
+        .split(|ch: char| !ch.is_alphanumeric() && ch != '_')
+        .filter(|token| token.chars().count() >= 2)
+    {
+        let token = token.to_lowercase();
+        if !tokens.contains(&token) {
+            tokens.push(token);
+        }
+    }
+    tokens
+}
+
+/// Reciprocal-rank fusion of the vector ranking (`by_vector`, best first), the keyword
+/// ranking and exact symbol matches: a chunk named after a query token counts as a
+/// first-place hit in a third ranking. The fused value replaces the cosine score.
+fn fuse_rankings(
+    by_vector: Vec<ContextChunk>,
+    keyword_ids: &[String],
+    tokens: &[String],
+    candidates: usize,
+    limit: usize,
+) -> Vec<ContextChunk> {
+    let mut fused: HashMap<String, f32> = HashMap::new();
+    for (rank, chunk) in by_vector.iter().take(candidates).enumerate() {
+        *fused.entry(chunk.id.clone()).or_default() += rrf(rank);
+    }
+    for (rank, id) in keyword_ids.iter().enumerate() {
+        *fused.entry(id.clone()).or_default() += rrf(rank);
+    }
+    for chunk in &by_vector {
+        if names_symbol(&chunk.name, tokens) {
+            *fused.entry(chunk.id.clone()).or_default() += rrf(0);
+        }
+    }
+
+    let mut ranked = by_vector
+        .into_iter()
+        .filter_map(|mut chunk| {
+            let score = *fused.get(&chunk.id)?;
+            chunk.score = score;
+            Some(chunk)
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    ranked.truncate(limit);
+    ranked
+}
+
+/// `Store.load` and `fmt::Display for Point` count as naming `load` and `point`.
+fn names_symbol(name: &str, tokens: &[String]) -> bool {
+    let name = name.to_lowercase();
+    let symbol = name
+        .rsplit(|ch: char| !ch.is_alphanumeric() && ch != '_')
+        .next()
+        .unwrap_or_default();
+    !symbol.is_empty() && tokens.iter().any(|token| token == symbol)
+}
 
-    Ok(scored)
+fn rrf(rank: usize) -> f32 {
+    1.0 / (RRF_K + rank as f32 + 1.0)
 }
 
 fn normalize_project_root(target_project: &str) -> Result<PathBuf, String> {
@@ -94,7 +425,7 @@ fn normalize_project_root(target_project: &str) -> Result<PathBuf, String> {
     Ok(normalized)
 }
 
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     if a.is_empty() || b.is_empty() || a.len() != b.len() {
         return 0.0;
     }
@@ -119,6 +450,8 @@ mod tests {
     use crate::db;
     use crate::vector::indexer::index_project;
 
+    use crate::vector::QueryFilters;
+
     use super::query_codebase;
 
     #[tokio::test]
@@ -152,6 +485,7 @@ mod tests {
             &project_temp.path().to_string_lossy(),
             "session loading state",
             5,
+            &QueryFilters::default(),
         )
         .await
         .expect("query should succeed");
@@ -161,4 +495,172 @@ mod tests {
             .iter()
             .any(|chunk| chunk.file_path.ends_with("session.ts")));
     }
+
+    #[tokio::test]
+    async fn reindexes_when_stored_dimensions_do_not_match_provider() {
+        let project_temp = tempdir().expect("project temp dir should exist");
+        std::fs::write(
+            project_temp.path().join("cache.rs"),
+            "pub fn evict(cache: &mut Cache, entries: usize) {}\n",
+        )
+        .expect("fixture should be written");
+
+        let db_dir = tempdir().expect("db temp dir should exist");
+        let pool = db::connect_pool(&db_dir.path().join("vector-stale.db"))
+            .await
+            .expect("sqlite pool should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should initialize");
+
+        let indexed = index_project(&pool, &project_temp.path().to_string_lossy())
+            .await
+            .expect("indexing should succeed");
+        assert_eq!(indexed.dimension as usize, crate::vector::VECTOR_DIM);
+
+        // Simulate vectors left behind by a provider with another dimension.
+        sqlx::query("UPDATE aop_vector_chunks SET vector_json = '[1.0,0.0,0.0]', dimension = 3")
+            .execute(&pool)
+            .await
+            .expect("chunks should update");
+        sqlx::query("UPDATE aop_vector_indexes SET dimension = 3")
+            .execute(&pool)
+            .await
+            .expect("metadata should update");
+
+        let chunks = query_codebase(
+            &pool,
+            &project_temp.path().to_string_lossy(),
+            "evict cache entries",
+            3,
+            &QueryFilters::default(),
+        )
+        .await
+        .expect("query should reindex and succeed");
+        assert!(chunks.iter().any(|chunk| chunk.score > 0.0));
+
+        let dimensions =
+            sqlx::query_scalar::<_, i64>("SELECT DISTINCT dimension FROM aop_vector_chunks")
+                .fetch_all(&pool)
+                .await
+                .expect("dimensions should load");
+        assert_eq!(dimensions, vec![crate::vector::VECTOR_DIM as i64]);
+    }
+
+    #[tokio::test]
+    async fn ranks_the_chunk_defining_a_queried_symbol_first() {
+        let project_temp = tempdir().expect("project temp dir should exist");
+        std::fs::write(
+            project_temp.path().join("billing.ts"),
+            "export function computeInvoiceTotal(lines: Line[]) {\n  return lines.reduce((sum, line) => sum + line.amount, 0)\n}\n",
+        )
+        .expect("fixture should be written");
+        std::fs::write(
+            project_temp.path().join("checkout.ts"),
+            "export function checkout(cart: Cart) {\n  const total = computeInvoiceTotal(cart.lines)\n  const again = computeInvoiceTotal(cart.lines)\n  return { total, again }\n}\n",
+        )
+        .expect("fixture should be written");
+
+        let db_dir = tempdir().expect("db temp dir should exist");
+        let pool = db::connect_pool(&db_dir.path().join("vector-hybrid.db"))
+            .await
+            .expect("sqlite pool should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should initialize");
+        index_project(&pool, &project_temp.path().to_string_lossy())
+            .await
+            .expect("indexing should succeed");
+
+        let chunks = query_codebase(
+            &pool,
+            &project_temp.path().to_string_lossy(),
+            "where is computeInvoiceTotal defined",
+            3,
+            &QueryFilters::default(),
+        )
+        .await
+        .expect("query should succeed");
+        assert_eq!(chunks[0].name, "computeInvoiceTotal");
+        assert_eq!(chunks[0].file_path, "billing.ts");
+    }
+
+    #[tokio::test]
+    async fn applies_path_language_chunk_type_and_test_filters() {
+        let project_temp = tempdir().expect("project temp dir should exist");
+        let write = |relative: &str, content: &str| {
+            let path = project_temp.path().join(relative);
+            std::fs::create_dir_all(path.parent().expect("parent")).expect("dirs");
+            std::fs::write(path, content).expect("fixture should be written");
+        };
+        write(
+            "src/components/button.tsx",
+            "export function renderButton(label: string) {\n  return label\n}\n",
+        );
+        write(
+            "src/components/button.test.tsx",
+            "export function testRenderButton() {\n  return renderButton('label')\n}\n",
+        );
+        write(
+            "server/button.rs",
+            "pub struct ButtonLabel {\n    label: String,\n}\n",
+        );
+
+        let db_dir = tempdir().expect("db temp dir should exist");
+        let pool = db::connect_pool(&db_dir.path().join("vector-filters.db"))
+            .await
+            .expect("sqlite pool should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should initialize");
+        let root = project_temp.path().to_string_lossy().to_string();
+        index_project(&pool, &root)
+            .await
+            .expect("indexing should succeed");
+        let files = |filters: QueryFilters| {
+            let pool = pool.clone();
+            let root = root.clone();
+            async move {
+                let mut files = query_codebase(&pool, &root, "button label", 10, &filters)
+                    .await
+                    .expect("query should succeed")
+                    .into_iter()
+                    .map(|chunk| chunk.file_path)
+                    .collect::<Vec<_>>();
+                files.sort();
+                files
+            }
+        };
+
+        assert_eq!(files(QueryFilters::default()).await.len(), 3);
+        assert_eq!(
+            files(QueryFilters {
+                path_glob: Some("src/components/**".to_string()),
+                exclude_tests: true,
+                ..QueryFilters::default()
+            })
+            .await,
+            vec!["src/components/button.tsx"]
+        );
+        assert_eq!(
+            files(QueryFilters {
+                language: Some("rust".to_string()),
+                ..QueryFilters::default()
+            })
+            .await,
+            vec!["server/button.rs"]
+        );
+        assert_eq!(
+            files(QueryFilters {
+                chunk_type: Some("function".to_string()),
+                language: Some(".tsx".to_string()),
+                ..QueryFilters::default()
+            })
+            .await,
+            vec![
+                "src/components/button.test.tsx",
+                "src/components/button.tsx"
+            ]
+        );
+    }
 }
@@ -0,0 +1,228 @@
+use std::collections::HashSet;
+
+use sqlx::SqlitePool;
+
+use crate::vector::{SymbolLocation, SymbolSearchResult};
+
+use super::indexer;
+
+const DEFAULT_REFERENCE_LIMIT: usize = 50;
+/// Chunk kinds that group several definitions and never define a symbol themselves.
+const NON_DEFINITION_KINDS: &[&str] = &["imports", "module", "members"];
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct SymbolChunk {
+    file_path: String,
+    start_line: i64,
+    end_line: i64,
+    chunk_type: String,
+    name: String,
+    content: String,
+}
+
+/// Definitions of `symbol` and the lines that reference it, from the syntax-aware chunk
+/// index. `symbol` is a bare name (`load`) or qualified by its container (`Store.load`).
+pub async fn search_symbols(
+    pool: &SqlitePool,
+    target_project: &str,
+    symbol: &str,
+    limit: Option<u32>,
+) -> Result<SymbolSearchResult, String> {
+    let symbol = symbol.trim();
+    let bare = bare_symbol(symbol);
+    if bare.is_empty() {
+        return Err("symbol is required".to_string());
+    }
+
+    let project_root = indexer::normalize_project_root(target_project)?;
+    let project_root_str = project_root.to_string_lossy().to_string();
+    // Lookups only read chunk boundaries and text, so an index built with another
+    // embedding provider serves as is.
+    let indexed_chunks = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM aop_vector_chunks WHERE project_root = ?",
+    )
+    .bind(&project_root_str)
+    .fetch_one(pool)
+    .await
+    .map_err(|error| format!("Failed to count vector chunks: {error}"))?;
+    if indexed_chunks == 0 {
+        indexer::index_project(pool, &project_root_str).await?;
+    }
+
+    // Chunk content is tokenized like identifiers, so this finds every chunk that
+    // mentions the name; exact, case-sensitive matching happens per line below.
+    let chunks = sqlx::query_as::<_, SymbolChunk>(
+        r#"
+        SELECT chunk.file_path, chunk.start_line, chunk.end_line, chunk.chunk_type,
+               chunk.name, chunk.content
+        FROM aop_vector_chunks_fts
+        JOIN aop_vector_chunks AS chunk ON chunk.rowid = aop_vector_chunks_fts.rowid
+        WHERE aop_vector_chunks_fts MATCH ? AND chunk.project_root = ?
+        ORDER BY chunk.file_path, chunk.start_line
+        "#,
+    )
+    .bind(format!("\"{bare}\""))
+    .bind(&project_root_str)
+    .fetch_all(pool)
+    .await
+    .map_err(|error| format!("Failed to query symbol index: {error}"))?;
+
+    let definitions = merge_split_definitions(
+        chunks
+            .iter()
+            .filter(|chunk| defines_symbol(chunk, symbol))
+            .map(|chunk| SymbolLocation {
+                file_path: chunk.file_path.clone(),
+                start_line: chunk.start_line.max(0) as u32,
+                end_line: chunk.end_line.max(0) as u32,
+                kind: chunk.chunk_type.clone(),
+                name: chunk.name.clone(),
+                snippet: chunk.content.clone(),
+            })
+            .collect(),
+    );
+
+    let limit = limit
+        .and_then(|value| usize::try_from(value).ok())
+        .unwrap_or(DEFAULT_REFERENCE_LIMIT)
+        .max(1);
+    let mut seen = HashSet::new();
+    let mut references = Vec::new();
+    for chunk in &chunks {
+        let first_line = chunk.start_line.max(1) as u32;
+        for (offset, line) in chunk.content.lines().enumerate() {
+            let line_number = first_line + offset as u32;
+            let inside_definition = definitions.iter().any(|definition| {
+                definition.file_path == chunk.file_path
+                    && definition.start_line <= line_number
+                    && line_number <= definition.end_line
+            });
+            if inside_definition || !mentions(line, bare) {
+                continue;
+            }
+            if !seen.insert((chunk.file_path.clone(), line_number)) {
+                continue;
+            }
+            references.push(SymbolLocation {
+                file_path: chunk.file_path.clone(),
+                start_line: line_number,
+                end_line: line_number,
+                kind: chunk.chunk_type.clone(),
+                name: chunk.name.clone(),
+                snippet: line.trim().to_string(),
+            });
+        }
+    }
+    references.truncate(limit);
+
+    Ok(SymbolSearchResult {
+        target_project: project_root_str,
+        symbol: symbol.to_string(),
+        definitions,
+        references,
+    })
+}
+
+/// `Store.load`, `Store::load` and `load` all name `load`.
+fn bare_symbol(symbol: &str) -> &str {
+    symbol.rsplit(['.', ':']).next().unwrap_or_default().trim()
+}
+
+/// Chunk names are the symbol (`load`), a member path (`Store.load`) or, for impls,
+/// the implementing type with its trait (`fmt::Display for Point`).
+fn defines_symbol(chunk: &SymbolChunk, symbol: &str) -> bool {
+    if NON_DEFINITION_KINDS.contains(&chunk.chunk_type.as_str()) {
+        return false;
+    }
+    if symbol.contains(['.', ':']) {
+        return chunk.name.replace("::", ".") == symbol.replace("::", ".");
+    }
+    chunk
+        .name
+        .rsplit(|ch: char| !ch.is_alphanumeric() && ch != '_')
+        .next()
+        .is_some_and(|name| name == symbol)
+}
+
+/// Line-window chunking splits long definitions into consecutive chunks with one name;
+/// report them as a single definition.
+fn merge_split_definitions(definitions: Vec<SymbolLocation>) -> Vec<SymbolLocation> {
+    let mut merged: Vec<SymbolLocation> = Vec::new();
+    for definition in definitions {
+        if let Some(last) = merged.last_mut() {
+            if last.file_path == definition.file_path
+                && last.name == definition.name
+                && last.end_line + 1 == definition.start_line
+            {
+                last.end_line = definition.end_line;
+                last.snippet.push('\n');
+                last.snippet.push_str(&definition.snippet);
+                continue;
+            }
+        }
+        merged.push(definition);
+    }
+    merged
+}
+
+/// Whole-identifier, case-sensitive occurrence of `symbol` in `line`.
+fn mentions(line: &str, symbol: &str) -> bool {
+    let is_identifier = |ch: char| ch.is_alphanumeric() || ch == '_';
+    line.match_indices(symbol).any(|(start, _)| {
+        let before = line[..start].chars().next_back();
+        let after = line[start + symbol.len()..].chars().next();
+        !before.is_some_and(is_identifier) && !after.is_some_and(is_identifier)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::db;
+
+    use super::search_symbols;
+
+    #[tokio::test]
+    async fn finds_definitions_and_references_by_line() {
+        let project_temp = tempdir().expect("project temp dir should exist");
+        std::fs::write(
+            project_temp.path().join("cache.rs"),
+            "pub fn evict(cache: &mut Vec<u32>) {\n    cache.clear();\n}\n\npub fn trim(cache: &mut Vec<u32>) {\n    if cache.len() > 8 {\n        evict(cache);\n    }\n}\n",
+        )
+        .expect("fixture should be written");
+        std::fs::write(
+            project_temp.path().join("main.rs"),
+            "fn main() {\n    let mut cache = Vec::new();\n    evict(&mut cache);\n    evicted(&mut cache);\n}\n",
+        )
+        .expect("fixture should be written");
+
+        let db_dir = tempdir().expect("db temp dir should exist");
+        let pool = db::connect_pool(&db_dir.path().join("symbols.db"))
+            .await
+            .expect("sqlite pool should initialize");
+        db::run_migrations(&pool)
+            .await
+            .expect("migrations should initialize");
+
+        let result = search_symbols(&pool, &project_temp.path().to_string_lossy(), "evict", None)
+            .await
+            .expect("symbol search should succeed");
+
+        assert_eq!(result.definitions.len(), 1);
+        assert_eq!(result.definitions[0].file_path, "cache.rs");
+        assert_eq!(
+            (
+                result.definitions[0].start_line,
+                result.definitions[0].end_line
+            ),
+            (1, 3)
+        );
+        let references = result
+            .references
+            .iter()
+            .map(|reference| (reference.file_path.as_str(), reference.start_line))
+            .collect::<Vec<_>>();
+        assert_eq!(references, vec![("cache.rs", 7), ("main.rs", 3)]);
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::Utc;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use sqlx::SqlitePool;
+use tauri::async_runtime::JoinHandle;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+
+use crate::project_ignore::{ProjectIgnore, AOP_IGNORE_FILE};
+use crate::vector::indexer;
+use crate::vector::IndexWatchStatus;
+
+/// Quiet period after the last change before reindexing, so a burst of saves or a
+/// `git checkout` triggers one pass.
+const DEBOUNCE: Duration = Duration::from_millis(750);
+
+/// Background watchers keeping the vector index of each watched project current.
+#[derive(Default)]
+pub struct IndexWatchers {
+    watches: HashMap<String, ProjectWatch>,
+}
+
+struct ProjectWatch {
+    // Dropping the watcher stops file notifications.
+    _watcher: RecommendedWatcher,
+    task: JoinHandle<()>,
+    status: Arc<Mutex<IndexWatchStatus>>,
+}
+
+impl IndexWatchers {
+    /// Starts watching `target_project` and runs one incremental index pass right away.
+    /// Watching an already watched project returns its current status.
+    pub fn start(
+        &mut self,
+        pool: SqlitePool,
+        target_project: &str,
+    ) -> Result<IndexWatchStatus, String> {
+        let root = indexer::normalize_project_root(target_project)?;
+        let key = root.to_string_lossy().to_string();
+        if let Some(watch) = self.watches.get(&key) {
+            return Ok(snapshot(&watch.status));
+        }
+
+        let (sender, receiver) = mpsc::unbounded_channel::<()>();
+        let ignore = ProjectIgnore::load(&root);
+        let event_sender = sender.clone();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            if let Ok(event) = result {
+                if is_relevant_event(&ignore, &event) {
+                    let _ = event_sender.send(());
+                }
+            }
+        })
+        .map_err(|error| format!("Failed to create file watcher: {error}"))?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(|error| format!("Failed to watch '{}': {error}", root.display()))?;
+
+        let status = Arc::new(Mutex::new(IndexWatchStatus {
+            target_project: key.clone(),
+            watching: true,
+            started_at: Utc::now().timestamp(),
+            last_indexed_at: None,
+            last_error: None,
+            reindex_count: 0,
+        }));
+        let _ = sender.send(());
+        let task =
+            tauri::async_runtime::spawn(reindex_loop(pool, key.clone(), receiver, status.clone()));
+
+        let current = snapshot(&status);
+        self.watches.insert(
+            key,
+            ProjectWatch {
+                _watcher: watcher,
+                task,
+                status,
+            },
+        );
+        Ok(current)
+    }
+
+    pub fn is_watching(&self, project_root: &str) -> bool {
+        self.watches.contains_key(project_root)
+    }
+
+    /// Stops watching `target_project`. Stopping a project that is not watched is a no-op.
+    pub fn stop(&mut self, target_project: &str) -> Result<IndexWatchStatus, String> {
+        let root = indexer::normalize_project_root(target_project)?;
+        let key = root.to_string_lossy().to_string();
+        let Some(watch) = self.watches.remove(&key) else {
+            return Ok(IndexWatchStatus {
+                target_project: key,
+                watching: false,
+                started_at: 0,
+                last_indexed_at: None,
+                last_error: None,
+                reindex_count: 0,
+            });
+        };
+        watch.task.abort();
+        let mut status = snapshot(&watch.status);
+        status.watching = false;
+        Ok(status)
+    }
+}
+
+async fn reindex_loop(
+    pool: SqlitePool,
+    project_root: String,
+    mut receiver: mpsc::UnboundedReceiver<()>,
+    status: Arc<Mutex<IndexWatchStatus>>,
+) {
+    while receiver.recv().await.is_some() {
+        sleep(DEBOUNCE).await;
+        while receiver.try_recv().is_ok() {}
+
+        let result = indexer::index_project(&pool, &project_root).await;
+        if let Ok(mut current) = status.lock() {
+            current.last_indexed_at = Some(Utc::now().timestamp());
+            current.reindex_count = current.reindex_count.saturating_add(1);
+            current.last_error = result.err();
+        }
+    }
+}
+
+fn snapshot(status: &Arc<Mutex<IndexWatchStatus>>) -> IndexWatchStatus {
+    status
+        .lock()
+        .map(|current| current.clone())
+        .unwrap_or_else(|poisoned| poisoned.into_inner().clone())
+}
+
+fn is_relevant_event(ignore: &ProjectIgnore, event: &Event) -> bool {
+    if !matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        return false;
+    }
+    // Removed directories carry no extension but still drop indexed files.
+    let removal = matches!(event.kind, EventKind::Remove(_));
+    event
+        .paths
+        .iter()
+        .any(|path| is_indexable_path(ignore, path, removal))
+}
+
+fn is_indexable_path(ignore: &ProjectIgnore, path: &Path, removal: bool) -> bool {
+    // Changed ignore rules can add or drop indexed files.
+    let is_ignore_file = path
+        .file_name()
+        .is_some_and(|name| name == ".gitignore" || name == AOP_IGNORE_FILE);
+    if is_ignore_file {
+        return true;
+    }
+    !ignore.is_ignored(path, false) && (removal || indexer::is_supported_extension(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_build_output_ignored_and_unsupported_files() {
+        let root = tempfile::tempdir().expect("temp dir should exist");
+        std::fs::write(root.path().join(".aopignore"), "fixtures/\n").expect("ignore file");
+        let ignore = ProjectIgnore::load(root.path());
+        let path = |relative: &str| root.path().join(relative);
+
+        assert!(is_indexable_path(&ignore, &path("src/app.ts"), false));
+        assert!(!is_indexable_path(
+            &ignore,
+            &path("node_modules/react/index.js"),
+            false
+        ));
+        assert!(!is_indexable_path(
+            &ignore,
+            &path("fixtures/user.json"),
+            false
+        ));
+        assert!(!is_indexable_path(&ignore, &path("logo.png"), false));
+        assert!(is_indexable_path(&ignore, &path("src/old"), true));
+        assert!(is_indexable_path(&ignore, &path(".aopignore"), false));
+    }
+}
@@ -0,0 +1,204 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::SqlitePool;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+use crate::db::metrics;
+use crate::secret_vault::SecretVault;
+
+/// Vault entry holding the key webhook payloads are signed with.
+pub const SIGNING_KEY_SECRET: &str = "webhook";
+/// Tells receivers whether to expect `X-AOP-Signature`: `signed` or `unsigned`.
+const SIGNATURE_STATUS_HEADER: &str = "X-AOP-Signature-Status";
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const DELIVERY_ATTEMPTS: u32 = 3;
+const RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Run lifecycle events posted to every URL in `AOP_WEBHOOK_URLS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEvent {
+    PlanReady,
+    RunCompleted,
+    RunFailed,
+    MutationApplied,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::PlanReady => "plan_ready",
+            WebhookEvent::RunCompleted => "run_completed",
+            WebhookEvent::RunFailed => "run_failed",
+            WebhookEvent::MutationApplied => "mutation_applied",
+        }
+    }
+}
+
+/// The configured receivers, separated by commas or whitespace.
+pub fn configured_urls() -> Vec<String> {
+    std::env::var("AOP_WEBHOOK_URLS")
+        .unwrap_or_default()
+        .split(|ch: char| ch == ',' || ch.is_whitespace())
+        .map(str::trim)
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Posts `event` for `task_id` to every configured URL in the background, signed with the
+/// key in `secret_vault`; failed deliveries are audited to `pool`. `summary` doubles as the
+/// message text of Slack and Discord incoming webhooks.
+pub fn emit(
+    pool: &SqlitePool,
+    secret_vault: &Arc<Mutex<SecretVault>>,
+    event: WebhookEvent,
+    task_id: &str,
+    summary: &str,
+    data: Value,
+) {
+    let urls = configured_urls();
+    if urls.is_empty() {
+        return;
+    }
+    let (pool, vault) = (pool.clone(), secret_vault.clone());
+    let task_id = task_id.to_string();
+    let payload = build_payload(event, &task_id, summary, data, Utc::now().timestamp());
+    tauri::async_runtime::spawn(async move {
+        let signing_key = vault
+            .lock()
+            .await
+            .read_secret(SIGNING_KEY_SECRET, "webhooks")
+            .ok()
+            .flatten();
+        let body = payload.to_string();
+        let signature = signing_key.map(|key| sign(key.as_bytes(), body.as_bytes()));
+        for url in urls {
+            if let Err(error) = deliver(&url, event, &body, signature.as_deref()).await {
+                let details = json!({
+                    "event": event.as_str(),
+                    "host": reqwest::Url::parse(&url)
+                        .ok()
+                        .and_then(|parsed| parsed.host_str().map(str::to_string)),
+                    "error": error,
+                });
+                let _ = metrics::record_audit_event(
+                    &pool,
+                    "webhooks",
+                    "webhook_delivery_failed",
+                    Some(&task_id),
+                    Some(&details.to_string()),
+                )
+                .await;
+            }
+        }
+    });
+}
+
+fn build_payload(
+    event: WebhookEvent,
+    task_id: &str,
+    summary: &str,
+    data: Value,
+    timestamp: i64,
+) -> Value {
+    let text = format!("[aop] {}: {summary}", event.as_str());
+    json!({
+        "event": event.as_str(),
+        "taskId": task_id,
+        "timestamp": timestamp,
+        "summary": summary,
+        "data": data,
+        "text": text,
+        "content": text,
+    })
+}
+
+/// Posts `body`, retrying with a growing pause; only the last error is returned.
+async fn deliver(
+    url: &str,
+    event: WebhookEvent,
+    body: &str,
+    signature: Option<&str>,
+) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .build()
+        .map_err(|error| format!("Failed to build webhook HTTP client: {error}"))?;
+    let delivery_id = Uuid::new_v4().to_string();
+    let mut last_error = String::new();
+    for attempt in 1..=DELIVERY_ATTEMPTS {
+        let mut builder = client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header("X-AOP-Event", event.as_str())
+            .header("X-AOP-Delivery", &delivery_id)
+            .body(body.to_string());
+        builder = match signature {
+            Some(signature) => builder
+                .header("X-AOP-Signature", format!("sha256={signature}"))
+                .header(SIGNATURE_STATUS_HEADER, "signed"),
+            None => builder.header(SIGNATURE_STATUS_HEADER, "unsigned"),
+        };
+        match builder.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => last_error = format!("receiver answered {}", response.status()),
+            Err(error) => last_error = format!("request failed: {error}"),
+        }
+        if attempt < DELIVERY_ATTEMPTS {
+            sleep(RETRY_BACKOFF * attempt).await;
+        }
+    }
+    Err(last_error)
+}
+
+/// HMAC-SHA256 of `body` under `key`, as lowercase hex.
+fn sign(key: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_match_hmac_sha256_and_payloads_carry_chat_text() {
+        // RFC 4231 test case 2.
+        assert_eq!(
+            sign(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // RFC 4231 test case 6: keys longer than a block are hashed first.
+        assert_eq!(
+            sign(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            ),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+
+        let payload = build_payload(
+            WebhookEvent::RunFailed,
+            "task-1",
+            "Ship the login page",
+            json!({ "status": "failed" }),
+            1_700_000_000,
+        );
+        assert_eq!(payload["event"], "run_failed");
+        assert_eq!(payload["data"]["status"], "failed");
+        assert_eq!(payload["text"], "[aop] run_failed: Ship the login page");
+        assert_eq!(payload["content"], payload["text"]);
+    }
+}
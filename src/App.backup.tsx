@@ -612,12 +612,10 @@ function App() {
     setBrowserFeedback(null)
 
     try {
-      const result = await indexTargetProject({ targetProject: target })
-      setIndexResult(result)
+      const job = await indexTargetProject({ targetProject: target })
+      setIndexResult(null)
       setSemanticResults([])
-      setBrowserFeedback(
-        `Indexed ${result.indexedFiles} files into ${result.indexedChunks} chunks (table: ${result.tableName}).`,
-      )
+      setBrowserFeedback(`Indexing ${job.targetProject} in the background (job ${job.jobId}).`)
     } catch (error) {
       setBrowserFeedback(error instanceof Error ? error.message : String(error))
     } finally {
@@ -37,6 +37,10 @@ describe('App', () => {
           activeRuns: [],
           recentEvents: [],
           modelHealth: [],
+          providerQueues: [],
+          executionQueue: { lanes: [], queued: [] },
+          stalledRuns: [],
+          mcpSessions: [],
         }
       }
 
@@ -59,6 +63,10 @@ describe('App', () => {
           retryCount: 0,
           createdAt: 1_718_234_567,
           updatedAt: 1_718_234_567,
+          priority: 0,
+          deadlineSeconds: null,
+          startedAt: null,
+          queuedAt: null,
         }
       }
 
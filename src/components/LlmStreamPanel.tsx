@@ -0,0 +1,76 @@
+import { useEffect, useState } from 'react'
+
+import { Button } from '@/components/ui/button'
+import { ScrollArea } from '@/components/ui/scroll-area'
+import { cancelLlmStream, onLlmStream } from '@/hooks/useTauri'
+import type { LlmStreamEvent } from '@/types'
+
+interface LlmStreamPanelProps {
+  label: string
+  taskId?: string | null
+}
+
+interface StreamOutput {
+  streamId: string
+  text: string
+  state: LlmStreamEvent['state']
+  error: string | null
+}
+
+/** Live output of the latest model call with `label`, with a way to stop it early. */
+export default function LlmStreamPanel({ label, taskId }: LlmStreamPanelProps) {
+  const [output, setOutput] = useState<StreamOutput | null>(null)
+  const [isCancelling, setIsCancelling] = useState(false)
+
+  useEffect(() => {
+    const unlisten = onLlmStream((event) => {
+      if (event.label !== label || (taskId && event.taskId !== taskId)) {
+        return
+      }
+      setOutput((current) => {
+        const text = current?.streamId === event.streamId ? current.text + event.delta : event.delta
+        return { streamId: event.streamId, text, state: event.state, error: event.error }
+      })
+    })
+    return () => {
+      void unlisten.then((stop) => stop())
+    }
+  }, [label, taskId])
+
+  async function handleCancel() {
+    if (!output) {
+      return
+    }
+    setIsCancelling(true)
+    try {
+      await cancelLlmStream({ streamId: output.streamId })
+    } catch {
+      // The stream finished before the cancel arrived.
+    } finally {
+      setIsCancelling(false)
+    }
+  }
+
+  if (!output) {
+    return null
+  }
+
+  return (
+    <div className="space-y-2 rounded-md border p-3">
+      <div className="flex items-center justify-between gap-2">
+        <span className="text-muted-foreground text-xs">
+          {output.state === 'streaming' ? `Receiving ${label} output... (${output.text.length} chars)` : `${label} output ${output.state}`}
+        </span>
+        {output.state === 'streaming' ? (
+          <Button disabled={isCancelling} onClick={() => void handleCancel()} size="sm" type="button" variant="outline">
+            {isCancelling ? 'Stopping...' : 'Stop'}
+          </Button>
+        ) : null}
+      </div>
+      <ScrollArea className="h-40 rounded border bg-muted/30 p-2">
+        <pre className="whitespace-pre-wrap font-mono text-xs">{output.text}</pre>
+      </ScrollArea>
+      {output.error ? <p className="text-destructive text-xs">{output.error}</p> : null}
+    </div>
+  )
+}
@@ -64,6 +64,7 @@ function AssignmentCard({ assignment }: { assignment: TaskAssignment }) {
               <Zap className="size-3" />
               Budget: {assignment.tokenBudget.toLocaleString()}
             </span>
+            <span>Priority: {assignment.priority}</span>
           </div>
           {assignment.relevantFiles.length > 0 && (
             <div className="space-y-1">
@@ -38,6 +38,8 @@ const MUTATION_STATUS_ICON: Record<string, typeof CheckCircle2> = {
   validated_no_tests: CheckCircle2,
   applied: CheckCircle2,
   rejected: XCircle,
+  stale_base: CircleDot,
+  expired: XCircle,
 }
 
 const MUTATION_STATUS_CLASS: Record<string, string> = {
@@ -46,6 +48,8 @@ const MUTATION_STATUS_CLASS: Record<string, string> = {
   validated_no_tests: 'text-yellow-500',
   applied: 'text-green-600',
   rejected: 'text-red-500',
+  stale_base: 'text-orange-500',
+  expired: 'text-muted-foreground',
 }
 
 function EmptyState() {
@@ -1,67 +1,178 @@
 import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 
 import type {
+  AddRegistryModelInput,
+  AgentEventPage,
   AgentEventRecord,
+  AgentRunPage,
   AgentRunRecord,
   AgentTerminalSession,
   AnalyzeObjectiveInput,
+  ApplyTargetPatchInput,
   ArchiveTelemetryInput,
   ArchiveTelemetryResult,
+  AttachObjectiveImagesInput,
   ApproveOrchestrationPlanInput,
   AuditLogEntry,
+  BenchmarkModelsInput,
+  BenchmarkModelsResult,
+  BudgetPolicyRecord,
   BudgetRequestRecord,
+  CancelLlmStreamInput,
+  CleanShadowCacheInput,
   ControlExecutionScopeInput,
+  CreateModelExperimentInput,
   CreateTaskInput,
+  DatabaseBackup,
+  DatabaseIntegrityReport,
+  DeleteProjectIndexResult,
   ContextChunk,
   ControlTaskInput,
   DirectoryListing,
+  DiscoverProviderModelsInput,
+  DiscoverProviderModelsResult,
+  EditMutationDiffInput,
+  ExportLlmTranscriptsInput,
+  ExportLlmTranscriptsResult,
   ExecuteDomainTaskInput,
+  FinalizeBranchInput,
+  FinalizeBranchResult,
   GeneratePlanInput,
+  GetPromptTemplatesInput,
+  GetTaskCostRollupInput,
+  GetTaskTimelineInput,
   GeneratedPlan,
-  IndexProjectResult,
+  GitHistoryEntry,
+  ImportTelemetryArchiveInput,
+  ImportTelemetryArchiveResult,
+  IndexGitHistoryResult,
+  IndexJobInput,
+  IndexJobStatus,
   IndexTargetProjectInput,
+  IndexWatchInput,
+  IndexWatchStatus,
+  IndexedProjectStats,
   IntentSummary,
   ListAuditLogInput,
+  ListSecretAccessLogInput,
   ListAgentTerminalsInput,
   ListAgentEventsInput,
   ListAgentRunsInput,
+  ListLlmTranscriptsInput,
+  ListModelArmsInput,
+  ListTaskAttachmentsInput,
   ListTaskBudgetRequestsInput,
   ListTaskActivityInput,
   ListTerminalEventsInput,
   ListTargetDirInput,
   ListTaskMutationsInput,
+  LlmStreamEvent,
+  LlmTranscriptRecord,
+  MutationPipelineBatchResult,
   MutationPipelineResult,
   MutationRecord,
+  ModelArmRecord,
+  ModelExperimentComparison,
+  ModelExperimentIdInput,
+  ModelExperimentRecord,
+  ModelHealthTrends,
+  ModelPinRecord,
   ModelRegistrySnapshot,
+  ListMcpToolsInput,
+  McpResourceSubscription,
+  McpServerConfig,
+  McpToolCatalog,
   MissionControlSnapshot,
+  MissionControlSubscription,
+  MissionControlSubscriptionInput,
+  MissionControlUpdate,
   MutationRevisionResult,
   ObjectiveAnalysis,
+  OpenPullRequestInput,
+  OpenPullRequestResult,
   OrchestrationResult,
   PlanExecutionResult,
+  PromptTemplate,
+  PurgeMutationsInput,
+  PurgeMutationsResult,
+  PurgeShadowDirsInput,
   QueryCodebaseInput,
+  QueryGitHistoryInput,
   ReadTargetFileInput,
+  RebaseMutationInput,
+  RemoveMcpServerInput,
+  RemoveRegistryModelInput,
+  RestoreDatabaseInput,
+  RestoreDatabaseResult,
   RequestTaskBudgetIncreaseInput,
   RequestMutationRevisionInput,
+  RecoverStalledRunInput,
   ResolveTaskBudgetRequestInput,
+  RunMutationPipelineBatchInput,
+  RunTargetCommandInput,
   RunMutationPipelineInput,
   SearchResult,
+  SearchSymbolsInput,
+  ShadowCleanupResult,
+  ShadowDirEntry,
+  SpendStatus,
+  SaveMcpServerInput,
+  SetBudgetPolicyInput,
+  SetModelPinInput,
+  SetPromptTemplateInput,
   SetProviderSecretInput,
   SecretOperationResult,
   RevealProviderSecretInput,
   RevealProviderSecretResult,
+  MigrateSecretBackendInput,
+  SecretMigrationResult,
+  ListProviderCredentialsInput,
+  ProviderCredential,
+  RotateProviderSecretInput,
+  SecretRotationResult,
+  ValidateProviderSecretInput,
+  ProviderSecretValidation,
   RuntimeFlags,
   RuntimeFlagsUpdateResult,
+  RuntimeProfile,
+  SetRuntimeProfileInput,
+  ConfigurationBundle,
+  ImportConfigurationInput,
+  ImportConfigurationResult,
   SetRuntimeFlagsInput,
+  SetTaskDeadlineInput,
+  SetTaskPriorityInput,
+  TaskAttachmentRecord,
+  TaskCostRollup,
+  TaskTimeline,
   GetProviderSecretStatusInput,
   ProviderSecretStatus,
   GetMissionControlSnapshotInput,
+  SubscribeMcpResourcesInput,
+  SubscribeMissionControlInput,
+  GetModelHealthTrendsInput,
+  GetCostBreakdownInput,
+  CostBreakdown,
+  CommandResult,
+  GetTokenUsageTrendsInput,
+  TokenUsageTrends,
+  SearchEventsInput,
+  EventSearchHit,
+  ExportTaskTraceInput,
+  ExportTaskTraceResult,
   SearchTargetFilesInput,
   SetMutationStatusInput,
+  SymbolSearchResult,
   TerminalEventRecord,
   TargetFileContent,
   TaskRecord,
+  UnsubscribeMcpResourcesInput,
+  UpdateRegistryModelInput,
   UserObjectiveInput,
   UpdateTaskStatusInput,
+  WriteResult,
+  WriteTargetFileInput,
 } from '@/types'
 
 export async function getTasks(): Promise<TaskRecord[]> {
@@ -80,6 +191,14 @@ export async function controlTask(input: ControlTaskInput): Promise<TaskRecord[]
   return invoke<TaskRecord[]>('control_task', { input })
 }
 
+export async function setTaskPriority(input: SetTaskPriorityInput): Promise<TaskRecord> {
+  return invoke<TaskRecord>('set_task_priority', { input })
+}
+
+export async function setTaskDeadline(input: SetTaskDeadlineInput): Promise<TaskRecord> {
+  return invoke<TaskRecord>('set_task_deadline', { input })
+}
+
 export async function requestTaskBudgetIncrease(input: RequestTaskBudgetIncreaseInput): Promise<BudgetRequestRecord> {
   return invoke<BudgetRequestRecord>('request_task_budget_increase', { input })
 }
@@ -88,10 +207,30 @@ export async function listTaskBudgetRequests(input: ListTaskBudgetRequestsInput)
   return invoke<BudgetRequestRecord[]>('list_task_budget_requests', { input })
 }
 
+export async function attachObjectiveImages(input: AttachObjectiveImagesInput): Promise<TaskAttachmentRecord[]> {
+  return invoke<TaskAttachmentRecord[]>('attach_objective_images', { input })
+}
+
+export async function listTaskAttachments(input: ListTaskAttachmentsInput): Promise<TaskAttachmentRecord[]> {
+  return invoke<TaskAttachmentRecord[]>('list_task_attachments', { input })
+}
+
 export async function resolveTaskBudgetRequest(input: ResolveTaskBudgetRequestInput): Promise<BudgetRequestRecord> {
   return invoke<BudgetRequestRecord>('resolve_task_budget_request', { input })
 }
 
+export async function listBudgetPolicies(): Promise<BudgetPolicyRecord[]> {
+  return invoke<BudgetPolicyRecord[]>('list_budget_policies')
+}
+
+export async function setBudgetPolicy(input: SetBudgetPolicyInput): Promise<BudgetPolicyRecord | null> {
+  return invoke<BudgetPolicyRecord | null>('set_budget_policy', { input })
+}
+
+export async function getSpendStatus(): Promise<SpendStatus> {
+  return invoke<SpendStatus>('get_spend_status')
+}
+
 export async function orchestrateObjective(input: UserObjectiveInput): Promise<OrchestrationResult> {
   return invoke<OrchestrationResult>('orchestrate_objective', { input })
 }
@@ -108,6 +247,14 @@ export async function approveOrchestrationPlan(input: ApproveOrchestrationPlanIn
   return invoke<PlanExecutionResult>('approve_orchestration_plan', { input })
 }
 
+export async function cancelLlmStream(input: CancelLlmStreamInput): Promise<void> {
+  return invoke<void>('cancel_llm_stream', { input })
+}
+
+export async function onLlmStream(handler: (event: LlmStreamEvent) => void): Promise<UnlistenFn> {
+  return listen<LlmStreamEvent>('llm-stream', (event) => handler(event.payload))
+}
+
 export async function executeDomainTask(input: ExecuteDomainTaskInput): Promise<IntentSummary> {
   return invoke<IntentSummary>('execute_domain_task', { input })
 }
@@ -120,6 +267,34 @@ export async function runMutationPipeline(input: RunMutationPipelineInput): Prom
   return invoke<MutationPipelineResult>('run_mutation_pipeline', { input })
 }
 
+export async function runMutationPipelineBatch(input: RunMutationPipelineBatchInput): Promise<MutationPipelineBatchResult> {
+  return invoke<MutationPipelineBatchResult>('run_mutation_pipeline_batch', { input })
+}
+
+export async function rebaseMutation(input: RebaseMutationInput): Promise<MutationRecord> {
+  return invoke<MutationRecord>('rebase_mutation', { input })
+}
+
+export async function cleanShadowCache(input: CleanShadowCacheInput): Promise<ShadowCleanupResult> {
+  return invoke<ShadowCleanupResult>('clean_shadow_cache', { input })
+}
+
+export async function listShadowDirs(): Promise<ShadowDirEntry[]> {
+  return invoke<ShadowDirEntry[]>('list_shadow_dirs')
+}
+
+export async function purgeShadowDirs(input: PurgeShadowDirsInput): Promise<ShadowCleanupResult> {
+  return invoke<ShadowCleanupResult>('purge_shadow_dirs', { input })
+}
+
+export async function clearLlmCache(): Promise<number> {
+  return invoke<number>('clear_llm_cache')
+}
+
+export async function purgeMutations(input: PurgeMutationsInput): Promise<PurgeMutationsResult> {
+  return invoke<PurgeMutationsResult>('purge_mutations', { input })
+}
+
 export async function setMutationStatus(input: SetMutationStatusInput): Promise<MutationRecord> {
   return invoke<MutationRecord>('set_mutation_status', { input })
 }
@@ -128,6 +303,18 @@ export async function requestMutationRevision(input: RequestMutationRevisionInpu
   return invoke<MutationRevisionResult>('request_mutation_revision', { input })
 }
 
+export async function finalizeBranch(input: FinalizeBranchInput): Promise<FinalizeBranchResult> {
+  return invoke<FinalizeBranchResult>('finalize_branch', { input })
+}
+
+export async function openPullRequest(input: OpenPullRequestInput): Promise<OpenPullRequestResult> {
+  return invoke<OpenPullRequestResult>('open_pull_request', { input })
+}
+
+export async function editMutationDiff(input: EditMutationDiffInput): Promise<MutationRecord> {
+  return invoke<MutationRecord>('edit_mutation_diff', { input })
+}
+
 export async function listAuditLog(input: ListAuditLogInput): Promise<AuditLogEntry[]> {
   return invoke<AuditLogEntry[]>('list_audit_log', { input })
 }
@@ -160,22 +347,206 @@ export async function searchTargetFiles(input: SearchTargetFilesInput): Promise<
   return invoke<SearchResult>('search_target_files', { input })
 }
 
-export async function indexTargetProject(input: IndexTargetProjectInput): Promise<IndexProjectResult> {
-  return invoke<IndexProjectResult>('index_target_project', { input })
+export async function writeTargetFile(input: WriteTargetFileInput): Promise<WriteResult> {
+  return invoke<WriteResult>('write_target_file', { input })
+}
+
+export async function applyTargetPatch(input: ApplyTargetPatchInput): Promise<WriteResult> {
+  return invoke<WriteResult>('apply_target_patch', { input })
+}
+
+export async function runTargetCommand(input: RunTargetCommandInput): Promise<CommandResult> {
+  return invoke<CommandResult>('run_target_command', { input })
+}
+
+export async function indexTargetProject(input: IndexTargetProjectInput): Promise<IndexJobStatus> {
+  return invoke<IndexJobStatus>('index_target_project', { input })
+}
+
+export async function getIndexJobStatus(input: IndexJobInput): Promise<IndexJobStatus> {
+  return invoke<IndexJobStatus>('get_index_job_status', { input })
+}
+
+export async function cancelIndexJob(input: IndexJobInput): Promise<IndexJobStatus> {
+  return invoke<IndexJobStatus>('cancel_index_job', { input })
+}
+
+export async function onIndexJobProgress(handler: (status: IndexJobStatus) => void): Promise<UnlistenFn> {
+  return listen<IndexJobStatus>('index-job-progress', (event) => handler(event.payload))
+}
+
+export async function listIndexedProjects(): Promise<IndexedProjectStats[]> {
+  return invoke<IndexedProjectStats[]>('list_indexed_projects')
+}
+
+export async function getProjectIndexStats(input: IndexTargetProjectInput): Promise<IndexedProjectStats> {
+  return invoke<IndexedProjectStats>('get_project_index_stats', { input })
+}
+
+export async function deleteProjectIndex(input: IndexTargetProjectInput): Promise<DeleteProjectIndexResult> {
+  return invoke<DeleteProjectIndexResult>('delete_project_index', { input })
 }
 
 export async function queryCodebase(input: QueryCodebaseInput): Promise<ContextChunk[]> {
   return invoke<ContextChunk[]>('query_codebase', { input })
 }
 
+export async function searchSymbols(input: SearchSymbolsInput): Promise<SymbolSearchResult> {
+  return invoke<SymbolSearchResult>('search_symbols', { input })
+}
+
+export async function indexGitHistory(input: IndexTargetProjectInput): Promise<IndexGitHistoryResult> {
+  return invoke<IndexGitHistoryResult>('index_git_history', { input })
+}
+
+export async function queryGitHistory(input: QueryGitHistoryInput): Promise<GitHistoryEntry[]> {
+  return invoke<GitHistoryEntry[]>('query_git_history', { input })
+}
+
+export async function startIndexWatch(input: IndexWatchInput): Promise<IndexWatchStatus> {
+  return invoke<IndexWatchStatus>('start_index_watch', { input })
+}
+
+export async function stopIndexWatch(input: IndexWatchInput): Promise<IndexWatchStatus> {
+  return invoke<IndexWatchStatus>('stop_index_watch', { input })
+}
+
 export async function getModelRegistry(): Promise<ModelRegistrySnapshot> {
   return invoke<ModelRegistrySnapshot>('get_model_registry')
 }
 
+export async function reloadModelRegistry(): Promise<ModelRegistrySnapshot> {
+  return invoke<ModelRegistrySnapshot>('reload_model_registry')
+}
+
+export async function addRegistryModel(input: AddRegistryModelInput): Promise<ModelRegistrySnapshot> {
+  return invoke<ModelRegistrySnapshot>('add_registry_model', { input })
+}
+
+export async function updateRegistryModel(input: UpdateRegistryModelInput): Promise<ModelRegistrySnapshot> {
+  return invoke<ModelRegistrySnapshot>('update_registry_model', { input })
+}
+
+export async function removeRegistryModel(input: RemoveRegistryModelInput): Promise<ModelRegistrySnapshot> {
+  return invoke<ModelRegistrySnapshot>('remove_registry_model', { input })
+}
+
+export async function discoverProviderModels(input: DiscoverProviderModelsInput): Promise<DiscoverProviderModelsResult> {
+  return invoke<DiscoverProviderModelsResult>('discover_provider_models', { input })
+}
+
+export async function benchmarkModels(input: BenchmarkModelsInput): Promise<BenchmarkModelsResult> {
+  return invoke<BenchmarkModelsResult>('benchmark_models', { input })
+}
+
+export async function listModelArms(input: ListModelArmsInput): Promise<ModelArmRecord[]> {
+  return invoke<ModelArmRecord[]>('list_model_arms', { input })
+}
+
+export async function listModelPins(): Promise<ModelPinRecord[]> {
+  return invoke<ModelPinRecord[]>('list_model_pins')
+}
+
+export async function setModelPin(input: SetModelPinInput): Promise<ModelPinRecord | null> {
+  return invoke<ModelPinRecord | null>('set_model_pin', { input })
+}
+
+export async function createModelExperiment(input: CreateModelExperimentInput): Promise<ModelExperimentRecord> {
+  return invoke<ModelExperimentRecord>('create_model_experiment', { input })
+}
+
+export async function listModelExperiments(): Promise<ModelExperimentRecord[]> {
+  return invoke<ModelExperimentRecord[]>('list_model_experiments')
+}
+
+export async function stopModelExperiment(input: ModelExperimentIdInput): Promise<ModelExperimentRecord> {
+  return invoke<ModelExperimentRecord>('stop_model_experiment', { input })
+}
+
+export async function compareModelExperiment(input: ModelExperimentIdInput): Promise<ModelExperimentComparison> {
+  return invoke<ModelExperimentComparison>('compare_model_experiment', { input })
+}
+
+export async function getPromptTemplates(input: GetPromptTemplatesInput): Promise<PromptTemplate[]> {
+  return invoke<PromptTemplate[]>('get_prompt_templates', { input })
+}
+
+export async function setPromptTemplate(input: SetPromptTemplateInput): Promise<PromptTemplate> {
+  return invoke<PromptTemplate>('set_prompt_template', { input })
+}
+
+export async function listMcpServers(): Promise<McpServerConfig[]> {
+  return invoke<McpServerConfig[]>('list_mcp_servers')
+}
+
+export async function saveMcpServer(input: SaveMcpServerInput): Promise<McpServerConfig> {
+  return invoke<McpServerConfig>('save_mcp_server', { input })
+}
+
+export async function removeMcpServer(input: RemoveMcpServerInput): Promise<boolean> {
+  return invoke<boolean>('remove_mcp_server', { input })
+}
+
+export async function listMcpTools(input: ListMcpToolsInput): Promise<McpToolCatalog> {
+  return invoke<McpToolCatalog>('list_mcp_tools', { input })
+}
+
+export async function subscribeMcpResources(input: SubscribeMcpResourcesInput): Promise<McpResourceSubscription> {
+  return invoke<McpResourceSubscription>('subscribe_mcp_resources', { input })
+}
+
+export async function unsubscribeMcpResources(input: UnsubscribeMcpResourcesInput): Promise<boolean> {
+  return invoke<boolean>('unsubscribe_mcp_resources', { input })
+}
+
+export async function listMcpResourceSubscriptions(): Promise<McpResourceSubscription[]> {
+  return invoke<McpResourceSubscription[]>('list_mcp_resource_subscriptions')
+}
+
 export async function getMissionControlSnapshot(input: GetMissionControlSnapshotInput): Promise<MissionControlSnapshot> {
   return invoke<MissionControlSnapshot>('get_mission_control_snapshot', { input })
 }
 
+export async function recoverStalledRun(input: RecoverStalledRunInput): Promise<TaskRecord[]> {
+  return invoke<TaskRecord[]>('recover_stalled_run', { input })
+}
+
+export async function subscribeMissionControl(
+  input: SubscribeMissionControlInput,
+): Promise<MissionControlSubscription> {
+  return invoke<MissionControlSubscription>('subscribe_mission_control', { input })
+}
+
+export async function unsubscribeMissionControl(input: MissionControlSubscriptionInput): Promise<void> {
+  return invoke<void>('unsubscribe_mission_control', { input })
+}
+
+export async function onMissionControlUpdate(
+  handler: (update: MissionControlUpdate) => void,
+): Promise<UnlistenFn> {
+  return listen<MissionControlUpdate>('mission-control-update', (event) => handler(event.payload))
+}
+
+export async function getModelHealthTrends(input: GetModelHealthTrendsInput): Promise<ModelHealthTrends> {
+  return invoke<ModelHealthTrends>('get_model_health_trends', { input })
+}
+
+export async function getCostBreakdown(input: GetCostBreakdownInput): Promise<CostBreakdown> {
+  return invoke<CostBreakdown>('get_cost_breakdown', { input })
+}
+
+export async function getTokenUsageTrends(input: GetTokenUsageTrendsInput): Promise<TokenUsageTrends> {
+  return invoke<TokenUsageTrends>('get_token_usage_trends', { input })
+}
+
+export async function getTaskCostRollup(input: GetTaskCostRollupInput): Promise<TaskCostRollup> {
+  return invoke<TaskCostRollup>('get_task_cost_rollup', { input })
+}
+
+export async function getTaskTimeline(input: GetTaskTimelineInput): Promise<TaskTimeline> {
+  return invoke<TaskTimeline>('get_task_timeline', { input })
+}
+
 export async function listAgentRuns(input: ListAgentRunsInput): Promise<AgentRunRecord[]> {
   return invoke<AgentRunRecord[]>('list_agent_runs', { input })
 }
@@ -184,6 +555,34 @@ export async function listAgentEvents(input: ListAgentEventsInput): Promise<Agen
   return invoke<AgentEventRecord[]>('list_agent_events', { input })
 }
 
+export async function pageAgentRuns(input: ListAgentRunsInput): Promise<AgentRunPage> {
+  return invoke<AgentRunPage>('page_agent_runs', { input })
+}
+
+export async function pageAgentEvents(input: ListAgentEventsInput): Promise<AgentEventPage> {
+  return invoke<AgentEventPage>('page_agent_events', { input })
+}
+
+export async function searchEvents(input: SearchEventsInput): Promise<EventSearchHit[]> {
+  return invoke<EventSearchHit[]>('search_events', { input })
+}
+
+export async function exportTaskTrace(input: ExportTaskTraceInput): Promise<ExportTaskTraceResult> {
+  return invoke<ExportTaskTraceResult>('export_task_trace', { input })
+}
+
+export async function listLlmTranscripts(
+  input: ListLlmTranscriptsInput,
+): Promise<LlmTranscriptRecord[]> {
+  return invoke<LlmTranscriptRecord[]>('list_llm_transcripts', { input })
+}
+
+export async function exportLlmTranscripts(
+  input: ExportLlmTranscriptsInput,
+): Promise<ExportLlmTranscriptsResult> {
+  return invoke<ExportLlmTranscriptsResult>('export_llm_transcripts', { input })
+}
+
 export async function controlExecutionScope(input: ControlExecutionScopeInput): Promise<TaskRecord[]> {
   return invoke<TaskRecord[]>('control_execution_scope', { input })
 }
@@ -196,6 +595,22 @@ export async function setRuntimeFlags(input: SetRuntimeFlagsInput): Promise<Runt
   return invoke<RuntimeFlagsUpdateResult>('set_runtime_flags', { input })
 }
 
+export async function setRuntimeProfile(input: SetRuntimeProfileInput): Promise<RuntimeFlagsUpdateResult> {
+  return invoke<RuntimeFlagsUpdateResult>('set_runtime_profile', { input })
+}
+
+export async function listRuntimeProfiles(): Promise<RuntimeProfile[]> {
+  return invoke<RuntimeProfile[]>('list_runtime_profiles')
+}
+
+export async function exportConfiguration(): Promise<ConfigurationBundle> {
+  return invoke<ConfigurationBundle>('export_configuration')
+}
+
+export async function importConfiguration(input: ImportConfigurationInput): Promise<ImportConfigurationResult> {
+  return invoke<ImportConfigurationResult>('import_configuration', { input })
+}
+
 export async function getProviderSecretStatus(input: GetProviderSecretStatusInput): Promise<ProviderSecretStatus> {
   return invoke<ProviderSecretStatus>('get_provider_secret_status', { input })
 }
@@ -208,6 +623,46 @@ export async function revealProviderSecret(input: RevealProviderSecretInput): Pr
   return invoke<RevealProviderSecretResult>('reveal_provider_secret', { input })
 }
 
+export async function listProviderCredentials(input: ListProviderCredentialsInput): Promise<ProviderCredential[]> {
+  return invoke<ProviderCredential[]>('list_provider_credentials', { input })
+}
+
+export async function listSecretAccessLog(input: ListSecretAccessLogInput): Promise<AuditLogEntry[]> {
+  return invoke<AuditLogEntry[]>('list_secret_access_log', { input })
+}
+
+export async function rotateProviderSecret(input: RotateProviderSecretInput): Promise<SecretRotationResult> {
+  return invoke<SecretRotationResult>('rotate_provider_secret', { input })
+}
+
+export async function validateProviderSecret(input: ValidateProviderSecretInput): Promise<ProviderSecretValidation> {
+  return invoke<ProviderSecretValidation>('validate_provider_secret', { input })
+}
+
+export async function migrateSecretBackend(input: MigrateSecretBackendInput): Promise<SecretMigrationResult> {
+  return invoke<SecretMigrationResult>('migrate_secret_backend', { input })
+}
+
 export async function archiveTelemetry(input: ArchiveTelemetryInput): Promise<ArchiveTelemetryResult> {
   return invoke<ArchiveTelemetryResult>('archive_telemetry', { input })
 }
+
+export async function importTelemetryArchive(input: ImportTelemetryArchiveInput): Promise<ImportTelemetryArchiveResult> {
+  return invoke<ImportTelemetryArchiveResult>('import_telemetry_archive', { input })
+}
+
+export async function backupDatabase(): Promise<DatabaseBackup> {
+  return invoke<DatabaseBackup>('backup_database')
+}
+
+export async function listDatabaseBackups(): Promise<DatabaseBackup[]> {
+  return invoke<DatabaseBackup[]>('list_database_backups')
+}
+
+export async function restoreDatabase(input: RestoreDatabaseInput): Promise<RestoreDatabaseResult> {
+  return invoke<RestoreDatabaseResult>('restore_database', { input })
+}
+
+export async function checkDatabaseIntegrity(): Promise<DatabaseIntegrityReport> {
+  return invoke<DatabaseIntegrityReport>('check_database_integrity')
+}
@@ -18,6 +18,13 @@ export interface TaskRecord {
   retryCount: number
   createdAt: number
   updatedAt: number
+  /** -10..10; higher runs first within a plan. */
+  priority: number
+  /** Wall-clock limit in seconds, counted from `startedAt`. */
+  deadlineSeconds: number | null
+  startedAt: number | null
+  /** Set while an approved plan waits, `pending`, for its execution window. */
+  queuedAt: number | null
 }
 
 export interface CreateTaskInput {
@@ -33,6 +40,7 @@ export interface UserObjectiveInput {
   targetProject: string
   globalTokenBudget: number
   maxRiskTolerance: number
+  allowedProtectedPaths?: string[]
 }
 
 export interface TaskAssignment {
@@ -45,6 +53,7 @@ export interface TaskAssignment {
   riskFactor: number
   constraints: string[]
   relevantFiles: string[]
+  priority: number
 }
 
 export interface OrchestrationResult {
@@ -59,6 +68,7 @@ export interface AnalyzeObjectiveInput {
   objective: string
   targetProject: string
   globalTokenBudget: number
+  bypassCache?: boolean
 }
 
 export interface ObjectiveAnalysis {
@@ -67,6 +77,19 @@ export interface ObjectiveAnalysis {
   initialAnalysis: string
   suggestedApproach: string
   fileTreeSummary: string
+  similarRuns: RunMemory[]
+}
+
+export interface RunMemory {
+  rootTaskId: string
+  targetProject: string
+  objective: string
+  planSummary: string
+  outcome: string
+  status: string
+  keyDiffs: string[]
+  recordedAt: number
+  score: number
 }
 
 export interface GeneratePlanInput {
@@ -76,6 +99,7 @@ export interface GeneratePlanInput {
   targetProject: string
   globalTokenBudget: number
   maxRiskTolerance: number
+  bypassCache?: boolean
 }
 
 export interface GeneratedPlan {
@@ -91,8 +115,11 @@ export interface ApproveOrchestrationPlanInput {
   rootTaskId: string
   targetProject: string
   topK?: number
+  mcpServer?: string
+  domainMcpServers?: Record<string, string>
   mcpCommand?: string
   mcpArgs?: string[]
+  allowedProtectedPaths?: string[]
 }
 
 export interface MutationSummary {
@@ -120,6 +147,7 @@ export interface ExecuteDomainTaskInput {
   taskId: string
   targetProject: string
   topK?: number
+  mcpServer?: string
   mcpCommand?: string
   mcpArgs?: string[]
 }
@@ -134,6 +162,8 @@ export interface DiffProposal {
   intentHash: string
   confidence: number
   tokensUsed: number
+  modifiedContent?: string | null
+  baseChecksum?: string | null
 }
 
 export interface ConflictReport {
@@ -175,9 +205,33 @@ export interface MutationRecord {
   status: string
   proposedAt: number
   appliedAt: number | null
+  parentMutationId: string | null
+  modifiedContent: string | null
+  baseChecksum: string | null
+  approvedHunks: string | null
+  rejectedHunksDiff: string | null
+}
+
+export type MutationStatus =
+  | 'proposed'
+  | 'validated'
+  | 'validated_no_tests'
+  | 'applied'
+  | 'rejected'
+  | 'stale_base'
+  | 'expired'
+
+export interface PurgeMutationsInput {
+  statuses?: MutationStatus[]
+  olderThanDays?: number
+  rootTaskId?: string
+  dryRun?: boolean
 }
 
-export type MutationStatus = 'proposed' | 'validated' | 'validated_no_tests' | 'applied' | 'rejected'
+export interface PurgeMutationsResult {
+  mutationIds: string[]
+  dryRun: boolean
+}
 
 export interface SetMutationStatusInput {
   mutationId: string
@@ -186,6 +240,7 @@ export interface SetMutationStatusInput {
   testExitCode?: number
   rejectionReason?: string
   rejectedAtStep?: string
+  approvedHunks?: number[]
 }
 
 export interface RequestMutationRevisionInput {
@@ -193,6 +248,14 @@ export interface RequestMutationRevisionInput {
   note: string
 }
 
+export interface EditMutationDiffInput {
+  mutationId: string
+  diffContent?: string
+  modifiedContent?: string
+  targetProject?: string
+  intentDescription?: string
+}
+
 export interface MutationRevisionResult {
   originalMutation: MutationRecord
   revisedTask: TaskRecord
@@ -205,6 +268,84 @@ export interface RunMutationPipelineInput {
   tier1Approved: boolean
   ciCommand?: string
   ciArgs?: string[]
+  maxRepairAttempts?: number
+  useTaskBranch?: boolean
+  approvedBy?: string[]
+  limits?: PipelineLimitsInput
+  acceptPartialAlignment?: boolean
+  mcpServer?: string
+}
+
+export interface PipelineLimitsInput {
+  testTimeoutSecs?: number
+  staticCheckTimeoutSecs?: number
+  applyTimeoutSecs?: number
+  maxOutputKb?: number
+  ciEnv?: Record<string, string>
+}
+
+export type FinalizeBranchAction = 'merge' | 'discard'
+
+export interface FinalizeBranchInput {
+  targetProject: string
+  rootTaskId: string
+  action: FinalizeBranchAction
+}
+
+export interface FinalizeBranchResult {
+  branch: string
+  action: FinalizeBranchAction
+  details: string
+}
+
+export type GitProvider = 'github' | 'gitlab'
+
+export interface OpenPullRequestInput {
+  targetProject: string
+  rootTaskId: string
+  provider?: GitProvider
+  remote?: string
+  baseBranch?: string
+  title?: string
+  draft?: boolean
+}
+
+export interface OpenPullRequestResult {
+  provider: GitProvider
+  branch: string
+  baseBranch: string
+  url: string
+  number?: number
+}
+
+export interface RebaseMutationInput {
+  mutationId: string
+  targetProject: string
+  useTaskBranch?: boolean
+}
+
+export interface CleanShadowCacheInput {
+  targetProject?: string
+}
+
+export interface PurgeShadowDirsInput {
+  paths?: string[]
+  includeCache?: boolean
+}
+
+export interface ShadowCleanupResult {
+  removedEntries: number
+  freedBytes: number
+}
+
+export type ShadowDirKind = 'workspace' | 'cache'
+
+export interface ShadowDirEntry {
+  path: string
+  kind: ShadowDirKind
+  sizeBytes: number
+  modifiedAt: number
+  inUse: boolean
 }
 
 export interface PipelineStepResult {
@@ -220,6 +361,34 @@ export interface MutationPipelineResult {
   shadowDir: string | null
 }
 
+export interface RunMutationPipelineBatchInput {
+  taskId: string
+  targetProject: string
+  tier1Approved: boolean
+  ciCommand?: string
+  ciArgs?: string[]
+  useTaskBranch?: boolean
+  approvedBy?: string[]
+  limits?: PipelineLimitsInput
+  acceptPartialAlignment?: boolean
+  mcpServer?: string
+}
+
+export interface BatchMutationResult {
+  mutationId: string
+  filePath: string
+  outcome: MutationStatus | 'skipped' | 'error'
+  details: string
+  steps: PipelineStepResult[]
+}
+
+export interface MutationPipelineBatchResult {
+  taskId: string
+  results: BatchMutationResult[]
+  summary: Record<string, number>
+  shadowDir: string | null
+}
+
 export interface AuditLogEntry {
   id: number
   timestamp: number
@@ -234,6 +403,13 @@ export interface ListAuditLogInput {
   limit?: number
 }
 
+export interface ListSecretAccessLogInput {
+  provider?: string
+  accessor?: string
+  since?: number
+  limit?: number
+}
+
 export interface ListAgentTerminalsInput {
   rootTaskId?: string
   includeDescendants?: boolean
@@ -279,6 +455,18 @@ export interface ControlTaskInput {
   reason?: string
 }
 
+export interface SetTaskPriorityInput {
+  taskId: string
+  /** -10..10 */
+  priority: number
+}
+
+export interface SetTaskDeadlineInput {
+  taskId: string
+  /** null removes the deadline. */
+  deadlineSeconds: number | null
+}
+
 export interface ListTaskActivityInput {
   taskId: string
   includeDescendants?: boolean
@@ -320,6 +508,32 @@ export interface ListTaskBudgetRequestsInput {
   limit?: number
 }
 
+export interface ImageAttachment {
+  name: string
+  /** `image/png`, `image/jpeg`, `image/webp` or `image/gif`; inferred from a data URL when empty. */
+  mediaType: string
+  /** Raw base64 or a `data:` URL. */
+  dataBase64: string
+}
+
+export interface AttachObjectiveImagesInput {
+  taskId: string
+  images: ImageAttachment[]
+}
+
+export interface ListTaskAttachmentsInput {
+  taskId: string
+}
+
+export interface TaskAttachmentRecord {
+  id: string
+  taskId: string
+  name: string
+  mediaType: string
+  sizeBytes: number
+  createdAt: number
+}
+
 export interface ResolveTaskBudgetRequestInput {
   requestId: string
   decision: BudgetRequestDecision
@@ -329,6 +543,31 @@ export interface ResolveTaskBudgetRequestInput {
   resumeTask?: boolean
 }
 
+/** Auto-approves budget requests it admits; unset limits do not restrict. */
+export interface BudgetPolicyRecord {
+  name: string
+  maxIncrement: number | null
+  /** Cap on all approved increments within one root task's tree. */
+  maxTotalPerRoot: number | null
+  /** Comma-separated tiers; empty allows every tier. */
+  allowedTiers: string
+  maxRisk: number | null
+  enabled: boolean
+  createdAt: number
+  updatedAt: number
+}
+
+export interface SetBudgetPolicyInput {
+  name: string
+  maxIncrement?: number
+  maxTotalPerRoot?: number
+  allowedTiers?: number[]
+  maxRisk?: number
+  enabled?: boolean
+  /** Deletes the policy instead of saving it. */
+  remove?: boolean
+}
+
 export interface UpdateTaskStatusInput {
   taskId: string
   status: TaskStatus
@@ -338,6 +577,7 @@ export interface UpdateTaskStatusInput {
 export interface ListTargetDirInput {
   targetProject: string
   dirPath?: string
+  mcpServer?: string
   mcpCommand?: string
   mcpArgs?: string[]
 }
@@ -345,6 +585,7 @@ export interface ListTargetDirInput {
 export interface ReadTargetFileInput {
   targetProject: string
   filePath: string
+  mcpServer?: string
   mcpCommand?: string
   mcpArgs?: string[]
 }
@@ -353,6 +594,7 @@ export interface SearchTargetFilesInput {
   targetProject: string
   pattern: string
   limit?: number
+  mcpServer?: string
   mcpCommand?: string
   mcpArgs?: string[]
 }
@@ -396,6 +638,58 @@ export interface SearchResult {
   warnings: string[]
 }
 
+export interface WriteTargetFileInput {
+  targetProject: string
+  filePath: string
+  content: string
+  dryRun?: boolean
+  allowedProtectedPaths?: string[]
+  mcpServer?: string
+  mcpCommand?: string
+  mcpArgs?: string[]
+}
+
+export interface ApplyTargetPatchInput {
+  targetProject: string
+  patch: string
+  dryRun?: boolean
+  allowedProtectedPaths?: string[]
+  mcpServer?: string
+  mcpCommand?: string
+  mcpArgs?: string[]
+}
+
+export interface WriteResult {
+  root: string
+  paths: string[]
+  dryRun: boolean
+  applied: boolean
+  bytesWritten: number | null
+  source: string
+  warnings: string[]
+}
+
+export interface RunTargetCommandInput {
+  targetProject: string
+  command: string
+  args?: string[]
+  timeoutSecs?: number
+  taskId?: string
+  actor?: string
+  mcpServer?: string
+  mcpCommand?: string
+  mcpArgs?: string[]
+}
+
+export interface CommandResult {
+  exitCode: number | null
+  stdout: string
+  stderr: string
+  timedOut: boolean
+  durationMs: number
+  source: string
+}
+
 export interface IndexTargetProjectInput {
   targetProject: string
 }
@@ -406,19 +700,150 @@ export interface IndexProjectResult {
   indexedFiles: number
   indexedChunks: number
   indexPath: string
+  embeddingProvider: string
+  dimension: number
+  skipped: number
+  updated: number
+  removed: number
+}
+
+export interface IndexJobInput {
+  jobId: string
+}
+
+export interface IndexJobStatus {
+  jobId: string
+  targetProject: string
+  state: 'running' | 'completed' | 'failed' | 'cancelled'
+  phase: 'scanning' | 'embedding' | 'writing'
+  filesProcessed: number
+  totalFiles: number
+  chunksEmbedded: number
+  totalChunks: number
+  startedAt: number
+  finishedAt: number | null
+  result: IndexProjectResult | null
+  error: string | null
+}
+
+export interface IndexedProjectStats {
+  targetProject: string
+  tableName: string | null
+  embeddingProvider: string | null
+  dimension: number | null
+  chunkCount: number
+  fileCount: number
+  indexedAt: number | null
+  exists: boolean
+  watching: boolean
+}
+
+export interface DeleteProjectIndexResult {
+  targetProject: string
+  deletedChunks: number
+  deletedFiles: number
+}
+
+export interface IndexWatchInput {
+  targetProject: string
+}
+
+export interface IndexWatchStatus {
+  targetProject: string
+  watching: boolean
+  startedAt: number
+  lastIndexedAt: number | null
+  lastError: string | null
+  reindexCount: number
 }
 
 export interface QueryCodebaseInput {
   targetProject: string
   query: string
   topK?: number
+  pathGlob?: string
+  language?: string
+  chunkType?: string
+  excludeTests?: boolean
 }
 
-export interface ModelProfile {
-  provider: string
-  modelId: string
+export interface QueryGitHistoryInput {
+  targetProject: string
+  query?: string
+  targetFiles?: string[]
+  limit?: number
+}
+
+export interface IndexGitHistoryResult {
+  targetProject: string
+  commits: number
+  indexedCommits: number
+  removedCommits: number
+  embeddingProvider: string
+}
+
+export interface GitHistoryEntry {
+  commitHash: string
+  filePath: string | null
+  author: string
+  committedAt: number
+  subject: string
+  summary: string
+  score: number
+}
+
+export interface SearchSymbolsInput {
+  targetProject: string
+  symbol: string
+  limit?: number
+}
+
+export interface SymbolLocation {
+  filePath: string
+  startLine: number
+  endLine: number
+  kind: string
+  name: string
+  snippet: string
+}
+
+export interface SymbolSearchResult {
+  targetProject: string
+  symbol: string
+  definitions: SymbolLocation[]
+  references: SymbolLocation[]
+}
+
+export interface GenerationParams {
   temperature?: number | null
+  topP?: number | null
   maxOutputTokens?: number | null
+  stop?: string[]
+}
+
+export interface ModelPricing {
+  inputPerMtok: number
+  outputPerMtok: number
+}
+
+export interface ModelProfile extends GenerationParams {
+  provider: string
+  modelId: string
+  pricing?: ModelPricing | null
+  maxContextTokens?: number | null
+  capabilities?: string[]
+  baseUrl?: string | null
+  apiVersion?: string | null
+  deployment?: string | null
+  apiKeyEnv?: string | null
+  /** Vault key such as `openai/work`; takes precedence over `apiKeyEnv`. */
+  credential?: string | null
+}
+
+export interface ProviderLimits {
+  requestsPerMinute?: number | null
+  burst?: number | null
+  maxConcurrent?: number | null
 }
 
 export interface ModelRoutingConfig {
@@ -426,6 +851,9 @@ export interface ModelRoutingConfig {
   defaultProvider: string
   tiers: Record<string, ModelProfile[]>
   personaOverrides: Record<string, ModelProfile[]>
+  providerLimits: Record<string, ProviderLimits>
+  roleGeneration: Record<string, GenerationParams>
+  catalog?: ModelProfile[]
 }
 
 export interface ModelRegistrySnapshot {
@@ -435,6 +863,290 @@ export interface ModelRegistrySnapshot {
   config: ModelRoutingConfig
 }
 
+/** Exactly one of `tier` or `persona`. */
+export interface RegistryModelSlot {
+  tier?: number
+  persona?: string
+}
+
+export interface AddRegistryModelInput extends RegistryModelSlot {
+  profile: ModelProfile
+  position?: number
+}
+
+export interface UpdateRegistryModelInput extends RegistryModelSlot {
+  provider: string
+  modelId: string
+  profile: ModelProfile
+}
+
+export interface RemoveRegistryModelInput extends RegistryModelSlot {
+  provider: string
+  modelId: string
+}
+
+export interface DiscoverProviderModelsInput {
+  merge?: boolean
+}
+
+export interface DiscoveredModel {
+  provider: string
+  modelId: string
+  capabilities: string[]
+  pricing: ModelPricing | null
+  routed: boolean
+  configured: boolean
+}
+
+export interface ProviderModelList {
+  provider: string
+  models: DiscoveredModel[]
+  error: string | null
+}
+
+export interface DeprecatedModelReference {
+  provider: string
+  modelId: string
+  slot: string
+}
+
+export interface DiscoverProviderModelsResult {
+  providers: ProviderModelList[]
+  deprecated: DeprecatedModelReference[]
+  merged: number
+}
+
+export interface BenchmarkModelRef {
+  provider: string
+  modelId: string
+}
+
+export interface BenchmarkModelsInput {
+  models?: BenchmarkModelRef[]
+  fixtures?: string[]
+}
+
+export interface BenchmarkRun {
+  provider: string
+  modelId: string
+  fixture: string
+  applied: boolean
+  diffQuality: number
+  latencyMs: number
+  tokensUsed: number
+  costUsd: number | null
+  score: number
+  error: string | null
+}
+
+export interface ModelBenchmarkSummary {
+  provider: string
+  modelId: string
+  runs: number
+  applyRate: number
+  avgDiffQuality: number
+  avgLatencyMs: number
+  totalCostUsd: number
+  score: number
+  health: ModelHealthRecord | null
+}
+
+export interface BenchmarkModelsResult {
+  runs: BenchmarkRun[]
+  summaries: ModelBenchmarkSummary[]
+}
+
+export type ModelSelectionPolicy = 'ucb' | 'epsilon_greedy' | 'static'
+
+export interface ModelArmRecord {
+  tier: number
+  persona: string
+  skill: string
+  provider: string
+  modelId: string
+  pulls: number
+  rewardSum: number
+  updatedAt: number
+}
+
+export interface ListModelArmsInput {
+  tier?: number
+  limit?: number
+}
+
+export interface ModelPinRecord {
+  tier: number
+  /** Empty matches any persona. */
+  persona: string
+  /** Empty matches any skill. */
+  skill: string
+  provider: string
+  modelId: string
+  createdAt: number
+}
+
+export interface SetModelPinInput {
+  tier: number
+  persona?: string | null
+  skill?: string | null
+  /** Leave both unset to remove the pin. */
+  provider?: string | null
+  modelId?: string | null
+}
+
+export type ModelExperimentStatus = 'running' | 'stopped'
+
+export interface ModelExperimentRecord {
+  id: string
+  name: string
+  tier: number
+  /** Empty matches any persona. */
+  persona: string
+  /** Empty matches any skill. */
+  skill: string
+  controlProvider: string
+  controlModelId: string
+  variantProvider: string
+  variantModelId: string
+  variantPercent: number
+  status: ModelExperimentStatus
+  createdAt: number
+  updatedAt: number
+}
+
+export interface CreateModelExperimentInput {
+  name: string
+  tier: number
+  persona?: string | null
+  skill?: string | null
+  controlProvider: string
+  controlModelId: string
+  variantProvider: string
+  variantModelId: string
+  /** 1 to 99. */
+  variantPercent: number
+}
+
+export interface ModelExperimentIdInput {
+  experimentId: string
+}
+
+export interface ExperimentArmStats {
+  arm: 'control' | 'variant'
+  provider: string
+  modelId: string
+  selections: number
+  tasks: number
+  applied: number
+  rejected: number
+  applyRate: number | null
+  totalCostUsd: number
+  avgCostUsdPerTask: number | null
+  avgLatencyMs: number | null
+}
+
+export interface ModelExperimentComparison {
+  experiment: ModelExperimentRecord
+  arms: ExperimentArmStats[]
+}
+
+export type PromptTemplateSource = 'builtin' | 'user' | 'project'
+
+export interface PromptTemplate {
+  name: string
+  description: string
+  source: PromptTemplateSource
+  version: number
+  content: string
+  path: string | null
+  builtinVersion: number
+}
+
+export interface GetPromptTemplatesInput {
+  targetProject?: string | null
+}
+
+export interface SetPromptTemplateInput {
+  name: string
+  content: string
+  targetProject?: string | null
+}
+
+export type McpTransport = 'stdio' | 'http' | 'sse'
+
+export interface McpServerConfig {
+  name: string
+  transport: McpTransport
+  command: string
+  args: string[]
+  env: Record<string, string>
+  url: string | null
+  headers: Record<string, string>
+  authSecret: string | null
+  allowedTools: string[]
+  allowedDomains: string[]
+  updatedAt: number
+}
+
+export interface SaveMcpServerInput {
+  name: string
+  transport?: McpTransport
+  command?: string
+  args?: string[]
+  env?: Record<string, string>
+  url?: string
+  headers?: Record<string, string>
+  authSecret?: string
+  allowedTools?: string[]
+  allowedDomains?: string[]
+}
+
+export interface RemoveMcpServerInput {
+  name: string
+}
+
+export interface McpToolSchema {
+  name: string
+  description: string | null
+  inputSchema: Record<string, unknown>
+}
+
+export interface ListMcpToolsInput {
+  mcpServer: string
+  refresh?: boolean
+}
+
+export interface McpToolCatalog {
+  server: string
+  tools: McpToolSchema[]
+  fetchedAt: number
+  cached: boolean
+}
+
+export interface SubscribeMcpResourcesInput {
+  mcpServer: string
+  /** Defaults to every resource the server lists. */
+  uris?: string[]
+  targetProject?: string
+  /** Reindex `targetProject` incrementally when a watched resource changes. */
+  reindex?: boolean
+}
+
+export interface UnsubscribeMcpResourcesInput {
+  mcpServer: string
+}
+
+export interface McpResourceSubscription {
+  server: string
+  uris: string[]
+  targetProject: string | null
+  reindex: boolean
+  subscribedAt: number
+  lastUpdateAt: number | null
+  lastUpdatedUri: string | null
+  updateCount: number
+}
+
 export interface AgentRunRecord {
   id: string
   rootTaskId: string | null
@@ -483,6 +1195,96 @@ export interface AgentEventRecord {
   createdAt: number
 }
 
+export interface LlmTranscriptRecord {
+  id: string
+  rootTaskId: string | null
+  taskId: string | null
+  provider: string
+  modelId: string
+  resolvedModel: string | null
+  systemPrompt: string
+  userPrompt: string
+  responseSchemaJson: string | null
+  responseText: string | null
+  error: string | null
+  inputTokens: number | null
+  outputTokens: number | null
+  latencyMs: number
+  truncated: boolean
+  createdAt: number
+}
+
+export interface ListLlmTranscriptsInput {
+  rootTaskId?: string
+  taskId?: string
+  limit?: number
+}
+
+export interface ExportLlmTranscriptsInput {
+  rootTaskId?: string
+  taskId?: string
+  outputPath: string
+}
+
+export interface ExportLlmTranscriptsResult {
+  outputPath: string
+  transcriptsExported: number
+}
+
+export interface GetTaskCostRollupInput {
+  rootTaskId: string
+}
+
+export interface ModelCostRollup {
+  provider: string | null
+  modelId: string | null
+  calls: number
+  tokensIn: number
+  tokensOut: number
+  costUsd: number
+}
+
+export interface TaskCostRollup {
+  rootTaskId: string
+  calls: number
+  tokensIn: number
+  tokensOut: number
+  totalCostUsd: number
+  byModel: ModelCostRollup[]
+}
+
+export interface GetTaskTimelineInput {
+  rootTaskId: string
+  /** Defaults to true. */
+  includeEvents?: boolean
+  /** Most recent agent events kept; defaults to 500. */
+  eventLimit?: number
+}
+
+export type TimelineEntryKind = 'task' | 'run' | 'event' | 'mutation' | 'pipeline_step' | 'budget_request'
+
+export interface TimelineEntry {
+  kind: TimelineEntryKind
+  id: string
+  taskId: string | null
+  /** Parent task, owning task or run, or the mutation of a pipeline step. */
+  parentId: string | null
+  label: string
+  status: string | null
+  startedAt: number
+  /** Null for instants and for tasks and runs still open. */
+  endedAt: number | null
+  detail: string | null
+}
+
+export interface TaskTimeline {
+  rootTaskId: string
+  startedAt: number
+  endedAt: number
+  /** Chronological. */
+  entries: TimelineEntry[]
+}
+
 export interface ModelHealthRecord {
   provider: string
   modelId: string
@@ -495,6 +1297,64 @@ export interface ModelHealthRecord {
   lastError: string | null
   lastUsedAt: number | null
   updatedAt: number
+  consecutiveFailures: number
+  circuitOpenUntil: number | null
+  timeoutCalls: number
+}
+
+export interface ProviderQueueMetrics {
+  provider: string
+  requestsPerMinute: number | null
+  maxConcurrent: number | null
+  inFlight: number
+  queued: number
+  totalRequests: number
+  throttledRequests: number
+  avgWaitMs: number
+  maxWaitMs: number
+}
+
+export type ExecutionLane = 'root_orchestration' | 'specialist' | 'pipeline_run'
+
+export interface ExecutionLaneStatus {
+  lane: ExecutionLane
+  /** Null when the lane is uncapped. */
+  limit: number | null
+  perProjectLimit: number | null
+  running: number
+  queued: number
+}
+
+export interface QueuedExecution {
+  lane: ExecutionLane
+  /** Root task, coordinating task or mutation id, depending on the lane. */
+  subjectId: string
+  targetProject: string | null
+  /** 1-based position within the lane. */
+  position: number
+  slots: number
+  queuedAt: number
+}
+
+export interface ExecutionQueueSnapshot {
+  lanes: ExecutionLaneStatus[]
+  queued: QueuedExecution[]
+}
+
+/** An executing run whose task stopped sending heartbeats. */
+export interface StalledRunRecord {
+  runId: string
+  rootTaskId: string | null
+  taskId: string
+  tier: number | null
+  actor: string
+  heartbeatAt: number
+  stalledAt: number
+}
+
+export interface RecoverStalledRunInput {
+  runId: string
+  action: Extract<TaskControlAction, 'stop' | 'restart'>
 }
 
 export interface MissionControlSnapshot {
@@ -502,6 +1362,54 @@ export interface MissionControlSnapshot {
   activeRuns: AgentRunRecord[]
   recentEvents: AgentEventRecord[]
   modelHealth: ModelHealthRecord[]
+  providerQueues: ProviderQueueMetrics[]
+  executionQueue: ExecutionQueueSnapshot
+  stalledRuns: StalledRunRecord[]
+  mcpSessions: McpSessionHealth[]
+}
+
+export interface McpSessionHealth {
+  server: string
+  status: 'starting' | 'healthy' | 'backoff'
+  pid: number | null
+  startedAt: number | null
+  lastPingAt: number | null
+  lastPingMs: number | null
+  consecutiveFailures: number
+  restarts: number
+  lastError: string | null
+  retryAt: number | null
+}
+
+export interface SubscribeMissionControlInput {
+  rootTaskId?: string | null
+  /** Resume after this event id; defaults to the newest event. */
+  sinceId?: number | null
+  /** Defaults to 1000, clamped to 250..30000. */
+  intervalMs?: number | null
+}
+
+export interface MissionControlSubscriptionInput {
+  subscriptionId: string
+}
+
+export interface MissionControlSubscription {
+  subscriptionId: string
+  rootTaskId: string | null
+  /** Last event id already seen; pass back as `sinceId` after a reconnect. */
+  cursor: number
+}
+
+export interface MissionControlUpdate {
+  subscriptionId: string
+  rootTaskId: string | null
+  cursor: number
+  generatedAt: number
+  events: AgentEventRecord[]
+  /** Runs that started or changed status since the previous push. */
+  runs: AgentRunRecord[]
+  /** Not scoped to the root task. */
+  modelHealth: ModelHealthRecord[]
 }
 
 export interface GetMissionControlSnapshotInput {
@@ -509,6 +1417,112 @@ export interface GetMissionControlSnapshotInput {
   limit?: number
 }
 
+export interface GetModelHealthTrendsInput {
+  provider?: string | null
+  modelId?: string | null
+  /** Defaults to 24. */
+  windowHours?: number | null
+  /** Defaults to 60; widened to keep at most 500 buckets. */
+  bucketMinutes?: number | null
+}
+
+export interface ModelHealthBucket {
+  bucketStart: number
+  calls: number
+  failures: number
+  timeouts: number
+  /** Null for a bucket without calls. */
+  successRate: number | null
+  latencyP50Ms: number | null
+  latencyP95Ms: number | null
+  costUsd: number
+}
+
+export interface ModelHealthSeries {
+  provider: string
+  modelId: string
+  calls: number
+  successRate: number
+  latencyP50Ms: number | null
+  latencyP95Ms: number | null
+  totalCostUsd: number
+  longestFailureStreak: number
+  currentFailureStreak: number
+  buckets: ModelHealthBucket[]
+}
+
+export interface ModelHealthTrends {
+  windowStart: number
+  windowEnd: number
+  bucketSecs: number
+  series: ModelHealthSeries[]
+}
+
+export type CostGroupBy = 'root_task' | 'provider' | 'model' | 'tier' | 'day'
+
+export interface GetCostBreakdownInput {
+  groupBy: CostGroupBy
+  /** Unix seconds; defaults to `days` before `until`. */
+  since?: number | null
+  /** Unix seconds; defaults to now. */
+  until?: number | null
+  /** Defaults to 7. */
+  days?: number | null
+  rootTaskId?: string | null
+  limit?: number | null
+}
+
+export interface CostBreakdownRow {
+  key: string
+  /** Root task objective when grouped by root task. */
+  label: string | null
+  calls: number
+  tokensIn: number
+  tokensOut: number
+  costUsd: number
+}
+
+export interface CostBreakdown {
+  groupBy: CostGroupBy
+  since: number
+  until: number
+  calls: number
+  tokensIn: number
+  tokensOut: number
+  totalCostUsd: number
+  rows: CostBreakdownRow[]
+}
+
+export type UsageBucket = 'hour' | 'day'
+
+export interface GetTokenUsageTrendsInput {
+  /** Defaults to 14. */
+  days?: number | null
+  bucket?: UsageBucket
+  provider?: string | null
+  modelId?: string | null
+}
+
+export interface TokenUsagePoint {
+  bucketStart: number
+  calls: number
+  tokensIn: number
+  tokensOut: number
+  costUsd: number
+}
+
+export interface TokenUsageTrends {
+  bucket: UsageBucket
+  since: number
+  until: number
+  points: TokenUsagePoint[]
+}
+
+export interface AgentRunCursor {
+  startedAt: number
+  id: string
+}
+
 export interface ListAgentRunsInput {
   rootTaskId?: string
   taskId?: string
@@ -516,6 +1530,16 @@ export interface ListAgentRunsInput {
   tier?: number
   status?: string
   limit?: number
+  /** Unix seconds, inclusive. */
+  startedAfter?: number
+  /** Unix seconds, exclusive. */
+  startedBefore?: number
+  /** Last run of the previous page; continues with older runs. */
+  before?: AgentRunCursor
+  /** First run of the previous page; goes back to newer runs. */
+  after?: AgentRunCursor
+  /** Also read runs moved to archive files by retention. */
+  includeArchived?: boolean
 }
 
 export interface ListAgentEventsInput {
@@ -525,6 +1549,66 @@ export interface ListAgentEventsInput {
   action?: string
   sinceId?: number
   limit?: number
+  /** Unix seconds, inclusive. */
+  createdAfter?: number
+  /** Unix seconds, exclusive. */
+  createdBefore?: number
+  /** Events older than this id. */
+  beforeId?: number
+  /** The page of events directly newer than this id. */
+  afterId?: number
+  /** Also read events moved to archive files by retention. */
+  includeArchived?: boolean
+}
+
+export interface AgentEventPage {
+  /** Newest first. */
+  items: AgentEventRecord[]
+  /** Rows matching the filters, ignoring the cursor. */
+  total: number
+  hasMore: boolean
+}
+
+export interface SearchEventsInput {
+  query: string
+  /** Audit entries match when they target a task in this tree. */
+  rootTaskId?: string | null
+  /** Defaults to true. */
+  includeAudit?: boolean | null
+  limit?: number | null
+}
+
+export interface EventSearchHit {
+  source: 'event' | 'audit'
+  id: number
+  createdAt: number
+  actor: string
+  action: string
+  runId: string | null
+  rootTaskId: string | null
+  /** The event's task, or the audit entry's target. */
+  taskId: string | null
+  /** Matching text with hits wrapped in brackets. */
+  snippet: string
+}
+
+export interface ExportTaskTraceInput {
+  rootTaskId: string
+  /** Defaults to the `otlpEndpoint` runtime flag. */
+  endpoint?: string | null
+}
+
+export interface ExportTaskTraceResult {
+  rootTaskId: string
+  traceId: string
+  spanCount: number
+  endpoint: string
+}
+
+export interface AgentRunPage {
+  items: AgentRunRecord[]
+  total: number
+  hasMore: boolean
 }
 
 export type ExecutionScopeType = 'tree' | 'tier' | 'agent'
@@ -542,23 +1626,135 @@ export interface RuntimeFlags {
   devMode: boolean
   modelAdapterEnabled: boolean
   modelAdapterStrict: boolean
+  offlineSpecialists: boolean
   autoApproveBudgetRequests: boolean
   autoCommitMutations: boolean
   budgetHeadroomPercent: number
   budgetAutoMaxPercent: number
   budgetMinIncrement: number
+  /** Org-wide model spend caps in USD per UTC day and month; 0 leaves a period uncapped. */
+  spendCapDailyUsd: number
+  spendCapMonthlyUsd: number
+  spendCapWarnPercent: number
+  /** Local `HH:MM-HH:MM` hours in which approved plans may start; empty allows any time. */
+  executionWindow: string
+  executionWindowAcPower: boolean
+  /** Plans with fewer assignments ignore the window; 0 holds every plan. */
+  executionWindowMinTasks: number
+  /** Desktop notifications for events waiting on a human; each kind can be muted. */
+  notificationsEnabled: boolean
+  notifyPlanReady: boolean
+  notifyConsensusFailed: boolean
+  notifyBudgetRequest: boolean
+  notifyRunFinished: boolean
+  /** Comma-separated URLs that receive run lifecycle webhooks; empty sends none. */
+  webhookUrls: string
   telemetryRetentionDays: number
+  mutationRepairAttempts: number
+  llmMaxRetries: number
+  llmRetryBaseMs: number
+  llmBatchParallelism: number
+  claudeCodeTimeoutSecs: number
+  openaiTimeoutSecs: number
+  ollamaTimeoutSecs: number
+  llmMaxResponseChars: number
+  modelSelectionPolicy: ModelSelectionPolicy
+  banditEpsilonPercent: number
+  modelCostPreference: 'auto' | 'cheap' | 'balanced' | 'best'
+  llmCacheEnabled: boolean
+  llmCacheTtlHours: number
+  llmTranscriptsEnabled: boolean
+  llmTranscriptMaxKb: number
+  circuitBreakerFailures: number
+  circuitBreakerOpenMinutes: number
+  mutationTtlHours: number
+  taskBranchMode: boolean
+  shadowKeepOnFailure: boolean
+  shadowDiskQuotaMb: number
+  shadowSandbox: boolean
+  semanticJudge: boolean
+  pipelineTestTimeoutSecs: number
+  pipelineStaticCheckTimeoutSecs: number
+  pipelineApplyTimeoutSecs: number
+  pipelineMaxOutputKb: number
+  pipelineCiEnv: string[]
+  embeddingProvider: 'hash' | 'openai' | 'ollama' | 'fastembed'
+  embeddingModel: string
+  /** OTLP/HTTP collector for finished task traces; empty disables export. */
+  otlpEndpoint: string
+  metricsEnabled: boolean
+  metricsPort: number
+  /** Serve the HTTP API on 127.0.0.1; takes effect on restart. */
+  httpApiEnabled: boolean
+  httpApiPort: number
+  dbEncryption: boolean
+  /** Switch with `migrateSecretBackend` to take stored secrets along. */
+  secretBackend: SecretBackend
 }
 
 export type SetRuntimeFlagsInput = Partial<RuntimeFlags>
 
+export type SpendCapStatus = 'ok' | 'warning' | 'spend_cap_exceeded'
+
+export interface SpendStatus {
+  dailyUsd: number
+  monthlyUsd: number
+  /** 0 when no daily cap is set. */
+  dailyCapUsd: number
+  /** 0 when no monthly cap is set. */
+  monthlyCapUsd: number
+  warnPercent: number
+  status: SpendCapStatus
+}
+
 export interface RuntimeFlagsUpdateResult {
   flags: RuntimeFlags
   restartRequired: boolean
 }
 
+export interface SetRuntimeProfileInput {
+  name: string
+  saveCurrent?: boolean
+}
+
+export interface RuntimeProfile {
+  name: string
+  builtin: boolean
+  active: boolean
+}
+
+export interface BundledPromptTemplate {
+  name: string
+  content: string
+}
+
+export interface ConfigurationBundle {
+  version: number
+  exportedAt: number
+  runtimeFlags: Partial<RuntimeFlags>
+  modelRouting: ModelRoutingConfig | null
+  promptTemplates: BundledPromptTemplate[]
+  mcpServers: McpServerConfig[]
+  omitted: string[]
+}
+
+export interface ImportConfigurationInput {
+  bundle: ConfigurationBundle
+}
+
+export interface ImportConfigurationResult {
+  runtimeFlags: string[]
+  modelRouting: boolean
+  promptTemplates: string[]
+  mcpServers: string[]
+  omitted: string[]
+  restartRequired: boolean
+}
+
 export interface GetProviderSecretStatusInput {
   provider: string
+  /** Named key such as `work`; the provider's default key when omitted. */
+  name?: string
 }
 
 export interface ProviderSecretStatus {
@@ -570,6 +1766,7 @@ export interface ProviderSecretStatus {
 
 export interface SetProviderSecretInput {
   provider: string
+  name?: string
   secret: string
   sessionToken?: string
 }
@@ -583,14 +1780,64 @@ export interface SecretOperationResult {
 
 export interface RevealProviderSecretInput {
   provider: string
+  name?: string
   sessionToken?: string
 }
 
+export interface ListProviderCredentialsInput {
+  provider?: string
+}
+
+export interface ProviderCredential {
+  reference: string
+  provider: string
+  name: string | null
+}
+
+export interface RotateProviderSecretInput {
+  provider: string
+  name?: string
+  secret: string
+  skipValidation?: boolean
+}
+
+export interface SecretRotationResult {
+  provider: string
+  validated: boolean
+}
+
+export interface ValidateProviderSecretInput {
+  provider: string
+  name?: string
+  secret?: string
+}
+
+export interface ProviderSecretValidation {
+  provider: string
+  valid: boolean
+  status: number
+  latencyMs: number
+  quotaHeaders: Record<string, string>
+}
+
 export interface RevealProviderSecretResult {
   provider: string
   secret: string
 }
 
+export type SecretBackend = 'stronghold' | 'keychain'
+
+export interface MigrateSecretBackendInput {
+  backend: SecretBackend
+}
+
+export interface SecretMigrationResult {
+  from: SecretBackend
+  to: SecretBackend
+  migrated: string[]
+  leftInSource: string[]
+}
+
 export interface ArchiveTelemetryInput {
   retentionDays?: number
 }
@@ -602,6 +1849,42 @@ export interface ArchiveTelemetryResult {
   archiveFile: string | null
 }
 
+export interface DatabaseBackup {
+  fileName: string
+  path: string
+  sizeBytes: number
+  createdAt: number
+}
+
+export interface RestoreDatabaseInput {
+  fileName: string
+}
+
+export interface RestoreDatabaseResult {
+  fileName: string
+  /** The restore is applied on the next start. */
+  restartRequired: boolean
+}
+
+export interface DatabaseIntegrityReport {
+  ok: boolean
+  integrityMessages: string[]
+  foreignKeyViolations: number
+  checkedAt: number
+}
+
+export interface ImportTelemetryArchiveInput {
+  /** Archive file name; imports every archive when omitted. */
+  fileName?: string
+}
+
+export interface ImportTelemetryArchiveResult {
+  filesImported: string[]
+  eventsImported: number
+  runsImported: number
+  rowsSkipped: number
+}
+
 export interface ContextChunk {
   id: string
   filePath: string
@@ -612,3 +1895,17 @@ export interface ContextChunk {
   content: string
   score: number
 }
+
+export interface LlmStreamEvent {
+  streamId: string
+  taskId: string | null
+  label: string
+  delta: string
+  receivedChars: number
+  state: 'streaming' | 'completed' | 'failed' | 'cancelled'
+  error: string | null
+}
+
+export interface CancelLlmStreamInput {
+  streamId: string
+}
@@ -208,6 +208,10 @@ export function CommandCenterView() {
             retryCount: 0,
             createdAt: Date.now() / 1000,
             updatedAt: Date.now() / 1000,
+            priority: assignment.priority,
+            deadlineSeconds: null,
+            startedAt: null,
+            queuedAt: null,
           })
         }
 
@@ -1,4 +1,4 @@
-import { useState, type FormEvent } from 'react'
+import { useEffect, useState, type FormEvent } from 'react'
 
 import { Button } from '@/components/ui/button'
 import { Card, CardContent, CardHeader, CardTitle } from '@/components/ui/card'
@@ -6,13 +6,53 @@ import { Input } from '@/components/ui/input'
 import { Label } from '@/components/ui/label'
 import { ScrollArea } from '@/components/ui/scroll-area'
 import { useTargetProjectConfig } from '@/hooks/useTargetProjectConfig'
-import { indexTargetProject, listTargetDir, queryCodebase, readTargetFile, searchTargetFiles } from '@/hooks/useTauri'
-import type { ContextChunk, DirectoryEntry, DirectoryListing, IndexProjectResult, SearchResult, TargetFileContent } from '@/types'
+import {
+  cancelIndexJob,
+  deleteProjectIndex,
+  getIndexJobStatus,
+  indexTargetProject,
+  listIndexedProjects,
+  listTargetDir,
+  onIndexJobProgress,
+  queryCodebase,
+  readTargetFile,
+  searchTargetFiles,
+  startIndexWatch,
+  stopIndexWatch,
+} from '@/hooks/useTauri'
+import type {
+  ContextChunk,
+  DirectoryEntry,
+  DirectoryListing,
+  IndexJobStatus,
+  IndexProjectResult,
+  IndexWatchStatus,
+  IndexedProjectStats,
+  SearchResult,
+  TargetFileContent,
+} from '@/types'
 
 function entryIcon(entry: DirectoryEntry): string {
   return entry.isDir ? 'DIR' : 'FILE'
 }
 
+function describeIndexJob(job: IndexJobStatus): string {
+  if (job.phase === 'embedding') {
+    return `Embedding ${job.chunksEmbedded}/${job.totalChunks} chunks`
+  }
+  if (job.phase === 'writing') {
+    return 'Writing index...'
+  }
+  return `Scanning ${job.filesProcessed}/${job.totalFiles} files`
+}
+
+function formatTimestamp(timestamp: number): string {
+  return new Intl.DateTimeFormat(undefined, {
+    dateStyle: 'medium',
+    timeStyle: 'short',
+  }).format(new Date(timestamp * 1000))
+}
+
 export function ContextView() {
   const {
     targetProject,
@@ -31,11 +71,47 @@ export function ContextView() {
   const [semanticQuery, setSemanticQuery] = useState('')
   const [semanticResults, setSemanticResults] = useState<ContextChunk[]>([])
   const [indexResult, setIndexResult] = useState<IndexProjectResult | null>(null)
+  const [indexJob, setIndexJob] = useState<IndexJobStatus | null>(null)
+  const [watchStatus, setWatchStatus] = useState<IndexWatchStatus | null>(null)
+  const [indexedProjects, setIndexedProjects] = useState<IndexedProjectStats[] | null>(null)
 
   const [isBrowsing, setIsBrowsing] = useState(false)
-  const [isIndexing, setIsIndexing] = useState(false)
+  const [isTogglingWatch, setIsTogglingWatch] = useState(false)
+  const [isLoadingProjects, setIsLoadingProjects] = useState(false)
   const [isSemanticSearching, setIsSemanticSearching] = useState(false)
   const [feedback, setFeedback] = useState<string | null>(null)
+  const isIndexing = indexJob?.state === 'running'
+
+  useEffect(() => {
+    const unlisten = onIndexJobProgress((status) => {
+      setIndexJob((current) => (current?.jobId === status.jobId ? status : current))
+    })
+    return () => {
+      void unlisten.then((stop) => stop())
+    }
+  }, [])
+
+  useEffect(() => {
+    if (!indexJob || indexJob.state === 'running') {
+      return
+    }
+    if (indexJob.state === 'completed' && indexJob.result) {
+      const result = indexJob.result
+      setIndexResult(result)
+      setSemanticResults([])
+      setFeedback(
+        `Indexed ${result.indexedFiles} files into ${result.indexedChunks} chunks (${result.tableName}): ${result.updated} updated, ${result.skipped} unchanged, ${result.removed} removed.`,
+      )
+      if (indexedProjects) {
+        void refreshIndexedProjects()
+      }
+    } else if (indexJob.state === 'cancelled') {
+      setFeedback('Indexing cancelled; the previous index is unchanged.')
+    } else {
+      setFeedback(indexJob.error ?? 'Indexing failed.')
+    }
+    setIndexJob(null)
+  }, [indexJob])
 
   async function browseDirectory(dirPath = '.'): Promise<void> {
     const target = targetProject.trim()
@@ -132,17 +208,77 @@ export function ContextView() {
       return
     }
 
-    setIsIndexing(true)
     setFeedback(null)
     try {
-      const result = await indexTargetProject({ targetProject: target })
-      setIndexResult(result)
-      setSemanticResults([])
-      setFeedback(`Indexed ${result.indexedFiles} files into ${result.indexedChunks} chunks (${result.tableName}).`)
+      const started = await indexTargetProject({ targetProject: target })
+      setIndexJob(started)
+      // Small projects can finish before the listener knows the job id.
+      const latest = await getIndexJobStatus({ jobId: started.jobId })
+      setIndexJob((current) => (current?.jobId === latest.jobId ? latest : current))
+    } catch (error) {
+      setFeedback(error instanceof Error ? error.message : String(error))
+    }
+  }
+
+  async function handleCancelIndex() {
+    if (!indexJob) {
+      return
+    }
+    try {
+      await cancelIndexJob({ jobId: indexJob.jobId })
+    } catch (error) {
+      setFeedback(error instanceof Error ? error.message : String(error))
+    }
+  }
+
+  async function refreshIndexedProjects() {
+    setIsLoadingProjects(true)
+    try {
+      setIndexedProjects(await listIndexedProjects())
     } catch (error) {
       setFeedback(error instanceof Error ? error.message : String(error))
     } finally {
-      setIsIndexing(false)
+      setIsLoadingProjects(false)
+    }
+  }
+
+  async function handleDeleteIndex(project: string) {
+    setIsLoadingProjects(true)
+    setFeedback(null)
+    try {
+      const result = await deleteProjectIndex({ targetProject: project })
+      setFeedback(`Deleted index of ${result.targetProject}: ${result.deletedChunks} chunks from ${result.deletedFiles} files.`)
+      if (result.targetProject === indexResult?.targetProject) {
+        setIndexResult(null)
+        setSemanticResults([])
+      }
+      setIndexedProjects(await listIndexedProjects())
+    } catch (error) {
+      setFeedback(error instanceof Error ? error.message : String(error))
+    } finally {
+      setIsLoadingProjects(false)
+    }
+  }
+
+  async function handleToggleIndexWatch() {
+    const target = targetProject.trim()
+    if (!target) {
+      setFeedback('Target project path is required.')
+      return
+    }
+
+    setIsTogglingWatch(true)
+    setFeedback(null)
+    try {
+      const status = watchStatus?.watching
+        ? await stopIndexWatch({ targetProject: target })
+        : await startIndexWatch({ targetProject: target })
+      setWatchStatus(status)
+      setFeedback(status.watching ? 'Watching project; the index updates as files change.' : 'Stopped watching project.')
+    } catch (error) {
+      setFeedback(error instanceof Error ? error.message : String(error))
+    } finally {
+      setIsTogglingWatch(false)
     }
   }
 
@@ -231,8 +367,20 @@ export function ContextView() {
               <Button disabled={isIndexing} onClick={() => void handleIndexProject()} type="button" variant="outline">
                 {isIndexing ? 'Indexing...' : 'Index Project'}
               </Button>
+              {isIndexing ? (
+                <Button onClick={() => void handleCancelIndex()} type="button" variant="ghost">
+                  Cancel
+                </Button>
+              ) : null}
+              <Button disabled={isTogglingWatch} onClick={() => void handleToggleIndexWatch()} type="button" variant="outline">
+                {watchStatus?.watching ? 'Stop Watching' : 'Watch Changes'}
+              </Button>
               <span className="text-muted-foreground self-center text-sm">
-                {indexResult ? `${indexResult.indexedFiles} files / ${indexResult.indexedChunks} chunks` : 'Index not run'}
+                {indexJob && isIndexing
+                  ? describeIndexJob(indexJob)
+                  : indexResult
+                    ? `${indexResult.indexedFiles} files / ${indexResult.indexedChunks} chunks`
+                    : 'Index not run'}
               </span>
             </div>
           </form>
@@ -377,6 +525,48 @@ export function ContextView() {
           </CardContent>
         </Card>
       </div>
+
+      <Card>
+        <CardHeader className="flex flex-row items-center justify-between">
+          <CardTitle>Indexed Projects</CardTitle>
+          <Button disabled={isLoadingProjects} onClick={() => void refreshIndexedProjects()} size="sm" type="button" variant="outline">
+            {isLoadingProjects ? 'Loading...' : indexedProjects ? 'Refresh' : 'Load'}
+          </Button>
+        </CardHeader>
+        <CardContent className="space-y-2">
+          {indexedProjects?.map((project) => (
+            <div className="flex items-center justify-between gap-2 rounded-md border p-2" key={project.targetProject}>
+              <div className="min-w-0">
+                <p className="truncate font-medium">{project.targetProject}</p>
+                <p className="text-muted-foreground text-xs">
+                  {project.fileCount} files / {project.chunkCount} chunks | {project.embeddingProvider ?? 'unknown provider'}
+                  {project.indexedAt ? ` | indexed ${formatTimestamp(project.indexedAt)}` : ''}
+                  {project.watching ? ' | watching' : ''}
+                  {project.exists ? '' : ' | directory missing'}
+                </p>
+              </div>
+              <div className="flex shrink-0 gap-2">
+                {project.exists ? (
+                  <Button onClick={() => setTargetProject(project.targetProject)} size="sm" type="button" variant="ghost">
+                    Select
+                  </Button>
+                ) : null}
+                <Button
+                  disabled={isLoadingProjects}
+                  onClick={() => void handleDeleteIndex(project.targetProject)}
+                  size="sm"
+                  type="button"
+                  variant="destructive"
+                >
+                  Delete
+                </Button>
+              </div>
+            </div>
+          ))}
+          {indexedProjects?.length === 0 ? <p className="text-muted-foreground text-sm">No projects are indexed.</p> : null}
+          {!indexedProjects ? <p className="text-muted-foreground text-sm">Load to see indexed projects.</p> : null}
+        </CardContent>
+      </Card>
     </div>
   )
 }
@@ -1,5 +1,6 @@
 import { useCallback, useEffect, useMemo, useState, type FormEvent } from 'react'
 
+import LlmStreamPanel from '@/components/LlmStreamPanel'
 import OrchestrationProgress from '@/components/OrchestrationProgress'
 import OrchestrationSummary from '@/components/OrchestrationSummary'
 import TaskActivityFeed from '@/components/TaskActivityFeed'
@@ -541,6 +542,8 @@ export function DashboardView() {
             )
           ) : null}
 
+          {isAnalyzing ? <LlmStreamPanel label="analysis" /> : null}
+
           {isAnalyzing ? (
             monitoredTaskId ? (
               <OrchestrationProgress taskId={monitoredTaskId} flow="analyze" />
@@ -566,6 +569,28 @@ export function DashboardView() {
                   <p className="text-muted-foreground text-sm">{analysisResult.suggestedApproach}</p>
                 </div>
               ) : null}
+              {analysisResult.similarRuns.length > 0 ? (
+                <details className="text-xs">
+                  <summary className="cursor-pointer text-sm font-semibold">
+                    Similar Past Runs ({analysisResult.similarRuns.length})
+                  </summary>
+                  <div className="mt-2 space-y-2">
+                    {analysisResult.similarRuns.map((run) => (
+                      <div className="rounded bg-muted p-2" key={run.rootTaskId}>
+                        <p className="font-medium">
+                          {run.objective}{' '}
+                          <span className="text-muted-foreground">
+                            ({run.status}, {(run.score * 100).toFixed(0)}% similar)
+                          </span>
+                        </p>
+                        <pre className="text-muted-foreground mt-1 whitespace-pre-wrap text-[11px]">
+                          {run.planSummary}
+                        </pre>
+                      </div>
+                    ))}
+                  </div>
+                </details>
+              ) : null}
               {analysisResult.fileTreeSummary ? (
                 <details className="text-xs">
                   <summary className="cursor-pointer text-sm font-semibold">
@@ -611,6 +636,8 @@ export function DashboardView() {
             </div>
           ) : null}
 
+          {isGeneratingPlan ? <LlmStreamPanel label="plan" taskId={analysisResult?.rootTaskId} /> : null}
+
           {isGeneratingPlan ? (
             monitoredTaskId ? (
               <OrchestrationProgress taskId={monitoredTaskId} flow="generate-plan" />
@@ -694,6 +721,8 @@ export function DashboardView() {
                 </Button>
               </div>
 
+              {isApprovingPlan ? <LlmStreamPanel label="specialist" /> : null}
+
               {isApprovingPlan ? (
                 monitoredTaskId ? (
                   <OrchestrationProgress taskId={monitoredTaskId} flow="approve-execute" />
@@ -10,13 +10,22 @@ import { Label } from '@/components/ui/label'
 import { ScrollArea } from '@/components/ui/scroll-area'
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '@/components/ui/select'
 import { Tabs, TabsContent, TabsList, TabsTrigger } from '@/components/ui/tabs'
-import { controlExecutionScope, getMissionControlSnapshot, getTasks, listTerminalEvents } from '@/hooks/useTauri'
+import {
+  controlExecutionScope,
+  getMissionControlSnapshot,
+  getSpendStatus,
+  getTasks,
+  listTerminalEvents,
+  recoverStalledRun,
+} from '@/hooks/useTauri'
 import { useAopStore } from '@/store/aop-store'
 import type {
   AgentEventRecord,
   AgentRunRecord,
+  ExecutionLane,
   ExecutionScopeType,
   MissionControlSnapshot,
+  SpendStatus,
   TaskControlAction,
   TaskRecord,
   TerminalEventRecord,
@@ -48,6 +57,12 @@ const DEFAULT_FILTERS: MissionFilters = {
   timeWindow: '1h',
 }
 
+const EXECUTION_LANE_LABELS: Record<ExecutionLane, string> = {
+  root_orchestration: 'Orchestrations',
+  specialist: 'Specialists',
+  pipeline_run: 'Pipeline runs',
+}
+
 function formatTimestamp(timestamp: number): string {
   return new Intl.DateTimeFormat(undefined, {
     dateStyle: 'short',
@@ -145,6 +160,7 @@ export function MissionControlView() {
   )
 
   const [snapshot, setSnapshot] = useState<MissionControlSnapshot | null>(null)
+  const [spendStatus, setSpendStatus] = useState<SpendStatus | null>(null)
   const [densityMode, setDensityMode] = useState<DensityMode>('balanced')
   const [filters, setFilters] = useState<MissionFilters>(DEFAULT_FILTERS)
   const [selectedRootTaskId, setSelectedRootTaskId] = useState<string>('')
@@ -156,6 +172,7 @@ export function MissionControlView() {
   const [snapshotError, setSnapshotError] = useState<string | null>(null)
   const [scopeError, setScopeError] = useState<string | null>(null)
   const [activeControlAction, setActiveControlAction] = useState<TaskControlAction | null>(null)
+  const [recoveringRunId, setRecoveringRunId] = useState<string | null>(null)
 
   const [terminalEvents, setTerminalEvents] = useState<TerminalEventRecord[]>([])
   const [isLoadingTerminal, setIsLoadingTerminal] = useState(false)
@@ -183,8 +200,9 @@ export function MissionControlView() {
       const input = selectedRootTaskId
         ? { rootTaskId: selectedRootTaskId, limit: 300 }
         : ({ limit: 300 } as const)
-      const next = await getMissionControlSnapshot(input)
+      const [next, spend] = await Promise.all([getMissionControlSnapshot(input), getSpendStatus()])
       setSnapshot(next)
+      setSpendStatus(spend)
       setSnapshotError(null)
     } catch (error) {
       setSnapshotError(error instanceof Error ? error.message : String(error))
@@ -409,6 +427,21 @@ export function MissionControlView() {
     }
   }
 
+  async function handleRecoverStalledRun(runId: string, action: 'stop' | 'restart') {
+    setScopeError(null)
+    setRecoveringRunId(runId)
+    try {
+      const updated = await recoverStalledRun({ runId, action })
+      updated.forEach((task) => addTask(task))
+      toast.success(`Stalled run ${action === 'stop' ? 'stopped' : 'restarted'} (${updated.length} task(s)).`)
+      await loadSnapshot()
+    } catch (error) {
+      setScopeError(error instanceof Error ? error.message : String(error))
+    } finally {
+      setRecoveringRunId(null)
+    }
+  }
+
   const mosaicGridClass =
     densityMode === 'pro'
       ? 'grid grid-cols-1 gap-2 2xl:grid-cols-3 xl:grid-cols-2'
@@ -437,7 +470,7 @@ export function MissionControlView() {
                   {rootTasks.length === 0 ? <SelectItem value="__empty__">No Tier 1 tasks</SelectItem> : null}
                   {rootTasks.map((task) => (
                     <SelectItem key={task.id} value={task.id}>
-                      {task.id.slice(0, 8)} · {task.domain} · {task.status}
+                      {task.id.slice(0, 8)} · {task.domain} · {task.queuedAt ? 'queued' : task.status}
                     </SelectItem>
                   ))}
                 </SelectContent>
@@ -658,6 +691,128 @@ export function MissionControlView() {
         </Card>
 
         <div className="space-y-4">
+          {spendStatus && spendStatus.status !== 'ok' ? (
+            <Card
+              className={
+                spendStatus.status === 'spend_cap_exceeded' ? 'border-destructive' : 'border-amber-400/60 bg-amber-500/10'
+              }
+            >
+              <CardHeader>
+                <CardTitle>{spendStatus.status === 'spend_cap_exceeded' ? 'Spend Cap Exceeded' : 'Spend Cap Warning'}</CardTitle>
+              </CardHeader>
+              <CardContent className="space-y-1 text-xs">
+                <p>
+                  Today ${spendStatus.dailyUsd.toFixed(2)}
+                  {spendStatus.dailyCapUsd > 0 ? ` of $${spendStatus.dailyCapUsd.toFixed(2)}` : ''} · this month $
+                  {spendStatus.monthlyUsd.toFixed(2)}
+                  {spendStatus.monthlyCapUsd > 0 ? ` of $${spendStatus.monthlyCapUsd.toFixed(2)}` : ''}
+                </p>
+                {spendStatus.status === 'spend_cap_exceeded' ? (
+                  <p className="text-destructive">
+                    New model calls are blocked and executing root tasks were paused. Raise the cap in System or wait
+                    for the next period, then resume them.
+                  </p>
+                ) : null}
+              </CardContent>
+            </Card>
+          ) : null}
+          {snapshot && snapshot.stalledRuns.length > 0 ? (
+            <Card className="border-destructive">
+              <CardHeader>
+                <CardTitle>Stalled Runs</CardTitle>
+              </CardHeader>
+              <CardContent className="space-y-2 text-xs">
+                {snapshot.stalledRuns.map((run) => (
+                  <div className="flex items-center justify-between gap-2 rounded-md border p-2" key={run.runId}>
+                    <span>
+                      {run.actor} · tier {run.tier ?? '-'} · task {run.taskId.slice(0, 8)} · last heartbeat{' '}
+                      {formatTimestamp(run.heartbeatAt)}
+                    </span>
+                    <div className="flex gap-1">
+                      <Button
+                        disabled={recoveringRunId === run.runId}
+                        onClick={() => void handleRecoverStalledRun(run.runId, 'stop')}
+                        size="sm"
+                        type="button"
+                        variant="outline"
+                      >
+                        Stop
+                      </Button>
+                      <Button
+                        disabled={recoveringRunId === run.runId}
+                        onClick={() => void handleRecoverStalledRun(run.runId, 'restart')}
+                        size="sm"
+                        type="button"
+                      >
+                        Restart
+                      </Button>
+                    </div>
+                  </div>
+                ))}
+              </CardContent>
+            </Card>
+          ) : null}
+
+          <Card>
+            <CardHeader>
+              <CardTitle>Execution Queue</CardTitle>
+            </CardHeader>
+            <CardContent className="space-y-3 text-xs">
+              <div className="grid grid-cols-1 gap-2 sm:grid-cols-3">
+                {(snapshot?.executionQueue.lanes ?? []).map((lane) => (
+                  <div className="rounded-md border p-2" key={lane.lane}>
+                    <p className="text-muted-foreground">{EXECUTION_LANE_LABELS[lane.lane]}</p>
+                    <p className="text-sm font-semibold">
+                      {lane.running} / {lane.limit ?? '∞'} running
+                    </p>
+                    <p className="text-muted-foreground">
+                      {lane.queued} queued{lane.perProjectLimit ? ` · ${lane.perProjectLimit} per project` : ''}
+                    </p>
+                  </div>
+                ))}
+              </div>
+              {(snapshot?.executionQueue.queued ?? []).map((entry) => (
+                <div
+                  className="flex items-center justify-between gap-2 rounded-md border p-2"
+                  key={`${entry.lane}-${entry.subjectId}-${entry.queuedAt}`}
+                >
+                  <span>
+                    #{entry.position} {EXECUTION_LANE_LABELS[entry.lane]} · {entry.subjectId.slice(0, 8)}
+                    {entry.targetProject ? ` · ${entry.targetProject}` : ''}
+                  </span>
+                  <span className="text-muted-foreground">since {formatTimestamp(entry.queuedAt)}</span>
+                </div>
+              ))}
+              {snapshot && snapshot.executionQueue.queued.length === 0 ? (
+                <p className="text-muted-foreground">Nothing is waiting for an execution slot.</p>
+              ) : null}
+            </CardContent>
+          </Card>
+
+          {snapshot && snapshot.mcpSessions.length > 0 ? (
+            <Card>
+              <CardHeader>
+                <CardTitle>MCP Sessions</CardTitle>
+              </CardHeader>
+              <CardContent className="space-y-2 text-xs">
+                {snapshot.mcpSessions.map((session) => (
+                  <div className="rounded-md border p-2" key={session.server}>
+                    <div className="flex items-center justify-between gap-2">
+                      <span className="font-semibold">{session.server}</span>
+                      <Badge variant={session.status === 'backoff' ? 'destructive' : 'secondary'}>{session.status}</Badge>
+                    </div>
+                    <p className="text-muted-foreground">
+                      {session.lastPingMs != null ? `ping ${session.lastPingMs}ms · ` : ''}
+                      {session.restarts} restarts
+                      {session.retryAt ? ` · retry at ${formatTimestamp(session.retryAt)}` : ''}
+                    </p>
+                    {session.lastError ? <p className="text-destructive">{session.lastError}</p> : null}
+                  </div>
+                ))}
+              </CardContent>
+            </Card>
+          ) : null}
+
           <Card>
             <CardHeader>
               <CardTitle>Execution Controls</CardTitle>
@@ -52,7 +52,7 @@ function mutationStatusVariant(status: string): 'secondary' | 'default' | 'destr
 function stepStatusVariant(status: string): 'secondary' | 'default' | 'destructive' | 'outline' {
   if (status === 'passed') return 'default'
   if (status === 'failed') return 'destructive'
-  if (status === 'pending') return 'outline'
+  if (status === 'pending' || status === 'warning') return 'outline'
   return 'secondary'
 }
 
@@ -304,6 +304,7 @@ export function MutationsView() {
         mutationId,
         targetProject: target,
         tier1Approved,
+        acceptPartialAlignment: tier1Approved,
       })
       setPipelineResult(result)
       setPipelineSteps(result.steps)
@@ -10,19 +10,37 @@ import { Switch } from '@/components/ui/switch'
 import { useTargetProjectConfig } from '@/hooks/useTargetProjectConfig'
 import {
   archiveTelemetry,
+  backupDatabase,
+  checkDatabaseIntegrity,
+  exportConfiguration,
   getModelRegistry,
   getProviderSecretStatus,
   getRuntimeFlags,
+  importConfiguration,
+  importTelemetryArchive,
+  listDatabaseBackups,
+  listProviderCredentials,
+  listRuntimeProfiles,
+  migrateSecretBackend,
+  restoreDatabase,
   revealProviderSecret,
+  rotateProviderSecret,
   setProviderSecret,
   setRuntimeFlags,
+  setRuntimeProfile,
+  validateProviderSecret,
 } from '@/hooks/useTauri'
 import type {
   ArchiveTelemetryResult,
+  DatabaseBackup,
+  DatabaseIntegrityReport,
+  ImportTelemetryArchiveResult,
   ModelRegistrySnapshot,
   ModelProfile,
+  ProviderCredential,
   ProviderSecretStatus,
   RuntimeFlags,
+  RuntimeProfile,
 } from '@/types'
 
 function formatProfile(profile: ModelProfile): string {
@@ -35,15 +53,49 @@ const DEFAULT_FLAGS: RuntimeFlags = {
   devMode: false,
   modelAdapterEnabled: true,
   modelAdapterStrict: false,
+  offlineSpecialists: false,
   autoApproveBudgetRequests: true,
   autoCommitMutations: false,
   budgetHeadroomPercent: 25,
   budgetAutoMaxPercent: 40,
   budgetMinIncrement: 250,
+  spendCapDailyUsd: 0,
+  spendCapMonthlyUsd: 0,
+  spendCapWarnPercent: 80,
+  executionWindow: '',
+  executionWindowAcPower: false,
+  executionWindowMinTasks: 0,
+  notificationsEnabled: true,
+  notifyPlanReady: true,
+  notifyConsensusFailed: true,
+  notifyBudgetRequest: true,
+  notifyRunFinished: true,
+  webhookUrls: '',
   telemetryRetentionDays: 7,
+  mutationRepairAttempts: 2,
+  mutationTtlHours: 168,
+  taskBranchMode: false,
+  shadowKeepOnFailure: false,
+  shadowDiskQuotaMb: 2048,
+  shadowSandbox: false,
+  semanticJudge: true,
+  pipelineTestTimeoutSecs: 120,
+  pipelineStaticCheckTimeoutSecs: 180,
+  pipelineApplyTimeoutSecs: 60,
+  pipelineMaxOutputKb: 64,
+  pipelineCiEnv: [],
+  embeddingProvider: 'hash',
+  embeddingModel: '',
+  otlpEndpoint: '',
+  metricsEnabled: false,
+  metricsPort: 9464,
+  httpApiEnabled: false,
+  httpApiPort: 7878,
+  dbEncryption: false,
+  secretBackend: 'stronghold',
 }
 
-const PROVIDER_OPTIONS = ['claude_code', 'openai', 'anthropic', 'gemini', 'xai']
+const PROVIDER_OPTIONS = ['claude_code', 'openai', 'anthropic', 'gemini', 'xai', 'webhook', 'http_api']
 
 export function SystemView() {
   const { targetProject, mcpCommand, mcpArgs } = useTargetProjectConfig()
@@ -57,8 +109,13 @@ export function SystemView() {
   const [isSavingFlags, setIsSavingFlags] = useState(false)
   const [flagsError, setFlagsError] = useState<string | null>(null)
   const [flagsFeedback, setFlagsFeedback] = useState<string | null>(null)
+  const [profiles, setProfiles] = useState<RuntimeProfile[]>([])
+  const [newProfileName, setNewProfileName] = useState('')
+  const [webhookSigningKeyConfigured, setWebhookSigningKeyConfigured] = useState(true)
 
   const [provider, setProvider] = useState('claude_code')
+  const [credentialName, setCredentialName] = useState('')
+  const [credentials, setCredentials] = useState<ProviderCredential[]>([])
   const [providerStatus, setProviderStatus] = useState<ProviderSecretStatus | null>(null)
   const [isLoadingProviderStatus, setIsLoadingProviderStatus] = useState(false)
   const [providerError, setProviderError] = useState<string | null>(null)
@@ -71,10 +128,22 @@ export function SystemView() {
   const [archiveResult, setArchiveResult] = useState<ArchiveTelemetryResult | null>(null)
   const [isArchiving, setIsArchiving] = useState(false)
   const [archiveError, setArchiveError] = useState<string | null>(null)
+  const [importResult, setImportResult] = useState<ImportTelemetryArchiveResult | null>(null)
+  const [isImporting, setIsImporting] = useState(false)
+
+  const [backups, setBackups] = useState<DatabaseBackup[]>([])
+  const [integrityReport, setIntegrityReport] = useState<DatabaseIntegrityReport | null>(null)
+  const [isDatabaseBusy, setIsDatabaseBusy] = useState(false)
+  const [databaseMessage, setDatabaseMessage] = useState<string | null>(null)
+  const [databaseError, setDatabaseError] = useState<string | null>(null)
+
+  const [configMessage, setConfigMessage] = useState<string | null>(null)
+  const [configError, setConfigError] = useState<string | null>(null)
 
   useEffect(() => {
     void loadRegistry()
     void loadFlags()
+    void loadBackups()
   }, [])
 
   useEffect(() => {
@@ -102,8 +171,14 @@ export function SystemView() {
     setIsLoadingFlags(true)
     setFlagsError(null)
     try {
-      const current = await getRuntimeFlags()
+      const [current, available, webhookKey] = await Promise.all([
+        getRuntimeFlags(),
+        listRuntimeProfiles(),
+        getProviderSecretStatus({ provider: 'webhook' }),
+      ])
       setFlags(current)
+      setProfiles(available)
+      setWebhookSigningKeyConfigured(webhookKey.configured)
     } catch (loadError) {
       setFlagsError(loadError instanceof Error ? loadError.message : String(loadError))
     } finally {
@@ -116,8 +191,15 @@ export function SystemView() {
     setProviderError(null)
     setRevealedSecret(null)
     try {
-      const status = await getProviderSecretStatus({ provider: nextProvider })
+      const [status, stored] = await Promise.all([
+        getProviderSecretStatus({ provider: nextProvider, name: credentialName.trim() || undefined }),
+        listProviderCredentials({ provider: nextProvider }),
+      ])
       setProviderStatus(status)
+      setCredentials(stored)
+      if (nextProvider === 'webhook') {
+        setWebhookSigningKeyConfigured(status.configured)
+      }
     } catch (loadError) {
       setProviderError(loadError instanceof Error ? loadError.message : String(loadError))
     } finally {
@@ -144,6 +226,20 @@ export function SystemView() {
     }
   }
 
+  async function handleRuntimeProfile(name: string, saveCurrent = false) {
+    setFlagsError(null)
+    setFlagsFeedback(null)
+    try {
+      const result = await setRuntimeProfile({ name, saveCurrent })
+      setFlags(result.flags)
+      setProfiles(await listRuntimeProfiles())
+      setNewProfileName('')
+      setFlagsFeedback(saveCurrent ? `Saved current flags as profile '${name}'.` : `Switched to profile '${name}'.`)
+    } catch (profileError) {
+      setFlagsError(profileError instanceof Error ? profileError.message : String(profileError))
+    }
+  }
+
   async function handleSaveProviderSecret() {
     if (!providerSecret.trim()) {
       setProviderError('Secret value is required.')
@@ -155,6 +251,7 @@ export function SystemView() {
     try {
       const result = await setProviderSecret({
         provider,
+        name: credentialName.trim() || undefined,
         secret: providerSecret.trim(),
         sessionToken: sessionToken.trim() || undefined,
       })
@@ -166,7 +263,7 @@ export function SystemView() {
           setSessionToken(result.confirmationToken)
         }
       } else {
-        setProviderFeedback(`Secret stored for provider '${provider}'.`)
+        setProviderFeedback(`Secret stored for '${result.provider}'.`)
         setProviderSecretValue('')
       }
       await loadProviderStatus(provider)
@@ -182,6 +279,7 @@ export function SystemView() {
     try {
       const result = await revealProviderSecret({
         provider,
+        name: credentialName.trim() || undefined,
         sessionToken: sessionToken.trim() || undefined,
       })
       setRevealedSecret(result.secret)
@@ -196,6 +294,68 @@ export function SystemView() {
     }
   }
 
+  async function handleRotateProviderSecret() {
+    if (!providerSecret.trim()) {
+      setProviderError('Enter the new key to rotate to.')
+      return
+    }
+
+    setProviderError(null)
+    setProviderFeedback(null)
+    try {
+      const result = await rotateProviderSecret({
+        provider,
+        name: credentialName.trim() || undefined,
+        secret: providerSecret.trim(),
+      })
+      setProviderFeedback(`Key for '${result.provider}' passed a test call and replaced the old one.`)
+      setProviderSecretValue('')
+      await loadProviderStatus(provider)
+    } catch (rotateError) {
+      setProviderError(rotateError instanceof Error ? rotateError.message : String(rotateError))
+    }
+  }
+
+  async function handleValidateProviderSecret() {
+    setProviderError(null)
+    setProviderFeedback(null)
+    try {
+      const result = await validateProviderSecret({
+        provider,
+        name: credentialName.trim() || undefined,
+        secret: providerSecret.trim() || undefined,
+      })
+      const quota = Object.entries(result.quotaHeaders)
+        .map(([header, value]) => `${header}: ${value}`)
+        .join(', ')
+      const summary = `HTTP ${result.status} in ${result.latencyMs} ms${quota ? ` (${quota})` : ''}`
+      if (result.valid) {
+        setProviderFeedback(`Key for '${result.provider}' works: ${summary}.`)
+      } else {
+        setProviderError(`Key for '${result.provider}' was rejected: ${summary}.`)
+      }
+    } catch (validateError) {
+      setProviderError(validateError instanceof Error ? validateError.message : String(validateError))
+    }
+  }
+
+  async function handleMigrateSecretBackend() {
+    const backend = (providerStatus?.backend ?? flags.secretBackend) === 'keychain' ? 'stronghold' : 'keychain'
+    setProviderError(null)
+    setProviderFeedback(null)
+    try {
+      const result = await migrateSecretBackend({ backend })
+      setFlags((current) => ({ ...current, secretBackend: result.to }))
+      setProviderFeedback(
+        `Moved ${result.migrated.length} secret(s) from ${result.from} to ${result.to}.` +
+          (result.leftInSource.length > 0 ? ` Could not remove from ${result.from}: ${result.leftInSource.join(', ')}.` : ''),
+      )
+      await loadProviderStatus(provider)
+    } catch (migrateError) {
+      setProviderError(migrateError instanceof Error ? migrateError.message : String(migrateError))
+    }
+  }
+
   async function handleArchiveTelemetry() {
     setIsArchiving(true)
     setArchiveError(null)
@@ -210,6 +370,109 @@ export function SystemView() {
     }
   }
 
+  async function loadBackups() {
+    try {
+      setBackups(await listDatabaseBackups())
+    } catch (backupFailure) {
+      setDatabaseError(backupFailure instanceof Error ? backupFailure.message : String(backupFailure))
+    }
+  }
+
+  async function runDatabaseAction(action: () => Promise<void>) {
+    setIsDatabaseBusy(true)
+    setDatabaseError(null)
+    setDatabaseMessage(null)
+    try {
+      await action()
+    } catch (databaseFailure) {
+      setDatabaseError(databaseFailure instanceof Error ? databaseFailure.message : String(databaseFailure))
+    } finally {
+      setIsDatabaseBusy(false)
+    }
+  }
+
+  async function handleBackupDatabase() {
+    await runDatabaseAction(async () => {
+      const backup = await backupDatabase()
+      setDatabaseMessage(`Backed up to ${backup.path}`)
+      await loadBackups()
+    })
+  }
+
+  async function handleCheckIntegrity() {
+    await runDatabaseAction(async () => {
+      setIntegrityReport(await checkDatabaseIntegrity())
+    })
+  }
+
+  async function handleRestoreDatabase(fileName: string) {
+    await runDatabaseAction(async () => {
+      const result = await restoreDatabase({ fileName })
+      setDatabaseMessage(`${result.fileName} will be restored when the app restarts.`)
+    })
+  }
+
+  async function handleExportConfiguration() {
+    setConfigMessage(null)
+    setConfigError(null)
+    try {
+      const bundle = await exportConfiguration()
+      const blob = new Blob([JSON.stringify(bundle, null, 2)], { type: 'application/json' })
+      const url = URL.createObjectURL(blob)
+      const link = document.createElement('a')
+      link.href = url
+      link.download = 'aop-configuration.json'
+      link.click()
+      URL.revokeObjectURL(url)
+      setConfigMessage(
+        bundle.omitted.length > 0
+          ? `Exported. Left out because they may hold secrets: ${bundle.omitted.join(', ')}`
+          : 'Exported.',
+      )
+    } catch (exportError) {
+      setConfigError(exportError instanceof Error ? exportError.message : String(exportError))
+    }
+  }
+
+  async function handleImportConfiguration(file: File | undefined) {
+    if (!file) {
+      return
+    }
+    setConfigMessage(null)
+    setConfigError(null)
+    try {
+      const result = await importConfiguration({ bundle: JSON.parse(await file.text()) })
+      await Promise.all([loadFlags(), loadRegistry()])
+      const lines = [
+        `flags changed: ${result.runtimeFlags.length}`,
+        `model routing: ${result.modelRouting ? 'replaced' : 'unchanged'}`,
+        `prompt templates: ${result.promptTemplates.join(', ') || 'none'}`,
+        `MCP servers: ${result.mcpServers.join(', ') || 'none'}`,
+      ]
+      if (result.omitted.length > 0) {
+        lines.push(`fill in locally: ${result.omitted.join(', ')}`)
+      }
+      if (result.restartRequired) {
+        lines.push('Restart required for some changes.')
+      }
+      setConfigMessage(lines.join('\n'))
+    } catch (importError) {
+      setConfigError(importError instanceof Error ? importError.message : String(importError))
+    }
+  }
+
+  async function handleImportTelemetryArchive() {
+    setIsImporting(true)
+    setArchiveError(null)
+    try {
+      setImportResult(await importTelemetryArchive({}))
+    } catch (importFailure) {
+      setArchiveError(importFailure instanceof Error ? importFailure.message : String(importFailure))
+    } finally {
+      setIsImporting(false)
+    }
+  }
+
   const tierEntries = useMemo(
     () =>
       registry?.config.tiers
@@ -235,6 +498,41 @@ export function SystemView() {
           </div>
         </CardHeader>
         <CardContent className="space-y-4">
+          <div className="flex flex-wrap items-end gap-2">
+            <div className="space-y-1">
+              <Label htmlFor="runtime-profile">Profile</Label>
+              <Select
+                onValueChange={(name) => void handleRuntimeProfile(name)}
+                value={profiles.find((profile) => profile.active)?.name ?? ''}
+              >
+                <SelectTrigger className="w-48" id="runtime-profile">
+                  <SelectValue placeholder="custom" />
+                </SelectTrigger>
+                <SelectContent>
+                  {profiles.map((profile) => (
+                    <SelectItem key={profile.name} value={profile.name}>
+                      {profile.name}
+                    </SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
+            </div>
+            <Input
+              className="w-48"
+              onChange={(event) => setNewProfileName(event.target.value)}
+              placeholder="new profile name"
+              value={newProfileName}
+            />
+            <Button
+              disabled={!newProfileName.trim()}
+              onClick={() => void handleRuntimeProfile(newProfileName.trim(), true)}
+              size="sm"
+              type="button"
+              variant="outline"
+            >
+              Save as Profile
+            </Button>
+          </div>
           <div className="grid grid-cols-1 gap-4 md:grid-cols-2 xl:grid-cols-4">
             <div className="flex items-center justify-between rounded-md border p-3">
               <Label htmlFor="flag-dev-mode">Developer Mode</Label>
@@ -260,6 +558,14 @@ export function SystemView() {
                 onCheckedChange={(checked) => setFlags((current) => ({ ...current, modelAdapterStrict: checked }))}
               />
             </div>
+            <div className="flex items-center justify-between rounded-md border p-3">
+              <Label htmlFor="flag-offline-specialists">Offline Specialists (local models)</Label>
+              <Switch
+                checked={flags.offlineSpecialists}
+                id="flag-offline-specialists"
+                onCheckedChange={(checked) => setFlags((current) => ({ ...current, offlineSpecialists: checked }))}
+              />
+            </div>
             <div className="flex items-center justify-between rounded-md border p-3">
               <Label htmlFor="flag-auto-approve">Auto Approve Budget</Label>
               <Switch
@@ -276,6 +582,54 @@ export function SystemView() {
                 onCheckedChange={(checked) => setFlags((current) => ({ ...current, autoCommitMutations: checked }))}
               />
             </div>
+            <div className="flex items-center justify-between rounded-md border p-3">
+              <Label htmlFor="flag-window-ac-power">Start Plans Only On AC Power</Label>
+              <Switch
+                checked={flags.executionWindowAcPower}
+                id="flag-window-ac-power"
+                onCheckedChange={(checked) => setFlags((current) => ({ ...current, executionWindowAcPower: checked }))}
+              />
+            </div>
+            <div className="flex items-center justify-between rounded-md border p-3">
+              <Label htmlFor="flag-notifications">Desktop Notifications</Label>
+              <Switch
+                checked={flags.notificationsEnabled}
+                id="flag-notifications"
+                onCheckedChange={(checked) => setFlags((current) => ({ ...current, notificationsEnabled: checked }))}
+              />
+            </div>
+            <div className="flex items-center justify-between rounded-md border p-3">
+              <Label htmlFor="flag-notify-plan-ready">Notify When A Plan Needs Review</Label>
+              <Switch
+                checked={flags.notifyPlanReady}
+                id="flag-notify-plan-ready"
+                onCheckedChange={(checked) => setFlags((current) => ({ ...current, notifyPlanReady: checked }))}
+              />
+            </div>
+            <div className="flex items-center justify-between rounded-md border p-3">
+              <Label htmlFor="flag-notify-consensus-failed">Notify On Consensus Failure</Label>
+              <Switch
+                checked={flags.notifyConsensusFailed}
+                id="flag-notify-consensus-failed"
+                onCheckedChange={(checked) => setFlags((current) => ({ ...current, notifyConsensusFailed: checked }))}
+              />
+            </div>
+            <div className="flex items-center justify-between rounded-md border p-3">
+              <Label htmlFor="flag-notify-budget-request">Notify On Budget Requests</Label>
+              <Switch
+                checked={flags.notifyBudgetRequest}
+                id="flag-notify-budget-request"
+                onCheckedChange={(checked) => setFlags((current) => ({ ...current, notifyBudgetRequest: checked }))}
+              />
+            </div>
+            <div className="flex items-center justify-between rounded-md border p-3">
+              <Label htmlFor="flag-notify-run-finished">Notify When A Run Finishes</Label>
+              <Switch
+                checked={flags.notifyRunFinished}
+                id="flag-notify-run-finished"
+                onCheckedChange={(checked) => setFlags((current) => ({ ...current, notifyRunFinished: checked }))}
+              />
+            </div>
           </div>
 
           <div className="grid grid-cols-1 gap-3 md:grid-cols-2 xl:grid-cols-4">
@@ -331,6 +685,82 @@ export function SystemView() {
                 value={flags.telemetryRetentionDays}
               />
             </div>
+            <div className="space-y-1">
+              <Label htmlFor="flag-spend-cap-daily">Daily Spend Cap (USD, 0 = off)</Label>
+              <Input
+                id="flag-spend-cap-daily"
+                min={0}
+                onChange={(event) =>
+                  setFlags((current) => ({ ...current, spendCapDailyUsd: Number(event.target.value || 0) }))
+                }
+                step={1}
+                type="number"
+                value={flags.spendCapDailyUsd}
+              />
+            </div>
+            <div className="space-y-1">
+              <Label htmlFor="flag-spend-cap-monthly">Monthly Spend Cap (USD, 0 = off)</Label>
+              <Input
+                id="flag-spend-cap-monthly"
+                min={0}
+                onChange={(event) =>
+                  setFlags((current) => ({ ...current, spendCapMonthlyUsd: Number(event.target.value || 0) }))
+                }
+                step={10}
+                type="number"
+                value={flags.spendCapMonthlyUsd}
+              />
+            </div>
+            <div className="space-y-1">
+              <Label htmlFor="flag-spend-cap-warn">Spend Cap Warning (%)</Label>
+              <Input
+                id="flag-spend-cap-warn"
+                min={1}
+                onChange={(event) =>
+                  setFlags((current) => ({ ...current, spendCapWarnPercent: Number(event.target.value || 0) }))
+                }
+                step={5}
+                type="number"
+                value={flags.spendCapWarnPercent}
+              />
+            </div>
+            <div className="space-y-1">
+              <Label htmlFor="flag-execution-window">Execution Window (HH:MM-HH:MM)</Label>
+              <Input
+                id="flag-execution-window"
+                onChange={(event) => setFlags((current) => ({ ...current, executionWindow: event.target.value }))}
+                placeholder="22:00-06:00"
+                value={flags.executionWindow}
+              />
+            </div>
+            <div className="space-y-1">
+              <Label htmlFor="flag-window-min-tasks">Window Applies From (assignments)</Label>
+              <Input
+                id="flag-window-min-tasks"
+                min={0}
+                onChange={(event) =>
+                  setFlags((current) => ({ ...current, executionWindowMinTasks: Number(event.target.value || 0) }))
+                }
+                step={1}
+                type="number"
+                value={flags.executionWindowMinTasks}
+              />
+            </div>
+            <div className="space-y-1">
+              <Label htmlFor="flag-webhook-urls">Webhook URLs (comma-separated)</Label>
+              <Input
+                id="flag-webhook-urls"
+                onChange={(event) => setFlags((current) => ({ ...current, webhookUrls: event.target.value }))}
+                placeholder="https://hooks.slack.com/services/..."
+                value={flags.webhookUrls}
+              />
+              {flags.webhookUrls.trim() && !webhookSigningKeyConfigured ? (
+                <p className="text-xs text-amber-600">
+                  Deliveries go out unsigned (X-AOP-Signature-Status: unsigned). Store a key for the webhook
+                  provider below to sign them.
+                </p>
+              ) : null}
+            </div>
           </div>
 
           {flagsFeedback ? <p className="text-sm">{flagsFeedback}</p> : null}
@@ -340,7 +770,7 @@ export function SystemView() {
 
       <Card>
         <CardHeader>
-          <CardTitle>Provider Secrets (Stronghold)</CardTitle>
+          <CardTitle>Provider Secrets</CardTitle>
         </CardHeader>
         <CardContent className="space-y-4">
           <div className="grid grid-cols-1 gap-3 md:grid-cols-[240px_1fr]">
@@ -358,6 +788,13 @@ export function SystemView() {
                   ))}
                 </SelectContent>
               </Select>
+              <Label htmlFor="credential-name">Key Name</Label>
+              <Input
+                id="credential-name"
+                onChange={(event) => setCredentialName(event.target.value)}
+                placeholder="default"
+                value={credentialName}
+              />
             </div>
 
             <div className="rounded-md border p-3 text-sm">
@@ -371,6 +808,15 @@ export function SystemView() {
                 </Badge>
                 {isLoadingProviderStatus ? <Badge variant="outline">syncing</Badge> : null}
               </div>
+              {credentials.length > 0 ? (
+                <div className="mt-2 flex flex-wrap gap-1">
+                  {credentials.map((credential) => (
+                    <Badge key={credential.reference} variant="secondary">
+                      {credential.reference}
+                    </Badge>
+                  ))}
+                </div>
+              ) : null}
               <p className="text-muted-foreground mt-2 text-xs">
                 Reveal/edit requires developer mode and a valid per-session confirmation token.
               </p>
@@ -406,6 +852,12 @@ export function SystemView() {
             <Button onClick={() => void handleRevealProviderSecret()} size="sm" type="button" variant="outline">
               Reveal Secret
             </Button>
+            <Button onClick={() => void handleRotateProviderSecret()} size="sm" type="button" variant="outline">
+              Rotate Key
+            </Button>
+            <Button onClick={() => void handleValidateProviderSecret()} size="sm" type="button" variant="outline">
+              Test Key
+            </Button>
             <Button
               onClick={() => void loadProviderStatus(provider)}
               size="sm"
@@ -414,6 +866,9 @@ export function SystemView() {
             >
               Refresh Status
             </Button>
+            <Button onClick={() => void handleMigrateSecretBackend()} size="sm" type="button" variant="outline">
+              Move to {(providerStatus?.backend ?? flags.secretBackend) === 'keychain' ? 'Stronghold' : 'OS keychain'}
+            </Button>
           </div>
 
           {providerFeedback ? <p className="text-sm whitespace-pre-wrap">{providerFeedback}</p> : null}
@@ -443,10 +898,18 @@ export function SystemView() {
                 value={archiveRetentionDays}
               />
             </div>
-            <div className="flex items-end">
+            <div className="flex items-end gap-2">
               <Button disabled={isArchiving} onClick={() => void handleArchiveTelemetry()} type="button">
                 {isArchiving ? 'Archiving...' : 'Run Archive Now'}
               </Button>
+              <Button
+                disabled={isImporting}
+                onClick={() => void handleImportTelemetryArchive()}
+                type="button"
+                variant="outline"
+              >
+                {isImporting ? 'Importing...' : 'Import Archives'}
+              </Button>
             </div>
           </div>
           {archiveResult ? (
@@ -457,10 +920,96 @@ export function SystemView() {
               <p>file: {archiveResult.archiveFile ?? 'none (nothing to archive)'}</p>
             </div>
           ) : null}
+          {importResult ? (
+            <div className="rounded-md border p-3 text-sm">
+              <p>files imported: {importResult.filesImported.length}</p>
+              <p>events restored: {importResult.eventsImported}</p>
+              <p>runs restored: {importResult.runsImported}</p>
+              <p>skipped: {importResult.rowsSkipped}</p>
+            </div>
+          ) : null}
           {archiveError ? <p className="text-destructive text-sm whitespace-pre-wrap">{archiveError}</p> : null}
         </CardContent>
       </Card>
 
+      <Card>
+        <CardHeader>
+          <CardTitle>Database Backups</CardTitle>
+        </CardHeader>
+        <CardContent className="space-y-3">
+          <div className="flex flex-wrap gap-2">
+            <Button disabled={isDatabaseBusy} onClick={() => void handleBackupDatabase()} type="button">
+              Back Up Now
+            </Button>
+            <Button disabled={isDatabaseBusy} onClick={() => void handleCheckIntegrity()} type="button" variant="outline">
+              Check Integrity
+            </Button>
+          </div>
+          {integrityReport ? (
+            <div className="rounded-md border p-3 text-sm">
+              <p>
+                integrity: <Badge variant={integrityReport.ok ? 'outline' : 'destructive'}>{integrityReport.ok ? 'ok' : 'issues found'}</Badge>
+              </p>
+              <p>foreign key violations: {integrityReport.foreignKeyViolations}</p>
+              {integrityReport.ok ? null : (
+                <p className="whitespace-pre-wrap">{integrityReport.integrityMessages.join('\n')}</p>
+              )}
+            </div>
+          ) : null}
+          {backups.length > 0 ? (
+            <div className="space-y-2">
+              {backups.map((backup) => (
+                <div className="flex items-center justify-between rounded-md border p-2 text-sm" key={backup.fileName}>
+                  <span>
+                    {backup.fileName} ({Math.ceil(backup.sizeBytes / 1024)} KiB)
+                  </span>
+                  <Button
+                    disabled={isDatabaseBusy}
+                    onClick={() => void handleRestoreDatabase(backup.fileName)}
+                    size="sm"
+                    type="button"
+                    variant="outline"
+                  >
+                    Restore
+                  </Button>
+                </div>
+              ))}
+            </div>
+          ) : (
+            <p className="text-muted-foreground text-sm">No backups yet.</p>
+          )}
+          {databaseMessage ? <p className="text-sm">{databaseMessage}</p> : null}
+          {databaseError ? <p className="text-destructive text-sm whitespace-pre-wrap">{databaseError}</p> : null}
+        </CardContent>
+      </Card>
+
+      <Card>
+        <CardHeader>
+          <CardTitle>Shared Configuration</CardTitle>
+        </CardHeader>
+        <CardContent className="space-y-3">
+          <p className="text-muted-foreground text-sm">
+            Runtime flags, model routing, saved prompt templates and MCP servers as one JSON file. Secrets are never included.
+          </p>
+          <div className="flex flex-wrap items-center gap-2">
+            <Button onClick={() => void handleExportConfiguration()} type="button">
+              Export
+            </Button>
+            <Input
+              accept="application/json"
+              className="max-w-xs"
+              onChange={(event) => {
+                void handleImportConfiguration(event.target.files?.[0])
+                event.target.value = ''
+              }}
+              type="file"
+            />
+          </div>
+          {configMessage ? <p className="text-sm whitespace-pre-wrap">{configMessage}</p> : null}
+          {configError ? <p className="text-destructive text-sm whitespace-pre-wrap">{configError}</p> : null}
+        </CardContent>
+      </Card>
+
       <Card>
         <CardHeader className="flex flex-row items-center justify-between">
           <CardTitle>Model Registry</CardTitle>
@@ -35,6 +35,8 @@ import {
   controlTask,
   createTask,
   getTasks,
+  setTaskDeadline,
+  setTaskPriority,
 } from "@/hooks/useTauri";
 import { executeRestartApply, formatRestartApplyIssue } from "@/lib/restartApply";
 import { useAopStore } from "@/store/aop-store";
@@ -268,6 +270,9 @@ export function TasksView() {
   const [activeTaskControl, setActiveTaskControl] =
     useState<TaskControlAction | null>(null);
   const [isApprovingPlan, setIsApprovingPlan] = useState(false);
+  const [isSettingPriority, setIsSettingPriority] = useState(false);
+  const [deadlineDraft, setDeadlineDraft] = useState("");
+  const [isSettingDeadline, setIsSettingDeadline] = useState(false);
   const [taskForm, setTaskForm] = useState<CreateTaskInput>(DEFAULT_TASK_FORM);
 
   const goToTab = useCallback(
@@ -297,6 +302,14 @@ export function TasksView() {
     void loadTasks();
   }, [loadTasks]);
 
+  useEffect(() => {
+    setDeadlineDraft(
+      selectedTask?.deadlineSeconds != null
+        ? String(selectedTask.deadlineSeconds)
+        : "",
+    );
+  }, [selectedTask?.id, selectedTask?.deadlineSeconds]);
+
   function resetCreateTaskState() {
     setCreateTaskError(null);
     setTaskForm((previous) => ({
@@ -399,6 +412,60 @@ export function TasksView() {
     }
   }
 
+  async function handleBumpPriority(delta: number) {
+    if (!selectedTask) {
+      return;
+    }
+
+    setTaskControlError(null);
+    setIsSettingPriority(true);
+    try {
+      const updated = await setTaskPriority({
+        taskId: selectedTask.id,
+        priority: Math.max(-10, Math.min(10, selectedTask.priority + delta)),
+      });
+      addTask(updated);
+    } catch (error) {
+      setTaskControlError(
+        error instanceof Error ? error.message : String(error),
+      );
+    } finally {
+      setIsSettingPriority(false);
+    }
+  }
+
+  async function handleSaveDeadline() {
+    if (!selectedTask) {
+      return;
+    }
+
+    const trimmed = deadlineDraft.trim();
+    const deadlineSeconds = trimmed ? Number(trimmed) : null;
+    if (
+      deadlineSeconds !== null &&
+      (!Number.isInteger(deadlineSeconds) || deadlineSeconds <= 0)
+    ) {
+      setTaskControlError("Deadline must be a whole number of seconds.");
+      return;
+    }
+
+    setTaskControlError(null);
+    setIsSettingDeadline(true);
+    try {
+      const updated = await setTaskDeadline({
+        taskId: selectedTask.id,
+        deadlineSeconds,
+      });
+      addTask(updated);
+    } catch (error) {
+      setTaskControlError(
+        error instanceof Error ? error.message : String(error),
+      );
+    } finally {
+      setIsSettingDeadline(false);
+    }
+  }
+
   async function handleApprovePlanForSelectedTask() {
     if (!selectedTask || selectedTask.tier !== 1) {
       return;
@@ -454,7 +521,7 @@ export function TasksView() {
                   Tier {selectedTask.tier}
                 </div>
                 <div className="rounded-md border p-2">
-                  {selectedTask.status}
+                  {selectedTask.queuedAt ? "queued for window" : selectedTask.status}
                 </div>
                 <div className="rounded-md border p-2">
                   Budget {selectedTask.tokenBudget}
@@ -468,6 +535,57 @@ export function TasksView() {
                 <div className="rounded-md border p-2">
                   Risk {selectedTask.riskFactor.toFixed(2)}
                 </div>
+                <div className="col-span-2 flex items-center justify-between gap-2 rounded-md border p-2">
+                  <span>Priority {selectedTask.priority}</span>
+                  <div className="flex gap-1">
+                    <Button
+                      disabled={isSettingPriority || selectedTask.priority <= -10}
+                      onClick={() => void handleBumpPriority(-1)}
+                      size="sm"
+                      type="button"
+                      variant="outline"
+                    >
+                      Lower
+                    </Button>
+                    <Button
+                      disabled={isSettingPriority || selectedTask.priority >= 10}
+                      onClick={() => void handleBumpPriority(1)}
+                      size="sm"
+                      type="button"
+                      variant="outline"
+                    >
+                      Raise
+                    </Button>
+                  </div>
+                </div>
+                <div className="col-span-2 flex items-center justify-between gap-2 rounded-md border p-2">
+                  <span>
+                    Deadline{" "}
+                    {selectedTask.deadlineSeconds != null
+                      ? `${selectedTask.deadlineSeconds}s`
+                      : "none"}
+                    {selectedTask.startedAt != null &&
+                      ` · started ${formatTimestamp(selectedTask.startedAt)}`}
+                  </span>
+                  <div className="flex gap-1">
+                    <Input
+                      className="h-8 w-24"
+                      inputMode="numeric"
+                      onChange={(event) => setDeadlineDraft(event.target.value)}
+                      placeholder="seconds"
+                      value={deadlineDraft}
+                    />
+                    <Button
+                      disabled={isSettingDeadline}
+                      onClick={() => void handleSaveDeadline()}
+                      size="sm"
+                      type="button"
+                      variant="outline"
+                    >
+                      Set
+                    </Button>
+                  </div>
+                </div>
               </div>
 
               <p className="text-muted-foreground text-xs">
@@ -623,7 +741,7 @@ export function TasksView() {
               <SelectContent>
                 {rootTier1Tasks.map((task) => (
                   <SelectItem key={task.id} value={task.id}>
-                    {task.domain} · {task.status} · {task.objective.slice(0, 42)}
+                    {task.domain} · {task.queuedAt ? "queued" : task.status} · {task.objective.slice(0, 42)}
                   </SelectItem>
                 ))}
               </SelectContent>